@@ -24,6 +24,9 @@
 //!
 //! When set, `MUTATE_ASSETS_DIR` and `MUTATE_BUILD_ASSETS_DIR` should point directly to an assets
 //! root i.e. a folder containing a shaders directory.
+//!
+//! Either way, bytes and shaders not found in any directory fall back to packs embedded in the
+//! binary.  See the [`pack`](crate::pack) module.
 
 // Decouple MUTATE_ASSETS_DIR for the visualizer from other programs.
 
@@ -127,35 +130,33 @@ impl AssetDirs {
     }
 
     pub fn find_bytes(&self, name: &str, kind: AssetKind) -> Result<Vec<u8>, AssetError> {
-        let found = self.find(name, kind)?;
-        std::fs::read(found).map_err(AssetError::ReadError)
+        match self.find(name, kind) {
+            Ok(found) => std::fs::read(found).map_err(AssetError::ReadError),
+            Err(e) => find_embedded(name, kind).map(<[u8]>::to_vec).ok_or(e),
+        }
     }
 
     pub fn find_shader(&self, name: &str) -> Result<Vec<u32>, AssetError> {
-        let path = self.find(name, AssetKind::Shader)?;
-
-        let mut file = std::fs::File::open(&path)?;
-
-        let byte_len = file.metadata()?.len() as usize;
-
-        if byte_len % size_of::<u32>() != 0 {
-            return Err(AssetError::InvalidShader(format!(
-                "SPIR-V length not multiple of 4: {} bytes",
-                byte_len
-            )));
+        // Source overrides SPIR-V.  See the compile module.
+        #[cfg(feature = "compile")]
+        match self.compile_shader(name) {
+            Err(AssetError::NotFound { .. }) => {}
+            compiled => return compiled,
         }
-
-        let word_len = byte_len / size_of::<u32>();
-        let mut words = Vec::<u32>::with_capacity(word_len);
-
-        // Treating things as just things, as things were meant to be!  🤗
-        unsafe {
-            words.set_len(word_len);
-            let byte_slice =
-                std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, byte_len);
-            file.read_exact(byte_slice)?;
+        match self.find(name, AssetKind::Shader) {
+            Ok(path) => read_spirv(&path),
+            Err(e) => find_embedded(name, AssetKind::Shader)
+                .ok_or(e)
+                .and_then(spirv_words),
         }
-        Ok(words)
+    }
+
+    /// Find and parse the BDF font `name`.
+    pub fn find_font(&self, name: &str) -> Result<crate::font::BitmapFont, AssetError> {
+        let bytes = self.find_bytes(name, AssetKind::Font)?;
+        let text = std::str::from_utf8(&bytes)
+            .map_err(|_| AssetError::InvalidFont(format!("{name} is not UTF-8")))?;
+        crate::font::BitmapFont::parse(text)
     }
 
     /// Return the hash of asset with `name`.  Use the `kind` of the asset you want the hash for!
@@ -179,3 +180,50 @@ impl AssetDirs {
         }
     }
 }
+
+pub(crate) fn read_spirv(path: &std::path::Path) -> Result<Vec<u32>, AssetError> {
+    let mut file = std::fs::File::open(path)?;
+
+    let byte_len = file.metadata()?.len() as usize;
+
+    if byte_len % size_of::<u32>() != 0 {
+        return Err(AssetError::InvalidShader(format!(
+            "SPIR-V length not multiple of 4: {} bytes",
+            byte_len
+        )));
+    }
+
+    let word_len = byte_len / size_of::<u32>();
+    let mut words = Vec::<u32>::with_capacity(word_len);
+
+    // Treating things as just things, as things were meant to be!  🤗
+    unsafe {
+        words.set_len(word_len);
+        let byte_slice = std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, byte_len);
+        file.read_exact(byte_slice)?;
+    }
+    Ok(words)
+}
+
+/// Look `name` up in the embedded packs, which use the same layout as an assets root.
+fn find_embedded(name: &str, kind: AssetKind) -> Option<&'static [u8]> {
+    let path = format!(
+        "{}/{name}.{}",
+        kind.subdir().to_string_lossy(),
+        kind.ext().to_string_lossy()
+    );
+    crate::pack::find_embedded(&path)
+}
+
+fn spirv_words(bytes: &[u8]) -> Result<Vec<u32>, AssetError> {
+    if !bytes.len().is_multiple_of(size_of::<u32>()) {
+        return Err(AssetError::InvalidShader(format!(
+            "SPIR-V length not multiple of 4: {} bytes",
+            bytes.len()
+        )));
+    }
+    Ok(bytes
+        .chunks_exact(size_of::<u32>())
+        .map(|w| u32::from_ne_bytes(w.try_into().unwrap()))
+        .collect())
+}
@@ -101,6 +101,36 @@ pub fn build_shaders() {
     }
 }
 
+/// Pack the crate's assets directory into `OUT_DIR` for [`include_assets!`](crate::include_assets).
+/// Call after building shaders and writing tables.
+pub fn write_pack() {
+    let manifest_dir = &std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    // NOTE no rerun-if-changed on assets.  Building shaders writes there, which would rerun every
+    // build.  Whatever reruns building shaders reruns packing.
+    let root = Path::new(manifest_dir).join("assets");
+    let pack = crate::pack::pack_dir(&root).unwrap();
+    fs::write(Path::new(&out_dir).join("assets.pack"), pack).unwrap();
+}
+
+/// Write a generated table to assets/tables.  The file is only touched when the contents change so
+/// that anything embedding it does not rebuild needlessly.
+pub fn write_table(name: &str, contents: &str) {
+    let manifest_dir = &std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let kind = crate::AssetKind::Table;
+    let mut out = Path::new(manifest_dir)
+        .join("assets")
+        .join(kind.subdir())
+        .join(name);
+    out.set_extension(kind.ext());
+
+    if fs::read_to_string(&out).is_ok_and(|old| old == contents) {
+        return;
+    }
+    fs::create_dir_all(out.parent().unwrap()).unwrap();
+    fs::write(&out, contents).unwrap();
+}
+
 ///  Sets the path for hard coding into the binary for use at runtime by the assets module.
 // Packagers, see the Cargo.toml for the visualizer.
 pub fn set_asset_default_dir() {
@@ -0,0 +1,196 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Compile
+//!
+//! Shaders normally ship as SPIR-V compiled from slang by the build.  Contributors without `slangc`
+//! can still tweak shaders with the **compile** feature.  A WGSL or GLSL source with the same name
+//! as a shader, such as `shaders/ring/compute.wgsl`, overrides its SPIR-V in
+//! [`AssetDirs::find_shader`] and is compiled with naga when loaded.  Put overrides in
+//! `MUTATE_ASSETS_DIR` to leave the build's assets alone.
+//!
+//! Pipeline layouts still come from the slang reflection at build time, so an override must keep the
+//! entry point, bindings, and push constants of the shader it replaces.
+//!
+//! GLSL does not declare its stage, so the extension does, after glslang: `comp`, `vert`, or
+//! `frag`.  WGSL is tried first.
+//!
+//! Compiled SPIR-V is cached by the xxh3 hash of the source, the same hash the build writes beside
+//! SPIR-V, so unchanged sources only compile once.  The cache lives in `MUTATE_SHADER_CACHE` when
+//! set and the user cache directory otherwise.  Without either, shaders compile on every load.
+
+// NOTE naga only knows WGSL and GLSL features, not the buffer device addresses and scalar layouts
+// our slang shaders lean on.  Overrides suit small experiments, not replacing slang.
+// NEXT hot reload watches source hashes once the watcher can hash sources itself.
+
+use std::path::{Path, PathBuf};
+
+use naga::back::spv;
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::assets::read_spirv;
+use crate::prelude::*;
+use crate::{AssetDirs, GlslStage};
+
+fn naga_stage(stage: GlslStage) -> naga::ShaderStage {
+    match stage {
+        GlslStage::Vertex => naga::ShaderStage::Vertex,
+        GlslStage::Fragment => naga::ShaderStage::Fragment,
+        GlslStage::Compute => naga::ShaderStage::Compute,
+    }
+}
+
+impl AssetDirs {
+    /// Compile WGSL or GLSL source for shader `name`, or load it from the cache.
+    pub fn compile_shader(&self, name: &str) -> Result<Vec<u32>, AssetError> {
+        let kinds = std::iter::once(AssetKind::Wgsl).chain(GlslStage::ALL.map(AssetKind::Glsl));
+        let mut tried = Vec::new();
+        for kind in kinds {
+            match self.find(name, kind) {
+                Ok(path) => {
+                    let source = std::fs::read_to_string(&path)?;
+                    return compile_cached(name, &source, kind, cache_dir().as_deref());
+                }
+                Err(AssetError::NotFound { tried: t, .. }) => tried.extend(t),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(AssetError::NotFound {
+            name: name.to_owned(),
+            tried,
+        })
+    }
+}
+
+/// Where compiled shaders are cached, if anywhere.
+pub fn cache_dir() -> Option<PathBuf> {
+    match std::env::var_os("MUTATE_SHADER_CACHE") {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => dirs::cache_dir().map(|d| d.join("mutate").join("shaders")),
+    }
+}
+
+/// Compile `source` of `kind`, reusing SPIR-V cached in `cache` under the source hash.  `name` is
+/// only for errors.  Failing to write the cache is not an error.
+pub fn compile_cached(
+    name: &str,
+    source: &str,
+    kind: AssetKind,
+    cache: Option<&Path>,
+) -> Result<Vec<u32>, AssetError> {
+    let cached = cache.map(|dir| {
+        let hash = xxh3_64(source.as_bytes());
+        dir.join(format!("{hash:x}.{}.spv", kind.ext().to_string_lossy()))
+    });
+    if let Some(words) = cached.as_deref().and_then(|p| read_spirv(p).ok()) {
+        return Ok(words);
+    }
+    let words = compile(name, source, kind)?;
+    if let Some(path) = cached {
+        // Written aside and renamed so that a concurrent load never reads half a shader.
+        let partial = path.with_extension(format!("{}.partial", std::process::id()));
+        let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_ne_bytes()).collect();
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&partial, bytes))
+            .and_then(|_| std::fs::rename(&partial, &path));
+        if let Err(e) = written {
+            eprintln!("warning: shader cache not written for {name}: {e}");
+        }
+    }
+    Ok(words)
+}
+
+/// Compile `source` of `kind` to SPIR-V words.
+pub fn compile(name: &str, source: &str, kind: AssetKind) -> Result<Vec<u32>, AssetError> {
+    let failed = |message: String| AssetError::CompileError {
+        name: name.to_owned(),
+        message,
+    };
+    let module = match kind {
+        AssetKind::Wgsl => {
+            naga::front::wgsl::parse_str(source).map_err(|e| failed(e.emit_to_string(source)))?
+        }
+        AssetKind::Glsl(stage) => naga::front::glsl::Frontend::default()
+            .parse(&naga_stage(stage).into(), source)
+            .map_err(|e| failed(e.emit_to_string(source)))?,
+        _ => return Err(failed(format!("{kind:?} is not shader source"))),
+    };
+    let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|e| failed(e.emit_to_string(source)))?;
+    spv::write_vec(&module, &info, &spv::Options::default(), None)
+        .map_err(|e| failed(e.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+    const WGSL: &str = "
+        @group(0) @binding(0) var<storage, read_write> data: array<u32>;
+
+        @compute @workgroup_size(64)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            data[id.x] = data[id.x] + 1u;
+        }
+    ";
+
+    const GLSL: &str = "
+        #version 450
+        layout(local_size_x = 64) in;
+        layout(set = 0, binding = 0) buffer Data { uint data[]; };
+
+        void main() {
+            data[gl_GlobalInvocationID.x] += 1;
+        }
+    ";
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("mutate-compile-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_compiles_sources() {
+        let wgsl = compile("wgsl", WGSL, AssetKind::Wgsl).unwrap();
+        assert_eq!(wgsl[0], SPIRV_MAGIC);
+        let glsl = compile("glsl", GLSL, AssetKind::Glsl(GlslStage::Compute)).unwrap();
+        assert_eq!(glsl[0], SPIRV_MAGIC);
+
+        let broken = compile("broken", "fn main( {", AssetKind::Wgsl);
+        assert!(matches!(broken, Err(AssetError::CompileError { .. })));
+        assert!(compile("spirv", WGSL, AssetKind::Shader).is_err());
+    }
+
+    #[test]
+    fn test_cache_by_hash() {
+        let dir = scratch("cache");
+        let words = compile_cached("ring", WGSL, AssetKind::Wgsl, Some(&dir)).unwrap();
+        let entries: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(read_spirv(&entries[0]).unwrap(), words);
+
+        // A cache hit never compiles, so a planted entry comes back as is.
+        std::fs::write(&entries[0], SPIRV_MAGIC.to_ne_bytes()).unwrap();
+        let cached = compile_cached("ring", WGSL, AssetKind::Wgsl, Some(&dir)).unwrap();
+        assert_eq!(cached, [SPIRV_MAGIC]);
+
+        // Edited source misses.
+        let edited = WGSL.replace("1u", "2u");
+        let words = compile_cached("ring", &edited, AssetKind::Wgsl, Some(&dir)).unwrap();
+        assert!(words.len() > 1);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,379 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Fonts
+//!
+//! Bitmap fonts in BDF, the X11 bitmap format.  BDF is plain text, so a font is reviewed like
+//! source and needs no rasterizer.  [`BitmapFont::atlas`] packs every glyph into one coverage image
+//! for drawing text as textured quads.
+//!
+//! Only what fixed cell text needs is read: the font bounding box, ascent and descent, and each
+//! glyph's encoding, advance, box, and bitmap.  Glyphs without a Unicode encoding are skipped.
+
+// MAYBE proportional fonts.  Cells are sized to the font bounding box, which wastes atlas space when
+// glyph widths vary a lot.
+
+use std::collections::BTreeMap;
+
+use crate::prelude::*;
+
+/// Cells per atlas row.
+const ATLAS_COLUMNS: u32 = 16;
+
+fn invalid(line: usize, reason: impl Into<String>) -> AssetError {
+    AssetError::InvalidFont(format!("line {}: {}", line + 1, reason.into()))
+}
+
+/// A box in font pixels, offset from the glyph origin on the baseline, y up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BoundingBox {
+    width: u32,
+    height: u32,
+    x: i32,
+    y: i32,
+}
+
+impl BoundingBox {
+    fn parse(line: usize, args: &[&str]) -> Result<Self, AssetError> {
+        let [w, h, x, y] = args else {
+            return Err(invalid(line, "a box needs width, height, x, and y"));
+        };
+        let number = |s: &str| {
+            s.parse::<i32>()
+                .map_err(|_| invalid(line, format!("`{s}` is not a number")))
+        };
+        let size = |s: &str| {
+            s.parse::<u32>()
+                .map_err(|_| invalid(line, format!("`{s}` is not a size")))
+        };
+        Ok(Self {
+            width: size(w)?,
+            height: size(h)?,
+            x: number(x)?,
+            y: number(y)?,
+        })
+    }
+
+    /// Top row, counted up from the baseline.
+    fn top(&self) -> i32 {
+        self.y + self.height as i32
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Glyph {
+    advance: u32,
+    bounds: BoundingBox,
+    /// One row per line of the box, top first, leftmost pixel in the high bit.
+    rows: Vec<u64>,
+}
+
+/// A parsed BDF font.
+#[derive(Clone, Debug)]
+pub struct BitmapFont {
+    bounds: BoundingBox,
+    ascent: u32,
+    descent: u32,
+    glyphs: BTreeMap<char, Glyph>,
+}
+
+impl BitmapFont {
+    pub fn parse(text: &str) -> Result<Self, AssetError> {
+        let mut bounds = None;
+        let (mut ascent, mut descent) = (None, None);
+        let mut glyphs = BTreeMap::new();
+
+        let mut lines = text.lines().enumerate();
+        while let Some((n, line)) = lines.next() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("FONTBOUNDINGBOX") => {
+                    let args: Vec<&str> = words.collect();
+                    bounds = Some(BoundingBox::parse(n, &args)?);
+                }
+                Some("FONT_ASCENT") => ascent = words.next().and_then(|s| s.parse().ok()),
+                Some("FONT_DESCENT") => descent = words.next().and_then(|s| s.parse().ok()),
+                Some("STARTCHAR") => {
+                    if let Some((c, glyph)) = Self::parse_glyph(&mut lines)? {
+                        glyphs.insert(c, glyph);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let bounds = bounds.ok_or_else(|| invalid(0, "no FONTBOUNDINGBOX"))?;
+        if bounds.width > 64 {
+            return Err(invalid(0, "glyphs wider than 64 pixels are not supported"));
+        }
+        for (c, glyph) in &glyphs {
+            let b = glyph.bounds;
+            if b.x < bounds.x
+                || b.y < bounds.y
+                || b.x + b.width as i32 > bounds.x + bounds.width as i32
+                || b.top() > bounds.top()
+            {
+                return Err(AssetError::InvalidFont(format!(
+                    "glyph {c:?} is outside the font bounding box"
+                )));
+            }
+        }
+        Ok(Self {
+            bounds,
+            // Fonts that leave these out are as tall as their bounding box.
+            ascent: ascent.unwrap_or(bounds.top().max(0) as u32),
+            descent: descent.unwrap_or((-bounds.y).max(0) as u32),
+            glyphs,
+        })
+    }
+
+    /// Read from `STARTCHAR` through `ENDCHAR`.  Unencoded glyphs are `None`.
+    fn parse_glyph<'a>(
+        lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    ) -> Result<Option<(char, Glyph)>, AssetError> {
+        let mut encoding = None;
+        let mut advance = None;
+        let mut bounds = None;
+        let mut last = 0;
+        while let Some((n, line)) = lines.next() {
+            last = n;
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("ENCODING") => {
+                    encoding = words.next().and_then(|s| s.parse::<i64>().ok());
+                }
+                Some("DWIDTH") => {
+                    advance = words.next().and_then(|s| s.parse::<u32>().ok());
+                }
+                Some("BBX") => {
+                    let args: Vec<&str> = words.collect();
+                    bounds = Some(BoundingBox::parse(n, &args)?);
+                }
+                Some("BITMAP") => {
+                    let bounds = bounds.ok_or_else(|| invalid(n, "BITMAP before BBX"))?;
+                    let bytes = bounds.width.div_ceil(8) as usize;
+                    if bytes > 8 {
+                        return Err(invalid(n, "glyphs wider than 64 pixels are not supported"));
+                    }
+                    let mut rows = Vec::with_capacity(bounds.height as usize);
+                    for _ in 0..bounds.height {
+                        let (n, row) = lines
+                            .next()
+                            .ok_or_else(|| invalid(n, "bitmap is truncated"))?;
+                        let row = row.trim();
+                        let value = u64::from_str_radix(row, 16)
+                            .ok()
+                            .filter(|_| row.len() == bytes * 2)
+                            .ok_or_else(|| invalid(n, format!("`{row}` is not a bitmap row")))?;
+                        // Left align in 64 bits.
+                        rows.push(value << (64 - bytes * 8));
+                    }
+                    let (n, end) = lines
+                        .next()
+                        .ok_or_else(|| invalid(n, "glyph has no ENDCHAR"))?;
+                    if end.trim() != "ENDCHAR" {
+                        return Err(invalid(n, "bitmap is longer than its box"));
+                    }
+                    let advance = advance.ok_or_else(|| invalid(n, "glyph has no DWIDTH"))?;
+                    let c = encoding
+                        .and_then(|e| u32::try_from(e).ok())
+                        .and_then(char::from_u32);
+                    return Ok(c.map(|c| {
+                        (
+                            c,
+                            Glyph {
+                                advance,
+                                bounds,
+                                rows,
+                            },
+                        )
+                    }));
+                }
+                Some("ENDCHAR") => return Err(invalid(n, "glyph has no BITMAP")),
+                _ => {}
+            }
+        }
+        Err(invalid(last, "glyph has no ENDCHAR"))
+    }
+
+    /// Distance between baselines.
+    pub fn line_height(&self) -> u32 {
+        self.ascent + self.descent
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.glyphs.contains_key(&c)
+    }
+
+    /// Pack every glyph into a grid of cells the size of the font bounding box.
+    pub fn atlas(&self) -> Atlas {
+        let (cell_width, cell_height) = (self.bounds.width, self.bounds.height);
+        let count = self.glyphs.len().max(1) as u32;
+        let columns = count.min(ATLAS_COLUMNS);
+        let width = columns * cell_width;
+        let height = count.div_ceil(columns) * cell_height;
+        let mut pixels = vec![0u8; (width * height) as usize];
+        let mut glyphs = BTreeMap::new();
+
+        for (i, (&c, glyph)) in self.glyphs.iter().enumerate() {
+            let i = i as u32;
+            let origin = [(i % columns) * cell_width, (i / columns) * cell_height];
+            // Where the glyph's box sits inside the cell.
+            let left = (glyph.bounds.x - self.bounds.x) as u32;
+            let top = (self.bounds.top() - glyph.bounds.top()) as u32;
+            for (r, row) in glyph.rows.iter().enumerate() {
+                let y = origin[1] + top + r as u32;
+                for x in 0..glyph.bounds.width {
+                    if row & (1 << (63 - x)) != 0 {
+                        pixels[(y * width + origin[0] + left + x) as usize] = 255;
+                    }
+                }
+            }
+            glyphs.insert(
+                c,
+                AtlasGlyph {
+                    origin,
+                    advance: glyph.advance,
+                },
+            );
+        }
+
+        Atlas {
+            width,
+            height,
+            cell_width,
+            cell_height,
+            baseline: self.bounds.top().max(0) as u32,
+            left: self.bounds.x,
+            ascent: self.ascent,
+            line_height: self.line_height(),
+            pixels,
+            glyphs,
+        }
+    }
+}
+
+/// Where a glyph's cell sits in the [`Atlas`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtlasGlyph {
+    /// Top left pixel of the cell.
+    pub origin: [u32; 2],
+    /// How far the pen moves after this glyph.
+    pub advance: u32,
+}
+
+/// Glyph coverage packed into one image, one byte per pixel, 255 where a glyph is drawn.  All
+/// metrics are in font pixels with y down.
+#[derive(Clone, Debug)]
+pub struct Atlas {
+    pub width: u32,
+    pub height: u32,
+    pub cell_width: u32,
+    pub cell_height: u32,
+    /// Row of each cell the baseline runs through, counted down from the cell's top.
+    pub baseline: u32,
+    /// Offset of a cell's left edge from the pen.
+    pub left: i32,
+    /// Distance from the top of a line to its baseline.
+    pub ascent: u32,
+    pub line_height: u32,
+    /// Row major, `width * height`.
+    pub pixels: Vec<u8>,
+    glyphs: BTreeMap<char, AtlasGlyph>,
+}
+
+impl Atlas {
+    pub fn glyph(&self, c: char) -> Option<AtlasGlyph> {
+        self.glyphs.get(&c).copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Two glyphs and one without an encoding.  `j` hangs below the baseline and `!` is narrower than
+    // the bounding box.
+    const FONT: &str = "\
+STARTFONT 2.1
+FONTBOUNDINGBOX 3 4 0 -1
+STARTPROPERTIES 2
+FONT_ASCENT 3
+FONT_DESCENT 1
+ENDPROPERTIES
+CHARS 3
+STARTCHAR exclam
+ENCODING 33
+DWIDTH 2 0
+BBX 1 3 1 0
+BITMAP
+80
+00
+80
+ENDCHAR
+STARTCHAR j
+ENCODING 106
+DWIDTH 4 0
+BBX 3 4 0 -1
+BITMAP
+20
+20
+20
+C0
+ENDCHAR
+STARTCHAR unencoded
+ENCODING -1
+DWIDTH 4 0
+BBX 3 4 0 -1
+BITMAP
+E0
+E0
+E0
+E0
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn test_parse_and_pack() {
+        let font = BitmapFont::parse(FONT).unwrap();
+        assert_eq!(font.line_height(), 4);
+        assert!(font.contains('j'));
+        assert!(!font.contains('?'));
+
+        let atlas = font.atlas();
+        assert_eq!((atlas.width, atlas.height), (6, 4));
+        assert_eq!(atlas.baseline, 3);
+        let draw = |c: char| {
+            let g = atlas.glyph(c).unwrap();
+            (0..atlas.cell_height)
+                .map(|y| {
+                    (0..atlas.cell_width)
+                        .map(|x| {
+                            let i = (g.origin[1] + y) * atlas.width + g.origin[0] + x;
+                            if atlas.pixels[i as usize] > 0 {
+                                '#'
+                            } else {
+                                '.'
+                            }
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+        };
+        // Offset one pixel right and sitting on the baseline, one row above the cell bottom.
+        assert_eq!(draw('!'), [".#.", "...", ".#.", "..."]);
+        assert_eq!(draw('j'), ["..#", "..#", "..#", "##."]);
+        assert_eq!(atlas.glyph('j').unwrap().advance, 4);
+    }
+
+    #[test]
+    fn test_rejects() {
+        let broken = |from: &str, to: &str| BitmapFont::parse(&FONT.replacen(from, to, 1));
+        assert!(broken("FONTBOUNDINGBOX 3 4 0 -1", "").is_err());
+        assert!(broken("BBX 1 3 1 0", "BBX 1 3 3 0").is_err());
+        assert!(broken("C0\n", "").is_err());
+        assert!(broken("C0", "XYZ").is_err());
+        assert!(broken("DWIDTH 2 0\n", "").is_err());
+    }
+}
@@ -16,8 +16,18 @@
 pub mod assets;
 #[cfg(feature = "build")]
 pub mod build;
+#[cfg(feature = "compile")]
+pub mod compile;
+#[cfg(feature = "runtime")]
+pub mod font;
+#[cfg(any(feature = "runtime", feature = "build"))]
+pub mod pack;
+#[cfg(feature = "runtime")]
+pub mod watch;
 #[cfg(feature = "runtime")]
 pub use assets::*;
+#[cfg(feature = "runtime")]
+pub use watch::ShaderWatcher;
 
 use std::ffi::OsStr;
 
@@ -28,10 +38,38 @@ mod prelude {
     pub use super::AssetKind;
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AssetKind {
     Shader,
     Hash,
+    /// Tables computed at build time, such as window factors.
+    Table,
+    /// WGSL shader source, compiled at load time with the **compile** feature.
+    Wgsl,
+    /// GLSL shader source, compiled at load time with the **compile** feature.
+    Glsl(GlslStage),
+    /// BDF bitmap fonts, read by the `font` module.
+    Font,
+}
+
+/// Stage of a GLSL source, which GLSL leaves to the file extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlslStage {
+    Vertex,
+    Fragment,
+    Compute,
+}
+
+impl GlslStage {
+    pub const ALL: [GlslStage; 3] = [GlslStage::Compute, GlslStage::Vertex, GlslStage::Fragment];
+
+    fn ext(self) -> &'static str {
+        match self {
+            GlslStage::Vertex => "vert",
+            GlslStage::Fragment => "frag",
+            GlslStage::Compute => "comp",
+        }
+    }
 }
 
 impl AssetKind {
@@ -39,6 +77,10 @@ impl AssetKind {
         match self {
             AssetKind::Shader => OsStr::new("spv"),
             AssetKind::Hash => OsStr::new("xx3h"),
+            AssetKind::Table => OsStr::new("toml"),
+            AssetKind::Wgsl => OsStr::new("wgsl"),
+            AssetKind::Glsl(stage) => OsStr::new(stage.ext()),
+            AssetKind::Font => OsStr::new("bdf"),
         }
     }
 
@@ -49,6 +91,10 @@ impl AssetKind {
             // meaning this path is basically never expected to be used unless we produce some kind
             // of hash not associated with a specific asset.
             AssetKind::Hash => OsStr::new("hashes"),
+            AssetKind::Table => OsStr::new("tables"),
+            AssetKind::Wgsl => OsStr::new("shaders"),
+            AssetKind::Glsl(_) => OsStr::new("shaders"),
+            AssetKind::Font => OsStr::new("fonts"),
         }
     }
 }
@@ -64,4 +110,24 @@ pub enum AssetError {
     },
     #[error("load spirv failed: {:?}", .0)]
     InvalidShader(String),
+    #[error("compiling {name} failed:\n{message}")]
+    CompileError { name: String, message: String },
+    #[error("invalid asset pack: {0}")]
+    InvalidPack(String),
+    #[error("invalid font: {0}")]
+    InvalidFont(String),
+    #[error("invalid hash file: {:?}", .0)]
+    InvalidHash(std::path::PathBuf),
+}
+
+/// Embed the assets pack written by [`build::write_pack`] in the calling crate's build script.
+/// Expands to a `Result<`[`pack::AssetPack`]`, AssetError>`.  Register it with [`pack::embed`].
+#[macro_export]
+macro_rules! include_assets {
+    () => {
+        $crate::pack::AssetPack::embedded(::std::include_bytes!(::std::concat!(
+            ::std::env!("OUT_DIR"),
+            "/assets.pack"
+        )))
+    };
 }
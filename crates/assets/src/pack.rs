@@ -0,0 +1,290 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Pack
+//!
+//! [`AssetDirs`](crate::AssetDirs) finds assets on the filesystem, which a single binary does not
+//! have.  A pack is every asset of a crate in one archive that can be embedded in the binary:
+//!
+//! 1. The build script calls [`build::write_pack`](crate::build::write_pack) after building
+//!    shaders, which packs the crate's assets directory into `OUT_DIR`.
+//! 2. The crate embeds it with [`include_assets!`](crate::include_assets) and registers it with
+//!    [`embed`] before loading anything.
+//! 3. Lookups that miss every search path fall back to embedded packs, so installed or overridden
+//!    assets still win.
+//!
+//! Each entry records the xxh3 hash of its contents, checked when the pack is parsed.  A pack that
+//! was truncated or corrupted never serves an asset.
+//!
+//! ## Format
+//!
+//! Little endian.  A header of the magic `MTPK`, a `u32` version, and a `u32` entry count.  Then
+//! one index record per entry: a `u32` path length, the UTF-8 path relative to the assets root with
+//! `/` separators, and `u64` offset, length, and hash.  Offsets count from the end of the index.
+//! Then the data.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::prelude::*;
+
+const MAGIC: &[u8; 4] = b"MTPK";
+const VERSION: u32 = 1;
+
+/// Parsed and verified asset pack.  See the [module docs](self).
+pub struct AssetPack {
+    data: Cow<'static, [u8]>,
+    /// Where the data begins, after the index.
+    start: usize,
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    offset: usize,
+    len: usize,
+    hash: u64,
+}
+
+impl AssetPack {
+    /// Parse a pack embedded with [`include_assets!`](crate::include_assets).
+    pub fn embedded(bytes: &'static [u8]) -> Result<Self, AssetError> {
+        Self::parse(Cow::Borrowed(bytes))
+    }
+
+    /// Parse a pack, such as one read from a file.
+    pub fn from_vec(bytes: Vec<u8>) -> Result<Self, AssetError> {
+        Self::parse(Cow::Owned(bytes))
+    }
+
+    fn parse(data: Cow<'static, [u8]>) -> Result<Self, AssetError> {
+        let mut reader = Reader { data: &data, at: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err(invalid("not an asset pack"));
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(invalid(format!("version {version}, expected {VERSION}")));
+        }
+        let count = reader.u32()?;
+        let mut entries = BTreeMap::new();
+        for _ in 0..count {
+            let len = reader.u32()? as usize;
+            let path = std::str::from_utf8(reader.take(len)?)
+                .map_err(|_| invalid("entry path is not UTF-8"))?
+                .to_owned();
+            let entry = Entry {
+                offset: reader.u64()? as usize,
+                len: reader.u64()? as usize,
+                hash: reader.u64()?,
+            };
+            entries.insert(path, entry);
+        }
+        let start = reader.at;
+        for (path, entry) in &entries {
+            let bytes = start
+                .checked_add(entry.offset)
+                .and_then(|begin| data.get(begin..begin.checked_add(entry.len)?))
+                .ok_or_else(|| invalid(format!("{path} is truncated")))?;
+            if xxh3_64(bytes) != entry.hash {
+                return Err(invalid(format!("{path} does not match its hash")));
+            }
+        }
+        Ok(Self {
+            data,
+            start,
+            entries,
+        })
+    }
+
+    /// Contents of the asset at `path`, relative to the assets root, such as
+    /// `shaders/ring/compute.spv`.
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        let entry = self.entries.get(path)?;
+        let begin = self.start + entry.offset;
+        Some(&self.data[begin..begin + entry.len])
+    }
+
+    /// Hash the asset at `path` was verified against.
+    pub fn hash(&self, path: &str) -> Option<u64> {
+        self.entries.get(path).map(|e| e.hash)
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Write a pack of `entries`, each a path relative to the assets root and its contents.
+pub fn pack<'a>(entries: impl IntoIterator<Item = (&'a str, &'a [u8])>) -> Vec<u8> {
+    let entries: BTreeMap<&str, &[u8]> = entries.into_iter().collect();
+    let mut index = Vec::new();
+    let mut data = Vec::new();
+    for (path, bytes) in &entries {
+        index.extend((path.len() as u32).to_le_bytes());
+        index.extend(path.as_bytes());
+        index.extend((data.len() as u64).to_le_bytes());
+        index.extend((bytes.len() as u64).to_le_bytes());
+        index.extend(xxh3_64(bytes).to_le_bytes());
+        data.extend_from_slice(bytes);
+    }
+    let mut out = Vec::with_capacity(12 + index.len() + data.len());
+    out.extend(MAGIC);
+    out.extend(VERSION.to_le_bytes());
+    out.extend((entries.len() as u32).to_le_bytes());
+    out.extend(index);
+    out.extend(data);
+    out
+}
+
+/// Pack every file below `root`, skipping files that are only read at compile time, such as
+/// reflection data.
+pub fn pack_dir(root: &Path) -> Result<Vec<u8>, AssetError> {
+    fn walk(dir: &Path, root: &Path, found: &mut Vec<(String, Vec<u8>)>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(&path, root, found)?;
+            } else if !path.to_string_lossy().ends_with(".reflection.json") {
+                let relative = path.strip_prefix(root).expect("walked from root");
+                let name = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                found.push((name, std::fs::read(&path)?));
+            }
+        }
+        Ok(())
+    }
+    let mut found = Vec::new();
+    if root.exists() {
+        walk(root, root, &mut found)?;
+    }
+    Ok(pack(found.iter().map(|(p, b)| (p.as_str(), b.as_slice()))))
+}
+
+static EMBEDDED: RwLock<Vec<&'static AssetPack>> = RwLock::new(Vec::new());
+
+/// Serve lookups that miss the filesystem from `pack` for the rest of the program.  Packs embedded
+/// first are searched first.
+pub fn embed(pack: AssetPack) {
+    let pack = Box::leak(Box::new(pack));
+    EMBEDDED
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .push(pack);
+}
+
+/// Contents of `path` from the first embedded pack that has it.
+pub fn find_embedded(path: &str) -> Option<&'static [u8]> {
+    EMBEDDED
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .iter()
+        .find_map(|&pack| pack.get(path))
+}
+
+fn invalid(reason: impl Into<String>) -> AssetError {
+    AssetError::InvalidPack(reason.into())
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], AssetError> {
+        let taken = self
+            .at
+            .checked_add(n)
+            .and_then(|end| self.data.get(self.at..end))
+            .ok_or_else(|| invalid("index is truncated"))?;
+        self.at += n;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, AssetError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, AssetError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        pack([
+            ("shaders/ring/compute.spv", &[3u8, 2, 35, 7][..]),
+            ("shaders/ring/compute.xx3h", b"1f\n"),
+            ("tables/windows.toml", b"hann = []\n"),
+        ])
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let read = AssetPack::from_vec(sample()).unwrap();
+        assert_eq!(read.len(), 3);
+        assert_eq!(
+            read.get("shaders/ring/compute.spv"),
+            Some(&[3u8, 2, 35, 7][..])
+        );
+        assert_eq!(read.get("tables/windows.toml"), Some(&b"hann = []\n"[..]));
+        assert_eq!(read.get("shaders/missing.spv"), None);
+        assert_eq!(
+            read.hash("shaders/ring/compute.xx3h"),
+            Some(xxh3_64(b"1f\n"))
+        );
+        assert_eq!(read.paths().next(), Some("shaders/ring/compute.spv"));
+        assert!(AssetPack::from_vec(pack([])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_damage() {
+        let good = sample();
+        // Flipping the last data byte breaks the last entry's hash.
+        let mut corrupt = good.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            AssetPack::from_vec(corrupt),
+            Err(AssetError::InvalidPack(_))
+        ));
+        for len in [0, 3, 12, 20, good.len() - 1] {
+            assert!(AssetPack::from_vec(good[..len].to_vec()).is_err());
+        }
+        let mut version = good.clone();
+        version[4] = 9;
+        assert!(AssetPack::from_vec(version).is_err());
+    }
+
+    #[test]
+    fn test_pack_dir() {
+        let root = std::env::temp_dir().join(format!("mutate-pack-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("shaders/ring")).unwrap();
+        std::fs::write(root.join("shaders/ring/compute.spv"), [1u8, 2, 3, 4]).unwrap();
+        std::fs::write(root.join("shaders/ring/compute.reflection.json"), "{}").unwrap();
+        let pack = AssetPack::from_vec(pack_dir(&root).unwrap()).unwrap();
+        assert_eq!(
+            pack.paths().collect::<Vec<_>>(),
+            ["shaders/ring/compute.spv"]
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
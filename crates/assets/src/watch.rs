@@ -0,0 +1,175 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Watch
+//!
+//! Shader iteration without restarting.  The build writes a hash of each shader's source beside its
+//! SPIR-V, after the SPIR-V itself.  [`ShaderWatcher`] remembers those hashes for the shaders a
+//! program loaded and reports which ones changed, so the program can reload them at its next frame
+//! boundary.  Rebuilding while the program runs is enough to swap the shaders it draws with.
+//!
+//! Watching is polling.  Checking a few dozen file modification times a couple times a second costs
+//! nothing next to a frame, and a hash is only read when its file was touched.  A hash that does not
+//! parse, such as one caught half-written, is ignored until it does.
+
+// MAYBE inotify or the notify crate once there are enough assets that polling shows up.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::prelude::*;
+use crate::AssetDirs;
+
+/// Polls shader hashes for changes.  See the [module docs](self).
+pub struct ShaderWatcher {
+    interval: Duration,
+    last: Option<Instant>,
+    watched: Vec<Watched>,
+}
+
+struct Watched {
+    name: String,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    hash: Option<u64>,
+}
+
+impl ShaderWatcher {
+    /// [`poll`](Self::poll) checks at most once per `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: None,
+            watched: Vec::new(),
+        }
+    }
+
+    /// Watch the shader `name`, as passed to [`AssetDirs::find_shader`].
+    pub fn watch(&mut self, dirs: &AssetDirs, name: &str) -> Result<(), AssetError> {
+        let path = dirs.find_hash(name, AssetKind::Shader)?;
+        self.watch_hash(name, path);
+        Ok(())
+    }
+
+    /// Watch the hash file at `path`, reporting changes as `name`.  Watching a name again replaces
+    /// its path.
+    pub fn watch_hash(&mut self, name: &str, path: PathBuf) {
+        let watched = Watched {
+            name: name.to_owned(),
+            modified: modified(&path),
+            hash: read_hash(&path).ok(),
+            path,
+        };
+        match self.watched.iter_mut().find(|w| w.name == name) {
+            Some(existing) => *existing = watched,
+            None => self.watched.push(watched),
+        }
+    }
+
+    /// Names of shaders whose hash changed since the last check, unless the last check was less than
+    /// an interval ago.
+    pub fn poll(&mut self) -> Vec<String> {
+        let now = Instant::now();
+        if self.last.is_some_and(|last| now < last + self.interval) {
+            return Vec::new();
+        }
+        self.last = Some(now);
+        self.check()
+    }
+
+    /// Names of shaders whose hash changed since the last check, checking now.
+    pub fn check(&mut self) -> Vec<String> {
+        let mut changed = Vec::new();
+        for watched in &mut self.watched {
+            let modified = modified(&watched.path);
+            if modified == watched.modified {
+                continue;
+            }
+            let Ok(hash) = read_hash(&watched.path) else {
+                continue;
+            };
+            watched.modified = modified;
+            if watched.hash.replace(hash) != Some(hash) {
+                changed.push(watched.name.clone());
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Parse a hash file written by the build.
+pub fn read_hash(path: &Path) -> Result<u64, AssetError> {
+    let text = std::fs::read_to_string(path)?;
+    u64::from_str_radix(text.trim(), 16).map_err(|_| AssetError::InvalidHash(path.to_owned()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mutate-watch-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write a hash and make sure its modification time moves, even on coarse filesystems.
+    fn write(path: &Path, text: &str) {
+        let before = modified(path);
+        std::fs::write(path, text).unwrap();
+        while modified(path) == before {
+            std::thread::sleep(Duration::from_millis(10));
+            std::fs::write(path, text).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_reports_changed_hashes() {
+        let dir = scratch("changed");
+        let ring = dir.join("ring.xx3h");
+        let bank = dir.join("bank.xx3h");
+        write(&ring, "1f\n");
+        write(&bank, "2e\n");
+
+        let mut watcher = ShaderWatcher::new(Duration::ZERO);
+        watcher.watch_hash("ring/compute", ring.clone());
+        watcher.watch_hash("bank/compute", bank.clone());
+        assert!(watcher.check().is_empty());
+
+        write(&ring, "3d\n");
+        assert_eq!(watcher.check(), ["ring/compute"]);
+        assert!(watcher.check().is_empty());
+
+        // Touched but unchanged is not a change.
+        write(&bank, "2e\n");
+        assert!(watcher.check().is_empty());
+
+        // Half-written hashes wait for the rest.
+        write(&bank, "");
+        assert!(watcher.check().is_empty());
+        write(&bank, "4c\n");
+        assert_eq!(watcher.check(), ["bank/compute"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_poll_interval() {
+        let dir = scratch("interval");
+        let ring = dir.join("ring.xx3h");
+        write(&ring, "1f\n");
+
+        let mut watcher = ShaderWatcher::new(Duration::from_secs(3600));
+        watcher.watch_hash("ring/compute", ring.clone());
+        assert!(watcher.poll().is_empty());
+        write(&ring, "3d\n");
+        assert!(watcher.poll().is_empty());
+        assert_eq!(watcher.check(), ["ring/compute"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -39,6 +39,7 @@ mod pipeline;
 mod push;
 mod root;
 mod slang;
+mod spirv;
 mod stage;
 
 /// # `#[stage]`
@@ -50,6 +51,14 @@ mod stage;
 /// #[stage("test/hello_compute", COMPUTE, c"main")]
 /// struct GoodStage {}
 /// ```
+///
+/// Add `push = Name` to also emit the shader's push constant block as a Pod struct named `Name`,
+/// with fields read from the SPIR-V.
+///
+/// ```ignore
+/// #[stage("triangle/vertex", Vertex, c"main", push = TrianglePush)]
+/// struct TriangleVertex;
+/// ```
 #[proc_macro_attribute]
 pub fn stage(
     attr: proc_macro::TokenStream,
@@ -223,6 +232,9 @@ pub fn derive_push(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 /// )]
 /// pub struct Compute;
 /// ```
+///
+/// Add `tier = Full` when the shader needs 8 or 16-bit features.  Devices without them load the
+/// shader's `-core` variant instead.
 #[proc_macro_attribute]
 pub fn compute_pipeline(
     attr: proc_macro::TokenStream,
@@ -92,19 +92,21 @@ impl Parse for ComputeValue {
 /// Parsed fields from the attribute list:
 ///
 /// ```text
-/// compute = SomeStage, push = SomePush
+/// compute = SomeStage, push = SomePush, tier = Full
 /// ```
 ///
-/// Both fields are required; order is flexible.
+/// `compute` and `push` are required; order is flexible.
 struct ComputePipelineAttr {
     compute: ComputeValue,
     push: PushValue,
+    tier: Option<Ident>,
 }
 
 impl Parse for ComputePipelineAttr {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut compute: Option<ComputeValue> = None;
         let mut push: Option<PushValue> = None;
+        let mut tier: Option<Ident> = None;
 
         loop {
             if input.is_empty() {
@@ -126,10 +128,22 @@ impl Parse for ComputePipelineAttr {
                         return Err(syn::Error::new(key.span(), "duplicate `push` field"));
                     }
                 }
+                "tier" => {
+                    let value: Ident = input.parse()?;
+                    if value != "Core" && value != "Full" {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            format!("unknown tier `{value}`, expected `Core` or `Full`"),
+                        ));
+                    }
+                    if tier.replace(value).is_some() {
+                        return Err(syn::Error::new(key.span(), "duplicate `tier` field"));
+                    }
+                }
                 other => {
                     return Err(syn::Error::new(
                         key.span(),
-                        format!("unknown field `{other}`, expected `compute` or `push`"),
+                        format!("unknown field `{other}`, expected `compute`, `push`, or `tier`"),
                     ));
                 }
             }
@@ -144,6 +158,7 @@ impl Parse for ComputePipelineAttr {
                 .ok_or_else(|| syn::Error::new(input.span(), "missing required field `compute`"))?,
             push: push
                 .ok_or_else(|| syn::Error::new(input.span(), "missing required field `push`"))?,
+            tier,
         })
     }
 }
@@ -156,7 +171,11 @@ pub(crate) fn compute_pipeline(attr: TokenStream, item: TokenStream) -> syn::Res
     let input = syn::parse2::<syn::DeriveInput>(item)?;
     let type_name = &input.ident;
 
-    let ComputePipelineAttr { compute, push } = syn::parse2::<ComputePipelineAttr>(attr)?;
+    let ComputePipelineAttr {
+        compute,
+        push,
+        tier,
+    } = syn::parse2::<ComputePipelineAttr>(attr)?;
 
     let (push_type, inline_push_items) = match push {
         PushValue::External(ident) => (ident, quote::quote! {}),
@@ -172,11 +191,14 @@ pub(crate) fn compute_pipeline(attr: TokenStream, item: TokenStream) -> syn::Res
         ComputeValue::Inline(stage_attr) => {
             // Emit Stage<Slot> and StageReflection<Slot> directly onto the
             // pipeline type.  No synthetic type needed.
-            let stage_impls = emit_stage_impls(type_name, stage_attr)?;
+            let stage_impls = emit_stage_impls(type_name, &input.vis, stage_attr)?;
             (type_name.clone(), stage_impls)
         }
     };
 
+    let tier_item =
+        tier.map(|tier| quote::quote! { const TIER: #root::Tier = #root::Tier::#tier; });
+
     Ok(quote::quote! {
         #input
 
@@ -187,6 +209,7 @@ pub(crate) fn compute_pipeline(attr: TokenStream, item: TokenStream) -> syn::Res
             type Push       = #push_type;
             type LayoutSpec = #push_type;
             type Stage      = #stage_type;
+            #tier_item
         }
     })
 }
@@ -8,7 +8,7 @@ use quote::quote;
 /// Resolves the root path to the `__` private module of mutate_vulkan.  The `::__` suffix is
 /// included in the return token so you may write `#root::` directly to access `__`.
 ///
-/// Uses `mutate_vulkan` or re-export as `vulkan` via `mutate_lib` facade.
+/// Uses `mutate_vulkan` or re-export as `gpu` via `mutate_lib` facade.
 pub(crate) fn mutate_vulkan_root() -> TokenStream {
     if let Ok(found) = crate_name("mutate-vulkan") {
         return match found {
@@ -22,10 +22,10 @@ pub(crate) fn mutate_vulkan_root() -> TokenStream {
 
     if let Ok(found) = crate_name("mutate-lib") {
         return match found {
-            FoundCrate::Itself => quote!(crate::vulkan::__),
+            FoundCrate::Itself => quote!(crate::gpu::__),
             FoundCrate::Name(name) => {
                 let ident = Ident::new(&name, Span::call_site());
-                quote!(::#ident::vulkan::__)
+                quote!(::#ident::gpu::__)
             }
         };
     }
@@ -0,0 +1,639 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # SPIR-V
+//!
+//! Just enough SPIR-V reading to recover push constant blocks and descriptor bindings from compiled
+//! shaders.  The slang reflection JSON is written beside the SPIR-V, but SPIR-V is what the driver
+//! actually sees, and it also describes shaders that did not come from slang.
+//!
+//! Push constant members are read with their explicit `Offset` decorations, so the block layout is
+//! whatever the compiler decided, scalar or otherwise.  Types we cannot express as plain Rust data,
+//! such as booleans and nested structs, are errors rather than guesses.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::collections::HashMap;
+
+const MAGIC: u32 = 0x0723_0203;
+
+// Opcodes
+const OP_NAME: u32 = 5;
+const OP_MEMBER_NAME: u32 = 6;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+const OP_TYPE_ACCELERATION_STRUCTURE: u32 = 5341;
+
+// Decorations
+const BUFFER_BLOCK: u32 = 3;
+const ARRAY_STRIDE: u32 = 6;
+const MATRIX_STRIDE: u32 = 7;
+const BINDING: u32 = 33;
+const DESCRIPTOR_SET: u32 = 34;
+const OFFSET: u32 = 35;
+
+// Storage classes
+const UNIFORM_CONSTANT: u32 = 0;
+const UNIFORM: u32 = 2;
+const PUSH_CONSTANT: u32 = 9;
+const STORAGE_BUFFER: u32 = 12;
+const PHYSICAL_STORAGE_BUFFER: u32 = 5349;
+
+const DIM_BUFFER: u32 = 5;
+
+/// What the macros need to know about a shader module.
+#[derive(Debug, Default)]
+pub(crate) struct Reflection {
+    pub push: Option<PushBlock>,
+    /// Sorted by set and binding.
+    pub bindings: Vec<Binding>,
+}
+
+#[derive(Debug)]
+pub(crate) struct PushBlock {
+    /// Name of the block's struct type, if the module kept names.
+    #[allow(unused)]
+    pub name: Option<String>,
+    /// End of the last member.  SPIR-V does not record any trailing padding.
+    pub size: usize,
+    /// Sorted by offset.
+    pub members: Vec<Member>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Member {
+    pub name: Option<String>,
+    pub offset: usize,
+    pub ty: Ty,
+}
+
+#[derive(Debug)]
+pub(crate) struct Binding {
+    pub set: u32,
+    pub binding: u32,
+    pub name: Option<String>,
+    pub descriptor: Descriptor,
+}
+
+/// Descriptor type of a binding, as the Vulkan descriptor set layout would need to declare it.
+/// Arrays of descriptors have the type of their elements.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Descriptor {
+    Sampler,
+    CombinedImageSampler,
+    SampledImage,
+    StorageImage,
+    UniformBuffer,
+    StorageBuffer,
+    UniformTexelBuffer,
+    StorageTexelBuffer,
+    AccelerationStructure,
+    Unknown,
+}
+
+impl Descriptor {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Descriptor::Sampler => "sampler",
+            Descriptor::CombinedImageSampler => "combined image sampler",
+            Descriptor::SampledImage => "sampled image",
+            Descriptor::StorageImage => "storage image",
+            Descriptor::UniformBuffer => "uniform buffer",
+            Descriptor::StorageBuffer => "storage buffer",
+            Descriptor::UniformTexelBuffer => "uniform texel buffer",
+            Descriptor::StorageTexelBuffer => "storage texel buffer",
+            Descriptor::AccelerationStructure => "acceleration structure",
+            Descriptor::Unknown => "unrecognized resource",
+        }
+    }
+}
+
+/// Types representable as plain Rust data.  Vectors and matrices are arrays.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Ty {
+    Int {
+        width: u32,
+        signed: bool,
+    },
+    Float {
+        width: u32,
+    },
+    Array(Box<Ty>, usize),
+    /// A `PhysicalStorageBuffer` pointer, which is a 64-bit device address.
+    Address,
+}
+
+impl Ty {
+    pub fn size(&self) -> usize {
+        match self {
+            Ty::Int { width, .. } | Ty::Float { width } => *width as usize / 8,
+            Ty::Array(elem, len) => elem.size() * len,
+            Ty::Address => 8,
+        }
+    }
+
+    /// Alignment of the Rust type, which decides where `repr(C)` would put it.
+    pub fn align(&self) -> usize {
+        match self {
+            Ty::Array(elem, _) => elem.align(),
+            _ => self.size(),
+        }
+    }
+
+    pub fn tokens(&self) -> TokenStream {
+        match self {
+            Ty::Int { width, signed } => {
+                let name = format!("{}{width}", if *signed { 'i' } else { 'u' });
+                let ident = syn::Ident::new(&name, proc_macro2::Span::call_site());
+                quote!(#ident)
+            }
+            Ty::Float { width } => {
+                let ident = syn::Ident::new(&format!("f{width}"), proc_macro2::Span::call_site());
+                quote!(#ident)
+            }
+            Ty::Array(elem, len) => {
+                let elem = elem.tokens();
+                quote!([#elem; #len])
+            }
+            Ty::Address => quote!(u64),
+        }
+    }
+}
+
+/// Types as declared, before deciding whether Rust can express them.
+enum Decl {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { elem: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Array { elem: u32, len: u32 },
+    RuntimeArray { elem: u32 },
+    Struct { members: Vec<u32> },
+    Pointer { class: u32, pointee: u32 },
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    SampledImage,
+    AccelerationStructure,
+}
+
+#[derive(Default)]
+struct Module {
+    names: HashMap<u32, String>,
+    member_names: HashMap<(u32, u32), String>,
+    decorations: HashMap<(u32, u32), u32>,
+    member_offsets: HashMap<(u32, u32), u32>,
+    member_matrix_strides: HashMap<(u32, u32), u32>,
+    types: HashMap<u32, Decl>,
+    constants: HashMap<u32, u32>,
+    /// Result id, pointer type, and storage class of each global variable.
+    variables: Vec<(u32, u32, u32)>,
+}
+
+/// Read the push constant block and descriptor bindings out of a SPIR-V module.
+pub(crate) fn reflect(words: &[u32]) -> Result<Reflection, String> {
+    let module = parse(words)?;
+
+    let mut reflection = Reflection::default();
+    for &(id, pointer, class) in &module.variables {
+        if class == PUSH_CONSTANT {
+            let block = match module.types.get(&pointer) {
+                Some(Decl::Pointer { pointee, .. }) => *pointee,
+                _ => return Err(format!("push constant %{id} is not a pointer")),
+            };
+            if reflection.push.is_some() {
+                // MAYBE several entry points with their own blocks, selected by entry name.
+                return Err("more than one push constant block".to_owned());
+            }
+            reflection.push = Some(module.push_block(block)?);
+        } else if let Some(&set) = module.decorations.get(&(id, DESCRIPTOR_SET)) {
+            reflection.bindings.push(Binding {
+                set,
+                binding: module.decorations.get(&(id, BINDING)).copied().unwrap_or(0),
+                name: module.names.get(&id).cloned(),
+                descriptor: module.descriptor(pointer, class),
+            });
+        }
+    }
+    reflection.bindings.sort_by_key(|b| (b.set, b.binding));
+    Ok(reflection)
+}
+
+fn parse(words: &[u32]) -> Result<Module, String> {
+    match words.first() {
+        Some(&MAGIC) if words.len() >= 5 => {}
+        Some(&magic) if magic.swap_bytes() == MAGIC => {
+            return Err("SPIR-V is byte swapped".to_owned());
+        }
+        _ => return Err("not a SPIR-V module".to_owned()),
+    }
+
+    let mut module = Module::default();
+    let mut at = 5;
+    while at < words.len() {
+        let count = (words[at] >> 16) as usize;
+        let opcode = words[at] & 0xffff;
+        if count == 0 || at + count > words.len() {
+            return Err(format!("truncated instruction at word {at}"));
+        }
+        let ops = &words[at + 1..at + count];
+        at += count;
+
+        let op = |i: usize| {
+            ops.get(i)
+                .copied()
+                .ok_or_else(|| format!("opcode {opcode} is missing operand {i}"))
+        };
+        match opcode {
+            OP_NAME => {
+                module.names.insert(op(0)?, string(&ops[1..]));
+            }
+            OP_MEMBER_NAME => {
+                module
+                    .member_names
+                    .insert((op(0)?, op(1)?), string(&ops[2..]));
+            }
+            OP_DECORATE => {
+                module
+                    .decorations
+                    .insert((op(0)?, op(1)?), op(2).unwrap_or(0));
+            }
+            OP_MEMBER_DECORATE => match op(2)? {
+                OFFSET => {
+                    module.member_offsets.insert((op(0)?, op(1)?), op(3)?);
+                }
+                MATRIX_STRIDE => {
+                    module
+                        .member_matrix_strides
+                        .insert((op(0)?, op(1)?), op(3)?);
+                }
+                _ => {}
+            },
+            OP_TYPE_BOOL => {
+                module.types.insert(op(0)?, Decl::Bool);
+            }
+            OP_TYPE_INT => {
+                let decl = Decl::Int {
+                    width: op(1)?,
+                    signed: op(2)? != 0,
+                };
+                module.types.insert(op(0)?, decl);
+            }
+            OP_TYPE_FLOAT => {
+                module.types.insert(op(0)?, Decl::Float { width: op(1)? });
+            }
+            OP_TYPE_VECTOR => {
+                let decl = Decl::Vector {
+                    elem: op(1)?,
+                    count: op(2)?,
+                };
+                module.types.insert(op(0)?, decl);
+            }
+            OP_TYPE_MATRIX => {
+                let decl = Decl::Matrix {
+                    column: op(1)?,
+                    count: op(2)?,
+                };
+                module.types.insert(op(0)?, decl);
+            }
+            OP_TYPE_ARRAY => {
+                let decl = Decl::Array {
+                    elem: op(1)?,
+                    len: op(2)?,
+                };
+                module.types.insert(op(0)?, decl);
+            }
+            OP_TYPE_IMAGE => {
+                let decl = Decl::Image {
+                    dim: op(2)?,
+                    sampled: op(6)?,
+                };
+                module.types.insert(op(0)?, decl);
+            }
+            OP_TYPE_SAMPLER => {
+                module.types.insert(op(0)?, Decl::Sampler);
+            }
+            OP_TYPE_SAMPLED_IMAGE => {
+                module.types.insert(op(0)?, Decl::SampledImage);
+            }
+            OP_TYPE_ACCELERATION_STRUCTURE => {
+                module.types.insert(op(0)?, Decl::AccelerationStructure);
+            }
+            OP_TYPE_RUNTIME_ARRAY => {
+                module
+                    .types
+                    .insert(op(0)?, Decl::RuntimeArray { elem: op(1)? });
+            }
+            OP_TYPE_STRUCT => {
+                let decl = Decl::Struct {
+                    members: ops.get(1..).unwrap_or_default().to_vec(),
+                };
+                module.types.insert(op(0)?, decl);
+            }
+            OP_TYPE_POINTER => {
+                let decl = Decl::Pointer {
+                    class: op(1)?,
+                    pointee: op(2)?,
+                };
+                module.types.insert(op(0)?, decl);
+            }
+            // Only 32-bit constants are read, which covers array lengths.
+            OP_CONSTANT if ops.len() == 3 => {
+                module.constants.insert(op(1)?, op(2)?);
+            }
+            // Globals only.  Function variables come after the first function and use the
+            // `Function` storage class, which nothing here looks for.
+            OP_VARIABLE => module.variables.push((op(1)?, op(0)?, op(2)?)),
+            _ => {}
+        }
+    }
+    Ok(module)
+}
+
+/// Decode a nul-terminated literal string.
+fn string(words: &[u32]) -> String {
+    let bytes: Vec<u8> = words
+        .iter()
+        .flat_map(|w| w.to_le_bytes())
+        .take_while(|&b| b != 0)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+impl Module {
+    fn descriptor(&self, pointer: u32, class: u32) -> Descriptor {
+        let Some(Decl::Pointer { pointee, .. }) = self.types.get(&pointer) else {
+            return Descriptor::Unknown;
+        };
+        let mut id = *pointee;
+        while let Some(Decl::Array { elem, .. } | Decl::RuntimeArray { elem }) = self.types.get(&id)
+        {
+            id = *elem;
+        }
+        match (class, self.types.get(&id)) {
+            (UNIFORM_CONSTANT, Some(Decl::Sampler)) => Descriptor::Sampler,
+            (UNIFORM_CONSTANT, Some(Decl::SampledImage)) => Descriptor::CombinedImageSampler,
+            (UNIFORM_CONSTANT, Some(Decl::Image { dim, sampled })) => {
+                match (*dim == DIM_BUFFER, *sampled) {
+                    (false, 1) => Descriptor::SampledImage,
+                    (false, 2) => Descriptor::StorageImage,
+                    (true, 1) => Descriptor::UniformTexelBuffer,
+                    (true, 2) => Descriptor::StorageTexelBuffer,
+                    // Sampled 0 is only known at runtime.
+                    _ => Descriptor::Unknown,
+                }
+            }
+            (UNIFORM_CONSTANT, Some(Decl::AccelerationStructure)) => {
+                Descriptor::AccelerationStructure
+            }
+            // Storage buffers were uniform buffer blocks before SPIR-V 1.3.
+            (UNIFORM, Some(Decl::Struct { .. }))
+                if self.decorations.contains_key(&(id, BUFFER_BLOCK)) =>
+            {
+                Descriptor::StorageBuffer
+            }
+            (UNIFORM, Some(Decl::Struct { .. })) => Descriptor::UniformBuffer,
+            (STORAGE_BUFFER, Some(Decl::Struct { .. })) => Descriptor::StorageBuffer,
+            _ => Descriptor::Unknown,
+        }
+    }
+
+    fn push_block(&self, id: u32) -> Result<PushBlock, String> {
+        let Some(Decl::Struct { members: types }) = self.types.get(&id) else {
+            return Err("push constant block is not a struct".to_owned());
+        };
+        let mut members = Vec::with_capacity(types.len());
+        for (index, &ty) in types.iter().enumerate() {
+            let key = (id, index as u32);
+            let name = self.member_names.get(&key).cloned();
+            let label = name.clone().unwrap_or_else(|| format!("member {index}"));
+            let offset = *self
+                .member_offsets
+                .get(&key)
+                .ok_or_else(|| format!("{label} has no offset"))? as usize;
+            let stride = self.member_matrix_strides.get(&key).copied();
+            let ty = self.ty(ty, stride).map_err(|e| format!("{label}: {e}"))?;
+            members.push(Member { name, offset, ty });
+        }
+        members.sort_by_key(|m| m.offset);
+        let size = members.last().map_or(0, |m| m.offset + m.ty.size());
+        Ok(PushBlock {
+            name: self.names.get(&id).cloned(),
+            size,
+            members,
+        })
+    }
+
+    fn ty(&self, id: u32, matrix_stride: Option<u32>) -> Result<Ty, String> {
+        match self.types.get(&id) {
+            Some(Decl::Int { width, signed }) => Ok(Ty::Int {
+                width: *width,
+                signed: *signed,
+            }),
+            Some(Decl::Float { width: 16 }) => Err("16-bit floats are not supported".to_owned()),
+            Some(Decl::Float { width }) => Ok(Ty::Float { width: *width }),
+            Some(Decl::Vector { elem, count }) => {
+                Ok(Ty::Array(Box::new(self.ty(*elem, None)?), *count as usize))
+            }
+            Some(Decl::Matrix { column, count }) => {
+                let column = self.ty(*column, None)?;
+                if let Some(stride) = matrix_stride
+                    && stride as usize != column.size()
+                {
+                    return Err(format!(
+                        "matrix stride {stride} pads columns of {} bytes",
+                        column.size()
+                    ));
+                }
+                Ok(Ty::Array(Box::new(column), *count as usize))
+            }
+            Some(Decl::Array { elem, len }) => {
+                let elem_ty = self.ty(*elem, None)?;
+                let len = *self
+                    .constants
+                    .get(len)
+                    .ok_or("array length is not a 32-bit constant")?;
+                if let Some(&stride) = self.decorations.get(&(id, ARRAY_STRIDE))
+                    && stride as usize != elem_ty.size()
+                {
+                    return Err(format!(
+                        "array stride {stride} pads elements of {} bytes",
+                        elem_ty.size()
+                    ));
+                }
+                Ok(Ty::Array(Box::new(elem_ty), len as usize))
+            }
+            Some(Decl::Pointer { class, .. }) if *class == PHYSICAL_STORAGE_BUFFER => {
+                Ok(Ty::Address)
+            }
+            Some(Decl::Bool) => Err("booleans have no defined size".to_owned()),
+            // NEXT nested structs, emitted as their own Pod structs.
+            Some(Decl::Struct { .. }) => Err("nested structs are not supported".to_owned()),
+            Some(Decl::RuntimeArray { .. }) => Err("runtime arrays have no size".to_owned()),
+            Some(
+                Decl::Image { .. }
+                | Decl::Sampler
+                | Decl::SampledImage
+                | Decl::AccelerationStructure,
+            ) => Err("opaque handles cannot be push constants".to_owned()),
+            Some(Decl::Pointer { .. }) => {
+                Err("only device address pointers are supported".to_owned())
+            }
+            None => Err(format!("unknown type %{id}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Assembles modules one instruction at a time.
+    struct Asm(Vec<u32>);
+
+    impl Asm {
+        fn new() -> Self {
+            Asm(vec![MAGIC, 0x0001_0300, 0, 100, 0])
+        }
+
+        fn op(&mut self, opcode: u32, operands: &[u32]) -> &mut Self {
+            self.0.push(((operands.len() as u32 + 1) << 16) | opcode);
+            self.0.extend(operands);
+            self
+        }
+
+        fn named(&mut self, opcode: u32, operands: &[u32], name: &str) -> &mut Self {
+            let mut bytes = name.as_bytes().to_vec();
+            bytes.resize(name.len() / 4 * 4 + 4, 0);
+            let mut all = operands.to_vec();
+            all.extend(
+                bytes
+                    .chunks(4)
+                    .map(|c| u32::from_le_bytes(c.try_into().unwrap())),
+            );
+            self.op(opcode, &all)
+        }
+    }
+
+    /// `struct Push { float4 color; float scale; uint* data; }` at scalar offsets, plus a storage
+    /// buffer at set 0, binding 3.
+    fn module(scale_type: u32) -> Vec<u32> {
+        let (float, vec4, uint, ptr, block, block_ptr, push, ssbo) = (1, 2, 3, 4, 5, 6, 7, 8);
+        let mut asm = Asm::new();
+        asm.named(OP_NAME, &[block], "PushData")
+            .named(OP_NAME, &[ssbo], "gData")
+            .named(OP_MEMBER_NAME, &[block, 0], "uColor")
+            .named(OP_MEMBER_NAME, &[block, 1], "_scale")
+            .op(OP_MEMBER_DECORATE, &[block, 0, OFFSET, 0])
+            .op(OP_MEMBER_DECORATE, &[block, 1, OFFSET, 16])
+            .op(OP_MEMBER_DECORATE, &[block, 2, OFFSET, 24])
+            .op(OP_DECORATE, &[ssbo, DESCRIPTOR_SET, 0])
+            .op(OP_DECORATE, &[ssbo, BINDING, 3])
+            .op(OP_TYPE_FLOAT, &[float, 32])
+            .op(OP_TYPE_VECTOR, &[vec4, float, 4])
+            .op(OP_TYPE_INT, &[uint, 32, 0])
+            .op(OP_TYPE_POINTER, &[ptr, PHYSICAL_STORAGE_BUFFER, uint])
+            .op(OP_TYPE_STRUCT, &[block, vec4, scale_type, ptr])
+            .op(OP_TYPE_POINTER, &[block_ptr, PUSH_CONSTANT, block])
+            .op(OP_VARIABLE, &[block_ptr, push, PUSH_CONSTANT])
+            .op(OP_VARIABLE, &[ptr, ssbo, 12]);
+        asm.0
+    }
+
+    #[test]
+    fn test_push_block() {
+        let reflection = reflect(&module(1)).unwrap();
+        let push = reflection.push.unwrap();
+        assert_eq!(push.name.as_deref(), Some("PushData"));
+        assert_eq!(push.size, 32);
+        let members: Vec<_> = push
+            .members
+            .iter()
+            .map(|m| (m.name.as_deref(), m.offset, m.ty.clone()))
+            .collect();
+        let float = Ty::Float { width: 32 };
+        assert_eq!(
+            members,
+            [
+                (Some("uColor"), 0, Ty::Array(Box::new(float.clone()), 4)),
+                (Some("_scale"), 16, float),
+                (None, 24, Ty::Address),
+            ]
+        );
+        assert_eq!(members[0].2.tokens().to_string(), "[f32 ; 4usize]");
+
+        assert_eq!(reflection.bindings.len(), 1);
+        assert_eq!(reflection.bindings[0].binding, 3);
+        assert_eq!(reflection.bindings[0].name.as_deref(), Some("gData"));
+    }
+
+    #[test]
+    fn test_descriptors() {
+        let (uint, sampler, samplers, image, storage, texels, data, block, combined) =
+            (1, 2, 3, 4, 5, 6, 7, 8, 9);
+        let mut asm = Asm::new();
+        asm.op(OP_DECORATE, &[block, BUFFER_BLOCK])
+            .op(OP_TYPE_INT, &[uint, 32, 0])
+            .op(OP_TYPE_SAMPLER, &[sampler])
+            .op(OP_TYPE_RUNTIME_ARRAY, &[samplers, sampler])
+            .op(OP_TYPE_IMAGE, &[image, uint, 1, 0, 0, 0, 1, 0])
+            .op(OP_TYPE_IMAGE, &[storage, uint, 1, 0, 0, 0, 2, 0])
+            .op(OP_TYPE_IMAGE, &[texels, uint, DIM_BUFFER, 0, 0, 0, 1, 0])
+            .op(OP_TYPE_STRUCT, &[data, uint])
+            .op(OP_TYPE_STRUCT, &[block, uint])
+            .op(OP_TYPE_SAMPLED_IMAGE, &[combined, image]);
+        let cases = [
+            (samplers, UNIFORM_CONSTANT, Descriptor::Sampler),
+            (image, UNIFORM_CONSTANT, Descriptor::SampledImage),
+            (storage, UNIFORM_CONSTANT, Descriptor::StorageImage),
+            (texels, UNIFORM_CONSTANT, Descriptor::UniformTexelBuffer),
+            (data, UNIFORM, Descriptor::UniformBuffer),
+            (data, STORAGE_BUFFER, Descriptor::StorageBuffer),
+            (block, UNIFORM, Descriptor::StorageBuffer),
+            (combined, UNIFORM_CONSTANT, Descriptor::CombinedImageSampler),
+            (uint, STORAGE_BUFFER, Descriptor::Unknown),
+        ];
+        for (binding, &(pointee, class, _)) in cases.iter().enumerate() {
+            let (pointer, variable) = (100 + 2 * binding as u32, 101 + 2 * binding as u32);
+            asm.op(OP_DECORATE, &[variable, DESCRIPTOR_SET, 0])
+                .op(OP_DECORATE, &[variable, BINDING, binding as u32])
+                .op(OP_TYPE_POINTER, &[pointer, class, pointee])
+                .op(OP_VARIABLE, &[pointer, variable, class]);
+        }
+        let reflection = reflect(&asm.0).unwrap();
+        let found: Vec<_> = reflection.bindings.iter().map(|b| b.descriptor).collect();
+        let expected: Vec<_> = cases.iter().map(|c| c.2).collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_rejects() {
+        // The struct's own id as a member type is a nested struct.
+        let nested = reflect(&module(5)).unwrap_err();
+        assert!(nested.contains("_scale: nested structs"), "{nested}");
+
+        assert!(reflect(&[]).is_err());
+        assert!(reflect(&[MAGIC.swap_bytes(), 0, 0, 0, 0]).is_err());
+        let mut truncated = module(1);
+        truncated.pop();
+        assert!(reflect(&truncated).is_err());
+
+        // A module without push constants is fine.
+        let empty = reflect(&Asm::new().0).unwrap();
+        assert!(empty.push.is_none() && empty.bindings.is_empty());
+    }
+}
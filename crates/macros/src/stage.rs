@@ -5,8 +5,29 @@
 //!
 //! Emits `Stage<Slot>` and `StageReflection<Slot>` impls onto any type, usually a pipeline or other
 //! ZST used for naming.
+//!
+//! The compiled SPIR-V is reflected to size the stage's push constant block.  With `push = Name`,
+//! the block is also emitted as a `repr(C)` Pod struct, so host code writes the fields the shader
+//! declares instead of a hand-counted array:
+//!
+//! ```ignore
+//! #[stage("triangle/vertex", Vertex, c"main", push = TrianglePush)]
+//! struct TriangleVertex;
+//!
+//! let push = TrianglePush { u_color: [1.0, 0.0, 0.0, 1.0], scale: 0.5 };
+//! ```
+//!
+//! Member names are converted to snake case with leading underscores dropped, since slang code
+//! often marks members a stage does not read that way.  Gaps between members become `_padN` byte
+//! arrays.
+//!
+//! Descriptor bindings are checked against the one bindless set every pipeline shares.  A binding
+//! outside set 0, or of a type the set has no slot for, is an error here.  Other bindings become
+//! assertions against the `SLOT_*` constants of `mutate_vulkan::device::descriptors`, so a storage
+//! buffer bound at the uniform buffer slot fails to compile instead of tripping validation layers.
 
 use proc_macro2::{TokenStream, Span};
+use quote::format_ident;
 use syn::Token;
 use syn::{
     parse::{Parse, ParseStream},
@@ -15,13 +36,17 @@ use syn::{
 
 use mutate_assets as assets;
 
-/// Parsed stage.  Accepts shader file stem, stage flags, and entry point.
+use crate::spirv;
+
+/// Parsed stage.  Accepts shader file stem, stage flags, entry point, and optionally `push = Name`.
 pub(crate) struct StageAttr {
     /// Shader file name, without extension.
     file: LitStr,
     /// Stage marker type, one that implements `[StageSlot](mutate_vulkan::pipeline::stage::StageSlot)`.
     stage: syn::Ident,
     entry: Option<LitCStr>,
+    /// Name of a struct to emit from the reflected push constant block.
+    push: Option<syn::Ident>,
 }
 
 impl Parse for StageAttr {
@@ -29,13 +54,29 @@ impl Parse for StageAttr {
         let file: LitStr = input.parse()?;
         input.parse::<Token![,]>()?;
         let stage: syn::Ident = input.parse()?;
-        let entry = if input.peek(Token![,]) {
+        let mut entry = None;
+        let mut push = None;
+        while input.peek(Token![,]) {
             input.parse::<Token![,]>()?;
-            Some(input.parse::<LitCStr>()?)
-        } else {
-            None
-        };
-        Ok(StageAttr { file, stage, entry })
+            if input.peek(LitCStr) && entry.is_none() && push.is_none() {
+                entry = Some(input.parse::<LitCStr>()?);
+            } else if input.peek(syn::Ident) && input.peek2(Token![=]) {
+                let key: syn::Ident = input.parse()?;
+                if key != "push" {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("unknown field `{key}`, expected `push`"),
+                    ));
+                }
+                input.parse::<Token![=]>()?;
+                if push.replace(input.parse::<syn::Ident>()?).is_some() {
+                    return Err(syn::Error::new(key.span(), "duplicate `push` field"));
+                }
+            } else if !input.is_empty() {
+                return Err(input.error("expected entry point or `push = Name`"));
+            }
+        }
+        Ok(StageAttr { file, stage, entry, push })
     }
 }
 
@@ -46,7 +87,7 @@ pub(crate) fn stage(attr: TokenStream, item: TokenStream) -> syn::Result<TokenSt
     let type_name  = &input.ident;
     let stage_attr = syn::parse2::<StageAttr>(attr)?;
 
-    let impls = emit_stage_impls(type_name, stage_attr)?;
+    let impls = emit_stage_impls(type_name, &input.vis, stage_attr)?;
 
     Ok(quote::quote! {
         #input
@@ -56,9 +97,10 @@ pub(crate) fn stage(attr: TokenStream, item: TokenStream) -> syn::Result<TokenSt
 
 // XXX Docs out of date
 /// Attach knowledge of a shader stage to a concrete type.  Implements witness traits that enable
-/// downstream type checking.
+/// downstream type checking.  A requested push struct gets the visibility `vis`.
 pub(crate) fn emit_stage_impls(
     target: &syn::Ident,
+    vis: &syn::Visibility,
     attr: StageAttr,
 ) -> syn::Result<TokenStream> {
     let root = crate::root::mutate_vulkan_root();
@@ -67,6 +109,7 @@ pub(crate) fn emit_stage_impls(
         file: file_literal,
         stage,
         entry,
+        push,
     } = attr;
 
     let file_string = file_literal.value();
@@ -108,8 +151,24 @@ pub(crate) fn emit_stage_impls(
     // DEBT error handling
     let hash_path_string = hash.unwrap().into_os_string().into_string().unwrap();
 
-    // NEXT: drive CONSTANT_BLOCK_SIZE from actual reflection data read off `shader`.
-    let constant_block_size: usize = 0;
+    let reflection = spirv::reflect(&shader.unwrap()).map_err(|e| {
+        syn::Error::new_spanned(&file_literal, format!("Failed to reflect {file_string:?}: {e}"))
+    })?;
+    let binding_checks = emit_binding_checks(&root, &file_literal, &reflection.bindings)?;
+    let constant_block_size = reflection.push.as_ref().map_or(0, |p| p.size);
+
+    let push_struct = match push {
+        Some(name) => match &reflection.push {
+            Some(block) => emit_push_struct(&root, vis, &name, block)?,
+            None => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("shader {file_string:?} has no push constants"),
+                ));
+            }
+        },
+        None => TokenStream::new(),
+    };
 
     Ok(quote::quote! {
         impl #root::Stage
@@ -128,6 +187,10 @@ pub(crate) fn emit_stage_impls(
             const CONSTANT_BLOCK_SIZE: usize = #constant_block_size;
         }
 
+        #push_struct
+
+        #binding_checks
+
         // NOTE Emit code that includes the bytes to ensure the compiler watches this file for future
         // changes.  We're just watching the hash to load less into memory and then using a zero
         // size range to save the linker the trouble.
@@ -137,3 +200,168 @@ pub(crate) fn emit_stage_impls(
         const _: &[u8] = ::std::include_bytes!(#hash_path_string);
     })
 }
+
+/// Check `bindings` against the bindless descriptor set, erroring on what can never fit and
+/// asserting the slot of everything else.
+fn emit_binding_checks(
+    root: &TokenStream,
+    file: &LitStr,
+    bindings: &[spirv::Binding],
+) -> syn::Result<TokenStream> {
+    use spirv::Descriptor as D;
+
+    let shader = file.value();
+    let mut checks = Vec::new();
+    for binding in bindings {
+        let spirv::Binding { set, binding: number, descriptor, .. } = binding;
+        let what = match &binding.name {
+            Some(name) => format!("{} `{name}`", descriptor.describe()),
+            None       => descriptor.describe().to_owned(),
+        };
+        if *set != 0 {
+            return Err(syn::Error::new_spanned(
+                file,
+                format!(
+                    "shader {shader:?} binds {what} at set {set}, binding {number}, but the \
+                     bindless layout only has set 0"
+                ),
+            ));
+        }
+        let slot = match descriptor {
+            D::Sampler            => "SLOT_SAMPLERS",
+            D::SampledImage       => "SLOT_SAMPLED_IMAGES",
+            D::StorageImage       => "SLOT_STORAGE_IMAGES",
+            D::UniformBuffer      => "SLOT_UNIFORM_BUFFERS",
+            D::StorageBuffer      => "SLOT_STORAGE_BUFFERS",
+            D::UniformTexelBuffer => "SLOT_UNIFORM_TEXEL_BUFFERS",
+            D::StorageTexelBuffer => "SLOT_STORAGE_TEXEL_BUFFERS",
+            D::CombinedImageSampler | D::AccelerationStructure | D::Unknown => {
+                return Err(syn::Error::new_spanned(
+                    file,
+                    format!(
+                        "shader {shader:?} binds {what} at binding {number}, which has no slot \
+                         in the bindless layout"
+                    ),
+                ));
+            }
+        };
+        let slot    = format_ident!("{slot}");
+        let message = format!(
+            "shader {shader:?} binds {what} at binding {number}, not at the bindless {} slot",
+            descriptor.describe(),
+        );
+        checks.push(quote::quote! {
+            assert!(#number == #root::descriptor_slots::#slot, #message);
+        });
+    }
+    if checks.is_empty() {
+        return Ok(TokenStream::new());
+    }
+    Ok(quote::quote! {
+        const _: () = {
+            #(#checks)*
+        };
+    })
+}
+
+/// Emit `name` with the members of the reflected push constant `block`, explicitly padded so that
+/// every byte is a field and the struct can be Pod.
+fn emit_push_struct(
+    root: &TokenStream,
+    vis: &syn::Visibility,
+    name: &syn::Ident,
+    block: &spirv::PushBlock,
+) -> syn::Result<TokenStream> {
+    let mut fields  = Vec::new();
+    let mut asserts = Vec::new();
+    let mut at      = 0;
+    let mut align   = 1;
+    for (index, member) in block.members.iter().enumerate() {
+        let label = member.name.clone().unwrap_or_else(|| format!("member {index}"));
+        if member.offset < at {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("push constant {label:?} overlaps the member before it"),
+            ));
+        }
+        if member.offset % member.ty.align() != 0 {
+            return Err(syn::Error::new(
+                name.span(),
+                format!(
+                    "push constant {label:?} at offset {} is not aligned for `{}`",
+                    member.offset,
+                    member.ty.tokens(),
+                ),
+            ));
+        }
+        if member.offset > at {
+            let pad   = format_ident!("_pad{}", fields.len());
+            let bytes = member.offset - at;
+            fields.push(quote::quote!(pub #pad: [u8; #bytes]));
+        }
+        let field  = field_name(member.name.as_deref(), index);
+        let ty     = member.ty.tokens();
+        let offset = member.offset;
+        fields.push(quote::quote!(pub #field: #ty));
+        asserts.push(quote::quote! {
+            assert!(::core::mem::offset_of!(#name, #field) == #offset);
+        });
+        at    = member.offset + member.ty.size();
+        align = align.max(member.ty.align());
+    }
+    if !block.size.is_multiple_of(align) {
+        return Err(syn::Error::new(
+            name.span(),
+            format!(
+                "push constant block of {} bytes would gain trailing padding in Rust",
+                block.size
+            ),
+        ));
+    }
+    let size = block.size;
+
+    Ok(quote::quote! {
+        #[repr(C)]
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #vis struct #name {
+            #(#fields,)*
+        }
+
+        const _: () = {
+            assert!(::core::mem::size_of::<#name>() == #size);
+            #(#asserts)*
+        };
+
+        // SAFETY every field is Pod and the assertions above rule out implicit padding.
+        unsafe impl #root::bytemuck::Zeroable for #name {}
+        unsafe impl #root::bytemuck::Pod for #name {}
+
+        impl #name {
+            /// Bytes to push, laid out as the shader reads them.
+            #vis fn as_bytes(&self) -> &[u8] {
+                #root::bytemuck::bytes_of(self)
+            }
+        }
+    })
+}
+
+/// Snake case field name for a reflected member, such as `u_color` for `uColor`.
+fn field_name(reflected: Option<&str>, index: usize) -> syn::Ident {
+    let mut name = String::new();
+    let mut lower = false;
+    for c in reflected.unwrap_or_default().trim_start_matches('_').chars() {
+        if c.is_ascii_uppercase() && lower {
+            name.push('_');
+        }
+        lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        name.push(c.to_ascii_lowercase());
+    }
+    if name.is_empty() {
+        name = format!("field{index}");
+    }
+    // Keywords such as `type` are not valid field names.
+    if syn::parse_str::<syn::Ident>(&name).is_err() {
+        name.push('_');
+    }
+    format_ident!("{name}")
+}
@@ -30,3 +30,4 @@ macro_rules! trybuild_fail {
 trybuild_pass!(independently_declared);
 trybuild_pass!(with_inline_stage);
 trybuild_pass!(with_inline_push);
+trybuild_pass!(with_tier);
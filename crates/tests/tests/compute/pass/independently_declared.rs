@@ -5,8 +5,8 @@
 // This is the case where the generated RANGES must contain exactly two entries
 // and the ranges must not share a stage flag (VUID-00292).
 
+use mutate_lib::gpu;
 use mutate_lib::prelude::*;
-use mutate_lib::vulkan;
 
 #[stage("test/hello_compute", Compute, c"main")]
 pub struct ComputeStage {}
@@ -26,8 +26,8 @@ pub struct ComputePipeline;
 
 fn main() {
     use ash::vk::ShaderStageFlags;
-    use vulkan::pipeline::layout::LayoutSpec;
-    use vulkan::pipeline::ComputePipelineSpec;
+    use gpu::pipeline::layout::LayoutSpec;
+    use gpu::pipeline::ComputePipelineSpec;
 
     fn assert_push<S: ComputePipelineSpec<Push = ComputeConstants>>() {}
     fn assert_stage<S: ComputePipelineSpec<Stage = ComputeStage>>() {}
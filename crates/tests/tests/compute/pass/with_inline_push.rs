@@ -1,8 +1,8 @@
 // Copyright 2026 The MuTate Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use mutate_lib::gpu;
 use mutate_lib::prelude::*;
-use mutate_lib::vulkan;
 
 #[stage("test/hello_compute", Compute, c"main")]
 pub struct ComputeStage {}
@@ -18,8 +18,8 @@ pub struct TestPipeline;
 
 fn main() {
     use ash::vk::ShaderStageFlags;
-    use vulkan::pipeline::layout::LayoutSpec;
-    use vulkan::pipeline::ComputePipelineSpec;
+    use gpu::pipeline::layout::LayoutSpec;
+    use gpu::pipeline::ComputePipelineSpec;
 
     fn assert_push<S: ComputePipelineSpec<Push = ComputeConstants>>() {}
     fn assert_stage<S: ComputePipelineSpec<Stage = ComputeStage>>() {}
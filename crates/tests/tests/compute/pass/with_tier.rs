@@ -0,0 +1,38 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+// A pipeline declares the feature tier its shader needs.  Without `tier`, pipelines need Core.
+
+use mutate_lib::gpu;
+use mutate_lib::prelude::*;
+
+#[stage("test/hello_compute", Compute, c"main")]
+pub struct ComputeStage {}
+
+#[derive(GpuType, Push)]
+#[repr(C)]
+pub struct ComputeConstants {
+    #[visible(Compute)]
+    foo: UInt,
+}
+
+#[compute_pipeline(
+    compute = ComputeStage,
+    push = ComputeConstants,
+    tier = Full,
+)]
+pub struct FullPipeline;
+
+#[compute_pipeline(
+    compute = ComputeStage,
+    push = ComputeConstants,
+)]
+pub struct CorePipeline;
+
+fn main() {
+    use gpu::device::capabilities::Tier;
+    use gpu::pipeline::ComputePipelineSpec;
+
+    assert_eq!(<FullPipeline as ComputePipelineSpec>::TIER, Tier::Full);
+    assert_eq!(<CorePipeline as ComputePipelineSpec>::TIER, Tier::Core);
+}
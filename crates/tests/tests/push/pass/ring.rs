@@ -3,8 +3,8 @@
 
 // This test was created from a buggy set of push constants.
 
+use mutate_lib::gpu;
 use mutate_lib::prelude::*;
-use mutate_lib::vulkan;
 
 #[derive(GpuType, Push)]
 #[repr(C)]
@@ -20,7 +20,7 @@ struct RawRingPushConstants {
 
 fn main() {
     use ash::vk::ShaderStageFlags;
-    use vulkan::pipeline::layout::LayoutSpec;
+    use gpu::pipeline::layout::LayoutSpec;
 
     let ranges = <RawRingPushConstants as LayoutSpec>::RANGES;
 
@@ -3,8 +3,8 @@
 //
 // Two stages with identical visible ranges must emit a single range with the combined flags.
 
+use mutate_lib::gpu;
 use mutate_lib::prelude::*;
-use mutate_lib::vulkan;
 
 #[derive(GpuType, Push)]
 #[repr(C)]
@@ -14,6 +14,6 @@ struct SharedPush {
 }
 
 fn main() {
-    use vulkan::pipeline::layout::LayoutSpec;
+    use gpu::pipeline::layout::LayoutSpec;
     assert_eq!(<SharedPush as LayoutSpec>::RANGES.len(), 1);
 }
@@ -5,8 +5,8 @@
 // This is the case where the generated RANGES must contain exactly two entries
 // and the ranges must not share a stage flag (VUID-00292).
 
+use mutate_lib::gpu;
 use mutate_lib::prelude::*;
-use mutate_lib::vulkan;
 
 #[derive(GpuType, Push)]
 #[repr(C)]
@@ -19,7 +19,7 @@ struct SplitPush {
 
 fn main() {
     use ash::vk::ShaderStageFlags;
-    use vulkan::pipeline::layout::LayoutSpec;
+    use gpu::pipeline::layout::LayoutSpec;
 
     let ranges = <SplitPush as LayoutSpec>::RANGES;
 
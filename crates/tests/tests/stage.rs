@@ -28,5 +28,7 @@ macro_rules! trybuild_fail {
 
 // basic smoke tests
 trybuild_pass!(compute);
+trybuild_pass!(push_struct);
 
 trybuild_fail!(shader_missing);
+trybuild_fail!(binding_set);
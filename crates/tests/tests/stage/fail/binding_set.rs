@@ -0,0 +1,9 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use mutate_lib::prelude::*;
+
+#[stage("test/binding_set", Compute, c"main")]
+struct OutsideSet {}
+
+fn main() {}
@@ -0,0 +1,21 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use mutate_lib::gpu::pipeline::stage::{Compute, StageReflection};
+use mutate_lib::prelude::*;
+
+#[stage("reflect/push_constants_global", Compute, c"main", push = PushData)]
+struct PushStage {}
+
+// Scalar block layout, four 32-bit members.
+const _: () = assert!(<PushStage as StageReflection<Compute>>::CONSTANT_BLOCK_SIZE == 16);
+
+fn main() {
+    let push = PushData {
+        dispatch_id: 1,
+        scale: 0.5,
+        ubo_index: 2,
+        ssbo_index: 3,
+    };
+    assert_eq!(push.as_bytes().len(), 16);
+}
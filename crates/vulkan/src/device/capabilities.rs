@@ -0,0 +1,178 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Capabilities
+//!
+//! Not every device that can run MuTate has every feature we like to use.  Devices are sorted into
+//! tiers when they are listed:
+//!
+//! - [`Tier::Core`] needs Vulkan 1.3, bindless descriptors, buffer device addresses, and the rest of
+//!   the required features.  Older integrated GPUs often stop here.
+//! - [`Tier::Full`] adds 8 and 16-bit storage and arithmetic, such as `storage_push_constant8`.
+//!
+//! A device below Core is not supported at all.  Pipelines declare the tier their shaders need with
+//! [`ComputePipelineSpec::TIER`](crate::pipeline::ComputePipelineSpec::TIER), Core unless they say
+//! otherwise.  On a device below that tier, a shader `name` is replaced by its Core variant, such as
+//! `ring/compute-core`, which trades precision or resolution for running at all.  Without a variant,
+//! creating the pipeline fails with [`VulkanError::MissingTier`].
+//!
+//! ## Samples
+//!
+//! Multisampling is not a tier.  Every device supports 1 and 4 samples per pixel for color
+//! attachments, and most support 2 and 8.  [`Capabilities::samples`] turns a requested count into
+//! the closest count the device supports, rounding down.
+
+use ash::vk;
+
+use crate::VulkanError;
+
+/// Features only [`Tier::Full`] requires, as `Instance::missing_features` names them.
+pub(crate) const FULL_FEATURES: &[&str] = &[
+    "shader_int16",
+    "1.1 storage_buffer16_bit_access",
+    "1.1 storage_push_constant16",
+    "1.1 uniform_and_storage_buffer16_bit_access",
+    "1.2 shader_float16",
+    "1.2 shader_int8",
+    "1.2 storage_buffer8_bit_access",
+    "1.2 storage_push_constant8",
+    "1.2 uniform_and_storage_buffer8_bit_access",
+];
+
+/// Suffix of a shader's Core variant.
+pub const CORE_VARIANT_SUFFIX: &str = "-core";
+
+/// Feature tier of a device or a shader.  Tiers are ordered, so `Full > Core`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Tier {
+    #[default]
+    Core,
+    Full,
+}
+
+impl Tier {
+    /// Tier of a device missing the required features `missing`, if it has one.
+    pub fn classify(missing: &[&str]) -> Option<Tier> {
+        if missing.is_empty() {
+            Some(Tier::Full)
+        } else if missing.iter().all(|m| FULL_FEATURES.contains(m)) {
+            Some(Tier::Core)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tier::Core => "core",
+            Tier::Full => "full",
+        }
+    }
+}
+
+/// What the device was created with.  See the [module docs](self).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    pub tier: Tier,
+    /// Sample counts supported by color attachments.
+    pub color_samples: vk::SampleCountFlags,
+}
+
+impl Capabilities {
+    pub fn supports(&self, tier: Tier) -> bool {
+        self.tier >= tier
+    }
+
+    /// The most samples per pixel the device supports, up to `requested`.  Never fewer than one.
+    pub fn samples(&self, requested: u32) -> vk::SampleCountFlags {
+        [64, 32, 16, 8, 4, 2]
+            .into_iter()
+            .map(vk::SampleCountFlags::from_raw)
+            .find(|&count| count.as_raw() <= requested && self.color_samples.contains(count))
+            .unwrap_or(vk::SampleCountFlags::TYPE_1)
+    }
+
+    /// Name of the shader to load for `name`, which needs `required`.  `exists` is asked about the
+    /// Core variant when the device is below `required`.
+    pub fn shader_name(
+        &self,
+        name: &str,
+        required: Tier,
+        exists: impl FnOnce(&str) -> bool,
+    ) -> Result<String, VulkanError> {
+        if self.supports(required) {
+            return Ok(name.to_owned());
+        }
+        let variant = format!("{name}{CORE_VARIANT_SUFFIX}");
+        if exists(&variant) {
+            Ok(variant)
+        } else {
+            Err(VulkanError::MissingTier {
+                shader: name.to_owned(),
+                required,
+                available: self.tier,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(Tier::classify(&[]), Some(Tier::Full));
+        assert_eq!(
+            Tier::classify(&["1.2 storage_push_constant8", "shader_int16"]),
+            Some(Tier::Core)
+        );
+        assert_eq!(
+            Tier::classify(&["1.2 storage_push_constant8", "1.2 buffer_device_address"]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_shader_name() {
+        let samples = vk::SampleCountFlags::TYPE_1;
+        let core = Capabilities {
+            tier: Tier::Core,
+            color_samples: samples,
+        };
+        let full = Capabilities {
+            tier: Tier::Full,
+            color_samples: samples,
+        };
+        let never = |_: &str| panic!("variant looked up");
+        assert_eq!(
+            full.shader_name("ring/compute", Tier::Full, never).unwrap(),
+            "ring/compute"
+        );
+        assert_eq!(
+            core.shader_name("ring/compute", Tier::Core, never).unwrap(),
+            "ring/compute"
+        );
+        let found = core.shader_name("ring/compute", Tier::Full, |v| v == "ring/compute-core");
+        assert_eq!(found.unwrap(), "ring/compute-core");
+        assert!(matches!(
+            core.shader_name("ring/compute", Tier::Full, |_| false),
+            Err(VulkanError::MissingTier { .. })
+        ));
+    }
+
+    #[test]
+    fn test_samples() {
+        let caps = Capabilities {
+            tier: Tier::Core,
+            color_samples: vk::SampleCountFlags::TYPE_1
+                | vk::SampleCountFlags::TYPE_2
+                | vk::SampleCountFlags::TYPE_4,
+        };
+        assert_eq!(caps.samples(4), vk::SampleCountFlags::TYPE_4);
+        assert_eq!(caps.samples(8), vk::SampleCountFlags::TYPE_4);
+        assert_eq!(caps.samples(3), vk::SampleCountFlags::TYPE_2);
+        assert_eq!(caps.samples(1), vk::SampleCountFlags::TYPE_1);
+        assert_eq!(caps.samples(0), vk::SampleCountFlags::TYPE_1);
+    }
+}
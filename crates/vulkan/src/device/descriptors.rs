@@ -29,6 +29,36 @@
 //! make one structure to track all of our descriptors.  It initializes with a big descriptor set.
 //! It has a static fixed size because any kind of dynamic growth messes up the descriptor slots and
 //! forces us to think about descriptors.  Okay, glad we are experts at Vulkan now!
+//!
+//! ## Registration and Frames
+//!
+//! Resources are registered into their type's array with
+//! [`register_sampled_image`](Descriptors::register_sampled_image),
+//! [`register_storage_image`](Descriptors::register_storage_image), and
+//! [`register_storage_buffer`](Descriptors::register_storage_buffer).  The [`Handle`] comes back at
+//! once and its index can be recorded into push constants right away, but the write is only
+//! staged.  Once per frame, after recording and before submitting, [`flush`](Descriptors::flush)
+//! applies every staged write in one update.  Work submitted outside of a frame, such as a
+//! one-off compute dispatch, flushes before its own submission.
+//!
+//! The set is bound in command buffers that may still be executing, which is only legal because
+//! every binding is update-after-bind.  The remaining rule is that no pending work may use a slot
+//! that is being written.  New slots are never used by pending work.  Released slots are, so
+//! [`release`](Descriptors::release) holds an index until the work that may still use it is done.
+//! Work that signals a timeline semaphore flushes with
+//! [`flush_signaling`](Descriptors::flush_signaling), and slots released before that flush are
+//! reused once every timeline flushed so far reaches the value it had then.  Work without a
+//! timeline flushes with [`flush`](Descriptors::flush), and its slots also wait [`RETIRE_FLUSHES`]
+//! flushes.
+//!
+//! Each array holds [`ARRAY_CAPACITY`] descriptors.  Registering past that is an error rather than
+//! a write out of bounds.
+//!
+//! ## Generations
+//!
+//! Every slot counts its releases.  A handle remembers the count at registration, so a handle
+//! that outlived its release no longer [`resolve`](Descriptors::resolve)s, and releasing it twice
+//! can't put one slot on the free list twice.  Shaders only get the bare index.
 
 // DEBT The descriptor management strategy has been marked up-in-the-air pending a design pass to
 // confirm or update the strategy taking shape.
@@ -36,6 +66,8 @@
 // blindly.
 // NEXT hand out Image descriptors on Image creation because not having descriptors would make them
 // kind of useless.
+// NEXT uniform and texel buffers and samplers beyond the defaults register the same way once
+// something needs them.
 // DEBT The synchronization of DescriptorsMut is hacky, only good enough to ignore while instead
 // working out the concurrency of the Device, which should be shared when possible (usually possible
 // unless multiple devices and displays have exclusive physical connections).  Updating descriptors
@@ -47,6 +79,7 @@ use std::{collections::VecDeque, slice, sync::Mutex};
 use ash::vk;
 
 use crate::descriptor_newtype;
+use crate::dispatch::sync::WaitValue;
 use crate::prelude::*;
 use crate::resource::image::ImageView;
 
@@ -74,28 +107,193 @@ pub const UNKNOWN: u32 = 8;
 // ROLL waiting on instance support for runtime extension dependency resolution.
 // pub const SLOT_ACCEL_STRUCTURES: u32      = 9;
 
-/// A few lies and some interior mutability to work on liberating the device.
-struct DescriptorsMut {
+/// Flushes that a released index waits before it may be registered again, when no timeline says
+/// when the work using it is done.  Work recorded before the release may still be executing until
+/// then.
+pub const RETIRE_FLUSHES: u64 = 3;
+
+/// Descriptors in each array of the set.
+// DEBT Max descriptor size calculation / management.
+pub const ARRAY_CAPACITY: u32 = 256;
+
+/// Index types of the arrays that [`Descriptors`] hands out [`Handle`]s into.
+pub trait Bindless: DescriptorIndex + Copy + std::fmt::Debug {
+    /// Binding of the array in the set, one of the `SLOT_*` constants.
+    const BINDING: u32;
+
+    fn from_raw(index: u32) -> Self;
+
+    fn to_raw(self) -> u32;
+}
+
+macro_rules! bindless {
+    ($name:ident, $binding:expr) => {
+        impl Bindless for $name {
+            const BINDING: u32 = $binding;
+
+            fn from_raw(index: u32) -> Self {
+                $name::new(index)
+            }
+
+            fn to_raw(self) -> u32 {
+                self.raw()
+            }
+        }
+    };
+}
+
+bindless!(SamplerIdx, SLOT_SAMPLERS);
+bindless!(SampledImageIdx, SLOT_SAMPLED_IMAGES);
+bindless!(StorageImageIdx, SLOT_STORAGE_IMAGES);
+bindless!(UboIdx, SLOT_UNIFORM_BUFFERS);
+bindless!(SsboIdx, SLOT_STORAGE_BUFFERS);
+bindless!(UniformTexelBufferIdx, SLOT_UNIFORM_TEXEL_BUFFERS);
+bindless!(StorageTexelBufferIdx, SLOT_STORAGE_TEXEL_BUFFERS);
+
+/// A registered descriptor.  Shaders only ever see [`index`](Self::index), usually through push
+/// constants.  The generation is the slot's at registration, so a handle kept after its release is
+/// caught by [`Descriptors::resolve`] and [`Descriptors::release`] instead of quietly naming
+/// whatever resource took the slot next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Handle<I> {
+    index: I,
+    generation: u32,
+}
+
+impl<I: Bindless> Handle<I> {
+    /// The index to pass to shaders.  Unchecked, so only push handles that are still registered.
+    pub fn index(&self) -> I {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// When a released slot may be written again.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Retirement {
+    /// The flush, when the work submitted after it signals no timeline.  That work is assumed
+    /// done [`RETIRE_FLUSHES`] later.
+    flush: Option<u64>,
+    /// Values that every timeline flushed so far must reach.
+    timelines: Vec<(vk::Semaphore, u64)>,
+}
+
+impl Retirement {
+    /// Whether the work that may use the slot is done at flush `now`.  `counter` reads a
+    /// timeline's value, or `None` for a timeline that was
+    /// [forgotten](Descriptors::forget_timeline).
+    fn is_done(&self, now: u64, counter: &impl Fn(vk::Semaphore) -> Option<u64>) -> bool {
+        let flushed = self.flush.is_none_or(|at| now - at >= RETIRE_FLUSHES);
+        let signaled = self
+            .timelines
+            .iter()
+            .all(|&(semaphore, value)| counter(semaphore).is_none_or(|c| c >= value));
+        flushed && signaled
+    }
+}
+
+/// Indexes of one descriptor array.
+#[derive(Default)]
+struct Slots {
     // Track the next never-used index.  This is implicitly a high-water mark for properly sizing
     // arrays.
-    next_sampler: SamplerIdx,
-    next_sampled_image: SampledImageIdx,
-    next_storage_image: StorageImageIdx,
-    next_ubo: UboIdx,
-    next_ssbo: SsboIdx,
-    next_utxb: UniformTexelBufferIdx,
-    next_stxb: StorageTexelBufferIdx,
-    // next_accel: AccelStructIdx,
-
+    next: u32,
     // Keep any re-usable indexes.
-    freelist_samplers: VecDeque<SamplerIdx>,
-    freelist_sampled_images: VecDeque<SampledImageIdx>,
-    freelist_storage_images: VecDeque<StorageImageIdx>,
-    freelist_ubos: VecDeque<UboIdx>,
-    freelist_ssbos: VecDeque<SsboIdx>,
-    freelist_utxbs: VecDeque<UniformTexelBufferIdx>,
-    // freelist_accels: VecDeque<AccelStructIdx>,
-    freelist_stxbs: VecDeque<StorageTexelBufferIdx>,
+    free: VecDeque<u32>,
+    // Released since the last flush, so possibly used by the work submitted after it.
+    released: Vec<u32>,
+    // Released indexes and when they retire.  They join `free` once retired.
+    retiring: Vec<(Retirement, u32)>,
+    // Generation of every index handed out so far, bumped on release.
+    generations: Vec<u32>,
+}
+
+impl Slots {
+    /// Slots below `next` are reserved, such as for the default samplers.
+    fn starting_at(next: u32) -> Self {
+        Self {
+            next,
+            generations: vec![0; next as usize],
+            ..Default::default()
+        }
+    }
+
+    /// Index and generation of a free slot, or `None` when all [`ARRAY_CAPACITY`] are taken.
+    fn take(&mut self) -> Option<(u32, u32)> {
+        let index = match self.free.pop_back() {
+            Some(index) => index,
+            None if self.next < ARRAY_CAPACITY => {
+                self.next += 1;
+                self.generations.push(0);
+                self.next - 1
+            }
+            None => return None,
+        };
+        Some((index, self.generations[index as usize]))
+    }
+
+    fn is_live(&self, index: u32, generation: u32) -> bool {
+        self.generations.get(index as usize) == Some(&generation)
+    }
+
+    /// Stop handing out a slot until the next flush says when it retires.  False if the
+    /// generation is stale.
+    fn release(&mut self, index: u32, generation: u32) -> bool {
+        if !self.is_live(index, generation) {
+            return false;
+        }
+        self.generations[index as usize] = generation.wrapping_add(1);
+        self.released.push(index);
+        true
+    }
+
+    /// Slots released since the last flush retire as `retirement` says.
+    fn submit(&mut self, retirement: &Retirement) {
+        let released = self.released.drain(..);
+        self.retiring
+            .extend(released.map(|index| (retirement.clone(), index)));
+    }
+
+    /// Free the slots whose work is done at flush `now`.
+    fn retire(&mut self, now: u64, counter: &impl Fn(vk::Semaphore) -> Option<u64>) {
+        let free = &mut self.free;
+        self.retiring.retain(|(retirement, index)| {
+            let done = retirement.is_done(now, counter);
+            if done {
+                free.push_back(*index);
+            }
+            !done
+        });
+    }
+}
+
+/// Descriptor info of a staged write.
+enum StagedInfo {
+    Image(vk::DescriptorImageInfo),
+    Buffer(vk::DescriptorBufferInfo),
+}
+
+/// A write waiting for the next flush.
+struct StagedWrite {
+    binding: u32,
+    ty: vk::DescriptorType,
+    element: u32,
+    info: StagedInfo,
+}
+
+/// A few lies and some interior mutability to work on liberating the device.
+struct DescriptorsMut {
+    // Indexed by binding.  Slot 1 is padding and slot 8 is not supported, see the `SLOT_*`
+    // constants.
+    slots: [Slots; UNKNOWN as usize],
+    // Writes waiting for the next flush.
+    staged: Vec<StagedWrite>,
+    flushes: u64,
+    // Latest value each timeline flushed so far is signaled with.
+    timelines: Vec<(vk::Semaphore, u64)>,
 }
 
 pub struct Descriptors {
@@ -113,35 +311,34 @@ unsafe impl Sync for Descriptors {}
 
 impl Descriptors {
     pub fn new(device: &ash::Device) -> Result<Self, VulkanError> {
-        // DEBT Max descriptor size calculation / management.
         let pool_sizes = [
             vk::DescriptorPoolSize {
                 ty: vk::DescriptorType::SAMPLER,
-                descriptor_count: 256,
+                descriptor_count: ARRAY_CAPACITY,
             },
             vk::DescriptorPoolSize {
                 ty: vk::DescriptorType::SAMPLED_IMAGE,
-                descriptor_count: 256,
+                descriptor_count: ARRAY_CAPACITY,
             },
             vk::DescriptorPoolSize {
                 ty: vk::DescriptorType::STORAGE_IMAGE,
-                descriptor_count: 256,
+                descriptor_count: ARRAY_CAPACITY,
             },
             vk::DescriptorPoolSize {
                 ty: vk::DescriptorType::STORAGE_BUFFER,
-                descriptor_count: 256,
+                descriptor_count: ARRAY_CAPACITY,
             },
             vk::DescriptorPoolSize {
                 ty: vk::DescriptorType::UNIFORM_BUFFER,
-                descriptor_count: 256,
+                descriptor_count: ARRAY_CAPACITY,
             },
             vk::DescriptorPoolSize {
                 ty: vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
-                descriptor_count: 256,
+                descriptor_count: ARRAY_CAPACITY,
             },
             vk::DescriptorPoolSize {
                 ty: vk::DescriptorType::STORAGE_TEXEL_BUFFER,
-                descriptor_count: 256,
+                descriptor_count: ARRAY_CAPACITY,
             },
             // vk::DescriptorPoolSize {
             //     ty: vk::DescriptorType::ACCELERATION_STRUCTURE_KHR,
@@ -162,37 +359,37 @@ impl Descriptors {
             vk::DescriptorSetLayoutBinding::default()
                 .binding(SLOT_SAMPLERS)
                 .descriptor_type(vk::DescriptorType::SAMPLER)
-                .descriptor_count(256)
+                .descriptor_count(ARRAY_CAPACITY)
                 .stage_flags(vk::ShaderStageFlags::ALL),
             vk::DescriptorSetLayoutBinding::default()
                 .binding(SLOT_SAMPLED_IMAGES)
                 .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
-                .descriptor_count(256)
+                .descriptor_count(ARRAY_CAPACITY)
                 .stage_flags(vk::ShaderStageFlags::ALL),
             vk::DescriptorSetLayoutBinding::default()
                 .binding(SLOT_STORAGE_IMAGES)
                 .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
-                .descriptor_count(256)
+                .descriptor_count(ARRAY_CAPACITY)
                 .stage_flags(vk::ShaderStageFlags::ALL),
             vk::DescriptorSetLayoutBinding::default()
                 .binding(SLOT_UNIFORM_BUFFERS)
                 .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
-                .descriptor_count(256)
+                .descriptor_count(ARRAY_CAPACITY)
                 .stage_flags(vk::ShaderStageFlags::ALL),
             vk::DescriptorSetLayoutBinding::default()
                 .binding(SLOT_STORAGE_BUFFERS)
                 .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
-                .descriptor_count(256)
+                .descriptor_count(ARRAY_CAPACITY)
                 .stage_flags(vk::ShaderStageFlags::ALL),
             vk::DescriptorSetLayoutBinding::default()
                 .binding(SLOT_UNIFORM_TEXEL_BUFFERS)
                 .descriptor_type(vk::DescriptorType::UNIFORM_TEXEL_BUFFER)
-                .descriptor_count(256)
+                .descriptor_count(ARRAY_CAPACITY)
                 .stage_flags(vk::ShaderStageFlags::ALL),
             vk::DescriptorSetLayoutBinding::default()
                 .binding(SLOT_STORAGE_TEXEL_BUFFERS)
                 .descriptor_type(vk::DescriptorType::STORAGE_TEXEL_BUFFER)
-                .descriptor_count(256)
+                .descriptor_count(ARRAY_CAPACITY)
                 .stage_flags(vk::ShaderStageFlags::ALL),
             // vk::DescriptorSetLayoutBinding::default()
             //     .binding(SLOT_ACCEL_STRUCTURES)
@@ -201,7 +398,28 @@ impl Descriptors {
             //     .stage_flags(vk::ShaderStageFlags::ALL),
         ];
 
-        let layout_info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&bindings);
+        // Every binding may be partially bound because the arrays are sparse.  Bindings with an
+        // update-after-bind feature enabled on the device may also be written while bound and while
+        // unused slots are read by pending work.  See `flush`.
+        let after_bind = vk::DescriptorBindingFlags::PARTIALLY_BOUND
+            | vk::DescriptorBindingFlags::UPDATE_AFTER_BIND
+            | vk::DescriptorBindingFlags::UPDATE_UNUSED_WHILE_PENDING;
+        let binding_flags: Vec<vk::DescriptorBindingFlags> = bindings
+            .iter()
+            .map(|b| match b.descriptor_type {
+                vk::DescriptorType::SAMPLER
+                | vk::DescriptorType::SAMPLED_IMAGE
+                | vk::DescriptorType::STORAGE_IMAGE
+                | vk::DescriptorType::STORAGE_BUFFER => after_bind,
+                _ => vk::DescriptorBindingFlags::PARTIALLY_BOUND,
+            })
+            .collect();
+        let mut flags_info =
+            vk::DescriptorSetLayoutBindingFlagsCreateInfo::default().binding_flags(&binding_flags);
+        let layout_info = vk::DescriptorSetLayoutCreateInfo::default()
+            .bindings(&bindings)
+            .flags(vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL)
+            .push_next(&mut flags_info);
         let layout = unsafe { device.create_descriptor_set_layout(&layout_info, None)? };
         let layouts = [layout];
         let alloc_info = vk::DescriptorSetAllocateInfo::default()
@@ -242,22 +460,13 @@ impl Descriptors {
             layout,
 
             inner: Mutex::new(DescriptorsMut {
-                next_sampler: SamplerIdx::new(samplers::N_DEFAULTS as u32),
-                next_sampled_image: SampledImageIdx::new(0),
-                next_storage_image: StorageImageIdx::new(0),
-                next_ubo: UboIdx::new(0),
-                next_ssbo: SsboIdx::new(0),
-                next_utxb: UniformTexelBufferIdx::new(0),
-                next_stxb: StorageTexelBufferIdx::new(0),
-                // next_accel: AccelStructIdx::new(0),
-                freelist_samplers: VecDeque::with_capacity(256),
-                freelist_sampled_images: VecDeque::with_capacity(256),
-                freelist_storage_images: VecDeque::with_capacity(256),
-                freelist_ubos: VecDeque::with_capacity(256),
-                freelist_ssbos: VecDeque::with_capacity(256),
-                freelist_utxbs: VecDeque::with_capacity(256),
-                freelist_stxbs: VecDeque::with_capacity(256),
-                // freelist_accels: VecDeque::with_capacity(256),
+                slots: std::array::from_fn(|binding| match binding as u32 {
+                    SLOT_SAMPLERS => Slots::starting_at(samplers::N_DEFAULTS as u32),
+                    _ => Slots::default(),
+                }),
+                staged: Vec::new(),
+                flushes: 0,
+                timelines: Vec::new(),
             }),
             default_samplers,
         })
@@ -284,104 +493,167 @@ impl Descriptors {
         }
     }
 
-    /// `layout` must be the layout that is intended for use, not the image's current layout.  If
-    /// you need multiple layouts, you need multiple descriptors.  The returned index may be used in
-    /// shaders and will later type-check against DescriptorHandles during introspection.
-    pub fn bind_sampled_image(
+    /// Stage a write into the next free slot of `I`'s array.
+    fn stage<I: Bindless>(
         &self,
-        device: &ash::Device,
-        view: vk::ImageView,
-        layout: vk::ImageLayout,
-    ) -> SampledImageIdx {
-        let descriptor_info = vk::DescriptorImageInfo::default()
-            .image_layout(layout)
-            .image_view(view);
-
-        let index = {
-            let mut inner = self.inner.lock().unwrap();
-            inner.freelist_sampled_images.pop_back().unwrap_or_else(|| {
-                let next = inner.next_sampled_image;
-                inner.next_sampled_image = SampledImageIdx::new(next.raw() + 1);
-                next
-            })
+        ty: vk::DescriptorType,
+        info: StagedInfo,
+    ) -> Result<Handle<I>, VulkanError> {
+        let mut guard = self.inner.lock()?;
+        let inner = &mut *guard;
+        let exhausted = VulkanError::DescriptorsExhausted {
+            binding: I::BINDING,
+            capacity: ARRAY_CAPACITY,
         };
+        let (index, generation) = inner.slots[I::BINDING as usize].take().ok_or(exhausted)?;
+        inner.staged.push(StagedWrite {
+            binding: I::BINDING,
+            ty,
+            element: index,
+            info,
+        });
+        Ok(Handle {
+            index: I::from_raw(index),
+            generation,
+        })
+    }
 
-        let write = vk::WriteDescriptorSet::default()
-            .dst_set(self.set)
-            .dst_binding(SLOT_SAMPLED_IMAGES)
-            .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
-            .dst_array_element(index.raw())
-            .image_info(slice::from_ref(&descriptor_info));
+    /// Stage a sampled image into the next free slot.  The index is valid in work submitted after
+    /// the next [`flush`](Self::flush).  `layout` is the layout the image will be in when sampled.
+    pub fn register_sampled_image(
+        &self,
+        view: vk::ImageView,
+        layout: vk::ImageLayout,
+    ) -> Result<Handle<SampledImageIdx>, VulkanError> {
+        let info = vk::DescriptorImageInfo::default()
+            .image_view(view)
+            .image_layout(layout);
+        self.stage(vk::DescriptorType::SAMPLED_IMAGE, StagedInfo::Image(info))
+    }
 
-        unsafe {
-            device.update_descriptor_sets(slice::from_ref(&write), &[]);
-        }
-        index
+    /// Stage a storage image into the next free slot.  Storage images are accessed in
+    /// [`vk::ImageLayout::GENERAL`].  The index is valid in work submitted after the next
+    /// [`flush`](Self::flush).
+    pub fn register_storage_image(
+        &self,
+        view: vk::ImageView,
+    ) -> Result<Handle<StorageImageIdx>, VulkanError> {
+        let info = vk::DescriptorImageInfo::default()
+            .image_view(view)
+            .image_layout(vk::ImageLayout::GENERAL);
+        self.stage(vk::DescriptorType::STORAGE_IMAGE, StagedInfo::Image(info))
     }
 
-    pub fn bind_ssbo(
+    /// Stage `size` bytes of a storage buffer from `offset` into the next free slot.  The index is
+    /// valid in work submitted after the next [`flush`](Self::flush).
+    pub fn register_storage_buffer(
         &self,
-        device: &ash::Device,
         buffer: vk::Buffer,
         offset: vk::DeviceSize,
         size: vk::DeviceSize,
-    ) -> SsboIdx {
-        let descriptor_info = vk::DescriptorBufferInfo::default()
+    ) -> Result<Handle<SsboIdx>, VulkanError> {
+        let info = vk::DescriptorBufferInfo::default()
             .buffer(buffer)
             .offset(offset)
             .range(size);
+        self.stage(vk::DescriptorType::STORAGE_BUFFER, StagedInfo::Buffer(info))
+    }
 
-        let index = {
-            let mut inner = self.inner.lock().unwrap();
-            inner.freelist_ssbos.pop_back().unwrap_or_else(|| {
-                let next = inner.next_ssbo;
-                inner.next_ssbo = SsboIdx::new(next.raw() + 1);
-                next
-            })
-        };
+    /// The handle's index, or `None` if it was released.
+    pub fn resolve<I: Bindless>(&self, handle: Handle<I>) -> Option<I> {
+        let inner = self.inner.lock().unwrap();
+        inner.slots[I::BINDING as usize]
+            .is_live(handle.index.to_raw(), handle.generation)
+            .then_some(handle.index)
+    }
 
-        let write = vk::WriteDescriptorSet::default()
-            .dst_set(self.set)
-            .dst_binding(SLOT_STORAGE_BUFFERS)
-            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
-            .dst_array_element(index.raw())
-            .buffer_info(slice::from_ref(&descriptor_info));
+    /// Give up a registered descriptor.  The slot is reused once in-flight work has retired, so the
+    /// resource may be destroyed as soon as that work is done with it.  Releasing a stale handle
+    /// does nothing and is a bug, caught in debug builds.
+    pub fn release<I: Bindless>(&self, handle: Handle<I>) {
+        let mut inner = self.inner.lock().unwrap();
+        let released =
+            inner.slots[I::BINDING as usize].release(handle.index.to_raw(), handle.generation);
+        debug_assert!(released, "release: stale handle {handle:?}");
+    }
 
-        unsafe {
-            device.update_descriptor_sets(slice::from_ref(&write), &[]);
-        }
-        index
+    /// Apply all staged writes in one update and age released indexes by one flush.  Call after
+    /// recording and before submitting work that does not signal a timeline.  Released indexes
+    /// wait [`RETIRE_FLUSHES`] flushes.
+    pub fn flush(&self, device: &ash::Device) {
+        self.flush_with(device, None);
     }
 
-    /// Return the index for the descriptor slot to the free list.  You should not use this index
-    /// again because it may belong to a new resource.
-    pub fn unbind_image(&self, index: SampledImageIdx) {
-        let mut inner = self.inner.lock().unwrap();
-        debug_assert!(
-            index.raw() < inner.next_sampled_image.raw(),
-            "unbind_ssbo: index {:?} was never bound",
-            index
-        );
-        inner.freelist_sampled_images.push_back(index);
+    /// [`flush`](Self::flush) before submitting work that signals `done`.  Indexes released since
+    /// the last flush are reused once `done` and every other timeline flushed so far reach the
+    /// values they were flushed with, so the work of any submitter that may still use them is over.
+    pub fn flush_signaling(&self, device: &ash::Device, done: &WaitValue) {
+        self.flush_with(device, Some((done.semaphore(), done.value())));
     }
 
-    /// Return the index for the descriptor slot to the free list.  You should not use this index
-    /// again because it may belong to a new resource.
-    pub fn unbind_ssbo(&self, index: SsboIdx) {
+    /// Stop reading `semaphore` before it is destroyed.  Its work must be drained, so the indexes
+    /// waiting on it only wait on the other timelines.
+    pub fn forget_timeline(&self, semaphore: vk::Semaphore) {
         let mut inner = self.inner.lock().unwrap();
-        debug_assert!(
-            index.raw() < inner.next_ssbo.raw(),
-            "unbind_ssbo: index {:?} was never bound",
-            index
-        );
-        inner.freelist_ssbos.push_back(index);
+        inner.timelines.retain(|&(s, _)| s != semaphore);
+    }
+
+    fn flush_with(&self, device: &ash::Device, done: Option<(vk::Semaphore, u64)>) {
+        let mut guard = self.inner.lock().unwrap();
+        let inner = &mut *guard;
+        if !inner.staged.is_empty() {
+            let writes: Vec<vk::WriteDescriptorSet> = inner
+                .staged
+                .iter()
+                .map(|w| {
+                    let write = vk::WriteDescriptorSet::default()
+                        .dst_set(self.set)
+                        .dst_binding(w.binding)
+                        .descriptor_type(w.ty)
+                        .dst_array_element(w.element);
+                    match &w.info {
+                        StagedInfo::Image(info) => write.image_info(slice::from_ref(info)),
+                        StagedInfo::Buffer(info) => write.buffer_info(slice::from_ref(info)),
+                    }
+                })
+                .collect();
+            unsafe { device.update_descriptor_sets(&writes, &[]) };
+            inner.staged.clear();
+        }
+
+        if let Some((semaphore, value)) = done {
+            match inner.timelines.iter_mut().find(|(s, _)| *s == semaphore) {
+                Some(latest) => latest.1 = value,
+                None => inner.timelines.push((semaphore, value)),
+            }
+        }
+        let retirement = Retirement {
+            flush: done.is_none().then_some(inner.flushes),
+            timelines: inner.timelines.clone(),
+        };
+        inner.flushes += 1;
+        let now = inner.flushes;
+        // Forgotten timelines were drained.  A failed read keeps waiting.
+        let timelines = &inner.timelines;
+        let counter = |semaphore: vk::Semaphore| {
+            if !timelines.iter().any(|&(s, _)| s == semaphore) {
+                return None;
+            }
+            Some(unsafe { device.get_semaphore_counter_value(semaphore) }.unwrap_or(0))
+        };
+        for slots in inner.slots.iter_mut() {
+            slots.submit(&retirement);
+            slots.retire(now, &counter);
+        }
     }
 }
 
-pub(crate) mod samplers {
+/// Samplers created with the descriptor table.  Their indexes are fixed.
+pub mod samplers {
     use super::*;
 
     // NOTE didn't want to explicitly double wrap, but without into, this is the way?
+    /// Nearest, clamp-to-edge.  Pixel exact.
     pub const NEAREST_CLAMP: SamplerIdx = SamplerIdx(UInt(0));
     /// Bi-linear, clamp-to-edge.  Smooth, no tiling.
     pub const LINEAR_CLAMP: SamplerIdx = SamplerIdx(UInt(1));
@@ -454,3 +726,95 @@ pub(crate) mod samplers {
         ]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use ash::vk::Handle as _;
+
+    use super::*;
+
+    /// Timelines that never signal.
+    fn pending(_: vk::Semaphore) -> Option<u64> {
+        Some(0)
+    }
+
+    #[test]
+    fn test_slots() {
+        let mut slots = Slots::starting_at(2);
+        let (first, generation) = slots.take().unwrap();
+        assert_eq!((first, generation), (2, 0));
+        assert!(slots.release(first, generation));
+        assert!(!slots.is_live(first, generation));
+        // A stale release is refused rather than freeing the slot twice.
+        assert!(!slots.release(first, generation));
+        slots.submit(&Retirement {
+            flush: Some(0),
+            timelines: Vec::new(),
+        });
+        assert_eq!(slots.take().unwrap().0, 3);
+
+        slots.retire(RETIRE_FLUSHES - 1, &pending);
+        assert_eq!(slots.take().unwrap().0, 4);
+        slots.retire(RETIRE_FLUSHES, &pending);
+        let (reused, generation) = slots.take().unwrap();
+        assert_eq!((reused, generation), (first, 1));
+        assert!(slots.is_live(reused, generation));
+        assert!(slots.free.is_empty());
+    }
+
+    #[test]
+    fn test_slots_retire_by_timeline() {
+        let [a, b] = [1, 2].map(vk::Semaphore::from_raw);
+        let mut slots = Slots::default();
+        let (index, generation) = slots.take().unwrap();
+        assert!(slots.release(index, generation));
+        slots.submit(&Retirement {
+            flush: None,
+            timelines: vec![(a, 5), (b, 2)],
+        });
+
+        // However many flushes pass, the slot waits on both timelines.
+        let counter = |values: [u64; 2]| {
+            move |s: vk::Semaphore| Some(if s == a { values[0] } else { values[1] })
+        };
+        slots.retire(100, &counter([4, 2]));
+        assert!(slots.free.is_empty());
+        slots.retire(100, &counter([5, 1]));
+        assert!(slots.free.is_empty());
+        slots.retire(0, &counter([5, 2]));
+        assert_eq!(slots.free, [index]);
+
+        // A forgotten timeline no longer holds a slot back.
+        let (index, generation) = slots.take().unwrap();
+        assert!(slots.release(index, generation));
+        slots.submit(&Retirement {
+            flush: None,
+            timelines: vec![(a, 9)],
+        });
+        slots.retire(0, &|_| None);
+        assert_eq!(slots.free, [index]);
+
+        // Work without a timeline waits out its flushes and the timelines in flight with it.
+        let (index, generation) = slots.take().unwrap();
+        assert!(slots.release(index, generation));
+        slots.submit(&Retirement {
+            flush: Some(0),
+            timelines: vec![(a, 9)],
+        });
+        slots.retire(RETIRE_FLUSHES, &counter([8, 0]));
+        assert!(slots.free.is_empty());
+        slots.retire(RETIRE_FLUSHES - 1, &counter([9, 0]));
+        assert!(slots.free.is_empty());
+        slots.retire(RETIRE_FLUSHES, &counter([9, 0]));
+        assert_eq!(slots.free, [index]);
+    }
+
+    #[test]
+    fn test_slots_capacity() {
+        let mut slots = Slots::default();
+        for _ in 0..ARRAY_CAPACITY {
+            assert!(slots.take().is_some());
+        }
+        assert_eq!(slots.take(), None);
+    }
+}
@@ -13,7 +13,10 @@ use mutate_assets as assets;
 
 use crate::internal::*;
 
+use super::capabilities::{self, Capabilities, Tier};
 use super::descriptors;
+use super::memory::{self, MemoryStats};
+use crate::pipeline::cache::{self, PipelineCache};
 use super::queue;
 
 pub struct Device {
@@ -24,6 +27,14 @@ pub struct Device {
     pub non_coherent_atom_size: vk::DeviceSize,
     /// Descriptor table and runtime management of its entries.
     pub descriptors: descriptors::Descriptors,
+    /// Sub-allocates device memory for buffers and images.
+    pub allocator: memory::Allocator,
+    /// Loaded at creation and saved at destruction.
+    pipeline_cache: PipelineCache,
+    /// Feature tier the device was created with.
+    capabilities: Capabilities,
+    /// Names objects for debug tools when the instance enabled `VK_EXT_debug_utils`.
+    debug_utils: Option<ash::ext::debug_utils::Device>,
 
     // XXX move to some other higher context?  PSOs are device-dependent, and we're using this to
     // get PSOs, so probably the other context is runtime support for shader loading.
@@ -32,13 +43,38 @@ pub struct Device {
 }
 
 impl Device {
-    pub(crate) fn new(instance: &Instance, supported_device: SupportedDevice) -> Self {
+    pub(crate) fn new(
+        instance: &Instance,
+        supported_device: SupportedDevice,
+    ) -> Result<Self, VulkanError> {
+        // Supported devices were checked when listed, but a device can be listed under one instance
+        // and created under another, or be lost and come back with a different driver.
+        let physical_device = supported_device.device();
+        let tier = supported_device.tier;
+        let missing = instance.missing_features(physical_device);
+        if let Some(feature) = missing
+            .iter()
+            .find(|m| tier == Tier::Full || !capabilities::FULL_FEATURES.contains(m))
+        {
+            return Err(VulkanError::MissingFeature(feature.to_string()));
+        }
+        // Only Full enables the 8 and 16-bit features.  Enabling a missing feature fails creation.
+        let full = tier == Tier::Full;
+        if let Some(extension) = instance
+            .missing_extensions(physical_device, &supported_device.extensions)
+            .first()
+        {
+            return Err(VulkanError::MissingExtension(
+                extension.to_string_lossy().into_owned(),
+            ));
+        }
+
+        let has_debug_utils = instance.has_debug_utils();
         let Instance {
             entry,
             raw: instance,
             ..
         } = &instance;
-        let physical_device = supported_device.device();
         let extensions = &supported_device.extensions;
 
         let mut pwid_features = vk::PhysicalDevicePresentIdFeaturesKHR::default().present_id(true);
@@ -51,11 +87,11 @@ impl Device {
 
         let mut features_1_1 = vk::PhysicalDeviceVulkan11Features::default()
             .shader_draw_parameters(true)
-            .storage_buffer16_bit_access(true)
-            .storage_push_constant16(true)
+            .storage_buffer16_bit_access(full)
+            .storage_push_constant16(full)
             // XXX remove this
             // .storage_input_output16(true)
-            .uniform_and_storage_buffer16_bit_access(true);
+            .uniform_and_storage_buffer16_bit_access(full);
 
         // MAYBE wonder if these attributes could be de-duped with a macro 🤔
         let mut features_1_2 = vk::PhysicalDeviceVulkan12Features::default()
@@ -65,21 +101,22 @@ impl Device {
             .descriptor_binding_sampled_image_update_after_bind(true)
             .descriptor_binding_storage_buffer_update_after_bind(true)
             .descriptor_binding_storage_image_update_after_bind(true)
+            .descriptor_binding_update_unused_while_pending(true)
             .descriptor_indexing(true)
             .draw_indirect_count(true)
             .host_query_reset(true)
             .runtime_descriptor_array(true)
             .scalar_block_layout(true)
-            .shader_float16(true)
-            .shader_int8(true)
+            .shader_float16(full)
+            .shader_int8(full)
             .shader_sampled_image_array_non_uniform_indexing(true)
             .shader_storage_buffer_array_non_uniform_indexing(true)
             .shader_storage_image_array_non_uniform_indexing(true)
             .shader_uniform_buffer_array_non_uniform_indexing(true)
-            .storage_buffer8_bit_access(true)
-            .storage_push_constant8(true)
+            .storage_buffer8_bit_access(full)
+            .storage_push_constant8(full)
             .timeline_semaphore(true)
-            .uniform_and_storage_buffer8_bit_access(true);
+            .uniform_and_storage_buffer8_bit_access(full);
 
         let mut features_1_3 = vk::PhysicalDeviceVulkan13Features::default()
             .compute_full_subgroups(true)
@@ -90,7 +127,7 @@ impl Device {
             .maintenance4(true);
 
         let mut features2 = vk::PhysicalDeviceFeatures2::default()
-            .features(vk::PhysicalDeviceFeatures::default().shader_int16(true))
+            .features(vk::PhysicalDeviceFeatures::default().shader_int16(full))
             .push_next(&mut features_1_3)
             .push_next(&mut features_1_2)
             .push_next(&mut features_1_1);
@@ -102,7 +139,7 @@ impl Device {
                 .push_next(&mut swapchain_maintenance1);
         }
 
-        let queue_plan = queue::QueuePlan::new(&instance, physical_device).unwrap();
+        let queue_plan = queue::QueuePlan::new(&instance, physical_device)?;
         let queue_cis = queue_plan.queue_cis(); // borrows queue_plan, no allocation
         let extensions: Vec<*const i8> = extensions.iter().map(|ext| ext.as_ptr()).collect();
         let mut device_info = vk::DeviceCreateInfo {
@@ -112,34 +149,72 @@ impl Device {
         .queue_create_infos(&queue_cis)
         .enabled_extension_names(&extensions);
 
-        let raw = unsafe {
-            instance
-                .create_device(physical_device, &device_info, None)
-                .unwrap()
-        };
+        let raw = unsafe { instance.create_device(physical_device, &device_info, None)? };
         let queues = queue::Queues::new(&raw, queue_plan);
-        let descriptors = descriptors::Descriptors::new(&raw).unwrap();
+        let debug_utils =
+            has_debug_utils.then(|| ash::ext::debug_utils::Device::new(instance, &raw));
+        let descriptors = match descriptors::Descriptors::new(&raw) {
+            Ok(descriptors) => descriptors,
+            Err(e) => {
+                unsafe { raw.destroy_device(None) };
+                return Err(e);
+            }
+        };
 
         let memory_props =
             unsafe { instance.get_physical_device_memory_properties(physical_device) };
-        let non_coherent_atom_size = unsafe {
-            instance
-                .get_physical_device_properties(physical_device)
-                .limits
-                .non_coherent_atom_size
-        };
-
-        Self {
+        let props = unsafe { instance.get_physical_device_properties(physical_device) };
+        let non_coherent_atom_size = props.limits.non_coherent_atom_size;
+        let pipeline_cache =
+            match PipelineCache::load(&raw, &props, cache::cache_dir().as_deref()) {
+                Ok(pipeline_cache) => pipeline_cache,
+                Err(e) => {
+                    unsafe {
+                        descriptors.destroy(&raw);
+                        raw.destroy_device(None);
+                    }
+                    return Err(e);
+                }
+            };
+
+        let device = Self {
             physical_device,
             raw,
             memory_props,
             non_coherent_atom_size,
             queues,
             descriptors,
+            allocator: memory::Allocator::new(memory_props, non_coherent_atom_size),
+            pipeline_cache,
+            capabilities: Capabilities {
+                tier,
+                color_samples: props.limits.framebuffer_color_sample_counts,
+            },
+            debug_utils,
 
             // XXX there is another context where this will likely belong better.
             assets: assets::AssetDirs::new(),
+        };
+        for (name, queue) in device.queues.labeled() {
+            device.set_name(queue, &name);
         }
+        Ok(device)
+    }
+
+    /// Name `handle` for validation messages and debug tools.  Does nothing without
+    /// `VK_EXT_debug_utils`, so call freely.
+    pub fn set_name<H: vk::Handle>(&self, handle: H, name: &str) {
+        let Some(debug_utils) = &self.debug_utils else {
+            return;
+        };
+        let Ok(name) = std::ffi::CString::new(name) else {
+            return;
+        };
+        let info = vk::DebugUtilsObjectNameInfoEXT::default()
+            .object_handle(handle)
+            .object_name(&name);
+        // Naming is best effort.  A failure here only costs a name in a message.
+        let _ = unsafe { debug_utils.set_debug_utils_object_name(&info) };
     }
 
     /// Device wait idle.  Waits until there is no in-flight work.  ⚠️ May not return if another
@@ -150,22 +225,15 @@ impl Device {
         Ok(())
     }
 
-    // XXX pass-through... uuuuuuuuugly though.  Not sure we want to do it like this at all....
-    // maybe hold on via another field.
-    pub fn bind_sampled_image(
-        &mut self,
-        view: vk::ImageView,
-        layout: vk::ImageLayout,
-    ) -> descriptors::SampledImageIdx {
-        let device = &self.raw;
-        let descriptors = &mut self.descriptors;
-        descriptors.bind_sampled_image(device, view, layout)
-    }
-
     // XXX in reality, this consumes the context, but ownership friction needs worked out.
     pub fn destroy(&self) {
+        if let Err(e) = self.save_pipeline_cache() {
+            log::warn!("pipeline cache not saved: {e}");
+        }
         unsafe {
+            self.pipeline_cache.destroy(&self.raw);
             self.descriptors.destroy(&self.raw);
+            self.allocator.destroy(&self.raw);
             self.raw.destroy_device(None);
         };
     }
@@ -202,6 +270,27 @@ impl Device {
         Ok(TimelineSemaphore::new(raw))
     }
 
+    /// Feature tier the device was created with.  Shaders above it load their Core variants.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Pass to pipeline creation so that the driver reuses what it compiled on earlier runs.
+    pub fn pipeline_cache(&self) -> vk::PipelineCache {
+        self.pipeline_cache.as_raw()
+    }
+
+    /// Save the pipeline cache now, such as once startup has created every pipeline, rather than
+    /// only when the device is destroyed.
+    pub fn save_pipeline_cache(&self) -> Result<(), VulkanError> {
+        self.pipeline_cache.save(&self.raw)
+    }
+
+    /// Device memory in use by the [`allocator`](Self::allocator).
+    pub fn memory_stats(&self) -> MemoryStats {
+        self.allocator.stats()
+    }
+
     pub fn non_coherent_atom_size(&self) -> vk::DeviceSize {
         self.non_coherent_atom_size
     }
@@ -0,0 +1,472 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Memory
+//!
+//! Drivers cap how many device memory objects may exist at once, often at 4096, and allocating one
+//! is slow.  Allocating per resource runs into the cap and fragments VRAM as graphs grow.  The
+//! [`Allocator`] instead allocates large blocks and hands out ranges of them:
+//!
+//! - Every memory type has its own blocks.  Buffers and images never share a block, so
+//!   `bufferImageGranularity` never has to be considered.
+//! - Ranges come from a first-fit free list per block.  Freed ranges merge with their neighbors,
+//!   and a block that becomes empty is returned to the driver.
+//! - Requests larger than half a block get a dedicated allocation of their own.
+//! - Host-visible blocks are mapped once, when they are allocated.  Their ranges are aligned and
+//!   sized to `nonCoherentAtomSize`, so flushing a whole range is always valid.
+//!
+//! Every block is allocated with `DEVICE_ADDRESS`, which buffer device addresses need.
+//! [`Device::memory_stats`](crate::device::Device::memory_stats) reports what is in use.
+
+// MAYBE keep one empty block per pool if resize storms show blocks being freed and reallocated.
+// NEXT route transient pools and swapchain-sized images through here once they want stats too.
+
+use std::ptr::NonNull;
+use std::sync::{Mutex, PoisonError};
+
+use crate::internal::*;
+use crate::util;
+
+/// Size of a block on heaps large enough to hold several.
+pub const BLOCK_SIZE: vk::DeviceSize = 64 << 20;
+
+/// What a range will be bound to.  Each kind draws from its own blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Buffer,
+    Image,
+}
+
+/// A range of device memory.  Return it with [`Allocator::free`].
+#[derive(Debug)]
+pub struct Allocation {
+    memory: vk::DeviceMemory,
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+    memory_type_index: u32,
+    /// Host address of `offset` when the memory is host visible.
+    mapped: Option<NonNull<u8>>,
+    /// Where the range came from, or `None` for a dedicated allocation.
+    block: Option<BlockId>,
+}
+
+// SAFETY the pointer is only an address into a mapping that the allocator keeps alive until the
+// allocation is freed.  Access through it is the owner's business, as with `MappedAllocation`.
+unsafe impl Send for Allocation {}
+unsafe impl Sync for Allocation {}
+
+impl Allocation {
+    /// Memory object to bind, shared with other allocations unless dedicated.
+    pub fn memory(&self) -> vk::DeviceMemory {
+        self.memory
+    }
+
+    /// Offset to bind at and to flush from.
+    pub fn offset(&self) -> vk::DeviceSize {
+        self.offset
+    }
+
+    /// Size of the range, at least what was required.
+    pub fn size(&self) -> vk::DeviceSize {
+        self.size
+    }
+
+    pub fn memory_type_index(&self) -> u32 {
+        self.memory_type_index
+    }
+
+    /// Host address of the range, if the memory is host visible.
+    pub fn mapped(&self) -> Option<NonNull<u8>> {
+        self.mapped
+    }
+
+    /// Whether the range owns its memory object.  Only dedicated memory should be named.
+    pub fn is_dedicated(&self) -> bool {
+        self.block.is_none()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BlockId {
+    pool: usize,
+    index: usize,
+}
+
+/// Memory in use, for tuning and for spotting leaks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Live device memory objects, blocks and dedicated allocations.  This is what drivers cap.
+    pub memory_objects: usize,
+    /// Bytes of device memory allocated from the driver.
+    pub reserved: vk::DeviceSize,
+    /// Live allocations handed out.
+    pub allocations: usize,
+    /// Bytes handed out.  The rest of `reserved` is free space in blocks.
+    pub used: vk::DeviceSize,
+}
+
+/// First-fit free list over one block, separate from the Vulkan objects so it can be reasoned
+/// about alone.
+#[derive(Clone, Debug)]
+struct FreeList {
+    size: vk::DeviceSize,
+    /// Free ranges as offset and size, sorted by offset.  Neighbors are always merged.
+    ranges: Vec<(vk::DeviceSize, vk::DeviceSize)>,
+}
+
+impl FreeList {
+    fn new(size: vk::DeviceSize) -> Self {
+        Self {
+            size,
+            ranges: vec![(0, size)],
+        }
+    }
+
+    /// Offset of a new range, or `None` when no free range fits.
+    fn alloc(&mut self, size: vk::DeviceSize, align: vk::DeviceSize) -> Option<vk::DeviceSize> {
+        let align = align.max(1);
+        let (i, start, offset, end) =
+            self.ranges
+                .iter()
+                .enumerate()
+                .find_map(|(i, &(start, len))| {
+                    let offset = start.div_ceil(align) * align;
+                    let end = offset.checked_add(size)?;
+                    (end <= start + len).then_some((i, start, offset, end))
+                })?;
+        let range_end = self.ranges[i].0 + self.ranges[i].1;
+        // Padding before the range stays free, as does the tail after it.
+        let before = (start, offset - start);
+        let after = (end, range_end - end);
+        match (before.1 > 0, after.1 > 0) {
+            (false, false) => {
+                self.ranges.remove(i);
+            }
+            (true, false) => self.ranges[i] = before,
+            (false, true) => self.ranges[i] = after,
+            (true, true) => {
+                self.ranges[i] = before;
+                self.ranges.insert(i + 1, after);
+            }
+        }
+        Some(offset)
+    }
+
+    fn free(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize) {
+        let mut i = self.ranges.partition_point(|&(o, _)| o < offset);
+        debug_assert!(
+            self.ranges.get(i).is_none_or(|&(o, _)| offset + size <= o),
+            "freed range overlaps free space"
+        );
+        self.ranges.insert(i, (offset, size));
+        if i > 0 && self.ranges[i - 1].0 + self.ranges[i - 1].1 == offset {
+            self.ranges[i - 1].1 += size;
+            self.ranges.remove(i);
+            i -= 1;
+        }
+        if let Some(&(next, len)) = self.ranges.get(i + 1) {
+            if self.ranges[i].0 + self.ranges[i].1 == next {
+                self.ranges[i].1 += len;
+                self.ranges.remove(i + 1);
+            }
+        }
+    }
+
+    fn used(&self) -> vk::DeviceSize {
+        self.size
+            - self
+                .ranges
+                .iter()
+                .map(|&(_, len)| len)
+                .sum::<vk::DeviceSize>()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ranges == [(0, self.size)]
+    }
+}
+
+struct Block {
+    memory: vk::DeviceMemory,
+    mapped: Option<NonNull<u8>>,
+    free: FreeList,
+    allocations: usize,
+}
+
+#[derive(Default)]
+struct Pools {
+    /// Blocks per memory type and kind, indexed by [`Allocator::pool`].  Freed blocks leave `None`
+    /// so that the indices of live allocations stay valid.
+    pools: Vec<Vec<Option<Block>>>,
+    dedicated: usize,
+    dedicated_bytes: vk::DeviceSize,
+}
+
+// SAFETY block pointers are only addresses into mappings owned by the allocator, and every access
+// to the bookkeeping goes through the mutex.
+unsafe impl Send for Pools {}
+
+/// Sub-allocates device memory.  See the [module docs](self).
+pub struct Allocator {
+    memory_props: vk::PhysicalDeviceMemoryProperties,
+    non_coherent_atom_size: vk::DeviceSize,
+    inner: Mutex<Pools>,
+}
+
+impl Allocator {
+    pub(crate) fn new(
+        memory_props: vk::PhysicalDeviceMemoryProperties,
+        non_coherent_atom_size: vk::DeviceSize,
+    ) -> Self {
+        let mut pools = Pools::default();
+        pools
+            .pools
+            .resize_with(memory_props.memory_type_count as usize * 2, Vec::new);
+        Self {
+            memory_props,
+            non_coherent_atom_size: non_coherent_atom_size.max(1),
+            inner: Mutex::new(pools),
+        }
+    }
+
+    fn pool(memory_type_index: u32, kind: Kind) -> usize {
+        memory_type_index as usize * 2 + kind as usize
+    }
+
+    /// Blocks take an eighth of small heaps, such as a 256 MiB BAR window, rather than hog them.
+    fn block_size(&self, memory_type_index: u32) -> vk::DeviceSize {
+        let heap = self.memory_props.memory_types[memory_type_index as usize].heap_index;
+        let heap_size = self.memory_props.memory_heaps[heap as usize].size;
+        BLOCK_SIZE
+            .min((heap_size / 8) & !((1 << 20) - 1))
+            .max(1 << 20)
+    }
+
+    /// Allocate memory meeting `requirements` from the first memory type with `properties`.
+    pub fn alloc(
+        &self,
+        device: &ash::Device,
+        requirements: &vk::MemoryRequirements,
+        properties: vk::MemoryPropertyFlags,
+        kind: Kind,
+    ) -> Result<Allocation, VulkanError> {
+        let memory_type_index =
+            util::find_memory_type_index(requirements, &self.memory_props, properties)
+                .ok_or(VulkanError::OutOfDeviceMemory)?;
+        let host_visible = self.memory_props.memory_types[memory_type_index as usize]
+            .property_flags
+            .contains(vk::MemoryPropertyFlags::HOST_VISIBLE);
+        let (size, align) = if host_visible {
+            let atom = self.non_coherent_atom_size;
+            (
+                requirements.size.div_ceil(atom) * atom,
+                requirements.alignment.max(atom),
+            )
+        } else {
+            (requirements.size, requirements.alignment)
+        };
+
+        let block_size = self.block_size(memory_type_index);
+        let mut inner = self.inner.lock().map_err(|_| VulkanError::Poisoned)?;
+        if size > block_size / 2 {
+            let (memory, mapped) = allocate(device, memory_type_index, size, host_visible)?;
+            inner.dedicated += 1;
+            inner.dedicated_bytes += size;
+            return Ok(Allocation {
+                memory,
+                offset: 0,
+                size,
+                memory_type_index,
+                mapped,
+                block: None,
+            });
+        }
+
+        let pool = Self::pool(memory_type_index, kind);
+        let blocks = &mut inner.pools[pool];
+        let found = blocks.iter_mut().enumerate().find_map(|(index, block)| {
+            let block = block.as_mut()?;
+            let offset = block.free.alloc(size, align)?;
+            Some((index, offset))
+        });
+        let (index, offset) = match found {
+            Some(found) => found,
+            None => {
+                let (memory, mapped) =
+                    allocate(device, memory_type_index, block_size, host_visible)?;
+                let mut free = FreeList::new(block_size);
+                let offset = free.alloc(size, align).expect("block fits half its size");
+                let block = Block {
+                    memory,
+                    mapped,
+                    free,
+                    allocations: 0,
+                };
+                let index = match blocks.iter().position(Option::is_none) {
+                    Some(index) => {
+                        blocks[index] = Some(block);
+                        index
+                    }
+                    None => {
+                        blocks.push(Some(block));
+                        blocks.len() - 1
+                    }
+                };
+                (index, offset)
+            }
+        };
+        let block = blocks[index].as_mut().expect("block was just found");
+        block.allocations += 1;
+        Ok(Allocation {
+            memory: block.memory,
+            offset,
+            size,
+            memory_type_index,
+            // SAFETY the offset lies within the mapped block.
+            mapped: block
+                .mapped
+                .map(|base| unsafe { base.add(offset as usize) }),
+            block: Some(BlockId { pool, index }),
+        })
+    }
+
+    /// Return `allocation`, exactly once.  Resources bound to it must already be destroyed and no
+    /// longer in use by the GPU.
+    pub fn free(&self, device: &ash::Device, allocation: &Allocation) {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(id) = allocation.block else {
+            inner.dedicated -= 1;
+            inner.dedicated_bytes -= allocation.size;
+            unsafe { device.free_memory(allocation.memory, None) };
+            return;
+        };
+        let slot = &mut inner.pools[id.pool][id.index];
+        let block = slot.as_mut().expect("allocation from a freed block");
+        block.free.free(allocation.offset, allocation.size);
+        block.allocations -= 1;
+        if block.allocations == 0 {
+            debug_assert!(block.free.is_empty());
+            let block = slot.take().expect("block is live");
+            unsafe { device.free_memory(block.memory, None) };
+        }
+    }
+
+    pub fn stats(&self) -> MemoryStats {
+        let inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut stats = MemoryStats {
+            memory_objects: inner.dedicated,
+            reserved: inner.dedicated_bytes,
+            allocations: inner.dedicated,
+            used: inner.dedicated_bytes,
+        };
+        for block in inner.pools.iter().flatten().flatten() {
+            stats.memory_objects += 1;
+            stats.reserved += block.free.size;
+            stats.allocations += block.allocations;
+            stats.used += block.free.used();
+        }
+        stats
+    }
+
+    /// Free every block.  Allocations still live are leaked into the device's destruction and
+    /// reported as a warning.
+    pub(crate) fn destroy(&self, device: &ash::Device) {
+        let stats = self.stats();
+        if stats.allocations > 0 {
+            log::warn!(
+                "destroying allocator with {} live allocations, {} bytes",
+                stats.allocations,
+                stats.used
+            );
+        }
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        for block in inner.pools.iter_mut().flatten().filter_map(Option::take) {
+            unsafe { device.free_memory(block.memory, None) };
+        }
+    }
+}
+
+/// Allocate one memory object, mapped when host visible.
+fn allocate(
+    device: &ash::Device,
+    memory_type_index: u32,
+    size: vk::DeviceSize,
+    host_visible: bool,
+) -> Result<(vk::DeviceMemory, Option<NonNull<u8>>), VulkanError> {
+    let mut flags =
+        vk::MemoryAllocateFlagsInfo::default().flags(vk::MemoryAllocateFlags::DEVICE_ADDRESS);
+    let alloc_info = vk::MemoryAllocateInfo::default()
+        .allocation_size(size)
+        .memory_type_index(memory_type_index)
+        .push_next(&mut flags);
+    let memory = unsafe { device.allocate_memory(&alloc_info, None)? };
+    if !host_visible {
+        return Ok((memory, None));
+    }
+    match unsafe { device.map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()) } {
+        Ok(ptr) => Ok((memory, NonNull::new(ptr as *mut u8))),
+        Err(e) => {
+            unsafe { device.free_memory(memory, None) };
+            Err(e.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_free_list() {
+        let mut list = FreeList::new(1024);
+        assert_eq!(list.alloc(100, 256), Some(0));
+        assert_eq!(list.alloc(100, 256), Some(256));
+        // Padding after the first range is still free for unaligned requests.
+        assert_eq!(list.alloc(100, 1), Some(100));
+        assert_eq!(list.used(), 300);
+        assert_eq!(list.alloc(1024, 1), None);
+
+        list.free(100, 100);
+        list.free(0, 100);
+        assert_eq!(list.ranges[0], (0, 256));
+        list.free(256, 100);
+        assert!(list.is_empty());
+        assert_eq!(list.alloc(1024, 1), Some(0));
+        assert_eq!(list.used(), 1024);
+    }
+
+    #[test]
+    fn test_allocator() {
+        with_context!(|device| {
+            let before = device.memory_stats();
+            let requirements = vk::MemoryRequirements {
+                size: 1000,
+                alignment: 256,
+                memory_type_bits: !0,
+            };
+            let host = vk::MemoryPropertyFlags::HOST_VISIBLE;
+            let allocator = &device.allocator;
+            let a = allocator
+                .alloc(device.as_raw(), &requirements, host, Kind::Buffer)
+                .unwrap();
+            let b = allocator
+                .alloc(device.as_raw(), &requirements, host, Kind::Buffer)
+                .unwrap();
+            assert_eq!(a.memory(), b.memory());
+            assert_ne!(a.offset(), b.offset());
+            assert!(a.mapped().is_some());
+            let image = allocator
+                .alloc(device.as_raw(), &requirements, host, Kind::Image)
+                .unwrap();
+            assert_ne!(image.memory(), a.memory());
+
+            let stats = device.memory_stats();
+            assert_eq!(stats.allocations, before.allocations + 3);
+            assert_eq!(stats.memory_objects, before.memory_objects + 2);
+            for allocation in [a, b, image] {
+                allocator.free(device.as_raw(), &allocation);
+            }
+            assert_eq!(device.memory_stats(), before);
+        });
+    }
+}
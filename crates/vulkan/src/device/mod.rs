@@ -3,13 +3,16 @@
 
 //! # Context
 
+pub mod capabilities;
 pub mod descriptors;
 pub mod device;
+pub mod memory;
 pub mod queue;
 
 pub use device::Device;
 
 pub mod prelude {
+    pub use super::capabilities::{Capabilities, Tier};
     pub use super::device::Device;
     pub use super::device::Fence;
     pub use super::queue::prelude::*;
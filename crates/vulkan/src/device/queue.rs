@@ -257,6 +257,20 @@ impl Queues {
         }
     }
 
+    /// ## Async Compute
+    ///
+    /// The high-priority compute queue, but only when it is in a family without graphics, where
+    /// its work can overlap with rendering.  Buffers it writes for graphics must change families,
+    /// see [`BufferTransfer`](crate::dispatch::ownership::BufferTransfer).  `None` on devices
+    /// without a dedicated compute family, which should submit to graphics instead.
+    pub fn async_compute(&self) -> Option<&Queue<Compute>> {
+        let compute = &self.high_compute;
+        (!compute
+            .capabilities()
+            .contains(vk::QueueFlags::GRAPHICS))
+        .then_some(compute)
+    }
+
     /// ## Transfer
     ///
     /// Transfer queues have no high-priority semantics, although they will use an overloaded
@@ -318,6 +332,23 @@ impl Queues {
         }
     }
 
+    /// Every queue with a name for debug tools.  Aliased queues appear once per role, so the last
+    /// role wins the name.
+    pub(crate) fn labeled(&self) -> Vec<(String, vk::Queue)> {
+        let graphics = |priority: &str, queues: &[Queue<Graphics>]| {
+            queues
+                .iter()
+                .map(|q| (format!("graphics {priority} family {}", q.family), q.raw))
+                .collect::<Vec<_>>()
+        };
+        let mut labeled = graphics("high", &self.high_graphics);
+        labeled.extend(graphics("low", &self.low_graphics));
+        labeled.push(("compute high".to_owned(), self.high_compute.raw));
+        labeled.push(("compute low".to_owned(), self.low_compute.raw));
+        labeled.push(("transfer".to_owned(), self.transfer.raw));
+        labeled
+    }
+
     // NOTE queues are owned by the device.  Just drop when done.
 }
 
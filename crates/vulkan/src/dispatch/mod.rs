@@ -114,10 +114,12 @@
 // into pool and a top level doc needs to present the high-level overview.
 
 pub mod cb;
+pub mod ownership;
 pub mod pool;
 pub mod pw;
 pub mod submit;
 pub mod sync;
+pub mod timer;
 
 pub mod prelude {
     // Put traits into there as they show up
@@ -131,8 +133,10 @@ pub mod prelude {
         ExecutableBuffer, ExecutableSecondary, RecordingBuffer, RecordingSecondary,
         RenderingBuffer, RenderingSecondary,
     };
+    pub use super::ownership::BufferTransfer;
     pub use super::pool::{CommandPool, PoolRing};
     pub use super::submit::QueueSubmit;
+    pub use super::timer::GpuTimer;
     // XXX make binary private after pulling in swapchain presentation gear
     pub use super::sync::{
         BinarySemaphore, BinarySignal, BinaryWait, SignalIntent, TimelineSemaphore, WaitValue,
@@ -0,0 +1,169 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Queue Ownership
+//!
+//! Buffers are created `EXCLUSIVE`, owned by one queue family at a time.  Work on an async compute
+//! queue, such as the DSP spectrogram, writes buffers that a graphics queue in another family later
+//! reads.  Moving a buffer between families takes a matching pair of barriers:
+//!
+//! 1. [`release`](BufferTransfer::release) at the end of the writer's command buffer.
+//! 2. A semaphore signaled by the writer's submission and waited on by the reader's.
+//! 3. [`acquire`](BufferTransfer::acquire) at the start of the reader's command buffer.
+//!
+//! Both barriers do nothing when the families match, which is the case on devices without a
+//! dedicated compute family.  The semaphore alone orders the work there, so callers can record the
+//! same commands on every device.
+
+// MAYBE images need the same pair with layouts.  Wait for a node that shares an image across
+// families rather than guess at the layouts it wants.
+
+use crate::internal::*;
+
+/// Moves a buffer from one queue family to another.  See the [module docs](self).
+#[derive(Clone, Copy, Debug)]
+pub struct BufferTransfer {
+    buffer: vk::Buffer,
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+    src_family: u32,
+    dst_family: u32,
+}
+
+impl BufferTransfer {
+    /// Transfer all of `buffer` from `src_family` to `dst_family`.
+    pub fn new(buffer: vk::Buffer, src_family: u32, dst_family: u32) -> Self {
+        Self {
+            buffer,
+            offset: 0,
+            size: vk::WHOLE_SIZE,
+            src_family,
+            dst_family,
+        }
+    }
+
+    /// Transfer only a range.  The release and acquire must name the same range.
+    pub fn with_range(mut self, offset: vk::DeviceSize, size: vk::DeviceSize) -> Self {
+        self.offset = offset;
+        self.size = size;
+        self
+    }
+
+    /// The transfer in the other direction, such as to hand a buffer back to its writer.
+    pub fn reversed(self) -> Self {
+        Self {
+            src_family: self.dst_family,
+            dst_family: self.src_family,
+            ..self
+        }
+    }
+
+    /// Whether the families differ and barriers will be recorded.
+    pub fn is_needed(&self) -> bool {
+        self.src_family != self.dst_family
+    }
+
+    /// Record the release on a command buffer for the source family.  `stage` and `access` are how
+    /// the source last wrote the buffer.
+    pub fn release(
+        &self,
+        device: &Device,
+        cb: vk::CommandBuffer,
+        stage: vk::PipelineStageFlags2,
+        access: vk::AccessFlags2,
+    ) {
+        if let Some(barrier) = self.release_barrier(stage, access) {
+            record(device, cb, barrier);
+        }
+    }
+
+    /// Record the acquire on a command buffer for the destination family.  `stage` and `access` are
+    /// how the destination will first use the buffer.
+    pub fn acquire(
+        &self,
+        device: &Device,
+        cb: vk::CommandBuffer,
+        stage: vk::PipelineStageFlags2,
+        access: vk::AccessFlags2,
+    ) {
+        if let Some(barrier) = self.acquire_barrier(stage, access) {
+            record(device, cb, barrier);
+        }
+    }
+
+    fn barrier(&self) -> vk::BufferMemoryBarrier2<'static> {
+        vk::BufferMemoryBarrier2::default()
+            .src_queue_family_index(self.src_family)
+            .dst_queue_family_index(self.dst_family)
+            .buffer(self.buffer)
+            .offset(self.offset)
+            .size(self.size)
+    }
+
+    /// The destination half of a release is ignored, so it only names the source's scope.
+    fn release_barrier(
+        &self,
+        stage: vk::PipelineStageFlags2,
+        access: vk::AccessFlags2,
+    ) -> Option<vk::BufferMemoryBarrier2<'static>> {
+        self.is_needed()
+            .then(|| self.barrier().src_stage_mask(stage).src_access_mask(access))
+    }
+
+    /// The source half of an acquire is ignored, so it only names the destination's scope.
+    fn acquire_barrier(
+        &self,
+        stage: vk::PipelineStageFlags2,
+        access: vk::AccessFlags2,
+    ) -> Option<vk::BufferMemoryBarrier2<'static>> {
+        self.is_needed()
+            .then(|| self.barrier().dst_stage_mask(stage).dst_access_mask(access))
+    }
+}
+
+fn record(device: &Device, cb: vk::CommandBuffer, barrier: vk::BufferMemoryBarrier2) {
+    let dependency =
+        vk::DependencyInfo::default().buffer_memory_barriers(std::slice::from_ref(&barrier));
+    unsafe { device.as_raw().cmd_pipeline_barrier2(cb, &dependency) };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_barriers() {
+        let stage = vk::PipelineStageFlags2::COMPUTE_SHADER;
+        let access = vk::AccessFlags2::SHADER_STORAGE_WRITE;
+        let shared = BufferTransfer::new(vk::Buffer::null(), 0, 0);
+        assert!(!shared.is_needed());
+        assert!(shared.release_barrier(stage, access).is_none());
+        assert!(shared.acquire_barrier(stage, access).is_none());
+
+        let transfer = BufferTransfer::new(vk::Buffer::null(), 1, 0).with_range(256, 1024);
+        let release = transfer.release_barrier(stage, access).unwrap();
+        assert_eq!(
+            (
+                release.src_queue_family_index,
+                release.dst_queue_family_index
+            ),
+            (1, 0)
+        );
+        assert_eq!(release.src_stage_mask, stage);
+        assert_eq!(release.dst_stage_mask, vk::PipelineStageFlags2::NONE);
+        assert_eq!((release.offset, release.size), (256, 1024));
+
+        let read = vk::PipelineStageFlags2::FRAGMENT_SHADER;
+        let acquire = transfer
+            .acquire_barrier(read, vk::AccessFlags2::SHADER_STORAGE_READ)
+            .unwrap();
+        assert_eq!(acquire.dst_stage_mask, read);
+        assert_eq!(acquire.src_access_mask, vk::AccessFlags2::NONE);
+
+        let back = transfer.reversed().release_barrier(read, access).unwrap();
+        assert_eq!(
+            (back.src_queue_family_index, back.dst_queue_family_index),
+            (0, 1)
+        );
+    }
+}
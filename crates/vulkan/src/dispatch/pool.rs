@@ -312,6 +312,7 @@ impl<C: Capability, M: SubmissionModel, const N: usize> PoolRing<C, N, M> {
         for pool in pools {
             pool.destroy(device);
         }
+        device.descriptors.forget_timeline(timeline.as_raw());
         timeline.destroy(device);
     }
 }
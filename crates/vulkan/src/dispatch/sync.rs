@@ -178,6 +178,10 @@ impl WaitValue {
     pub(crate) fn value(&self) -> u64 {
         self.value
     }
+
+    pub(crate) fn semaphore(&self) -> vk::Semaphore {
+        self.semaphore
+    }
 }
 
 /// Vulkan **binary** [Semaphore](vk::Semaphore) with some light abstraction to encourage valid
@@ -245,6 +249,13 @@ pub struct BinarySignal {
 }
 
 impl BinarySignal {
+    /// Signals nothing.  Submissions must not be given it.
+    pub(crate) fn null() -> Self {
+        Self {
+            semaphore: vk::Semaphore::null(),
+        }
+    }
+
     pub fn as_raw(&self) -> vk::Semaphore {
         self.semaphore
     }
@@ -262,6 +273,13 @@ pub struct BinaryWait {
 }
 
 impl BinaryWait {
+    /// Waits on nothing.  Submissions must not be given it.
+    pub(crate) fn null() -> Self {
+        Self {
+            semaphore: vk::Semaphore::null(),
+        }
+    }
+
     pub fn as_raw(&self) -> vk::Semaphore {
         self.semaphore
     }
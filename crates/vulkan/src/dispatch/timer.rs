@@ -0,0 +1,143 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # GPU Timer
+//!
+//! Timing a frame on the host includes waiting on acquire and fences, which measures the display
+//! rather than the work.  A [`GpuTimer`] writes a timestamp at the start and end of a command buffer
+//! and reads the difference back once the frame has retired.
+//!
+//! Each of the `N` frames in flight has its own pair of queries.  [`begin`](GpuTimer::begin) reads
+//! the pair left by the frame that last used its slot before resetting it, so results arrive `N`
+//! frames late.  The caller must have waited on that frame, as a [`PoolRing`] of the same size
+//! does.  Results that are not available yet are skipped rather than waited on.
+
+use std::time::Duration;
+
+use crate::internal::*;
+
+/// Times command buffers on a queue family for `N` frames in flight.  See the [module docs](self).
+pub struct GpuTimer<const N: usize = 2> {
+    pool: vk::QueryPool,
+    /// Nanoseconds per timestamp tick.
+    period: f64,
+    /// Bits of each timestamp that are valid.  The rest are garbage.
+    mask: u64,
+    /// Whether each slot's pair was written since it was last read.
+    written: [bool; N],
+    cursor: usize,
+}
+
+impl<const N: usize> GpuTimer<N> {
+    /// Fails with [`VulkanError::MissingFeature`] when queues of `family` cannot write timestamps.
+    pub fn new(device: &Device, instance: &Instance, family: u32) -> Result<Self, VulkanError> {
+        let (props, families) = unsafe {
+            (
+                instance
+                    .raw
+                    .get_physical_device_properties(device.physical_device),
+                instance
+                    .raw
+                    .get_physical_device_queue_family_properties(device.physical_device),
+            )
+        };
+        let bits = families
+            .get(family as usize)
+            .map_or(0, |f| f.timestamp_valid_bits);
+        if bits == 0 {
+            return Err(VulkanError::MissingFeature(format!(
+                "timestamps on queue family {family}"
+            )));
+        }
+        let ci = vk::QueryPoolCreateInfo::default()
+            .query_type(vk::QueryType::TIMESTAMP)
+            .query_count(2 * N as u32);
+        let pool = unsafe { device.as_raw().create_query_pool(&ci, None)? };
+        device.set_name(pool, "gpu timer");
+        Ok(Self {
+            pool,
+            period: f64::from(props.limits.timestamp_period),
+            mask: u64::MAX >> (64 - bits.min(64)),
+            written: [false; N],
+            cursor: 0,
+        })
+    }
+
+    /// Start timing the next frame on `cb`, which should be empty so far.  Returns the time of the
+    /// frame that last used the slot, if it is available.
+    pub fn begin(&mut self, device: &Device, cb: vk::CommandBuffer) -> Option<Duration> {
+        let slot = self.cursor;
+        let first = 2 * slot as u32;
+        let previous = match self.written[slot] {
+            true => self.read(device, first),
+            false => None,
+        };
+        self.written[slot] = false;
+        unsafe {
+            let raw = device.as_raw();
+            raw.cmd_reset_query_pool(cb, self.pool, first, 2);
+            raw.cmd_write_timestamp2(cb, vk::PipelineStageFlags2::TOP_OF_PIPE, self.pool, first);
+        }
+        previous
+    }
+
+    /// Stop timing the frame begun on `cb`.  Record last.
+    pub fn end(&mut self, device: &Device, cb: vk::CommandBuffer) {
+        let slot = self.cursor;
+        unsafe {
+            device.as_raw().cmd_write_timestamp2(
+                cb,
+                vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
+                self.pool,
+                2 * slot as u32 + 1,
+            );
+        }
+        self.written[slot] = true;
+        self.cursor = (slot + 1) % N;
+    }
+
+    fn read(&self, device: &Device, first: u32) -> Option<Duration> {
+        let mut ticks = [0u64; 2];
+        let read = unsafe {
+            device.as_raw().get_query_pool_results(
+                self.pool,
+                first,
+                &mut ticks,
+                vk::QueryResultFlags::TYPE_64,
+            )
+        };
+        // NOT_READY for a frame that never ran, such as one abandoned after a failed submit.
+        read.ok()?;
+        Some(elapsed(ticks[0], ticks[1], self.mask, self.period))
+    }
+
+    /// Caller must drain work that wrote timestamps first.
+    pub fn destroy(&self, device: &Device) {
+        unsafe { device.as_raw().destroy_query_pool(self.pool, None) }
+    }
+}
+
+/// Time between two timestamps of `mask` valid bits, `period` nanoseconds per tick apart.
+fn elapsed(begin: u64, end: u64, mask: u64, period: f64) -> Duration {
+    // Counters narrower than 64 bits wrap.
+    let ticks = end.wrapping_sub(begin) & mask;
+    Duration::from_nanos((ticks as f64 * period) as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_elapsed() {
+        assert_eq!(elapsed(100, 1100, u64::MAX, 1.0), Duration::from_micros(1));
+        // Some devices tick slower than once per nanosecond.
+        assert_eq!(
+            elapsed(0, 1000, u64::MAX, 52.08),
+            Duration::from_nanos(52080)
+        );
+        // A 36 bit counter wrapped between the two.
+        let mask = (1 << 36) - 1;
+        assert_eq!(elapsed(mask - 9, 10, mask, 1.0), Duration::from_nanos(20));
+    }
+}
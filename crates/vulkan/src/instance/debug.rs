@@ -0,0 +1,228 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Debug
+//!
+//! Validation layers and `VK_EXT_debug_utils`.  [`DebugOptions`] decide what the instance turns on:
+//!
+//! - The Khronos validation layer, on by default in debug builds.  A missing layer is a warning, not
+//!   an error, so that machines without the SDK still run.
+//! - A debug messenger, when the loader has `VK_EXT_debug_utils`.  Messages go to the `log` crate
+//!   under the `vulkan` target, at the level matching their severity.  Programs without a logger
+//!   get them on stderr instead.
+//! - Object names, set with [`Device::set_name`](crate::device::Device::set_name), which show up in
+//!   validation messages and in tools like RenderDoc.
+//!
+//! ## `MUTATE_VALIDATION`
+//!
+//! [`with_env`](DebugOptions::with_env) lets users override the defaults without rebuilding:
+//!
+//! - `0`, `off`, or `false` disables validation.
+//! - `1`, `on`, or `true` enables it, such as in release builds.
+//! - `verbose` enables it and also passes on info and verbose messages.
+
+use std::ffi::{c_void, CStr};
+
+use ash::vk;
+
+use crate::VulkanError;
+
+/// Environment variable read by [`DebugOptions::with_env`].
+pub const VALIDATION_ENV: &str = "MUTATE_VALIDATION";
+
+pub(crate) const VALIDATION_LAYER: &CStr = c"VK_LAYER_KHRONOS_validation";
+
+/// Target of logged debug messages.
+pub const LOG_TARGET: &str = "vulkan";
+
+/// What debugging support an [`Instance`](super::Instance) asks for.  See the
+/// [module docs](self).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DebugOptions {
+    validation: bool,
+    messenger: bool,
+    verbose: bool,
+}
+
+impl Default for DebugOptions {
+    fn default() -> Self {
+        Self {
+            validation: cfg!(debug_assertions),
+            messenger: true,
+            verbose: false,
+        }
+    }
+}
+
+impl DebugOptions {
+    /// Validation in debug builds and a messenger when the loader supports one.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defaults with `MUTATE_VALIDATION` applied.
+    pub fn from_env() -> Self {
+        Self::new().with_env()
+    }
+
+    pub fn with_validation(mut self, validation: bool) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    /// Register a debug messenger when `VK_EXT_debug_utils` is present.
+    pub fn with_messenger(mut self, messenger: bool) -> Self {
+        self.messenger = messenger;
+        self
+    }
+
+    /// Pass on info and verbose messages, not just warnings and errors.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Apply a user's choice as described in the [module docs](self).  Unrecognized choices are
+    /// ignored.
+    pub fn with_choice(self, choice: &str) -> Self {
+        match choice.trim().to_lowercase().as_str() {
+            "0" | "off" | "false" => self.with_validation(false),
+            "1" | "on" | "true" => self.with_validation(true),
+            "verbose" => self.with_validation(true).with_verbose(true),
+            _ => self,
+        }
+    }
+
+    /// Apply `MUTATE_VALIDATION` if it is set.
+    pub fn with_env(self) -> Self {
+        match std::env::var(VALIDATION_ENV) {
+            Ok(choice) => self.with_choice(&choice),
+            Err(_) => self,
+        }
+    }
+
+    pub fn validation(&self) -> bool {
+        self.validation
+    }
+
+    pub fn messenger(&self) -> bool {
+        self.messenger
+    }
+
+    fn severity(&self) -> vk::DebugUtilsMessageSeverityFlagsEXT {
+        let severity = vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+            | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING;
+        if self.verbose {
+            severity
+                | vk::DebugUtilsMessageSeverityFlagsEXT::INFO
+                | vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE
+        } else {
+            severity
+        }
+    }
+
+    /// Also chained into instance creation to catch messages from creating and destroying the
+    /// instance itself.
+    pub(crate) fn messenger_ci(&self) -> vk::DebugUtilsMessengerCreateInfoEXT<'static> {
+        vk::DebugUtilsMessengerCreateInfoEXT::default()
+            .message_severity(self.severity())
+            .message_type(
+                vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                    | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                    | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+            )
+            .pfn_user_callback(Some(callback))
+    }
+}
+
+/// A registered debug messenger.  Destroy before the instance.
+pub(crate) struct Messenger {
+    loader: ash::ext::debug_utils::Instance,
+    raw: vk::DebugUtilsMessengerEXT,
+}
+
+impl Messenger {
+    pub(crate) fn new(
+        entry: &ash::Entry,
+        instance: &ash::Instance,
+        options: &DebugOptions,
+    ) -> Result<Self, VulkanError> {
+        let loader = ash::ext::debug_utils::Instance::new(entry, instance);
+        let raw = unsafe { loader.create_debug_utils_messenger(&options.messenger_ci(), None)? };
+        Ok(Self { loader, raw })
+    }
+
+    pub(crate) fn destroy(&self) {
+        unsafe { self.loader.destroy_debug_utils_messenger(self.raw, None) }
+    }
+}
+
+fn level(severity: vk::DebugUtilsMessageSeverityFlagsEXT) -> log::Level {
+    if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+        log::Level::Error
+    } else if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+        log::Level::Warn
+    } else if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO) {
+        log::Level::Info
+    } else {
+        log::Level::Debug
+    }
+}
+
+unsafe extern "system" fn callback(
+    severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    types: vk::DebugUtilsMessageTypeFlagsEXT,
+    data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
+    _user_data: *mut c_void,
+) -> vk::Bool32 {
+    // SAFETY the loader passes valid callback data that outlives the call, or null.
+    let Some(data) = (unsafe { data.as_ref() }) else {
+        return vk::FALSE;
+    };
+    let message =
+        unsafe { data.message_as_c_str() }.map_or_else(Default::default, CStr::to_string_lossy);
+    let id = unsafe { data.message_id_name_as_c_str() }
+        .map_or_else(Default::default, CStr::to_string_lossy);
+    let level = level(severity);
+    if log::log_enabled!(target: LOG_TARGET, level) {
+        log::log!(target: LOG_TARGET, level, "{types:?} {id}: {message}");
+    } else if level <= log::Level::Warn {
+        eprintln!("vulkan {level}: {types:?} {id}: {message}");
+    }
+    // Returning true would abort the call that triggered the message, which only layers should do.
+    vk::FALSE
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_choices() {
+        let off = DebugOptions::new().with_validation(true).with_choice("OFF");
+        assert!(!off.validation());
+        let on = DebugOptions::new()
+            .with_validation(false)
+            .with_choice(" 1 ");
+        assert!(on.validation());
+        let verbose = DebugOptions::new().with_choice("verbose");
+        assert!(verbose.validation());
+        assert!(verbose
+            .severity()
+            .contains(vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE));
+        let ignored = DebugOptions::new().with_choice("loud");
+        assert_eq!(ignored, DebugOptions::new());
+        assert!(!ignored
+            .severity()
+            .contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO));
+    }
+
+    #[test]
+    fn test_levels() {
+        use vk::DebugUtilsMessageSeverityFlagsEXT as S;
+        assert_eq!(level(S::ERROR), log::Level::Error);
+        assert_eq!(level(S::WARNING), log::Level::Warn);
+        assert_eq!(level(S::INFO), log::Level::Info);
+        assert_eq!(level(S::VERBOSE), log::Level::Debug);
+    }
+}
@@ -9,7 +9,9 @@
 //! We configure the instance to the support required for the Vulkan subset we support, which is
 //! Vulkan 1.4 with a fixed descriptor table, and scalar block layout support among others,
 //!
-//! For headless rendering to a TUI etc, we likely don't need any platform extensions, but for
+//! For headless rendering to a TUI, GPU DSP on a server, tests on CI, or exporting video, use
+//! [`Instance::new_headless`], which loads no surface extensions and asks devices for no swapchain
+//! extensions.  Machines without a window system or presentation support still get a device.  For
 //! rendering to a window, check `with_extensions` and its use of `ash_window` to cooperate with a
 //! `winit::event_loop::ActiveEventLoop` usually, although these specific dependencies are not
 //! strictly required.
@@ -25,10 +27,18 @@ use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawDisplayHandle, Raw
 
 use crate::present::surface::Surface;
 use crate::device;
+use crate::device::capabilities::Tier;
+use crate::VulkanError;
+
+pub mod debug;
+pub mod report;
+pub mod select;
 
 pub mod prelude {
     pub use super::Instance;
     pub use super::SupportedDevice;
+    pub use super::debug::DebugOptions;
+    pub use super::select::DeviceSelector;
 }
 
 /// The entry and instance represent a connection to the Vulkan implementation.
@@ -36,6 +46,8 @@ pub struct Instance {
     pub(crate) entry: ash::Entry,
     pub(crate) raw: ash::Instance,
     pub profile: InstanceProfile,
+    /// Present when `VK_EXT_debug_utils` is enabled, which also lets devices name objects.
+    pub(crate) messenger: Option<debug::Messenger>,
 }
 
 // For non-surface applications.
@@ -83,12 +95,10 @@ impl InstanceProfile {
 // DEBT currently the requirements and support checks are all hardcoded.
 pub(crate) const DEVICE_EXTENSIONS_CORE: &[&CStr] = &[
     vk::EXT_EXTENDED_DYNAMIC_STATE3_NAME,
-    // NEXT better debug gating (see validation layer activation above).
     // Enables some debug functionality in shaders.
     vk::KHR_SHADER_NON_SEMANTIC_INFO_NAME,
     vk::EXT_TOOLING_INFO_NAME,
-    // MAYBE I might just need to install something, but this fails at runtime on my machine.
-    // vk::EXT_DEBUG_UTILS_NAME,
+    // NOTE VK_EXT_debug_utils is an instance extension.  See the debug module.
 
     // MAYBE If we start running into lots of pipeline creation costs for slight variants,
     // we are advised to look at this extension.
@@ -116,14 +126,20 @@ pub(crate) const DEVICE_EXTENSIONS_SURFACE: &[&CStr] = &[
     vk::EXT_SWAPCHAIN_MAINTENANCE1_NAME,
 ];
 
-// DEBT Errors instead of panics, but that might require a lot of test re-writing that should be
-// done with macros to ease future pain.
+// DEBT surface creation still panics.  Construction returns errors so that embedders can explain a
+// missing driver or extension instead of aborting.
 impl Instance {
     /// Basic context for testing.  Does not have platform extensions for window or surface etc.
     /// Still useful for some workloads like compute or test rendering without swapchain or
     /// presentation.
-    pub fn new_headless() -> Self {
-        Self::with_extensions_inner(&[], &[], InstanceProfile::HEADLESS)
+    pub fn new_headless() -> Result<Self, VulkanError> {
+        Self::headless_with_debug(debug::DebugOptions::from_env())
+    }
+
+    /// [`new_headless`](Self::new_headless) with explicit `debug` options instead of the defaults
+    /// and `MUTATE_VALIDATION`.
+    pub fn headless_with_debug(debug: debug::DebugOptions) -> Result<Self, VulkanError> {
+        Self::with_extensions_inner(&[], &[], InstanceProfile::HEADLESS, debug)
     }
 
     /// Context with `required_exts` for the display platform enabled.  You cannot create a context
@@ -132,23 +148,55 @@ impl Instance {
     /// ```ignore
     /// // `event_loop` from winit::event_loop::ActiveEventLoop
     /// let required = ash_window::enumerate_required_extensions(event_loop);
-    /// let instance = Instance::with_extensions(required)
+    /// let instance = Instance::with_extensions(required)?;
     /// ```
-    pub fn with_extensions(required_exts: &[*const i8]) -> Self {
-        Self::with_extensions_inner(required_exts, INSTANCE_EXTENSIONS_SURFACE, InstanceProfile::SURFACE)
+    pub fn with_extensions(required_exts: &[*const i8]) -> Result<Self, VulkanError> {
+        Self::with_extensions_debug(required_exts, debug::DebugOptions::from_env())
+    }
+
+    /// [`with_extensions`](Self::with_extensions) with explicit `debug` options.
+    pub fn with_extensions_debug(
+        required_exts: &[*const i8],
+        debug: debug::DebugOptions,
+    ) -> Result<Self, VulkanError> {
+        Self::with_extensions_inner(required_exts, INSTANCE_EXTENSIONS_SURFACE, InstanceProfile::SURFACE, debug)
     }
 
     /// Context with the required extensions learned from a display handle, provided by winit
     /// applications.  Extra user-supplied `extensions` will be appended to those required by the
     /// display (in practice, required by the compositor such as Wayland or X).
     #[cfg(feature="winit")]
-    pub fn with_display(event_loop: &winit::event_loop::EventLoop<()>, extra_exts: &[*const i8]) -> Self {
+    pub fn with_display(
+        event_loop: &winit::event_loop::EventLoop<()>,
+        extra_exts: &[*const i8],
+    ) -> Result<Self, VulkanError> {
+        Self::with_display_debug(event_loop, extra_exts, debug::DebugOptions::from_env())
+    }
+
+    /// [`with_display`](Self::with_display) with explicit `debug` options.
+    #[cfg(feature="winit")]
+    pub fn with_display_debug(
+        event_loop: &winit::event_loop::EventLoop<()>,
+        extra_exts: &[*const i8],
+        debug: debug::DebugOptions,
+    ) -> Result<Self, VulkanError> {
         let display_handle = event_loop
             .display_handle()
-            .expect("winit: event loop has no raw display handle")
+            .map_err(|e| VulkanError::DriverError(format!("winit: no raw display handle: {e}")))?
             .as_raw();
-        let platform_exts = ash_window::enumerate_required_extensions(display_handle)
-            .expect("ash_window: unknown platform");
+        Self::with_raw_display_debug(display_handle, extra_exts, debug)
+    }
+
+    /// [`with_display_debug`](Self::with_display_debug) for a display connected without winit, such
+    /// as a Wayland connection that draws layer shell surfaces.
+    #[cfg(feature="winit")]
+    pub fn with_raw_display_debug(
+        display_handle: RawDisplayHandle,
+        extra_exts: &[*const i8],
+        debug: debug::DebugOptions,
+    ) -> Result<Self, VulkanError> {
+        // Fails with extension not present on platforms ash_window does not know.
+        let platform_exts = ash_window::enumerate_required_extensions(display_handle)?;
 
         // DEBT logging & configuration so we can toggle this at runtime
         // platform_exts.iter().for_each(|&e| {
@@ -165,66 +213,113 @@ impl Instance {
             .copied()
             .collect();
 
-        Self::with_extensions_inner(&all_exts, INSTANCE_EXTENSIONS_SURFACE, InstanceProfile::SURFACE)
+        Self::with_extensions_inner(&all_exts, INSTANCE_EXTENSIONS_SURFACE, InstanceProfile::SURFACE, debug)
     }
 
-    fn with_extensions_inner(required_exts: &[*const i8], instance_exts: &[&CStr], profile: InstanceProfile) -> Self {
-        let entry = unsafe { ash::Entry::load().expect("failed to load Vulkan library") };
-
-        // XXX Was about to add some explicit errors, but need to stabilize for mass text replace of
-        // the non-Result signatures.
-        let v = unsafe { entry.try_enumerate_instance_version() }
-            .expect("Vulkan Loader does not support Vulkan 1.3")
-            .expect("Vulkan Loader does not support Vulkan 1.3");
-        (v >= vk::make_api_version(0, 1, 3, 0))
-            .then_some(())
-            .expect("Vulkan Loader does not support Vulkan 1.3");
+    fn with_extensions_inner(
+        required_exts: &[*const i8],
+        instance_exts: &[&CStr],
+        profile: InstanceProfile,
+        debug: debug::DebugOptions,
+    ) -> Result<Self, VulkanError> {
+        let entry = unsafe { ash::Entry::load() }
+            .map_err(|e| VulkanError::LoaderUnavailable(e.to_string()))?;
+
+        // Loaders without the query are Vulkan 1.0.
+        let v = unsafe { entry.try_enumerate_instance_version()? }.unwrap_or(vk::API_VERSION_1_0);
+        if v < vk::make_api_version(0, 1, 3, 0) {
+            return Err(VulkanError::UnsupportedVersion(format!(
+                "{}.{}",
+                vk::api_version_major(v),
+                vk::api_version_minor(v)
+            )));
+        }
 
-        let available_instance_extensions = unsafe {
-            entry
-                .enumerate_instance_extension_properties(None)
-                .expect("Failed to enumerate instance extensions")
-        };
+        let available_instance_extensions =
+            unsafe { entry.enumerate_instance_extension_properties(None)? };
 
         for req in required_exts.iter().copied()
             .map(|p| unsafe { CStr::from_ptr(p) })
             .chain(INSTANCE_EXTENSIONS_CORE.iter().copied())
             .chain(instance_exts.iter().copied())
             {
-                assert!(
-                    available_instance_extensions.iter().any(|ext| {
-                        let name = unsafe { CStr::from_ptr(ext.extension_name.as_ptr()) };
-                        name == req
-                    }),
-                    "Required Vulkan instance extension {req:?} not found"
-                );
+                let found = available_instance_extensions.iter().any(|ext| {
+                    ext.extension_name_as_c_str().is_ok_and(|name| name == req)
+                });
+                if !found {
+                    return Err(VulkanError::MissingExtension(req.to_string_lossy().into_owned()));
+                }
             }
 
         let app_info =
             vk::ApplicationInfo::default().api_version(vk::make_api_version(0, 1, 3, 0));
-        let validation_layers = [
-            // #[cfg(debug_assertions)]
-            // NOTE Leaving this on all the time because there are still issues in `--release`
-            // builds and we need to default to leaving it on via the dev shells or something.
-            c"VK_LAYER_KHRONOS_validation".as_ptr()
-        ];
+
+        let mut layers: SmallVec<*const c_char, 1> = SmallVec::new();
+        if debug.validation() {
+            let available_layers = unsafe { entry.enumerate_instance_layer_properties()? };
+            let found = available_layers.iter().any(|layer| {
+                layer.layer_name_as_c_str().is_ok_and(|name| name == debug::VALIDATION_LAYER)
+            });
+            if found {
+                layers.push(debug::VALIDATION_LAYER.as_ptr());
+            } else {
+                log::warn!(target: debug::LOG_TARGET,
+                           "validation requested but {} is not installed",
+                           debug::VALIDATION_LAYER.to_string_lossy());
+            }
+        }
+        // The validation layer also provides debug utils, but it may be missing.
+        let debug_utils = debug.messenger()
+            && available_instance_extensions.iter().any(|ext| {
+                ext.extension_name_as_c_str().is_ok_and(|name| name == ash::ext::debug_utils::NAME)
+            });
 
         let ext_ptrs: Vec<*const i8> = required_exts.iter().copied()
             .chain(INSTANCE_EXTENSIONS_CORE.iter().map(|s| s.as_ptr()))
             .chain(instance_exts.iter().map(|s| s.as_ptr()))
+            .chain(debug_utils.then_some(ash::ext::debug_utils::NAME.as_ptr()))
             .collect();
-        let instance_ci = vk::InstanceCreateInfo::default()
+        let mut messenger_ci = debug.messenger_ci();
+        let mut instance_ci = vk::InstanceCreateInfo::default()
             .application_info(&app_info)
             .enabled_extension_names(&ext_ptrs)
-            .enabled_layer_names(&validation_layers);
-        let instance = unsafe { entry.create_instance(&instance_ci, None).unwrap() };
-        Self {
+            .enabled_layer_names(&layers);
+        if debug_utils {
+            instance_ci = instance_ci.push_next(&mut messenger_ci);
+        }
+        let instance = unsafe { entry.create_instance(&instance_ci, None)? };
+        let messenger = if debug_utils {
+            match debug::Messenger::new(&entry, &instance, &debug) {
+                Ok(messenger) => Some(messenger),
+                Err(e) => {
+                    unsafe { instance.destroy_instance(None) };
+                    return Err(e);
+                }
+            }
+        } else {
+            None
+        };
+        Ok(Self {
             entry,
             raw: instance,
             profile,
-        }
+            messenger,
+        })
+    }
+
+    /// Whether `VK_EXT_debug_utils` is enabled, so that devices can name objects.
+    pub fn has_debug_utils(&self) -> bool {
+        self.messenger.is_some()
     }
 
+    /// Whether this instance was created without surface extensions, by
+    /// [`new_headless`](Self::new_headless).  Headless instances and their devices can compute and
+    /// render offscreen but never present.
+    pub fn is_headless(&self) -> bool {
+        !self.profile.surface
+    }
+
+    /// Surface functions panic when called on a [headless](Self::is_headless) instance.
     pub fn surface_loader(&self) -> ash::khr::surface::Instance {
        ash::khr::surface::Instance::new(&self.entry, &self.raw)
     }
@@ -235,6 +330,7 @@ impl Instance {
         event_loop:&winit::event_loop::ActiveEventLoop,
         window: &winit::window::Window
     ) -> vk::SurfaceKHR {
+        assert!(!self.is_headless(), "headless instances cannot create surfaces");
         let display_handle = event_loop
             .display_handle()
             .expect("Event loop has no display handle")
@@ -249,6 +345,22 @@ impl Instance {
         }
     }
 
+    /// Create a surface from raw handles, for windows that winit does not make.
+    ///
+    /// # Safety
+    ///
+    /// Both handles must stay valid until the surface is destroyed, and `display_handle` must be the
+    /// display this instance was created for.
+    #[cfg(feature = "winit")]
+    pub unsafe fn surface_from_raw(
+        &self,
+        display_handle: RawDisplayHandle,
+        window_handle: RawWindowHandle,
+    ) -> Result<vk::SurfaceKHR, VulkanError> {
+        assert!(!self.is_headless(), "headless instances cannot create surfaces");
+        Ok(ash_window::create_surface(&self.entry, &self.raw, display_handle, window_handle, None)?)
+    }
+
     /// Returns a list of physical devices that meet requirements, sorted in order of preference for
     /// discrete, integrated, and virtual, with memory heap sizes as the secondary sort key.
     ///
@@ -269,7 +381,7 @@ impl Instance {
             exts.extend_from_slice(extensions);
             exts
         };
-        let mut physical_devices: Vec<(vk::PhysicalDevice, vk::PhysicalDeviceProperties)> = physical_devices
+        let mut physical_devices: Vec<(vk::PhysicalDevice, vk::PhysicalDeviceProperties, Tier)> = physical_devices
             .into_iter()
             .filter_map(|physical_device| {
                 let props = unsafe { self.raw.get_physical_device_properties(physical_device) };
@@ -278,13 +390,15 @@ impl Instance {
                     let minor = vk::api_version_minor(props.api_version);
                     major > 1 || (major == 1 && minor >= 3)
                 };
-                (meets_version
-                    && self.device_meets_features(physical_device)
-                    && self.device_meets_extensions(physical_device, &extensions))
-                    .then_some((physical_device, props))
+                if !meets_version || !self.device_meets_extensions(physical_device, &extensions) {
+                    return None;
+                }
+                self.device_tier(physical_device)
+                    .map(|tier| (physical_device, props, tier))
             })
             .collect();
-        physical_devices.sort_by_key(|(physical_device, props)| {
+        // Full tier devices first.  A Core device only wins when it is the only choice.
+        physical_devices.sort_by_key(|(physical_device, props, tier)| {
             let device_type_rank = |t: vk::PhysicalDeviceType| match t {
                 vk::PhysicalDeviceType::DISCRETE_GPU  => 0u8,
                 vk::PhysicalDeviceType::INTEGRATED_GPU => 1,
@@ -298,16 +412,38 @@ impl Instance {
                 .map(|heap| heap.size)
                 .max()
                 .unwrap_or(0);
-            (device_type_rank(props.device_type), std::cmp::Reverse(max_memory))
+            (std::cmp::Reverse(*tier), device_type_rank(props.device_type), std::cmp::Reverse(max_memory))
         });
         physical_devices
             .into_iter()
-            .map(|(physical_device, _)| SupportedDevice::new(physical_device, self, &extensions, self.profile))
+            .map(|(physical_device, _, tier)| SupportedDevice::new(physical_device, self, &extensions, self.profile, tier))
             .collect()
     }
 
-    fn device_meets_features(&self, physical_device: vk::PhysicalDevice) -> bool {
+    /// The [`Tier`] of features the device supports, if any.
+    fn device_tier(&self, physical_device: vk::PhysicalDevice) -> Option<Tier> {
+        let missing = self.missing_features(physical_device);
+        let tier = Tier::classify(&missing);
+        if tier.is_none() {
+            // DEBT logging.  We could return an error but it's not an error for a device to be
+            // missing functionality, only for all devices to be missing some functionality.
+            #[cfg(debug_assertions)]
+            {
+                let props = unsafe {
+                    self.raw.get_physical_device_properties(physical_device)
+                };
+                let name = unsafe { std::ffi::CStr::from_ptr(props.device_name.as_ptr()) };
+                println!("Physical device unsupported: {}", name.to_string_lossy());
+                for m in missing {
+                    println!("  missing feature: {}", m)
+                }
+            }
+        }
+        tier
+    }
 
+    /// Required features the device lacks, including those only [`Tier::Full`] requires.
+    pub(crate) fn missing_features(&self, physical_device: vk::PhysicalDevice) -> Vec<&'static str> {
         let mut features_1_3 = vk::PhysicalDeviceVulkan13Features::default();
         let mut features_1_2 = vk::PhysicalDeviceVulkan12Features::default();
         let mut features_1_1 = vk::PhysicalDeviceVulkan11Features::default();
@@ -339,6 +475,7 @@ impl Instance {
             ("1.2 descriptor_binding_sampled_image_update_after_bind",  features_1_2.descriptor_binding_sampled_image_update_after_bind == vk::TRUE),
             ("1.2 descriptor_binding_storage_buffer_update_after_bind", features_1_2.descriptor_binding_storage_buffer_update_after_bind == vk::TRUE),
             ("1.2 descriptor_binding_storage_image_update_after_bind",  features_1_2.descriptor_binding_storage_image_update_after_bind == vk::TRUE),
+            ("1.2 descriptor_binding_update_unused_while_pending",      features_1_2.descriptor_binding_update_unused_while_pending == vk::TRUE),
             ("1.2 descriptor_binding_variable_descriptor_count",        features_1_2.descriptor_binding_variable_descriptor_count == vk::TRUE),
             ("1.2 descriptor_indexing",                                 features_1_2.descriptor_indexing == vk::TRUE),
             ("1.2 draw_indirect_count",                                 features_1_2.draw_indirect_count == vk::TRUE),
@@ -362,10 +499,27 @@ impl Instance {
             ("1.3 shader_demote_to_helper_invocation",                  features_1_3.shader_demote_to_helper_invocation == vk::TRUE),
             ("1.3 synchronization2",                                    features_1_3.synchronization2 == vk::TRUE),
         ];
-        let missing: Vec<&'static str> = checks
+        checks
             .iter()
             .filter_map(|(name, present)| (!present).then_some(*name))
-            .collect();
+            .collect()
+    }
+
+    fn device_meets_version(&self, physical_device: vk::PhysicalDevice) -> bool {
+        let props = unsafe { self.raw.get_physical_device_properties(physical_device) };
+        let api_version = props.api_version;
+
+        let major = vk::api_version_major(api_version);
+        let minor = vk::api_version_minor(api_version);
+
+        major > 1 || (major == 1 && minor >= 3)
+    }
+
+    fn device_meets_extensions (&self,
+        physical_device: vk::PhysicalDevice,
+        extensions: &[&CStr]
+    ) -> bool {
+        let missing = self.missing_extensions(physical_device, extensions);
 
         if missing.is_empty() {
             true
@@ -380,34 +534,25 @@ impl Instance {
                 let name = unsafe { std::ffi::CStr::from_ptr(props.device_name.as_ptr()) };
                 println!("Physical device unsupported: {}", name.to_string_lossy());
                 for m in missing {
-                    println!("  missing feature: {}", m)
+                    println!("  missing extension: {}", m.to_string_lossy())
                 }
             }
             false
         }
     }
 
-    fn device_meets_version(&self, physical_device: vk::PhysicalDevice) -> bool {
-        let props = unsafe { self.raw.get_physical_device_properties(physical_device) };
-        let api_version = props.api_version;
-
-        let major = vk::api_version_major(api_version);
-        let minor = vk::api_version_minor(api_version);
-
-        major > 1 || (major == 1 && minor >= 3)
-    }
-
-    fn device_meets_extensions (&self,
+    /// Members of `extensions` the device lacks.
+    pub(crate) fn missing_extensions<'a>(&self,
         physical_device: vk::PhysicalDevice,
-        extensions: &[&CStr]
-    ) -> bool {
+        extensions: &[&'a CStr]
+    ) -> Vec<&'a CStr> {
         let available_device_extensions = unsafe {
             self.raw
                 .enumerate_device_extension_properties(physical_device)
                 .expect("Failed to enumerate device extensions")
         };
 
-        let missing: Vec<&CStr> = extensions
+        extensions
             .iter()
             .filter_map(|req| {
                 let found = available_device_extensions.iter().any(|ext| {
@@ -417,29 +562,13 @@ impl Instance {
                 });
                 if found { None } else { Some(*req) }
             })
-            .collect();
-
-        if missing.is_empty() {
-            true
-        } else {
-            // DEBT logging.  We could return an error but it's not an error for a device to be
-            // missing functionality, only for all devices to be missing some functionality.
-            #[cfg(debug_assertions)]
-            {
-                let props = unsafe {
-                    self.raw.get_physical_device_properties(physical_device)
-                };
-                let name = unsafe { std::ffi::CStr::from_ptr(props.device_name.as_ptr()) };
-                println!("Physical device unsupported: {}", name.to_string_lossy());
-                for m in missing {
-                    println!("  missing extension: {}", m.to_string_lossy())
-                }
-            }
-            false
-        }
+            .collect()
     }
 
     pub fn destroy(&self) {
+        if let Some(messenger) = &self.messenger {
+            messenger.destroy();
+        }
         unsafe {self.raw.destroy_instance(None)}
     }
 
@@ -447,6 +576,7 @@ impl Instance {
         &self.raw
     }
 
+    /// The debug messenger, if any, is leaked.
     pub fn into_raw(self) -> ash::Instance {
         self.raw
     }
@@ -483,18 +613,26 @@ impl std::ops::Deref for Instance {
 #[macro_export]
 macro_rules! with_context {
     (|$device:ident| $($body:tt)*) => {{
-        let mut instance = $crate::instance::Instance::new_headless();
-        let mut physical_device = instance.supported_devices(&[]).remove(0);
-        let mut $device = physical_device.into_logical(&instance);
+        let mut instance = $crate::instance::Instance::new_headless()
+            .expect("with_context: no Vulkan instance");
+        let mut physical_device = instance.supported_devices(&[]).into_iter().next()
+            .expect("with_context: no supported device");
+        let mut $device = physical_device
+            .into_logical(&instance)
+            .expect("with_context: no logical device");
         let __result = (|| { $($body)* })();
         $device.destroy();
         instance.destroy();
         __result
     }};
     (|$device:ident, $instance:ident| $($body:tt)*) => {{
-        let mut $instance = $crate::instance::Instance::new_headless();
-        let mut physical_device = $instance.supported_devices(&[]).remove(0);
-        let mut $device = physical_device.into_logical(&$instance);
+        let mut $instance = $crate::instance::Instance::new_headless()
+            .expect("with_context: no Vulkan instance");
+        let mut physical_device = $instance.supported_devices(&[]).into_iter().next()
+            .expect("with_context: no supported device");
+        let mut $device = physical_device
+            .into_logical(&$instance)
+            .expect("with_context: no logical device");
         let __result = (|| { $($body)* })();
         $device.destroy();
         $instance.destroy();
@@ -511,10 +649,13 @@ pub struct SupportedDevice {
     physical_device: vk::PhysicalDevice,
     /// `AMD Renoir...`  `Nvidia Max-Q...` etc
     pub name: String,
+    pub device_type: vk::PhysicalDeviceType,
+    /// Highest tier of features the device supports, and the features it will be created with.
+    pub tier: Tier,
     /// Features we checked for during inspection and ones that will be used when instantiating this
     /// device.  Modifying without checking is a really bad idea.  Pass any extra features you
     /// require into the [`supported_devices`] call.
-    // DEBT no dynamic feature and technique negotiation or fallback support.
+    // DEBT feature tiers are the only fallback.  No dynamic extension or technique negotiation.
     pub extensions: Vec<&'static CStr>,
 
     pub profile: InstanceProfile,
@@ -522,10 +663,9 @@ pub struct SupportedDevice {
 
 impl SupportedDevice {
     fn new(physical_device: vk::PhysicalDevice, instance: &Instance, extensions: &[&'static
-    CStr], profile: InstanceProfile) -> SupportedDevice{
+    CStr], profile: InstanceProfile, tier: Tier) -> SupportedDevice{
+        let props = unsafe { instance.raw.get_physical_device_properties(physical_device) };
         let name = unsafe {
-            let props =
-                instance.raw.get_physical_device_properties(physical_device);
             std::ffi::CStr::from_ptr(props.device_name.as_ptr())
                 .to_string_lossy()
                 .to_string()
@@ -536,6 +676,8 @@ impl SupportedDevice {
         Self {
             physical_device,
             name,
+            device_type: props.device_type,
+            tier,
             extensions,
             profile,
         }
@@ -550,6 +692,9 @@ impl SupportedDevice {
         surface: vk::SurfaceKHR,
         instance: &Instance,
     ) -> bool {
+        if instance.is_headless() {
+            return false;
+        }
         let surface_loader = instance.surface_loader();
         let Instance { entry: _, raw: instance, .. } = instance;
         let families = unsafe {
@@ -573,7 +718,7 @@ impl SupportedDevice {
     }
 
     /// Instantiate the logical device context.
-    pub fn into_logical (self, instance: &Instance) -> device::Device {
+    pub fn into_logical (self, instance: &Instance) -> Result<device::Device, VulkanError> {
         device::Device::new(instance, self)
     }
 
@@ -598,7 +743,7 @@ mod test {
 
     #[test]
     fn supported_devices () {
-        let instance = Instance::with_extensions(&[]);
+        let instance = Instance::with_extensions(&[]).unwrap();
         let supported = instance.supported_devices(&[]);
         println!("Supported devices:");
         for device in supported.iter() {
@@ -608,7 +753,7 @@ mod test {
 
     #[test]
     fn custom_features () {
-        let instance = Instance::with_extensions(&[]);
+        let instance = Instance::new_headless().unwrap();
         let supported = instance.supported_devices(&[c"VK_KHR_commercial_success"]);
         assert!(supported.is_empty());
     }
@@ -621,5 +766,16 @@ mod test {
         with_context!(|instance, device| {});
     }
 
-    // NEXT Headless tests.  Fake windows.  Something.  Want to check on surface support!
+    #[test]
+    fn headless () {
+        let instance = Instance::new_headless().unwrap();
+        assert!(instance.is_headless());
+        for device in instance.supported_devices(&[]) {
+            assert!(!device.extensions.contains(&vk::KHR_SWAPCHAIN_NAME));
+            assert!(!device.supports_surface(vk::SurfaceKHR::null(), &instance));
+        }
+        instance.destroy();
+    }
+
+    // NEXT Fake windows.  Something.  Want to check on surface support!
 }
@@ -0,0 +1,236 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Capability Report
+//!
+//! A snapshot of what the loader and every physical device support, including devices that
+//! [`Instance::supported_devices`] filters out.  Bug reports and runtime feature gating read the
+//! same [`Report`] so that what users paste is what the code decided on.
+//!
+//! `Display` renders Markdown that can be pasted into an issue as-is.
+
+// NEXT gate present timing and calibrated timestamps on `DeviceReport::has` once the code paths
+// that use them exist.
+
+use std::ffi::CStr;
+use std::fmt;
+
+use ash::vk;
+
+use super::{Instance, SupportedDevice};
+use crate::device::capabilities::{Tier, FULL_FEATURES};
+use crate::VulkanError;
+
+/// Extensions we use when present, paired with what is lost without them.
+pub const OPTIONAL_DEVICE_EXTENSIONS: &[(&CStr, &str)] = &[
+    (
+        c"VK_EXT_present_timing",
+        "frame pacing estimates presentation from present wait, with about 2ms of jitter",
+    ),
+    (
+        vk::KHR_CALIBRATED_TIMESTAMPS_NAME,
+        "render times cannot be compared against the audio clock",
+    ),
+];
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    /// Highest instance version the loader supports.
+    pub loader_version: u32,
+    pub devices: Vec<DeviceReport>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DeviceReport {
+    pub name: String,
+    pub device_type: vk::PhysicalDeviceType,
+    pub api_version: u32,
+    /// Driver name and version as the driver reports them.
+    pub driver: String,
+    /// Required features the device lacks, including those only [`Tier::Full`] requires.
+    pub missing_features: Vec<&'static str>,
+    /// Required extensions the device lacks.
+    pub missing_extensions: Vec<&'static CStr>,
+    /// Each of [`OPTIONAL_DEVICE_EXTENSIONS`] and whether the device has it.
+    pub optional: Vec<(&'static CStr, bool)>,
+}
+
+impl DeviceReport {
+    /// Whether [`Instance::supported_devices`] would offer this device.
+    pub fn usable(&self) -> bool {
+        api_at_least_1_3(self.api_version)
+            && self.tier().is_some()
+            && self.missing_extensions.is_empty()
+    }
+
+    /// Feature tier the device would be created with, if any.
+    pub fn tier(&self) -> Option<Tier> {
+        Tier::classify(&self.missing_features)
+    }
+
+    /// Whether an optional extension is available.
+    pub fn has(&self, extension: &CStr) -> bool {
+        self.optional
+            .iter()
+            .any(|(name, present)| *name == extension && *present)
+    }
+}
+
+impl Instance {
+    /// Probe the loader and every physical device.
+    pub fn report(&self) -> Result<Report, VulkanError> {
+        let loader_version =
+            unsafe { self.entry.try_enumerate_instance_version()? }.unwrap_or(vk::API_VERSION_1_0);
+        let required = self.profile.device_extensions();
+        let optional: Vec<&'static CStr> = OPTIONAL_DEVICE_EXTENSIONS
+            .iter()
+            .map(|(name, _)| *name)
+            .collect();
+        let devices = unsafe { self.raw.enumerate_physical_devices()? }
+            .into_iter()
+            .map(|physical_device| {
+                let mut driver_props = vk::PhysicalDeviceDriverProperties::default();
+                let mut props2 =
+                    vk::PhysicalDeviceProperties2::default().push_next(&mut driver_props);
+                unsafe {
+                    self.raw
+                        .get_physical_device_properties2(physical_device, &mut props2)
+                };
+                let props = props2.properties;
+                let lossy = |s: Result<&CStr, _>| {
+                    s.map(|s| s.to_string_lossy().into_owned())
+                        .unwrap_or_default()
+                };
+                let missing_optional = self.missing_extensions(physical_device, &optional);
+                DeviceReport {
+                    name: lossy(props.device_name_as_c_str()),
+                    device_type: props.device_type,
+                    api_version: props.api_version,
+                    driver: format!(
+                        "{} {}",
+                        lossy(driver_props.driver_name_as_c_str()),
+                        lossy(driver_props.driver_info_as_c_str())
+                    ),
+                    missing_features: self.missing_features(physical_device),
+                    missing_extensions: self.missing_extensions(physical_device, &required),
+                    optional: optional
+                        .iter()
+                        .map(|name| (*name, !missing_optional.contains(name)))
+                        .collect(),
+                }
+            })
+            .collect();
+        Ok(Report {
+            loader_version,
+            devices,
+        })
+    }
+}
+
+impl SupportedDevice {
+    /// Present modes the device offers for `surface`.
+    pub fn present_modes(
+        &self,
+        instance: &Instance,
+        surface: vk::SurfaceKHR,
+    ) -> Result<Vec<vk::PresentModeKHR>, VulkanError> {
+        if instance.is_headless() {
+            return Err(VulkanError::MissingExtension(
+                vk::KHR_SURFACE_NAME.to_string_lossy().into_owned(),
+            ));
+        }
+        let modes = unsafe {
+            instance
+                .surface_loader()
+                .get_physical_device_surface_present_modes(self.physical_device, surface)?
+        };
+        Ok(modes)
+    }
+}
+
+fn api_at_least_1_3(version: u32) -> bool {
+    let major = vk::api_version_major(version);
+    let minor = vk::api_version_minor(version);
+    major > 1 || (major == 1 && minor >= 3)
+}
+
+/// `major.minor.patch` of a Vulkan API version.
+pub fn api_version(version: u32) -> String {
+    format!(
+        "{}.{}.{}",
+        vk::api_version_major(version),
+        vk::api_version_minor(version),
+        vk::api_version_patch(version)
+    )
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "- loader: Vulkan {}", api_version(self.loader_version))?;
+        if self.devices.is_empty() {
+            writeln!(f, "- no physical devices")?;
+        }
+        for device in &self.devices {
+            let verdict = if device.usable() {
+                "usable"
+            } else {
+                "UNUSABLE"
+            };
+            writeln!(f, "- {} ({verdict})", device.name)?;
+            writeln!(f, "  - type: {:?}", device.device_type)?;
+            writeln!(f, "  - api: Vulkan {}", api_version(device.api_version))?;
+            writeln!(f, "  - driver: {}", device.driver.trim())?;
+            if let Some(tier) = device.tier() {
+                writeln!(f, "  - tier: {}", tier.name())?;
+            }
+            if !api_at_least_1_3(device.api_version) {
+                writeln!(f, "  - too old: Vulkan 1.3 is required")?;
+            }
+            for feature in &device.missing_features {
+                if FULL_FEATURES.contains(feature) {
+                    writeln!(f, "  - missing full tier feature: {feature}")?;
+                } else {
+                    writeln!(f, "  - missing required feature: {feature}")?;
+                }
+            }
+            for extension in &device.missing_extensions {
+                writeln!(
+                    f,
+                    "  - missing required extension: {}",
+                    extension.to_string_lossy()
+                )?;
+            }
+            for (extension, present) in &device.optional {
+                if *present {
+                    writeln!(f, "  - optional {}: present", extension.to_string_lossy())?;
+                } else {
+                    let consequence = OPTIONAL_DEVICE_EXTENSIONS
+                        .iter()
+                        .find(|(name, _)| name == extension)
+                        .map_or("", |(_, c)| c);
+                    writeln!(
+                        f,
+                        "  - optional {}: missing, {consequence}",
+                        extension.to_string_lossy()
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn report_agrees_with_supported() {
+        let instance = Instance::with_extensions(&[]).unwrap();
+        let report = instance.report().unwrap();
+        println!("{report}");
+        let usable = report.devices.iter().filter(|d| d.usable()).count();
+        assert_eq!(usable, instance.supported_devices(&[]).len());
+        instance.destroy();
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Device Selection
+//!
+//! [`Instance::supported_devices`] already sorts devices by a heuristic, discrete before integrated
+//! and then by memory, but the first device is not always the one the user wants.  A laptop may
+//! prefer its integrated GPU to save power, and a machine with two discrete GPUs has no right
+//! answer.  [`DeviceSelector`] picks from the supported devices:
+//!
+//! ```ignore
+//! let selected = DeviceSelector::new()
+//!     .prefer_discrete()
+//!     .with_env()
+//!     .select(instance.supported_devices(&[]))?;
+//! ```
+//!
+//! A name filter is a requirement.  A type is only a preference, falling back to the heuristic
+//! order when no device of that type is supported.
+//!
+//! ## `MUTATE_GPU`
+//!
+//! [`with_env`](DeviceSelector::with_env) lets users override selection without a frontend:
+//!
+//! - `discrete`, `integrated`, `virtual`, or `cpu` prefer that type of device.
+//! - A number picks that index of the supported devices, as [`Instance::enumerate_devices`] lists
+//!   them.
+//! - Anything else selects the first device whose name contains it, ignoring case.
+
+use ash::vk;
+
+use super::{Instance, SupportedDevice};
+use crate::VulkanError;
+
+/// Environment variable read by [`DeviceSelector::with_env`].
+pub const GPU_ENV: &str = "MUTATE_GPU";
+
+/// What a frontend needs to offer a choice of device.
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    /// Position in [`Instance::supported_devices`], which selection by index refers to.
+    pub index: usize,
+    pub name: String,
+    pub device_type: vk::PhysicalDeviceType,
+}
+
+impl DeviceInfo {
+    /// Short name of the device type, as `MUTATE_GPU` accepts it.
+    pub fn type_name(&self) -> &'static str {
+        type_name(self.device_type)
+    }
+}
+
+impl Instance {
+    /// Supported devices in preference order, without creating anything.
+    pub fn enumerate_devices(&self) -> Vec<DeviceInfo> {
+        self.supported_devices(&[])
+            .into_iter()
+            .enumerate()
+            .map(|(index, device)| DeviceInfo {
+                index,
+                name: device.name,
+                device_type: device.device_type,
+            })
+            .collect()
+    }
+}
+
+/// Chooses a device from [`Instance::supported_devices`].  See the [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct DeviceSelector {
+    name: Option<String>,
+    index: Option<usize>,
+    device_type: Option<vk::PhysicalDeviceType>,
+}
+
+impl DeviceSelector {
+    /// Select the first device in heuristic order.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn prefer_discrete(self) -> Self {
+        self.prefer_type(vk::PhysicalDeviceType::DISCRETE_GPU)
+    }
+
+    pub fn prefer_integrated(self) -> Self {
+        self.prefer_type(vk::PhysicalDeviceType::INTEGRATED_GPU)
+    }
+
+    /// Prefer devices of `device_type` when one is supported.
+    pub fn prefer_type(mut self, device_type: vk::PhysicalDeviceType) -> Self {
+        self.device_type = Some(device_type);
+        self
+    }
+
+    /// Require a device whose name contains `filter`, ignoring case.
+    pub fn with_name(mut self, filter: impl Into<String>) -> Self {
+        self.name = Some(filter.into());
+        self.index = None;
+        self
+    }
+
+    /// Require the supported device at `index`.
+    pub fn with_index(mut self, index: usize) -> Self {
+        self.index = Some(index);
+        self.name = None;
+        self
+    }
+
+    /// Apply a user's choice, a device type, index, or name as described in the
+    /// [module docs](self).  Replaces whatever it overlaps with.
+    pub fn with_choice(self, choice: &str) -> Self {
+        let choice = choice.trim();
+        if choice.is_empty() {
+            return self;
+        }
+        if let Some(device_type) = parse_type(choice) {
+            return Self {
+                device_type: Some(device_type),
+                ..self
+            };
+        }
+        match choice.parse::<usize>() {
+            Ok(index) => self.with_index(index),
+            Err(_) => self.with_name(choice),
+        }
+    }
+
+    /// Apply `MUTATE_GPU` if it is set.
+    pub fn with_env(self) -> Self {
+        match std::env::var(GPU_ENV) {
+            Ok(choice) => self.with_choice(&choice),
+            Err(_) => self,
+        }
+    }
+
+    /// Take the chosen device out of `devices`, which should be in the order
+    /// [`Instance::supported_devices`] returns.
+    pub fn select(
+        &self,
+        mut devices: Vec<SupportedDevice>,
+    ) -> Result<SupportedDevice, VulkanError> {
+        if devices.is_empty() {
+            return Err(VulkanError::NoSuitableDevice(
+                "no device meets the requirements".to_owned(),
+            ));
+        }
+        let candidates: Vec<(&str, vk::PhysicalDeviceType)> = devices
+            .iter()
+            .map(|d| (d.name.as_str(), d.device_type))
+            .collect();
+        let index = self.pick(&candidates)?;
+        Ok(devices.swap_remove(index))
+    }
+
+    fn pick(&self, devices: &[(&str, vk::PhysicalDeviceType)]) -> Result<usize, VulkanError> {
+        if let Some(index) = self.index {
+            return (index < devices.len()).then_some(index).ok_or_else(|| {
+                VulkanError::NoMatchingDevice(format!(
+                    "index {index} of {} supported devices",
+                    devices.len()
+                ))
+            });
+        }
+        let filter = self.name.as_ref().map(|n| n.to_lowercase());
+        let matching = devices.iter().enumerate().filter(|(_, (name, _))| {
+            filter
+                .as_ref()
+                .is_none_or(|f| name.to_lowercase().contains(f))
+        });
+        let mut first = None;
+        for (index, (_, device_type)) in matching {
+            if self.device_type.is_none_or(|t| t == *device_type) {
+                return Ok(index);
+            }
+            first.get_or_insert(index);
+        }
+        first.ok_or_else(|| {
+            VulkanError::NoMatchingDevice(match &self.name {
+                Some(name) => format!("name containing {name:?}"),
+                None => "any supported device".to_owned(),
+            })
+        })
+    }
+}
+
+fn parse_type(choice: &str) -> Option<vk::PhysicalDeviceType> {
+    match choice.to_lowercase().as_str() {
+        "discrete" => Some(vk::PhysicalDeviceType::DISCRETE_GPU),
+        "integrated" => Some(vk::PhysicalDeviceType::INTEGRATED_GPU),
+        "virtual" => Some(vk::PhysicalDeviceType::VIRTUAL_GPU),
+        "cpu" => Some(vk::PhysicalDeviceType::CPU),
+        _ => None,
+    }
+}
+
+fn type_name(device_type: vk::PhysicalDeviceType) -> &'static str {
+    match device_type {
+        vk::PhysicalDeviceType::DISCRETE_GPU => "discrete",
+        vk::PhysicalDeviceType::INTEGRATED_GPU => "integrated",
+        vk::PhysicalDeviceType::VIRTUAL_GPU => "virtual",
+        vk::PhysicalDeviceType::CPU => "cpu",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DISCRETE: vk::PhysicalDeviceType = vk::PhysicalDeviceType::DISCRETE_GPU;
+    const INTEGRATED: vk::PhysicalDeviceType = vk::PhysicalDeviceType::INTEGRATED_GPU;
+    const CPU: vk::PhysicalDeviceType = vk::PhysicalDeviceType::CPU;
+
+    // Heuristic order, as supported_devices sorts them.
+    const DEVICES: &[(&str, vk::PhysicalDeviceType)] = &[
+        ("NVIDIA GeForce RTX 4070", DISCRETE),
+        ("AMD Radeon 780M", INTEGRATED),
+        ("llvmpipe (LLVM 19.1.7, 256 bits)", CPU),
+    ];
+
+    #[test]
+    fn test_preferences() {
+        assert_eq!(DeviceSelector::new().pick(DEVICES).unwrap(), 0);
+        assert_eq!(
+            DeviceSelector::new()
+                .prefer_integrated()
+                .pick(DEVICES)
+                .unwrap(),
+            1
+        );
+        // Preferences fall back to heuristic order.
+        let virtual_gpu = DeviceSelector::new().prefer_type(vk::PhysicalDeviceType::VIRTUAL_GPU);
+        assert_eq!(virtual_gpu.pick(DEVICES).unwrap(), 0);
+        assert!(DeviceSelector::new().pick(&[]).is_err());
+    }
+
+    #[test]
+    fn test_filters() {
+        let radeon = DeviceSelector::new().prefer_discrete().with_name("radeon");
+        assert_eq!(radeon.pick(DEVICES).unwrap(), 1);
+        let missing = DeviceSelector::new().with_name("intel");
+        assert!(matches!(
+            missing.pick(DEVICES),
+            Err(VulkanError::NoMatchingDevice(_))
+        ));
+        assert_eq!(
+            DeviceSelector::new().with_index(2).pick(DEVICES).unwrap(),
+            2
+        );
+        assert!(DeviceSelector::new().with_index(3).pick(DEVICES).is_err());
+    }
+
+    #[test]
+    fn test_choices() {
+        let choose = |choice: &str| {
+            DeviceSelector::new()
+                .prefer_discrete()
+                .with_choice(choice)
+                .pick(DEVICES)
+                .unwrap()
+        };
+        assert_eq!(choose("cpu"), 2);
+        assert_eq!(choose("Integrated"), 1);
+        assert_eq!(choose("1"), 1);
+        assert_eq!(choose("llvmpipe"), 2);
+        assert_eq!(choose("  "), 0);
+        // A name replaces an index and the reverse.
+        let selector = DeviceSelector::new().with_choice("2").with_choice("nvidia");
+        assert_eq!(selector.pick(DEVICES).unwrap(), 0);
+    }
+}
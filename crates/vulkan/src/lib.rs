@@ -67,6 +67,7 @@
 //! - Resources
 //!   + Image
 //!     * Sampler
+//!     * Offscreen Target
 //!   + Buffer
 //!   + UBO
 //!   + Shader Modules
@@ -166,6 +167,9 @@ pub mod prelude {
     pub use crate::present::prelude::*;
     pub use crate::present::surface::Surface;
     pub use crate::resource::buffer::{MappedAllocation, MappedWriteView};
+    pub use crate::resource::staging::{BufferSlice, StagingRing};
+    pub use crate::resource::target::OffscreenTarget;
+    pub use crate::resource::transient::{TransientPool, TransientRange};
     pub use crate::slang::prelude::*;
     pub use crate::slang_newtype;
 
@@ -195,6 +199,8 @@ pub(crate) mod internal {
 // Flattened paths and re-exports for use in proc macro expansions
 #[doc(hidden)]
 pub mod __ {
+    pub use crate::device::capabilities::Tier;
+    pub use crate::device::descriptors as descriptor_slots;
     pub use crate::pipeline::stage as stage_slots;
     pub use crate::pipeline::stage::{Stage, StageReflection, StageSlot, StageSpec};
     pub use crate::pipeline::{layout::LayoutSpec, push::PushConstants, ComputePipelineSpec};
@@ -207,10 +213,10 @@ pub mod __ {
 
 #[derive(thiserror::Error, Debug)]
 pub enum VulkanError {
-    #[error("vulkan: {0}")]
-    // DEBT Use this only to begin returning results.  Use something else to actually start handling
-    // them.
-    ReplaceMe(&'static str),
+    /// A per-frame resource such as the staging ring was used outside of a frame.  This is a bug in
+    /// the caller.
+    #[error("{0}: called before begin_frame")]
+    NotInFrame(&'static str),
 
     #[error("thread poisoned")]
     Poisoned,
@@ -218,6 +224,10 @@ pub enum VulkanError {
     #[error("io: {0}")]
     Io(#[from] std::io::Error),
 
+    /// An asset such as a shader could not be found or loaded.
+    #[error("asset: {0}")]
+    Asset(#[from] mutate_assets::AssetError),
+
     /// Attempted to acquire on a swapchain that is already marked for recreation needed.
     #[error("vulkan: Swapchain recreation needed.")]
     SwapchainRecreationRequired,
@@ -235,7 +245,7 @@ pub enum VulkanError {
     SurfaceLost,
     #[error("vulkan: Fullscreen exclusive mode lost")]
     FullscreenExclusiveLost,
-    #[error("vuklan: Device lost")]
+    #[error("vulkan: Device lost")]
     DeviceLost,
     #[error("vulkan: Out of memory (host)")]
     OutOfHostMemory,
@@ -261,6 +271,32 @@ pub enum VulkanError {
     /// capability on the surface.
     #[error("queue: no queue family with requested capabilities found")]
     QueueNotFound,
+    /// The Vulkan loader library could not be loaded, usually because no driver is installed.
+    #[error("vulkan: loader unavailable: {0}")]
+    LoaderUnavailable(String),
+    /// The loader or device supports an older Vulkan than the 1.3 we require.
+    #[error("vulkan: version {0} is older than the required 1.3")]
+    UnsupportedVersion(String),
+    /// A required instance or device extension is not available.
+    #[error("vulkan: missing required extension {0}")]
+    MissingExtension(String),
+    /// The device lacks a required feature.
+    #[error("vulkan: missing required feature {0}")]
+    MissingFeature(String),
+    /// No device meets our requirements, or none that can present to the surface.
+    #[error("vulkan: no suitable device: {0}")]
+    NoSuitableDevice(String),
+    /// No supported device matched the selection, such as a `MUTATE_GPU` naming a missing device.
+    #[error("device: no supported device matches {0}")]
+    NoMatchingDevice(String),
+    /// A shader needs a higher [`Tier`](device::capabilities::Tier) than the device has and has no
+    /// Core variant.
+    #[error("vulkan: shader {shader} needs the {} tier but the device is {}", required.name(), available.name())]
+    MissingTier {
+        shader: String,
+        required: device::capabilities::Tier,
+        available: device::capabilities::Tier,
+    },
 
     /// Polling the window and compositor could not decide a useable swapchain size, and the correct
     /// behavior is to request redraw and wait for another event.
@@ -272,6 +308,23 @@ pub enum VulkanError {
     // We inferred the minimized state from a zero inner pixel size.
     #[error("surface: window seems minimized")]
     WindowMinimized,
+    /// A present mode choice that names none of the
+    /// [`PresentPreference`](present::surface::PresentPreference) variants.
+    #[error("surface: unknown present mode `{0}`")]
+    UnknownPresentMode(String),
+
+    /// A frame asked for more transient scratch memory than the pool was created with.  Raise the
+    /// pool capacity.
+    #[error("transient: requested {requested} bytes from a {capacity} byte pool")]
+    TransientExhausted { requested: u64, capacity: u64 },
+
+    /// A frame uploaded more than the staging ring was created with.  Raise the ring capacity.
+    #[error("staging: requested {requested} bytes from a {capacity} byte ring")]
+    StagingExhausted { requested: u64, capacity: u64 },
+
+    /// Every slot of a descriptor array is registered.  Release handles that are no longer used.
+    #[error("descriptors: all {capacity} slots of binding {binding} are registered")]
+    DescriptorsExhausted { binding: u32, capacity: u32 },
 }
 
 impl<T> From<std::sync::PoisonError<T>> for VulkanError {
@@ -280,6 +333,28 @@ impl<T> From<std::sync::PoisonError<T>> for VulkanError {
     }
 }
 
+impl VulkanError {
+    /// Whether the swapchain must be recreated, such as after a resize, before presenting again.
+    pub fn needs_swapchain_recreation(&self) -> bool {
+        matches!(
+            self,
+            Self::SwapchainOutOfDate | Self::SwapchainSuboptimal | Self::SwapchainRecreationRequired
+        )
+    }
+
+    /// Whether the device is gone.  Everything created from it must be destroyed and the device
+    /// created again, which may select a different physical device.
+    pub fn is_device_lost(&self) -> bool {
+        matches!(self, Self::DeviceLost)
+    }
+
+    /// Whether host or device memory ran out.  Freeing resources, such as dropping to smaller
+    /// targets, may let a retry succeed.
+    pub fn is_out_of_memory(&self) -> bool {
+        matches!(self, Self::OutOfHostMemory | Self::OutOfDeviceMemory)
+    }
+}
+
 impl From<vk::Result> for VulkanError {
     fn from(r: vk::Result) -> Self {
         match r {
@@ -0,0 +1,149 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Pipeline Cache
+//!
+//! Drivers compile SPIR-V to machine code when a pipeline is created, which is most of the startup
+//! time once graphs have many nodes.  A pipeline cache lets the driver skip compiling what it has
+//! compiled before.  Every [`Device`] owns one, loaded when the device is created and saved when it
+//! is destroyed.
+//!
+//! The cache lives in `MUTATE_PIPELINE_CACHE` when set and in `mutate/pipelines` under the user
+//! cache directory otherwise.  Setting `MUTATE_PIPELINE_CACHE` empty disables persistence.  Files
+//! are named by the device's pipeline cache UUID, vendor, device, and driver version, so a driver
+//! update or another GPU starts a fresh cache instead of handing the driver a stale one.  Drivers
+//! check the data too, and a cache the driver rejects is discarded with a warning.
+
+use std::path::{Path, PathBuf};
+
+use crate::internal::*;
+
+/// Environment variable overriding where caches are saved.
+pub const PIPELINE_CACHE_ENV: &str = "MUTATE_PIPELINE_CACHE";
+
+/// Where pipeline caches are saved, if anywhere.
+pub fn cache_dir() -> Option<PathBuf> {
+    match std::env::var_os(PIPELINE_CACHE_ENV) {
+        Some(dir) if dir.is_empty() => None,
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => dirs::cache_dir().map(|d| d.join("mutate").join("pipelines")),
+    }
+}
+
+/// Name of the cache file for a device.
+pub fn file_name(props: &vk::PhysicalDeviceProperties) -> String {
+    let uuid: String = props
+        .pipeline_cache_uuid
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!(
+        "{uuid}-{:04x}-{:04x}-{:08x}.bin",
+        props.vendor_id, props.device_id, props.driver_version
+    )
+}
+
+/// A device's pipeline cache and where it is saved.  See the [module docs](self).
+pub struct PipelineCache {
+    raw: vk::PipelineCache,
+    path: Option<PathBuf>,
+}
+
+impl PipelineCache {
+    /// Load the cache for the device with `props` from `dir`, or start an empty one.
+    pub(crate) fn load(
+        device: &ash::Device,
+        props: &vk::PhysicalDeviceProperties,
+        dir: Option<&Path>,
+    ) -> Result<Self, VulkanError> {
+        let path = dir.map(|d| d.join(file_name(props)));
+        let data = path
+            .as_deref()
+            .and_then(|p| std::fs::read(p).ok())
+            .unwrap_or_default();
+        let create = |data: &[u8]| {
+            let ci = vk::PipelineCacheCreateInfo::default().initial_data(data);
+            unsafe { device.create_pipeline_cache(&ci, None) }
+        };
+        let raw = match create(&data) {
+            Ok(raw) => raw,
+            Err(e) if !data.is_empty() => {
+                log::warn!("discarding pipeline cache {path:?}: {e}");
+                create(&[])?
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { raw, path })
+    }
+
+    pub fn as_raw(&self) -> vk::PipelineCache {
+        self.raw
+    }
+
+    /// Where the cache is saved, if anywhere.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Write the cache to its file.  Does nothing when persistence is disabled.
+    pub fn save(&self, device: &ash::Device) -> Result<(), VulkanError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let data = unsafe { device.get_pipeline_cache_data(self.raw)? };
+        // Written aside and renamed so that another process never loads half a cache.
+        let partial = path.with_extension(format!("{}.partial", std::process::id()));
+        path.parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&partial, data))
+            .and_then(|_| std::fs::rename(&partial, path))?;
+        Ok(())
+    }
+
+    pub(crate) fn destroy(&self, device: &ash::Device) {
+        unsafe { device.destroy_pipeline_cache(self.raw, None) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        let mut props = vk::PhysicalDeviceProperties {
+            vendor_id: 0x10de,
+            device_id: 0x2786,
+            driver_version: 0x8a9c_0000,
+            ..Default::default()
+        };
+        props.pipeline_cache_uuid[0] = 0xab;
+        let name = file_name(&props);
+        assert_eq!(
+            name,
+            format!("ab{}-10de-2786-8a9c0000.bin", "00".repeat(15))
+        );
+        props.driver_version += 1;
+        assert_ne!(file_name(&props), name);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        with_context!(|device, instance| {
+            let dir = std::env::temp_dir().join(format!("mutate-pso-{}", std::process::id()));
+            let props = unsafe {
+                instance
+                    .raw
+                    .get_physical_device_properties(device.physical_device)
+            };
+            let cache = PipelineCache::load(device.as_raw(), &props, Some(&dir)).unwrap();
+            cache.save(device.as_raw()).unwrap();
+            assert!(cache.path().unwrap().exists());
+            cache.destroy(device.as_raw());
+            // A cache the driver wrote loads back.
+            let cache = PipelineCache::load(device.as_raw(), &props, Some(&dir)).unwrap();
+            cache.destroy(device.as_raw());
+            std::fs::remove_dir_all(dir).unwrap();
+        });
+    }
+}
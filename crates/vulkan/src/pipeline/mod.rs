@@ -97,6 +97,7 @@ use std::marker::PhantomData;
 use crate::internal::*;
 use crate::resource::shader;
 
+pub mod cache;
 pub mod layout;
 pub mod push;
 pub mod stage;
@@ -144,6 +145,9 @@ pub trait ComputePipelineSpec {
     type LayoutSpec: layout::LayoutSpec<Push = Self::Push>;
     /// The compute stage.  Slot is statically fixed to [`stage::Compute`].
     type Stage: stage::Stage<stage::Compute>;
+    /// Feature tier the shader needs.  Devices below it load the shader's Core variant instead.
+    /// See [`capabilities`](crate::device::capabilities).
+    const TIER: Tier = Tier::Core;
 }
 
 /// Hydrated compute pipeline ready to dispatch.  Retains a type-level connection with the spec to
@@ -159,11 +163,51 @@ impl<S: ComputePipelineSpec> ComputePipeline<S> {
     pub fn new(device: &Device) -> Result<Self, VulkanError> {
         // Compute ranges only have one stage and only need one range.
         let layout = layout::Layout::<S::LayoutSpec>::new(device)?;
+        let pipeline = Self::create(device, &layout)?;
+        Ok(Self {
+            pipeline,
+            layout,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Shader program the compute stage loads, for matching against changed assets.
+    pub fn shader_name(&self) -> &'static str {
+        <S::Stage as stage::Stage<stage::Compute>>::SPEC.name
+    }
 
+    /// Load the shader again and swap in a new pipeline over the same layout.  Returns the replaced
+    /// pipeline, which command buffers still in flight may use.  Destroy it with
+    /// [`destroy_replaced`](Self::destroy_replaced) once they retire.  On failure, such as a shader
+    /// that no longer compiles into a pipeline, the current pipeline is kept.
+    pub fn rebuild(&mut self, device: &Device) -> Result<vk::Pipeline, VulkanError> {
+        let pipeline = Self::create(device, &self.layout)?;
+        Ok(std::mem::replace(&mut self.pipeline, pipeline))
+    }
+
+    /// Destroy a pipeline returned by [`rebuild`](Self::rebuild).
+    ///
+    /// # Safety
+    ///
+    /// No pending command buffer may use `replaced`.
+    pub unsafe fn destroy_replaced(device: &Device, replaced: vk::Pipeline) {
+        unsafe { device.as_raw().destroy_pipeline(replaced, None) }
+    }
+
+    fn create(
+        device: &Device,
+        layout: &layout::Layout<S::LayoutSpec>,
+    ) -> Result<vk::Pipeline, VulkanError> {
         // NOTE the shader module is still just half-baked fumbling in the dark at the shape of the
         // async loading code.  Not going to live long.
         let stage_spec = <S::Stage as stage::Stage<stage::Compute>>::SPEC;
-        let shader = shader::ShaderModule::load(device, stage_spec.name)?;
+        // NOTE a variant is read twice, once to find it.  Only Core devices pay.
+        let name = device
+            .capabilities()
+            .shader_name(stage_spec.name, S::TIER, |variant| {
+                device.assets.find_shader(variant).is_ok()
+            })?;
+        let shader = shader::ShaderModule::load(device, &name)?;
 
         let stage = vk::PipelineShaderStageCreateInfo::default()
             .stage(vk::ShaderStageFlags::COMPUTE)
@@ -177,14 +221,10 @@ impl<S: ComputePipelineSpec> ComputePipeline<S> {
         let pipeline = unsafe {
             device
                 .as_raw()
-                .create_compute_pipelines(vk::PipelineCache::null(), &[pipeline_ci], None)
+                .create_compute_pipelines(device.pipeline_cache(), &[pipeline_ci], None)
                 .map_err(|huh| huh.1)?[0] // XXX 🤠
         };
-        Ok(Self {
-            pipeline,
-            layout,
-            _marker: PhantomData,
-        })
+        Ok(pipeline)
     }
 
     // XXX typed recording slot
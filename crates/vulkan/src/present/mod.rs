@@ -12,23 +12,31 @@
 // NEXT create kinds of targets that may only use specific layouts that are valid for the upstream
 // render target.
 
+pub mod msaa;
+pub mod stats;
 pub mod surface;
 pub mod swapchain;
 
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 use std::slice;
+use std::time::{Duration, Instant};
 
 use ash::vk::Handle;
 
 use crate::dispatch::pw;
+use crate::dispatch::timer::GpuTimer;
 use crate::internal::*;
+use crate::present::stats::{PresentSample, PresentStats};
 use crate::present::surface::ExtentSource;
 use crate::resource::image;
 
 pub mod prelude {
     pub use super::compute_present;
     pub use super::graphics_present;
-    pub use super::surface::Surface;
+    pub use super::msaa::MsaaTarget;
+    pub use super::stats::PresentStats;
+    pub use super::surface::{PresentPreference, Surface};
     pub use super::swapchain::{AcquiredImage, Swapchain};
     pub use super::PresentRing;
 }
@@ -38,8 +46,16 @@ pub struct PresentRing {
     present: pw::PresentConsumer,
     queue: QueueRef<Graphics>,
     swapchain: Swapchain,
+    /// When recent present IDs were queued, to measure present latency.
+    queued: VecDeque<(u64, Instant)>,
+    stats: PresentStats,
+    /// `None` when the queue cannot write timestamps.
+    timer: Option<GpuTimer>,
 }
 
+/// Queue times kept for matching against present wait.  The waiter trails by a frame or two.
+const QUEUED_HISTORY: usize = 8;
+
 impl PresentRing {
     pub fn new(
         device: &Device,
@@ -55,14 +71,59 @@ impl PresentRing {
             .queue_ref();
         let pool_ring = PoolRing::new(device, &queue)?;
         let present = pw::PresentConsumer::new(instance, device, *swapchain.as_raw())?;
+        let timer = match GpuTimer::new(device, instance, queue.family()) {
+            Ok(timer) => Some(timer),
+            Err(e) => {
+                log::warn!("present: frames will not be timed on the GPU: {e}");
+                None
+            }
+        };
         Ok(Self {
             present,
             pool_ring,
             queue,
             swapchain,
+            queued: VecDeque::with_capacity(QUEUED_HISTORY),
+            stats: PresentStats::default(),
+            timer,
         })
     }
 
+    /// Present ID and the time from queueing it until present wait returned for it, for the most
+    /// recently observed present.  `None` until present wait has caught two consecutive presents.
+    pub fn present_latency(&self) -> Option<(u64, Duration)> {
+        let last = self.present.read_last_present()?;
+        let (id, queued_at) = self.queued.iter().find(|(id, _)| *id == last.last_id)?;
+        Some((*id, last.last_present.saturating_duration_since(*queued_at)))
+    }
+
+    /// Latency, interval, and GPU time statistics of recent frames, updated as frames are recorded.
+    pub fn stats(&self) -> &PresentStats {
+        &self.stats
+    }
+
+    /// Present ID of the most recently recorded frame, to match against [`PresentStats::last`].
+    pub fn queued_id(&self) -> Option<u64> {
+        self.queued.back().map(|(id, _)| *id)
+    }
+
+    /// Record the latest present that present wait caught, if it is new.
+    fn observe_present(&mut self) {
+        let Some(last) = self.present.read_last_present() else {
+            return;
+        };
+        let Some((_, queued_at)) = self.queued.iter().find(|(id, _)| *id == last.last_id) else {
+            return;
+        };
+        let sample = PresentSample {
+            id: last.last_id,
+            presented: last.last_present,
+            latency: last.last_present.saturating_duration_since(*queued_at),
+        };
+        // Present wait only reports presents it caught after the one before.
+        self.stats.observe(sample, Some(last.last_window));
+    }
+
     /// Draw with a user-supplied recording function.
     ///
     /// **Contract**: `record_fn` receives a started command buffer and the acquired image.
@@ -79,21 +140,29 @@ impl PresentRing {
         F: FnOnce(&Device, &RecordingBuffer<Graphics, OneTime>, &AcquiredImage),
         G: FnOnce(),
     {
-        // let recorded = self.present.read_last_present();
-        // if let Some(time) = recorded {
-        //     let duration = time.last_present - self.last;
-        //     self.last = time.last_present;
-        //     println!("present-to-present: {:?}", duration.as_micros());
-        // }
         if self.swapchain.recreation_required() {
             return Err(VulkanError::SwapchainRecreationRequired);
         }
         let acquired_image = self.swapchain.acquire()?;
 
-        let (pool, intent) = self.pool_ring.acquire(device, 1_000_000_000).unwrap();
-        let cb = pool.primary(device).unwrap();
+        // Errors from here on are usually a lost device, which the caller must rebuild from scratch.
+        let (pool, intent) = self.pool_ring.acquire(device, 1_000_000_000)?;
+        let cb = pool.primary(device)?;
+        if let Some(timer) = &mut self.timer {
+            // The pool ring waited on the frame that last used the timer's slot.
+            if let Some(elapsed) = timer.begin(device, *cb) {
+                self.stats.observe_gpu(elapsed);
+            }
+        }
         record_fn(device, &cb, &acquired_image);
-        let recorded = cb.end(device).unwrap();
+        if let Some(timer) = &mut self.timer {
+            timer.end(device, *cb);
+        }
+        let recorded = cb.end(device)?;
+        // Descriptors registered while recording must be written before the work is submitted.
+        device
+            .descriptors
+            .flush_signaling(device.as_raw(), &intent.wait_value());
         self.queue
             .submission()
             .wait_binary(
@@ -106,8 +175,7 @@ impl PresentRing {
                 vk::PipelineStageFlags2::ALL_GRAPHICS,
             )
             .signal(intent, vk::PipelineStageFlags2::ALL_COMMANDS)
-            .submit(device, vk::Fence::null())
-            .unwrap();
+            .submit(device, vk::Fence::null())?;
         // MAYBE this little line only exists for the sole purpose of enabling `pre_present_notify`
         // on the winit `Window`, which is said to be only for Wayland.  Calling it from a threaded
         // render loop may call back into the main thread of the application on some platforms.  The
@@ -115,7 +183,7 @@ impl PresentRing {
         // on Wayland after we add VRR and FRR phase tracking.
         post_draw_fn();
 
-        if let Some(_last) = self.present.read_last_present() {}
+        self.observe_present();
         let next_id = self.present.next_present_id();
         let mut present_id = vk::PresentIdKHR::default().present_ids(slice::from_ref(&next_id));
         let present_ready = acquired_image.present_ready.as_raw();
@@ -137,6 +205,10 @@ impl PresentRing {
         // ROLL VK_KHR_present_wait will enable "display this no sooner than X".
         // For FRR, we just need to hit the deadline.  For VRR and using a user-configured maximum
         // frame rate, aligning the call to present with our chosen cadence is the correct knob.
+        if self.queued.len() == QUEUED_HISTORY {
+            self.queued.pop_front();
+        }
+        self.queued.push_back((next_id, Instant::now()));
         let present_result = self.swapchain.present(
             unsafe { self.queue.as_raw() },
             acquired_image.sync_index,
@@ -151,36 +223,46 @@ impl PresentRing {
                 // and let them skip acquisition until the event propertly comes through.
                 Err(VulkanError::SwapchainRecreationRequired)
             }
-            Err(e) => {
-                eprintln!("presentation: unknown error: {:?}", e);
-                Err(VulkanError::ReplaceMe("idk man.  it's broke"))
-            }
+            // Suboptimal and lost devices or surfaces go to the caller, who can recover from them.
+            Err(e) => Err(e),
         };
     }
 
+    /// Recreate the swapchain for the surface's new size and wait up to 100ms for frames in flight,
+    /// so that the caller may destroy and re-provision resources sized to the old one at once.
     pub fn maybe_update_swapchain<'a>(
         &mut self,
         device: &Device,
         surface: &mut Surface,
         extent_source: impl Into<ExtentSource<'a>>,
     ) -> Result<vk::Extent2D, VulkanError> {
-        // XXX Check if surface actually needs recreation!
-        let new_size = surface.update(device, extent_source)?;
         // DEBT Up to 100ms drain on the pool is enough to allow in-flight CBs to retire and allow
-        // naive asset re-provision.  If we had our asset system a bit more mature, this would be
-        // unnecessary.
+        // naive asset re-provision.  Callers with a deletion queue use `update_swapchain`.
         self.pool_ring.drain(device, 100_000_000)?;
-        self.swapchain.recreate(device, surface);
+        self.update_swapchain(device, surface, extent_source)
+    }
+
+    /// Recreate the swapchain for the surface's new size without waiting.  The old swapchain
+    /// retires on its own, but resources sized to it are still in use by frames in flight.  Defer
+    /// their destruction, such as on a `DeletionQueue`, until those frames retire.
+    pub fn update_swapchain<'a>(
+        &mut self,
+        device: &Device,
+        surface: &mut Surface,
+        extent_source: impl Into<ExtentSource<'a>>,
+    ) -> Result<vk::Extent2D, VulkanError> {
+        // XXX Check if surface actually needs recreation!
+        let new_size = surface.update(device, extent_source)?;
+        self.swapchain.recreate(device, surface)?;
         self.present
             .notify_swapchain_recreation(*self.swapchain.as_raw());
         Ok(new_size)
     }
 
-    /// Wait on the swapchain.  `timeout` is nanoseconds.  See `Swapchain` drain for return value
-    /// semantics.
-    // DEBT resources deletion queue to lazily get rid of things.  These drains are propagating all
-    // over the place.
-    pub fn drain(self, device: &Device) -> Result<bool, VulkanError> {
+    /// Wait for frames in flight and then their presents, up to a second each.  See `Swapchain`
+    /// drain for return value semantics.  Waits on the ring only, so other windows keep drawing.
+    pub fn drain(&self, device: &Device) -> Result<bool, VulkanError> {
+        self.pool_ring.drain(device, 1_000_000_000)?;
         self.swapchain.drain(device, 1_000_000_000)
     }
 
@@ -189,8 +271,12 @@ impl PresentRing {
         let Self {
             swapchain,
             pool_ring,
+            timer,
             ..
         } = self;
+        if let Some(timer) = timer {
+            timer.destroy(device);
+        }
         swapchain.destroy(device);
         pool_ring.destroy(device);
     }
@@ -0,0 +1,113 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # MSAA
+//!
+//! Thin lines, such as a spectrogram's, alias badly at one sample per pixel.  An [`MsaaTarget`] is a
+//! multisampled color image the size of the swapchain.  Draws go into it and dynamic rendering
+//! resolves it into the acquired image at the end of the pass, so presentation does not change.  At
+//! one sample the target holds no image and draws go straight to the acquired image.
+//!
+//! Pipelines that draw into a target must be created with its [`samples`](MsaaTarget::samples).
+//! The count is [`Capabilities::samples`], so a count the device lacks rounds down.
+
+// MAYBE lazily allocated memory on tilers, where the samples never leave tile memory.
+
+use crate::internal::*;
+use crate::resource::image::{self, Image, ImageView};
+
+/// A multisampled color target resolving into swapchain images.  See the [module docs](self).
+pub struct MsaaTarget {
+    samples: vk::SampleCountFlags,
+    color: Option<(Image, ImageView)>,
+}
+
+impl MsaaTarget {
+    /// A target of `extent` and `format`, which should match the swapchain, with up to `requested`
+    /// samples per pixel.
+    pub fn new(
+        device: &Device,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        requested: u32,
+    ) -> Result<Self, VulkanError> {
+        let samples = device.capabilities().samples(requested);
+        if samples == vk::SampleCountFlags::TYPE_1 {
+            return Ok(Self {
+                samples,
+                color: None,
+            });
+        }
+        let usage =
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT;
+        let color = Image::multisampled(device, extent, format, usage, samples)?;
+        color.set_name(device, "msaa color");
+        let view = match color.default_view(device) {
+            Ok(view) => view,
+            Err(e) => {
+                let _ = color.destroy(device);
+                return Err(e);
+            }
+        };
+        Ok(Self {
+            samples,
+            color: Some((color, view)),
+        })
+    }
+
+    pub fn samples(&self) -> vk::SampleCountFlags {
+        self.samples
+    }
+
+    /// Whether draws resolve from a multisampled image.
+    pub fn is_multisampled(&self) -> bool {
+        self.color.is_some()
+    }
+
+    /// Ready the multisampled image for drawing.  Record before beginning rendering.  Its previous
+    /// contents are discarded.
+    pub fn prepare(&self, device: &Device, cb: vk::CommandBuffer) {
+        if let Some((color, _)) = &self.color {
+            color.transition_layout(
+                cb,
+                image::range(),
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                device,
+            );
+        }
+    }
+
+    /// The color attachment drawing into `acquired`, cleared to black.  Override the load op or
+    /// clear value on the returned info as needed.
+    pub fn color_attachment(
+        &self,
+        acquired: &AcquiredImage,
+    ) -> vk::RenderingAttachmentInfo<'static> {
+        let attachment = vk::RenderingAttachmentInfo::default()
+            .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .clear_value(vk::ClearValue::default());
+        match &self.color {
+            // The samples are only needed until they are resolved.
+            Some((_, view)) => attachment
+                .image_view(view.view)
+                .store_op(vk::AttachmentStoreOp::DONT_CARE)
+                .resolve_mode(vk::ResolveModeFlags::AVERAGE)
+                .resolve_image_view(acquired.image_view)
+                .resolve_image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL),
+            None => attachment
+                .image_view(acquired.image_view)
+                .store_op(vk::AttachmentStoreOp::STORE),
+        }
+    }
+
+    /// Caller must drain work that drew into the target first.
+    pub fn destroy(self, device: &Device) -> Result<(), VulkanError> {
+        if let Some((color, view)) = self.color {
+            view.destroy(device)?;
+            color.destroy(device)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Present Statistics
+//!
+//! Present wait reports when each present reached the display.  [`PresentStats`] keeps a window of
+//! recent observations of three durations:
+//!
+//! - **latency**: from queueing a present until it reached the display.  Frame pacing plans around
+//!   this, so a frame is submitted no earlier than it must be.
+//! - **interval**: between consecutive presents.  On a fixed refresh display these are multiples of
+//!   the refresh period.  On a variable refresh display they follow the frames.
+//! - **gpu**: from the start to the end of each frame's command buffer, measured with a
+//!   [`GpuTimer`](crate::dispatch::timer::GpuTimer).  Empty when the queue cannot write
+//!   timestamps.
+//!
+//! Present wait returns on its own thread, so latency and interval include some scheduler jitter.
+//! Prefer quantiles over single samples.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Observations kept.  About two seconds at 60Hz.
+pub const HISTORY: usize = 120;
+
+/// One present as seen by present wait.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PresentSample {
+    pub id: u64,
+    /// When present wait returned for it.
+    pub presented: Instant,
+    /// From queueing it until `presented`.
+    pub latency: Duration,
+}
+
+/// The most recent [`HISTORY`] values of a duration.
+#[derive(Clone, Debug, Default)]
+pub struct DurationWindow {
+    samples: VecDeque<Duration>,
+}
+
+impl DurationWindow {
+    /// Add a sample, forgetting the oldest once full.
+    pub fn push(&mut self, sample: Duration) {
+        if self.samples.len() == HISTORY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn last(&self) -> Option<Duration> {
+        self.samples.back().copied()
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.samples.iter().min().copied()
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.samples.iter().max().copied()
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        let total: Duration = self.samples.iter().sum();
+        (!self.is_empty()).then(|| total / self.len() as u32)
+    }
+
+    /// The value `q` of the way through the sorted samples, from 0.0 for the minimum to 1.0 for the
+    /// maximum.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let i = ((sorted.len() - 1) as f64 * q.clamp(0.0, 1.0)).round() as usize;
+        Some(sorted[i])
+    }
+}
+
+/// Recent present latencies and intervals.  See the [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct PresentStats {
+    latency: DurationWindow,
+    interval: DurationWindow,
+    gpu: DurationWindow,
+    last: Option<PresentSample>,
+    observed: u64,
+}
+
+impl PresentStats {
+    /// Record a present.  `interval` is the time since the previous present when present wait
+    /// caught both.  Returns false for a present already recorded.
+    pub(crate) fn observe(&mut self, sample: PresentSample, interval: Option<Duration>) -> bool {
+        if self.last.is_some_and(|last| last.id >= sample.id) {
+            return false;
+        }
+        self.latency.push(sample.latency);
+        if let Some(interval) = interval {
+            self.interval.push(interval);
+        }
+        self.last = Some(sample);
+        self.observed += 1;
+        true
+    }
+
+    pub(crate) fn observe_gpu(&mut self, elapsed: Duration) {
+        self.gpu.push(elapsed);
+    }
+
+    /// The most recently observed present.
+    pub fn last(&self) -> Option<PresentSample> {
+        self.last
+    }
+
+    pub fn latency(&self) -> &DurationWindow {
+        &self.latency
+    }
+
+    pub fn interval(&self) -> &DurationWindow {
+        &self.interval
+    }
+
+    pub fn gpu(&self) -> &DurationWindow {
+        &self.gpu
+    }
+
+    /// Presents observed, including those no longer in the history.
+    pub fn observed(&self) -> u64 {
+        self.observed
+    }
+}
+
+impl std::fmt::Display for PresentStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |d: Option<Duration>| d.unwrap_or_default().as_secs_f64() * 1e3;
+        if self.latency.is_empty() {
+            return f.write_str("no presents observed");
+        }
+        write!(
+            f,
+            "latency median {:.2}ms, p95 {:.2}ms, max {:.2}ms; interval median {:.2}ms over {} presents",
+            ms(self.latency.quantile(0.5)),
+            ms(self.latency.quantile(0.95)),
+            ms(self.latency.max()),
+            ms(self.interval.quantile(0.5)),
+            self.latency.len()
+        )?;
+        if !self.gpu.is_empty() {
+            write!(f, "; gpu median {:.2}ms", ms(self.gpu.quantile(0.5)))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_present_stats() {
+        let start = Instant::now();
+        let mut stats = PresentStats::default();
+        assert!(stats.latency().quantile(0.5).is_none());
+        for id in 1..=10u64 {
+            let sample = PresentSample {
+                id,
+                presented: start + Duration::from_millis(16 * id),
+                latency: Duration::from_millis(id),
+            };
+            let interval = (id > 1).then_some(Duration::from_millis(16));
+            assert!(stats.observe(sample, interval));
+            // Present wait trails, so the same present is read again.
+            assert!(!stats.observe(sample, interval));
+        }
+        assert_eq!(stats.observed(), 10);
+        assert_eq!(stats.last().unwrap().id, 10);
+        let latency = stats.latency();
+        assert_eq!(latency.min(), Some(Duration::from_millis(1)));
+        assert_eq!(latency.max(), Some(Duration::from_millis(10)));
+        assert_eq!(latency.mean(), Some(Duration::from_micros(5500)));
+        assert_eq!(latency.quantile(0.0), latency.min());
+        assert_eq!(latency.quantile(1.0), latency.max());
+        assert_eq!(stats.interval().len(), 9);
+    }
+
+    #[test]
+    fn test_history_bound() {
+        let mut window = DurationWindow::default();
+        for ms in 0..(HISTORY as u64 * 2) {
+            window.push(Duration::from_millis(ms));
+        }
+        assert_eq!(window.len(), HISTORY);
+        assert_eq!(window.min(), Some(Duration::from_millis(HISTORY as u64)));
+    }
+}
@@ -50,6 +50,14 @@
 //! This module focuses on the surface interrogation necessary to inform swapchain creation and
 //! re-creation.  Nonetheless, the surface and dependencies for the surface can be found from the
 //! earliest stages of the application.
+//!
+//! ## Present Modes
+//!
+//! [`PresentPreference::Auto`] picks FIFO.  Frames are drawn late on purpose, just before the
+//! deadline of the vblank they target, so FIFO costs no latency and never tears.  On a variable
+//! refresh display FIFO presents as soon as a frame is ready, up to the display's maximum rate.
+//! The other modes exist for downstreams and displays that want them.  A preferred mode the surface
+//! does not support falls back to the automatic choice with a warning.
 
 // XXX We really need to propagate the surface update back to the application so that provisioned
 // things can be re-provisioned.  A runtime will eventually handle the threading, but for now...
@@ -59,11 +67,84 @@ use winit::window::Window;
 
 use crate::internal::*;
 
+/// Which present mode to ask for.  See the [module docs](self#present-modes).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PresentPreference {
+    /// FIFO, which every surface supports.
+    #[default]
+    Auto,
+    /// Wait for vblank.  Never tears.
+    Fifo,
+    /// Wait for vblank unless the frame is late, and then tear rather than wait for the next.
+    FifoRelaxed,
+    /// Replace the queued frame with a newer one.  Renders at any rate and never tears.
+    Mailbox,
+    /// Present at once.  Tears.
+    Immediate,
+}
+
+impl PresentPreference {
+    /// Modes to try, in order.
+    fn candidates(self) -> &'static [vk::PresentModeKHR] {
+        match self {
+            Self::Auto => &[
+                vk::PresentModeKHR::FIFO,
+                vk::PresentModeKHR::FIFO_RELAXED,
+                vk::PresentModeKHR::MAILBOX,
+            ],
+            Self::Fifo => &[vk::PresentModeKHR::FIFO],
+            Self::FifoRelaxed => &[vk::PresentModeKHR::FIFO_RELAXED],
+            Self::Mailbox => &[vk::PresentModeKHR::MAILBOX],
+            Self::Immediate => &[vk::PresentModeKHR::IMMEDIATE],
+        }
+    }
+
+    /// The preferred mode among `supported`, or the automatic choice if it is missing.
+    pub fn select(self, supported: &[vk::PresentModeKHR]) -> vk::PresentModeKHR {
+        let find = |candidates: &[vk::PresentModeKHR]| {
+            candidates
+                .iter()
+                .copied()
+                .find(|mode| supported.contains(mode))
+        };
+        find(self.candidates())
+            .or_else(|| find(Self::Auto.candidates()))
+            .unwrap_or(vk::PresentModeKHR::FIFO)
+    }
+}
+
+impl std::fmt::Display for PresentPreference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Auto => "auto",
+            Self::Fifo => "fifo",
+            Self::FifoRelaxed => "relaxed",
+            Self::Mailbox => "mailbox",
+            Self::Immediate => "immediate",
+        })
+    }
+}
+
+impl std::str::FromStr for PresentPreference {
+    type Err = VulkanError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "fifo" | "vsync" => Ok(Self::Fifo),
+            "relaxed" | "fifo_relaxed" => Ok(Self::FifoRelaxed),
+            "mailbox" => Ok(Self::Mailbox),
+            "immediate" => Ok(Self::Immediate),
+            _ => Err(VulkanError::UnknownPresentMode(s.to_owned())),
+        }
+    }
+}
+
 /// The set of decisions resulting from surface updates, obtained from querying the Vulkan surface
 /// capabilities and selecting in our preference order the values we support.
 #[derive(Clone, Debug)]
 pub struct SurfaceCaps {
-    /// MAILBOX if supported.  FIFO_RELAXED is pretty close.
+    /// Chosen by the surface's [`PresentPreference`].
     present_mode: vk::PresentModeKHR,
     swapchain_image_count: u32,
 
@@ -75,6 +156,8 @@ pub struct SurfaceCaps {
     pub pre_transform: vk::SurfaceTransformFlagsKHR,
     /// Resolved swapchain image size.
     pub extent: vk::Extent2D,
+    /// Swapchain image usage.  Includes `TRANSFER_SRC` for reading frames back where supported.
+    pub image_usage: vk::ImageUsageFlags,
 }
 
 // We want to unify the signatures over the source of fallback extent to support windowed and
@@ -109,6 +192,7 @@ pub struct Surface {
     /// Raw Vulkan surface handle.
     raw: vk::SurfaceKHR,
     surface_loader: ash::khr::surface::Instance,
+    preference: PresentPreference,
     /// Last updated view of our choices to support for this surface.
     pub caps: SurfaceCaps,
 }
@@ -127,20 +211,45 @@ impl Surface {
         let surface_loader = instance.surface_loader();
         let raw_caps = Self::fetch_raw_caps(&surface_loader, device.physical_device, surface)?;
         let extent = Self::resolve_extent(&raw_caps, extent_source)?;
+        let preference = PresentPreference::Auto;
         let caps = Self::resolve_caps(
             &surface_loader,
             device.physical_device,
             surface,
             &raw_caps,
             extent,
+            preference,
         )?;
         Ok(Self {
             raw: surface,
             surface_loader,
+            preference,
             caps,
         })
     }
 
+    /// Choose the present mode by `preference` instead of automatically.  Call before creating the
+    /// swapchain.
+    pub fn with_present_preference(
+        mut self,
+        device: &Device,
+        preference: PresentPreference,
+    ) -> Result<Self, VulkanError> {
+        self.preference = preference;
+        self.update(device, self.caps.extent)?;
+        if !preference.candidates().contains(&self.caps.present_mode) {
+            log::warn!(
+                "surface: {preference} presentation is unsupported, using {:?}",
+                self.caps.present_mode
+            );
+        }
+        Ok(self)
+    }
+
+    pub fn present_preference(&self) -> PresentPreference {
+        self.preference
+    }
+
     /// Re-query the surface capabilities
     ///
     /// Call this on resize events, [`SwapchainOutOfDate`]() or after a
@@ -161,6 +270,7 @@ impl Surface {
             self.raw,
             &raw_caps,
             extent,
+            self.preference,
         )?;
         Ok(self.caps.extent)
     }
@@ -245,6 +355,7 @@ impl Surface {
         surface: vk::SurfaceKHR,
         raw_caps: &vk::SurfaceCapabilitiesKHR,
         extent: vk::Extent2D,
+        preference: PresentPreference,
     ) -> Result<SurfaceCaps, VulkanError> {
         let formats = unsafe {
             surface_loader.get_physical_device_surface_formats(physical_device, surface)?
@@ -296,15 +407,7 @@ impl Surface {
         // to be nearer to the latch (to reduce power draw, pipeline efficiently, and to reduce
         // latency), FIFO is actually the most correct and we support the other modes primarily to
         // satisfy downstreams that demand them.
-        let present_mode = [
-            vk::PresentModeKHR::FIFO,
-            vk::PresentModeKHR::FIFO_RELAXED,
-            vk::PresentModeKHR::MAILBOX,
-        ]
-        .iter()
-        .copied()
-        .find(|mode| present_modes.contains(mode))
-        .unwrap_or(vk::PresentModeKHR::FIFO);
+        let present_mode = preference.select(&present_modes);
 
         // Generally we're expecting any surface we write to might have a compositor behind it.  If
         // so, we might get a funky compositor alpha blend back.  It might mean something to
@@ -343,6 +446,12 @@ impl Surface {
             }
         };
 
+        // Drawing and copying into images is required.  Copying out, such as for screenshots, is
+        // optional.
+        let image_usage = vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | vk::ImageUsageFlags::TRANSFER_DST
+            | (raw_caps.supported_usage_flags & vk::ImageUsageFlags::TRANSFER_SRC);
+
         // Extent degeneracy was checked upstream.  This clamp just complies with the spec.
         let extent = vk::Extent2D {
             width: extent.width.clamp(
@@ -362,6 +471,7 @@ impl Surface {
             pre_transform,
             swapchain_image_count,
             extent,
+            image_usage,
         })
     }
 
@@ -385,8 +495,7 @@ impl Surface {
             .flags(vk::SwapchainCreateFlagsKHR::DEFERRED_MEMORY_ALLOCATION_EXT)
             .image_array_layers(1)
             .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
-            // XXX Can we add more flags here?
-            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST)
+            .image_usage(caps.image_usage)
     }
 
     /// Get the chosen supported format.
@@ -399,6 +508,11 @@ impl Surface {
         self.caps.extent
     }
 
+    /// Get the chosen present mode.
+    pub fn present_mode(&self) -> vk::PresentModeKHR {
+        self.caps.present_mode
+    }
+
     /// Return the raw surface.
     pub fn as_raw(&self) -> &vk::SurfaceKHR {
         &self.raw
@@ -424,3 +538,42 @@ impl std::ops::Deref for Surface {
         &self.raw
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_present_preference() {
+        use vk::PresentModeKHR as Mode;
+        let fifo_only = [Mode::FIFO];
+        let all = [
+            Mode::IMMEDIATE,
+            Mode::MAILBOX,
+            Mode::FIFO,
+            Mode::FIFO_RELAXED,
+        ];
+        assert_eq!(PresentPreference::Auto.select(&all), Mode::FIFO);
+        assert_eq!(PresentPreference::Mailbox.select(&all), Mode::MAILBOX);
+        assert_eq!(
+            PresentPreference::FifoRelaxed.select(&all),
+            Mode::FIFO_RELAXED
+        );
+        // Unsupported preferences fall back to the automatic choice.
+        assert_eq!(PresentPreference::Immediate.select(&fifo_only), Mode::FIFO);
+
+        for preference in [
+            PresentPreference::Auto,
+            PresentPreference::Fifo,
+            PresentPreference::FifoRelaxed,
+            PresentPreference::Mailbox,
+            PresentPreference::Immediate,
+        ] {
+            assert_eq!(
+                preference.to_string().parse::<PresentPreference>().unwrap(),
+                preference
+            );
+        }
+        assert!("tearing".parse::<PresentPreference>().is_err());
+    }
+}
@@ -140,6 +140,24 @@ pub struct AcquiredImage {
     pub(crate) sync_index: usize,
 }
 
+impl AcquiredImage {
+    /// Wrap an image that is drawn like a swapchain image but never presented, such as frames
+    /// rendered offline.  The semaphores and fence are null.  Submit the work without waiting on
+    /// `image_available` or signaling `present_ready`, and never hand the image to a swapchain.
+    pub fn offscreen(image: vk::Image, image_view: vk::ImageView, extent: vk::Extent2D) -> Self {
+        Self {
+            image_available: BinaryWait::null(),
+            present_ready: BinarySignal::null(),
+            present_finished: Fence(vk::Fence::null()),
+            image_view,
+            image,
+            extent,
+            swapchain_image_index: 0,
+            sync_index: 0,
+        }
+    }
+}
+
 /// When recreating swapchains, we may need to hold onto old in-flight resources to drain and
 /// destroy them together.  The swapchain controls its own deferred destruction for now.  A later
 /// deletion queue solution may be able to reclaim this without ever looking at the fences in the
@@ -292,7 +310,7 @@ impl Swapchain {
         // Even if we only have 2 images, we need at least three semaphores to avoid lapping those
         // in-flight.
         let slots = images.len().max(3);
-        let image_views = create_image_views(&device, &images, surface.format());
+        let image_views = create_image_views(&device, &images, surface.format())?;
 
         Ok(Self {
             raw: swapchain,
@@ -322,7 +340,7 @@ impl Swapchain {
         let new_images: SmallVec<vk::Image, 4> =
             unsafe { self.loader.get_swapchain_images(new_swapchain)?.into() };
         let new_slots = new_images.len().clamp(3, 4);
-        let new_image_views = create_image_views(device, &new_images, surface.format());
+        let new_image_views = create_image_views(device, &new_images, surface.format())?;
         let new_sync = PresentSync::new(device, new_slots)?;
 
         // Old in-flight Vulkan resources will be moved to retirement for later culling.
@@ -483,24 +501,31 @@ fn create_image_views(
     device: &Device,
     images: &[vk::Image],
     format: vk::Format,
-) -> SmallVec<vk::ImageView, 4> {
-    images
-        .iter()
-        .map(|&image| {
-            let view_ci = vk::ImageViewCreateInfo {
-                image,
-                view_type: vk::ImageViewType::TYPE_2D,
-                format,
-                components: vk::ComponentMapping::default(),
-                subresource_range: vk::ImageSubresourceRange {
-                    aspect_mask: vk::ImageAspectFlags::COLOR,
-                    level_count: 1,
-                    layer_count: 1,
-                    ..Default::default()
-                },
+) -> Result<SmallVec<vk::ImageView, 4>, VulkanError> {
+    let mut views = SmallVec::new();
+    for &image in images {
+        let view_ci = vk::ImageViewCreateInfo {
+            image,
+            view_type: vk::ImageViewType::TYPE_2D,
+            format,
+            components: vk::ComponentMapping::default(),
+            subresource_range: vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                level_count: 1,
+                layer_count: 1,
                 ..Default::default()
-            };
-            unsafe { device.as_raw().create_image_view(&view_ci, None).unwrap() }
-        })
-        .collect()
+            },
+            ..Default::default()
+        };
+        match unsafe { device.as_raw().create_image_view(&view_ci, None) } {
+            Ok(view) => views.push(view),
+            Err(e) => {
+                for view in views {
+                    unsafe { device.as_raw().destroy_image_view(view, None) };
+                }
+                return Err(e.into());
+            }
+        }
+    }
+    Ok(views)
 }
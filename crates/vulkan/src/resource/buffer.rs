@@ -7,7 +7,6 @@
 //! that we will flush to the device periodically.
 
 // FIXME guarantee initialization.
-// DEBT sub-allocation / resource runtime
 // MAYBE backing pools and buffer capabilities can drive type divergence, which we would handle with
 // generics.  The number of dimensions and motivations for these changes are not too clear.  ReBAR
 // support is likely just increasing.  Write-combined vs write-back and device local are clear areas
@@ -19,17 +18,21 @@ use std::ptr::NonNull;
 use ash::vk;
 
 use crate::{
-    device::{descriptors, Device},
-    util, VulkanError,
+    device::{
+        descriptors,
+        memory::{Allocation, Kind},
+        Device,
+    },
+    VulkanError,
 };
 
 pub struct MappedAllocation<T> {
     pub buffer: vk::Buffer,
-    pub memory: vk::DeviceMemory,
+    /// Range of a block from the device's [`Allocator`](crate::device::memory::Allocator).
+    pub allocation: Allocation,
     pub ptr: NonNull<T>,
     pub len: usize,
     pub size_bytes: vk::DeviceSize,
-    pub memory_type_index: u32,
 }
 
 impl<T> MappedAllocation<T> {
@@ -43,53 +46,56 @@ impl<T> MappedAllocation<T> {
             sharing_mode: vk::SharingMode::EXCLUSIVE,
             ..Default::default()
         };
-        let buffer = unsafe { device.as_raw().create_buffer(&buffer_info, None).unwrap() };
+        let buffer = unsafe { device.as_raw().create_buffer(&buffer_info, None)? };
         let mem_req = unsafe { device.as_raw().get_buffer_memory_requirements(buffer) };
-        let memory_type_index = util::find_memory_type_index(
+        let allocation = match device.allocator.alloc(
+            device.as_raw(),
             &mem_req,
-            &device.memory_props,
             vk::MemoryPropertyFlags::HOST_VISIBLE,
-        )
-        .ok_or(VulkanError::Ash(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY))?;
-
-        let mut flags =
-            vk::MemoryAllocateFlagsInfo::default().flags(vk::MemoryAllocateFlags::DEVICE_ADDRESS);
-        let alloc_info = vk::MemoryAllocateInfo::default()
-            .allocation_size(mem_req.size)
-            .memory_type_index(memory_type_index)
-            .push_next(&mut flags);
-        let memory = unsafe { device.as_raw().allocate_memory(&alloc_info, None)? };
+            Kind::Buffer,
+        ) {
+            Ok(allocation) => allocation,
+            Err(e) => {
+                unsafe { device.as_raw().destroy_buffer(buffer, None) };
+                return Err(e);
+            }
+        };
         unsafe {
-            device.as_raw().bind_buffer_memory(buffer, memory, 0)?;
-        }
-
-        let raw_ptr = unsafe {
             device
                 .as_raw()
-                .map_memory(memory, 0, mem_req.size, vk::MemoryMapFlags::empty())?
-        };
+                .bind_buffer_memory(buffer, allocation.memory(), allocation.offset())?;
+        }
 
-        let ptr = NonNull::new(raw_ptr as *mut T).unwrap();
+        let ptr = allocation
+            .mapped()
+            .expect("host visible memory is mapped")
+            .cast();
+        let size_bytes = allocation.size();
 
         Ok(Self {
             buffer,
             ptr,
             len: size,
-            memory,
-            size_bytes: mem_req.size,
-            memory_type_index,
+            allocation,
+            size_bytes,
         })
     }
 
+    /// Name the buffer for debug tools, and its memory if dedicated.  See [`Device::set_name`].
+    pub fn set_name(&self, device: &Device, name: &str) {
+        device.set_name(self.buffer, name);
+        if self.allocation.is_dedicated() {
+            device.set_name(self.allocation.memory(), &format!("{name} memory"));
+        }
+    }
+
     // DEBT memory management.  We just need to devolve the allocation into a memento that can be
     // recycled or destroyed asynchronously.
     pub fn destroy(&self, device: &Device) -> Result<(), VulkanError> {
-        let device = device.as_raw();
         unsafe {
-            device.unmap_memory(self.memory);
-            device.free_memory(self.memory, None);
-            device.destroy_buffer(self.buffer, None);
+            device.as_raw().destroy_buffer(self.buffer, None);
         }
+        device.allocator.free(device.as_raw(), &self.allocation);
         Ok(())
     }
 
@@ -101,8 +107,8 @@ impl<T> MappedAllocation<T> {
     /// Move writes to device memory.
     pub fn flush(&mut self, device: &Device) -> Result<(), VulkanError> {
         let flush_range = vk::MappedMemoryRange {
-            memory: self.memory,
-            offset: 0,
+            memory: self.allocation.memory(),
+            offset: self.allocation.offset(),
             size: self.size_bytes,
             ..Default::default()
         };
@@ -115,8 +121,8 @@ impl<T> MappedAllocation<T> {
     /// Refresh host view with device writes.
     pub fn invalidate(&mut self, device: &Device) -> Result<(), VulkanError> {
         let invalidate_range = vk::MappedMemoryRange {
-            memory: self.memory,
-            offset: 0,
+            memory: self.allocation.memory(),
+            offset: self.allocation.offset(),
             size: self.size_bytes,
             ..Default::default()
         };
@@ -128,12 +134,16 @@ impl<T> MappedAllocation<T> {
         Ok(())
     }
 
+    /// Register the whole buffer as a storage buffer.  Usable after the next descriptor flush.
     // XXX argument order
-    pub fn bound(&self, device: &Device) -> descriptors::SsboIdx {
-        let descriptors = &device.descriptors;
-        // XXX do this more cleaner 🤡
+    pub fn register(
+        &self,
+        device: &Device,
+    ) -> Result<descriptors::Handle<descriptors::SsboIdx>, VulkanError> {
         let byte_size = (std::mem::size_of::<T>() * self.len) as u64;
-        descriptors.bind_ssbo(&device.raw, self.buffer, 0, byte_size)
+        device
+            .descriptors
+            .register_storage_buffer(self.buffer, 0, byte_size)
     }
 
     // XXX Slang type for buffer device address
@@ -202,7 +212,8 @@ impl<T> MappedAllocation<T> {
     pub fn write_view(&self, device: &Device) -> MappedWriteView<T> {
         MappedWriteView {
             device: device.as_raw().clone(),
-            memory: self.memory,
+            memory: self.allocation.memory(),
+            offset: self.allocation.offset(),
             ptr: self.ptr,
             len: self.len,
             size_bytes: self.size_bytes,
@@ -234,6 +245,8 @@ pub fn buffer_image_copy_full(extent: vk::Extent2D) -> vk::BufferImageCopy {
 pub struct MappedWriteView<T> {
     device: ash::Device,
     memory: vk::DeviceMemory,
+    /// Where the allocation begins in `memory`.
+    offset: vk::DeviceSize,
     ptr: NonNull<T>,
     len: usize,
     size_bytes: vk::DeviceSize,
@@ -254,7 +267,7 @@ impl<T> MappedWriteView<T> {
     pub fn flush(&self) -> Result<(), VulkanError> {
         let range = vk::MappedMemoryRange {
             memory: self.memory,
-            offset: 0,
+            offset: self.offset,
             size: self.size_bytes,
             ..Default::default()
         };
@@ -283,7 +296,7 @@ impl<T> MappedWriteView<T> {
         );
         let range = vk::MappedMemoryRange {
             memory: self.memory,
-            offset,
+            offset: self.offset + offset,
             size,
             ..Default::default()
         };
@@ -13,16 +13,19 @@
 use ash::vk;
 
 use crate::{
-    device::{descriptors, Device},
+    device::{
+        descriptors,
+        memory::{Allocation, Kind},
+        Device,
+    },
     VulkanError,
 };
 
-use crate::util;
-
 /// The memory and dimensions for an allocated Vulkan Image.
 pub struct Image {
     pub image: vk::Image,
-    pub memory: vk::DeviceMemory,
+    /// Range of a block from the device's [`Allocator`](crate::device::memory::Allocator).
+    pub allocation: Allocation,
     pub format: vk::Format,
     pub extent: vk::Extent2D,
 }
@@ -33,6 +36,18 @@ impl Image {
         extent: vk::Extent2D,
         format: vk::Format,
         usage: vk::ImageUsageFlags,
+    ) -> Result<Self, VulkanError> {
+        Self::multisampled(device, extent, format, usage, vk::SampleCountFlags::TYPE_1)
+    }
+
+    /// An image with `samples` per pixel, such as a render target that resolves into a swapchain
+    /// image.  See [`msaa`](crate::present::msaa).
+    pub fn multisampled(
+        device: &Device,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        usage: vk::ImageUsageFlags,
+        samples: vk::SampleCountFlags,
     ) -> Result<Self, VulkanError> {
         let image_ci = vk::ImageCreateInfo {
             image_type: vk::ImageType::TYPE_2D,
@@ -44,7 +59,7 @@ impl Image {
             },
             mip_levels: 1,
             array_layers: 1,
-            samples: vk::SampleCountFlags::TYPE_1,
+            samples,
             tiling: vk::ImageTiling::OPTIMAL,
             usage,
             sharing_mode: vk::SharingMode::EXCLUSIVE,
@@ -54,40 +69,46 @@ impl Image {
 
         let image = unsafe { device.as_raw().create_image(&image_ci, None)? };
 
-        // DEBT memory management centralize onto the thingy.
         let mem_req = unsafe { device.as_raw().get_image_memory_requirements(image) };
-
-        // Ah, bind up into a memory object
-        let memory_type_index = util::find_memory_type_index(
+        let allocation = match device.allocator.alloc(
+            device.as_raw(),
             &mem_req,
-            &device.memory_props,
             vk::MemoryPropertyFlags::DEVICE_LOCAL,
-        )
-        .ok_or(VulkanError::Ash(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY))?;
-
-        let alloc_info = vk::MemoryAllocateInfo {
-            allocation_size: mem_req.size,
-            memory_type_index,
-            ..Default::default()
+            Kind::Image,
+        ) {
+            Ok(allocation) => allocation,
+            Err(e) => {
+                unsafe { device.as_raw().destroy_image(image, None) };
+                return Err(e);
+            }
+        };
+        unsafe {
+            device
+                .as_raw()
+                .bind_image_memory(image, allocation.memory(), allocation.offset())?
         };
-
-        // Hmmm....
-        let memory = unsafe { device.as_raw().allocate_memory(&alloc_info, None)? };
-        unsafe { device.as_raw().bind_image_memory(image, memory, 0)? };
 
         Ok(Self {
             image,
-            memory,
+            allocation,
             format,
             extent,
         })
     }
 
+    /// Name the image for debug tools, and its memory if dedicated.  See [`Device::set_name`].
+    pub fn set_name(&self, device: &Device, name: &str) {
+        device.set_name(self.image, name);
+        if self.allocation.is_dedicated() {
+            device.set_name(self.allocation.memory(), &format!("{name} memory"));
+        }
+    }
+
     pub fn destroy(self, device: &Device) -> Result<(), VulkanError> {
         unsafe {
             device.as_raw().destroy_image(self.image, None);
-            device.as_raw().free_memory(self.memory, None);
         }
+        device.allocator.free(device.as_raw(), &self.allocation);
         Ok(())
     }
 
@@ -212,17 +233,14 @@ impl ImageView {
         Ok(())
     }
 
-    // XXX extremely raw.  Bludgeon if found.
+    /// Register the view for sampling in `layout`.  Usable after the next descriptor flush.
     pub fn sampled(
         &self,
-        device: &mut Device,
+        device: &Device,
         layout: vk::ImageLayout,
-    ) -> descriptors::SampledImageIdx {
+    ) -> Result<descriptors::Handle<descriptors::SampledImageIdx>, VulkanError> {
         // MAYBE not so sure about the layout choice
-        let f = device.as_raw().clone();
-        let descriptors = &mut device.descriptors;
-        descriptors.bind_sampled_image(&f, self.view, layout)
-        // device.bind_sampled_image(self.view, layout)
+        device.descriptors.register_sampled_image(self.view, layout)
     }
 }
 
@@ -268,6 +286,13 @@ pub fn transition_layout(
             vk::AccessFlags::TRANSFER_WRITE,
             vk::AccessFlags::SHADER_READ,
         ),
+        // Drawing over discarded contents, such as a fresh render target
+        (vk::ImageLayout::UNDEFINED, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL) => (
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::AccessFlags::empty(),
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+        ),
         (vk::ImageLayout::UNDEFINED, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL) => (
             vk::PipelineStageFlags::TOP_OF_PIPE,
             vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
@@ -289,14 +314,14 @@ pub fn transition_layout(
         // From color attachment to shader read (offscreen render → sampling)
         (vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL) => (
             vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER,
             vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
             vk::AccessFlags::SHADER_READ,
         ),
 
-        // From shader read back to color attachment (rare, but valid)
+        // From shader read back to color attachment (offscreen targets drawn every frame)
         (vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL) => (
-            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER,
             vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
             vk::AccessFlags::SHADER_READ,
             vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
@@ -316,6 +341,34 @@ pub fn transition_layout(
             vk::AccessFlags::TRANSFER_WRITE,
         ),
 
+        // Sampled images updated in place, such as a history written a row at a time
+        (vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, vk::ImageLayout::TRANSFER_DST_OPTIMAL) => (
+            vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::AccessFlags::empty(),
+            vk::AccessFlags::TRANSFER_WRITE,
+        ),
+
+        // Render targets copied out, then sampled or drawn again
+        (vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL, vk::ImageLayout::TRANSFER_SRC_OPTIMAL) => (
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            vk::AccessFlags::TRANSFER_READ,
+        ),
+        (vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL) => (
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::AccessFlags::empty(),
+            vk::AccessFlags::SHADER_READ,
+        ),
+        (vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL) => (
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::AccessFlags::empty(),
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+        ),
+
         // Sample depth in shader
         (
             vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
@@ -12,6 +12,9 @@
 pub mod buffer;
 pub mod image;
 pub mod shader;
+pub mod staging;
+pub mod target;
+pub mod transient;
 pub mod ubo;
 
 #[cfg(test)]
@@ -23,12 +23,12 @@ pub struct ShaderModule<'ctx> {
 
 impl<'device> ShaderModule<'device> {
     // XXX trace this up and move assets to instance or elsewhere!
-    pub fn load(device: &'device Device, path: &'static str) -> Result<Self, VulkanError> {
+    pub fn load(device: &'device Device, path: &str) -> Result<Self, VulkanError> {
         // NEXT We could further type shader names to verify that hardcoded names exist.  Dynamic
         // names for source files doesn't really make sense unless the GPU has gone Skynet and is
         // emitting fresh slang code to hot swap with itself.  Static shader file names would do
         // some justice.
-        let spv = device.assets.find_shader(path).unwrap();
+        let spv = device.assets.find_shader(path)?;
         let module_ci = vk::ShaderModuleCreateInfo::default().code(spv.as_slice());
         let module = unsafe { device.as_raw().create_shader_module(&module_ci, None)? };
 
@@ -0,0 +1,342 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Staging
+//!
+//! DSP nodes produce new data on the host every frame: spectra, envelopes, onset lists.  Keeping
+//! that data in host-visible memory means every shader read crosses the bus on discrete GPUs.  The
+//! [`StagingRing`] instead keeps, per frame in flight, a host-visible staging buffer and a
+//! device-local buffer of the same capacity.  [`upload`](StagingRing::upload) copies data into
+//! staging and returns the [`BufferSlice`] of the device-local buffer where it will land.
+//! [`record`](StagingRing::record) then records every copy of the frame at once, followed by the
+//! barrier that makes them visible to their readers.
+//!
+//! ## Queues
+//!
+//! The copies may be recorded on the queue that reads the data or on the transfer queue, where they
+//! overlap with rendering.  A ring built [`with_families`](StagingRing::with_families) for
+//! different families ends [`record`](StagingRing::record) with a release instead of a barrier.
+//! The reader then records [`acquire`](StagingRing::acquire) after waiting on the transfer
+//! submission's semaphore.  See [`BufferTransfer`].
+//!
+//! ## Frame Boundaries
+//!
+//! Slots retire exactly like those of the [`TransientPool`](super::transient::TransientPool).
+//! [`begin_frame`](StagingRing::begin_frame) waits on the value the slot's last frame promised to
+//! signal, and slices are only valid until the slot comes around again.
+
+// MAYBE devices where all device-local memory is host visible (integrated, ReBAR) could skip the
+// copy and hand out slices of staging directly.
+
+use crate::device::memory::{Allocation, Kind};
+use crate::dispatch::ownership::BufferTransfer;
+use crate::internal::*;
+use crate::resource::buffer::MappedAllocation;
+use crate::resource::transient::{Bump, RANGE_ALIGNMENT};
+
+/// A range of a device-local staging destination, valid for the current frame only.
+#[derive(Clone, Copy, Debug)]
+pub struct BufferSlice {
+    pub buffer: vk::Buffer,
+    pub offset: vk::DeviceSize,
+    pub size: vk::DeviceSize,
+    /// Device address of the first byte of the slice.
+    pub address: vk::DeviceAddress,
+}
+
+/// Usage summary for tuning the ring capacity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StagingStats {
+    pub capacity: vk::DeviceSize,
+    /// Bytes uploaded so far in the current frame.
+    pub used: vk::DeviceSize,
+    /// Most bytes uploaded in any single frame.
+    pub high_water: vk::DeviceSize,
+}
+
+struct Slot {
+    staging: MappedAllocation<u8>,
+    buffer: vk::Buffer,
+    allocation: Allocation,
+    address: vk::DeviceAddress,
+    /// Timeline value the slot's previous frame promised to signal.
+    done: Option<WaitValue>,
+}
+
+/// Per-frame uploads to device-local memory for `N` frames in flight.
+pub struct StagingRing<const N: usize = 2> {
+    slots: Vec<Slot>,
+    bump: Bump,
+    cursor: usize,
+    /// Slot that `upload` writes to.  `None` until the first `begin_frame`.
+    current: Option<usize>,
+    /// Copies waiting for `record`.  Data lands at the same offset it was staged at.
+    pending: Vec<vk::BufferCopy>,
+    src_family: u32,
+    dst_family: u32,
+}
+
+impl<const N: usize> StagingRing<N> {
+    /// Allocate `N` pairs of buffers of `capacity` bytes each.
+    pub fn new(device: &Device, capacity: vk::DeviceSize) -> Result<Self, VulkanError> {
+        const { assert!(N >= 1, "StagingRing requires at least one slot") };
+        let mut ring = Self {
+            slots: Vec::with_capacity(N),
+            bump: Bump::new(capacity),
+            cursor: 0,
+            current: None,
+            pending: Vec::new(),
+            src_family: vk::QUEUE_FAMILY_IGNORED,
+            dst_family: vk::QUEUE_FAMILY_IGNORED,
+        };
+        for _ in 0..N {
+            match Self::make_slot(device, capacity) {
+                Ok(slot) => ring.slots.push(slot),
+                Err(e) => {
+                    // DEBT manual destruction of partially constructed pool resources
+                    ring.destroy(device);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(ring)
+    }
+
+    /// Copies are recorded on `src_family` and read on `dst_family`.  Without this, both are
+    /// assumed to be the same family.
+    pub fn with_families(mut self, src_family: u32, dst_family: u32) -> Self {
+        self.src_family = src_family;
+        self.dst_family = dst_family;
+        self
+    }
+
+    fn make_slot(device: &Device, capacity: vk::DeviceSize) -> Result<Slot, VulkanError> {
+        let raw = device.as_raw();
+        let staging = MappedAllocation::<u8>::new(capacity as usize, device)?;
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size(capacity)
+            .usage(
+                vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::TRANSFER_DST
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            )
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let buffer = match unsafe { raw.create_buffer(&buffer_info, None) } {
+            Ok(buffer) => buffer,
+            Err(e) => {
+                let _ = staging.destroy(device);
+                return Err(e.into());
+            }
+        };
+        let mem_req = unsafe { raw.get_buffer_memory_requirements(buffer) };
+        // The point is device-local memory.  Fall back to any memory type on odd hardware.
+        let allocation = device
+            .allocator
+            .alloc(
+                raw,
+                &mem_req,
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                Kind::Buffer,
+            )
+            .or_else(|_| {
+                device.allocator.alloc(
+                    raw,
+                    &mem_req,
+                    vk::MemoryPropertyFlags::empty(),
+                    Kind::Buffer,
+                )
+            });
+        let allocation = match allocation {
+            Ok(allocation) => allocation,
+            Err(e) => {
+                unsafe { raw.destroy_buffer(buffer, None) };
+                let _ = staging.destroy(device);
+                return Err(e);
+            }
+        };
+        unsafe { raw.bind_buffer_memory(buffer, allocation.memory(), allocation.offset())? };
+        let info = vk::BufferDeviceAddressInfo::default().buffer(buffer);
+        let address = unsafe { raw.get_buffer_device_address(&info) };
+
+        Ok(Slot {
+            staging,
+            buffer,
+            allocation,
+            address,
+            done: None,
+        })
+    }
+
+    /// Move to the next slot, waiting until the GPU has retired its previous frame.  `done` is the
+    /// timeline value that this frame's final submission will signal.
+    pub fn begin_frame(
+        &mut self,
+        device: &Device,
+        done: WaitValue,
+        timeout: u64,
+    ) -> Result<(), VulkanError> {
+        debug_assert!(
+            self.pending.is_empty(),
+            "begin_frame: uploads of the last frame were never recorded"
+        );
+        let slot = &mut self.slots[self.cursor];
+        if let Some(previous) = slot.done.take() {
+            previous.wait(device, timeout)?;
+        }
+        slot.done = Some(done);
+        self.bump.reset();
+        self.pending.clear();
+        self.current = Some(self.cursor);
+        self.cursor = (self.cursor + 1) % N;
+        Ok(())
+    }
+
+    /// Stage `data` and return where it will land once [`record`](Self::record)ed copies execute.
+    /// Slices are aligned for use as storage buffer bindings and device addresses.
+    pub fn upload<T: bytemuck::Pod>(&mut self, data: &[T]) -> Result<BufferSlice, VulkanError> {
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+        let size = bytes.len() as vk::DeviceSize;
+        let slot = self
+            .current
+            .map(|i| &mut self.slots[i])
+            .ok_or(VulkanError::NotInFrame("staging upload"))?;
+        let offset =
+            self.bump
+                .alloc(size, RANGE_ALIGNMENT)
+                .ok_or(VulkanError::StagingExhausted {
+                    requested: size,
+                    capacity: self.bump.capacity,
+                })?;
+        slot.staging.as_mut_slice()[offset as usize..(offset + size) as usize]
+            .copy_from_slice(bytes);
+        if size > 0 {
+            self.pending.push(
+                vk::BufferCopy::default()
+                    .src_offset(offset)
+                    .dst_offset(offset)
+                    .size(size),
+            );
+        }
+        Ok(BufferSlice {
+            buffer: slot.buffer,
+            offset,
+            size,
+            address: slot.address + offset,
+        })
+    }
+
+    /// Record the frame's copies on a command buffer for the source family.  `stage` and `access`
+    /// are how the data will first be read.  When the families differ, the readers must
+    /// [`acquire`](Self::acquire) instead of relying on this barrier.
+    pub fn record(
+        &mut self,
+        device: &Device,
+        cb: vk::CommandBuffer,
+        stage: vk::PipelineStageFlags2,
+        access: vk::AccessFlags2,
+    ) -> Result<(), VulkanError> {
+        let Some(slot) = self.current.map(|i| &mut self.slots[i]) else {
+            return Ok(());
+        };
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        slot.staging.flush(device)?;
+        let raw = device.as_raw();
+        unsafe { raw.cmd_copy_buffer(cb, slot.staging.buffer, slot.buffer, &self.pending) };
+        self.pending.clear();
+
+        let transfer = BufferTransfer::new(slot.buffer, self.src_family, self.dst_family);
+        if transfer.is_needed() {
+            transfer.release(
+                device,
+                cb,
+                vk::PipelineStageFlags2::COPY,
+                vk::AccessFlags2::TRANSFER_WRITE,
+            );
+        } else {
+            let barrier = vk::BufferMemoryBarrier2::default()
+                .src_stage_mask(vk::PipelineStageFlags2::COPY)
+                .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+                .dst_stage_mask(stage)
+                .dst_access_mask(access)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .buffer(slot.buffer)
+                .size(vk::WHOLE_SIZE);
+            let dependency = vk::DependencyInfo::default()
+                .buffer_memory_barriers(std::slice::from_ref(&barrier));
+            unsafe { raw.cmd_pipeline_barrier2(cb, &dependency) };
+        }
+        Ok(())
+    }
+
+    /// Record the acquire of the frame's uploads on a command buffer for the destination family.
+    /// Does nothing when the families match.
+    pub fn acquire(
+        &self,
+        device: &Device,
+        cb: vk::CommandBuffer,
+        stage: vk::PipelineStageFlags2,
+        access: vk::AccessFlags2,
+    ) {
+        if let Some(slot) = self.current.map(|i| &self.slots[i]) {
+            self.transfer(slot.buffer)
+                .acquire(device, cb, stage, access);
+        }
+    }
+
+    fn transfer(&self, buffer: vk::Buffer) -> BufferTransfer {
+        BufferTransfer::new(buffer, self.src_family, self.dst_family)
+    }
+
+    pub fn stats(&self) -> StagingStats {
+        StagingStats {
+            capacity: self.bump.capacity,
+            used: self.bump.cursor,
+            high_water: self.bump.high_water,
+        }
+    }
+
+    /// Wait for every slot's last frame, then free the buffers.
+    pub fn destroy(self, device: &Device) {
+        let raw = device.as_raw();
+        for slot in self.slots {
+            if let Some(done) = slot.done {
+                let _ = done.wait(device, u64::MAX);
+            }
+            unsafe { raw.destroy_buffer(slot.buffer, None) };
+            device.allocator.free(raw, &slot.allocation);
+            let _ = slot.staging.destroy(device);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_staging_ring() {
+        with_context!(|device| {
+            let mut ring = StagingRing::<2>::new(&device, 4096).unwrap();
+            let mut timeline = device.make_timeline_semaphore().unwrap();
+            for frame in 0..4u32 {
+                let intent = timeline.next_signal();
+                ring.begin_frame(&device, intent.wait_value(), u64::MAX)
+                    .unwrap();
+                let a = ring.upload(&[frame; 250]).unwrap();
+                let b = ring.upload(&[1.0f32; 4]).unwrap();
+                assert_eq!((a.size, b.offset, b.size), (1000, 1024, 16));
+                assert_eq!(b.address, a.address + 1024);
+                assert!(ring.upload(&[0u8; 4096]).is_err());
+                assert_eq!(ring.pending.len(), 2);
+                intent.try_consume(&device, u64::MAX).unwrap();
+                ring.pending.clear();
+            }
+            assert_eq!(ring.stats().high_water, 1040);
+            ring.destroy(&device);
+            timeline.destroy(&device);
+        });
+    }
+}
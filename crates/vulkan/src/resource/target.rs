@@ -0,0 +1,260 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Offscreen Targets
+//!
+//! Post-processing such as blur, bloom, and feedback trails draws into one image and samples it
+//! while drawing the next.  An [`OffscreenTarget`] is a color image that can be rendered into and
+//! then sampled, registered once in the bindless sampled image array.
+//!
+//! The target remembers its layout as commands are recorded.  [`color_attachment`], [`sample`],
+//! [`copy_to`], [`copy_to_buffer`], and [`copy_from`] transition it from whatever the last recorded
+//! use left, so a chain of nodes only states what it is about to do.  Recording order must be
+//! submission order, which holds for frames recorded and submitted one after another on one queue.  A recording abandoned before submission
+//! leaves the remembered layout wrong.  Call [`discard`] afterward.
+//!
+//! Feedback reads last frame's contents while drawing this frame's, so it needs two targets that
+//! trade places each frame.
+//!
+//! [`color_attachment`]: OffscreenTarget::color_attachment
+//! [`sample`]: OffscreenTarget::sample
+//! [`copy_to`]: OffscreenTarget::copy_to
+//! [`copy_to_buffer`]: OffscreenTarget::copy_to_buffer
+//! [`copy_from`]: OffscreenTarget::copy_from
+//! [`discard`]: OffscreenTarget::discard
+
+// MAYBE storage image usage for compute passes, once a node needs one.
+
+use crate::device::descriptors::{Handle, SampledImageIdx};
+use crate::internal::*;
+use crate::resource::buffer;
+use crate::resource::image::{self, Image, ImageView};
+
+/// A color image to render into and sample from.  See the [module docs](self).
+pub struct OffscreenTarget {
+    pub image: Image,
+    pub view: ImageView,
+    pub format: vk::Format,
+    /// Layout left by the last recorded use.
+    layout: vk::ImageLayout,
+    sampled: Handle<SampledImageIdx>,
+}
+
+impl OffscreenTarget {
+    /// A target of `extent` and `format`.  Its contents are undefined until first drawn.
+    pub fn new(
+        device: &Device,
+        extent: vk::Extent2D,
+        format: vk::Format,
+    ) -> Result<Self, VulkanError> {
+        let usage = vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | vk::ImageUsageFlags::SAMPLED
+            | vk::ImageUsageFlags::TRANSFER_SRC
+            | vk::ImageUsageFlags::TRANSFER_DST;
+        let image = Image::new(device, extent, format, usage)?;
+        let view = match image.default_view(device) {
+            Ok(view) => view,
+            Err(e) => {
+                let _ = image.destroy(device);
+                return Err(e);
+            }
+        };
+        let sampled = match view.sampled(device, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL) {
+            Ok(sampled) => sampled,
+            Err(e) => {
+                let _ = view.destroy(device);
+                let _ = image.destroy(device);
+                return Err(e);
+            }
+        };
+        Ok(Self {
+            image,
+            view,
+            format,
+            layout: vk::ImageLayout::UNDEFINED,
+            sampled,
+        })
+    }
+
+    /// Name the image for debug tools.  See [`Image::set_name`].
+    pub fn set_name(&self, device: &Device, name: &str) {
+        self.image.set_name(device, name);
+        device.set_name(self.view.view, &format!("{name} view"));
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        self.image.extent
+    }
+
+    /// The sampled image handle.  Shaders may read the target after [`sample`](Self::sample) has
+    /// been recorded.
+    pub fn handle(&self) -> Handle<SampledImageIdx> {
+        self.sampled
+    }
+
+    /// Layout left by the last recorded use.
+    pub fn layout(&self) -> vk::ImageLayout {
+        self.layout
+    }
+
+    /// Ready the target for drawing and return its color attachment, cleared to black.  Override
+    /// the load op with `LOAD` to draw over the previous contents.  Record before beginning
+    /// rendering.
+    pub fn color_attachment(
+        &mut self,
+        device: &Device,
+        cb: vk::CommandBuffer,
+    ) -> vk::RenderingAttachmentInfo<'static> {
+        self.transition(device, cb, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+        vk::RenderingAttachmentInfo::default()
+            .image_view(self.view.view)
+            .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::STORE)
+            .clear_value(vk::ClearValue::default())
+    }
+
+    /// Ready the target for sampling by later commands and return its handle.  Record after ending
+    /// rendering.
+    pub fn sample(&mut self, device: &Device, cb: vk::CommandBuffer) -> Handle<SampledImageIdx> {
+        self.transition(device, cb, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+        self.sampled
+    }
+
+    /// Copy the target into `dst`, which must be in `TRANSFER_DST_OPTIMAL` and match the target's
+    /// extent and texel size, such as an acquired swapchain image.  Record after ending rendering.
+    pub fn copy_to(&mut self, device: &Device, cb: vk::CommandBuffer, dst: vk::Image) {
+        let area = vk::Rect2D {
+            offset: vk::Offset2D::default(),
+            extent: self.extent(),
+        };
+        self.copy_area_to(device, cb, dst, area);
+    }
+
+    /// [Copy](Self::copy_to) only `area`, to the same place in `dst`, such as one tile of a window.
+    pub fn copy_area_to(
+        &mut self,
+        device: &Device,
+        cb: vk::CommandBuffer,
+        dst: vk::Image,
+        area: vk::Rect2D,
+    ) {
+        self.transition(device, cb, vk::ImageLayout::TRANSFER_SRC_OPTIMAL);
+        let offset = vk::Offset3D {
+            x: area.offset.x,
+            y: area.offset.y,
+            z: 0,
+        };
+        let region = self
+            .region()
+            .src_offset(offset)
+            .dst_offset(offset)
+            .extent(vk::Extent3D {
+                width: area.extent.width,
+                height: area.extent.height,
+                depth: 1,
+            });
+        unsafe {
+            device.as_raw().cmd_copy_image(
+                cb,
+                self.image.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                dst,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[region],
+            );
+        }
+    }
+
+    /// Copy the target into `dst`, tightly packed with the top row first, such as for reading back
+    /// on the host.  Record after ending rendering, and a barrier before the reads of `dst`.
+    pub fn copy_to_buffer(&mut self, device: &Device, cb: vk::CommandBuffer, dst: vk::Buffer) {
+        self.transition(device, cb, vk::ImageLayout::TRANSFER_SRC_OPTIMAL);
+        unsafe {
+            device.as_raw().cmd_copy_image_to_buffer(
+                cb,
+                self.image.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                dst,
+                &[buffer::buffer_image_copy_full(self.extent())],
+            );
+        }
+    }
+
+    /// Replace the contents with `src`, which must be in `TRANSFER_SRC_OPTIMAL` after its writes are
+    /// visible to transfers, and match the target's extent and texel size.  Record after ending
+    /// rendering.
+    pub fn copy_from(&mut self, device: &Device, cb: vk::CommandBuffer, src: vk::Image) {
+        self.transition(device, cb, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+        unsafe {
+            device.as_raw().cmd_copy_image(
+                cb,
+                src,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                self.image.image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[self.region()],
+            );
+        }
+    }
+
+    fn region(&self) -> vk::ImageCopy {
+        let layers = vk::ImageSubresourceLayers::default()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .layer_count(1);
+        let extent = self.extent();
+        vk::ImageCopy::default()
+            .src_subresource(layers)
+            .dst_subresource(layers)
+            .extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+    }
+
+    /// Forget the contents, such as after abandoning a recording that used the target.  The next
+    /// use transitions from `UNDEFINED`.
+    pub fn discard(&mut self) {
+        self.layout = vk::ImageLayout::UNDEFINED;
+    }
+
+    fn transition(&mut self, device: &Device, cb: vk::CommandBuffer, layout: vk::ImageLayout) {
+        if self.layout == layout {
+            return;
+        }
+        self.image
+            .transition_layout(cb, image::range(), self.layout, layout, device);
+        self.layout = layout;
+    }
+
+    /// Caller must drain work that drew into or sampled the target first.
+    pub fn destroy(self, device: &Device) -> Result<(), VulkanError> {
+        device.descriptors.release(self.sampled);
+        self.view.destroy(device)?;
+        self.image.destroy(device)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn offscreen_target() {
+        with_context!(|device| {
+            let extent = vk::Extent2D {
+                width: 64,
+                height: 32,
+            };
+            let mut target =
+                OffscreenTarget::new(&device, extent, vk::Format::R8G8B8A8_UNORM).unwrap();
+            assert_eq!(target.extent().width, 64);
+            assert_eq!(target.layout(), vk::ImageLayout::UNDEFINED);
+            assert!(device.descriptors.resolve(target.handle()).is_some());
+            target.discard();
+            assert_eq!(target.layout(), vk::ImageLayout::UNDEFINED);
+            target.destroy(&device).unwrap();
+        });
+    }
+}
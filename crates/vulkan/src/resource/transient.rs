@@ -0,0 +1,292 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Transient Buffers
+//!
+//! Nodes need scratch storage that only lives for one frame: reduction partials, particle sort
+//! keys, intermediate spectra.  Allocating device memory per frame is slow and the driver limits how
+//! many allocations may exist at once, so the [`TransientPool`] allocates one large device-local
+//! buffer per frame in flight up front and hands out ranges of it with a bump cursor.
+//!
+//! ## Frame Boundaries
+//!
+//! A slot may only be reused after every submission that used its ranges has finished.  Like the
+//! [`PoolRing`](crate::dispatch::pool::PoolRing), each slot remembers the timeline value that its
+//! last frame promised to signal.  [`TransientPool::begin_frame`] waits on that value before
+//! rewinding the slot's cursor.  Pass the same `WaitValue` that the frame's command pool lease will
+//! signal so that scratch memory and command buffers retire together.
+//!
+//! Ranges are only valid until the slot comes around again.  Holding one longer is a use after
+//! free on the GPU.
+//!
+//! ## Tuning
+//!
+//! [`TransientPool::stats`] keeps the most bytes any frame used.  Hosts read it once per frame,
+//! before [`begin_frame`](TransientPool::begin_frame) rewinds the cursor, and show it with their
+//! frame statistics so that the capacity can be sized to real frames.
+
+// MAYBE grow by retiring the buffer on overflow and allocating a bigger one for the next lap.
+// Today an overflow is an error so that the budget is tuned rather than hidden.
+
+use crate::internal::*;
+use crate::util;
+
+/// Vulkan caps `minStorageBufferOffsetAlignment` at 256 bytes, so aligning every range to it is
+/// valid on every device without querying limits.
+pub const RANGE_ALIGNMENT: vk::DeviceSize = 256;
+
+/// A slice of a transient buffer, valid for the current frame only.
+#[derive(Clone, Copy, Debug)]
+pub struct TransientRange {
+    pub buffer: vk::Buffer,
+    pub offset: vk::DeviceSize,
+    pub size: vk::DeviceSize,
+    /// Device address of the first byte of the range.
+    pub address: vk::DeviceAddress,
+}
+
+/// Usage summary for tuning the pool capacity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransientStats {
+    pub capacity: vk::DeviceSize,
+    /// Bytes handed out so far in the current frame.
+    pub used: vk::DeviceSize,
+    /// Most bytes handed out in any single frame.
+    pub high_water: vk::DeviceSize,
+}
+
+/// Bump allocation bookkeeping, separate from the Vulkan objects so it can be reasoned about alone.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Bump {
+    pub(crate) cursor: vk::DeviceSize,
+    pub(crate) capacity: vk::DeviceSize,
+    pub(crate) high_water: vk::DeviceSize,
+}
+
+impl Bump {
+    pub(crate) fn new(capacity: vk::DeviceSize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    /// Offset of a new range, or `None` when the frame's budget is exhausted.
+    pub(crate) fn alloc(&mut self, size: vk::DeviceSize, align: vk::DeviceSize) -> Option<vk::DeviceSize> {
+        let align = align.max(1);
+        let offset = self.cursor.div_ceil(align) * align;
+        let end = offset.checked_add(size)?;
+        if end > self.capacity {
+            return None;
+        }
+        self.cursor = end;
+        self.high_water = self.high_water.max(end);
+        Some(offset)
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.cursor = 0;
+    }
+}
+
+struct Slot {
+    buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+    address: vk::DeviceAddress,
+    /// Timeline value the slot's previous frame promised to signal.
+    done: Option<WaitValue>,
+}
+
+/// Per-frame scratch buffers for `N` frames in flight.
+pub struct TransientPool<const N: usize = 2> {
+    slots: Vec<Slot>,
+    bump: Bump,
+    cursor: usize,
+    /// Slot that `alloc` draws from.  `None` until the first `begin_frame`.
+    current: Option<usize>,
+}
+
+impl<const N: usize> TransientPool<N> {
+    /// Allocate `N` buffers of `capacity` bytes each.
+    pub fn new(device: &Device, capacity: vk::DeviceSize) -> Result<Self, VulkanError> {
+        const { assert!(N >= 1, "TransientPool requires at least one slot") };
+        let mut pool = Self {
+            slots: Vec::with_capacity(N),
+            bump: Bump::new(capacity),
+            cursor: 0,
+            current: None,
+        };
+        for _ in 0..N {
+            match Self::make_slot(device, capacity) {
+                Ok(slot) => pool.slots.push(slot),
+                Err(e) => {
+                    // DEBT manual destruction of partially constructed pool resources
+                    pool.destroy(device);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(pool)
+    }
+
+    fn make_slot(device: &Device, capacity: vk::DeviceSize) -> Result<Slot, VulkanError> {
+        let raw = device.as_raw();
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size(capacity)
+            .usage(
+                vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::TRANSFER_DST
+                    | vk::BufferUsageFlags::TRANSFER_SRC
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            )
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let buffer = unsafe { raw.create_buffer(&buffer_info, None)? };
+        let mem_req = unsafe { raw.get_buffer_memory_requirements(buffer) };
+        // Scratch never leaves the device.  Fall back to any memory type on odd hardware.
+        let memory_type_index = util::find_memory_type_index(
+            &mem_req,
+            &device.memory_props,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        )
+        .or_else(|| {
+            util::find_memory_type_index(
+                &mem_req,
+                &device.memory_props,
+                vk::MemoryPropertyFlags::empty(),
+            )
+        });
+        let Some(memory_type_index) = memory_type_index else {
+            unsafe { raw.destroy_buffer(buffer, None) };
+            return Err(VulkanError::OutOfDeviceMemory);
+        };
+
+        let mut flags =
+            vk::MemoryAllocateFlagsInfo::default().flags(vk::MemoryAllocateFlags::DEVICE_ADDRESS);
+        let alloc_info = vk::MemoryAllocateInfo::default()
+            .allocation_size(mem_req.size)
+            .memory_type_index(memory_type_index)
+            .push_next(&mut flags);
+        let memory = match unsafe { raw.allocate_memory(&alloc_info, None) } {
+            Ok(memory) => memory,
+            Err(e) => {
+                unsafe { raw.destroy_buffer(buffer, None) };
+                return Err(e.into());
+            }
+        };
+        unsafe { raw.bind_buffer_memory(buffer, memory, 0)? };
+        let info = vk::BufferDeviceAddressInfo::default().buffer(buffer);
+        let address = unsafe { raw.get_buffer_device_address(&info) };
+
+        Ok(Slot {
+            buffer,
+            memory,
+            address,
+            done: None,
+        })
+    }
+
+    /// Move to the next slot, waiting until the GPU has retired its previous frame.  `done` is the
+    /// timeline value that this frame's final submission will signal.
+    pub fn begin_frame(
+        &mut self,
+        device: &Device,
+        done: WaitValue,
+        timeout: u64,
+    ) -> Result<(), VulkanError> {
+        let slot = &mut self.slots[self.cursor];
+        if let Some(previous) = slot.done.take() {
+            previous.wait(device, timeout)?;
+        }
+        slot.done = Some(done);
+        self.bump.reset();
+        self.current = Some(self.cursor);
+        self.cursor = (self.cursor + 1) % N;
+        Ok(())
+    }
+
+    /// Hand out `size` bytes from the current frame's buffer.  Ranges are aligned for use as
+    /// storage buffer bindings and device addresses.
+    pub fn alloc(&mut self, size: vk::DeviceSize) -> Result<TransientRange, VulkanError> {
+        let slot = self
+            .current
+            .map(|i| &self.slots[i])
+            .ok_or(VulkanError::NotInFrame("transient alloc"))?;
+        let offset =
+            self.bump
+                .alloc(size, RANGE_ALIGNMENT)
+                .ok_or(VulkanError::TransientExhausted {
+                    requested: size,
+                    capacity: self.bump.capacity,
+                })?;
+        Ok(TransientRange {
+            buffer: slot.buffer,
+            offset,
+            size,
+            address: slot.address + offset,
+        })
+    }
+
+    pub fn stats(&self) -> TransientStats {
+        TransientStats {
+            capacity: self.bump.capacity,
+            used: self.bump.cursor,
+            high_water: self.bump.high_water,
+        }
+    }
+
+    /// Wait for every slot's last frame, then free the buffers.
+    pub fn destroy(self, device: &Device) {
+        let raw = device.as_raw();
+        for slot in self.slots {
+            if let Some(done) = slot.done {
+                let _ = done.wait(device, u64::MAX);
+            }
+            unsafe {
+                raw.destroy_buffer(slot.buffer, None);
+                raw.free_memory(slot.memory, None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn transient_bump() {
+        let mut bump = Bump::new(1024);
+        assert_eq!(bump.alloc(100, RANGE_ALIGNMENT), Some(0));
+        assert_eq!(bump.alloc(100, RANGE_ALIGNMENT), Some(256));
+        assert_eq!(bump.alloc(600, RANGE_ALIGNMENT), None);
+        assert_eq!(bump.alloc(512, RANGE_ALIGNMENT), Some(512));
+        assert_eq!(bump.high_water, 1024);
+
+        bump.reset();
+        assert_eq!(bump.alloc(8, RANGE_ALIGNMENT), Some(0));
+        assert_eq!(bump.cursor, 8);
+        assert_eq!(bump.high_water, 1024);
+    }
+
+    #[test]
+    fn transient_pool_frames() {
+        with_context!(|device| {
+            let mut pool = TransientPool::<2>::new(&device, 4096).unwrap();
+            let mut timeline = device.make_timeline_semaphore().unwrap();
+            for _ in 0..4 {
+                let intent = timeline.next_signal();
+                pool.begin_frame(&device, intent.wait_value(), u64::MAX)
+                    .unwrap();
+                let a = pool.alloc(1000).unwrap();
+                let b = pool.alloc(1000).unwrap();
+                assert_eq!(b.offset, 1024);
+                assert_eq!(b.address, a.address + 1024);
+                assert!(pool.alloc(4096).is_err());
+                intent.try_consume(&device, u64::MAX).unwrap();
+            }
+            assert_eq!(pool.stats().high_water, 2024);
+            pool.destroy(&device);
+            timeline.destroy(&device);
+        });
+    }
+}
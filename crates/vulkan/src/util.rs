@@ -8,8 +8,6 @@
 
 use ash::vk;
 
-use crate::VulkanError;
-
 /// Implementation is just a first pass to get going.
 // Probably goes in a dedicated memory management module.
 // DEBT binding
@@ -29,35 +27,3 @@ pub fn find_memory_type_index(
 
     None
 }
-
-/// Layouts and descriptor management in general are annoying.
-// Probably goes into a dedicated binding module.
-// ROLL move spectrum to new descriptor style
-pub fn descriptor_set_layout(device: &ash::Device) -> Result<vk::DescriptorSetLayout, VulkanError> {
-    let bindings = [
-        vk::DescriptorSetLayoutBinding {
-            binding: 0, // spectrum
-            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
-            descriptor_count: 1,
-            stage_flags: vk::ShaderStageFlags::COMPUTE,
-            p_immutable_samplers: std::ptr::null(),
-            ..Default::default()
-        },
-        vk::DescriptorSetLayoutBinding {
-            binding: 1, // output buffer
-            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
-            descriptor_count: 1,
-            stage_flags: vk::ShaderStageFlags::COMPUTE,
-            p_immutable_samplers: std::ptr::null(),
-            ..Default::default()
-        },
-    ];
-
-    let layout_info = vk::DescriptorSetLayoutCreateInfo {
-        binding_count: bindings.len() as u32,
-        p_bindings: bindings.as_ptr(),
-        ..Default::default()
-    };
-
-    Ok(unsafe { device.create_descriptor_set_layout(&layout_info, None)? })
-}
@@ -8,7 +8,8 @@
 /// warp size on the device, usually 32 lanes.
 use ash::vk;
 use mutate_lib::{self as utate, prelude::*};
-use utate::vulkan::resource::{buffer, image};
+use utate::gpu::device::descriptors::Handle;
+use utate::gpu::resource::{buffer, image};
 
 #[compute_pipeline(
     compute = stage!("hello/compute", Compute, c"main"),
@@ -26,7 +27,7 @@ pub struct HelloDraw {
     counter: u32,
 
     output_buffer: Option<buffer::MappedAllocation<rgb::Rgba<u8>>>,
-    output_idx: SsboIdx,
+    output_idx: Option<Handle<SsboIdx>>,
 }
 
 impl HelloDraw {
@@ -35,7 +36,7 @@ impl HelloDraw {
             pipeline: ComputePipeline::<HelloPipeline>::new(device).unwrap(),
             counter: 0,
             output_buffer: None,
-            output_idx: SsboIdx::INVALID,
+            output_idx: None,
         }
     }
 
@@ -47,15 +48,16 @@ impl HelloDraw {
         if let Some(existing) = self.output_buffer.take() {
             unsafe {
                 existing.destroy(device)?;
-                device.descriptors.unbind_ssbo(self.output_idx);
             }
-            self.output_idx = SsboIdx::INVALID;
+        }
+        if let Some(output_idx) = self.output_idx.take() {
+            device.descriptors.release(output_idx);
         }
 
         let output_buffer =
             buffer::MappedAllocation::new((size.width * size.height) as usize, device)?;
 
-        self.output_idx = output_buffer.bound(device);
+        self.output_idx = Some(output_buffer.register(device)?);
         self.output_buffer = Some(output_buffer);
 
         Ok(())
@@ -79,7 +81,7 @@ impl HelloDraw {
             counter: self.counter.into(),
             window_width: (extent.width as f32).into(),
             window_height: (extent.height as f32).into(),
-            output_idx: self.output_idx,
+            output_idx: self.output_idx.unwrap().index(),
         };
         // XXX allow pushing to wrapped buffers
         self.pipeline.push(device, **cb, &push);
@@ -119,7 +121,9 @@ impl HelloDraw {
             self.pipeline.destroy(device);
             if let Some(allocated) = self.output_buffer {
                 allocated.destroy(&device)?;
-                device.descriptors.unbind_ssbo(self.output_idx);
+            }
+            if let Some(output_idx) = self.output_idx {
+                device.descriptors.release(output_idx);
             }
         }
         Ok(())
@@ -86,9 +86,9 @@ impl WindowContext {
                                 || {},
                             )
                             .map_err(|e| match e {
-                                utate::vulkan::VulkanError::SwapchainOutOfDate
-                                | utate::vulkan::VulkanError::SwapchainSuboptimal
-                                | utate::vulkan::VulkanError::SwapchainRecreationRequired => {
+                                utate::gpu::VulkanError::SwapchainOutOfDate
+                                | utate::gpu::VulkanError::SwapchainSuboptimal
+                                | utate::gpu::VulkanError::SwapchainRecreationRequired => {
                                     let new_size = present_ring
                                         .maybe_update_swapchain(device, &mut surface, extent)
                                         .unwrap();
@@ -145,7 +145,11 @@ impl ActiveApp {
         let selected = supported[0].clone();
         println!("device: {}", selected.name);
         // Once we have chosen the physical device, then we can create the logical device.
-        let device: &'static Device = Box::leak(Box::new(selected.into_logical(instance)));
+        let device: &'static Device = Box::leak(Box::new(
+            selected
+                .into_logical(instance)
+                .expect("could not create the logical device"),
+        ));
         // Rendering gear is created inside the self-contained render thread scope.  We will talk to
         // it via a channel.
         let window_context = WindowContext::spawn(instance, device, &window, raw_surface);
@@ -241,7 +245,7 @@ impl ApplicationHandler for MinimalApp {
 
 fn main() -> Result<(), utate::MutateError> {
     let event_loop = EventLoop::builder().build().unwrap();
-    let instance: &'static Instance = Box::leak(Box::new(Instance::with_display(&event_loop, &[])));
+    let instance: &'static Instance = Box::leak(Box::new(Instance::with_display(&event_loop, &[])?));
     let mut app = MinimalApp {
         instance,
         state: AppState::Dormant,
@@ -125,6 +125,8 @@
         buildInputs = with pkgs; [
           pkg-config
           mesa.drivers
+          # MIDI through ALSA, for the `midi` feature.
+          alsa-lib
         ] ++ pipewireDeps ++ vulkanDeps ++ rustDeps;
 
         shellHook = ''
@@ -3,7 +3,28 @@
 
 use mutate_assets as assets;
 
+// The window shapes and factors only depend on std, num-complex, and toml, so they are included
+// as-is rather than duplicated.
+#[cfg(feature = "dsp")]
+#[allow(dead_code)]
+#[path = "src/dsp/window/factors.rs"]
+mod factors;
+#[cfg(feature = "dsp")]
+#[allow(dead_code)]
+#[path = "src/dsp/window/shapes.rs"]
+mod shapes;
+
 fn main() {
     assets::build::set_asset_default_dir();
     assets::build::build_shaders();
+
+    #[cfg(feature = "dsp")]
+    {
+        println!("cargo:rerun-if-changed=src/dsp/window/shapes.rs");
+        println!("cargo:rerun-if-changed=src/dsp/window/factors.rs");
+        let table = factors::FactorTable::generate();
+        assets::build::write_table("windows", &table.to_toml());
+    }
+
+    assets::build::write_pack();
 }
@@ -0,0 +1,222 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Channel Maps
+//!
+//! A six channel stream read as stereo puts the center and LFE of the first frame into the left and
+//! right of the third.  A [`ChannelMap`] says which speaker each interleaved channel feeds so that
+//! consumers can downmix surround to stereo or take channels one at a time.
+//!
+//! Audio servers report positions with the format.  Files and other sources that carry no
+//! positions use the conventional layout for their channel count, see [`ChannelMap::standard`].
+//!
+//! Downmixing follows ITU-R BS.775: the center and surrounds are folded into both sides at -3dB
+//! and the LFE is dropped.  Each side is then scaled so that full-scale input cannot clip.
+
+/// The speaker one interleaved channel feeds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Mono,
+    FrontLeft,
+    FrontRight,
+    FrontCenter,
+    LowFrequency,
+    SideLeft,
+    SideRight,
+    RearLeft,
+    RearRight,
+    RearCenter,
+    /// A position we do not mix, such as height channels.  Dropped by downmixes.
+    Unknown,
+}
+
+/// -3dB, the share of a center or surround channel that goes to each side.
+const FOLD: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+impl Channel {
+    /// Share of this channel in the left and right of a stereo downmix, before normalizing.
+    fn stereo(self) -> [f32; 2] {
+        match self {
+            Self::Mono => [1.0, 1.0],
+            Self::FrontLeft => [1.0, 0.0],
+            Self::FrontRight => [0.0, 1.0],
+            Self::FrontCenter | Self::RearCenter => [FOLD, FOLD],
+            Self::SideLeft | Self::RearLeft => [FOLD, 0.0],
+            Self::SideRight | Self::RearRight => [0.0, FOLD],
+            Self::LowFrequency | Self::Unknown => [0.0, 0.0],
+        }
+    }
+
+    /// Convert a PipeWire `spa_audio_channel`.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn from_spa(position: u32) -> Self {
+        use pipewire::spa::sys;
+        match position {
+            sys::SPA_AUDIO_CHANNEL_MONO => Self::Mono,
+            sys::SPA_AUDIO_CHANNEL_FL => Self::FrontLeft,
+            sys::SPA_AUDIO_CHANNEL_FR => Self::FrontRight,
+            sys::SPA_AUDIO_CHANNEL_FC => Self::FrontCenter,
+            sys::SPA_AUDIO_CHANNEL_LFE => Self::LowFrequency,
+            sys::SPA_AUDIO_CHANNEL_SL => Self::SideLeft,
+            sys::SPA_AUDIO_CHANNEL_SR => Self::SideRight,
+            sys::SPA_AUDIO_CHANNEL_RL => Self::RearLeft,
+            sys::SPA_AUDIO_CHANNEL_RR => Self::RearRight,
+            sys::SPA_AUDIO_CHANNEL_RC => Self::RearCenter,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Positions of the interleaved channels of a stream, in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChannelMap(Vec<Channel>);
+
+impl ChannelMap {
+    pub fn new(positions: Vec<Channel>) -> Self {
+        Self(positions)
+    }
+
+    /// The conventional layout for `channels`, the same one PipeWire and WAV assume when a source
+    /// does not say: mono, stereo, 2.1, quad, 5.0, 5.1, 6.1, and 7.1.  Larger counts are unknown.
+    pub fn standard(channels: u32) -> Self {
+        use Channel::*;
+        let positions: &[Channel] = match channels {
+            1 => &[Mono],
+            2 => &[FrontLeft, FrontRight],
+            3 => &[FrontLeft, FrontRight, LowFrequency],
+            4 => &[FrontLeft, FrontRight, RearLeft, RearRight],
+            5 => &[FrontLeft, FrontRight, FrontCenter, RearLeft, RearRight],
+            6 => &[
+                FrontLeft,
+                FrontRight,
+                FrontCenter,
+                LowFrequency,
+                RearLeft,
+                RearRight,
+            ],
+            7 => &[
+                FrontLeft,
+                FrontRight,
+                FrontCenter,
+                LowFrequency,
+                RearCenter,
+                SideLeft,
+                SideRight,
+            ],
+            8 => &[
+                FrontLeft,
+                FrontRight,
+                FrontCenter,
+                LowFrequency,
+                RearLeft,
+                RearRight,
+                SideLeft,
+                SideRight,
+            ],
+            n => return Self(vec![Unknown; n as usize]),
+        };
+        Self(positions.to_vec())
+    }
+
+    /// Convert PipeWire positions.  Servers that leave every position unknown get the standard
+    /// layout for the count.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn from_spa(positions: &[u32]) -> Self {
+        let map = Self(positions.iter().map(|&p| Channel::from_spa(p)).collect());
+        if map.0.iter().all(|&c| c == Channel::Unknown) {
+            return Self::standard(positions.len() as u32);
+        }
+        map
+    }
+
+    pub fn positions(&self) -> &[Channel] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Index of the interleaved channel that feeds `channel`.
+    pub fn find(&self, channel: Channel) -> Option<usize> {
+        self.0.iter().position(|&c| c == channel)
+    }
+
+    /// Whether reading as stereo needs a downmix rather than taking the first two channels.
+    pub fn is_surround(&self) -> bool {
+        self.0.len() > 2
+    }
+
+    /// Left and right weight of each interleaved channel in a stereo downmix.
+    pub fn stereo_weights(&self) -> Vec<[f32; 2]> {
+        let mut weights: Vec<[f32; 2]> = self.0.iter().map(|c| c.stereo()).collect();
+        for side in 0..2 {
+            let total: f32 = weights.iter().map(|w| w[side]).sum();
+            if total > 1.0 {
+                weights.iter_mut().for_each(|w| w[side] /= total);
+            }
+        }
+        weights
+    }
+
+    /// Downmix one interleaved frame to stereo.  Prefer [`stereo_weights`](Self::stereo_weights)
+    /// for more than a few frames.
+    pub fn downmix(&self, frame: &[f32]) -> [f32; 2] {
+        mix(&self.stereo_weights(), frame)
+    }
+}
+
+/// Apply `weights` from [`ChannelMap::stereo_weights`] to one interleaved frame.
+pub(crate) fn mix(weights: &[[f32; 2]], frame: &[f32]) -> [f32; 2] {
+    weights
+        .iter()
+        .zip(frame)
+        .fold([0.0; 2], |[l, r], (w, s)| [l + w[0] * s, r + w[1] * s])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_standard_maps() {
+        for channels in 1..=10 {
+            assert_eq!(ChannelMap::standard(channels).len(), channels as usize);
+        }
+        let surround = ChannelMap::standard(6);
+        assert!(surround.is_surround());
+        assert_eq!(surround.find(Channel::LowFrequency), Some(3));
+        assert!(!ChannelMap::standard(2).is_surround());
+    }
+
+    #[test]
+    fn test_downmix() {
+        // Stereo passes through, mono feeds both sides.
+        assert_eq!(ChannelMap::standard(2).downmix(&[0.25, 0.5]), [0.25, 0.5]);
+        assert_eq!(ChannelMap::standard(1).downmix(&[0.5]), [0.5, 0.5]);
+
+        // Full scale on every 5.1 channel is full scale on both sides, LFE or not.
+        let surround = ChannelMap::standard(6);
+        let [l, r] = surround.downmix(&[1.0; 6]);
+        assert!((l - 1.0).abs() < 1e-6 && (r - 1.0).abs() < 1e-6);
+        assert_eq!(
+            surround.downmix(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0]),
+            [0.0, 0.0]
+        );
+
+        // Center is -3dB relative to the fronts on both sides.
+        let [l, r] = surround.downmix(&[0.0, 0.0, 1.0, 0.0, 0.0, 0.0]);
+        let [front, _] = surround.downmix(&[1.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(l, r);
+        assert!((l / front - FOLD).abs() < 1e-6);
+
+        // 7.1 keeps sides apart.
+        let [l, r] = ChannelMap::standard(8).downmix(&[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+        assert!(l > 0.0);
+        assert_eq!(r, 0.0);
+    }
+}
@@ -0,0 +1,458 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Demo Backend
+//!
+//! A first run on a quiet machine should not be a black window.  [`AudioContext::demo`] returns a
+//! context with a single choice that plays a [`DemoSong`], a small generative loop synthesized in
+//! real time.  Connections receive it through the same ring and timing path as a real stream, so
+//! every stage downstream runs exactly as it would on music.
+//!
+//! The song is built to exercise the visuals rather than to be listened to:
+//!
+//! - Kick, snare, and hats on a steady grid give the beat tracker something to lock to.
+//! - A bass line, pad chords, and an arpeggio fill the spectrum from sub-bass to the top octaves.
+//! - Sections with different instrumentation repeat, so segmentation sees both novelty and repeats.
+//! - Hats and the arpeggio are panned apart so stereo views have width.
+//!
+//! Noise comes from a fixed seed.  Two runs produce identical samples, which keeps screenshots and
+//! tests reproducible.
+
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use ringbuf::traits::Producer;
+
+use super::*;
+
+/// Name of the only choice in a demo context.
+pub const DEMO_NAME: &str = "µTate demo";
+/// Identifier of the only choice in a demo context.
+const DEMO_ID: u32 = 0;
+/// Sample rate of the demo stream.
+pub const DEMO_RATE: u32 = 48_000;
+/// Frames per chunk.  Chunks are paced against the wall clock like a real server's quantum.
+const DEMO_QUANTUM: usize = 512;
+
+const TEMPO_BPM: f64 = 116.0;
+/// Steps are sixteenth notes.
+const STEPS_PER_BAR: usize = 16;
+const BARS_PER_SECTION: usize = 4;
+
+/// Root and quality of each bar's chord, as MIDI notes.  Am F C G.
+const PROGRESSION: [(u8, bool); 4] = [(57, true), (53, false), (48, false), (55, false)];
+
+/// Which instruments play in a section.
+#[derive(Clone, Copy)]
+struct Arrangement {
+    kick: bool,
+    snare: bool,
+    hats: bool,
+    bass: bool,
+    pad: bool,
+    arp: bool,
+}
+
+/// Verse, chorus, verse, breakdown.  The verses repeat and the breakdown drops the drums.
+const SECTIONS: [Arrangement; 4] = [
+    Arrangement {
+        kick: true,
+        snare: true,
+        hats: true,
+        bass: true,
+        pad: false,
+        arp: false,
+    },
+    Arrangement {
+        kick: true,
+        snare: true,
+        hats: true,
+        bass: true,
+        pad: true,
+        arp: true,
+    },
+    Arrangement {
+        kick: true,
+        snare: true,
+        hats: true,
+        bass: true,
+        pad: false,
+        arp: false,
+    },
+    Arrangement {
+        kick: false,
+        snare: false,
+        hats: false,
+        bass: false,
+        pad: true,
+        arp: true,
+    },
+];
+
+fn midi_hz(note: u8) -> f64 {
+    440.0 * 2f64.powf((note as f64 - 69.0) / 12.0)
+}
+
+/// Exponential decay envelope, re-triggered by notes.
+#[derive(Clone, Copy, Default)]
+struct Decay {
+    level: f64,
+    factor: f64,
+}
+
+impl Decay {
+    fn new(seconds: f64, fs: f64) -> Self {
+        Self {
+            level: 0.0,
+            factor: (-1.0 / (seconds * fs)).exp(),
+        }
+    }
+
+    fn trigger(&mut self, level: f64) {
+        self.level = level;
+    }
+
+    fn next(&mut self) -> f64 {
+        let level = self.level;
+        self.level *= self.factor;
+        level
+    }
+}
+
+/// A generative loop of drums, bass, chords, and arpeggio.  Produces interleaved stereo.
+pub struct DemoSong {
+    fs: f64,
+    samples_per_step: f64,
+    /// Sample clock.  The song is a pure function of it plus the noise state.
+    n: u64,
+    step: Option<usize>,
+    noise: u32,
+
+    kick: Decay,
+    kick_phase: f64,
+    snare: Decay,
+    hat: Decay,
+    hat_last: f64,
+    bass: Decay,
+    bass_phase: f64,
+    bass_hz: f64,
+    bass_lp: f64,
+    pad_phases: [f64; 6],
+    pad_lp: [f64; 2],
+    arp: Decay,
+    arp_phase: f64,
+    arp_hz: f64,
+    arp_pan: f64,
+}
+
+impl DemoSong {
+    pub fn new(rate: u32) -> Self {
+        let fs = rate as f64;
+        Self {
+            fs,
+            samples_per_step: fs * 60.0 / TEMPO_BPM / 4.0,
+            n: 0,
+            step: None,
+            noise: 0x9e37_79b9,
+            kick: Decay::new(0.12, fs),
+            kick_phase: 0.0,
+            snare: Decay::new(0.08, fs),
+            hat: Decay::new(0.025, fs),
+            hat_last: 0.0,
+            bass: Decay::new(0.2, fs),
+            bass_phase: 0.0,
+            bass_hz: 0.0,
+            bass_lp: 0.0,
+            pad_phases: [0.0; 6],
+            pad_lp: [0.0; 2],
+            arp: Decay::new(0.09, fs),
+            arp_phase: 0.0,
+            arp_hz: 0.0,
+            arp_pan: 0.5,
+        }
+    }
+
+    /// Length of one pass through every section.
+    pub fn loop_length(&self) -> Duration {
+        let steps = STEPS_PER_BAR * BARS_PER_SECTION * SECTIONS.len();
+        Duration::from_secs_f64(steps as f64 * self.samples_per_step / self.fs)
+    }
+
+    /// Time between beats.
+    pub fn beat_period(&self) -> Duration {
+        Duration::from_secs_f64(60.0 / TEMPO_BPM)
+    }
+
+    /// Fill interleaved stereo frames.
+    pub fn fill(&mut self, frames: &mut [[f32; 2]]) {
+        for frame in frames {
+            let (l, r) = self.next_frame();
+            *frame = [l as f32, r as f32];
+        }
+    }
+
+    /// Uniform noise in `-1.0..1.0` from a fixed seed.
+    fn white(&mut self) -> f64 {
+        // xorshift32
+        self.noise ^= self.noise << 13;
+        self.noise ^= self.noise >> 17;
+        self.noise ^= self.noise << 5;
+        self.noise as f64 / u32::MAX as f64 * 2.0 - 1.0
+    }
+
+    /// Start the notes of a new sixteenth.
+    fn on_step(&mut self, step: usize) {
+        let bar = step / STEPS_PER_BAR;
+        let beat_step = step % STEPS_PER_BAR;
+        let section = SECTIONS[bar / BARS_PER_SECTION % SECTIONS.len()];
+        let (root, minor) = PROGRESSION[bar % PROGRESSION.len()];
+        let third = if minor { 3 } else { 4 };
+        let chord = [root, root + third, root + 7, root + 12];
+
+        if section.kick && beat_step % 4 == 0 {
+            self.kick.trigger(1.0);
+            self.kick_phase = 0.0;
+        }
+        if section.snare && beat_step % 8 == 4 {
+            self.snare.trigger(0.6);
+        }
+        if section.hats && beat_step % 2 == 0 {
+            // Off-beat hats are accented.
+            self.hat
+                .trigger(if beat_step % 4 == 2 { 0.35 } else { 0.15 });
+        }
+        // Eighth note bass with a syncopated octave jump.
+        if section.bass && beat_step % 2 == 0 {
+            let octave = if beat_step == 6 || beat_step == 14 {
+                0
+            } else {
+                12
+            };
+            self.bass_hz = midi_hz(root - 24 + octave);
+            self.bass.trigger(0.5);
+        }
+        if section.arp {
+            let note = chord[step % chord.len()] + 12;
+            self.arp_hz = midi_hz(note);
+            self.arp.trigger(0.22);
+            self.arp_pan = if step % 2 == 0 { 0.2 } else { 0.8 };
+        }
+    }
+
+    fn next_frame(&mut self) -> (f64, f64) {
+        let step = (self.n as f64 / self.samples_per_step) as usize;
+        if self.step != Some(step) {
+            self.step = Some(step);
+            self.on_step(step);
+        }
+        let bar = step / STEPS_PER_BAR;
+        let section = SECTIONS[bar / BARS_PER_SECTION % SECTIONS.len()];
+        let (root, minor) = PROGRESSION[bar % PROGRESSION.len()];
+        let dt = 1.0 / self.fs;
+        let tau = std::f64::consts::TAU;
+
+        // Kick sweeps down from a click to a thump.
+        let kick_env = self.kick.next();
+        let kick_hz = 45.0 + 110.0 * kick_env * kick_env;
+        self.kick_phase += kick_hz * dt;
+        let kick = (tau * self.kick_phase).sin() * kick_env;
+
+        let noise = self.white();
+        let snare =
+            (0.7 * noise + 0.3 * (tau * 190.0 * self.n as f64 * dt).sin()) * self.snare.next();
+
+        // First difference of white noise tilts it toward the top octaves.
+        let hat = (noise - self.hat_last) * 0.5 * self.hat.next();
+        self.hat_last = noise;
+
+        // A saw through a one-pole lowpass.
+        self.bass_phase = (self.bass_phase + self.bass_hz * dt).fract();
+        let saw = 2.0 * self.bass_phase - 1.0;
+        self.bass_lp += 0.08 * (saw - self.bass_lp);
+        let bass = self.bass_lp * self.bass.next();
+
+        // Three detuned voices per side make the pad wide.
+        let (mut pad_l, mut pad_r) = (0.0, 0.0);
+        if section.pad {
+            let third = if minor { 3 } else { 4 };
+            let notes = [root, root + third, root + 7];
+            for (i, note) in notes.iter().enumerate() {
+                let hz = midi_hz(*note);
+                for side in 0..2 {
+                    let detune = if side == 0 { 0.998 } else { 1.002 };
+                    let p = &mut self.pad_phases[i * 2 + side];
+                    *p = (*p + hz * detune * dt).fract();
+                    let saw = 2.0 * *p - 1.0;
+                    if side == 0 {
+                        pad_l += saw;
+                    } else {
+                        pad_r += saw;
+                    }
+                }
+            }
+        }
+        self.pad_lp[0] += 0.03 * (pad_l * 0.06 - self.pad_lp[0]);
+        self.pad_lp[1] += 0.03 * (pad_r * 0.06 - self.pad_lp[1]);
+
+        self.arp_phase = (self.arp_phase + self.arp_hz * dt).fract();
+        let arp = (tau * self.arp_phase).sin() * self.arp.next();
+
+        self.n += 1;
+        let center = kick * 0.8 + snare + bass;
+        let l = center + hat * 0.4 + self.pad_lp[0] + arp * (1.0 - self.arp_pan);
+        let r = center + hat + self.pad_lp[1] + arp * self.arp_pan;
+        (l.clamp(-1.0, 1.0), r.clamp(-1.0, 1.0))
+    }
+}
+
+/// A connection being fed by the demo thread.
+struct Playing {
+    tx: AudioProducer,
+    song: DemoSong,
+}
+
+impl AudioContext {
+    /// A context whose only choice is a synthesized [`DemoSong`].  Use it when there is no audio
+    /// server, no sources, or nothing playing.
+    pub fn demo() -> Self {
+        let choices = Box::into_raw(Box::new(AudioChoices::new()));
+        let choices_addr = choices as usize;
+        let (tx, rx) = mpsc::channel();
+        let events = std::sync::Arc::new(AudioEvents::default());
+        let handle = std::thread::spawn(move || {
+            // Safety: AudioContext::drop joins this thread before freeing choices.
+            let choices: &AudioChoices = unsafe { &*(choices_addr as *mut AudioChoices) };
+            choices.publish([AudioChoice {
+                kind: AudioSourceKind::ApplicationStream,
+                name: Some(DEMO_NAME.to_owned()),
+                application: None,
+                object_serial: DEMO_ID,
+                global_id: DEMO_ID,
+            }]);
+
+            let period = Duration::from_secs_f64(DEMO_QUANTUM as f64 / DEMO_RATE as f64);
+            let mut playing: Vec<Playing> = Vec::new();
+            let mut frames = vec![[0f32; 2]; DEMO_QUANTUM];
+            let mut deadline = Instant::now();
+            loop {
+                // Paced by an absolute deadline so that chunk timing does not drift.
+                let wait = deadline.saturating_duration_since(Instant::now());
+                match rx.recv_timeout(wait) {
+                    Ok(Message::Connect { choice, tx, .. }) => {
+                        if choice.object_serial == DEMO_ID {
+                            let format = StreamFormat {
+                                rate: DEMO_RATE,
+                                channels: 2,
+                            };
+                            if let Err(e) = tx.set_format(format) {
+                                tx.error(e);
+                            }
+                            tx.emit(|stream| AudioEvent::StreamStateChanged {
+                                stream,
+                                state: StreamState::Streaming,
+                            });
+                            playing.push(Playing {
+                                tx,
+                                song: DemoSong::new(DEMO_RATE),
+                            });
+                        }
+                        // Anything else drops the producer, which tombstones the connection.
+                        continue;
+                    }
+                    Ok(Message::Terminate) | Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                }
+                deadline += period;
+                let arrived = Instant::now();
+                playing.retain_mut(|p| {
+                    p.song.fill(&mut frames);
+                    p.tx.set_quantum(DEMO_QUANTUM as u32, DEMO_RATE);
+                    let bytes: Vec<u8> = frames
+                        .iter()
+                        .flatten()
+                        .flat_map(|s| s.to_le_bytes())
+                        .collect();
+                    match p
+                        .tx
+                        .write_with(bytes.len(), arrived, |buf| buf.push_slice(&bytes))
+                    {
+                        Ok(_) => true,
+                        Err(MutateError::Dropped) => false,
+                        Err(e) => {
+                            p.tx.error(e);
+                            true
+                        }
+                    }
+                });
+            }
+        });
+
+        AudioContext {
+            handle: Some(handle),
+            choices,
+            events,
+            tx: Box::new(tx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn render(song: &mut DemoSong, seconds: f64) -> Vec<[f32; 2]> {
+        let mut frames = vec![[0.0; 2]; (seconds * DEMO_RATE as f64) as usize];
+        song.fill(&mut frames);
+        frames
+    }
+
+    #[test]
+    fn test_demo_song_deterministic_and_rhythmic() {
+        let mut a = DemoSong::new(DEMO_RATE);
+        let mut b = DemoSong::new(DEMO_RATE);
+        let beat = (a.beat_period().as_secs_f64() * DEMO_RATE as f64) as usize;
+        let frames = render(&mut a, 4.0);
+        assert_eq!(frames, render(&mut b, 4.0));
+        assert!(frames.iter().flatten().all(|s| s.abs() <= 1.0));
+
+        // Energy right after each beat is well above the energy just before the next.
+        let energy = |from: usize| -> f32 {
+            frames[from..from + beat / 8]
+                .iter()
+                .map(|[l, r]| l * l + r * r)
+                .sum()
+        };
+        for k in 0..6 {
+            let on = energy(k * beat);
+            let before = energy((k + 1) * beat - beat / 8);
+            assert!(on > 4.0 * before, "beat {k}: {on} vs {before}");
+        }
+
+        // The hats and arpeggio are panned, so the channels differ.
+        assert!(frames.iter().any(|[l, r]| (l - r).abs() > 1e-3));
+    }
+
+    #[test]
+    fn test_demo_context_streams() {
+        let context = AudioContext::demo();
+        let mut choice = None;
+        context
+            .with_choices_blocking(|c| choice = c.first().cloned())
+            .unwrap();
+        let choice = choice.unwrap();
+        assert_eq!(choice.name(), DEMO_NAME);
+
+        let mut consumer = context.connect(&choice, "test").unwrap();
+        consumer.wait(Duration::from_secs(1)).unwrap();
+        assert_eq!(
+            consumer.format_change().unwrap().map(|c| c.new),
+            Some(StreamFormat {
+                rate: DEMO_RATE,
+                channels: 2
+            })
+        );
+        let mut buf = vec![0u8; DEMO_QUANTUM * 8];
+        assert!(consumer.read(&mut buf).unwrap() > 0);
+        assert_eq!(consumer.quantum().map(|q| q.frames), Some(512));
+    }
+}
@@ -0,0 +1,300 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # File Backend
+//!
+//! Live audio never plays the same way twice, and a laptop on a train has nothing to listen to.
+//! [`AudioContext::file`] returns a context with a single choice that plays a [`FileSource`], a
+//! WAV or FLAC file decoded up front.  Connections receive it through the same ring and timing path
+//! as a real stream.
+//!
+//! By default the file is paced like a live stream.  Without real-time pacing it plays as fast as
+//! the consumer drains the ring, so offline analysis of a whole track takes only as long as the
+//! analysis.
+//!
+//! At the end of the file, a looping source starts over without a gap.  Otherwise the stream
+//! reports [`StreamState::Paused`] and stays connected, so consumers can drain what is left.
+
+// DEBT the whole file is decoded into memory.  An hour of 48kHz stereo is about 1.4GB.  Stream the
+// decoder once long files matter.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::local::Generator;
+use super::*;
+
+/// Decoded audio and how to play it.
+#[derive(Clone, Debug)]
+pub struct FileSource {
+    name: String,
+    format: StreamFormat,
+    /// Interleaved, whole frames only.
+    samples: Arc<[f32]>,
+    looping: bool,
+    realtime: bool,
+}
+
+impl FileSource {
+    /// Decode a `.wav` or `.flac` file.  The choice is named after the file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, MutateError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let (format, samples) = match extension.as_deref() {
+            Some("wav") => decode_wav(path)?,
+            Some("flac") => decode_flac(path)?,
+            _ => {
+                return Err(MutateError::AudioSource(format!(
+                    "{}: not a WAV or FLAC file",
+                    path.display()
+                )))
+            }
+        };
+        if format.channels == 0 || format.rate == 0 {
+            return Err(MutateError::AudioFormat(format!(
+                "{}: no channels or no rate",
+                path.display()
+            )));
+        }
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(Self::from_samples(&name, format, samples))
+    }
+
+    /// Play interleaved samples that are already in memory.  A trailing partial frame is dropped.
+    pub fn from_samples(name: &str, format: StreamFormat, mut samples: Vec<f32>) -> Self {
+        let channels = format.channels.max(1) as usize;
+        samples.truncate(samples.len() / channels * channels);
+        Self {
+            name: name.to_owned(),
+            format,
+            samples: samples.into(),
+            looping: false,
+            realtime: true,
+        }
+    }
+
+    /// Start over at the end instead of pausing.
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Pace chunks to the file's sample rate, like a live stream.  When `false`, chunks are written
+    /// as fast as the consumer makes room for them and never overrun.
+    pub fn with_realtime(mut self, realtime: bool) -> Self {
+        self.realtime = realtime;
+        self
+    }
+
+    pub fn format(&self) -> StreamFormat {
+        self.format
+    }
+
+    /// Interleaved samples of one pass through the file, for offline analysis.
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// Length of one pass through the file.
+    pub fn duration(&self) -> Duration {
+        let frames = self.samples.len() / self.format.channels.max(1) as usize;
+        Duration::from_secs_f64(frames as f64 / self.format.rate as f64)
+    }
+}
+
+/// Full scale of signed integer samples of `bits` width.
+fn int_scale(bits: u32) -> f32 {
+    1.0 / (1u64 << (bits.clamp(1, 32) - 1)) as f32
+}
+
+fn decode_wav(path: &Path) -> Result<(StreamFormat, Vec<f32>), MutateError> {
+    let error = |e: hound::Error| MutateError::AudioSource(format!("{}: {e}", path.display()));
+    let reader = hound::WavReader::open(path).map_err(error)?;
+    let spec = reader.spec();
+    let samples: Result<Vec<f32>, _> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.into_samples::<f32>().collect(),
+        hound::SampleFormat::Int => {
+            let scale = int_scale(spec.bits_per_sample as u32);
+            reader
+                .into_samples::<i32>()
+                .map(|s| s.map(|s| s as f32 * scale))
+                .collect()
+        }
+    };
+    let format = StreamFormat {
+        rate: spec.sample_rate,
+        channels: spec.channels as u32,
+    };
+    Ok((format, samples.map_err(error)?))
+}
+
+fn decode_flac(path: &Path) -> Result<(StreamFormat, Vec<f32>), MutateError> {
+    let error = |e: claxon::Error| MutateError::AudioSource(format!("{}: {e}", path.display()));
+    let mut reader = claxon::FlacReader::open(path).map_err(error)?;
+    let info = reader.streaminfo();
+    let scale = int_scale(info.bits_per_sample);
+    let samples = reader
+        .samples()
+        .map(|s| s.map(|s| s as f32 * scale))
+        .collect::<Result<_, _>>()
+        .map_err(error)?;
+    let format = StreamFormat {
+        rate: info.sample_rate,
+        channels: info.channels,
+    };
+    Ok((format, samples))
+}
+
+/// Where one connection is in the file.
+#[derive(Clone)]
+struct Cursor {
+    samples: Arc<[f32]>,
+    next: usize,
+    looping: bool,
+}
+
+impl Generator for Cursor {
+    fn fill(&mut self, out: &mut [f32]) -> usize {
+        let mut n = 0;
+        while n < out.len() {
+            if self.next == self.samples.len() {
+                if !self.looping || self.samples.is_empty() {
+                    break;
+                }
+                self.next = 0;
+            }
+            let take = (out.len() - n).min(self.samples.len() - self.next);
+            out[n..n + take].copy_from_slice(&self.samples[self.next..self.next + take]);
+            n += take;
+            self.next += take;
+        }
+        n
+    }
+}
+
+impl AudioContext {
+    /// A context whose only choice plays `source`.
+    pub fn file(source: FileSource) -> Self {
+        let cursor = Cursor {
+            samples: source.samples,
+            next: 0,
+            looping: source.looping,
+        };
+        Self::local(source.name, source.format, source.realtime, cursor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    fn choice(context: &AudioContext) -> AudioChoice {
+        let mut found = None;
+        context
+            .with_choices_blocking(|choices| found = choices.first().cloned())
+            .unwrap();
+        found.unwrap()
+    }
+
+    /// Read `count` frames, waiting for them to arrive.
+    fn read_frames(consumer: &mut AudioConsumer, count: usize) -> Vec<StereoFrame> {
+        let mut frames = vec![[0.0; 2]; count];
+        let mut n = 0;
+        let deadline = Instant::now() + TIMEOUT;
+        while n < count {
+            assert!(Instant::now() < deadline, "only {n} frames");
+            consumer.format_change().unwrap();
+            n += consumer.read_frames(&mut frames[n..]).unwrap();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        frames
+    }
+
+    fn ramp(frames: usize) -> Vec<f32> {
+        (0..frames * 2).map(|i| (i / 2) as f32).collect()
+    }
+
+    #[test]
+    fn test_file_unpaced_never_overruns() {
+        let format = StreamFormat {
+            rate: 48_000,
+            channels: 2,
+        };
+        // Ten seconds of audio through a ring that holds a fraction of a second.
+        let source = FileSource::from_samples("ramp", format, ramp(480_000)).with_realtime(false);
+        let context = AudioContext::file(source);
+        let events = context.events().unwrap();
+        let options = ConnectOptions::new("test").with_ring_bytes(64 * 1024);
+        let mut consumer = context.connect_with(&choice(&context), &options).unwrap();
+
+        let frames = read_frames(&mut consumer, 480_000);
+        assert!(frames.iter().enumerate().all(|(i, f)| f[0] == i as f32));
+        assert_eq!(consumer.overruns(), 0);
+        let paused = std::iter::from_fn(|| events.recv_timeout(TIMEOUT).ok()).any(|e| {
+            matches!(
+                e,
+                AudioEvent::StreamStateChanged {
+                    state: StreamState::Paused,
+                    ..
+                }
+            )
+        });
+        assert!(paused);
+    }
+
+    #[test]
+    fn test_file_loops() {
+        let format = StreamFormat {
+            rate: 48_000,
+            channels: 2,
+        };
+        let source = FileSource::from_samples("ramp", format, ramp(1000))
+            .with_realtime(false)
+            .with_looping(true);
+        let context = AudioContext::file(source);
+        let mut consumer = context.connect(&choice(&context), "test").unwrap();
+
+        let frames = read_frames(&mut consumer, 2500);
+        assert!(frames
+            .iter()
+            .enumerate()
+            .all(|(i, f)| f[0] == (i % 1000) as f32));
+    }
+
+    #[test]
+    fn test_file_decodes_wav() {
+        let path = std::env::temp_dir().join(format!("mutate-file-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 44_100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for s in [0i16, i16::MIN, 16384, -16384] {
+            writer.write_sample(s).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let source = FileSource::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            source.format(),
+            StreamFormat {
+                rate: 44_100,
+                channels: 2
+            }
+        );
+        assert_eq!(&source.samples[..], &[0.0, -1.0, 0.5, -0.5]);
+        assert!(FileSource::open("song.mp3").is_err());
+    }
+}
@@ -1,7 +1,7 @@
 //! # Import to Device
 //!
 //! Publish audio server chunks to a Vulkan device.  Pick an `AudioChoice` and call
-//! `AudioContext::import` with a [`Device`](crate::vulkan::device::Device) and `AudioChoice` to
+//! `AudioContext::import` with a [`Device`](crate::gpu::device::Device) and `AudioChoice` to
 //! obtain a `Consumer` handle.  The consumer serves three roles:
 //!
 //! - own audio consumption that copies upstream chunks to a persistently mapped device buffer.
@@ -27,6 +27,12 @@
 //! torn during the reads.  Deciding how to manage a torn processing result is beyond the scope of
 //! this module.
 //!
+//! An [offline](Consumer::offline) consumer has no stream or reader thread.  The host pushes
+//! samples into its rings between frames, such as when rendering a file to video at a fixed rate.
+//!
+//! The host sees the same audio by [feeding](Consumer::feed) an [`AudioInlet`], which a
+//! [`Graph`](crate::graph::Graph) reads through its [`AudioNode`](crate::audio::node::AudioNode).
+//!
 //! ## Usage
 //!
 //! ```ignore
@@ -92,21 +98,32 @@
 // Storing the full array of offsets was chosen to duplicate less logic on the device.
 // NEXT sub-allocation alignments were not designed for wide loads.  Vectorized reading of the ring
 // is a bit more complex for the consumer, more complexity than it's worth on this pass.
+// NEXT publish `Consumer::sample_rate` as `graph::config::SOURCE_RATE` so the resampler and bank
+// re-provision through `Node::update`.  Until then a rate change is published but the bank keeps
+// its old tuning.
 // NEXT consumer hazard tracking and slack rotation-reclaim support on producer so that
 // discontinuities are swallowed faster and without being presented to the consumer.
 
 use std::sync::{
-    atomic::{AtomicBool, AtomicU64, Ordering},
+    atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
     Arc,
 };
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 use ash::vk;
 
-use crate::audio::{AudioChoice, AudioConsumer, AudioContext};
-use crate::vulkan::prelude::*;
+use crate::audio::node::AudioInlet;
+#[cfg(feature = "file")]
+use crate::audio::record::{Recorder, Tap};
+use crate::audio::timing::{AudioTimestamp, StampLog};
+use crate::audio::{AudioChoice, AudioConsumer, AudioContext, ConnectOptions};
+use crate::gpu::prelude::*;
 use crate::MutateError;
 
+/// Peak sample magnitude below which a chunk counts as silence, about -80dBFS.
+const SILENCE_FLOOR: f32 = 1e-4;
+
 /// When dispatching a shader, provide the base address as a buffer and read `len` samples.
 /// Physical index straddles will be returned as two spans, and dispatching twice is appropriate.
 /// Barrier insertion is **not** needed because flushed ranges are guaranteed safe for read until
@@ -180,6 +197,54 @@ struct Control {
     read_head: AtomicU64,
     /// When closed is set, the thread's read-write loop breaks.
     closed: AtomicBool,
+    /// Sample rate of the samples being written.  Zero until the stream negotiates a format.
+    rate: AtomicU32,
+    /// Nanoseconds after `started` of the last chunk above the silence floor.
+    loud_at: AtomicU64,
+    started: Instant,
+    /// When recent writes were captured, by logical address.
+    stamps: std::sync::Mutex<StampLog>,
+    /// A recorder tap waiting for the reader thread to attach it.
+    #[cfg(feature = "file")]
+    tap: std::sync::Mutex<Option<Tap>>,
+    /// Where written samples are also pushed for the host.
+    inlets: std::sync::Mutex<Vec<AudioInlet>>,
+}
+
+impl Control {
+    fn new(rate: u32) -> Arc<Self> {
+        Arc::new(Control {
+            write_head: AtomicU64::new(0),
+            read_head: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+            rate: AtomicU32::new(rate),
+            loud_at: AtomicU64::new(0),
+            started: Instant::now(),
+            stamps: Default::default(),
+            #[cfg(feature = "file")]
+            tap: std::sync::Mutex::new(None),
+            inlets: std::sync::Mutex::new(Vec::new()),
+        })
+    }
+}
+
+/// Scatter interleaved `frames` across the planar channel rings of `dst`, starting at logical
+/// address `start`.
+fn scatter<const CHANNELS: usize>(
+    dst: &mut [u8],
+    channel_offsets: &[u32; CHANNELS],
+    sample_count: u32,
+    start: u64,
+    frames: &[[f32; CHANNELS]],
+) {
+    for c in 0..CHANNELS {
+        let ring_base = channel_offsets[c] as usize;
+        for (s, frame) in frames.iter().enumerate() {
+            let logical = start.wrapping_add(s as u64) % sample_count as u64;
+            let dst_byte = ring_base + logical as usize * 4;
+            dst[dst_byte..dst_byte + 4].copy_from_slice(&frame[c].to_le_bytes());
+        }
+    }
 }
 
 impl<const CHANNELS: usize> Consumer<CHANNELS> {
@@ -189,45 +254,59 @@ impl<const CHANNELS: usize> Consumer<CHANNELS> {
         device: &Device,
         choice: &AudioChoice,
         sample_count: u32,
-        name: &str,
+        options: &ConnectOptions,
     ) -> Result<Consumer<CHANNELS>, MutateError> {
-        let control = Arc::new(Control {
-            write_head: AtomicU64::new(0),
-            read_head: AtomicU64::new(0),
-            closed: AtomicBool::new(false),
-        });
-
+        let control = Control::new(0);
         let non_coherent_atom_size = device.non_coherent_atom_size();
-        let channel_bytes = sample_count as u64 * 4;
-        // rounded up for atom flush size
-        let channel_stride = channel_bytes.next_multiple_of(non_coherent_atom_size as u64);
-        let size = channel_stride as usize * CHANNELS;
-        let channel_offsets: [u32; CHANNELS] =
-            std::array::from_fn(|i| (channel_stride * i as u64) as u32);
-        // FIXME reverse device-size argument order in buffer module
-        let mut buffer: MappedAllocation<u8> = MappedAllocation::new(size, device)?;
+        let (buffer, channel_stride, channel_offsets) = Self::allocate(device, sample_count)?;
         let base_address = buffer.device_address(device)?;
 
-        // f32 0.0 is all-zero bytes, so this write is safe.
-        buffer.as_mut_slice().fill(0u8);
-        buffer.flush(device)?;
-
         let mut view = buffer.write_view(device);
-        let mut rx = context.connect(choice, name)?;
+        let mut rx = context.connect_with(choice, options)?;
         let writer_control = control.clone();
 
         let read_thread_handle = Some(std::thread::spawn(move || {
-            let mut scratch = vec![0u8; 4 * CHANNELS * sample_count as usize];
+            let mut scratch = vec![[0.0f32; CHANNELS]; sample_count as usize];
             let mut write_head: u64 = 0;
 
             while !writer_control.closed.load(Ordering::Relaxed) {
+                #[cfg(feature = "file")]
+                if let Some(tap) = writer_control.tap.lock()?.take() {
+                    rx.tap = Some(tap);
+                }
                 // Wait up to 16ms for a chunk and then warn that chunks are late.
                 match rx.wait(std::time::Duration::from_micros(16_000)) {
                     Ok(got) => {
-                        let got = rx.read(&mut scratch)?;
-                        let frame_bytes = 4 * CHANNELS;
-                        // NOTE ignoring incomplete samples as pipewire seems well-behaved so far.
-                        let incoming = got / frame_bytes;
+                        if let Some(change) = rx.format_change()? {
+                            let downmixed = CHANNELS == 2 && rx.channel_map().is_surround();
+                            if change.new.channels as usize != CHANNELS && !downmixed {
+                                // DEBT channel mapping other than surround to stereo.  Until then
+                                // extra channels are dropped.
+                                println!(
+                                    "audio stream has {} channels, ring expects {}",
+                                    change.new.channels, CHANNELS
+                                );
+                            }
+                            writer_control
+                                .rate
+                                .store(change.new.rate, Ordering::Release);
+                        }
+                        let incoming = rx.read_frames(&mut scratch)?;
+                        let stamp = rx.read_timestamp()?;
+                        let rate = writer_control.rate.load(Ordering::Acquire);
+                        if incoming > 0 && rate > 0 {
+                            for inlet in writer_control.inlets.lock()?.iter() {
+                                inlet.push(scratch[..incoming].as_flattened(), rate);
+                            }
+                        }
+                        let loud = scratch[..incoming]
+                            .iter()
+                            .flatten()
+                            .any(|s| s.abs() > SILENCE_FLOOR);
+                        if loud {
+                            let at = writer_control.started.elapsed().as_nanos() as u64;
+                            writer_control.loud_at.store(at, Ordering::Relaxed);
+                        }
                         let read_head = writer_control.read_head.load(Ordering::Acquire);
                         let occupied = write_head.wrapping_sub(read_head);
                         let free = (sample_count as u64).saturating_sub(occupied);
@@ -241,18 +320,13 @@ impl<const CHANNELS: usize> Consumer<CHANNELS> {
                         }
                         let start = write_head;
                         let dst = unsafe { view.as_mut_slice() };
-
-                        // Scatter interleaved input samples across channels
-                        // DEBT the interleaved assumption here can break pretty spectacularly.
-                        for c in 0..CHANNELS {
-                            let ring_base = channel_offsets[c] as usize;
-                            for s in 0..to_write {
-                                let src = (s * CHANNELS + c) * 4;
-                                let logical = start.wrapping_add(s as u64) % sample_count as u64;
-                                let dst_byte = ring_base + logical as usize * 4;
-                                dst[dst_byte..dst_byte + 4].copy_from_slice(&scratch[src..src + 4]);
-                            }
-                        }
+                        scatter(
+                            dst,
+                            &channel_offsets,
+                            sample_count,
+                            start,
+                            &scratch[..to_write],
+                        );
 
                         // Per-channel flush.  One run if the written region is contiguous, two if it
                         // wraps the ring end. Ring slots are 4 bytes. ring_base is stride-aligned.
@@ -293,6 +367,18 @@ impl<const CHANNELS: usize> Consumer<CHANNELS> {
                             }
                         }
 
+                        if let Some(stamp) = stamp.filter(|_| to_write > 0) {
+                            let rate = rate as f64;
+                            let start = AudioTimestamp {
+                                position: start,
+                                ..stamp
+                            };
+                            writer_control
+                                .stamps
+                                .lock()?
+                                .push(start, to_write as u64, rate);
+                        }
+
                         // Publish new write head
                         let new_head = write_head.wrapping_add(to_write as u64);
                         writer_control.write_head.store(new_head, Ordering::Release);
@@ -321,6 +407,158 @@ impl<const CHANNELS: usize> Consumer<CHANNELS> {
         })
     }
 
+    /// A consumer without a stream, written by the host with [`push`](Self::push).  Offline
+    /// rendering feeds each frame exactly the samples it covers, so the output does not depend on
+    /// how fast the device draws.  `rate` is published as the [`sample_rate`](Self::sample_rate).
+    pub fn offline(device: &Device, sample_count: u32, rate: u32) -> Result<Self, MutateError> {
+        let (buffer, _, channel_offsets) = Self::allocate(device, sample_count)?;
+        let base_address = buffer.device_address(device)?;
+        Ok(Consumer {
+            buffer,
+            read_thread_handle: None,
+            base_address,
+            sample_count,
+            channel_offsets,
+            control: Control::new(rate),
+        })
+    }
+
+    /// Write `frames` behind the newest samples of an [offline](Self::offline) consumer.  Like the
+    /// stream's writer, frames that do not fit before the read head are dropped.  Returns how many
+    /// were written.  Dispatches that read the ring must be retired first, since the whole ring is
+    /// flushed.
+    pub fn push(
+        &mut self,
+        device: &Device,
+        frames: &[[f32; CHANNELS]],
+    ) -> Result<usize, MutateError> {
+        if self.read_thread_handle.is_some() {
+            return Err(MutateError::AudioSource(
+                "only offline consumers are pushed to".to_owned(),
+            ));
+        }
+        let write = self.control.write_head.load(Ordering::Acquire);
+        let read = self.control.read_head.load(Ordering::Acquire);
+        let free = (self.sample_count as u64).saturating_sub(write.wrapping_sub(read));
+        let to_write = frames.len().min(free as usize);
+        scatter(
+            self.buffer.as_mut_slice(),
+            &self.channel_offsets,
+            self.sample_count,
+            write,
+            &frames[..to_write],
+        );
+        self.buffer.flush(device)?;
+        let rate = self.control.rate.load(Ordering::Acquire);
+        for inlet in self.control.inlets.lock()?.iter() {
+            inlet.push(frames[..to_write].as_flattened(), rate);
+        }
+        let loud = frames[..to_write]
+            .iter()
+            .flatten()
+            .any(|s| s.abs() > SILENCE_FLOOR);
+        if loud {
+            let at = self.control.started.elapsed().as_nanos() as u64;
+            self.control.loud_at.store(at, Ordering::Relaxed);
+        }
+        self.control
+            .write_head
+            .store(write.wrapping_add(to_write as u64), Ordering::Release);
+        Ok(to_write)
+    }
+
+    /// Allocate zeroed channel rings of `sample_count` samples in one buffer.  Returns the buffer,
+    /// the stride between channels, and each channel's offset.
+    fn allocate(
+        device: &Device,
+        sample_count: u32,
+    ) -> Result<(MappedAllocation<u8>, u64, [u32; CHANNELS]), MutateError> {
+        let channel_bytes = sample_count as u64 * 4;
+        // rounded up for atom flush size
+        let channel_stride = channel_bytes.next_multiple_of(device.non_coherent_atom_size());
+        let size = channel_stride as usize * CHANNELS;
+        let channel_offsets: [u32; CHANNELS] =
+            std::array::from_fn(|i| (channel_stride * i as u64) as u32);
+        // FIXME reverse device-size argument order in buffer module
+        let mut buffer: MappedAllocation<u8> = MappedAllocation::new(size, device)?;
+
+        // f32 0.0 is all-zero bytes, so this write is safe.
+        buffer.as_mut_slice().fill(0u8);
+        buffer.flush(device)?;
+        Ok((buffer, channel_stride, channel_offsets))
+    }
+
+    /// Sample rate of the most recently written samples.  `None` until the stream negotiates a
+    /// format.  Downstream stages should re-provision when this changes.
+    pub fn sample_rate(&self) -> Option<u32> {
+        let rate = self.control.rate.load(Ordering::Acquire);
+        (rate > 0).then_some(rate)
+    }
+
+    /// Samples per channel written since the stream began.  Over the sample rate, this is how far a
+    /// file has played.
+    pub fn written(&self) -> u64 {
+        self.control.write_head.load(Ordering::Acquire)
+    }
+
+    /// Record what the reader thread reads from now on.  See [`Recorder::attach`].
+    #[cfg(feature = "file")]
+    pub fn record(&self, recorder: &Recorder) -> Result<(), MutateError> {
+        *self.control.tap.lock()? = Some(recorder.tap());
+        Ok(())
+    }
+
+    /// Push every sample written from now on into `inlet` as well, until [`unfeed`].  Inlets see
+    /// what the stream delivers, including samples the ring was too full to take.
+    ///
+    /// [`unfeed`]: Self::unfeed
+    pub fn feed(&self, inlet: &AudioInlet) -> Result<(), MutateError> {
+        if inlet.channels() != CHANNELS {
+            return Err(MutateError::AudioSource(format!(
+                "an inlet of {} channels cannot take a ring of {CHANNELS}",
+                inlet.channels()
+            )));
+        }
+        let mut inlets = self.control.inlets.lock()?;
+        if !inlets.iter().any(|i| i.ptr_eq(inlet)) {
+            inlets.push(inlet.clone());
+        }
+        Ok(())
+    }
+
+    /// Stop pushing to `inlet`.
+    pub fn unfeed(&self, inlet: &AudioInlet) -> Result<(), MutateError> {
+        self.control.inlets.lock()?.retain(|i| !i.ptr_eq(inlet));
+        Ok(())
+    }
+
+    /// When the sample at logical address `address` was captured.  The window returned by
+    /// [`regions`] starts at the read head, which counts every sample reclaimed by
+    /// [`advance_read`].  `None` for samples not yet written or written too long ago.
+    pub fn captured_at(&self, address: u64) -> Result<Option<Instant>, MutateError> {
+        Ok(self.control.stamps.lock()?.at(address).map(|s| s.captured))
+    }
+
+    /// When the first sample of the window returned by [`regions`] was captured.
+    pub fn window_captured_at(&self) -> Result<Option<Instant>, MutateError> {
+        self.captured_at(self.control.read_head.load(Ordering::Acquire))
+    }
+
+    /// When the newest written sample was captured.  `None` before the first write.
+    pub fn newest_captured_at(&self) -> Result<Option<Instant>, MutateError> {
+        match self.control.write_head.load(Ordering::Acquire).checked_sub(1) {
+            Some(address) => self.captured_at(address),
+            None => Ok(None),
+        }
+    }
+
+    /// How long the stream has been below the silence floor.  Counted from creation if it has never
+    /// been loud.
+    pub fn silent_for(&self) -> Duration {
+        let loud_at = Duration::from_nanos(self.control.loud_at.load(Ordering::Relaxed));
+        self.control.started.elapsed().saturating_sub(loud_at)
+    }
+
     /// The size in elements that the physical rings can store when full.  This is also the repeat
     /// modulus for physical indexes.
     pub fn capacity(&self) -> u32 {
@@ -338,6 +576,18 @@ impl<const CHANNELS: usize> Consumer<CHANNELS> {
         Ok(write.wrapping_sub(read) as u32)
     }
 
+    /// Physical index one past the newest written sample, where the writer continues.  The samples
+    /// just behind it are the most recent audio whether or not they were reclaimed, which is what
+    /// displays of the raw signal want.  Like [`channels`](Self::channels), reads far behind it may
+    /// observe the writer.
+    pub fn write_index(&self) -> Result<u32, MutateError> {
+        if self.control.closed.load(Ordering::Relaxed) {
+            return Err(MutateError::Dropped);
+        }
+        let write = self.control.write_head.load(Ordering::Acquire);
+        Ok((write % self.sample_count as u64) as u32)
+    }
+
     /// Physical base address of each channel's ring sub-allocation.  **Bare use of these addresses
     /// is undefined behavior** that will read uninitialized or torn data.  Use for fun or ring
     /// diagnostics.  All bit patterns are valid float, but the data found may be nonsensical and
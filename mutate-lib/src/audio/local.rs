@@ -0,0 +1,144 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Local Backends
+//!
+//! Sources that make their own audio, such as files and test signals, run a thread of their own
+//! instead of talking to a server.  Every connection gets a fresh copy of the [`Generator`], so two
+//! consumers of the same choice read identical samples.
+//!
+//! Real-time sources write one quantum per period against an absolute deadline, like a live
+//! stream.  Unpaced sources write whenever the ring has room for a whole quantum and never overrun,
+//! so tests and offline analysis run as fast as the consumer.
+
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use ringbuf::traits::Producer;
+
+use super::*;
+
+/// Identifier of the only choice in a local context.
+pub(super) const LOCAL_ID: u32 = 0;
+/// Frames per chunk, about 11ms at 48kHz.
+const LOCAL_QUANTUM: usize = 512;
+/// How long an unpaced source waits on a full ring before looking again.
+const FULL_RING_WAIT: Duration = Duration::from_millis(1);
+
+/// Produces interleaved samples for one connection.
+pub(super) trait Generator: Clone + Send + 'static {
+    /// Fill `out`, a whole number of frames, and return how many samples were written.  Writing
+    /// fewer than `out.len()` ends the stream.
+    fn fill(&mut self, out: &mut [f32]) -> usize;
+}
+
+/// A connection being fed by the local thread.
+struct Playing<G> {
+    tx: AudioProducer,
+    generator: G,
+    ended: bool,
+}
+
+impl AudioContext {
+    /// A context whose only choice, `name`, plays `generator` in `format`.
+    pub(super) fn local<G: Generator>(
+        name: String,
+        format: StreamFormat,
+        realtime: bool,
+        generator: G,
+    ) -> Self {
+        let choices = Box::into_raw(Box::new(AudioChoices::new()));
+        let choices_addr = choices as usize;
+        let (tx, rx) = mpsc::channel();
+        let events = Arc::new(AudioEvents::default());
+        let handle = std::thread::spawn(move || {
+            // Safety: AudioContext::drop joins this thread before freeing choices.
+            let choices: &AudioChoices = unsafe { &*(choices_addr as *mut AudioChoices) };
+            choices.publish([AudioChoice {
+                kind: AudioSourceKind::ApplicationStream,
+                name: Some(name),
+                application: None,
+                object_serial: LOCAL_ID,
+                global_id: LOCAL_ID,
+            }]);
+
+            let channels = format.channels.max(1) as usize;
+            let period = Duration::from_secs_f64(LOCAL_QUANTUM as f64 / format.rate as f64);
+            let mut samples = vec![0.0f32; LOCAL_QUANTUM * channels];
+            let mut playing: Vec<Playing<G>> = Vec::new();
+            let mut deadline = Instant::now();
+            loop {
+                let wait = deadline.saturating_duration_since(Instant::now());
+                match rx.recv_timeout(wait) {
+                    Ok(Message::Connect { choice, tx, .. }) => {
+                        if choice.object_serial == LOCAL_ID {
+                            if let Err(e) = tx.set_format(format) {
+                                tx.error(e);
+                            }
+                            tx.emit(|stream| AudioEvent::StreamStateChanged {
+                                stream,
+                                state: StreamState::Streaming,
+                            });
+                            playing.push(Playing {
+                                tx,
+                                generator: generator.clone(),
+                                ended: false,
+                            });
+                        }
+                        // Anything else drops the producer, which tombstones the connection.
+                        continue;
+                    }
+                    Ok(Message::Terminate) | Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                }
+
+                let arrived = Instant::now();
+                let mut stalled = true;
+                playing.retain_mut(|p| {
+                    // Ended streams stay connected so that consumers can drain them.
+                    if p.ended || (!realtime && p.tx.vacant() < samples.len() * 4) {
+                        return true;
+                    }
+                    let n = p.generator.fill(&mut samples);
+                    p.ended = n < samples.len();
+                    let mut keep = true;
+                    if n > 0 {
+                        stalled = false;
+                        let bytes: Vec<u8> =
+                            samples[..n].iter().flat_map(|s| s.to_le_bytes()).collect();
+                        p.tx.set_quantum((n / channels) as u32, format.rate);
+                        match p
+                            .tx
+                            .write_with(bytes.len(), arrived, |buf| buf.push_slice(&bytes))
+                        {
+                            Ok(_) => {}
+                            Err(MutateError::Dropped) => keep = false,
+                            Err(e) => p.tx.error(e),
+                        }
+                    }
+                    if keep && p.ended {
+                        p.tx.emit(|stream| AudioEvent::StreamStateChanged {
+                            stream,
+                            state: StreamState::Paused,
+                        });
+                    }
+                    keep
+                });
+
+                deadline = match (realtime, stalled) {
+                    // Paced by an absolute deadline so that chunk timing does not drift.
+                    (true, _) => deadline + period,
+                    (false, true) => arrived + FULL_RING_WAIT,
+                    (false, false) => arrived,
+                };
+            }
+        });
+
+        AudioContext {
+            handle: Some(handle),
+            choices,
+            events,
+            tx: Box::new(tx),
+        }
+    }
+}
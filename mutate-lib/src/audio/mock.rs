@@ -0,0 +1,779 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Mock Backend
+//!
+//! Code downstream of [`AudioContext`] is hard to test against a live audio server.  The sources
+//! on the test machine are whatever happens to be playing, and nothing plays the same way twice.
+//! A [`MockServer`] is a script instead: a fixed list of choices and, for each choice, the exact
+//! sequence of chunks, format changes, and disconnects that a connection to it will receive.
+//!
+//! [`AudioContext::mock`] returns an ordinary `AudioContext`.  Connections hand out ordinary
+//! [`AudioConsumer`]s fed through the same ring and timing path as the real backends, so code under
+//! test cannot tell the difference.
+//!
+//! ```ignore
+//! let server = MockServer::new()
+//!     .source(7, "Sine", AudioSourceKind::ApplicationStream, vec![
+//!         StreamStep::Chunk(vec![0.0; 1024]),
+//!         StreamStep::Disconnect,
+//!     ]);
+//! let context = AudioContext::mock(server);
+//! ```
+//!
+//! Each connection plays its script as soon as it is made, without pacing, unless the script asks
+//! for a [`StreamStep::Sleep`].  Chunks arrive faster than real time, so tests should poll for the
+//! data they expect rather than wait on individual chunks.
+
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use ringbuf::traits::Producer;
+
+use super::*;
+
+/// One event on a scripted connection.
+#[derive(Clone, Debug)]
+pub enum StreamStep {
+    /// Deliver one process callback's worth of interleaved samples.
+    Chunk(Vec<f32>),
+    /// The stream renegotiated its format.  Later chunks are interpreted with it.
+    Format { rate: u32, channels: u32 },
+    /// Pause before the next step.
+    Sleep(Duration),
+    /// The source stops playing and disappears from the choices.  The connection stays open.  Unless
+    /// the server would move it to another source, it is suspended and later chunks are lost.  See
+    /// [`Reconnect`].
+    Remove,
+    /// A removed source reappears under the same name.  Suspended connections with
+    /// [`Reconnect::SameName`] resume.
+    Return,
+    /// The server drops the connection.  The consumer starts returning
+    /// [`MutateError::Dropped`] and the rest of the script is ignored.
+    Disconnect,
+}
+
+/// A scripted audio server.
+#[derive(Clone, Debug, Default)]
+pub struct MockServer {
+    choices: Vec<AudioChoice>,
+    streams: HashMap<u32, Vec<StreamStep>>,
+}
+
+impl MockServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a choice that will play `steps` to every connection made to it.
+    pub fn source(
+        mut self,
+        id: u32,
+        name: &str,
+        kind: AudioSourceKind,
+        steps: Vec<StreamStep>,
+    ) -> Self {
+        self.choices.push(AudioChoice {
+            kind,
+            name: Some(name.to_owned()),
+            application: None,
+            object_serial: id,
+            global_id: id,
+        });
+        self.streams.insert(id, steps);
+        self
+    }
+
+    /// Attribute the choice added as `id` to `application`.
+    pub fn application(mut self, id: u32, application: &str) -> Self {
+        if let Some(choice) = self.choices.iter_mut().find(|c| c.global_id == id) {
+            choice.application = Some(application.to_owned());
+        }
+        self
+    }
+}
+
+impl AudioContext {
+    /// A context backed by a scripted server instead of a real one.
+    pub fn mock(server: MockServer) -> Self {
+        let choices = Box::into_raw(Box::new(AudioChoices::new()));
+        let choices_addr = choices as usize;
+        let (tx, rx) = mpsc::channel();
+        let events = std::sync::Arc::new(AudioEvents::default());
+        let handle = std::thread::spawn(move || {
+            // Safety: AudioContext::drop joins this thread before freeing choices.
+            let choices: &AudioChoices = unsafe { &*(choices_addr as *mut AudioChoices) };
+            choices.publish(server.choices.iter().cloned());
+
+            // Producers stay alive, like real streams, until the context terminates.
+            let mut live = Vec::new();
+            while let Ok(message) = rx.recv() {
+                match message {
+                    Message::Connect {
+                        choice,
+                        tx,
+                        options,
+                    } => {
+                        match server.streams.get(&choice.object_serial) {
+                            Some(steps) => {
+                                if let Some(tx) =
+                                    play(steps, tx, choices, &choice, options.reconnect)
+                                {
+                                    live.push(tx);
+                                }
+                            }
+                            // Dropping the producer tombstones the connection.
+                            None => tx.error(format!("no source {}", choice.object_serial)),
+                        }
+                    }
+                    Message::Terminate => break,
+                }
+            }
+        });
+
+        AudioContext {
+            handle: Some(handle),
+            choices,
+            events,
+            tx: Box::new(tx),
+        }
+    }
+}
+
+/// Play a connection's script.  Returns the producer unless the script disconnected it.
+fn play(
+    steps: &[StreamStep],
+    mut tx: AudioProducer,
+    choices: &AudioChoices,
+    choice: &AudioChoice,
+    reconnect: Reconnect,
+) -> Option<AudioProducer> {
+    let (mut rate, mut channels) = (48_000, 2);
+    let publish = |tx: &AudioProducer, rate: u32, channels: usize| {
+        let format = StreamFormat {
+            rate,
+            channels: channels as u32,
+        };
+        if let Err(e) = tx.set_format(format) {
+            tx.error(e);
+        }
+    };
+    // Like a real server, the format is negotiated before the first buffer.
+    publish(&tx, rate, channels);
+    let state = |tx: &AudioProducer, state: StreamState| {
+        tx.emit(|stream| AudioEvent::StreamStateChanged { stream, state });
+    };
+    state(&tx, StreamState::Streaming);
+    let mut suspended = false;
+    for step in steps {
+        match step {
+            // The source is gone, so nothing arrives.
+            StreamStep::Chunk(_) if suspended => {}
+            StreamStep::Chunk(samples) => {
+                // Published before the bytes so that a consumer that sees the bytes sees the
+                // quantum that delivered them.
+                tx.set_quantum((samples.len() / channels) as u32, rate);
+                let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+                match tx.write_with(bytes.len(), Instant::now(), |buf| buf.push_slice(&bytes)) {
+                    Ok(_) => {}
+                    Err(MutateError::Dropped) => return None,
+                    Err(e) => tx.error(e),
+                }
+            }
+            StreamStep::Format {
+                rate: r,
+                channels: c,
+            } => {
+                rate = *r;
+                channels = (*c).max(1) as usize;
+                publish(&tx, rate, channels);
+            }
+            StreamStep::Sleep(duration) => std::thread::sleep(*duration),
+            StreamStep::Remove => {
+                if let Err(e) = choices.remove(choice.global_id) {
+                    tx.error(e);
+                }
+                if dont_reconnect(choice, reconnect) {
+                    suspended = true;
+                    tx.suspend(None);
+                }
+            }
+            StreamStep::Return => {
+                if let Err(e) = choices.add(choice.clone()) {
+                    tx.error(e);
+                }
+                if suspended && reconnect == Reconnect::SameName {
+                    suspended = false;
+                    tx.suspend(Some(&choice.name()));
+                }
+            }
+            StreamStep::Disconnect => {
+                state(&tx, StreamState::Unconnected);
+                return None;
+            }
+        }
+    }
+    Some(tx)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Chunks arrive faster than real time, so poll instead of waiting for a single chunk.
+    fn wait_for_bytes(consumer: &AudioConsumer, bytes: usize) {
+        let deadline = Instant::now() + TIMEOUT;
+        while consumer.occupied() < bytes {
+            assert!(
+                Instant::now() < deadline,
+                "only {} bytes",
+                consumer.occupied()
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn wait_for_drop(consumer: &mut AudioConsumer) {
+        let deadline = Instant::now() + TIMEOUT;
+        let mut buf = [0u8; 4096];
+        while !matches!(consumer.read(&mut buf), Err(MutateError::Dropped)) {
+            assert!(Instant::now() < deadline, "connection never dropped");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn choice(context: &AudioContext, name: &str) -> AudioChoice {
+        let mut found = None;
+        context
+            .with_choices_blocking(|choices| {
+                found = choices.iter().find(|c| c.name() == name).cloned();
+            })
+            .unwrap();
+        found.unwrap()
+    }
+
+    /// Drain everything available, across format changes.
+    fn read_f32(consumer: &mut AudioConsumer) -> Vec<f32> {
+        let mut bytes = vec![0u8; consumer.occupied()];
+        let mut n = 0;
+        while n < bytes.len() {
+            n += consumer.read(&mut bytes[n..]).unwrap();
+        }
+        bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_mock_chunks_and_format() {
+        let ramp: Vec<f32> = (0..512).map(|i| i as f32).collect();
+        let server = MockServer::new()
+            .source(1, "mic", AudioSourceKind::HardwareInput, vec![])
+            .source(
+                2,
+                "player",
+                AudioSourceKind::ApplicationStream,
+                vec![
+                    StreamStep::Chunk(ramp.clone()),
+                    StreamStep::Format {
+                        rate: 44_100,
+                        channels: 1,
+                    },
+                    StreamStep::Chunk(vec![0.5; 128]),
+                ],
+            );
+        let context = AudioContext::mock(server);
+        context
+            .with_choices_blocking(|c| assert_eq!(c.len(), 2))
+            .unwrap();
+
+        let mut consumer = context
+            .connect(&choice(&context, "player"), "test")
+            .unwrap();
+        wait_for_bytes(&consumer, (512 + 128) * 4);
+        let samples = read_f32(&mut consumer);
+        assert_eq!(&samples[..512], &ramp[..]);
+        assert_eq!(&samples[512..], &[0.5; 128]);
+        assert_eq!(
+            consumer.quantum(),
+            Some(Quantum {
+                frames: 128,
+                rate: 44_100
+            })
+        );
+    }
+
+    #[test]
+    fn test_mock_read_frames() {
+        let ramp: Vec<f32> = (0..512).map(|i| i as f32).collect();
+        let server = MockServer::new().source(
+            5,
+            "player",
+            AudioSourceKind::ApplicationStream,
+            vec![
+                StreamStep::Chunk(ramp),
+                StreamStep::Format {
+                    rate: 48_000,
+                    channels: 1,
+                },
+                StreamStep::Chunk(vec![0.5; 128]),
+            ],
+        );
+        let context = AudioContext::mock(server);
+        let mut consumer = context
+            .connect(&choice(&context, "player"), "test")
+            .unwrap();
+        wait_for_bytes(&consumer, (512 + 128) * 4);
+
+        let mut frames = [[0.0f32; 2]; 1024];
+        assert_eq!(consumer.peek_frames(&mut frames[..2]).unwrap(), 2);
+        assert_eq!(&frames[..2], &[[0.0, 1.0], [2.0, 3.0]]);
+        // Stops at the change to mono.
+        assert_eq!(consumer.read_frames(&mut frames).unwrap(), 256);
+        assert_eq!(frames[255], [510.0, 511.0]);
+        // Mono is copied to both channels.
+        assert_eq!(consumer.read_frames(&mut frames).unwrap(), 128);
+        assert_eq!(frames[0], [0.5, 0.5]);
+        assert_eq!(consumer.read_frames(&mut frames).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_mock_surround() {
+        // Two 5.1 frames: a front left hit, then the center.
+        let frames = vec![
+            1.0, 0.0, 0.0, 0.5, 0.0, 0.0, //
+            0.0, 0.0, 1.0, 0.5, 0.0, 0.0,
+        ];
+        let steps = vec![
+            StreamStep::Format {
+                rate: 48_000,
+                channels: 6,
+            },
+            StreamStep::Chunk(frames.clone()),
+            StreamStep::Chunk(frames),
+        ];
+        let server =
+            MockServer::new().source(9, "movie", AudioSourceKind::ApplicationStream, steps);
+        let context = AudioContext::mock(server);
+        let mut consumer = context.connect(&choice(&context, "movie"), "test").unwrap();
+        wait_for_bytes(&consumer, 4 * 6 * 4);
+        consumer.format_change().unwrap();
+        let map = consumer.channel_map().clone();
+        assert_eq!(map, channel::ChannelMap::standard(6));
+
+        // Stereo reads are downmixed instead of taking the first two channels.
+        let mut stereo = [[0.0f32; 2]; 2];
+        assert_eq!(consumer.read_frames(&mut stereo).unwrap(), 2);
+        assert_eq!(stereo[0], map.downmix(&[1.0, 0.0, 0.0, 0.5, 0.0, 0.0]));
+        assert_eq!(stereo[1][0], stereo[1][1]);
+        assert!(stereo[1][0] > 0.0);
+
+        // Planar reads keep every channel.
+        let mut channels = [[0.0f32; 2]; 6];
+        let mut planes: Vec<&mut [f32]> = channels.iter_mut().map(|c| &mut c[..]).collect();
+        assert_eq!(consumer.read_planar(&mut planes).unwrap(), 2);
+        let lfe = map.find(channel::Channel::LowFrequency).unwrap();
+        assert_eq!(channels[0], [1.0, 0.0]);
+        assert_eq!(channels[lfe], [0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_mock_timestamps() {
+        // Two 10ms chunks of 48kHz stereo, 50ms apart.
+        let server = MockServer::new().source(
+            6,
+            "player",
+            AudioSourceKind::ApplicationStream,
+            vec![
+                StreamStep::Chunk(vec![0.0; 960]),
+                StreamStep::Sleep(Duration::from_millis(50)),
+                StreamStep::Chunk(vec![0.0; 960]),
+            ],
+        );
+        let context = AudioContext::mock(server);
+        let mut consumer = context
+            .connect(&choice(&context, "player"), "test")
+            .unwrap();
+        assert_eq!(consumer.read_timestamp().unwrap(), None);
+        wait_for_bytes(&consumer, 960 * 4);
+        let first = consumer.latest_timestamp().unwrap().unwrap();
+        assert_eq!(first.position, 0);
+
+        // Frames inside a chunk are stamped at the chunk's rate.
+        let mut frames = [[0.0f32; 2]; 480];
+        assert_eq!(consumer.read_frames(&mut frames[..240]).unwrap(), 240);
+        assert_eq!(consumer.read_timestamp().unwrap(), Some(first));
+        assert_eq!(consumer.read_frames(&mut frames[..240]).unwrap(), 240);
+        let half = consumer.read_timestamp().unwrap().unwrap();
+        assert_eq!(half.position, 240 * 8);
+        assert_eq!(half.captured, first.captured + Duration::from_millis(5));
+        assert_eq!(half.arrived, first.arrived);
+
+        wait_for_bytes(&consumer, 960 * 4);
+        let second = consumer.latest_timestamp().unwrap().unwrap();
+        assert_eq!(second.position, 960 * 4);
+        assert!(second.captured >= first.captured + Duration::from_millis(50));
+        assert_eq!(consumer.read_frames(&mut frames).unwrap(), 480);
+        assert_eq!(consumer.read_timestamp().unwrap(), Some(second));
+    }
+
+    #[test]
+    fn test_mock_format_change_boundary() {
+        let server = MockServer::new().source(
+            4,
+            "dac",
+            AudioSourceKind::SinkMonitor,
+            vec![
+                StreamStep::Chunk(vec![1.0; 256]),
+                StreamStep::Format {
+                    rate: 44_100,
+                    channels: 2,
+                },
+                StreamStep::Chunk(vec![2.0; 64]),
+            ],
+        );
+        let context = AudioContext::mock(server);
+        let mut consumer = context.connect(&choice(&context, "dac"), "test").unwrap();
+        wait_for_bytes(&consumer, (256 + 64) * 4);
+
+        let first = StreamFormat {
+            rate: 48_000,
+            channels: 2,
+        };
+        let second = StreamFormat {
+            rate: 44_100,
+            channels: 2,
+        };
+        assert_eq!(
+            consumer.format_change().unwrap(),
+            Some(FormatChange {
+                old: None,
+                new: first
+            })
+        );
+        assert_eq!(consumer.format_change().unwrap(), None);
+
+        // The read stops where the old format ends, even with room to spare.
+        let mut buf = [0u8; 4096];
+        assert_eq!(consumer.read(&mut buf).unwrap(), 256 * 4);
+        assert_eq!(consumer.format(), Some(first));
+        assert_eq!(
+            consumer.format_change().unwrap(),
+            Some(FormatChange {
+                old: Some(first),
+                new: second
+            })
+        );
+        assert_eq!(consumer.read(&mut buf).unwrap(), 64 * 4);
+        assert_eq!(f32::from_le_bytes(buf[..4].try_into().unwrap()), 2.0);
+        assert_eq!(consumer.format(), Some(second));
+    }
+
+    #[test]
+    fn test_mock_disconnect_and_remove() {
+        let server = MockServer::new().source(
+            3,
+            "browser",
+            AudioSourceKind::ApplicationStream,
+            vec![
+                StreamStep::Chunk(vec![0.0; 256]),
+                StreamStep::Remove,
+                StreamStep::Disconnect,
+                StreamStep::Chunk(vec![1.0; 256]),
+            ],
+        );
+        let context = AudioContext::mock(server);
+        let mut consumer = context
+            .connect(&choice(&context, "browser"), "test")
+            .unwrap();
+        wait_for_drop(&mut consumer);
+        assert!(matches!(consumer.wait(TIMEOUT), Err(MutateError::Dropped)));
+        context.with_choices(|c| assert!(c.is_empty())).unwrap();
+        assert_eq!(context.choices_version(), 2);
+    }
+
+    #[test]
+    fn test_mock_reconnect() {
+        let steps = vec![
+            StreamStep::Chunk(vec![0.0; 256]),
+            StreamStep::Remove,
+            StreamStep::Chunk(vec![0.5; 256]),
+            StreamStep::Sleep(Duration::from_millis(50)),
+            StreamStep::Return,
+            StreamStep::Chunk(vec![1.0; 256]),
+        ];
+        let connect = |reconnect| {
+            let server = MockServer::new().source(
+                8,
+                "headset",
+                AudioSourceKind::HardwareInput,
+                steps.clone(),
+            );
+            let context = AudioContext::mock(server);
+            let events = context.events().unwrap();
+            let options = ConnectOptions::new("test").with_reconnect(reconnect);
+            let consumer = context
+                .connect_with(&choice(&context, "headset"), &options)
+                .unwrap();
+            (context, events, consumer)
+        };
+        let suspensions = |events: &mpsc::Receiver<AudioEvent>| -> Vec<String> {
+            let mut seen = Vec::new();
+            while let Ok(event) = events.recv_timeout(Duration::from_millis(200)) {
+                match event {
+                    AudioEvent::Suspended { .. } => seen.push("Suspended".to_owned()),
+                    AudioEvent::Resumed { source, .. } => seen.push(source),
+                    _ => {}
+                }
+            }
+            seen
+        };
+
+        // The server moves the stream, so every chunk arrives.
+        let (_context, events, mut consumer) = connect(Reconnect::Server);
+        wait_for_bytes(&consumer, 3 * 256 * 4);
+        assert!(suspensions(&events).is_empty());
+        assert_eq!(read_f32(&mut consumer)[256..512], [0.5; 256]);
+
+        // The chunk played while the headset was gone is lost.
+        let (_context, events, mut consumer) = connect(Reconnect::SameName);
+        wait_for_bytes(&consumer, 256 * 4);
+        assert_eq!(suspensions(&events), ["Suspended", "headset"]);
+        assert!(!consumer.is_suspended());
+        wait_for_bytes(&consumer, 2 * 256 * 4);
+        let samples = read_f32(&mut consumer);
+        assert_eq!(samples[256..], [1.0; 256]);
+
+        let (_context, events, consumer) = connect(Reconnect::Never);
+        assert_eq!(suspensions(&events), ["Suspended"]);
+        assert!(consumer.is_suspended());
+        assert_eq!(consumer.occupied(), 256 * 4);
+    }
+
+    #[test]
+    fn test_mock_application_streams() {
+        let server = MockServer::new()
+            .source(1, "speakers", AudioSourceKind::SinkMonitor, vec![])
+            .source(
+                2,
+                "Spotify: Loin Girding Hymns",
+                AudioSourceKind::ApplicationStream,
+                vec![StreamStep::Chunk(vec![0.25; 256])],
+            )
+            .application(2, "Spotify")
+            .source(
+                3,
+                "Firefox: Cat Video",
+                AudioSourceKind::ApplicationStream,
+                vec![StreamStep::Chunk(vec![0.75; 256])],
+            )
+            .application(3, "Firefox");
+        let context = AudioContext::mock(server);
+        let mut spotify = Vec::new();
+        context
+            .with_choices_blocking(|choices| {
+                spotify.extend(
+                    choices
+                        .iter()
+                        .filter(|c| c.application() == Some("Spotify"))
+                        .cloned(),
+                );
+            })
+            .unwrap();
+        assert_eq!(spotify.len(), 1);
+        assert_eq!(spotify[0].kind(), AudioSourceKind::ApplicationStream);
+
+        let mut consumer = context.connect(&spotify[0], "test").unwrap();
+        wait_for_bytes(&consumer, 256 * 4);
+        assert_eq!(read_f32(&mut consumer), [0.25; 256]);
+    }
+
+    #[test]
+    fn test_mock_choice_events() {
+        let server = MockServer::new()
+            .source(1, "mic", AudioSourceKind::HardwareInput, vec![])
+            .source(
+                2,
+                "browser",
+                AudioSourceKind::ApplicationStream,
+                vec![StreamStep::Remove],
+            );
+        let context = AudioContext::mock(server);
+        context.with_choices_blocking(|_| ()).unwrap();
+        let events = context.choice_events().unwrap();
+        let added: Vec<String> = events
+            .try_iter()
+            .map(|e| match e {
+                ChoiceEvent::Added(c) => c.name(),
+                ChoiceEvent::Removed(c) => panic!("{} removed early", c.name()),
+            })
+            .collect();
+        assert_eq!(added, ["mic", "browser"]);
+
+        let _consumer = context
+            .connect(&choice(&context, "browser"), "test")
+            .unwrap();
+        match events.recv_timeout(TIMEOUT).unwrap() {
+            ChoiceEvent::Removed(c) => assert_eq!(c.name(), "browser"),
+            e => panic!("unexpected {e:?}"),
+        }
+        drop(context);
+        assert!(events.recv_timeout(TIMEOUT).is_err());
+    }
+
+    #[test]
+    fn test_mock_audio_events() {
+        let steps = vec![
+            StreamStep::Chunk(vec![0.0; 512]),
+            StreamStep::Format {
+                rate: 44_100,
+                channels: 1,
+            },
+            // The ring holds exactly the first two chunks.
+            StreamStep::Chunk(vec![0.0; 512]),
+            StreamStep::Chunk(vec![0.0; 512]),
+            StreamStep::Disconnect,
+        ];
+        let server = MockServer::new().source(3, "deck", AudioSourceKind::ApplicationStream, steps);
+        let context = AudioContext::mock(server);
+        let events = context.events().unwrap();
+        let options = ConnectOptions::new("test").with_ring_bytes(4096);
+        let _consumer = context
+            .connect_with(&choice(&context, "deck"), &options)
+            .unwrap();
+
+        let mut seen = Vec::new();
+        while let Ok(event) = events.recv_timeout(TIMEOUT) {
+            match event {
+                AudioEvent::StreamError { error, .. } => panic!("unexpected error {error}"),
+                AudioEvent::StreamStateChanged { stream, state } => {
+                    assert_eq!(stream, "test");
+                    seen.push(format!("{state:?}"));
+                }
+                AudioEvent::XRun { stream } => {
+                    assert_eq!(stream, "test");
+                    seen.push("XRun".to_owned());
+                }
+                AudioEvent::FormatChanged { format, .. } => seen.push(format!("{}", format.rate)),
+                AudioEvent::Suspended { .. } | AudioEvent::Resumed { .. } => {
+                    panic!("unexpected {event}")
+                }
+                AudioEvent::QuantumChanged { .. } | AudioEvent::Connected { .. } => {}
+            }
+            if seen.last().is_some_and(|s| s == "Unconnected") {
+                break;
+            }
+        }
+        assert_eq!(seen, ["48000", "Streaming", "44100", "XRun", "Unconnected"]);
+    }
+
+    #[test]
+    fn test_mock_audio_event_backlog() {
+        let steps = vec![StreamStep::Chunk(vec![0.0; 256])];
+        let server = MockServer::new().source(6, "tape", AudioSourceKind::ApplicationStream, steps);
+        let context = AudioContext::mock(server);
+        let consumer = context.connect(&choice(&context, "tape"), "test").unwrap();
+        wait_for_bytes(&consumer, 256 * 4);
+
+        // Emitted before anyone subscribed, and still delivered to the first subscriber.
+        let events = context.events().unwrap();
+        match events.recv_timeout(TIMEOUT).unwrap() {
+            AudioEvent::FormatChanged { stream, .. } => assert_eq!(stream, "test"),
+            e => panic!("unexpected {e}"),
+        }
+        // Later subscribers only see what comes after them.
+        let late = context.events().unwrap();
+        assert!(late.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_mock_shutdown() {
+        let server = MockServer::new().source(
+            4,
+            "radio",
+            AudioSourceKind::ApplicationStream,
+            vec![StreamStep::Chunk(vec![0.0; 256])],
+        );
+        let context = AudioContext::mock(server);
+        let mut consumer = context.connect(&choice(&context, "radio"), "test").unwrap();
+        wait_for_bytes(&consumer, 256 * 4);
+        context.shutdown().unwrap();
+        wait_for_drop(&mut consumer);
+    }
+
+    #[test]
+    fn test_mock_overrun_policy() {
+        let ramp: Vec<f32> = (0..512).map(|i| i as f32).collect();
+        let steps = vec![
+            StreamStep::Chunk(ramp.clone()),
+            StreamStep::Chunk(vec![1.0; 512]),
+            // The ring holds exactly the first two chunks.
+            StreamStep::Chunk(vec![2.0; 512]),
+        ];
+        let server = MockServer::new().source(5, "deck", AudioSourceKind::ApplicationStream, steps);
+        let context = AudioContext::mock(server);
+        let deck = choice(&context, "deck");
+        let options = ConnectOptions::new("test").with_ring_bytes(4096);
+        let wait_for_overrun = |consumer: &AudioConsumer| {
+            let deadline = Instant::now() + TIMEOUT;
+            while consumer.overruns() == 0 {
+                assert!(Instant::now() < deadline, "never overran");
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        };
+
+        let mut newest = context.connect_with(&deck, &options).unwrap();
+        wait_for_overrun(&newest);
+        let samples = read_f32(&mut newest);
+        assert_eq!(&samples[..512], &ramp[..]);
+        assert_eq!(&samples[512..], &[1.0; 512]);
+
+        let options = options.with_overrun(Overrun::DropOldest);
+        let mut oldest = context.connect_with(&deck, &options).unwrap();
+        wait_for_overrun(&oldest);
+        let mut frames = [[0.0f32; 2]; 512];
+        assert_eq!(oldest.read_frames(&mut frames).unwrap(), 256);
+        assert!(frames[..256].iter().flatten().all(|s| *s == 1.0));
+    }
+
+    #[test]
+    fn test_mock_max_delay() {
+        let ramp: Vec<f32> = (0..1024).map(|i| i as f32).collect();
+        let server = MockServer::new().source(
+            6,
+            "deck",
+            AudioSourceKind::ApplicationStream,
+            vec![StreamStep::Chunk(ramp.clone())],
+        );
+        let context = AudioContext::mock(server);
+        // 128 frames at the mock's 48kHz.
+        let options =
+            ConnectOptions::new("test").with_max_delay(Duration::from_secs_f64(128.0 / 48_000.0));
+        let mut consumer = context
+            .connect_with(&choice(&context, "deck"), &options)
+            .unwrap();
+        wait_for_bytes(&consumer, 1024 * 4);
+        let mut frames = [[0.0f32; 2]; 512];
+        assert_eq!(consumer.read_frames(&mut frames).unwrap(), 128);
+        assert_eq!(frames[0], [768.0, 769.0]);
+        assert_eq!(consumer.overruns(), 0);
+    }
+
+    #[test]
+    fn test_mock_unknown_source() {
+        let context = AudioContext::mock(MockServer::new());
+        let ghost = AudioChoice {
+            kind: AudioSourceKind::SinkMonitor,
+            name: None,
+            application: None,
+            object_serial: 99,
+            global_id: 99,
+        };
+        let mut consumer = context.connect(&ghost, "test").unwrap();
+        wait_for_drop(&mut consumer);
+    }
+}
@@ -15,9 +15,10 @@
 //! [`AudioContext`] sets up communication threads that receive mapped buffers from an audio server
 //! such as Pipewire.  The communication thread either polls or tracks available audio sources.
 //! [`AudioContext`] provides [`with_choices`] and [`with_choices_blocking`] methods for displaying
-//! those choices to the user.  An [`AudioChoice`] can be used to call [`connect`], which will
+//! those choices to the user, and [`choice_events`] for keeping a display up to date.  An [`AudioChoice`] can be used to call [`connect`], which will
 //! return an [`AudioConsumer`].  An `AudioConsumer`, which is backed by a ring buffer, provides
-//! synchronization data, media names, and access to the sliding window for reads.
+//! synchronization data, media names, and access to the sliding window for reads.  Failures and
+//! stream changes on the audio thread arrive as [`AudioEvent`]s from [`AudioContext::events`].
 //!
 //! ## Implementations
 //!
@@ -62,9 +63,9 @@
 // such as i64 etc.
 // NOTE The model for receiving stream data from pipewire, which might hold up when talking to other
 // audio servers, is that pipewire sends us monotonic buffer chunks without skips (via padding or
-// stream parameter change, the latter of which is not yet handled).  Due to audio playback being
-// naturally self-pacing, the monotonic chunks without skips behavior provides implicit relative
-// timing signal without use of any explicit time values.
+// stream parameter change, the latter of which consumers see as a `FormatChange`).  Due to audio
+// playback being naturally self-pacing, the monotonic chunks without skips behavior provides
+// implicit relative timing signal without use of any explicit time values.
 // NEXT Absolute presentation timing data may be obtainable, but seems to require customizing our
 // pipewire link to match the presentation timing of the sink monitor.
 // FIXME cfg gates on Linux need features instead.
@@ -72,13 +73,39 @@
 // Also looks like we need DBUS for seeing Spotify title changes.  If we have it, we can render
 // title changes in the middle of playback, something Milkdrop has done right for twenty years or
 // so.
+pub mod channel;
+pub mod demo;
+#[cfg(feature = "file")]
+pub mod file;
 #[cfg(feature = "vulkan")]
 pub mod import;
+#[cfg(any(feature = "file", feature = "dsp"))]
+mod local;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod node;
+#[cfg(feature = "dsp")]
+pub mod probe;
+#[cfg(feature = "file")]
+pub mod record;
+#[cfg(feature = "dsp")]
+pub mod synthetic;
 pub mod timing;
 
-use std::cell::UnsafeCell;
-use std::sync::atomic;
-use std::time::Instant;
+pub mod prelude {
+    pub use super::channel::{Channel, ChannelMap};
+    pub use super::timing::AudioTimestamp;
+    pub use super::{
+        AudioChoice, AudioConsumer, AudioContext, AudioEvent, AudioSourceKind, ChoiceEvent,
+        ConnectOptions, FormatChange, LatencyHint, Overrun, Reconnect, StereoFrame, StreamFormat,
+        StreamState,
+    };
+}
+
+use std::cell::{Cell, UnsafeCell};
+use std::collections::VecDeque;
+use std::sync::{atomic, mpsc};
+use std::time::{Duration, Instant};
 
 #[cfg(target_os = "linux")]
 use pipewire::{self as pw, main_loop::MainLoopBox, spa, stream::StreamListener};
@@ -121,18 +148,390 @@ impl std::fmt::Display for AudioSourceKind {
     }
 }
 
+/// Sample rate that [`LatencyHint`] quanta are expressed against.  The server scales the request
+/// when the graph runs at another rate.
+pub const LATENCY_RATE: u32 = 48_000;
+
+/// How many frames the audio server should deliver per process callback.  Smaller quanta reach the
+/// visuals sooner but give the server less slack before an xrun, which is heard as crackling.
+///
+/// This is a request.  Pipewire rounds up to a power of two and runs the whole graph at the largest
+/// quantum any node asked for, so read the outcome from [`AudioConsumer::quantum`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LatencyHint {
+    /// 256 frames, about 5ms.  For visuals that track transients tightly.
+    Low,
+    /// 512 frames, about 11ms.  Roughly one quantum per frame at 60Hz.
+    #[default]
+    Balanced,
+    /// 2048 frames, about 43ms.  For background analysis, such as a daemon, that should never
+    /// cause an xrun.
+    Relaxed,
+    /// An explicit quantum in frames at [`LATENCY_RATE`].
+    Frames(u32),
+}
+
+impl LatencyHint {
+    /// Requested frames per process callback at [`LATENCY_RATE`].
+    pub fn frames(&self) -> u32 {
+        match self {
+            LatencyHint::Low => 256,
+            LatencyHint::Balanced => 512,
+            LatencyHint::Relaxed => 2048,
+            LatencyHint::Frames(n) => (*n).max(1),
+        }
+    }
+
+    /// Requested time between process callbacks.
+    pub fn period(&self) -> Duration {
+        Duration::from_secs_f64(self.frames() as f64 / LATENCY_RATE as f64)
+    }
+
+    /// Value for `PW_KEY_NODE_LATENCY`.
+    #[cfg(target_os = "linux")]
+    fn node_latency(&self) -> String {
+        format!("{}/{}", self.frames(), LATENCY_RATE)
+    }
+}
+
+/// Ring size when [`ConnectOptions::ring_bytes`] is not set, about 0.7s of stereo at 48kHz.
+pub const DEFAULT_RING_BYTES: usize = 1024 * 256;
+
+/// What a connection gives up when its consumer falls so far behind that the ring fills.
+///
+/// The producer cannot discard bytes the consumer owns, so the chunk that does not fit is lost
+/// under either policy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overrun {
+    /// Keep the queued audio.  Consumers see a gap where the lost chunks were.  For analysis that
+    /// wants every sample it can get.
+    #[default]
+    DropNewest,
+    /// Also discard the oldest queued audio on the consumer's next read so that it catches up to
+    /// the present.  For visuals, where late audio is worse than missing audio.
+    DropOldest,
+}
+
+/// What a connection does when its source disappears, such as a Bluetooth headset turning off.
+///
+/// Under [`Never`](Self::Never) and [`SameName`](Self::SameName) the connection is suspended
+/// rather than dropped.  The consumer keeps its ring, which stops filling, and
+/// [`AudioEvent::Suspended`] and [`AudioEvent::Resumed`] report the gap.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Reconnect {
+    /// Leave it to the audio server, which usually moves the stream to the default device.
+    /// Application streams are never moved, since the default is likely a microphone.
+    #[default]
+    Server,
+    /// Stay suspended.
+    Never,
+    /// Resume from the next choice of the same kind and name, such as the headset coming back.
+    SameName,
+}
+
+/// Per-connection settings for [`AudioContext::connect_with`].
+#[derive(Clone, Debug)]
+pub struct ConnectOptions {
+    /// The name of the connection we are creating.  Displays our application correctly in
+    /// patchbays and mixers.
+    pub name: String,
+    pub latency: LatencyHint,
+    /// Ring capacity.  Larger rings survive longer consumer stalls.
+    pub ring_bytes: usize,
+    pub overrun: Overrun,
+    /// With [`Overrun::DropOldest`], the most audio a consumer is allowed to have queued.  Older
+    /// audio is discarded on each read, even before the ring fills.
+    pub max_delay: Option<Duration>,
+    pub reconnect: Reconnect,
+    /// Channels to ask the server for.  The server mixes the source to this count.  `None` takes
+    /// whatever the source has.  Backends without a mixer ignore it, so check
+    /// [`AudioConsumer::channel_map`] either way.
+    pub channels: Option<u32>,
+}
+
+impl ConnectOptions {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            latency: LatencyHint::default(),
+            ring_bytes: DEFAULT_RING_BYTES,
+            overrun: Overrun::default(),
+            max_delay: None,
+            reconnect: Reconnect::default(),
+            channels: None,
+        }
+    }
+
+    pub fn with_latency(mut self, latency: LatencyHint) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn with_ring_bytes(mut self, ring_bytes: usize) -> Self {
+        self.ring_bytes = ring_bytes;
+        self
+    }
+
+    pub fn with_overrun(mut self, overrun: Overrun) -> Self {
+        self.overrun = overrun;
+        self
+    }
+
+    /// Keep at most `max_delay` of audio queued, dropping the oldest.  Implies
+    /// [`Overrun::DropOldest`].
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.overrun = Overrun::DropOldest;
+        self.max_delay = Some(max_delay);
+        self
+    }
+
+    pub fn with_reconnect(mut self, reconnect: Reconnect) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    pub fn with_channels(mut self, channels: u32) -> Self {
+        self.channels = Some(channels.max(1));
+        self
+    }
+}
+
+/// The processing quantum the server actually delivers, measured from the buffers it sends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quantum {
+    /// Frames per process callback.
+    pub frames: u32,
+    /// Sample rate of the stream.
+    pub rate: u32,
+}
+
+impl Quantum {
+    /// Time covered by one quantum, the delay it adds to everything downstream.
+    pub fn period(&self) -> Duration {
+        Duration::from_secs_f64(self.frames as f64 / self.rate as f64)
+    }
+}
+
+/// Layout of the samples in a stream.  Samples are always interleaved F32LE.  Which speaker each
+/// channel feeds is the [`ChannelMap`](channel::ChannelMap) that arrives with the format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamFormat {
+    pub rate: u32,
+    pub channels: u32,
+}
+
+impl StreamFormat {
+    /// Bytes per interleaved frame.
+    pub fn frame_bytes(&self) -> usize {
+        self.channels as usize * 4
+    }
+}
+
+/// One frame of a stereo stream, left then right.
+pub type StereoFrame = [f32; 2];
+
+/// The server renegotiated the stream format, such as when the monitored device switches from 48k
+/// to 44.1k.  Returned by [`AudioConsumer::format_change`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FormatChange {
+    /// `None` for the first format of a connection.
+    pub old: Option<StreamFormat>,
+    pub new: StreamFormat,
+}
+
+/// Formats published by the producer that the consumer has not reached yet.  Each entry begins at
+/// a total byte count so that consumers can switch exactly where the bytes do.
+#[derive(Default)]
+struct FormatLog {
+    /// The most recently published format.
+    latest: Option<StreamFormat>,
+    map: channel::ChannelMap,
+    pending: VecDeque<(u64, StreamFormat, channel::ChannelMap)>,
+}
+
 /// Commands for calling into the Audio thread
 enum Message {
     /// Connect to a particular identifier
     Connect {
-        /// The name of the connection we are creating.  Displays our application correctly
-        name: String,
+        options: ConnectOptions,
         choice: AudioChoice,
         tx: AudioProducer,
     },
     Terminate,
 }
 
+/// A change to the available choices.  See [`AudioContext::choice_events`].
+#[derive(Clone, Debug)]
+pub enum ChoiceEvent {
+    Added(AudioChoice),
+    Removed(AudioChoice),
+}
+
+/// Something that happened on the audio thread.  See [`AudioContext::events`].
+///
+/// Streams are named by the [`ConnectOptions::name`] they connected with.
+#[derive(Clone, Debug)]
+pub enum AudioEvent {
+    /// A failure nobody called into.  `stream` is `None` for failures of the context itself, such
+    /// as the server connection or source listing.
+    StreamError {
+        stream: Option<String>,
+        error: String,
+    },
+    StreamStateChanged {
+        stream: String,
+        state: StreamState,
+    },
+    /// The consumer fell behind and a chunk was lost.  See [`AudioConsumer::overruns`].
+    XRun {
+        stream: String,
+    },
+    /// The format of bytes written from now on, including the first negotiated format.
+    FormatChanged {
+        stream: String,
+        format: StreamFormat,
+    },
+    /// The source went away.  See [`Reconnect`].
+    Suspended {
+        stream: String,
+    },
+    /// A suspended stream is capturing again, from `source`.
+    Resumed {
+        stream: String,
+        source: String,
+    },
+    /// The server delivers `frames` per buffer instead of `old`.
+    QuantumChanged {
+        stream: String,
+        old: u32,
+        frames: u32,
+    },
+    /// The stream was linked to the server object with serial `serial`, capturing `target` if the
+    /// server names it.
+    Connected {
+        stream: String,
+        serial: String,
+        target: Option<String>,
+    },
+}
+
+impl std::fmt::Display for AudioEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StreamError {
+                stream: Some(stream),
+                error,
+            } => write!(f, "{stream}: {error}"),
+            Self::StreamError {
+                stream: None,
+                error,
+            } => write!(f, "{error}"),
+            Self::StreamStateChanged { stream, state } => write!(f, "{stream}: {state:?}"),
+            Self::XRun { stream } => write!(f, "{stream}: consumer falling behind"),
+            Self::FormatChanged { stream, format } => write!(
+                f,
+                "{stream}: rate:{} channels:{}",
+                format.rate, format.channels
+            ),
+            Self::Suspended { stream } => write!(f, "{stream}: source disappeared"),
+            Self::Resumed { stream, source } => write!(f, "{stream}: resumed from {source}"),
+            Self::QuantumChanged {
+                stream,
+                old,
+                frames,
+            } => write!(f, "{stream}: quantum changed {old} -> {frames} frames"),
+            Self::Connected {
+                stream,
+                serial,
+                target: Some(target),
+            } => write!(f, "{stream}: object {serial} capturing {target}"),
+            Self::Connected {
+                stream,
+                serial,
+                target: None,
+            } => write!(f, "{stream}: object {serial}"),
+        }
+    }
+}
+
+/// Lifecycle of a stream as the server reports it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamState {
+    Connecting,
+    Paused,
+    Streaming,
+    /// The server dropped the stream, or it never connected.
+    Unconnected,
+    Error(String),
+}
+
+#[cfg(target_os = "linux")]
+impl From<pw::stream::StreamState> for StreamState {
+    fn from(state: pw::stream::StreamState) -> Self {
+        match state {
+            pw::stream::StreamState::Connecting => Self::Connecting,
+            pw::stream::StreamState::Paused => Self::Paused,
+            pw::stream::StreamState::Streaming => Self::Streaming,
+            pw::stream::StreamState::Unconnected => Self::Unconnected,
+            pw::stream::StreamState::Error(e) => Self::Error(e),
+        }
+    }
+}
+
+/// Events kept while nobody is subscribed.  Older ones are dropped.
+const EVENT_BACKLOG: usize = 64;
+
+/// Subscribers to [`AudioEvent`]s, shared by the context, its thread, and every producer.
+#[derive(Default)]
+struct AudioEvents {
+    subscribers: std::sync::Mutex<Subscribers>,
+}
+
+#[derive(Default)]
+struct Subscribers {
+    senders: Vec<mpsc::Sender<AudioEvent>>,
+    /// Emitted while nobody was subscribed, for the next subscriber.
+    backlog: std::collections::VecDeque<AudioEvent>,
+}
+
+impl AudioEvents {
+    /// Send to every subscriber, forgetting those that hung up.  With no subscribers, the event
+    /// waits in a short backlog so that failures before anyone subscribes are not lost.  A
+    /// poisoned lock drops the event, since the panic that poisoned it was already reported.
+    fn emit(&self, event: AudioEvent) {
+        let Ok(mut subscribers) = self.subscribers.lock() else {
+            return;
+        };
+        subscribers
+            .senders
+            .retain(|tx| tx.send(event.clone()).is_ok());
+        if subscribers.senders.is_empty() {
+            if subscribers.backlog.len() == EVENT_BACKLOG {
+                subscribers.backlog.pop_front();
+            }
+            subscribers.backlog.push_back(event);
+        }
+    }
+
+    /// Emit a [`AudioEvent::StreamError`] for the context itself.
+    fn error(&self, error: impl std::fmt::Display) {
+        self.emit(AudioEvent::StreamError {
+            stream: None,
+            error: error.to_string(),
+        });
+    }
+
+    fn subscribe(&self) -> Result<mpsc::Receiver<AudioEvent>, MutateError> {
+        let (tx, rx) = mpsc::channel();
+        let mut subscribers = self.subscribers.lock()?;
+        for event in subscribers.backlog.drain(..) {
+            let _ = tx.send(event);
+        }
+        subscribers.senders.push(tx);
+        Ok(rx)
+    }
+}
+
 /// Even-driven clients will maintain a client-side view.  Polling clients do not require
 /// maintaining a view of stream choices.
 // XXX cfg and feature gates
@@ -141,6 +540,8 @@ struct AudioChoices {
     choices: std::sync::Mutex<Vec<AudioChoice>>,
     version: atomic::AtomicUsize,
     initialized: atomic::AtomicBool,
+    /// Locked after `choices` so that subscribers see every change exactly once.
+    subscribers: std::sync::Mutex<Vec<mpsc::Sender<ChoiceEvent>>>,
 }
 
 impl AudioChoices {
@@ -149,16 +550,75 @@ impl AudioChoices {
         self.ready.notify_all();
     }
 
+    /// Send to every subscriber, forgetting those that hung up.  Call with `choices` locked.
+    fn broadcast(&self, events: impl IntoIterator<Item = ChoiceEvent>) {
+        let Ok(mut subscribers) = self.subscribers.lock() else {
+            return;
+        };
+        for event in events {
+            subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+        }
+    }
+
+    /// Replace the choices all at once and wake anyone waiting on the first set.  For backends
+    /// that know every choice up front.
+    /// A poisoned lock keeps the old choices, since the panic that poisoned it was already
+    /// reported.
+    fn publish(&self, choices: impl IntoIterator<Item = AudioChoice>) {
+        if let Ok(mut guard) = self.choices.lock() {
+            let old = std::mem::take(&mut *guard);
+            guard.extend(choices);
+            let removed = old.into_iter().map(ChoiceEvent::Removed);
+            let added = guard.iter().cloned().map(ChoiceEvent::Added);
+            self.broadcast(removed.chain(added));
+        }
+        self.version.fetch_add(1, atomic::Ordering::Relaxed);
+        self.notify();
+    }
+
+    fn add(&self, choice: AudioChoice) -> Result<(), MutateError> {
+        let mut guard = self.choices.lock()?;
+        self.broadcast([ChoiceEvent::Added(choice.clone())]);
+        guard.push(choice);
+        self.version.fetch_add(1, atomic::Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Remove the choice registered under `global_id`, if any.
+    #[cfg(target_os = "linux")]
+    fn remove(&self, global_id: u32) -> Result<(), MutateError> {
+        let mut guard = self.choices.lock()?;
+        if let Some(found) = guard.iter().position(|c| c.global_id == global_id) {
+            self.broadcast([ChoiceEvent::Removed(guard.remove(found))]);
+            self.version.fetch_add(1, atomic::Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
     fn new() -> Self {
         Self {
             ready: std::sync::Condvar::new(),
             choices: std::sync::Mutex::new(Vec::new()),
             version: atomic::AtomicUsize::new(0),
             initialized: atomic::AtomicBool::new(false),
+            subscribers: std::sync::Mutex::new(Vec::new()),
         }
     }
 }
 
+/// Version of the PipeWire client library loaded at runtime, for bug reports.
+// NEXT the server version arrives in the core info event.  Report it once the context listens.
+#[cfg(target_os = "linux")]
+pub fn library_version() -> String {
+    // Safety: PipeWire returns a pointer to a static string.
+    unsafe { std::ffi::CStr::from_ptr(pw::sys::pw_get_library_version()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// How long [`AudioContext::shutdown`] waits for the audio thread.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// `AudioContext` represents the connection to an audio server, which usually takes care of
 /// multiplexing the applications and hardware devices.  Most workflows need to look for usable
 /// audio streams before obtaining connections.  The `AudioContext` provides access to usable
@@ -169,9 +629,29 @@ impl AudioChoices {
 pub struct AudioContext {
     handle: Option<std::thread::JoinHandle<()>>,
     choices: *mut AudioChoices,
+    events: std::sync::Arc<AudioEvents>,
 
-    #[cfg(target_os = "linux")]
-    tx: pw::channel::Sender<Message>,
+    tx: Box<dyn Backend>,
+}
+
+/// Where [`Message`]s to the audio thread go.  Each audio server, and each server of our own such
+/// as the demo and mock backends, runs a thread that receives them.
+trait Backend {
+    fn send(&self, msg: Message) -> Result<(), MutateError>;
+}
+
+#[cfg(target_os = "linux")]
+impl Backend for pw::channel::Sender<Message> {
+    fn send(&self, msg: Message) -> Result<(), MutateError> {
+        pw::channel::Sender::send(self, msg).map_err(|_| MutateError::AudioThreadGone)
+    }
+}
+
+/// A backend running on a thread of our own.
+impl Backend for mpsc::Sender<Message> {
+    fn send(&self, msg: Message) -> Result<(), MutateError> {
+        mpsc::Sender::send(self, msg).map_err(|_| MutateError::AudioThreadGone)
+    }
 }
 
 impl AudioContext {
@@ -188,17 +668,19 @@ impl AudioContext {
         let choices = Box::into_raw(Box::new(AudioChoices::new()));
         let choices_addr = choices as usize;
         let (pw_sender, pw_receiver) = pipewire::channel::channel();
+        let events = std::sync::Arc::new(AudioEvents::default());
+        let thread_events = events.clone();
         let handle = std::thread::spawn(move || {
+            let events = thread_events;
             // Safety: AudioContext::drop joins this thread before freeing choices, so &AudioChoices
             // is valid for the thread's entire lifetime.
             let choices: &AudioChoices = unsafe { &*(choices_addr as *mut AudioChoices) };
-            // Due to borrowed data and lack of try blocks in stable, Rust, seems like this is an
-            // okay-ish way to know of issues in the terminal without forcing callers to fail.  At
-            // least that's the goal.
+            // Due to borrowed data and lack of try blocks in stable, Rust, failures are reported as
+            // events rather than returned.  Nobody has subscribed yet, so they reach stderr.
             let mainloop = match MainLoopBox::new(None) {
                 Ok(mainloop) => mainloop,
                 Err(e) => {
-                    eprintln!("PipeWire initialization failed: {:?}", MutateError::from(e));
+                    events.error(format!("initialization failed: {}", MutateError::from(e)));
                     return;
                 }
             };
@@ -206,7 +688,7 @@ impl AudioContext {
             let context = match pw::context::ContextBox::new(&mainloop.loop_(), None) {
                 Ok(context) => context,
                 Err(e) => {
-                    eprintln!("PipeWire initialization failed: {:?}", MutateError::from(e));
+                    events.error(format!("initialization failed: {}", MutateError::from(e)));
                     return;
                 }
             };
@@ -214,7 +696,7 @@ impl AudioContext {
             let core = match context.connect(None) {
                 Ok(core) => core,
                 Err(e) => {
-                    eprintln!("PipeWire initialization failed: {:?}", MutateError::from(e));
+                    events.error(format!("initialization failed: {}", MutateError::from(e)));
                     return;
                 }
             };
@@ -222,7 +704,7 @@ impl AudioContext {
             let registry = match core.get_registry() {
                 Ok(registry) => registry,
                 Err(e) => {
-                    eprintln!("PipeWire initialization failed: {:?}", MutateError::from(e));
+                    events.error(format!("initialization failed: {}", MutateError::from(e)));
                     return;
                 }
             };
@@ -233,24 +715,34 @@ impl AudioContext {
             let _receiver = pw_receiver.attach(mainloop.loop_(), {
                 let mainloop_ptr = mainloop.as_raw_ptr();
                 let core_ptr = core.as_raw_ptr();
+                let events = events.clone();
                 move |message| match message {
-                    Message::Connect { choice, tx, name } => {
-                        let conn_ptr = tx.conn;
-                        match create_stream(core_ptr, &choice, &name, tx) {
+                    Message::Connect {
+                        choice,
+                        tx,
+                        options,
+                    } => {
+                        let conn = tx.conn;
+                        match create_stream(core_ptr, &choice, &options, tx) {
                             Ok((listener, stream)) => {
                                 unsafe { &mut *pw_connections }.push(PipewireConnection {
                                     stream: Some(stream),
                                     listener: Some(listener),
+                                    choice,
+                                    options,
+                                    conn,
                                 });
                             }
-                            Err(e) => {
-                                eprintln!("stream creation failed: {}", e);
-                            }
+                            Err(e) => events.emit(AudioEvent::StreamError {
+                                stream: Some(options.name),
+                                error: format!("stream creation failed: {e}"),
+                            }),
                         };
                     }
                     Message::Terminate => {
-                        unsafe { drop(Box::from_raw(pw_connections)) };
-                        eprintln!("Terminating mainloop");
+                        // Registry listeners still hold the pointer, so the box is freed after the
+                        // loop exits.
+                        unsafe { &mut *pw_connections }.clear();
                         unsafe { pw::sys::pw_main_loop_quit(mainloop_ptr) };
                     }
                 }
@@ -263,80 +755,113 @@ impl AudioContext {
 
             let _monitor_listener = registry
                 .add_listener_local()
-                .global(move |global| {
-                    // NEXT this will become a big match statement in order to track a node -> ports
-                    // mapping.
-                    if global.type_ != pw::types::ObjectType::Node {
-                        return;
-                    }
+                .global({
+                    let events = events.clone();
+                    move |global| {
+                        // NEXT this will become a big match statement in order to track a node ->
+                        // ports mapping.
+                        if global.type_ != pw::types::ObjectType::Node {
+                            return;
+                        }
 
-                    let Some(props) = &global.props else { return };
-                    let Some(media_class) = props.get("media.class") else {
-                        return;
-                    };
-                    let Some(kind) = AudioSourceKind::from_media_class(media_class) else {
-                        return;
-                    };
+                        let Some(props) = &global.props else { return };
+                        let Some(media_class) = props.get("media.class") else {
+                            return;
+                        };
+                        let Some(kind) = AudioSourceKind::from_media_class(media_class) else {
+                            return;
+                        };
 
-                    match AudioChoice::try_new(kind, *props, global.id) {
-                        Ok(choice) => match choices.choices.lock() {
-                            Ok(mut guard) => guard.push(choice),
-                            Err(e) => eprintln!(
-                                "listing audio source failed.  skipping: {:?}",
-                                MutateError::from(e)
-                            ),
-                        },
-                        Err(e) => eprintln!("Skipping {}: {:?}", media_class, e),
+                        match AudioChoice::try_new(kind, *props, global.id) {
+                            Ok(choice) => {
+                                let connections = unsafe { &mut *pw_connections };
+                                for pc in connections.iter_mut().filter(|pc| pc.resumes(&choice)) {
+                                    pc.resume(&choice, &events);
+                                }
+                                if let Err(e) = choices.add(choice) {
+                                    events.error(e);
+                                }
+                            }
+                            Err(e) => events.error(e),
+                        }
                     }
                 })
                 .register();
 
             let _remove_listener = registry
                 .add_listener_local()
-                .global_remove(move |removed_id| match choices.choices.lock() {
-                    Ok(mut choices) => {
-                        if let Some(found) =
-                            choices.iter_mut().position(|c| c.global_id == removed_id)
-                        {
-                            choices.remove(found);
+                .global_remove({
+                    let events = events.clone();
+                    move |removed_id| {
+                        let connections = unsafe { &mut *pw_connections };
+                        for pc in connections.iter_mut() {
+                            if pc.choice.global_id == removed_id && !pc.server_reconnects() {
+                                pc.suspend(&events);
+                            }
+                        }
+                        if let Err(e) = choices.remove(removed_id) {
+                            events.error(e);
                         }
-                    }
-                    Err(e) => {
-                        eprintln!("removing audio source failed: {:?}", MutateError::from(e));
                     }
                 })
                 .register();
 
             match core.sync(0) {
                 Err(e) => {
-                    eprintln!("PipeWire initialization failed: {:?}", MutateError::from(e));
+                    events.error(format!("initialization failed: {}", MutateError::from(e)));
                     return;
                 }
                 _ => {}
             };
 
             mainloop.run();
+            unsafe { drop(Box::from_raw(pw_connections)) };
         });
 
         Ok(AudioContext {
             handle: Some(handle),
             choices,
-            tx: pw_sender,
+            events,
+            tx: Box::new(pw_sender),
         })
     }
 
-    /// Connect to a stream
+    /// Connect to a stream with default [`ConnectOptions`].
     pub fn connect(&self, choice: &AudioChoice, name: &str) -> Result<AudioConsumer, MutateError> {
-        let conn = AudioConnection::new();
+        self.connect_with(choice, &ConnectOptions::new(name))
+    }
+
+    /// Connect to a stream, requesting a processing quantum with [`ConnectOptions::latency`].
+    pub fn connect_with(
+        &self,
+        choice: &AudioChoice,
+        options: &ConnectOptions,
+    ) -> Result<AudioConsumer, MutateError> {
+        let conn = AudioConnection::new(options);
         let msg = Message::Connect {
             choice: choice.clone(),
-            tx: AudioProducer { conn: conn.clone() },
-            name: name.to_owned(),
+            tx: AudioProducer {
+                conn: conn.clone(),
+                events: self.events.clone(),
+                stream: options.name.clone(),
+            },
+            options: options.clone(),
         };
-        self.tx
-            .send(msg)
-            .map_err(|_e| MutateError::AudioConnect("connection creation failed"))?;
-        Ok(AudioConsumer { conn })
+        self.tx.send(msg)?;
+        Ok(AudioConsumer {
+            conn,
+            overrun: options.overrun,
+            max_delay: options.max_delay,
+            consumed: Cell::new(0),
+            format: None,
+            map: Default::default(),
+            downmix: None,
+            unseen: None,
+            scratch: Vec::new(),
+            window: None,
+            #[cfg(feature = "file")]
+            tap: None,
+        })
     }
 
     /// Connect a stream and import it into a device-side ring.
@@ -351,7 +876,20 @@ impl AudioContext {
         sample_count: u32,
         name: &str,
     ) -> Result<import::Consumer<CHANNELS>, MutateError> {
-        import::Consumer::new(self, device, choice, sample_count, name)
+        let options = ConnectOptions::new(name);
+        import::Consumer::new(self, device, choice, sample_count, &options)
+    }
+
+    /// Connect with `options`, such as a latency hint, and import the stream into a device-side ring.
+    #[cfg(feature = "vulkan")]
+    pub fn import_to_device_with<const CHANNELS: usize>(
+        &self,
+        device: &Device,
+        choice: &AudioChoice,
+        sample_count: u32,
+        options: &ConnectOptions,
+    ) -> Result<import::Consumer<CHANNELS>, MutateError> {
+        import::Consumer::new(self, device, choice, sample_count, options)
     }
 
     pub fn choices_version(&self) -> usize {
@@ -362,6 +900,27 @@ impl AudioContext {
             .load(atomic::Ordering::Relaxed)
     }
 
+    /// Receive a [`ChoiceEvent`] whenever a source appears or disappears.  Every current choice is
+    /// sent as `Added` first so that a picker can be built from the events alone.  GUIs can drain
+    /// the receiver with `try_recv` once per frame.  The channel closes when the context drops.
+    pub fn choice_events(&self) -> Result<mpsc::Receiver<ChoiceEvent>, MutateError> {
+        let ac: &AudioChoices = unsafe { &*self.choices };
+        let choices = ac.choices.lock()?;
+        let (tx, rx) = mpsc::channel();
+        for choice in choices.iter() {
+            let _ = tx.send(ChoiceEvent::Added(choice.clone()));
+        }
+        ac.subscribers.lock()?.push(tx);
+        Ok(rx)
+    }
+
+    /// Receive an [`AudioEvent`] for every stream error, state change, overrun, and format change.
+    /// The first subscriber also receives the last few events emitted while nobody was subscribed.
+    /// The channel closes when the context drops.
+    pub fn events(&self) -> Result<mpsc::Receiver<AudioEvent>, MutateError> {
+        self.events.subscribe()
+    }
+
     /// Run a function on the most recent choices.  If you need to wait on the first updates, use
     /// [`with_choices_blocking`] instead.  Your provided function should complete quickly because
     /// it uses a lock that will block the audio thread.  If you need more time, record a copy of
@@ -375,6 +934,33 @@ impl AudioContext {
         Ok(())
     }
 
+    /// Stop the audio thread and wait up to [`SHUTDOWN_TIMEOUT`] for it to exit.  Live streams are
+    /// torn down, so their consumers start returning [`MutateError::Dropped`].  Dropping the
+    /// context does the same but can only log failures.
+    pub fn shutdown(mut self) -> Result<(), MutateError> {
+        self.terminate()
+    }
+
+    fn terminate(&mut self) -> Result<(), MutateError> {
+        let Some(handle) = self.handle.take() else {
+            return Ok(());
+        };
+        // Send Terminate first so the thread stops touching choices.  If the thread is already
+        // gone, the send fails and the join below still collects it.
+        let _ = self.tx.send(Message::Terminate);
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        while !handle.is_finished() {
+            if Instant::now() >= deadline {
+                // The thread may still touch the choices, so they leak instead of being freed.
+                return Err(MutateError::Timeout("audio thread did not exit"));
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let joined = handle.join().map_err(|_| MutateError::AudioTerminate);
+        unsafe { drop(Box::from_raw(self.choices)) };
+        joined
+    }
+
     /// Same as with_choices, but will wait for choices to be initially populated, which may require
     /// waiting on the audio server thread when called immediately after creating the context.  The
     /// timeout is one second and will return an error.
@@ -400,12 +986,9 @@ impl AudioContext {
 
 impl Drop for AudioContext {
     fn drop(&mut self) {
-        // send Terminate first so the thread stops touching choices
-        let _ = self.tx.send(Message::Terminate);
-        if self.handle.take().unwrap().join().is_err() {
-            eprintln!("audio thread panicked");
+        if let Err(e) = self.terminate() {
+            eprintln!("audio shutdown failed: {:?}", e);
         }
-        unsafe { drop(Box::from_raw(self.choices)) };
     }
 }
 
@@ -416,6 +999,7 @@ pub struct AudioChoice {
     // of platform variations.
     kind: AudioSourceKind,
     name: Option<String>,
+    application: Option<String>,
     #[cfg(target_os = "linux")]
     object_serial: u32,
     /// Integer passed to the global registry listener.  Does not correspond perfectly to any fields
@@ -443,6 +1027,12 @@ impl AudioChoice {
         self.kind
     }
 
+    /// The application playing an [`AudioSourceKind::ApplicationStream`], such as "Spotify".  One
+    /// application may play several streams at once.
+    pub fn application(&self) -> Option<&str> {
+        self.application.as_deref()
+    }
+
     // This was going to be a try_from implementation until I realized the global_id was needed to
     // support removals on Linux / pipewire.
     fn try_new(
@@ -457,6 +1047,14 @@ impl AudioChoice {
                 "invalid or missing object.serial".to_owned(),
             ))?;
 
+        let application = match kind {
+            AudioSourceKind::ApplicationStream => props
+                .get("application.name") // "Spotify"
+                .or_else(|| props.get("application.process.binary")) // "spotify"
+                .map(ToString::to_string),
+            _ => None,
+        };
+
         // The "name" here is a rather arbitrary choice.  Different choices for different devices
         // may mean more to users.
         let name = props
@@ -465,11 +1063,19 @@ impl AudioChoice {
             .or_else(|| props.get("application.name")) // "Firefox" (application when media.name isn't set)
             .or_else(|| props.get("node.name")) // technical but always present
             .map(ToString::to_string);
+        // Streams are named by what they play, which says nothing about who plays it.
+        let name = match (&application, name) {
+            (Some(app), Some(name)) if !name.starts_with(app.as_str()) => {
+                Some(format!("{app}: {name}"))
+            }
+            (app, name) => name.or_else(|| app.clone()),
+        };
 
         Ok(AudioChoice {
             kind,
             object_serial,
             name,
+            application,
             global_id,
         })
     }
@@ -489,6 +1095,20 @@ pub struct AudioConnection {
     /// An online timing data accumulator to estimate phase and jitter to assist in accurate video
     /// tracking of audio.
     pub timing: timing::TimingFilter,
+    /// Frames in the most recent buffer and the stream rate.  Zero until the first buffer.
+    quantum: atomic::AtomicU32,
+    rate: atomic::AtomicU32,
+    /// Total bytes ever written, the position format changes are recorded against.
+    written: atomic::AtomicU64,
+    formats: std::sync::Mutex<FormatLog>,
+    /// When recent chunks were captured, by position.
+    stamps: std::sync::Mutex<timing::StampLog>,
+    /// Chunks lost to a full ring.
+    overruns: atomic::AtomicU64,
+    /// Set by the producer on overrun, cleared by a consumer that catches up.
+    overran: atomic::AtomicBool,
+    /// The source disappeared and the stream is waiting for it.  See [`Reconnect`].
+    suspended: atomic::AtomicBool,
 
     // Tombstone for either end of the resource to finish up.
     // XXX poison if we can't drop while holding some lock?
@@ -499,22 +1119,100 @@ pub struct AudioConnection {
 struct PipewireConnection {
     listener: Option<pw::stream::StreamListener<Box<StreamData>>>,
     stream: Option<pw::stream::StreamBox<'static>>,
+    /// What the stream captures, or last captured while suspended.
+    choice: AudioChoice,
+    options: ConnectOptions,
+    /// Kept alive by the producer inside `listener`.
+    conn: *mut AudioConnection,
+}
+
+#[cfg(target_os = "linux")]
+impl PipewireConnection {
+    /// Whether the server moves the stream when its source disappears.
+    fn server_reconnects(&self) -> bool {
+        !dont_reconnect(&self.choice, self.options.reconnect)
+    }
+
+    fn is_suspended(&self) -> bool {
+        unsafe { &*self.conn }
+            .suspended
+            .load(atomic::Ordering::Acquire)
+    }
+
+    /// Whether a newly listed `choice` should resume this suspended stream.
+    fn resumes(&self, choice: &AudioChoice) -> bool {
+        self.options.reconnect == Reconnect::SameName
+            && self.is_suspended()
+            && choice.kind == self.choice.kind
+            && choice.name() == self.choice.name()
+    }
+
+    /// Stop capturing from a source that went away.
+    fn suspend(&mut self, events: &AudioEvents) {
+        if let Some(stream) = &self.stream {
+            if let Err(e) = stream.disconnect() {
+                events.error(format!("{}: disconnect failed: {e}", self.options.name));
+            }
+        }
+        unsafe { &*self.conn }.suspend(events, &self.options.name, None);
+    }
+
+    /// Point the suspended stream at `choice` and connect it again.
+    fn resume(&mut self, choice: &AudioChoice, events: &AudioEvents) {
+        let Some(stream) = &self.stream else { return };
+        if let Err(e) = retarget(stream, choice, &self.options) {
+            events.emit(AudioEvent::StreamError {
+                stream: Some(self.options.name.clone()),
+                error: format!("reconnecting failed: {e}"),
+            });
+            return;
+        }
+        self.choice = choice.clone();
+        unsafe { &*self.conn }.suspend(events, &self.options.name, Some(&choice.name()));
+    }
 }
 
 impl AudioConnection {
+    /// The requested latency seeds the timing filter.
     #[cfg(target_os = "linux")]
-    fn new() -> *mut Self {
-        let buffer = ringbuf::HeapRb::new(1024 * 256);
+    fn new(options: &ConnectOptions) -> *mut Self {
+        let buffer = ringbuf::HeapRb::new(options.ring_bytes.max(1));
         Box::into_raw(Box::new(AudioConnection {
             buffer: UnsafeCell::new(buffer),
 
             ready: std::sync::Condvar::new(),
             lock: std::sync::Mutex::new(timing::AudioTiming::new()),
-            timing: timing::TimingFilter::new(),
+            timing: timing::TimingFilter::new(options.latency.period()),
+            quantum: 0.into(),
+            rate: 0.into(),
+            written: 0.into(),
+            formats: Default::default(),
+            stamps: Default::default(),
+            overruns: 0.into(),
+            overran: false.into(),
+            suspended: false.into(),
             // XXX make sure we can't accidentally ask a dropped object for timing data
             dropped: false.into(),
         }))
     }
+
+    /// Suspend the connection, or resume it when `resume_from` names the new source, and tell
+    /// subscribers.  Returns whether anything changed.
+    fn suspend(&self, events: &AudioEvents, stream: &str, resume_from: Option<&str>) -> bool {
+        let suspended = resume_from.is_none();
+        if self.suspended.swap(suspended, atomic::Ordering::AcqRel) == suspended {
+            return false;
+        }
+        let stream = stream.to_owned();
+        events.emit(match resume_from {
+            None => AudioEvent::Suspended { stream },
+            Some(source) => AudioEvent::Resumed {
+                stream,
+                source: source.to_owned(),
+            },
+        });
+        true
+    }
 }
 
 /// The user side of a connection, obtained by calling [`connect`](AudioContext::connect) with an
@@ -525,6 +1223,24 @@ impl AudioConnection {
 /// connection after the corresponding `AudioProducer` has an opportunity to clean up.
 pub struct AudioConsumer {
     pub conn: *mut AudioConnection,
+    overrun: Overrun,
+    max_delay: Option<Duration>,
+    /// Total bytes read or skipped.
+    consumed: Cell<u64>,
+    /// Format of the next byte to be read.
+    format: Option<StreamFormat>,
+    map: channel::ChannelMap,
+    /// Stereo weights of each channel when `map` is surround.
+    downmix: Option<Vec<[f32; 2]>>,
+    /// A change that was applied but not yet returned by `format_change`.
+    unseen: Option<FormatChange>,
+    /// Bytes on their way to becoming frames.
+    scratch: Vec<u8>,
+    /// Position of the first byte of the most recent read.
+    window: Option<u64>,
+    /// Where consumed bytes are recorded, if anywhere.
+    #[cfg(feature = "file")]
+    tap: Option<record::Tap>,
 }
 
 unsafe impl Send for AudioConsumer {}
@@ -556,6 +1272,9 @@ impl AudioConsumer {
         Ok(timing.count)
     }
 
+    /// Read bytes in the current [`format`](Self::format).  A read stops short at a format change
+    /// so that no read ever mixes formats.  Check [`format_change`](Self::format_change) before
+    /// each read to learn the format of the bytes about to be read.
     // The reader is doing pull-based consumption into it's own output slice, enabling us to handle
     // the ring buffer as minimally as possible.
     pub fn read(&mut self, output: &mut [u8]) -> Result<usize, MutateError> {
@@ -564,7 +1283,215 @@ impl AudioConsumer {
         if conn.dropped.load(atomic::Ordering::Acquire) {
             return Err(MutateError::Dropped);
         }
-        Ok(buf.pop_slice(output))
+        self.catch_up()?;
+        // NOTE measured before looking for format changes.  The producer logs a change before
+        // writing its bytes, so every byte counted here has its format in the log already.
+        let mut limit = output.len().min(buf.occupied_len());
+        if let Some(boundary) = self.apply_formats()? {
+            limit = limit.min((boundary - self.consumed.get()) as usize);
+        }
+        let read = buf.pop_slice(&mut output[..limit]);
+        if read > 0 {
+            self.window = Some(self.consumed.get());
+        }
+        self.consumed.set(self.consumed.get() + read as u64);
+        #[cfg(feature = "file")]
+        record::tee(&mut self.tap, self.format, conn, &output[..read]);
+        Ok(read)
+    }
+
+    /// Read whole frames as `N` channels of `f32`.  Like [`read`](Self::read), stops short at a
+    /// format change.  Returns the number of frames read.
+    ///
+    /// Mono streams are copied to every channel.  Surround streams read as stereo are downmixed
+    /// with their [`channel_map`](Self::channel_map).  Otherwise the first `N` channels are kept
+    /// and missing channels are silent.
+    pub fn read_frames<const N: usize>(
+        &mut self,
+        output: &mut [[f32; N]],
+    ) -> Result<usize, MutateError> {
+        self.frames(output, true)
+    }
+
+    /// [`read_frames`](Self::read_frames) without consuming them.
+    pub fn peek_frames<const N: usize>(
+        &mut self,
+        output: &mut [[f32; N]],
+    ) -> Result<usize, MutateError> {
+        self.frames(output, false)
+    }
+
+    fn frames<const N: usize>(
+        &mut self,
+        output: &mut [[f32; N]],
+        consume: bool,
+    ) -> Result<usize, MutateError> {
+        let Some((frames, format)) = self.pull(output.len(), consume)? else {
+            return Ok(0);
+        };
+        let channels = format.channels as usize;
+        let downmix = self.downmix.as_deref().filter(|_| N == 2);
+        let mut samples = vec![0.0f32; channels];
+        for (frame, bytes) in output
+            .iter_mut()
+            .zip(self.scratch.chunks_exact(format.frame_bytes()))
+        {
+            for (sample, b) in samples.iter_mut().zip(bytes.chunks_exact(4)) {
+                *sample = f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+            }
+            if let Some(weights) = downmix {
+                frame.copy_from_slice(&channel::mix(weights, &samples));
+                continue;
+            }
+            for (c, sample) in frame.iter_mut().enumerate() {
+                let src = if channels == 1 { 0 } else { c };
+                *sample = samples.get(src).copied().unwrap_or(0.0);
+            }
+        }
+        Ok(frames)
+    }
+
+    /// Read whole frames with each channel into its own slice, in [`channel_map`](Self::channel_map)
+    /// order.  Reads as many frames as the shortest slice holds.  Slices past the last channel are
+    /// left alone.  Like [`read`](Self::read), stops short at a format change.  Returns the number
+    /// of frames read.
+    pub fn read_planar(&mut self, output: &mut [&mut [f32]]) -> Result<usize, MutateError> {
+        let len = output.iter().map(|o| o.len()).min().unwrap_or(0);
+        let Some((frames, format)) = self.pull(len, true)? else {
+            return Ok(0);
+        };
+        for (f, bytes) in self.scratch.chunks_exact(format.frame_bytes()).enumerate() {
+            for (out, b) in output.iter_mut().zip(bytes.chunks_exact(4)) {
+                out[f] = f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+            }
+        }
+        Ok(frames)
+    }
+
+    /// Move up to `max_frames` whole frames of the current format into `scratch`.  Returns how
+    /// many and in what format, or `None` before the first format.
+    fn pull(
+        &mut self,
+        max_frames: usize,
+        consume: bool,
+    ) -> Result<Option<(usize, StreamFormat)>, MutateError> {
+        let conn = unsafe { &mut (*self.conn) };
+        let buf = unsafe { &mut *conn.buffer.get() };
+        if conn.dropped.load(atomic::Ordering::Acquire) {
+            return Err(MutateError::Dropped);
+        }
+        if consume {
+            self.catch_up()?;
+        }
+        // NOTE measured before looking for format changes, as in `read`.
+        let mut available = buf.occupied_len();
+        let boundary = self.apply_formats()?;
+        let Some(format) = self.format else {
+            return Ok(None);
+        };
+        let frame_bytes = format.frame_bytes();
+        if let Some(boundary) = boundary {
+            available = available.min((boundary - self.consumed.get()) as usize);
+        }
+        // NOTE partial frames stay in the ring until the rest arrives.
+        let frames = max_frames.min(available / frame_bytes);
+        self.scratch.resize(frames * frame_bytes, 0);
+        let from = self.consumed.get();
+        let got = if consume {
+            let got = buf.pop_slice(&mut self.scratch);
+            self.consumed.set(self.consumed.get() + got as u64);
+            #[cfg(feature = "file")]
+            record::tee(&mut self.tap, Some(format), conn, &self.scratch);
+            got
+        } else {
+            buf.peek_slice(&mut self.scratch)
+        };
+        debug_assert_eq!(got, self.scratch.len());
+        if got > 0 {
+            self.window = Some(from);
+        }
+        Ok(Some((frames, format)))
+    }
+
+    /// Returns the format change that takes effect at the next byte, once.  Consumers that never
+    /// call this still read correctly but cannot tell when the layout changed.
+    pub fn format_change(&mut self) -> Result<Option<FormatChange>, MutateError> {
+        self.apply_formats()?;
+        Ok(self.unseen.take())
+    }
+
+    /// Format of the next byte to be read.  `None` until the server negotiates one.
+    pub fn format(&self) -> Option<StreamFormat> {
+        self.format
+    }
+
+    /// Which speaker each channel of the next byte feeds.  Empty until the server negotiates a
+    /// format.  Changes with [`format_change`](Self::format_change).
+    pub fn channel_map(&self) -> &channel::ChannelMap {
+        &self.map
+    }
+
+    /// Apply every published format that begins at or before the next byte.  Returns where the
+    /// next unapplied format begins.
+    fn apply_formats(&mut self) -> Result<Option<u64>, MutateError> {
+        let conn = unsafe { &(*self.conn) };
+        let mut log = conn.formats.lock()?;
+        while let Some(&(at, new, _)) = log.pending.front() {
+            if at > self.consumed.get() {
+                return Ok(Some(at));
+            }
+            let Some((_, _, map)) = log.pending.pop_front() else {
+                break;
+            };
+            // Several changes between reads collapse into one that spans them all.
+            let old = self.unseen.map_or(self.format, |u| u.old);
+            self.unseen = (old != Some(new)).then_some(FormatChange { old, new });
+            self.format = Some(new);
+            self.downmix = map.is_surround().then(|| map.stereo_weights());
+            self.map = map;
+        }
+        Ok(None)
+    }
+
+    /// With [`Overrun::DropOldest`], skip whole frames of the oldest audio until no more than the
+    /// budget is queued.  The budget is [`ConnectOptions::max_delay`], or half the ring after an
+    /// overrun.  Never skips across a format change.
+    fn catch_up(&mut self) -> Result<(), MutateError> {
+        if self.overrun != Overrun::DropOldest {
+            return Ok(());
+        }
+        let conn = unsafe { &(*self.conn) };
+        let overran = conn.overran.swap(false, atomic::Ordering::AcqRel);
+        let boundary = self.apply_formats()?;
+        let Some(format) = self.format else {
+            return Ok(());
+        };
+        let frame_bytes = format.frame_bytes();
+        let by_delay = self
+            .max_delay
+            .map(|d| (d.as_secs_f64() * format.rate as f64) as usize * frame_bytes);
+        let by_overrun = overran.then(|| self.capacity() / 2);
+        let Some(budget) = by_delay.into_iter().chain(by_overrun).min() else {
+            return Ok(());
+        };
+        let mut excess = self.occupied().saturating_sub(budget);
+        if let Some(boundary) = boundary {
+            excess = excess.min((boundary - self.consumed.get()) as usize);
+        }
+        self.skip(excess / frame_bytes * frame_bytes);
+        Ok(())
+    }
+
+    /// Whether the source disappeared and the connection is waiting for it.  See [`Reconnect`].
+    pub fn is_suspended(&self) -> bool {
+        let conn = unsafe { &(*self.conn) };
+        conn.suspended.load(atomic::Ordering::Acquire)
+    }
+
+    /// Chunks lost because the ring was full.
+    pub fn overruns(&self) -> u64 {
+        let conn = unsafe { &(*self.conn) };
+        conn.overruns.load(atomic::Ordering::Relaxed)
     }
 
     /// Return how many bytes are available for read
@@ -585,7 +1512,25 @@ impl AudioConsumer {
     pub fn skip(&self, count: usize) {
         let conn = unsafe { &(*self.conn) };
         let buf = unsafe { &mut *conn.buffer.get() };
-        buf.skip(count);
+        let skipped = buf.skip(count);
+        self.consumed.set(self.consumed.get() + skipped as u64);
+    }
+
+    /// When the first frame of the newest chunk was captured.  `None` until the first chunk.
+    pub fn latest_timestamp(&self) -> Result<Option<timing::AudioTimestamp>, MutateError> {
+        let conn = unsafe { &(*self.conn) };
+        Ok(conn.stamps.lock()?.latest())
+    }
+
+    /// When the first frame of the most recent read or peek was captured.  Reads that skip audio
+    /// to catch up are stamped after the skip.  `None` before the first read that returned
+    /// anything, or once the window is too far behind to remember.
+    pub fn read_timestamp(&self) -> Result<Option<timing::AudioTimestamp>, MutateError> {
+        let conn = unsafe { &(*self.conn) };
+        match self.window {
+            Some(position) => Ok(conn.stamps.lock()?.at(position)),
+            None => Ok(None),
+        }
     }
 
     /// Get most recent phase data.
@@ -593,6 +1538,15 @@ impl AudioConsumer {
         let conn = unsafe { &(*self.conn) };
         Ok(conn.lock.lock().map(|t| t.clone())?)
     }
+
+    /// The quantum the server negotiated, which may differ from the [`LatencyHint`] requested.
+    /// `None` until the first buffer arrives.
+    pub fn quantum(&self) -> Option<Quantum> {
+        let conn = unsafe { &(*self.conn) };
+        let frames = conn.quantum.load(atomic::Ordering::Relaxed);
+        let rate = conn.rate.load(atomic::Ordering::Relaxed);
+        (frames > 0 && rate > 0).then_some(Quantum { frames, rate })
+    }
 }
 
 impl Drop for AudioConsumer {
@@ -608,6 +1562,9 @@ impl Drop for AudioConsumer {
 /// audio thread.
 struct AudioProducer {
     conn: *mut AudioConnection,
+    events: std::sync::Arc<AudioEvents>,
+    /// The connection name that events are reported under.
+    stream: String,
 }
 
 unsafe impl Send for AudioProducer {}
@@ -617,6 +1574,40 @@ impl AudioProducer {
         &mut self,
         datas: &mut [spa::buffer::Data],
         arrived: Instant,
+        captured: Instant,
+    ) -> Result<usize, MutateError> {
+        let input_len = datas.iter().fold(0, |accum, d| accum + d.chunk().size()) as usize;
+        self.write_captured(input_len, arrived, captured, |buf| {
+            let mut written = 0;
+            datas.iter_mut().for_each(|d| {
+                let offset = d.chunk().offset() as usize;
+                let size = d.chunk().size() as usize;
+                if let Some(input) = d.data() {
+                    written += buf.push_slice(&input[offset..offset + size]);
+                }
+            });
+            written
+        })
+    }
+
+    /// Push one chunk of `input_len` bytes with `fill` and publish its timing.  Shared by every
+    /// backend so that consumers see the same behavior no matter where the bytes came from.
+    fn write_with(
+        &mut self,
+        input_len: usize,
+        arrived: Instant,
+        fill: impl FnOnce(&mut ringbuf::HeapRb<u8>) -> usize,
+    ) -> Result<usize, MutateError> {
+        self.write_captured(input_len, arrived, arrived, fill)
+    }
+
+    /// [`write_with`](Self::write_with) for backends that know when the chunk was captured.
+    fn write_captured(
+        &mut self,
+        input_len: usize,
+        arrived: Instant,
+        captured: Instant,
+        fill: impl FnOnce(&mut ringbuf::HeapRb<u8>) -> usize,
     ) -> Result<usize, MutateError> {
         let conn = unsafe { &mut *self.conn };
         let buf = unsafe { &mut *conn.buffer.get() };
@@ -624,27 +1615,35 @@ impl AudioProducer {
             return Err(MutateError::Dropped);
         }
 
-        let input_len = datas.iter().fold(0, |accum, d| accum + d.chunk().size()) as usize;
         let capacity: usize = buf.capacity().into();
         if input_len > capacity {
-            eprintln!(
-                "total input len {} exceeds ring capacity {}",
+            return Err(MutateError::AudioSource(format!(
+                "chunk of {} bytes exceeds ring capacity {}",
                 input_len, capacity
-            );
-            return Err(MutateError::AudioSource("ring too small".to_owned()));
-        }
-        let vacant_len = buf.vacant_len();
-        if input_len > vacant_len {
-            eprintln!("audio consumer falling behind");
-        }
-        let mut written = 0;
-        datas.iter_mut().for_each(|d| {
-            let offset = d.chunk().offset() as usize;
-            let size = d.chunk().size() as usize;
-            if let Some(input) = d.data() {
-                written += buf.push_slice(&input[offset..offset + size]);
-            }
-        });
+            )));
+        }
+        if input_len > buf.vacant_len() {
+            // A partial chunk would split frames, so the whole chunk is lost.
+            self.emit(|stream| AudioEvent::XRun { stream });
+            conn.overruns.fetch_add(1, atomic::Ordering::Relaxed);
+            conn.overran.store(true, atomic::Ordering::Release);
+            return Ok(0);
+        }
+        // Stamped before the bytes are visible so that every byte a consumer can read has one.
+        let byte_rate = conn
+            .formats
+            .lock()?
+            .latest
+            .map_or(0.0, |f| f.rate as f64 * f.frame_bytes() as f64);
+        let start = timing::AudioTimestamp {
+            position: conn.written.load(atomic::Ordering::Acquire),
+            captured,
+            arrived,
+        };
+        conn.stamps.lock()?.push(start, input_len as u64, byte_rate);
+        let written = fill(buf);
+        conn.written
+            .fetch_add(written as u64, atomic::Ordering::Release);
 
         let snapshot = conn.timing.observe(arrived, written);
         let mut audio_timing = conn.lock.lock()?;
@@ -655,6 +1654,77 @@ impl AudioProducer {
         conn.ready.notify_all();
         Ok(written)
     }
+
+    /// Bytes the ring can take without overrunning.  For backends that pace themselves to the
+    /// consumer instead of a clock.
+    #[cfg(any(feature = "file", feature = "dsp"))]
+    fn vacant(&self) -> usize {
+        let buf = unsafe { &*(*self.conn).buffer.get() };
+        buf.vacant_len()
+    }
+
+    /// Publish the format of every byte written after this call, in the standard layout for its
+    /// channel count.  Returns whether it changed.
+    fn set_format(&self, format: StreamFormat) -> Result<bool, MutateError> {
+        self.set_layout(format, channel::ChannelMap::standard(format.channels))
+    }
+
+    /// [`set_format`](Self::set_format) for backends that know their channel positions.
+    fn set_layout(
+        &self,
+        format: StreamFormat,
+        map: channel::ChannelMap,
+    ) -> Result<bool, MutateError> {
+        let conn = unsafe { &*self.conn };
+        let mut log = conn.formats.lock()?;
+        if log.latest == Some(format) && log.map == map {
+            return Ok(false);
+        }
+        log.latest = Some(format);
+        log.map = map.clone();
+        let at = conn.written.load(atomic::Ordering::Acquire);
+        // A change before any byte of the previous format was written replaces it.
+        if log.pending.back().is_some_and(|&(last, _, _)| last == at) {
+            log.pending.pop_back();
+        }
+        log.pending.push_back((at, format, map));
+        drop(log);
+        self.emit(|stream| AudioEvent::FormatChanged { stream, format });
+        Ok(true)
+    }
+
+    fn emit(&self, event: impl FnOnce(String) -> AudioEvent) {
+        self.events.emit(event(self.stream.clone()));
+    }
+
+    /// See [`AudioConnection::suspend`].
+    #[cfg(feature = "mock")]
+    fn suspend(&self, resume_from: Option<&str>) -> bool {
+        let conn = unsafe { &*self.conn };
+        conn.suspend(&self.events, &self.stream, resume_from)
+    }
+
+    /// Emit a [`AudioEvent::StreamError`] for this stream.
+    fn error(&self, error: impl std::fmt::Display) {
+        self.emit(|stream| AudioEvent::StreamError {
+            stream: Some(stream),
+            error: error.to_string(),
+        });
+    }
+
+    /// Publish the frames per buffer that the server is actually delivering.
+    fn set_quantum(&self, frames: u32, rate: u32) {
+        let conn = unsafe { &*self.conn };
+        let old = conn.quantum.swap(frames, atomic::Ordering::Relaxed);
+        conn.rate.store(rate, atomic::Ordering::Relaxed);
+        if old != 0 && old != frames {
+            self.emit(|stream| AudioEvent::QuantumChanged {
+                stream,
+                old,
+                frames,
+            });
+        }
+    }
 }
 
 impl Drop for AudioProducer {
@@ -678,7 +1748,7 @@ struct StreamData {
 fn create_stream<'c>(
     core: *mut pw::sys::pw_core,
     choice: &AudioChoice,
-    name: &str,
+    options: &ConnectOptions,
     tx: AudioProducer,
 ) -> Result<
     (
@@ -687,28 +1757,34 @@ fn create_stream<'c>(
     ),
     MutateError,
 > {
-    let props = pw::properties::properties! {
+    let mut props = pw::properties::properties! {
         *pw::keys::MEDIA_TYPE => "Audio",
         *pw::keys::MEDIA_CATEGORY => "Capture",
         *pw::keys::MEDIA_ROLE => "Music",
-        *pw::keys::STREAM_CAPTURE_SINK => "true",
         // NODE_LATENCY values control the chunk sizes sent to the process callback.  Pipewire will
         // use rounded up PoT values.  Latency less than the frame size (800 for 60FPS) will result
         // in a better approximation of continuous feed, making it easier to achieve just-in-time
         // processing without underruns.  However, low values can also cause crackling.  Switching
         // the scheduling configuration for the pipewire process or other changes may help avoid
-        // this, but for now it was easier just to choose a slightly relaxed value to be on the safe
-        // side.
+        // this, so the choice is left to callers through `LatencyHint`.
         // NEXT run-time changes to the latency?
-        *pw::keys::NODE_LATENCY => "512/48000",
+        *pw::keys::NODE_LATENCY => options.latency.node_latency(),
         *pw::keys::TARGET_OBJECT => choice.object_serial.to_string(),
     };
+    // Capture what the sink plays, from its monitor ports.
+    if choice.kind == AudioSourceKind::SinkMonitor {
+        props.insert(*pw::keys::STREAM_CAPTURE_SINK, "true");
+    }
+    // Without this, the server links a stream whose source disappeared to the default source.
+    if dont_reconnect(choice, options.reconnect) {
+        props.insert(*pw::keys::NODE_DONT_RECONNECT, "true");
+    }
 
     // 🤠 Whatever breauxseph, just let me use a pointer like a pointer!
     let core_raw = std::ptr::NonNull::new(core).unwrap();
     let core = unsafe { core_raw.cast::<pw::core::Core>().as_ref() };
 
-    let stream = pw::stream::StreamBox::new(core, name, props)?;
+    let stream = pw::stream::StreamBox::new(core, &options.name, props)?;
 
     let data = Box::new(StreamData {
         format: Default::default(), // XXX format is not exposed to receiver
@@ -719,8 +1795,11 @@ fn create_stream<'c>(
     // This is the minimum
     let listener = stream
         .add_local_listener_with_user_data(data)
-        .state_changed(|_stream, _user_data, _old_state, new_state| {
-            eprintln!("state changed!: {:?}", new_state);
+        .state_changed(|_stream, user_data, _old_state, new_state| {
+            let state = new_state.into();
+            user_data
+                .tx
+                .emit(|stream| AudioEvent::StreamStateChanged { stream, state });
         })
         .param_changed(|stream, user_data, id, param| {
             let Some(param) = param else {
@@ -740,48 +1819,88 @@ fn create_stream<'c>(
                 return;
             }
 
-            // NEXT actually do the negotiation and propagate format information downstream.
-            user_data
-                .format
-                .parse(param)
-                .expect("Failed to parse param changed to AudioInfoRaw");
-
-            if let Some(object_serial) = stream.properties().get("object.serial") {
-                println!("new stream object serial: {}", object_serial);
+            if let Err(e) = user_data.format.parse(param) {
+                user_data
+                    .tx
+                    .error(format!("unparseable stream format: {e:?}"));
+                return;
+            }
+            let format = StreamFormat {
+                rate: user_data.format.rate(),
+                channels: user_data.format.channels(),
+            };
+            let positions = user_data.format.position();
+            let channels = (format.channels as usize).min(positions.len());
+            let map = channel::ChannelMap::from_spa(&positions[..channels]);
+            // Renegotiation arrives here too, such as when the monitored device changes rate.
+            match user_data.tx.set_layout(format, map) {
+                Ok(false) => return,
+                Ok(true) => {}
+                Err(e) => user_data.tx.error(e),
             }
-            if let Some(target_id) = stream.properties().get("target.object") {
-                println!("connected to target: {}", target_id);
+
+            let properties = stream.properties();
+            if let Some(serial) = properties.get("object.serial") {
+                let target = properties.get("target.object").map(str::to_owned);
+                user_data.tx.emit(|stream| AudioEvent::Connected {
+                    stream,
+                    serial: serial.to_owned(),
+                    target,
+                });
             }
-            println!(
-                "capturing rate:{} channels:{}",
-                user_data.format.rate(),
-                user_data.format.channels()
-            );
         })
         .process(|stream, user_data| {
             if user_data.dead {
                 return;
             }
             let arrived = Instant::now();
+            let captured = capture_time(stream, arrived);
             match stream.dequeue_buffer() {
                 Some(mut buffer) => {
                     let datas = buffer.datas_mut(); // drop implicitly dequeues
-                    match user_data.tx.write(datas, arrived) {
-                        Ok(_written) => {}
+                    match user_data.tx.write(datas, arrived, captured) {
+                        Ok(written) => {
+                            // Interleaved F32LE, so frames are four bytes per channel.
+                            let channels = user_data.format.channels();
+                            if channels > 0 && written > 0 {
+                                let frames = written / (channels as usize * 4);
+                                user_data
+                                    .tx
+                                    .set_quantum(frames as u32, user_data.format.rate());
+                            }
+                        }
                         // XXX Drop dance might be more clean if we had an explicit disconnect
                         // message and send it somewhere in the drop glue.
                         Err(MutateError::Dropped) => user_data.dead = true,
-                        Err(e) => eprintln!("Stream write error: {:?}", e),
+                        Err(e) => user_data.tx.error(e),
                     };
                 }
-                None => {
-                    eprintln!("no buffer dequeued");
-                }
+                None => user_data.tx.error("no buffer dequeued"),
             }
         })
         .register()?;
 
-    let pod_object = spa::pod::object! {
+    connect_stream(&stream, options)?;
+
+    // NEXT configure node delay.  Pipewire might allow it, but so far this is doubtful.
+
+    Ok((listener, stream))
+}
+
+/// Whether to keep the server from moving a stream whose source disappears.  An application stream
+/// that ends is gone for good, and the default source it would move to is likely the microphone.
+#[cfg(target_os = "linux")]
+fn dont_reconnect(choice: &AudioChoice, reconnect: Reconnect) -> bool {
+    reconnect != Reconnect::Server || choice.kind == AudioSourceKind::ApplicationStream
+}
+
+/// Negotiate the format and link the stream to its target.
+#[cfg(target_os = "linux")]
+fn connect_stream(
+    stream: &pw::stream::Stream,
+    options: &ConnectOptions,
+) -> Result<(), MutateError> {
+    let mut pod_object = spa::pod::object! {
         spa::utils::SpaTypes::ObjectParamFormat,
         spa::param::ParamType::EnumFormat,
         spa::pod::property!(
@@ -801,17 +1920,25 @@ fn create_stream<'c>(
             spa::param::audio::AudioFormat::F32LE
         ),
     };
+    // The server's adapter mixes to the requested count, so leaving it out takes the source's own.
+    if let Some(channels) = options.channels {
+        pod_object.properties.push(spa::pod::Property::new(
+            spa::param::format::FormatProperties::AudioChannels.as_raw(),
+            spa::pod::Value::Int(channels as i32),
+        ));
+    }
 
     let mut buf = Vec::new();
     let _ = pw::spa::pod::serialize::PodSerializer::serialize(
         std::io::Cursor::new(&mut buf),
         &pw::spa::pod::Value::Object(pod_object),
     )
-    .map_err(|e| {
-        eprintln!("serializing pod failed: {}", e);
-        return;
-    });
-    let pod = pw::spa::pod::Pod::from_bytes(&buf).unwrap();
+    .map_err(|e| MutateError::AudioStream {
+        context: "serializing format",
+        source: Box::new(e),
+    })?;
+    let pod = pw::spa::pod::Pod::from_bytes(&buf)
+        .ok_or_else(|| MutateError::AudioFormat("serialized format is not a pod".to_owned()))?;
 
     // NOTE Unless we pass AUTOCONNECT, an explicit link must be created between a compatible output
     // port and input port.
@@ -823,8 +1950,62 @@ fn create_stream<'c>(
             | pw::stream::StreamFlags::RT_PROCESS,
         &mut [pod],
     )?;
+    Ok(())
+}
 
-    // NEXT configure node delay.  Pipewire might allow it, but so far this is doubtful.
+/// Connect a disconnected stream to `choice` instead of its old target.
+#[cfg(target_os = "linux")]
+fn retarget(
+    stream: &pw::stream::Stream,
+    choice: &AudioChoice,
+    options: &ConnectOptions,
+) -> Result<(), MutateError> {
+    let props = pw::properties::properties! {
+        *pw::keys::TARGET_OBJECT => choice.object_serial.to_string(),
+    };
+    // pipewire-rs does not wrap updating properties.
+    let res =
+        unsafe { pw::sys::pw_stream_update_properties(stream.as_raw_ptr(), props.dict().as_raw()) };
+    if res < 0 {
+        return Err(MutateError::AudioStream {
+            context: "updating target",
+            source: Box::new(std::io::Error::from_raw_os_error(-res)),
+        });
+    }
+    connect_stream(stream, options)
+}
 
-    Ok((listener, stream))
+/// When the first frame of the buffer about to be dequeued was captured, from the stream's clock.
+/// Falls back to `arrived` when the stream has no clock yet.
+// MAYBE monitor streams are captured on their way to the sink, ahead of the speaker by the sink's
+// own latency.  Adding it would line draws up with what is heard rather than with the graph.
+#[cfg(target_os = "linux")]
+fn capture_time(stream: &pw::stream::Stream, arrived: Instant) -> Instant {
+    let mut time: pw::sys::pw_time = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<pw::sys::pw_time>();
+    let res = unsafe { pw::sys::pw_stream_get_time_n(stream.as_raw_ptr(), &mut time, size) };
+    if res < 0 || time.now <= 0 || time.rate.denom == 0 {
+        return arrived;
+    }
+    // `time.now` is CLOCK_MONOTONIC, the clock behind `Instant` on Linux.
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) } != 0 {
+        return arrived;
+    }
+    let now_ns = now.tv_sec as i128 * 1_000_000_000 + now.tv_nsec as i128;
+    // The cycle started `since_cycle` ago, and its samples took `delay` to reach the stream.
+    let since_cycle = now_ns - time.now as i128;
+    let delay_ns =
+        time.delay as i128 * 1_000_000_000 * time.rate.num as i128 / time.rate.denom as i128;
+    let total = since_cycle + delay_ns;
+    let ago = Duration::from_nanos(total.unsigned_abs() as u64);
+    let captured = if total >= 0 {
+        arrived.checked_sub(ago)
+    } else {
+        arrived.checked_add(ago)
+    };
+    captured.unwrap_or(arrived)
 }
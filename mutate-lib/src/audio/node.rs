@@ -0,0 +1,279 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Audio Node
+//!
+//! The source of a [`Graph`](crate::graph::Graph).  The host reads audio however it arrives, from
+//! an [`AudioConsumer`] or the host side of a device ring, and pushes it into an [`AudioInlet`].
+//! Each frame the [`AudioNode`] emits everything pushed since the last frame as one `Samples` event
+//! on its `output` port.  Downstream nodes follow the rate of what arrives, so a format change only
+//! has to reach the inlet.
+//!
+//! ```ignore
+//! let inlet = AudioInlet::new(2);
+//! let audio = graph.add("audio", inlet.node())?;
+//! graph.connect(audio, "output", resample, "input")?;
+//! // Every frame:
+//! inlet.read_from::<2>(&mut consumer)?;
+//! graph.run_frame()?;
+//! ```
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::audio::AudioConsumer;
+use crate::graph::prelude::*;
+use crate::MutateError;
+
+/// Frames read from a consumer at a time.
+const READ_FRAMES: usize = 1024;
+
+/// Seconds of audio kept while no frame runs.  Older audio is dropped.
+const MAX_PENDING_SECONDS: usize = 1;
+
+#[derive(Default)]
+struct Pending {
+    /// Interleaved frames pushed since the node last ran.
+    frames: Vec<f32>,
+    rate: u32,
+}
+
+/// The host's end of an [`AudioNode`].  Clones push to the same node.
+#[derive(Clone)]
+pub struct AudioInlet {
+    pending: Arc<Mutex<Pending>>,
+    channels: usize,
+}
+
+impl AudioInlet {
+    /// An inlet for interleaved frames of `channels` samples.
+    pub fn new(channels: usize) -> Self {
+        Self {
+            pending: Arc::default(),
+            channels,
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// A node emitting what this inlet receives.  Every node made from one inlet shares its audio,
+    /// so only one should be in a graph.
+    pub fn node(&self) -> AudioNode {
+        AudioNode {
+            inlet: self.clone(),
+        }
+    }
+
+    /// Append interleaved `frames` sampled at `rate`.  Frames still pending at another rate are
+    /// dropped, since one event carries one rate.
+    pub fn push(&self, frames: &[f32], rate: u32) {
+        debug_assert_eq!(frames.len() % self.channels, 0);
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        if pending.rate != rate {
+            pending.frames.clear();
+            pending.rate = rate;
+        }
+        pending.frames.extend_from_slice(frames);
+        let limit = rate as usize * MAX_PENDING_SECONDS * self.channels;
+        if pending.frames.len() > limit {
+            let excess = pending.frames.len() - limit;
+            pending.frames.drain(..excess);
+        }
+    }
+
+    /// Everything pushed since the last take, and its rate, for hosts that read it themselves.
+    pub fn take(&self) -> Option<(Vec<f32>, u32)> {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        if pending.frames.is_empty() {
+            return None;
+        }
+        Some((std::mem::take(&mut pending.frames), pending.rate))
+    }
+
+    /// Whether `other` pushes to the same node.
+    pub fn ptr_eq(&self, other: &AudioInlet) -> bool {
+        Arc::ptr_eq(&self.pending, &other.pending)
+    }
+
+    /// Drain everything `consumer` has, across format changes.  `N` must match the inlet's
+    /// channels.  Returns the frames read.
+    pub fn read_from<const N: usize>(
+        &self,
+        consumer: &mut AudioConsumer,
+    ) -> Result<usize, MutateError> {
+        debug_assert_eq!(N, self.channels);
+        let mut frames = [[0.0f32; N]; READ_FRAMES];
+        let mut total = 0;
+        loop {
+            consumer.format_change()?;
+            let read = consumer.read_frames(&mut frames)?;
+            let Some(format) = consumer.format().filter(|_| read > 0) else {
+                return Ok(total);
+            };
+            self.push(frames[..read].as_flattened(), format.rate);
+            total += read;
+        }
+    }
+}
+
+/// Emits the audio pushed into its [`AudioInlet`] each frame.  Frames with nothing pushed emit
+/// nothing.
+pub struct AudioNode {
+    inlet: AudioInlet,
+}
+
+impl Params for AudioNode {
+    fn param_specs(&self) -> &'static [ParamSpec] {
+        &[]
+    }
+}
+
+impl Node for AudioNode {
+    fn outputs(&self) -> &'static [PortSpec] {
+        &[PortSpec {
+            name: "output",
+            kind: PortKind::Samples,
+        }]
+    }
+
+    fn attach(&mut self, _params: ParamHandle) {}
+
+    fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+        if let Some((frames, rate)) = self.inlet.take() {
+            frame.emit(
+                0,
+                GraphEvent::Samples {
+                    frames: frames.into(),
+                    channels: self.inlet.channels,
+                    rate,
+                },
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mock", feature = "dsp"))]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::audio::mock::{MockServer, StreamStep};
+    use crate::audio::{AudioContext, AudioSourceKind, ConnectOptions};
+    use crate::dsp::loudness::LoudnessNode;
+    use crate::dsp::resample::ResampleNode;
+    use crate::dsp::units::SampleRate;
+    use crate::dsp::SineSweeper;
+    use crate::graph::Graph;
+
+    /// Stereo 997Hz at -29dBFS in chunks of 10ms, paced at ten times real time so that frames see
+    /// a few chunks each.
+    fn tone(rate: u32, seconds: usize) -> Vec<StreamStep> {
+        let amplitude = 10f32.powf(-29.0 / 20.0);
+        let samples: Vec<f32> = SineSweeper::new(997.0, rate as f64)
+            .take(rate as usize * seconds)
+            .flat_map(|x| [amplitude * x, amplitude * x])
+            .collect();
+        samples
+            .chunks(rate as usize / 100 * 2)
+            .flat_map(|c| {
+                [
+                    StreamStep::Chunk(c.to_vec()),
+                    StreamStep::Sleep(Duration::from_millis(1)),
+                ]
+            })
+            .collect()
+    }
+
+    fn connect(context: &AudioContext) -> AudioConsumer {
+        let mut choice = None;
+        context
+            .with_choices_blocking(|c| choice = c.first().cloned())
+            .unwrap();
+        // Scripts play faster than real time, so the ring holds all of it.
+        let options = ConnectOptions::new("test").with_ring_bytes(1 << 23);
+        context.connect_with(&choice.unwrap(), &options).unwrap()
+    }
+
+    /// Run frames until `frames` have been read from `consumer`.
+    fn run(graph: &mut Graph, inlet: &AudioInlet, consumer: &mut AudioConsumer, frames: usize) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut read = 0;
+        while read < frames {
+            assert!(Instant::now() < deadline, "only read {read} frames");
+            read += inlet.read_from::<2>(consumer).unwrap();
+            graph.run_frame().unwrap();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn scalar(graph: &Graph, node: &str, port: &str) -> f64 {
+        match graph.output(graph.node(node).unwrap(), port) {
+            Some(GraphEvent::Scalar(x)) => *x,
+            other => panic!("{node}.{port}: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_audio_node_feeds_graph() {
+        let server = MockServer::new().source(
+            1,
+            "tone",
+            AudioSourceKind::ApplicationStream,
+            tone(48_000, 5),
+        );
+        let context = AudioContext::mock(server);
+        let mut consumer = connect(&context);
+
+        let mut graph = Graph::new();
+        let inlet = AudioInlet::new(2);
+        let audio = graph.add("audio", inlet.node()).unwrap();
+        let loudness = graph
+            .add("loudness", LoudnessNode::new(SampleRate(48_000.0), 2))
+            .unwrap();
+        graph.connect(audio, "output", loudness, "input").unwrap();
+
+        // Nothing pushed, nothing emitted.
+        graph.run_frame().unwrap();
+        assert_eq!(graph.output(audio, "output"), None);
+
+        run(&mut graph, &inlet, &mut consumer, 48_000 * 5);
+        let measured = scalar(&graph, "loudness", "loudness");
+        assert!((measured + 29.0).abs() < 0.2, "{measured}");
+    }
+
+    #[test]
+    fn test_audio_node_follows_format_change() {
+        let mut steps = tone(48_000, 2);
+        steps.push(StreamStep::Format {
+            rate: 44_100,
+            channels: 2,
+        });
+        steps.extend(tone(44_100, 4));
+        let server = MockServer::new().source(1, "tone", AudioSourceKind::ApplicationStream, steps);
+        let context = AudioContext::mock(server);
+        let mut consumer = connect(&context);
+
+        let mut graph = Graph::new();
+        let inlet = AudioInlet::new(2);
+        let audio = graph.add("audio", inlet.node()).unwrap();
+        let resample = graph.add("resample", ResampleNode::new(48_000, 2)).unwrap();
+        let loudness = graph
+            .add("loudness", LoudnessNode::new(SampleRate(48_000.0), 2))
+            .unwrap();
+        graph.connect(audio, "output", resample, "input").unwrap();
+        graph
+            .connect(resample, "output", loudness, "input")
+            .unwrap();
+
+        run(&mut graph, &inlet, &mut consumer, 48_000 * 2 + 44_100 * 4);
+        match graph.output(audio, "output") {
+            Some(GraphEvent::Samples { rate, .. }) => assert_eq!(*rate, 44_100),
+            other => panic!("{other:?}"),
+        }
+        // The resampler re-provisioned, so the level reads the same after the change.
+        let measured = scalar(&graph, "loudness", "loudness");
+        assert!((measured + 29.0).abs() < 0.2, "{measured}");
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Level Probe
+//!
+//! Several sinks often share a name, and only one of them has the music.  A [`LevelProbe`] connects
+//! a monitoring stream to each candidate so that a picker can show which ones are making sound
+//! before the user commits to one.
+//!
+//! Levels are [true peaks](crate::dsp::peak), so a candidate near clipping reads as such even when
+//! no sample lands on a crest.  Each stream is an ordinary connection, so probing many candidates
+//! costs one stream each.  Drop the probe before connecting the chosen source.
+
+// MAYBE read node peak metrics from the server instead of connecting streams, if pipewire exposes
+// them without a link.
+
+use crate::audio::{AudioChoice, AudioConsumer, AudioContext, StereoFrame};
+use crate::dsp::peak::{amplitude_db, TruePeak};
+use crate::MutateError;
+
+/// Levels below this read as silence.
+pub const FLOOR_DBFS: f32 = -90.0;
+
+/// Frames drained per read while measuring.
+const DRAIN_FRAMES: usize = 1024;
+
+/// Peak level since the last measurement, per candidate.
+pub struct LevelProbe {
+    consumers: Vec<Option<AudioConsumer>>,
+    /// One detector per channel of each candidate, kept across calls so reads join seamlessly.
+    detectors: Vec<[TruePeak; 2]>,
+    scratch: Vec<StereoFrame>,
+}
+
+impl LevelProbe {
+    /// Connect to every choice.  A choice that fails to connect reads as `None` rather than failing
+    /// the whole probe.
+    pub fn new(context: &AudioContext, choices: &[AudioChoice]) -> Self {
+        let consumers: Vec<_> = choices
+            .iter()
+            .map(|c| context.connect(c, "µTate preview").ok())
+            .collect();
+        Self {
+            detectors: vec![Default::default(); consumers.len()],
+            consumers,
+            scratch: vec![[0.0; 2]; DRAIN_FRAMES],
+        }
+    }
+
+    /// Drain every stream and return the true peak of what arrived since the last call, in dBTP
+    /// and floored at [`FLOOR_DBFS`].  `None` for candidates that are not connected or have dropped.
+    pub fn levels(&mut self) -> Vec<Option<f32>> {
+        let scratch = &mut self.scratch;
+        self.consumers
+            .iter_mut()
+            .zip(&mut self.detectors)
+            .map(|(slot, detectors)| {
+                let consumer = slot.as_mut()?;
+                match peak(consumer, detectors, scratch) {
+                    Ok(peak) => Some(dbfs(peak)),
+                    Err(_) => {
+                        *slot = None;
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+fn peak(
+    consumer: &mut AudioConsumer,
+    detectors: &mut [TruePeak; 2],
+    scratch: &mut [StereoFrame],
+) -> Result<f32, MutateError> {
+    detectors.iter_mut().for_each(TruePeak::reset);
+    loop {
+        // Format changes are irrelevant to a peak, so just read across them.
+        consumer.format_change()?;
+        let read = consumer.read_frames(scratch)?;
+        if read == 0 {
+            return Ok(detectors.iter().map(TruePeak::peak).fold(0.0, f32::max));
+        }
+        for frame in &scratch[..read] {
+            for (detector, &x) in detectors.iter_mut().zip(frame) {
+                detector.push(x);
+            }
+        }
+    }
+}
+
+fn dbfs(peak: f32) -> f32 {
+    if peak > 0.0 {
+        amplitude_db(peak).max(FLOOR_DBFS)
+    } else {
+        FLOOR_DBFS
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::audio::mock::{MockServer, StreamStep};
+    use crate::audio::AudioSourceKind;
+
+    #[test]
+    fn test_probe_finds_the_loud_one() {
+        // Half scale at a quarter of the rate, sampled 45° off its crests, reads 3dB low by sample
+        // peak.
+        let crests: Vec<f32> = (0..256)
+            .map(|n| {
+                0.5 * (std::f32::consts::FRAC_PI_2 * n as f32 + std::f32::consts::FRAC_PI_4).sin()
+            })
+            .flat_map(|x| [x, x])
+            .collect();
+        let server = MockServer::new()
+            .source(
+                1,
+                "Speakers",
+                AudioSourceKind::SinkMonitor,
+                vec![StreamStep::Chunk(vec![0.0; 512])],
+            )
+            .source(
+                2,
+                "Speakers",
+                AudioSourceKind::SinkMonitor,
+                vec![StreamStep::Chunk(crests)],
+            );
+        let context = AudioContext::mock(server);
+        let mut choices = Vec::new();
+        context
+            .with_choices_blocking(|c| choices.extend_from_slice(c))
+            .unwrap();
+        choices.sort_by_key(|c| c.id());
+
+        let mut probe = LevelProbe::new(&context, &choices);
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let mut loud = None;
+        while loud.is_none() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+            let levels = probe.levels();
+            assert_eq!(levels[0], Some(FLOOR_DBFS));
+            loud = levels[1].filter(|l| *l > FLOOR_DBFS);
+        }
+        let loud = loud.expect("second source never made sound");
+        // EBU Tech 3341 true-peak tolerance.
+        let error = loud - amplitude_db(0.5);
+        assert!((-0.4..=0.2).contains(&error), "{loud}");
+    }
+}
@@ -0,0 +1,289 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Recording
+//!
+//! "The bars froze during the drop" is only reproducible with the audio that was playing.  A
+//! [`Recorder`] attached to an [`AudioConsumer`] writes everything the consumer reads to a 32-bit
+//! float WAV, bit for bit, so that a [`FileSource`](super::file::FileSource) can replay it through
+//! the same path later.
+//!
+//! The recorder taps the consumer, not the stream.  Audio that the consumer skipped, such as after
+//! an overrun, is missing from the recording just as it was missing from the visuals.
+//!
+//! Next to `take.wav`, `take.csv` has one row per read: the file and frame the read starts at, how
+//! many samples the connection had seen, and when the newest chunk arrived, in microseconds after
+//! the recording started.  Gaps and bursts in arrival line up with glitches in the recording.
+//!
+//! WAV cannot change format mid-file, so a format change closes the file and continues in the next
+//! one: `take.wav`, `take.1.wav`, `take.2.wav`, and so on.  Files are written on a thread of the
+//! recorder's own, so a reading thread never waits on the disk.
+
+// NEXT FLAC output.  claxon only decodes, so FLAC needs an encoder dependency such as flacenc.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+use super::timing::AudioTiming;
+use super::{AudioConnection, AudioConsumer, StreamFormat};
+use crate::MutateError;
+
+enum Record {
+    Samples {
+        format: StreamFormat,
+        bytes: Vec<u8>,
+        timing: AudioTiming,
+    },
+    Stop,
+}
+
+/// Writes what attached consumers read.  Dropping the recorder stops it.
+pub struct Recorder {
+    tx: mpsc::Sender<Record>,
+    paused: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Result<Vec<PathBuf>, MutateError>>>,
+}
+
+impl Recorder {
+    /// Start recording to `path`, which must end in `.wav`.  Nothing is written until an attached
+    /// consumer reads.
+    pub fn start(path: impl Into<PathBuf>) -> Result<Self, MutateError> {
+        let path = path.into();
+        if path.extension().and_then(|e| e.to_str()) != Some("wav") {
+            return Err(MutateError::AudioSource(format!(
+                "{}: recordings are WAV files",
+                path.display()
+            )));
+        }
+        let stamps = File::create(path.with_extension("csv"))
+            .map_err(|e| MutateError::AudioSource(format!("{}: {e}", path.display())))?;
+        let (tx, rx) = mpsc::channel();
+        let handle = std::thread::spawn(move || write(&path, BufWriter::new(stamps), rx));
+        Ok(Self {
+            tx,
+            paused: Arc::new(AtomicBool::new(false)),
+            handle: Some(handle),
+        })
+    }
+
+    /// Write everything `consumer` reads from now on.  A consumer feeds at most one recorder.
+    pub fn attach(&self, consumer: &mut AudioConsumer) {
+        consumer.tap = Some(self.tap());
+    }
+
+    pub(super) fn tap(&self) -> Tap {
+        Tap {
+            tx: self.tx.clone(),
+            paused: self.paused.clone(),
+        }
+    }
+
+    /// Discard reads until [`resume`](Self::resume).  The recording continues without a gap.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Finish every file and return the WAV files written, in order.
+    pub fn stop(mut self) -> Result<Vec<PathBuf>, MutateError> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> Result<Vec<PathBuf>, MutateError> {
+        let Some(handle) = self.handle.take() else {
+            return Ok(Vec::new());
+        };
+        let _ = self.tx.send(Record::Stop);
+        handle.join().map_err(|_| MutateError::JobPanicked)?
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            eprintln!("recording failed: {:?}", e);
+        }
+    }
+}
+
+/// A consumer's end of a [`Recorder`].
+pub(super) struct Tap {
+    tx: mpsc::Sender<Record>,
+    paused: Arc<AtomicBool>,
+}
+
+/// Send bytes just consumed from `conn` to the recorder, if any.  A stopped recorder detaches.
+pub(super) fn tee(
+    tap: &mut Option<Tap>,
+    format: Option<StreamFormat>,
+    conn: &AudioConnection,
+    bytes: &[u8],
+) {
+    let (Some(t), Some(format)) = (tap.as_ref(), format) else {
+        return;
+    };
+    if bytes.is_empty() || t.paused.load(Ordering::Relaxed) {
+        return;
+    }
+    // A poisoned lock only costs the timestamp.
+    let timing = match conn.lock.lock() {
+        Ok(timing) => *timing,
+        Err(poisoned) => *poisoned.into_inner(),
+    };
+    let record = Record::Samples {
+        format,
+        bytes: bytes.to_vec(),
+        timing,
+    };
+    if t.tx.send(record).is_err() {
+        *tap = None;
+    }
+}
+
+/// `take.wav`, then `take.1.wav`, `take.2.wav`, ...
+fn segment_path(path: &Path, segment: usize) -> PathBuf {
+    match segment {
+        0 => path.to_owned(),
+        n => path.with_extension(format!("{n}.wav")),
+    }
+}
+
+fn write(
+    path: &Path,
+    mut stamps: BufWriter<File>,
+    rx: mpsc::Receiver<Record>,
+) -> Result<Vec<PathBuf>, MutateError> {
+    let error = |e: &dyn std::fmt::Display| MutateError::AudioSource(format!("recording: {e}"));
+    let started = Instant::now();
+    let mut written = Vec::new();
+    let mut wav: Option<(StreamFormat, hound::WavWriter<BufWriter<File>>)> = None;
+    let mut frame = 0u64;
+    writeln!(stamps, "file,frame,count,arrived_us").map_err(|e| error(&e))?;
+
+    while let Ok(Record::Samples {
+        format,
+        bytes,
+        timing,
+    }) = rx.recv()
+    {
+        if wav.as_ref().map(|(f, _)| *f) != Some(format) {
+            if let Some((_, writer)) = wav.take() {
+                writer.finalize().map_err(|e| error(&e))?;
+            }
+            let spec = hound::WavSpec {
+                channels: format.channels as u16,
+                sample_rate: format.rate,
+                bits_per_sample: 32,
+                sample_format: hound::SampleFormat::Float,
+            };
+            let segment = segment_path(path, written.len());
+            let writer = hound::WavWriter::create(&segment, spec).map_err(|e| error(&e))?;
+            written.push(segment);
+            wav = Some((format, writer));
+            frame = 0;
+        }
+        let Some((_, writer)) = wav.as_mut() else {
+            continue;
+        };
+        let file = written.len() - 1;
+        let arrived = timing.last.saturating_duration_since(started).as_micros();
+        writeln!(stamps, "{file},{frame},{},{arrived}", timing.count).map_err(|e| error(&e))?;
+        for sample in bytes.chunks_exact(4) {
+            let sample = f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]);
+            writer.write_sample(sample).map_err(|e| error(&e))?;
+        }
+        frame += (bytes.len() / format.frame_bytes().max(1)) as u64;
+    }
+
+    if let Some((_, writer)) = wav {
+        writer.finalize().map_err(|e| error(&e))?;
+    }
+    stamps.flush().map_err(|e| error(&e))?;
+    Ok(written)
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::audio::file::FileSource;
+    use crate::audio::mock::{MockServer, StreamStep};
+    use crate::audio::{AudioContext, AudioSourceKind};
+
+    #[test]
+    fn test_record_round_trip() {
+        let ramp: Vec<f32> = (0..1024).map(|i| i as f32 / 1024.0).collect();
+        let steps = vec![
+            StreamStep::Chunk(ramp.clone()),
+            StreamStep::Sleep(Duration::from_millis(50)),
+            StreamStep::Format {
+                rate: 44_100,
+                channels: 1,
+            },
+            StreamStep::Chunk(vec![0.25; 256]),
+        ];
+        let server = MockServer::new().source(1, "deck", AudioSourceKind::ApplicationStream, steps);
+        let context = AudioContext::mock(server);
+        let mut choice = None;
+        context
+            .with_choices_blocking(|c| choice = c.first().cloned())
+            .unwrap();
+        let mut consumer = context.connect(&choice.unwrap(), "test").unwrap();
+
+        let dir = std::env::temp_dir().join(format!("mutate-record-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let recorder = Recorder::start(dir.join("take.wav")).unwrap();
+        recorder.attach(&mut consumer);
+
+        // Read everything, pausing over the first half of the ramp.
+        recorder.pause();
+        let mut buf = [0u8; 2048];
+        let mut read = 0;
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while read < 1024 * 4 + 256 * 4 {
+            assert!(Instant::now() < deadline, "only {read} bytes");
+            consumer.format_change().unwrap();
+            let n = consumer.read(&mut buf).unwrap();
+            read += n;
+            if read >= 2048 {
+                recorder.resume();
+            }
+        }
+        let files = recorder.stop().unwrap();
+        assert_eq!(files, [dir.join("take.wav"), dir.join("take.1.wav")]);
+
+        // Only the second half of the ramp, exactly.
+        let first: Vec<f32> = hound::WavReader::open(&files[0])
+            .unwrap()
+            .into_samples()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(first, ramp[512..]);
+        let second = FileSource::open(&files[1]).unwrap();
+        assert_eq!(
+            second.format(),
+            StreamFormat {
+                rate: 44_100,
+                channels: 1
+            }
+        );
+        assert_eq!(second.duration(), Duration::from_secs_f64(256.0 / 44_100.0));
+        let stamps = std::fs::read_to_string(dir.join("take.csv")).unwrap();
+        assert_eq!(stamps.lines().next(), Some("file,frame,count,arrived_us"));
+        assert!(stamps.lines().skip(1).all(|l| l.split(',').count() == 4));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -9,6 +9,11 @@
 //! duration estimation.  The implementation uses a Kalman filter.  The timing grid must match the
 //! audio streaming rate, but its phase is slightly uncertain.  By filtering, we can maintain a
 //! robust sense of phase.
+//!
+//! Every chunk is also stamped with when its first frame was captured.  PipeWire reports how long
+//! samples took to reach the stream, so capture can precede arrival by several milliseconds.
+//! Other backends capture at arrival.  A read position between stamps is extrapolated
+//! from the chunk that contains it at the chunk's rate.
 
 // NEXT whenever playback stalls or jumps, we would prefer to generate several new particles to
 // attempt to lock onto the new phase faster than the old filter can loosen up.  The new particles
@@ -17,13 +22,14 @@
 // but after several predictions, the new filters will will have tightened up their covariance
 // matrix closer to the true phase and they will be much more reliable than the old filter.
 
-use std::time::Instant;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
 use ringbuf::traits::{Consumer, Observer, RingBuffer}; // Producer,
 
-// ♻️ this period also shows up in requesting the pipewire latency
-const NOMINAL_PERIOD_NS: f64 = 512.0 / 48000.0 * 1e9;
 const Q_DELTA: f64 = 1.0; // ns², period error diffusion per callback
+/// Chunks remembered for timestamping reads.  Reads further behind extrapolate from the oldest.
+const STAMP_HISTORY: usize = 256;
 
 /// Integrates successive audio chunk timings to predict phase alignment of deadlines downstream.
 /// The implementation is a very basic Kalman filter using the
@@ -39,6 +45,9 @@ pub(crate) struct TimingFilter {
     // innovation drop below the old particle, we switch to the new particles.  Each particle will
     // need to be able to keep a short history of log probs.  When the Bayes ratio becomes
     // overwhelming, we switch particles and lock the new timing.
+    /// Expected time between chunks, from the requested latency.
+    // NEXT re-seed from the negotiated quantum when the server runs a larger one than requested.
+    nominal_period_ns: f64,
     /// Server's phase offset
     phase_offset: f64,
     /// Amount of drift
@@ -60,10 +69,12 @@ pub(crate) struct TimingFilter {
 }
 
 impl TimingFilter {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(period: Duration) -> Self {
+        let nominal_period_ns = period.as_nanos() as f64;
         Self {
             t0: Instant::now(),
             k: 0,
+            nominal_period_ns,
             phase_offset: 0.0,
             period_error: 0.0,
 
@@ -71,7 +82,7 @@ impl TimingFilter {
             // p00: phase uncertainty ±T/2, p11: drift uncertainty ±100ns/callback
             observation_covariance: (40_000.0f64).powi(2), // 40µs jitter prior in ns²
             error_covariance: [
-                (NOMINAL_PERIOD_NS / 2.0).powi(2), // p00
+                (nominal_period_ns / 2.0).powi(2), // p00
                 0.0,                               // p01
                 (100.0f64).powi(2),                // p11
             ],
@@ -86,7 +97,7 @@ impl TimingFilter {
         let [p00, p01, p11] = self.error_covariance;
 
         self.k += 1;
-        let r = (arrived - self.t0).as_nanos() as f64 - self.k as f64 * NOMINAL_PERIOD_NS;
+        let r = (arrived - self.t0).as_nanos() as f64 - self.k as f64 * self.nominal_period_ns;
         let mu_pred = self.phase_offset + self.period_error;
         let delta_pred = self.period_error;
 
@@ -144,3 +155,116 @@ impl AudioTiming {
         }
     }
 }
+
+/// When audio was captured and when it reached us.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AudioTimestamp {
+    /// Bytes written to the connection before this point, the same count that format changes are
+    /// recorded against.
+    pub position: u64,
+    /// When the frame at `position` was captured.
+    pub captured: Instant,
+    /// When the chunk holding the frame at `position` arrived in the process callback.
+    pub arrived: Instant,
+}
+
+/// One chunk's timestamp, with what it takes to extrapolate inside the chunk.
+#[derive(Clone, Copy, Debug)]
+struct Stamp {
+    start: AudioTimestamp,
+    len: u64,
+    /// Positions per second.
+    rate: f64,
+}
+
+/// Recent chunk timestamps by position.  Positions count bytes on a connection, but any unit works
+/// as long as `rate` counts the same unit.
+#[derive(Default)]
+pub(crate) struct StampLog {
+    stamps: VecDeque<Stamp>,
+}
+
+impl StampLog {
+    /// Record a chunk `len` long written at `start.position`.  A `rate` of zero, such as before any
+    /// format, stamps the whole chunk with the chunk's times.
+    pub(crate) fn push(&mut self, start: AudioTimestamp, len: u64, rate: f64) {
+        if self.stamps.len() == STAMP_HISTORY {
+            self.stamps.pop_front();
+        }
+        self.stamps.push_back(Stamp { start, len, rate });
+    }
+
+    pub(crate) fn latest(&self) -> Option<AudioTimestamp> {
+        self.stamps.back().map(|s| s.start)
+    }
+
+    /// Timestamp of `position`.  `None` until the chunk holding it is written.
+    pub(crate) fn at(&self, position: u64) -> Option<AudioTimestamp> {
+        let after = self
+            .stamps
+            .partition_point(|s| s.start.position <= position);
+        let stamp = match after {
+            0 => self.stamps.front()?,
+            n => &self.stamps[n - 1],
+        };
+        if after == self.stamps.len() && position >= stamp.start.position + stamp.len {
+            return None;
+        }
+        let distance = position as f64 - stamp.start.position as f64;
+        let offset = if stamp.rate > 0.0 {
+            Duration::from_secs_f64(distance.abs() / stamp.rate)
+        } else {
+            Duration::ZERO
+        };
+        let captured = if distance < 0.0 {
+            stamp.start.captured.checked_sub(offset)
+        } else {
+            stamp.start.captured.checked_add(offset)
+        };
+        Some(AudioTimestamp {
+            position,
+            captured: captured.unwrap_or(stamp.start.captured),
+            arrived: stamp.start.arrived,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stamp_log_extrapolates() {
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+        let mut log = StampLog::default();
+        assert_eq!(log.at(0), None);
+        // Two 10ms chunks at 1000 bytes per second, the second captured late.
+        for (position, late) in [(0, ms(0)), (10, ms(5))] {
+            let start = AudioTimestamp {
+                position,
+                captured: t0 + ms(position) + late,
+                arrived: t0 + ms(position + 20),
+            };
+            log.push(start, 10, 1000.0);
+        }
+        assert_eq!(log.latest().unwrap().position, 10);
+
+        let inside = log.at(4).unwrap();
+        assert_eq!(inside.captured, t0 + ms(4));
+        assert_eq!(inside.arrived, t0 + ms(20));
+        assert_eq!(log.at(15).unwrap().captured, t0 + ms(20));
+        assert_eq!(log.at(20), None);
+
+        for position in 0..STAMP_HISTORY as u64 {
+            let start = AudioTimestamp {
+                position: 20 + position * 10,
+                captured: t0 + ms(20 + position * 10),
+                arrived: t0,
+            };
+            log.push(start, 10, 1000.0);
+        }
+        // Forgotten chunks extrapolate back from the oldest one remembered.
+        assert_eq!(log.at(10).unwrap().captured, t0 + ms(10));
+    }
+}
@@ -0,0 +1,360 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Daemon
+//!
+//! Listen to one audio source and publish its analysis over a Unix socket for other programs.  The
+//! protocol is described with [`mutate_lib::daemon`].  No GPU is needed.
+//!
+//! ```sh
+//! cargo daemon -- --source Firefox
+//! mutate-daemon --socket /tmp/mutate.sock --fps 30
+//! ```
+//!
+//! With the `midi` feature, `--midi-map` sends clock, beat notes, and band energy to a MIDI output as
+//! described with [`mutate_lib::control::output`].  The clock runs while the beats agree.  Beat
+//! notes lead the beat by how long the audio took to be read plus `--latency`, the time the MIDI
+//! gear takes to sound.
+//!
+//! ```sh
+//! cargo daemon --features midi -- --midi-map output.toml --midi-port Synth
+//! ```
+//!
+//! The `audio` and `dsp` tables of the visualizer's config file apply here too, so both see the
+//! same bins.  The file is read once at startup.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Parser;
+
+use mutate_lib::{
+    self as utate,
+    audio::{self, AudioContext, AudioSourceKind},
+    daemon::{self, Analyzer, Publisher},
+    dsp::{bank::BankTable, units::SampleRate},
+    settings::Settings,
+};
+#[cfg(feature = "midi")]
+use mutate_lib::{
+    control::{
+        midi::MidiSender,
+        output::{MidiMessage, MidiOutput, OutputMap},
+    },
+    dsp::units::Seconds,
+};
+
+/// How long to wait for audio before checking for shutdown again.
+const POLL: Duration = Duration::from_millis(100);
+/// Sections of the shared config file that belong to the visualizer.
+const SECTIONS: &[&str] = &["keys", "scenes"];
+
+#[derive(Parser, Debug)]
+#[command(name = "mutate-daemon")]
+#[command(about = "Publish audio analysis over a Unix socket.", long_about = None)]
+struct Args {
+    /// Socket to publish on.  Defaults to `mutate/analysis.sock` in `$XDG_RUNTIME_DIR`.
+    #[arg(long, value_name = "PATH")]
+    socket: Option<PathBuf>,
+
+    /// Analysis frames per second of audio.
+    #[arg(long, value_name = "FPS", default_value_t = 60.0)]
+    fps: f64,
+
+    /// Listen to the audio source with this name.  Part of a name is enough, ignoring case.
+    /// Defaults to the first output, so that whatever is playing is analyzed.
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["demo", "file", "signal"])]
+    source: Option<String>,
+
+    /// Analyze the synthesized demo song instead of an audio source.
+    #[arg(long)]
+    demo: bool,
+
+    /// Analyze a WAV or FLAC file on a loop instead of an audio source.
+    #[arg(long, value_name = "PATH", conflicts_with = "demo")]
+    file: Option<PathBuf>,
+
+    /// Analyze a test signal instead of an audio source, such as `sine:440` or `pink`.
+    #[arg(long, value_name = "SIGNAL", conflicts_with_all = ["demo", "file"])]
+    signal: Option<audio::synthetic::Signal>,
+
+    /// Frequency bins, over `dsp.bins` from the config file.
+    #[arg(long, value_name = "COUNT")]
+    bins: Option<u32>,
+
+    /// A bank table written by the workbench, instead of designing one for the stream's rate.  Its
+    /// rate must match the source.
+    #[arg(long, value_name = "PATH", conflicts_with = "bins")]
+    table: Option<PathBuf>,
+
+    /// Read settings from this file instead of `mutate/mutate.toml` in `$XDG_CONFIG_HOME`,
+    /// `~/.config`, or `$XDG_CONFIG_DIRS`.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Send MIDI to an output port as this output map describes.
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "PATH")]
+    midi_map: Option<PathBuf>,
+
+    /// Send MIDI to the first output port whose name contains this.  Defaults to the first port.
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "NAME", requires = "midi_map")]
+    midi_port: Option<String>,
+
+    /// Milliseconds from sending a MIDI note until it sounds, added to the measured delay of the
+    /// audio read.
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "MS", default_value_t = 0.0, requires = "midi_map")]
+    latency: f64,
+}
+
+impl Args {
+    /// Flags that override the config file, as a table of the same shape.
+    fn overrides(&self) -> toml::Table {
+        let mut audio = toml::Table::new();
+        if let Some(source) = &self.source {
+            audio.insert("source".into(), source.clone().into());
+        }
+        let mut dsp = toml::Table::new();
+        if let Some(bins) = self.bins {
+            dsp.insert("bins".into(), i64::from(bins).into());
+        }
+        let mut table = toml::Table::new();
+        table.insert("audio".into(), audio.into());
+        table.insert("dsp".into(), dsp.into());
+        table
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum DaemonError {
+    #[error("Unhandled error: {0}")]
+    Unhandled(#[from] utate::MutateError),
+}
+
+fn main() -> Result<(), DaemonError> {
+    let args = Args::parse();
+    utate::shutdown::install()?;
+
+    let mut settings = Settings::new(SECTIONS);
+    let path = match &args.config {
+        Some(path) => Some(path.clone()),
+        None => utate::settings::search("mutate", "mutate.toml"),
+    };
+    if let Some(path) = &path {
+        settings.read(path)?;
+    }
+    settings.apply(&args.overrides())?;
+    let table = match &args.table {
+        Some(path) => {
+            let bytes = std::fs::read(path)
+                .map_err(|e| utate::MutateError::BankTable(format!("{}: {e}", path.display())))?;
+            Some(BankTable::from_bytes(&bytes)?)
+        }
+        None => None,
+    };
+
+    let context = open_context(&args)?;
+    let events = context.events()?;
+    let mut found = None;
+    context.with_choices_blocking(|choices| {
+        found = match &settings.audio.source {
+            Some(name) => find_source(choices, name),
+            None => choices
+                .iter()
+                .find(|c| c.kind() == AudioSourceKind::SinkMonitor)
+                .or(choices.first()),
+        }
+        .cloned();
+    })?;
+    let choice = found.ok_or_else(|| match &settings.audio.source {
+        Some(name) => utate::MutateError::AudioSource(format!("no audio source named {name}")),
+        None => utate::MutateError::NoAudioSource,
+    })?;
+    let options = audio::ConnectOptions::new("µTate daemon")
+        .with_latency(settings.audio.latency)
+        .with_channels(2);
+    let mut consumer = context.connect_with(&choice, &options)?;
+
+    let socket = args.socket.clone().unwrap_or_else(daemon::default_socket);
+    let mut publisher = Publisher::bind(&socket)?;
+    eprintln!("publishing {} on {}", choice.name(), socket.display());
+
+    #[cfg(feature = "midi")]
+    let mut midi = match &args.midi_map {
+        Some(path) => Some(Midi::connect(path, args.midi_port.as_deref())?),
+        None => None,
+    };
+
+    let mut analyzer = None;
+    let mut frames = vec![[0.0f32; 2]; 4096];
+    while !utate::shutdown::requested() {
+        match consumer.wait(POLL) {
+            Ok(_) | Err(utate::MutateError::Timeout(_)) => {}
+            Err(e) => return Err(e.into()),
+        }
+        for event in events.try_iter() {
+            eprintln!("audio: {event}");
+        }
+        loop {
+            if let Some(change) = consumer.format_change()? {
+                let fs = SampleRate(change.new.rate as f64);
+                let table = match &table {
+                    Some(table) if table.sample_rate as f64 != fs.get() => {
+                        return Err(utate::MutateError::BankTable(format!(
+                            "the source is {} Hz but the table is {} Hz",
+                            fs.get(),
+                            table.sample_rate
+                        ))
+                        .into());
+                    }
+                    Some(table) => table.clone(),
+                    None => settings.dsp.design_table(fs),
+                };
+                let next = Analyzer::new(table, 2, args.fps);
+                publisher.set_hello(next.hello(&choice.name()));
+                #[cfg(feature = "midi")]
+                if let Some(midi) = midi.as_mut() {
+                    midi.restart(&next)?;
+                }
+                analyzer = Some(next);
+            }
+            let read = consumer.read_frames(&mut frames)?;
+            if read == 0 {
+                break;
+            }
+            #[cfg(feature = "midi")]
+            if let (Some(analyzer), Some(stamp)) = (analyzer.as_mut(), consumer.read_timestamp()?) {
+                // The last frame read was captured this long ago.
+                let read_for = Duration::from_secs_f64(read as f64 / analyzer.sample_rate().get());
+                let delay = stamp.captured.elapsed().saturating_sub(read_for);
+                analyzer.set_latency(Seconds(delay.as_secs_f64() + args.latency / 1e3));
+            }
+            if let Some(analyzer) = analyzer.as_mut() {
+                analyzer.push(frames[..read].as_flattened(), |frame| {
+                    publisher.publish(&frame.to_json());
+                    if let Some(beat) = frame.beat_json() {
+                        publisher.publish(&beat);
+                    }
+                    #[cfg(feature = "midi")]
+                    if let Some(midi) = midi.as_mut() {
+                        midi.frame(frame);
+                    }
+                });
+            }
+            #[cfg(feature = "midi")]
+            if let Some(midi) = midi.as_mut() {
+                midi.flush()?;
+            }
+        }
+    }
+    #[cfg(feature = "midi")]
+    if let Some(midi) = midi {
+        midi.stop()?;
+    }
+    Ok(())
+}
+
+/// MIDI output driven by the analysis frames.  Beat notes go out in the last frame before the
+/// predicted beat, leading it by up to a frame rather than trailing it.
+#[cfg(feature = "midi")]
+struct Midi {
+    output: MidiOutput,
+    sender: MidiSender,
+    /// Bin centers of the current analyzer.
+    centers: Vec<f32>,
+    /// Time between frames of the current analyzer.
+    interval: Seconds,
+    /// The beat whose notes were played last.
+    played: Option<Seconds>,
+    /// Messages waiting to be sent.
+    pending: Vec<MidiMessage>,
+}
+
+#[cfg(feature = "midi")]
+impl Midi {
+    fn connect(map: &std::path::Path, port: Option<&str>) -> Result<Self, utate::MutateError> {
+        let output = MidiOutput::new(OutputMap::load(map)?);
+        let sender = MidiSender::connect(port)?;
+        Ok(Self {
+            output,
+            sender,
+            centers: Vec::new(),
+            interval: Seconds(0.0),
+            played: None,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Frame times start over with a new analyzer, so held notes are released and the clock stopped
+    /// first.
+    fn restart(&mut self, analyzer: &Analyzer) -> Result<(), utate::MutateError> {
+        self.output.stop(&mut self.pending);
+        self.centers = analyzer.centers().to_vec();
+        self.interval = Seconds(1.0 / analyzer.fps());
+        self.played = None;
+        self.flush()
+    }
+
+    fn frame(&mut self, frame: &daemon::AnalysisFrame) {
+        if let (Some(next), Some(phase)) = (frame.next, frame.phase) {
+            // Predictions of one beat move a little as beats are heard.
+            let played = self
+                .played
+                .is_some_and(|at| (next.beat_at - at).get().abs() < phase.period.get() / 2.0);
+            if !played && next.fire_at < frame.t + self.interval {
+                self.output.beat(frame.t, &next, &mut self.pending);
+                self.played = Some(next.beat_at);
+            }
+        }
+        self.output.tick(frame.t, frame.phase, &mut self.pending);
+        self.output
+            .bands(&self.centers, frame.bins, &mut self.pending);
+    }
+
+    fn flush(&mut self) -> Result<(), utate::MutateError> {
+        for message in self.pending.drain(..) {
+            self.sender.send(&message.bytes())?;
+        }
+        Ok(())
+    }
+
+    fn stop(mut self) -> Result<(), utate::MutateError> {
+        self.output.stop(&mut self.pending);
+        self.flush()?;
+        self.sender.stop();
+        Ok(())
+    }
+}
+
+fn open_context(args: &Args) -> Result<AudioContext, utate::MutateError> {
+    if let Some(path) = &args.file {
+        let source = audio::file::FileSource::open(path)?.with_looping(true);
+        return Ok(AudioContext::file(source));
+    }
+    if let Some(signal) = &args.signal {
+        let source = audio::synthetic::SyntheticSource::new(signal.clone(), 48_000);
+        return Ok(AudioContext::synthetic(source));
+    }
+    if args.demo {
+        return Ok(AudioContext::demo());
+    }
+    AudioContext::new()
+}
+
+/// The choice named exactly `name`, or else the first whose name contains it, ignoring case.
+fn find_source<'a>(
+    choices: &'a [audio::AudioChoice],
+    name: &str,
+) -> Option<&'a audio::AudioChoice> {
+    let lower = name.to_lowercase();
+    choices
+        .iter()
+        .find(|c| c.name() == name || c.id() == name)
+        .or_else(|| {
+            choices
+                .iter()
+                .find(|c| c.name().to_lowercase().contains(&lower))
+        })
+}
@@ -261,12 +261,12 @@ fn cmd_lowpass(args: LowpassArgs) {
     ] {
         input.set_frequency(f_test);
         let warmup = input.nsamples(64.0);
-        for _ in 0..warmup {
+        for _ in 0..warmup.get() {
             filter.process(input.next().unwrap());
         }
         let measure = input.nsamples(128.0);
         let mut peak: f32 = 0.0;
-        for _ in 0..measure {
+        for _ in 0..measure.get() {
             peak = peak.max(filter.process(input.next().unwrap()).abs());
         }
 
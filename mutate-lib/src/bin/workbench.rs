@@ -22,8 +22,11 @@ use clap::{Parser, Subcommand, ValueEnum};
 use mutate_lib::{
     self as utate,
     dsp::{
-        self, dft,
-        iir::{self, Biquad, Cascade, CytomicSvf, Svf},
+        self, dft, fft,
+        iir::{
+            self, Biquad, Biquad64, Cascade, ComplexResonator, CytomicSvf, CytomicSvf64, Svf, Svf64,
+        },
+        units::{Hertz, SampleRate, Samples, Seconds},
         window, Filter, FilterArgs, FilterMode, SineSweeper,
     },
     prelude::*,
@@ -36,36 +39,85 @@ use mutate_lib::{
 struct EntryPoint {
     #[command(subcommand)]
     command: Option<Command>,
+
+    /// How to print results
+    #[arg(long, global = true, value_enum, default_value_t = OutputChoice::Table)]
+    output: OutputChoice,
+
+    /// Config file.  Otherwise `workbench.toml` is read from the user config directory if present.
+    #[arg(long, global = true)]
+    config: Option<std::path::PathBuf>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+enum OutputChoice {
+    /// Aligned tables for people
+    #[default]
+    Table,
+    /// One record per line after a header row
+    Csv,
+    /// One JSON object per line
+    Json,
+}
+
+static OUTPUT: std::sync::OnceLock<OutputChoice> = std::sync::OnceLock::new();
+
+fn output() -> OutputChoice {
+    OUTPUT.get().copied().unwrap_or_default()
 }
 
 #[derive(Debug, thiserror::Error)]
 enum WorkbenchError {
     #[error("Unhandled error: {0}")]
     Unhandled(#[from] utate::MutateError),
+    #[error("Config error: {0}")]
+    Config(String),
 }
 
 fn main() -> Result<(), WorkbenchError> {
     let args = EntryPoint::parse();
+    OUTPUT.set(args.output).unwrap();
+    if args.output == OutputChoice::Csv {
+        println!("{}", RECORD_FIELDS.join(","));
+    }
+    // Sweeps can run for minutes.  Ctrl-C stops them between measurements.
+    utate::shutdown::install()?;
+
+    let config = WorkbenchConfig::load(args.config.as_deref())?;
+    let config = match &args.command {
+        Some(Command::Gain(a)) => config.merge_with_args(&a.common),
+        Some(Command::Response(a)) => config.merge_with_args(&a.common),
+        _ => config,
+    };
+    CONFIG.get_or_init(|| config);
 
     match args.command {
         None => unreachable!(),
         Some(Command::List(_)) => cmd_list(),
         Some(Command::Config(_)) => cmd_config(),
-        Some(Command::Optimize(_)) => cmd_optimize(),
+        Some(Command::Optimize(a)) => cmd_optimize(a)?,
         Some(Command::Sanity(a)) => cmd_sanity(a),
         Some(Command::Stress(a)) => cmd_stress(a),
         Some(Command::Rise(a)) => cmd_rise(a),
         Some(Command::Decay(a)) => cmd_decay(a),
         Some(Command::Bandwidth(a)) => cmd_bandwidth(a),
+        Some(Command::Response(a)) => cmd_response(a),
+        Some(Command::Delay(a)) => cmd_delay(a),
+        Some(Command::Precision(a)) => cmd_precision(a),
         Some(Command::Noise(a)) => cmd_noise(a),
         Some(Command::Gain(a)) => cmd_gain(a),
         Some(Command::Bin(a)) => cmd_bin(a.center),
+        Some(Command::QSweep(a)) => cmd_q_sweep(a),
+        Some(Command::Calibrate(a)) => cmd_calibrate(a)?,
+        Some(Command::Analyze(a)) => cmd_analyze(a)?,
+        #[cfg(feature = "vulkan")]
+        Some(Command::Gpu(a)) => cmd_gpu(a)?,
     }
 
     Ok(())
 }
 
-#[derive(Copy, Clone, Debug, ValueEnum)]
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
 enum FilterChoice {
     /// Simple 1st order complex resonator
     Complex,
@@ -85,9 +137,19 @@ impl FilterChoice {
             Self::Biquad => Box::new(Cascade::<Biquad>::from_args(args)),
             Self::Svf => Box::new(Cascade::<Svf>::from_args(args)),
             Self::Cytomic => Box::new(Cascade::<CytomicSvf>::from_args(args)),
+            Self::Complex => Box::new(Cascade::<ComplexResonator<f32>>::from_args(args)),
             Self::Dft => Box::new(dft::Dft::from_args(args)),
-            // Vanilla complex not supported yet.
-            _ => todo!(),
+        }
+    }
+
+    /// The same filter with 64bit state, if there is one.
+    fn instantiate_64(&self, args: &FilterArgs) -> Option<Box<dyn Filter>> {
+        match self {
+            Self::Biquad => Some(Box::new(Cascade::<Biquad64>::from_args(args))),
+            Self::Svf => Some(Box::new(Cascade::<Svf64>::from_args(args))),
+            Self::Cytomic => Some(Box::new(Cascade::<CytomicSvf64>::from_args(args))),
+            Self::Complex => Some(Box::new(Cascade::<ComplexResonator<f64>>::from_args(args))),
+            Self::Dft => None,
         }
     }
 }
@@ -122,12 +184,27 @@ enum Command {
     Gain(GainArgs),
     /// Measure width of the pass band
     Bandwidth(BandwidthArgs),
+    /// Cross-check swept gains against the FFT of the impulse response
+    Response(ResponseArgs),
+    /// Measure group delay and phase across each bin's passband
+    Delay(DelayArgs),
     /// Sweep for unexpected resonance frequencies
     Noise(NoiseArgs),
+    /// Compare 32bit filters against 64bit twins to find where precision runs out
+    Precision(PrecisionArgs),
     /// Calibrate bank and generate table
     Optimize(OptimizeArgs),
     /// Locate the visual bin for a frequency
     Bin(BinArgs),
+    /// Sweep window lengths and fit Q and rise time
+    QSweep(QSweepArgs),
+    /// Measure a reference tone on a microphone and save an SPL calibration
+    Calibrate(CalibrateArgs),
+    /// Run filters over a recording and report level statistics per bin
+    Analyze(AnalyzeArgs),
+    /// Run a bank table on the GPU and diff it against the CPU reference
+    #[cfg(feature = "vulkan")]
+    Gpu(GpuArgs),
 }
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum)]
@@ -145,6 +222,29 @@ pub enum WindowChoice {
     Hamming,
     /// Optimal and parametric.  Narrowest possible main lobe for any *chosen* PSLL.
     DolphChebyshev,
+    /// Parametric.  Side lobes keep falling away from the main lobe.
+    Kaiser,
+    /// Dolph-Chebyshev with side lobes tilted by mu.
+    Ultraspherical,
+}
+
+impl WindowChoice {
+    /// Attenuation is only used by tunable windows and `mu` only by Ultraspherical.
+    fn function(&self, attenuation_db: f64, mu: f64) -> window::WindowFunction {
+        match self {
+            WindowChoice::Boxcar => window::WindowFunction::BoxCar,
+            WindowChoice::Welch => window::WindowFunction::Welch,
+            WindowChoice::Bartlett => window::WindowFunction::Bartlett,
+            WindowChoice::Hamming => window::WindowFunction::Hamming,
+            WindowChoice::DolphChebyshev => {
+                window::WindowFunction::DolphChebyshev { attenuation_db }
+            }
+            WindowChoice::Kaiser => window::WindowFunction::kaiser(attenuation_db),
+            WindowChoice::Ultraspherical => {
+                window::WindowFunction::Ultraspherical { mu, attenuation_db }
+            }
+        }
+    }
 }
 
 #[derive(Debug, clap::Args, Clone, Copy)]
@@ -181,17 +281,14 @@ impl From<FilterModeChoice> for FilterMode {
 pub struct CommonFilterArgs {
     #[arg(long)]
     /// Center frequency
-    // XXX Not implemented
     pub center: Option<f64>,
 
     #[arg(long)]
     /// Number of stages to cascade (for filters that support it)
-    // XXX Not implemented
     pub stages: Option<usize>,
 
     #[arg(long)]
     /// Perturbs upper stage frequencies by factors of the given *product*.
-    // XXX Not implemented
     pub detune: Option<f64>,
 
     /// Use butterworth Q ratios to flatten pass-bands.
@@ -201,7 +298,6 @@ pub struct CommonFilterArgs {
 
     /// Use an alternative Quality factor (center / bandwidth).
     #[arg(short, long = "quality")]
-    // XXX Not implemented
     pub q: Option<f64>,
 
     #[arg(long)]
@@ -264,6 +360,21 @@ struct BandwidthArgs {
     threshold: Option<f32>,
 }
 
+#[derive(clap::Args, Debug)]
+struct ResponseArgs {
+    /// Filters to test, or `all`
+    #[arg(index = 1, value_delimiter = ',')]
+    filters: Vec<FilterSelector>,
+
+    /// Impulse response length as a power of two (default 2^16)
+    #[arg(long)]
+    log2_len: Option<u32>,
+
+    /// Common filter args.
+    #[command(flatten)]
+    common: CommonFilterArgs,
+}
+
 #[derive(clap::Args, Debug)]
 struct ListArgs {}
 
@@ -280,13 +391,223 @@ struct NoiseArgs {
     #[arg(long)]
     center: f32,
 
-    /// Optional flags for noise behavior
+    /// Octaves to sweep on each side of the center
+    #[arg(long, default_value_t = 4.0)]
+    octaves: f64,
+
+    /// Measurements per octave
+    #[arg(long, default_value_t = 24)]
+    resolution: usize,
+
+    /// Secondary peaks above this level, relative to the center, are flagged
+    #[arg(long, default_value_t = -40.0, allow_hyphen_values = true)]
+    threshold: f64,
+
+    /// Print the response curve
+    #[arg(long, value_enum)]
+    plot: Option<PlotChoice>,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum PlotChoice {
+    /// Bars in the terminal
+    Ascii,
+    /// Frequency and level columns for a spreadsheet
+    Csv,
+}
+
+#[derive(clap::Args, Debug)]
+struct OptimizeArgs {
+    /// Window functions to choose from
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "dolph-chebyshev,kaiser"
+    )]
+    windows: Vec<WindowChoice>,
+
+    /// Side lobe floors to try with each tunable window
+    #[arg(long, value_delimiter = ',', default_value = "22.5,40,60,80")]
+    attenuations: Vec<f64>,
+
+    /// Multiples of each bin's Q to try.  Below one, neighboring bins overlap more and rise faster.
+    #[arg(long, value_delimiter = ',', default_value = "0.5,0.71,1")]
+    q_scales: Vec<f64>,
+
+    /// Side lobes below this are not penalized
+    #[arg(long, default_value_t = -60.0, allow_hyphen_values = true)]
+    noise_floor_db: f64,
+
+    /// Side lobe tilt.  Only used by Ultraspherical.
+    #[arg(long, default_value_t = 0.5, allow_hyphen_values = true)]
+    mu: f64,
+
+    /// Number of bins
+    #[arg(long, default_value_t = dsp::spectrogram::RESOLUTION_4K_WIDTH)]
+    resolution: usize,
+
+    /// Longest acceptable rise time, in milliseconds.  Also the rise that costs as much as missing
+    /// the Q by a factor of e.
+    #[arg(long, default_value_t = 50.0)]
+    max_rise_ms: f64,
+
+    /// Where to save the bank table
+    #[arg(long, default_value = "bank.bin")]
+    save: std::path::PathBuf,
+
+    /// Also save the table as JSON
+    #[arg(long)]
+    json: Option<std::path::PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct QSweepArgs {
+    /// Window function
+    #[arg(long, value_enum, default_value_t = WindowChoice::DolphChebyshev)]
+    window: WindowChoice,
+
+    /// Side lobe floor.  Only used by tunable windows.
+    #[arg(long, default_value_t = 22.5)]
+    attenuation_db: f64,
+
+    /// Side lobe tilt.  Only used by Ultraspherical.
+    #[arg(long, default_value_t = 0.5, allow_hyphen_values = true)]
+    mu: f64,
+
+    /// Center frequency of the measured filters
+    #[arg(long, default_value_t = 1000.0)]
+    center: f64,
+
+    /// Band edge threshold in dB
+    #[arg(long, default_value_t = -3.0)]
+    threshold: f64,
+
+    /// Window lengths to measure, in cycles of the center frequency
+    #[arg(long, value_delimiter = ',', default_value = "4,8,16,32,64,128")]
+    cycles: Vec<f64>,
+
+    /// Longest acceptable rise time when picking bank lengths, in milliseconds
+    #[arg(long, default_value_t = 50.0)]
+    max_rise_ms: f64,
+}
+
+#[derive(clap::Args, Debug)]
+struct CalibrateArgs {
+    /// Part of the name of the microphone's audio source
+    #[arg(index = 1, required = true)]
+    source: String,
+
+    /// SPL of the reference tone at the microphone, such as from an acoustic calibrator
+    #[arg(long, default_value_t = dsp::calibration::CALIBRATOR_SPL)]
+    spl: f64,
+
+    /// Frequency of the reference tone
+    #[arg(long, default_value_t = dsp::calibration::REFERENCE_HZ.get())]
+    reference: f64,
+
+    /// Capture length in seconds, including settling
+    #[arg(long, default_value_t = 3.0)]
+    seconds: f64,
+
+    /// Where to save the calibration
+    #[arg(long, default_value = "calibration.toml")]
+    save: std::path::PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct DelayArgs {
+    /// Filters to test, or `all`
+    #[arg(index = 1, value_delimiter = ',')]
+    filters: Vec<FilterSelector>,
+
+    /// Number of log spaced bins across the visible range
+    #[arg(long, default_value_t = 24)]
+    bins: usize,
+
+    /// Measure only the bin centered here, with the configured Q
     #[arg(long)]
-    flags: Option<String>,
+    center: Option<f64>,
 }
 
 #[derive(clap::Args, Debug)]
-struct OptimizeArgs {}
+struct PrecisionArgs {
+    /// Filters to test, or `all`
+    #[arg(index = 1, value_delimiter = ',')]
+    filters: Vec<FilterSelector>,
+
+    /// Number of log spaced bins across the visible range
+    #[arg(long, default_value_t = 12)]
+    bins: usize,
+
+    /// Compare only the bin centered here, with the configured Q
+    #[arg(long)]
+    center: Option<f64>,
+
+    /// Seconds of tone at each bin's center
+    #[arg(long, default_value_t = 2.0)]
+    seconds: f64,
+
+    /// Error above this level, relative to the 64bit output, is flagged
+    #[arg(long, default_value_t = -60.0, allow_hyphen_values = true)]
+    threshold: f64,
+}
+
+#[cfg(feature = "vulkan")]
+#[derive(clap::Args, Debug)]
+struct GpuArgs {
+    /// Bank table, as saved by `optimize`
+    #[arg(index = 1, required = true)]
+    table: std::path::PathBuf,
+
+    /// WAV or FLAC file to run.  Otherwise a log sweep across the visible range.
+    #[arg(long)]
+    file: Option<std::path::PathBuf>,
+
+    /// Seconds of sweep, or of the file
+    #[arg(long, default_value_t = 4.0)]
+    seconds: f64,
+
+    /// Input samples between dispatches
+    #[arg(long, default_value_t = 480)]
+    hop: usize,
+
+    /// Bins whose error, relative to their CPU level, exceeds this are counted
+    #[arg(long, default_value_t = -60.0, allow_hyphen_values = true)]
+    threshold: f64,
+
+    /// How many of the worst bins to list
+    #[arg(long, default_value_t = 10)]
+    worst: usize,
+}
+
+#[derive(clap::Args, Debug)]
+struct AnalyzeArgs {
+    /// WAV or FLAC file.  Channels are averaged.
+    #[arg(index = 1, required = true)]
+    file: std::path::PathBuf,
+
+    /// Filter to run for each bin
+    #[arg(long, value_enum, default_value_t = FilterChoice::Cytomic)]
+    filter: FilterChoice,
+
+    /// Number of log spaced bins across the visible range.  Every bin runs its own filter over the
+    /// whole file, so the full bank resolution takes a while.
+    #[arg(long, default_value_t = 96)]
+    bins: usize,
+
+    /// Analyze only the bin centered here, with the configured Q
+    #[arg(long)]
+    center: Option<f64>,
+
+    /// Blocks that peak above this level count as occupied
+    #[arg(long, default_value_t = -60.0, allow_hyphen_values = true)]
+    floor_db: f64,
+
+    /// Length of the blocks used for occupancy, in milliseconds
+    #[arg(long, default_value_t = 10.0)]
+    block_ms: f64,
+}
 
 #[derive(clap::Args, Debug)]
 struct BinArgs {
@@ -298,12 +619,16 @@ const INDENT: usize = 2;
 const LABEL_W: usize = 32; // includes colon
 const VALUE_W: usize = 22;
 
+/// Headers also name the section of the records that follow.
 macro_rules! header {
     ($($arg:tt)*) => {{
         const WIDTH: usize = INDENT + LABEL_W + 1 + VALUE_W;
         let title = format!($($arg)*);
-        println!("\n{title}");
-        println!("{}", "=".repeat(WIDTH));
+        if output() == OutputChoice::Table {
+            println!("\n{title}");
+            println!("{}", "=".repeat(WIDTH));
+        }
+        *SECTION.lock().unwrap() = title;
     }};
 }
 
@@ -312,18 +637,94 @@ macro_rules! header {
 macro_rules! row {
     ($label:expr, $fmt:expr, $value:expr) => {{
         let value = format!($fmt, $value);
-        println!(
-            "{:indent$}{label:<label_w$} {:>value_w$}",
-            "",
-            value,
-            indent = INDENT,
-            label = format!("{}:", $label),
-            label_w = LABEL_W,
-            value_w = VALUE_W,
-        );
+        match output() {
+            OutputChoice::Table => println!(
+                "{:indent$}{label:<label_w$} {:>value_w$}",
+                "",
+                value,
+                indent = INDENT,
+                label = format!("{}:", $label),
+                label_w = LABEL_W,
+                value_w = VALUE_W,
+            ),
+            // Units stay in the value.  Measurements that scripts care about use `record`.
+            _ => emit(None, "", &format!("{}", $label), value.trim(), ""),
+        }
     }};
 }
 
+/// Prose and ad-hoc tables that only make sense to people.
+macro_rules! table {
+    ($($arg:tt)*) => {{
+        if output() == OutputChoice::Table {
+            println!($($arg)*);
+        }
+    }};
+}
+
+static SECTION: std::sync::Mutex<String> = std::sync::Mutex::new(String::new());
+
+const RECORD_FIELDS: [&str; 6] = [
+    "section",
+    "filter",
+    "condition",
+    "quantity",
+    "value",
+    "unit",
+];
+
+/// Emit one measurement for scripts.  Table output is left to the caller.
+fn record(filter: FilterChoice, condition: &str, quantity: &str, value: f64, unit: &str) {
+    if output() != OutputChoice::Table {
+        emit(Some(filter), condition, quantity, &value.to_string(), unit);
+    }
+}
+
+fn emit(filter: Option<FilterChoice>, condition: &str, quantity: &str, value: &str, unit: &str) {
+    let section = SECTION.lock().unwrap().clone();
+    let filter = filter.map(|f| format!("{f:?}")).unwrap_or_default();
+    let fields = [section.as_str(), &filter, condition, quantity, value, unit];
+    match output() {
+        OutputChoice::Table => {}
+        OutputChoice::Csv => {
+            let escaped: Vec<String> = fields
+                .iter()
+                .map(|f| match f.contains([',', '"', '\n']) {
+                    true => format!("\"{}\"", f.replace('"', "\"\"")),
+                    false => f.to_string(),
+                })
+                .collect();
+            println!("{}", escaped.join(","));
+        }
+        OutputChoice::Json => {
+            let members: Vec<String> = RECORD_FIELDS
+                .iter()
+                .zip(fields)
+                .map(|(name, f)| match (*name, f.parse::<f64>()) {
+                    ("value", Ok(v)) if v.is_finite() => format!("\"{name}\":{v}"),
+                    _ => format!("\"{name}\":{}", json_string(f)),
+                })
+                .collect();
+            println!("{{{}}}", members.join(","));
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
 fn cmd_config() {
     let defaults = WorkbenchConfig::defaults();
 
@@ -352,6 +753,13 @@ fn cmd_config() {
         "{:4.2}dB",
         defaults.bandwidth_db_threshold()
     );
+    match &defaults.source {
+        Some(path) => row!("Config file", "{}", path.display()),
+        None => row!("Config file", "{}", "none"),
+    }
+    for (choice, section) in &defaults.sections {
+        row!(format!("{choice:?} overrides"), "{}", section);
+    }
 }
 
 fn cmd_list() {
@@ -374,28 +782,29 @@ fn cmd_list() {
 
 fn cmd_sanity(args: SanityArgs) {
     let filter_choices = expand_filter_choices(args.filters);
-    let cfg = WorkbenchConfig::defaults().args();
+    let config = WorkbenchConfig::defaults();
 
     for fc in filter_choices.iter() {
+        let cfg = config.args_for(*fc);
         let mut filter = fc.instantiate(&cfg);
         let mut sg = cfg.sine_gen();
         let nsamples = sg.nsamples(64.0);
 
         // measure on-center peak gain
         let mut center_peak: f32 = 0.0;
-        for _ in 0..nsamples {
+        for _ in 0..nsamples.get() {
             center_peak = center_peak.max(filter.process(sg.next().unwrap()).abs());
         }
 
         // Drain for off-center measurement
         sg.set_frequency(cfg.center * 7.77);
-        for _ in 0..nsamples {
+        for _ in 0..nsamples.get() {
             filter.process(sg.next().unwrap());
         }
 
         // measure off-center peak gain
         let mut off_center_peak: f32 = 0.0;
-        for _ in 0..nsamples {
+        for _ in 0..nsamples.get() {
             off_center_peak = off_center_peak.max(filter.process(sg.next().unwrap()).abs());
         }
 
@@ -404,7 +813,7 @@ fn cmd_sanity(args: SanityArgs) {
         } else if !(0.95 < center_peak && off_center_peak < 1.05) {
             eprintln!("warning: {fc:?} gains appear un-normalized: {center_peak:4.2}");
         } else {
-            println!("  {fc:?}: sane");
+            table!("  {fc:?}: sane");
         }
     }
 }
@@ -419,40 +828,44 @@ fn cmd_stress(_filters: StressArgs) {
 // XXX rename to "attack!"
 fn cmd_rise(args: RiseArgs) {
     let filter_choices = expand_filter_choices(args.filters);
-    let args = WorkbenchConfig::defaults().args();
+    let config = WorkbenchConfig::defaults();
+    let filter_args: Vec<FilterArgs> = filter_choices
+        .iter()
+        .map(|fc| config.args_for(*fc))
+        .collect();
 
     header!("Normalizing Max Gains");
-    let gains = normalized_gains(&filter_choices, &args);
+    let gains = normalized_gains(&filter_choices, &filter_args);
     for (fc, gain) in filter_choices.iter().zip(gains.iter()) {
-        println!("  {fc:?}: {gain:3.2}");
+        table!("  {fc:?}: {gain:3.2}");
+        record(*fc, "", "max gain", *gain as f64, "");
     }
 
-    let f0 = args.center;
-    let fs = args.fs;
-    let q = args.q;
-
     header!("Rise Test");
     for goal in [0.1, 0.25, 0.5, 0.75, 0.9] {
-        let mut sg = dsp::SineSweeper::new(f0, fs);
-        let mut filters: Vec<Box<dyn Filter>> = filter_choices
-            .iter()
-            .map(|fc| fc.instantiate(&args))
-            .collect();
+        if interrupted() {
+            return;
+        }
 
         // NEXT change how we report time, cycles vs seconds
-        println!("time to {goal:2.1}");
-        for (fc, (filter, max_gain)) in filter_choices
+        let condition = format!("time to {goal}");
+        table!("{condition}");
+        for (fc, (args, max_gain)) in filter_choices
             .iter()
-            .zip(filters.iter_mut().zip(gains.iter()))
+            .zip(filter_args.iter().zip(gains.iter()))
         {
-            let max_samples = (4096.0 * fs / f0) as usize;
+            let (f0, fs) = (args.center, args.fs);
+            let mut sg = dsp::SineSweeper::new(f0, fs);
+            let mut filter = fc.instantiate(args);
+            let max_samples = fs.cycles(f0, 4096.0);
             let mut peak: f32 = 0.0;
             let mut found = false;
-            for s in 0..max_samples {
+            for s in 0..max_samples.get() {
                 peak = peak.max(filter.process(sg.next().unwrap()));
-                let waves = s as f64 / (fs / f0);
+                let waves = fs.waves(f0, Samples(s));
                 if peak.abs() > goal * max_gain {
-                    println!("  {fc:?}: {waves:7.2} cycles");
+                    table!("  {fc:?}: {waves:7.2} cycles");
+                    record(*fc, &condition, "rise", waves, "cycles");
                     found = true;
                     break;
                 };
@@ -468,23 +881,31 @@ fn cmd_decay(args: DecayArgs) {
     // Find the gains manually by spinning them up.  Taper off one half wave.  Then measure waves
     // until signal passes below some threshold.
     let filter_choices = expand_filter_choices(args.filters);
-    let args = WorkbenchConfig::defaults().args();
+    let config = WorkbenchConfig::defaults();
+    let filter_args: Vec<FilterArgs> = filter_choices
+        .iter()
+        .map(|fc| config.args_for(*fc))
+        .collect();
 
     // NEXT if the filters have non-linear max, we will register their decay early!  We need to
     // normalize at the target, not the maximum!  For now, just start each filter at the max and
     // only vary the decay gaol.
-    let gains = normalized_gains(&filter_choices, &args);
-
-    let f0 = args.center;
-    let fs = args.fs;
-    let q = args.q;
+    let gains = normalized_gains(&filter_choices, &filter_args);
 
     let goals = [0.75, 0.5, 0.25, 0.1, 0.05];
     for goal in goals {
-        println!("time from 1.0 to {goal:2.1}");
-        for (fc, gain) in filter_choices.iter().zip(gains.iter()) {
+        if interrupted() {
+            return;
+        }
+        let condition = format!("time from 1.0 to {goal}");
+        table!("{condition}");
+        for (fc, (args, gain)) in filter_choices
+            .iter()
+            .zip(filter_args.iter().zip(gains.iter()))
+        {
+            let (f0, fs) = (args.center, args.fs);
             let mut sg = args.sine_gen();
-            let mut filter = fc.instantiate(&args);
+            let mut filter = fc.instantiate(args);
 
             // Peak the filter
             let mut peak: f32 = 0.0;
@@ -506,77 +927,153 @@ fn cmd_decay(args: DecayArgs) {
             }
             // Decay one half wave
             let half_wave = sg.nsamples(0.5);
-            for n in 0..half_wave {
-                let decaying_gain = n as f32 / half_wave as f32;
+            for n in 0..half_wave.get() {
+                let decaying_gain = n as f32 / half_wave.get() as f32;
                 filter.process(sg.next().unwrap() * decaying_gain);
             }
 
             // Measure time to decay
             let threshold = gain * goal;
-            let max_decay_cycles = 1_000_000;
+            let max_decay_samples = Samples(1_000_000);
             let mut decayed = false;
-            let mut decay_cycles = 0;
-            let mut since_exceed = 0;
+            let mut decay_samples = Samples(0);
+            let mut since_exceed = Samples(0);
             // MAYBE we can look at the half wavelength, but this does open us up to dynamic
             // interactions with the decaying filter.
             let wave = sg.nsamples(1.0);
             loop {
                 let out = filter.process(0.0).abs();
-                if decay_cycles > max_decay_cycles {
+                if decay_samples > max_decay_samples {
                     break;
                 }
 
                 if out > threshold {
-                    since_exceed = 0;
+                    since_exceed = Samples(0);
                 } else {
-                    since_exceed += 1;
+                    since_exceed += Samples(1);
                     if since_exceed > wave {
                         decayed = true;
                         break;
                     }
                 }
-                decay_cycles += 1;
+                decay_samples += Samples(1);
             }
             if !decayed {
                 eprintln!("warning: {fc:?} did not reach {goal:3.2}");
             }
-            // DEBT We need to type waves and samples to prevent their mixing.
-            let waves = decay_cycles as f64 / (wave as f64);
-            println!("  {fc:?}: {waves:7.2} cycles");
+            let waves = fs.waves(f0, decay_samples);
+            table!("  {fc:?}: {waves:7.2} cycles");
+            record(*fc, &condition, "decay", waves, "cycles");
         }
     }
 }
 
-fn cmd_noise(_args: NoiseArgs) {
-    // Find the +/- shoulder and sweep out to edges, looking for secondary modes.
+/// Levels below this are drawn as empty bars.
+const PLOT_FLOOR_DB: f64 = -100.0;
+const PLOT_W: usize = 50;
+
+fn cmd_noise(args: NoiseArgs) {
+    header!("Noise Test");
+    let filter_choices = expand_filter_choices(args.filters);
+    let config = WorkbenchConfig::defaults();
+    let f0 = args.center as f64;
+    let nyquist = 0.5 * config.sample_rate().get();
+    let threshold = -(args.threshold.abs());
+
+    // Log spaced from the lowest to the highest frequency, center included.
+    let steps = (args.octaves * args.resolution as f64).ceil() as i64;
+    let freqs: Vec<f64> = (-steps..=steps)
+        .map(|i| f0 * (i as f64 / args.resolution as f64).exp2())
+        .filter(|&f| f > 0.0 && f < nyquist)
+        .collect();
+    let center = freqs.iter().position(|&f| f == f0).unwrap();
+
+    for fc in filter_choices.iter() {
+        let mut filter_args = config.args_for(*fc);
+        filter_args.center = Hertz(f0);
+        let gain = steady_gain(fc, &filter_args, f0);
+        let mut levels = Vec::with_capacity(freqs.len());
+        for &f in &freqs {
+            if interrupted() {
+                return;
+            }
+            levels.push(20.0 * (steady_gain(fc, &filter_args, f) / gain).log10());
+        }
+
+        // Walk out of the main lobe on each side.  Past the shoulder, the response should only
+        // fall, and any peak that rises back above the threshold is a secondary mode.
+        let below = (0..center).rev().find(|&i| levels[i] < -3.0);
+        let above = (center + 1..freqs.len()).find(|&i| levels[i] < -3.0);
+        let in_stopband = |i: usize| below.is_some_and(|b| i <= b) || above.is_some_and(|a| i >= a);
+        let modes: Vec<usize> = (1..freqs.len() - 1)
+            .filter(|&i| in_stopband(i))
+            .filter(|&i| levels[i] > levels[i - 1] && levels[i] >= levels[i + 1])
+            .filter(|&i| levels[i] > threshold)
+            .collect();
+        let stopband_peak = (0..freqs.len())
+            .filter(|&i| in_stopband(i))
+            .map(|i| levels[i])
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        table!("{fc:?}:");
+        match below {
+            Some(i) => row!("lower shoulder", "{:.1} Hz", freqs[i]),
+            None => row!("lower shoulder", "{}", "not found"),
+        }
+        match above {
+            Some(i) => row!("upper shoulder", "{:.1} Hz", freqs[i]),
+            None => row!("upper shoulder", "{}", "not found"),
+        }
+        row!("stopband peak", "{:.1} dB", stopband_peak);
+        row!("secondary modes", "{}", modes.len());
+        for &i in &modes {
+            row!(
+                format!("  mode at {:.1} Hz", freqs[i]),
+                "{:.1} dB",
+                levels[i]
+            );
+        }
+
+        match args.plot {
+            None => {}
+            Some(PlotChoice::Csv) => {
+                println!("frequency_hz,level_db,mode");
+                for (i, (f, db)) in freqs.iter().zip(&levels).enumerate() {
+                    println!("{f:.3},{db:.3},{}", modes.contains(&i));
+                }
+            }
+            Some(PlotChoice::Ascii) => {
+                for (i, (f, db)) in freqs.iter().zip(&levels).enumerate() {
+                    let fill = ((db - PLOT_FLOOR_DB) / -PLOT_FLOOR_DB).clamp(0.0, 1.0);
+                    let bar = "#".repeat((fill * PLOT_W as f64).round() as usize);
+                    let mark = if modes.contains(&i) { " <- mode" } else { "" };
+                    println!("  {f:>9.1} Hz {db:>7.1} dB |{bar:<PLOT_W$}|{mark}");
+                }
+            }
+        }
+    }
 }
 
 fn cmd_gain(cmd_args: GainArgs) {
     header!("Gain Test");
 
-    let mut filter_args = WorkbenchConfig::defaults()
-        .merge_with_args(&cmd_args.common)
-        .args();
-
+    let config = WorkbenchConfig::defaults().merge_with_args(&cmd_args.common);
     let filter_choices = expand_filter_choices(cmd_args.filters);
 
-    // NEXT this manual merging scales pretty poorly.  Merge command line defaults over the
-    // Workbench defaults (which themselves should come from toml defaults).
     // NEXT supporting Cartesian iteration will involve some kind of Planner structure that
     // prioritizes dimensions where multiple values were given and then runs the test over the
     // dimensions as ordered.  Three dimensions can be printed with tables.  More will have to be
     // iterated in sequence.
-    filter_args.center = cmd_args.common.center.unwrap_or(filter_args.center);
-    filter_args.q = cmd_args.common.q.unwrap_or(filter_args.q);
-    filter_args.butterworth = true;
-
-    let f0 = filter_args.center;
-    let fs = filter_args.fs;
 
     // NEXT make amplitudes part of the arguments
     for input_amp in [1.0, 0.5, 0.25, 0.05] {
-        println!("input amplitude = {input_amp:2.1}");
+        let condition = format!("input amplitude = {input_amp}");
+        table!("{condition}");
         for fc in filter_choices.iter() {
+            let mut filter_args = config.args_for(*fc);
+            filter_args.butterworth = true;
+            let f0 = filter_args.center.get();
+            let fs = filter_args.fs.get();
             let mut filter = fc.instantiate(&filter_args);
             let mut sg = filter_args.sine_gen();
 
@@ -593,10 +1090,11 @@ fn cmd_gain(cmd_args: GainArgs) {
 
             let samples = sg.nsamples(64.0);
             let mut max: f32 = 0.0;
-            for s in 0..samples {
+            for s in 0..samples.get() {
                 max = max.max(filter.process(sg.next().unwrap() * input_amp).abs());
             }
-            println!("  {fc:?}: {max:7.5}");
+            table!("  {fc:?}: {max:7.5}");
+            record(*fc, &condition, "gain", max as f64, "");
         }
     }
 }
@@ -607,27 +1105,34 @@ fn cmd_bandwidth(args: BandwidthArgs) {
     let filter_choices = expand_filter_choices(args.filters);
     header!("Bandwidth Test");
 
-    let args = WorkbenchConfig::defaults().args();
-    let gains = normalized_gains(&filter_choices, &args);
-
     let cfg = WorkbenchConfig::defaults();
+    let filter_args: Vec<FilterArgs> = filter_choices.iter().map(|fc| cfg.args_for(*fc)).collect();
+    let gains = normalized_gains(&filter_choices, &filter_args);
+
     let threshold_db_find = -(cfg.bandwidth_db_threshold().abs());
     let threshold_db_lose = threshold_db_find - 5.0;
 
     // NOTE at very low frequencies, 128 Q results in a really long DFTs that become quite slow.  In
     // the GPU this is not a problem.
     for q in [3.0, 5.0, 8.0, 16.0, 32.0, 42.0, 64.0, 128.0, 256.0, 512.0] {
-        println!("Goal Q: {q:4.2}");
-        for (fc, gain) in filter_choices.iter().zip(gains.iter()) {
-            let mut args = args.clone();
+        if interrupted() {
+            return;
+        }
+        let condition = format!("Goal Q: {q:4.2}");
+        table!("{condition}");
+        for (fc, (args, gain)) in filter_choices
+            .iter()
+            .zip(filter_args.iter().zip(gains.iter()))
+        {
+            let mut args = *args;
             args.q = q;
 
             let mut filter = fc.instantiate(&args);
             let mut sg = args.sine_gen();
 
             // Sweep up
-            let start_freq = args.center;
-            let limit_freq = args.center * 8.0; // three octave outward sweep
+            let start_freq = args.center.get();
+            let limit_freq = start_freq * 8.0; // three octave outward sweep
             let gain_threshold = power_db_to_amplitude(threshold_db_lose, *gain as f64);
             let lost_freq =
                 sweep_outward(&mut filter, &mut sg, start_freq, limit_freq, gain_threshold);
@@ -637,8 +1142,11 @@ fn cmd_bandwidth(args: BandwidthArgs) {
                 let regained = sweep_inward(&mut filter, &mut sg, lost, start_freq, gain_threshold);
                 if let Some(found) = regained {
                     let bandwidth = ((start_freq - found) * 2.0).abs();
-                    println!("  {fc:?}: {:8.2} Hz", bandwidth);
-                    println!("    measured Q: {:6.2}", args.center / bandwidth);
+                    let measured_q = start_freq / bandwidth;
+                    table!("  {fc:?}: {:8.2} Hz", bandwidth);
+                    table!("    measured Q: {:6.2}", measured_q);
+                    record(*fc, &condition, "bandwidth", bandwidth, "Hz");
+                    record(*fc, &condition, "q", measured_q, "");
                 } else {
                     eprintln!("warning: {fc:?} did not reach threshold while sweeping inward.");
                     continue;
@@ -651,12 +1159,547 @@ fn cmd_bandwidth(args: BandwidthArgs) {
     }
 }
 
+/// The FFT of an impulse response is the transfer function of a linear filter.  Where it disagrees
+/// with a swept sine, either the filter is not linear or one of the measurements is wrong.
+fn cmd_response(args: ResponseArgs) {
+    header!("Response Test");
+
+    let config = WorkbenchConfig::defaults().merge_with_args(&args.common);
+    let filter_choices = expand_filter_choices(args.filters);
+
+    let n = 1usize << args.log2_len.unwrap_or(16);
+    let fft = fft::Fft::<f64>::new(n);
+
+    for fc in filter_choices.iter() {
+        let filter_args = config.args_for(*fc);
+        let fs = filter_args.fs.get();
+        let f0 = filter_args.center.get();
+        if interrupted() {
+            return;
+        }
+        // NOTE the DFT filter reports a magnitude, so its impulse response is not a transfer
+        // function.
+        if matches!(fc, FilterChoice::Dft) {
+            eprintln!("warning: {fc:?} is not linear, skipping");
+            continue;
+        }
+
+        let mut filter = fc.instantiate(&filter_args);
+        let mut response: Vec<_> = (0..n)
+            .map(|i| {
+                let x = if i == 0 { 1.0 } else { 0.0 };
+                num_complex::Complex::new(filter.process(x) as f64, 0.0)
+            })
+            .collect();
+        // XXX a response that has not decayed by the end of the buffer smears every bin.
+        let tail = response[n - n / 16..]
+            .iter()
+            .map(|x| x.re.abs())
+            .fold(0.0, f64::max);
+        if tail > 1e-4 {
+            eprintln!("warning: {fc:?} impulse response has not decayed, try a longer --log2-len");
+        }
+        fft.forward(&mut response);
+
+        table!("{fc:?}: FFT / swept gain");
+        for ratio in [
+            0.5,
+            std::f64::consts::FRAC_1_SQRT_2,
+            1.0,
+            std::f64::consts::SQRT_2,
+            2.0,
+        ] {
+            let freq = f0 * ratio;
+            let bin = (freq * n as f64 / fs).round() as usize;
+            let fft_db = 20.0 * response[bin].norm().log10();
+
+            // Steady state only: skip the first half of the tone.
+            let mut filter = fc.instantiate(&filter_args);
+            let mut sg = SineSweeper::new(fft::bin_frequency(bin, n, fs), fs);
+            let samples = sg.nsamples(256.0).get();
+            let mut peak: f32 = 0.0;
+            for i in 0..samples {
+                let y = filter.process(sg.next().unwrap()).abs();
+                if i >= samples / 2 {
+                    peak = peak.max(y);
+                }
+            }
+            let swept_db = 20.0 * (peak as f64).log10();
+            row!(
+                format!("{:.1} Hz", fft::bin_frequency(bin, n, fs)),
+                "{}",
+                format!("{fft_db:7.2} / {swept_db:7.2} dB")
+            );
+        }
+    }
+}
+
+/// Levels of one filter over a whole recording.
+#[derive(Default)]
+struct Occupancy {
+    peak: f32,
+    sum_squares: f64,
+    samples: usize,
+    /// Blocks whose peak passed the floor.
+    occupied: usize,
+    blocks: usize,
+}
+
+impl Occupancy {
+    fn measure(filter: &mut dyn Filter, samples: &[f32], block: usize, floor: f32) -> Self {
+        let mut stats = Self::default();
+        for chunk in samples.chunks(block.max(1)) {
+            let mut block_peak: f32 = 0.0;
+            for &x in chunk {
+                let y = filter.process(x).abs();
+                block_peak = block_peak.max(y);
+                stats.sum_squares += (y * y) as f64;
+            }
+            stats.peak = stats.peak.max(block_peak);
+            stats.occupied += (block_peak > floor) as usize;
+            stats.blocks += 1;
+            stats.samples += chunk.len();
+        }
+        stats
+    }
+
+    fn peak_db(&self) -> f64 {
+        20.0 * (self.peak as f64).log10()
+    }
+
+    fn rms_db(&self) -> f64 {
+        10.0 * (self.sum_squares / self.samples.max(1) as f64).log10()
+    }
+
+    fn occupancy(&self) -> f64 {
+        self.occupied as f64 / self.blocks.max(1) as f64
+    }
+}
+
+/// Synthetic tones only show how a filter treats tones.  Music decides how busy the bins really
+/// are, how much headroom they need, and which ones never light up.
+fn cmd_analyze(args: AnalyzeArgs) -> Result<(), utate::MutateError> {
+    let source = utate::audio::file::FileSource::open(&args.file)?;
+    let format = source.format();
+    let channels = format.channels as usize;
+    let samples: Vec<f32> = source
+        .samples()
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+    let fs = SampleRate(format.rate as f64);
+
+    header!("Analyze {}", args.file.display());
+    row!("sample rate", "{} Hz", format.rate);
+    row!("channels", "{}", format.channels);
+    row!("duration", "{:.1} s", source.duration().as_secs_f64());
+
+    let mut base = WorkbenchConfig::defaults().args_for(args.filter);
+    base.fs = fs;
+    let nyquist = 0.5 * fs.get();
+    let bins = match args.center {
+        Some(center) => vec![(center, base.q)],
+        None => dsp::bank::bins(
+            Hertz(dsp::MIN_FREQ_CHEAP_DRIVERS),
+            Hertz(dsp::MAX_FREQ_OLD_PEOPLE.min(0.9 * nyquist)),
+            args.bins,
+        )
+        .iter()
+        .map(|bin| (bin.center.get(), bin.q()))
+        .collect(),
+    };
+    let block = fs.samples(Seconds(args.block_ms / 1000.0)).get();
+    let floor = power_db_to_amplitude(args.floor_db, 1.0) as f32;
+
+    header!("{:?} bins", args.filter);
+    table!(
+        "  {:>9} {:>7} {:>9} {:>9} {:>9}",
+        "center",
+        "Q",
+        "peak dB",
+        "RMS dB",
+        "occupied"
+    );
+    for (center, q) in bins {
+        if interrupted() {
+            break;
+        }
+        let mut filter_args = base;
+        filter_args.center = Hertz(center);
+        filter_args.q = q;
+        let mut filter = args.filter.instantiate(&filter_args);
+        let stats = Occupancy::measure(filter.as_mut(), &samples, block, floor);
+
+        table!(
+            "  {center:>9.1} {q:>7.2} {:>9.1} {:>9.1} {:>8.1}%",
+            stats.peak_db(),
+            stats.rms_db(),
+            stats.occupancy() * 100.0
+        );
+        let condition = format!("{center:.1} Hz");
+        record(args.filter, &condition, "peak", stats.peak_db(), "dBFS");
+        record(args.filter, &condition, "rms", stats.rms_db(), "dBFS");
+        record(args.filter, &condition, "occupancy", stats.occupancy(), "");
+    }
+    Ok(())
+}
+
+/// Visuals are scheduled by delay, so every bin reports how late it is.  Group delay is measured at
+/// the center and both band edges of linear filters.  Envelope delay works for every filter,
+/// including the DFT.
+fn cmd_delay(args: DelayArgs) {
+    let filter_choices = expand_filter_choices(args.filters);
+    let config = WorkbenchConfig::defaults();
+    let fs = config.sample_rate();
+    let bins: Vec<(f64, f64)> = match args.center {
+        Some(center) => vec![(center, config.q())],
+        None => dsp::bank::bins(
+            Hertz(dsp::MIN_FREQ_CHEAP_DRIVERS),
+            Hertz(dsp::MAX_FREQ_OLD_PEOPLE),
+            args.bins,
+        )
+        .iter()
+        .map(|bin| (bin.center.get(), bin.q()))
+        .collect(),
+    };
+    let ms = |samples: f64| 1000.0 * samples / fs.get();
+
+    for fc in filter_choices.iter() {
+        header!("{fc:?} Delay");
+        table!(
+            "  {:>9} {:>7} {:>9} {:>9} {:>9} {:>9} {:>9} {:>8}",
+            "center",
+            "Q",
+            "low ms",
+            "gd ms",
+            "high ms",
+            "gd n",
+            "env ms",
+            "phase"
+        );
+        // NOTE the DFT filter reports a magnitude, so it has no phase to differentiate.
+        let linear = !matches!(fc, FilterChoice::Dft);
+        for &(center, q) in &bins {
+            if interrupted() {
+                return;
+            }
+            let mut filter_args = config.args_for(*fc);
+            filter_args.center = Hertz(center);
+            filter_args.q = q;
+            let make = || fc.instantiate(&filter_args);
+
+            // Resonators ring for about Q cycles.  Give them several.
+            let settle = fs.cycles(filter_args.center, 8.0 * q);
+            let measure = fs.cycles(filter_args.center, 4.0 * q);
+            let envelope =
+                dsp::delay::envelope_delay(make().as_mut(), filter_args.center, fs, settle, settle);
+            let condition = format!("{center:.1} Hz");
+            record(*fc, &condition, "envelope delay", ms(envelope), "ms");
+
+            if !linear {
+                table!(
+                    "  {center:>9.1} {q:>7.2} {:>9} {:>9} {:>9} {:>9} {:>9.3} {:>8}",
+                    "-",
+                    "-",
+                    "-",
+                    "-",
+                    ms(envelope),
+                    "-"
+                );
+                continue;
+            }
+
+            let step = Hertz(center / (20.0 * q));
+            let group = |f: f64| dsp::delay::group_delay(make, Hertz(f), step, fs, settle, measure);
+            let low = group(center * (1.0 - 0.5 / q));
+            let mid = group(center);
+            let high = group(center * (1.0 + 0.5 / q));
+            let tone =
+                dsp::delay::tone_response(make().as_mut(), filter_args.center, fs, settle, measure);
+            let phase = tone.phase.to_degrees();
+
+            table!(
+                "  {center:>9.1} {q:>7.2} {:>9.3} {:>9.3} {:>9.3} {:>9.1} {:>9.3} {:>7.1}°",
+                ms(low),
+                ms(mid),
+                ms(high),
+                mid,
+                ms(envelope),
+                phase
+            );
+            record(*fc, &condition, "group delay low edge", ms(low), "ms");
+            record(*fc, &condition, "group delay", ms(mid), "ms");
+            record(*fc, &condition, "group delay", mid, "samples");
+            record(*fc, &condition, "group delay high edge", ms(high), "ms");
+            record(*fc, &condition, "phase", phase, "degrees");
+        }
+    }
+}
+
+// NOTE the tone is generated once in f32 so both filters see identical input.  Only the filters'
+// own arithmetic differs.
+fn cmd_precision(args: PrecisionArgs) {
+    let filter_choices = expand_filter_choices(args.filters);
+    let config = WorkbenchConfig::defaults();
+    let fs = config.sample_rate();
+    let bins: Vec<(f64, f64)> = match args.center {
+        Some(center) => vec![(center, config.q())],
+        None => dsp::bank::bins(
+            Hertz(dsp::MIN_FREQ_CHEAP_DRIVERS),
+            Hertz(dsp::MAX_FREQ_OLD_PEOPLE),
+            args.bins,
+        )
+        .iter()
+        .map(|bin| (bin.center.get(), bin.q()))
+        .collect(),
+    };
+    let db = |ratio: f64| 20.0 * ratio.max(1e-12).log10();
+
+    for fc in filter_choices.iter() {
+        header!("{fc:?} Precision");
+        let mut filter_args = config.args_for(*fc);
+        if fc.instantiate_64(&filter_args).is_none() {
+            table!("  no 64bit variant");
+            continue;
+        }
+        table!(
+            "  {:>9} {:>7} {:>9} {:>9} {:>9} {:>11}",
+            "center",
+            "Q",
+            "gain dB",
+            "drift dB",
+            "error dB",
+            "max error"
+        );
+        for &(center, q) in &bins {
+            if interrupted() {
+                return;
+            }
+            filter_args.center = Hertz(center);
+            filter_args.q = q;
+            let mut single = fc.instantiate(&filter_args);
+            let mut double = fc.instantiate_64(&filter_args).unwrap();
+
+            let samples = (args.seconds * fs.get()) as usize;
+            let (mut reference, mut single_sum, mut error, mut max_error) = (0.0, 0.0, 0.0, 0.0f64);
+            for x in filter_args.sine_gen().take(samples) {
+                let y32 = single.process(x) as f64;
+                let y64 = double.process(x) as f64;
+                reference += y64 * y64;
+                single_sum += y32 * y32;
+                error += (y32 - y64).powi(2);
+                max_error = max_error.max((y32 - y64).abs());
+            }
+
+            // A sine's RMS is 1 / sqrt(2) of its amplitude.
+            let input = samples as f64 / 2.0;
+            let gain = db((reference / input).sqrt());
+            let drift = db((single_sum / reference).sqrt());
+            let error_db = db((error / reference).sqrt());
+            let mark = if error_db > args.threshold {
+                " <- f32 fails"
+            } else {
+                ""
+            };
+            table!(
+                "  {center:>9.1} {q:>7.2} {gain:>9.2} {drift:>9.4} {error_db:>9.1} {max_error:>11.3e}{mark}"
+            );
+            let condition = format!("{center:.1} Hz");
+            record(*fc, &condition, "gain", gain, "dB");
+            record(*fc, &condition, "f32 drift", drift, "dB");
+            record(*fc, &condition, "f32 error", error_db, "dB");
+            record(*fc, &condition, "f32 max error", max_error, "");
+        }
+    }
+}
+
+/// The GPU bank and the CPU reference see the same frames, one hop at a time.  After each dispatch,
+/// the GPU output is compared with the CPU row for the same hop.
+#[cfg(feature = "vulkan")]
+fn cmd_gpu(args: GpuArgs) -> Result<(), utate::MutateError> {
+    use ash::vk;
+    use utate::dsp::compute::GpuSpectrogram;
+    use utate::dsp::spectrogram::Spectrogram;
+    use utate::gpu::prelude::*;
+
+    let bytes = std::fs::read(&args.table)
+        .map_err(|e| utate::MutateError::BankTable(format!("{}: {e}", args.table.display())))?;
+    let table = dsp::bank::BankTable::from_bytes(&bytes)?;
+    let fs = SampleRate(table.sample_rate as f64);
+    let length = fs.samples(Seconds(args.seconds)).get();
+
+    let (frames, channels) = match &args.file {
+        Some(path) => {
+            let source = utate::audio::file::FileSource::open(path)?;
+            let format = source.format();
+            if format.rate as f64 != fs.get() {
+                return Err(utate::MutateError::BankTable(format!(
+                    "{} is {} Hz but the table is {} Hz",
+                    path.display(),
+                    format.rate,
+                    fs.get()
+                )));
+            }
+            let channels = format.channels as usize;
+            let frames = source.samples().iter().take(length * channels).copied();
+            (frames.collect::<Vec<f32>>(), channels)
+        }
+        None => {
+            let (low, high) = (dsp::MIN_FREQ_CHEAP_DRIVERS, dsp::MAX_FREQ_OLD_PEOPLE);
+            let mut sweep = SineSweeper::new(low, fs);
+            let frames = (0..length)
+                .map(|n| {
+                    let f = low * (high / low).powf(n as f64 / length as f64);
+                    sweep.set_frequency(f);
+                    0.5 * sweep.next().unwrap()
+                })
+                .collect();
+            (frames, 1)
+        }
+    };
+
+    header!("GPU vs CPU {}", args.table.display());
+    row!("bins", "{}", table.bins.len());
+    row!("sample rate", "{} Hz", table.sample_rate);
+    row!("hop", "{} samples", args.hop);
+
+    let hop = args.hop.max(1);
+    let mut cpu = Spectrogram::new(table.clone(), Samples(hop)).with_channels(channels);
+    let width = cpu.width();
+    // Per column: worst absolute error, summed squared error, summed squared CPU level.
+    let mut worst = vec![0.0f64; width];
+    let mut error = vec![0.0f64; width];
+    let mut level = vec![0.0f64; width];
+    let mut dispatches = 0usize;
+
+    utate::gpu::with_context!(|device| {
+        // The queue the visualizer would use, async compute when the device has a family for it.
+        let queue = device.queues.compute(QueuePriority::High).queue_ref();
+        let async_compute = device.queues.async_compute().is_some();
+        let suffix = if async_compute { ", async compute" } else { "" };
+        row!("queue", "family {}", format!("{}{suffix}", queue.family()));
+        let mut pool = CommandPool::<Compute, OneTime>::transient(&device, &queue)?;
+        let mut semaphore = device.make_timeline_semaphore()?;
+        let mut bank = GpuSpectrogram::new(&device, table.clone())?.with_channels(channels);
+
+        for chunk in frames.chunks_exact(hop * channels) {
+            if interrupted() {
+                break;
+            }
+            cpu.push(chunk);
+            bank.push(chunk);
+            bank.upload(&device)?;
+
+            let cb = pool.primary(&device)?;
+            bank.record(&device, *cb);
+            let to_host = vk::MemoryBarrier2::default()
+                .src_stage_mask(vk::PipelineStageFlags2::COMPUTE_SHADER)
+                .src_access_mask(vk::AccessFlags2::SHADER_STORAGE_WRITE)
+                .dst_stage_mask(vk::PipelineStageFlags2::HOST)
+                .dst_access_mask(vk::AccessFlags2::HOST_READ);
+            let dependency =
+                vk::DependencyInfo::default().memory_barriers(std::slice::from_ref(&to_host));
+            unsafe {
+                device.as_raw().cmd_pipeline_barrier2(*cb, &dependency);
+            }
+            let done = cb.end(&device)?;
+            // There is no present ring here to flush the bank's registrations.
+            device.descriptors.flush(device.as_raw());
+            let intent = semaphore.next_signal();
+            let wait = intent.wait_value();
+            queue
+                .submission()
+                .execute(done)
+                .signal(intent, vk::PipelineStageFlags2::COMPUTE_SHADER)
+                .submit(&device, vk::Fence::null())?;
+            wait.wait(&device, 1_000_000_000)?;
+            // The only buffer from this pool just retired.
+            unsafe { pool.reset(&device, false)? };
+
+            let expected = cpu.row(cpu.rows() - 1);
+            let observed = bank.read_output(&device)?;
+            for (i, (&e, &o)) in expected.iter().zip(observed).enumerate() {
+                let diff = (e as f64 - o as f64).abs();
+                worst[i] = worst[i].max(diff);
+                error[i] += diff * diff;
+                level[i] += e as f64 * e as f64;
+            }
+            dispatches += 1;
+        }
+
+        bank.destroy(&device)?;
+        semaphore.destroy(&device);
+        pool.destroy(&device);
+        Ok::<_, utate::MutateError>(())
+    })?;
+
+    let db = |ratio: f64| 20.0 * ratio.max(1e-12).log10();
+    // Relative error, in dB, for columns that saw any signal.
+    let relative: Vec<Option<f64>> = error
+        .iter()
+        .zip(&level)
+        .map(|(&e, &l)| (l > 0.0).then(|| db((e / l).sqrt())))
+        .collect();
+    let failing = relative
+        .iter()
+        .filter(|r| r.is_some_and(|r| r > args.threshold))
+        .count();
+    let total_error: f64 = error.iter().sum();
+    let total_level: f64 = level.iter().sum();
+
+    row!("dispatches", "{}", dispatches);
+    row!(
+        "overall error",
+        "{:.1} dB",
+        db((total_error / total_level).sqrt())
+    );
+    row!(
+        "worst absolute error",
+        "{:.3e}",
+        worst.iter().fold(0.0f64, |a, &b| a.max(b))
+    );
+    row!("bins above threshold", "{}", failing);
+    record(
+        FilterChoice::Dft,
+        "overall",
+        "error",
+        db((total_error / total_level).sqrt()),
+        "dB",
+    );
+
+    let center = |column: usize| {
+        table
+            .bins
+            .iter()
+            .find(|b| b.output as usize == column)
+            .map_or(0.0, |b| b.center)
+    };
+    let mut order: Vec<usize> = (0..width).filter(|&i| relative[i].is_some()).collect();
+    order.sort_by(|&a, &b| relative[b].partial_cmp(&relative[a]).unwrap());
+    header!("Worst bins");
+    table!(
+        "  {:>7} {:>9} {:>9} {:>11}",
+        "column",
+        "center",
+        "error dB",
+        "max error"
+    );
+    for &i in order.iter().take(args.worst) {
+        let rel = relative[i].unwrap();
+        table!("  {i:>7} {:>9.1} {rel:>9.1} {:>11.3e}", center(i), worst[i]);
+        let condition = format!("{:.1} Hz", center(i));
+        record(FilterChoice::Dft, &condition, "error", rel, "dB");
+        record(FilterChoice::Dft, &condition, "max error", worst[i], "");
+    }
+    Ok(())
+}
+
 fn cmd_bin(center: f64) {
     let bin = dsp::bank::bin_lookup(
-        dsp::MIN_FREQ_CHEAP_DRIVERS,
-        dsp::MAX_FREQ_OLD_PEOPLE,
+        Hertz(dsp::MIN_FREQ_CHEAP_DRIVERS),
+        Hertz(dsp::MAX_FREQ_OLD_PEOPLE),
         dsp::spectrogram::RESOLUTION_4K_WIDTH,
-        center,
+        Hertz(center),
     );
     header!("Bin centered at {:6.1}Hz", bin.center);
 
@@ -665,7 +1708,266 @@ fn cmd_bin(center: f64) {
     row!("bandwidth", "{:.2} Hz", bin.bandwidth());
     row!("quality", "{:.1}", bin.q());
 }
-fn cmd_optimize() {}
+
+// NOTE stages and detune only shape IIR cascades.  The GPU bank is all windowed DFTs, so the
+// optimizer searches windows, attenuations, and Q.
+fn cmd_optimize(args: OptimizeArgs) -> Result<(), utate::MutateError> {
+    let fs = WorkbenchConfig::defaults().sample_rate();
+    let goals = dsp::sizing::Goals {
+        max_rise: Seconds(args.max_rise_ms / 1000.0),
+        noise_floor_db: args.noise_floor_db,
+    };
+
+    // Fixed windows ignore attenuation, so only try them once.
+    let mut windows = Vec::new();
+    for choice in &args.windows {
+        match choice {
+            WindowChoice::DolphChebyshev | WindowChoice::Kaiser | WindowChoice::Ultraspherical => {
+                windows.extend(
+                    args.attenuations
+                        .iter()
+                        .map(|&a| choice.function(a, args.mu)),
+                );
+            }
+            _ => windows.push(choice.function(0.0, args.mu)),
+        }
+    }
+
+    header!("Candidate windows");
+    table!(
+        "  {:<44} {:>10} {:>8} {:>10}",
+        "window",
+        "bw bins",
+        "rise",
+        "side lobe"
+    );
+    let mut fits = Vec::new();
+    for window in windows {
+        if interrupted() {
+            return Ok(());
+        }
+        // Long enough for the closed form to hold, short enough to measure quickly.
+        let points = dsp::sizing::sweep(window, Hertz(1000.0), fs, &[8.0, 16.0], -3.0);
+        let Some(fit) = dsp::sizing::fit(window, -3.0, &points) else {
+            eprintln!("warning: {window:?} band edges not found, skipping");
+            continue;
+        };
+        table!(
+            "  {:<44} {:>10.4} {:>8.3} {:>7.1} dB",
+            format!("{window:?}"),
+            fit.bandwidth_bins,
+            fit.rise_fraction,
+            fit.side_lobe_db
+        );
+        fits.push(fit);
+    }
+    if fits.is_empty() {
+        eprintln!("warning: nothing to fit");
+        return Ok(());
+    }
+
+    let bins = dsp::bank::bins(
+        Hertz(dsp::MIN_FREQ_CHEAP_DRIVERS),
+        Hertz(dsp::MAX_FREQ_OLD_PEOPLE),
+        args.resolution,
+    );
+    // Every candidate is scored in closed form, so this is cheap next to the sweeps.
+    let mut chosen = vec![0usize; fits.len()];
+    let mut total = dsp::sizing::Score::default();
+    let table = dsp::bank::BankTable::design_with(&bins, &fits, fs, |bin, rate| {
+        let mut best: Option<(f64, usize, dsp::sizing::Pick, dsp::sizing::Score)> = None;
+        for (i, fit) in fits.iter().enumerate() {
+            for &scale in &args.q_scales {
+                let pick = fit.pick_q(bin.center, bin.q() * scale, rate, goals.max_rise);
+                let score = fit.score(&pick, bin.q(), &goals);
+                if best.is_none_or(|(cost, ..)| score.total() < cost) {
+                    best = Some((score.total(), i, pick, score));
+                }
+            }
+        }
+        let (_, i, pick, score) = best.unwrap();
+        chosen[i] += 1;
+        total.rise += score.rise;
+        total.bandwidth += score.bandwidth;
+        total.noise += score.noise;
+        (i, pick)
+    });
+
+    header!("Bank");
+    row!("bins", "{}", table.bins.len());
+    row!("weights", "{}", table.weights.len());
+    let n = table.bins.len() as f64;
+    row!("mean rise cost", "{:.3}", total.rise / n);
+    row!("mean bandwidth cost", "{:.3}", total.bandwidth / n);
+    row!("mean noise cost", "{:.3}", total.noise / n);
+    for (fit, count) in fits.iter().zip(&chosen).filter(|(_, c)| **c > 0) {
+        row!(format!("{:?}", fit.window), "{} bins", count);
+    }
+
+    let save = |path: &std::path::Path, bytes: &[u8]| {
+        std::fs::write(path, bytes)
+            .map_err(|e| utate::MutateError::BankTable(format!("{}: {e}", path.display())))
+    };
+    save(&args.save, &table.to_bytes())?;
+    table!("\nsaved {}", args.save.display());
+    if let Some(json) = &args.json {
+        save(json, table.to_json().as_bytes())?;
+        table!("saved {}", json.display());
+    }
+    Ok(())
+}
+
+fn cmd_q_sweep(args: QSweepArgs) {
+    let window = args.window.function(args.attenuation_db, args.mu);
+    let fs = WorkbenchConfig::defaults().sample_rate();
+    let center = Hertz(args.center);
+    let threshold = -(args.threshold.abs());
+
+    header!("Q Sweep {window:?} at {:.1} Hz", center.get());
+    table!(
+        "  {:>8} {:>8} {:>8} {:>10} {:>10}",
+        "cycles",
+        "length",
+        "Q",
+        "bw bins",
+        "rise ms"
+    );
+    // Measured one length at a time rather than with `sweep` so that rows print as they finish
+    // and an interrupt lands between lengths.
+    let mut points = Vec::new();
+    for &cycles in &args.cycles {
+        if interrupted() {
+            return;
+        }
+        let length = fs.cycles(center, cycles).get();
+        let Some(p) = dsp::sizing::measure(window, center, fs, length, threshold) else {
+            eprintln!("warning: {cycles} cycles never reached the threshold");
+            continue;
+        };
+        table!(
+            "  {:>8.1} {:>8} {:>8.2} {:>10.4} {:>10.2}",
+            p.cycles,
+            p.length,
+            p.q,
+            p.bandwidth_bins,
+            fs.seconds(p.rise).get() * 1000.0
+        );
+        points.push(p);
+    }
+
+    let Some(fit) = dsp::sizing::fit(window, threshold, &points) else {
+        eprintln!("warning: nothing to fit");
+        return;
+    };
+    header!("Fit");
+    row!("bandwidth", "{:.4} bins", fit.bandwidth_bins);
+    row!("spread", "{:.2} %", fit.spread * 100.0);
+    row!("rise", "{:.3} x length", fit.rise_fraction);
+    table!(
+        "  length = ceil(Q * {:.4} * fs / center)",
+        fit.bandwidth_bins
+    );
+
+    let max_rise = Seconds(args.max_rise_ms / 1000.0);
+    header!("Bank picks (max rise {:.1} ms)", args.max_rise_ms);
+    let bins = dsp::bank::bins(
+        Hertz(dsp::MIN_FREQ_CHEAP_DRIVERS),
+        Hertz(dsp::MAX_FREQ_OLD_PEOPLE),
+        dsp::spectrogram::RESOLUTION_4K_WIDTH,
+    );
+    let step = (bins.len() / 16).max(1);
+    for bin in bins.iter().step_by(step) {
+        let pick = fit.pick(bin, fs, max_rise);
+        let limited = if pick.limited { "  rise limited" } else { "" };
+        table!(
+            "  {:>10.1} Hz  want Q {:>7.1}  got Q {:>7.1}  length {:>7}  rise {:>7.2} ms{limited}",
+            bin.center,
+            bin.q(),
+            pick.q,
+            pick.length,
+            pick.rise.get() * 1000.0
+        );
+    }
+}
+
+fn cmd_calibrate(args: CalibrateArgs) -> Result<(), utate::MutateError> {
+    use dsp::calibration::Calibrator;
+    use utate::audio::AudioContext;
+
+    let context = AudioContext::new()?;
+    let mut found = None;
+    context.with_choices_blocking(|choices| {
+        found = choices
+            .iter()
+            .find(|c| c.name().contains(&args.source))
+            .cloned();
+    })?;
+    let Some(choice) = found else {
+        return Err(utate::MutateError::AudioSource(args.source));
+    };
+    let mut consumer = context.connect(&choice, "µTate calibrate")?;
+    table!(
+        "Hold the {:.0} Hz reference at {:.1} dB SPL on {}",
+        args.reference,
+        args.spl,
+        choice.name()
+    );
+
+    let reference = Hertz(args.reference);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs_f64(args.seconds);
+    let mut calibrator = None;
+    // The first channel is the microphone on multichannel interfaces.
+    let mut mono = vec![[0.0f32; 1]; 1 << 14];
+    while std::time::Instant::now() < deadline {
+        if interrupted() {
+            return Ok(());
+        }
+        consumer.wait(std::time::Duration::from_secs(1))?;
+        loop {
+            if let Some(change) = consumer.format_change()? {
+                // Levels from different rates are not comparable.  Start over.
+                let fs = SampleRate(change.new.rate as f64);
+                calibrator = Some(Calibrator::new(fs, reference));
+            }
+            let read = consumer.read_frames(&mut mono)?;
+            if read == 0 {
+                break;
+            }
+            if let Some(c) = calibrator.as_mut() {
+                c.push(mono[..read].as_flattened());
+            }
+        }
+    }
+
+    let Some(calibrator) = calibrator else {
+        return Err(utate::MutateError::Timeout(
+            "source never negotiated a format",
+        ));
+    };
+    let measured = calibrator.measured_dbfs();
+    let calibration = calibrator.finish(args.spl)?;
+    header!("Calibration");
+    row!(
+        "measured",
+        "{:.2} dBFS",
+        measured.unwrap_or(f64::NEG_INFINITY)
+    );
+    row!("offset", "{:.2} dB", calibration.offset_db);
+    row!("full scale", "{:.1} dB SPL", calibration.spl(0.0));
+    calibration.save(&args.save)?;
+    table!("\nSaved {}", args.save.display());
+    table!("Add its table to mutate.toml to report absolute levels.");
+    Ok(())
+}
+
+/// Long-running commands check this between measurements and return early when it is set.
+fn interrupted() -> bool {
+    let stop = utate::shutdown::requested();
+    if stop {
+        eprintln!("interrupted, stopping early");
+    }
+    stop
+}
 
 // Just convert the choices.  Don't instantiate filters yet!
 fn expand_filter_choices(selectors: Vec<FilterSelector>) -> Vec<FilterChoice> {
@@ -693,71 +1995,268 @@ fn expand_filter_choices(selectors: Vec<FilterSelector>) -> Vec<FilterChoice> {
     }
 }
 
-/// A stub for an actual configuration that has merged flags and defaults and can produce a set of
-/// FilterArgs.
+/// Config file name, searched for under `$XDG_CONFIG_HOME/mutate` and then `~/.config/mutate`.
+const CONFIG_FILE: &str = "workbench.toml";
+
+/// Filter settings that a config file, one of its per-filter sections, or the command line may set.
+/// Unset fields fall through to the layer below.
+#[derive(Clone, Debug, Default)]
+struct Overrides {
+    q: Option<f64>,
+    center: Option<f64>,
+    fs: Option<f64>,
+    stages: Option<usize>,
+    detune: Option<f64>,
+    window: Option<window::WindowFunction>,
+}
+
+impl Overrides {
+    const KEYS: [&str; 6] = ["q", "center", "fs", "stages", "detune", "window"];
+
+    /// `section` only names the table in errors.
+    fn parse(table: &toml::Table, section: &str) -> Result<Self, String> {
+        let bad = |msg: String| format!("[{section}] {msg}");
+        if let Some(key) = table.keys().find(|k| !Self::KEYS.contains(&k.as_str())) {
+            return Err(bad(format!("unknown key `{key}`")));
+        }
+        let float = |key: &str| match table.get(key) {
+            None => Ok(None),
+            Some(v) => v
+                .as_float()
+                .or_else(|| v.as_integer().map(|i| i as f64))
+                .map(Some)
+                .ok_or_else(|| bad(format!("`{key}` must be a number"))),
+        };
+        let stages = match table.get("stages") {
+            None => None,
+            Some(v) => Some(
+                v.as_integer()
+                    .filter(|&s| s > 0)
+                    .ok_or_else(|| bad("`stages` must be a positive integer".into()))?
+                    as usize,
+            ),
+        };
+        let window = match table.get("window") {
+            None => None,
+            Some(v) => Some(Self::parse_window(v).map_err(bad)?),
+        };
+        Ok(Self {
+            q: float("q")?,
+            center: float("center")?,
+            fs: float("fs")?,
+            stages,
+            detune: float("detune")?,
+            window,
+        })
+    }
+
+    /// Either a bare name, `window = "hamming"`, or a table with the tunable parameters,
+    /// `window = { kind = "kaiser", attenuation_db = 80 }`.
+    fn parse_window(value: &toml::Value) -> Result<window::WindowFunction, String> {
+        let (kind, table) = match value {
+            toml::Value::String(kind) => (kind.as_str(), None),
+            toml::Value::Table(t) => (
+                t.get("kind")
+                    .and_then(|k| k.as_str())
+                    .ok_or("window table needs a `kind`")?,
+                Some(t),
+            ),
+            _ => return Err("`window` must be a name or a table".into()),
+        };
+        let choice = WindowChoice::from_str(kind, true)?;
+        let float = |key: &str, default: f64| {
+            table
+                .and_then(|t| t.get(key))
+                .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+                .unwrap_or(default)
+        };
+        Ok(choice.function(float("attenuation_db", 40.0), float("mu", 0.5)))
+    }
+
+    fn apply(&self, args: &mut FilterArgs) {
+        if let Some(q) = self.q {
+            args.q = q;
+        }
+        if let Some(center) = self.center {
+            args.center = Hertz(center);
+        }
+        if let Some(fs) = self.fs {
+            args.fs = SampleRate(fs);
+        }
+        if let Some(stages) = self.stages {
+            args.stages = stages;
+        }
+        if self.detune.is_some() {
+            args.stagger = self.detune;
+        }
+        if let Some(window) = self.window {
+            args.window_choice = window;
+        }
+    }
+}
+
+/// Only the settings that are set, as they would be written in the config file.
+impl std::fmt::Display for Overrides {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut set = Vec::new();
+        let mut push = |key: &str, value: Option<String>| {
+            if let Some(v) = value {
+                set.push(format!("{key} = {v}"));
+            }
+        };
+        push("q", self.q.map(|v| v.to_string()));
+        push("center", self.center.map(|v| v.to_string()));
+        push("fs", self.fs.map(|v| v.to_string()));
+        push("stages", self.stages.map(|v| v.to_string()));
+        push("detune", self.detune.map(|v| v.to_string()));
+        push("window", self.window.map(|v| v.to_string()));
+        write!(f, "{}", set.join(", "))
+    }
+}
+
+impl From<&CommonFilterArgs> for Overrides {
+    fn from(args: &CommonFilterArgs) -> Self {
+        Self {
+            q: args.q,
+            center: args.center,
+            fs: None,
+            stages: args.stages,
+            detune: args.detune,
+            // XXX the window flag has no way to pass attenuation yet
+            window: None,
+        }
+    }
+}
+
+/// Defaults layered from the lowest priority up: built in, the config file, the config file's
+/// section for each filter, and finally command line flags.  Commands then set whatever they are
+/// sweeping on top.
+///
+/// ```toml
+/// q = 32
+/// center = 440
+/// window = { kind = "dolph-chebyshev", attenuation_db = 22.5 }
+///
+/// [biquad]
+/// stages = 2
+///
+/// [dft]
+/// window = "hamming"
+/// ```
+#[derive(Clone)]
 struct WorkbenchConfig {
-    mode: FilterMode,
-    stages: usize,
-    center: f64,
-    q: f64,
-    // NEXT keep going.  Wonder if we just need an internal args field... probably a lot more
-    // straightforward to delegate instead of duplicate FilterArgs fields.
+    args: FilterArgs,
+    sections: Vec<(FilterChoice, Overrides)>,
+    cli: Overrides,
+    /// Where the config was read from, if anywhere.
+    source: Option<std::path::PathBuf>,
 }
 
+static CONFIG: std::sync::OnceLock<WorkbenchConfig> = std::sync::OnceLock::new();
+
 impl WorkbenchConfig {
-    /// Return a config with only defaults,
-    /// NEXT instantiate this by reading the config file
+    /// The loaded config, merged with any command line flags.
     fn defaults() -> Self {
-        // LIES not from config lol
-        let from_config = FilterArgs::default();
+        CONFIG.get().cloned().unwrap_or_else(Self::builtin)
+    }
+
+    fn builtin() -> Self {
+        let args = FilterArgs {
+            // XXX ALWAYS BUTTERWORTH FOR BANDPASS!
+            butterworth: true,
+            window_choice: window::WindowFunction::DolphChebyshev {
+                attenuation_db: 22.5,
+            },
+            ..Default::default()
+        };
         WorkbenchConfig {
-            mode: from_config.mode,
-            stages: from_config.stages,
-            center: from_config.center,
-            q: from_config.q,
+            args,
+            sections: Vec::new(),
+            cli: Overrides::default(),
+            source: None,
+        }
+    }
+
+    /// Read `path`, or the first config file found in the usual places.  Only a missing `path` is
+    /// an error.  Without any file, the built in defaults are used.
+    fn load(path: Option<&std::path::Path>) -> Result<Self, WorkbenchError> {
+        let path = match path {
+            Some(p) => p.to_path_buf(),
+            None => match Self::search() {
+                Some(p) => p,
+                None => return Ok(Self::builtin()),
+            },
+        };
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| WorkbenchError::Config(format!("{}: {e}", path.display())))?;
+        let mut config = Self::parse(&text)
+            .map_err(|e| WorkbenchError::Config(format!("{}: {e}", path.display())))?;
+        config.source = Some(path);
+        Ok(config)
+    }
+
+    fn search() -> Option<std::path::PathBuf> {
+        let var = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty());
+        let dir = var("XDG_CONFIG_HOME")
+            .map(std::path::PathBuf::from)
+            .or_else(|| var("HOME").map(|home| std::path::Path::new(&home).join(".config")))?;
+        Some(dir.join("mutate").join(CONFIG_FILE)).filter(|p| p.is_file())
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let table: toml::Table = text.parse().map_err(|e| format!("{e}"))?;
+        let mut config = Self::builtin();
+        let mut top = toml::Table::new();
+        for (key, value) in table {
+            match (FilterChoice::from_str(&key, true), value) {
+                (Ok(choice), toml::Value::Table(t)) => {
+                    config.sections.push((choice, Overrides::parse(&t, &key)?))
+                }
+                (Ok(_), _) => return Err(format!("`{key}` must be a table")),
+                (Err(_), value) => {
+                    top.insert(key, value);
+                }
+            }
         }
+        Overrides::parse(&top, "top level")?.apply(&mut config.args);
+        Ok(config)
     }
 
-    /// XXX only supports `mode`!
+    /// Command line flags outrank every section of the config file.
     fn merge_with_args(mut self, args: &CommonFilterArgs) -> Self {
         if let Some(mode) = args.filter_mode {
-            self.mode = mode.into();
-        }
-        if let Some(stages) = args.stages {
-            self.stages = stages;
-        }
-        if let Some(center) = args.center {
-            self.center = center;
-        }
-        if let Some(q) = args.q {
-            self.q = q;
+            self.args.mode = mode.into();
         }
+        self.cli = Overrides::from(args);
+        self.cli.apply(&mut self.args);
         self
     }
 
     /// Return the default arguments used to build a filter.
     fn args(&self) -> FilterArgs {
-        FilterArgs {
-            q: self.q(),
-            center: self.center(),
-            fs: self.sample_rate(),
-            gain_factor: 1.00,
-            butterworth: self.cascade_butterworth(),
-            mode: self.filter_mode(),
-            stagger: self.cascade_detune(),
-            stages: self.cascade_stages(),
-            window_choice: self.dft_window(),
+        self.args
+    }
+
+    /// Arguments for one filter, with its section of the config file applied.
+    fn args_for(&self, filter_choice: FilterChoice) -> FilterArgs {
+        let mut args = self.args();
+        for (choice, section) in &self.sections {
+            if *choice == filter_choice {
+                section.apply(&mut args);
+            }
         }
+        self.cli.apply(&mut args);
+        args
     }
 
     /// Build a filter with the default arguments.
     fn filter(&self, filter_choice: FilterChoice) -> Box<dyn Filter> {
-        filter_choice.instantiate(&self.args())
+        filter_choice.instantiate(&self.args_for(filter_choice))
     }
 
     /// Default target bandwidth ratio.
     fn q(&self) -> f64 {
-        self.q
+        self.args.q
     }
 
     /// Default gain threshold for bandwidth estimation.
@@ -766,52 +2265,48 @@ impl WorkbenchConfig {
     }
 
     /// Default sampling frequency
-    fn sample_rate(&self) -> f64 {
-        48000.0
+    fn sample_rate(&self) -> SampleRate {
+        self.args.fs
     }
 
     /// Default center frequency
-    fn center(&self) -> f64 {
-        self.center
+    fn center(&self) -> Hertz {
+        self.args.center
     }
 
     /// Default pass style (bandpass, highpass etc).
     fn filter_mode(&self) -> FilterMode {
-        self.mode
+        self.args.mode
     }
 
     /// Default stages for cascaded filters.
     fn cascade_stages(&self) -> usize {
-        self.stages
+        self.args.stages
     }
 
     /// Toggle for Butterworth Q distribution in cascaded filters.
     fn cascade_butterworth(&self) -> bool {
-        // XXX ALWAYS BUTTERWORTH FOR BANDPASS!
-        true
+        self.args.butterworth
     }
 
     /// Default de-tuning for cascaded filters.
     fn cascade_detune(&self) -> Option<f64> {
-        // Some(1.01)
-        None
+        self.args.stagger
     }
 
     /// DFT window choice
     fn dft_window(&self) -> window::WindowFunction {
-        window::WindowFunction::DolphChebyshev {
-            attenuation_db: 22.5,
-        }
-        // window::WindowFunction::BoxCar
-        // window::WindowFunction::Bartlett
-        // window::WindowFunction::Hamming
-        // window::WindowFunction::Welch
+        self.args.window_choice
     }
 }
 
-/// Find maximum gain for each filter choice.
-fn normalized_gains(choices: &[FilterChoice], args: &FilterArgs) -> Vec<f32> {
-    choices.iter().map(|fc| normalized_gain(fc, args)).collect()
+/// Find maximum gain for each filter choice, each with its own arguments.
+fn normalized_gains(choices: &[FilterChoice], args: &[FilterArgs]) -> Vec<f32> {
+    choices
+        .iter()
+        .zip(args)
+        .map(|(fc, args)| normalized_gain(fc, args))
+        .collect()
 }
 
 /// Find maximum gain.
@@ -822,15 +2317,36 @@ fn normalized_gain(choice: &FilterChoice, args: &FilterArgs) -> f32 {
     let mut filter = choice.instantiate(args);
 
     // NEXT dynamic max gain detection.  No new peaks in n samples etc.
-    let gain_samples = (args.fs / args.center * 512.0) as usize;
+    let gain_samples = args.nsamples(512.0);
 
     let mut peak: f32 = 0.0;
-    for _ in 0..gain_samples {
+    for _ in 0..gain_samples.get() {
         peak = peak.max(filter.process(sg.next().unwrap()).abs());
     }
     peak
 }
 
+/// Settled peak output for a steady tone at `freq`, from a fresh filter.
+fn steady_gain(choice: &FilterChoice, args: &FilterArgs, freq: f64) -> f64 {
+    let mut filter = choice.instantiate(args);
+    let mut sg = SineSweeper::new(freq, args.fs);
+    // Long enough for the slowest filters at the center to settle, and for several waves of
+    // distant low tones.
+    let settle = args
+        .nsamples(8.0 * args.q)
+        .get()
+        .max(sg.nsamples(8.0).get());
+    for _ in 0..settle {
+        filter.process(sg.next().unwrap());
+    }
+    let measure = sg.nsamples(4.0).get().max(settle / 4);
+    let mut peak: f32 = 0.0;
+    for _ in 0..measure {
+        peak = peak.max(filter.process(sg.next().unwrap()).abs());
+    }
+    peak as f64
+}
+
 /// Sweep from `start` to `end` until `threshold_amplitude` is no longer observed for several waves.
 fn sweep_outward(
     filter: &mut Box<dyn Filter>,
@@ -840,7 +2356,7 @@ fn sweep_outward(
     threshold_amplitude: f64,
 ) -> Option<f64> {
     let warmup_samples = sg.nsamples(128.0);
-    for _ in 0..warmup_samples {
+    for _ in 0..warmup_samples.get() {
         filter.process(sg.next().unwrap());
     }
 
@@ -851,7 +2367,7 @@ fn sweep_outward(
 
     let mut found = false;
     let mut last_peak_freq = start;
-    let mut last_peak_samples = 0;
+    let mut last_peak_samples = Samples(0);
 
     'sweep: for s in 0..(sweep_resolution + 1) {
         let freq = next_freq(s);
@@ -861,15 +2377,15 @@ fn sweep_outward(
         let wave_samples = sg.nsamples(1.0);
         let mut wave_peak: f64 = 0.0;
 
-        for w in 0..wave_samples {
+        for w in 0..wave_samples.get() {
             let y = filter.process(sg.next().unwrap()) as f64;
             wave_peak = wave_peak.max(y.abs());
 
             if y.abs() > threshold_amplitude {
-                last_peak_samples = 0;
+                last_peak_samples = Samples(0);
                 last_peak_freq = freq;
             } else {
-                last_peak_samples += 1;
+                last_peak_samples += Samples(1);
                 if last_peak_samples > threshold_samples {
                     found = true;
                     break 'sweep;
@@ -903,7 +2419,7 @@ fn sweep_inward(
         let freq = next_freq(s);
         sg.set_frequency(freq);
         let wave_samples = sg.nsamples(1.0);
-        for w in 0..wave_samples {
+        for w in 0..wave_samples.get() {
             let y = filter.process(sg.next().unwrap()) as f64;
             if y.abs() > threshold_amplitude {
                 return Some(freq);
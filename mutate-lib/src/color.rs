@@ -0,0 +1,427 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Color
+//!
+//! Palettes map a normalized level in `0.0..=1.0` to a color.  Every node that colors by level,
+//! such as spectrogram posters, the waterfall, and particles, uses the same [`Palette`] so that a
+//! look carries across all of them.
+//!
+//! A palette is either one of the built-in [`Colormap`]s or a [`Gradient`] of user stops.  The
+//! perceptual maps, viridis, magma, and turbo, are polynomial fits of the published tables and
+//! change brightness evenly, so equal steps in level read as equal steps in color.  Gradients blend
+//! in Oklab for the same reason.
+//!
+//! ## Config
+//!
+//! A palette is written as the name of a built-in or as a table of stops.  Bare colors are spread
+//! evenly.  `[position, color]` pairs place them.
+//!
+//! ```toml
+//! [palette]
+//! night = "magma"
+//! ember = { stops = ["#000000", "#7f1000", "#ff8000", "#ffffe0"] }
+//! ice = { stops = [[0.0, "#000010"], [0.7, "#2060ff"], [1.0, "#ffffff"]] }
+//! ```
+//!
+//! ## GPU
+//!
+//! Shaders do not evaluate palettes.  [`Palette::lut`] samples one into a table that is uploaded as
+//! a buffer and read with linear interpolation, so a shader needs one lookup no matter which
+//! palette is in use.
+//!
+//! Colors are sRGB encoded, as the shaders write them.
+
+// NEXT the auto-VJ switches palettes on section changes.  Crossfading two tables is cheap once
+// nodes read the table through a handle that can be swapped between frames.
+
+use std::str::FromStr;
+
+use palette::{FromColor, Mix, Oklab, Srgb};
+
+use crate::MutateError;
+
+fn bad(msg: String) -> MutateError {
+    MutateError::Palette(msg)
+}
+
+/// Built-in palettes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Colormap {
+    Grayscale,
+    /// Black through red and yellow to white.  Quiet regions stay dark and the loudest partials
+    /// still separate from each other.
+    #[default]
+    Heat,
+    /// Blue through green to yellow.  Perceptually uniform and readable without color vision.
+    Viridis,
+    /// Black through purple and orange to pale yellow.  Perceptually uniform with a dark floor.
+    Magma,
+    /// A rainbow with smooth lightness.  Good at separating levels, not at ordering them.
+    Turbo,
+}
+
+impl Colormap {
+    pub const ALL: [Colormap; 5] = [
+        Colormap::Grayscale,
+        Colormap::Heat,
+        Colormap::Viridis,
+        Colormap::Magma,
+        Colormap::Turbo,
+    ];
+
+    /// Name used in config.
+    pub fn name(self) -> &'static str {
+        match self {
+            Colormap::Grayscale => "grayscale",
+            Colormap::Heat => "heat",
+            Colormap::Viridis => "viridis",
+            Colormap::Magma => "magma",
+            Colormap::Turbo => "turbo",
+        }
+    }
+
+    /// Color at `x`, clamped to `0.0..=1.0`.
+    pub fn sample(self, x: f32) -> Srgb<f32> {
+        let x = x.clamp(0.0, 1.0);
+        let rgb = match self {
+            Colormap::Grayscale => [x; 3],
+            Colormap::Heat => {
+                let x = x * 3.0;
+                [x, x - 1.0, x - 2.0]
+            }
+            Colormap::Viridis => polynomial(&VIRIDIS, x),
+            Colormap::Magma => polynomial(&MAGMA, x),
+            Colormap::Turbo => polynomial(&TURBO, x),
+        };
+        let [r, g, b] = rgb.map(|c| c.clamp(0.0, 1.0));
+        Srgb::new(r, g, b)
+    }
+}
+
+impl FromStr for Colormap {
+    type Err = MutateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.name() == s)
+            .ok_or_else(|| bad(format!("unknown colormap `{s}`")))
+    }
+}
+
+/// Coefficients from the constant term up, one column per channel.
+fn polynomial(coefficients: &[[f32; 3]], x: f32) -> [f32; 3] {
+    let mut acc = [0.0; 3];
+    for c in coefficients.iter().rev() {
+        for (a, c) in acc.iter_mut().zip(c) {
+            *a = *a * x + c;
+        }
+    }
+    acc
+}
+
+// Fits of the matplotlib tables by Matt Zucker, released CC0.
+const VIRIDIS: [[f32; 3]; 7] = [
+    [0.277_727_33, 0.005_407_344_5, 0.334_099_8],
+    [0.105_093_04, 1.404_613_5, 1.384_590_2],
+    [-0.330_861_83, 0.214_847_56, 0.095_095_16],
+    [-4.634_230_6, -5.799_101, -19.332_44],
+    [6.228_27, 14.179_933, 56.690_55],
+    [4.776_385, -13.745_145, -65.353_03],
+    [-5.435_456, 4.645_852_6, 26.312_435],
+];
+const MAGMA: [[f32; 3]; 7] = [
+    [-0.002_136_485, -0.000_749_655, -0.005_386_128],
+    [0.251_660_54, 0.677_523_24, 2.494_026_6],
+    [8.353_717, -3.577_719_5, 0.314_467_9],
+    [-27.668_733, 14.264_731, -13.649_213],
+    [52.176_14, -27.943_606, 12.944_169],
+    [-50.768_524, 29.046_583, 4.234_153],
+    [18.655_705, -11.489_774, -5.601_961_5],
+];
+// Google's polynomial approximation, Apache-2.0.  It strays by up to an eighth at both dark ends.
+const TURBO: [[f32; 3]; 6] = [
+    [0.135_721_38, 0.091_402_61, 0.106_673_3],
+    [4.615_392_6, 2.194_188_4, 12.641_946],
+    [-42.660_324, 4.842_966_6, -60.582_047],
+    [132.131_08, -14.185_033, 110.362_77],
+    [-152.942_4, 4.277_299, -89.903_11],
+    [59.286_38, 2.829_566, 27.348_25],
+];
+
+/// Colors at positions in `0.0..=1.0`, blended in Oklab.  Levels below the first stop take its
+/// color, and likewise above the last.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(f32, Oklab)>,
+}
+
+impl Gradient {
+    /// Stops must be in order, within `0.0..=1.0`, and there must be at least two.
+    pub fn new(stops: impl IntoIterator<Item = (f32, Srgb<f32>)>) -> Result<Self, MutateError> {
+        let stops: Vec<_> = stops
+            .into_iter()
+            .map(|(at, color)| (at, Oklab::from_color(color)))
+            .collect();
+        if stops.len() < 2 {
+            return Err(bad("a gradient needs at least two stops".into()));
+        }
+        if stops.iter().any(|(at, _)| !(0.0..=1.0).contains(at)) {
+            return Err(bad("gradient stops must be within 0..=1".into()));
+        }
+        if stops.windows(2).any(|w| w[0].0 > w[1].0) {
+            return Err(bad("gradient stops must be in order".into()));
+        }
+        Ok(Self { stops })
+    }
+
+    /// `colors` spread evenly from 0 to 1.
+    pub fn even(colors: impl IntoIterator<Item = Srgb<f32>>) -> Result<Self, MutateError> {
+        let colors: Vec<_> = colors.into_iter().collect();
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        Self::new(
+            colors
+                .into_iter()
+                .enumerate()
+                .map(|(i, c)| (i as f32 / last, c)),
+        )
+    }
+
+    pub fn sample(&self, x: f32) -> Srgb<f32> {
+        let after = self.stops.partition_point(|(at, _)| *at <= x);
+        let lab = match after {
+            0 => self.stops[0].1,
+            n if n == self.stops.len() => self.stops[n - 1].1,
+            n => {
+                let (a, b) = (self.stops[n - 1], self.stops[n]);
+                a.1.mix(b.1, (x - a.0) / (b.0 - a.0))
+            }
+        };
+        Srgb::from_color(lab)
+    }
+
+    fn parse(table: &toml::Table) -> Result<Self, MutateError> {
+        let stops = table
+            .get("stops")
+            .and_then(|s| s.as_array())
+            .ok_or_else(|| bad("a gradient needs `stops`, an array".into()))?;
+        if stops.iter().all(|s| s.is_str()) {
+            return Self::even(
+                stops
+                    .iter()
+                    .map(|s| hex(s.as_str().unwrap()))
+                    .collect::<Result<Vec<_>, _>>()?,
+            );
+        }
+        let stops = stops
+            .iter()
+            .map(|s| match s.as_array().map(|a| a.as_slice()) {
+                Some([at, color]) => {
+                    let at = at
+                        .as_float()
+                        .or_else(|| at.as_integer().map(|i| i as f64))
+                        .ok_or_else(|| bad("stop positions must be numbers".into()))?;
+                    let color = color
+                        .as_str()
+                        .ok_or_else(|| bad("stop colors must be strings".into()))?;
+                    Ok((at as f32, hex(color)?))
+                }
+                _ => Err(bad(
+                    "stops are all colors or all `[position, color]` pairs".into()
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(stops)
+    }
+}
+
+/// Read `#rrggbb`.
+fn hex(text: &str) -> Result<Srgb<f32>, MutateError> {
+    let rgb: Srgb<u8> = text
+        .parse()
+        .map_err(|_| bad(format!("`{text}` is not a #rrggbb color")))?;
+    Ok(rgb.into_format())
+}
+
+/// A map from level to color.  See the [module docs](self).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Palette {
+    Map(Colormap),
+    Gradient(Gradient),
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::Map(Colormap::default())
+    }
+}
+
+impl From<Colormap> for Palette {
+    fn from(map: Colormap) -> Self {
+        Palette::Map(map)
+    }
+}
+
+impl From<Gradient> for Palette {
+    fn from(gradient: Gradient) -> Self {
+        Palette::Gradient(gradient)
+    }
+}
+
+impl Palette {
+    /// Color at `x`, clamped to `0.0..=1.0`.
+    pub fn sample(&self, x: f32) -> Srgb<f32> {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            Palette::Map(map) => map.sample(x),
+            Palette::Gradient(gradient) => gradient.sample(x),
+        }
+    }
+
+    /// Color at `x` as bytes, for images written on the host.
+    pub fn rgb8(&self, x: f32) -> [u8; 3] {
+        let c = self.sample(x);
+        // Truncated rather than rounded, so the top byte is only reached at the top of the range.
+        [c.red, c.green, c.blue].map(|v| (v * 255.0) as u8)
+    }
+
+    /// `len` colors sampled evenly from 0 to 1, with alpha of one, for upload to the GPU.  The
+    /// first and last entries are the ends of the palette, so a shader interpolating between
+    /// entries reproduces both.
+    pub fn lut(&self, len: usize) -> Vec<[f32; 4]> {
+        let last = len.saturating_sub(1).max(1) as f32;
+        (0..len)
+            .map(|i| {
+                let c = self.sample(i as f32 / last);
+                [c.red, c.green, c.blue, 1.0]
+            })
+            .collect()
+    }
+
+    /// Read the name of a built-in or a gradient table.  See the [module docs](self).
+    pub fn parse(value: &toml::Value) -> Result<Self, MutateError> {
+        match value {
+            toml::Value::String(name) => Ok(Palette::Map(name.parse()?)),
+            toml::Value::Table(table) => Ok(Palette::Gradient(Gradient::parse(table)?)),
+            _ => Err(bad(
+                "a palette is a colormap name or a table of stops".into()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn close(c: Srgb<f32>, rgb: [f32; 3], tolerance: f32) -> bool {
+        [c.red, c.green, c.blue]
+            .iter()
+            .zip(rgb)
+            .all(|(a, b)| (a - b).abs() <= tolerance)
+    }
+
+    #[test]
+    fn test_colormap_ends() {
+        // Ends of the published tables.  The turbo fit is loose at its dark ends.
+        let cases = [
+            (
+                Colormap::Viridis,
+                0.03,
+                [0.267, 0.005, 0.329],
+                [0.993, 0.906, 0.144],
+            ),
+            (
+                Colormap::Magma,
+                0.03,
+                [0.001, 0.000, 0.014],
+                [0.987, 0.991, 0.750],
+            ),
+            (
+                Colormap::Turbo,
+                0.15,
+                [0.190, 0.072, 0.232],
+                [0.480, 0.016, 0.011],
+            ),
+        ];
+        for (map, tolerance, low, high) in cases {
+            assert!(close(map.sample(0.0), low, tolerance), "{map:?} low");
+            assert!(close(map.sample(1.0), high, tolerance), "{map:?} high");
+        }
+        assert_eq!(Colormap::Heat.sample(2.0), Srgb::new(1.0, 1.0, 1.0));
+        for map in Colormap::ALL {
+            assert_eq!(map.name().parse::<Colormap>().unwrap(), map);
+        }
+    }
+
+    #[test]
+    fn test_perceptual_maps_brighten() {
+        // The sequential maps only get lighter.  Turbo is a rainbow and does not.
+        for map in [Colormap::Viridis, Colormap::Magma] {
+            let lightness: Vec<f32> = (0..=32)
+                .map(|i| Oklab::from_color(map.sample(i as f32 / 32.0)).l)
+                .collect();
+            assert!(lightness.windows(2).all(|w| w[1] > w[0]), "{map:?}");
+        }
+    }
+
+    #[test]
+    fn test_gradient() {
+        let black = Srgb::new(0.0, 0.0, 0.0);
+        let white = Srgb::new(1.0, 1.0, 1.0);
+        let gradient = Gradient::new([(0.25, black), (0.75, white)]).unwrap();
+        assert!(close(gradient.sample(0.0), [0.0; 3], 1e-4));
+        assert!(close(gradient.sample(1.0), [1.0; 3], 1e-4));
+        // Halfway in Oklab is a perceptual mid gray, lighter than half the encoded value.
+        let mid = gradient.sample(0.5);
+        assert!(mid.red > 0.35 && mid.red < 0.5, "{mid:?}");
+
+        assert!(Gradient::new([(0.0, black)]).is_err());
+        assert!(Gradient::new([(0.5, black), (0.25, white)]).is_err());
+        assert!(Gradient::new([(0.0, black), (1.5, white)]).is_err());
+    }
+
+    #[test]
+    fn test_palette_parse() {
+        let table: toml::Table = r##"
+            night = "magma"
+            ember = { stops = ["#000000", "#ff8000", "#ffffff"] }
+            ice = { stops = [[0, "#000010"], [0.7, "#2060ff"], [1.0, "#ffffff"]] }
+            bad_name = "sepia"
+            bad_color = { stops = ["#000000", "orange"] }
+            mixed = { stops = ["#000000", [1.0, "#ffffff"]] }
+        "##
+        .parse()
+        .unwrap();
+        let parse = |key: &str| Palette::parse(&table[key]);
+
+        assert_eq!(parse("night").unwrap(), Palette::Map(Colormap::Magma));
+        let ember = parse("ember").unwrap();
+        assert!(close(
+            ember.sample(0.5),
+            [1.0, 0x80 as f32 / 255.0, 0.0],
+            1e-3
+        ));
+        let ice = parse("ice").unwrap();
+        assert!(close(
+            ice.sample(0.7),
+            [0x20 as f32 / 255.0, 0x60 as f32 / 255.0, 1.0],
+            1e-3
+        ));
+        for key in ["bad_name", "bad_color", "mixed"] {
+            assert!(parse(key).is_err(), "{key}");
+        }
+    }
+
+    #[test]
+    fn test_palette_lut() {
+        let palette = Palette::from(Colormap::Viridis);
+        let lut = palette.lut(256);
+        assert_eq!(lut.len(), 256);
+        let first = palette.sample(0.0);
+        let last = palette.sample(1.0);
+        assert_eq!(lut[0], [first.red, first.green, first.blue, 1.0]);
+        assert_eq!(lut[255], [last.red, last.green, last.blue, 1.0]);
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # MIDI
+//!
+//! Receives control changes from a MIDI input port and sends [output](super::output) to a MIDI
+//! output port.  On Linux, midir uses ALSA sequencer ports, which pipewire also exposes.
+
+use std::sync::mpsc::Sender;
+
+use midir::{MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+
+use super::ControlEvent;
+use crate::MutateError;
+
+const CLIENT_NAME: &str = "µTate";
+
+fn midi_error(e: impl std::fmt::Display) -> MutateError {
+    MutateError::ControlMapping(format!("MIDI: {e}"))
+}
+
+/// Names of the available input ports.
+pub fn ports() -> Result<Vec<String>, MutateError> {
+    let input = MidiInput::new(CLIENT_NAME).map_err(midi_error)?;
+    Ok(input
+        .ports()
+        .iter()
+        .filter_map(|p| input.port_name(p).ok())
+        .collect())
+}
+
+/// Names of the available output ports.
+pub fn output_ports() -> Result<Vec<String>, MutateError> {
+    let output = MidiOutput::new(CLIENT_NAME).map_err(midi_error)?;
+    Ok(output
+        .ports()
+        .iter()
+        .filter_map(|p| output.port_name(p).ok())
+        .collect())
+}
+
+/// An open MIDI input.  Events are forwarded from midir's own thread.
+pub struct MidiListener {
+    connection: MidiInputConnection<()>,
+}
+
+impl MidiListener {
+    /// Connect to the first port whose name contains `port`, or the first port at all when `port` is
+    /// `None`.
+    pub fn connect(port: Option<&str>, events: Sender<ControlEvent>) -> Result<Self, MutateError> {
+        let input = MidiInput::new(CLIENT_NAME).map_err(midi_error)?;
+        let ports = input.ports();
+        let found = ports
+            .iter()
+            .find(|p| match port {
+                Some(wanted) => input.port_name(p).is_ok_and(|name| name.contains(wanted)),
+                None => true,
+            })
+            .ok_or_else(|| midi_error(format!("no input port matching {port:?}")))?
+            .clone();
+        let connection = input
+            .connect(
+                &found,
+                "µTate control",
+                move |_stamp, message, _| {
+                    if let Some(event) = ControlEvent::from_midi(message) {
+                        // A dropped receiver just means nobody is listening anymore.
+                        let _ = events.send(event);
+                    }
+                },
+                (),
+            )
+            .map_err(midi_error)?;
+        Ok(Self { connection })
+    }
+
+    pub fn stop(self) {
+        self.connection.close();
+    }
+}
+
+/// An open MIDI output.
+pub struct MidiSender {
+    connection: MidiOutputConnection,
+}
+
+impl MidiSender {
+    /// Connect to the first output port whose name contains `port`, or the first port at all when
+    /// `port` is `None`.
+    pub fn connect(port: Option<&str>) -> Result<Self, MutateError> {
+        let output = MidiOutput::new(CLIENT_NAME).map_err(midi_error)?;
+        let ports = output.ports();
+        let found = ports
+            .iter()
+            .find(|p| match port {
+                Some(wanted) => output.port_name(p).is_ok_and(|name| name.contains(wanted)),
+                None => true,
+            })
+            .ok_or_else(|| midi_error(format!("no output port matching {port:?}")))?
+            .clone();
+        let connection = output.connect(&found, "µTate output").map_err(midi_error)?;
+        Ok(Self { connection })
+    }
+
+    pub fn send(&mut self, message: &[u8]) -> Result<(), MutateError> {
+        self.connection.send(message).map_err(midi_error)
+    }
+
+    pub fn stop(self) {
+        self.connection.close();
+    }
+}
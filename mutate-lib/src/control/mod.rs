@@ -0,0 +1,320 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Control
+//!
+//! External controllers drive node parameters through the [`Graph`].  Hardware faders speak MIDI
+//! CC.  Lighting desks and tablet apps speak OSC.  Both are turned into [`ControlEvent`]s by a
+//! listener thread and sent over a channel.  The thread that owns the graph drains the channel and
+//! hands each event to a [`MappingTable`], which decides which parameters it moves.
+//!
+//! ## Mapping Table
+//!
+//! Mappings are user-edited TOML, a file of their own or a section of the [settings
+//! file](crate::settings):
+//!
+//! ```toml
+//! [[midi]]
+//! cc = 74
+//! channel = 1          # 1-16, omit to accept any channel
+//! param = "ring/gain"
+//! range = [0.0, 0.5]   # optional, the normalized portion of the parameter to sweep
+//!
+//! [[osc]]
+//! address = "/1/fader1"
+//! param = "ring/gain"
+//! ```
+//!
+//! Mapped values are normalized.  A CC of 127 or an OSC float of `1.0` is the top of the range.
+//!
+//! OSC messages that are not mapped but whose address is `/mutate/<node>/<param>` are written
+//! directly with their raw value, so scripts can address every parameter without a table entry.
+//!
+//! ## Output
+//!
+//! MIDI also goes the other way.  See [`output`] for clock, beat notes, and band energy sent to
+//! synths and lighting controllers.
+
+// MAYBE soft takeover.  Motorized faders aside, a fader that is far from the current value causes a
+// jump on first touch.  Waiting until the fader crosses the current value is the usual fix.
+
+#[cfg(feature = "midi")]
+pub mod midi;
+pub mod osc;
+#[cfg(feature = "dsp")]
+pub mod output;
+
+use std::path::Path;
+
+use crate::graph::{Graph, ParamValue};
+use crate::MutateError;
+
+/// Address prefix of OSC messages that write parameters without a mapping.
+pub const OSC_DIRECT_PREFIX: &str = "/mutate/";
+
+/// One message from an external controller.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ControlEvent {
+    /// MIDI control change.  `channel` is `1..=16` as printed on hardware.
+    Cc {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    Osc {
+        address: String,
+        args: Vec<osc::OscArg>,
+    },
+}
+
+impl ControlEvent {
+    /// Decode a raw MIDI message.  Only control changes are of interest.
+    pub fn from_midi(message: &[u8]) -> Option<Self> {
+        match message {
+            [status, controller, value] if status & 0xF0 == 0xB0 => Some(ControlEvent::Cc {
+                channel: (status & 0x0F) + 1,
+                controller: controller & 0x7F,
+                value: value & 0x7F,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ControlSource {
+    Cc { channel: Option<u8>, controller: u8 },
+    Osc(String),
+}
+
+/// Route from one controller input to one parameter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mapping {
+    pub source: ControlSource,
+    /// `<node>/<param>` path understood by [`Graph::set`].
+    pub param: String,
+    /// Normalized sub-range of the parameter that the control sweeps.
+    pub range: (f64, f64),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MappingTable {
+    pub mappings: Vec<Mapping>,
+}
+
+impl MappingTable {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MutateError> {
+        let text = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            MutateError::ControlMapping(format!("{}: {e}", path.as_ref().display()))
+        })?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, MutateError> {
+        let table: toml::Table = text
+            .parse()
+            .map_err(|e| MutateError::ControlMapping(format!("{e}")))?;
+        Self::from_table(&table)
+    }
+
+    /// Read the `midi` and `osc` arrays of `table`.  Any other key is an error.
+    pub fn from_table(table: &toml::Table) -> Result<Self, MutateError> {
+        let bad = |msg: String| MutateError::ControlMapping(msg);
+        let mut mappings = Vec::new();
+        for (key, entries) in table {
+            let entries = entries
+                .as_array()
+                .ok_or_else(|| bad(format!("`{key}` must be an array of tables")))?;
+            for entry in entries {
+                let entry = entry
+                    .as_table()
+                    .ok_or_else(|| bad(format!("`{key}` entries must be tables")))?;
+                let int = |name: &str| entry.get(name).and_then(|v| v.as_integer());
+                let source = match key.as_str() {
+                    "midi" => {
+                        let controller = int("cc")
+                            .filter(|cc| (0..128).contains(cc))
+                            .ok_or_else(|| bad("midi mapping needs `cc` in 0..=127".into()))?;
+                        let channel = match int("channel") {
+                            Some(ch @ 1..=16) => Some(ch as u8),
+                            Some(ch) => {
+                                return Err(bad(format!("midi channel {ch} not in 1..=16")))
+                            }
+                            None => None,
+                        };
+                        ControlSource::Cc {
+                            channel,
+                            controller: controller as u8,
+                        }
+                    }
+                    "osc" => {
+                        let address = entry
+                            .get("address")
+                            .and_then(|v| v.as_str())
+                            .filter(|a| a.starts_with('/'))
+                            .ok_or_else(|| {
+                                bad("osc mapping needs an `address` starting with /".into())
+                            })?;
+                        ControlSource::Osc(address.to_owned())
+                    }
+                    other => return Err(bad(format!("unknown mapping kind `{other}`"))),
+                };
+                let param = entry
+                    .get("param")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| bad(format!("{key} mapping needs a `param` path")))?;
+                let range = match entry.get("range").and_then(|v| v.as_array()) {
+                    Some(r) => match r.as_slice() {
+                        [lo, hi] => (
+                            number(lo).ok_or_else(|| bad("bad range".into()))?,
+                            number(hi).ok_or_else(|| bad("bad range".into()))?,
+                        ),
+                        _ => return Err(bad("`range` must be [low, high]".into())),
+                    },
+                    None => (0.0, 1.0),
+                };
+                mappings.push(Mapping {
+                    source,
+                    param: param.to_owned(),
+                    range,
+                });
+            }
+        }
+        Ok(Self { mappings })
+    }
+
+    /// Check that every mapped path exists in `graph`.  Run after loading so that typos are reported
+    /// once instead of silently ignored on every event.
+    pub fn validate(&self, graph: &Graph) -> Result<(), MutateError> {
+        for m in &self.mappings {
+            graph.resolve(&m.param)?;
+        }
+        Ok(())
+    }
+
+    /// Write every parameter that `event` is mapped to.  Returns how many were written.
+    pub fn apply(&self, graph: &Graph, event: &ControlEvent) -> Result<usize, MutateError> {
+        let mut written = 0;
+        for m in &self.mappings {
+            let normalized = match (&m.source, event) {
+                (
+                    ControlSource::Cc {
+                        channel,
+                        controller,
+                    },
+                    ControlEvent::Cc {
+                        channel: ch,
+                        controller: cc,
+                        value,
+                    },
+                ) if cc == controller && channel.is_none_or(|c| c == *ch) => *value as f64 / 127.0,
+                (ControlSource::Osc(address), ControlEvent::Osc { address: a, args })
+                    if a == address =>
+                {
+                    match args.first().and_then(|a| a.as_f64()) {
+                        Some(v) => v,
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            let (handle, index) = graph.resolve(&m.param)?;
+            let (lo, hi) = m.range;
+            let x = lo + normalized.clamp(0.0, 1.0) * (hi - lo);
+            handle.set(index, handle.specs()[index].from_normalized(x))?;
+            written += 1;
+        }
+
+        if written == 0 {
+            if let ControlEvent::Osc { address, args } = event {
+                if let Some(path) = address.strip_prefix(OSC_DIRECT_PREFIX) {
+                    if let Some(value) = args.first().and_then(|a| a.as_param()) {
+                        graph.set(path, value)?;
+                        written += 1;
+                    }
+                }
+            }
+        }
+        Ok(written)
+    }
+}
+
+fn number(value: &toml::Value) -> Option<f64> {
+    value
+        .as_float()
+        .or_else(|| value.as_integer().map(|i| i as f64))
+}
+
+#[cfg(test)]
+mod test {
+    use super::osc::OscArg;
+    use super::*;
+    use crate::graph::{ParamKind, ParamSpec, Params};
+
+    struct Ring;
+
+    impl Params for Ring {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[ParamSpec {
+                name: "gain",
+                description: "ring radius gain",
+                kind: ParamKind::Float { min: 0.0, max: 2.0 },
+                default: ParamValue::Float(1.0),
+            }]
+        }
+    }
+
+    const TABLE: &str = r#"
+        [[midi]]
+        cc = 74
+        channel = 2
+        param = "ring/gain"
+        range = [0.0, 0.5]
+
+        [[osc]]
+        address = "/1/fader1"
+        param = "ring/gain"
+    "#;
+
+    #[test]
+    fn test_control_mapping() {
+        let mut graph = Graph::new();
+        let (_, ring) = graph.register("ring", &Ring).unwrap();
+        let table = MappingTable::parse(TABLE).unwrap();
+        assert_eq!(table.mappings.len(), 2);
+        table.validate(&graph).unwrap();
+
+        let cc = ControlEvent::from_midi(&[0xB1, 74, 127]).unwrap();
+        assert_eq!(table.apply(&graph, &cc).unwrap(), 1);
+        assert_eq!(ring.f64(0), 1.0);
+
+        // Wrong channel.
+        let cc = ControlEvent::from_midi(&[0xB0, 74, 0]).unwrap();
+        assert_eq!(table.apply(&graph, &cc).unwrap(), 0);
+
+        let osc = ControlEvent::Osc {
+            address: "/1/fader1".into(),
+            args: vec![OscArg::Float(0.25)],
+        };
+        table.apply(&graph, &osc).unwrap();
+        assert_eq!(ring.f64(0), 0.5);
+
+        let direct = ControlEvent::Osc {
+            address: "/mutate/ring/gain".into(),
+            args: vec![OscArg::Float(1.5)],
+        };
+        table.apply(&graph, &direct).unwrap();
+        assert_eq!(ring.f64(0), 1.5);
+    }
+
+    #[test]
+    fn test_control_mapping_errors() {
+        assert!(MappingTable::parse("[[midi]]\ncc = 200\nparam = \"a/b\"").is_err());
+        assert!(MappingTable::parse("[[osc]]\naddress = \"x\"\nparam = \"a/b\"").is_err());
+        assert!(MappingTable::parse("[[dmx]]\nparam = \"a/b\"").is_err());
+        let graph = Graph::new();
+        let table = MappingTable::parse("[[midi]]\ncc = 1\nparam = \"a/b\"").unwrap();
+        assert!(table.validate(&graph).is_err());
+        assert!(ControlEvent::from_midi(&[0x90, 60, 100]).is_none());
+    }
+}
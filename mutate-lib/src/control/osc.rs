@@ -0,0 +1,243 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # OSC
+//!
+//! Receives [Open Sound Control](https://opensoundcontrol.stanford.edu/spec-1_0.html) over UDP.
+//! Only the subset that controllers send is decoded: messages and bundles carrying numbers and
+//! booleans.  Other argument types are skipped.  Bundle time tags are ignored and every message is
+//! delivered as soon as it arrives.
+
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use super::ControlEvent;
+use crate::graph::ParamValue;
+use crate::MutateError;
+
+/// Largest datagram we accept.  Controllers send far less.
+const MAX_PACKET: usize = 4096;
+/// How often the receiving thread checks whether it should stop.
+const POLL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    Double(f64),
+    Long(i64),
+    Bool(bool),
+}
+
+impl OscArg {
+    pub fn as_f64(self) -> Option<f64> {
+        match self {
+            OscArg::Int(i) => Some(i as f64),
+            OscArg::Float(f) => Some(f as f64),
+            OscArg::Double(d) => Some(d),
+            OscArg::Long(l) => Some(l as f64),
+            OscArg::Bool(b) => Some(b as u8 as f64),
+        }
+    }
+
+    /// The value as written to a parameter without normalization.
+    pub fn as_param(self) -> Option<ParamValue> {
+        match self {
+            OscArg::Int(i) => Some(ParamValue::Int(i as i64)),
+            OscArg::Long(l) => Some(ParamValue::Int(l)),
+            OscArg::Float(f) => Some(ParamValue::Float(f as f64)),
+            OscArg::Double(d) => Some(ParamValue::Float(d)),
+            OscArg::Bool(b) => Some(ParamValue::Bool(b)),
+        }
+    }
+}
+
+/// Decode one UDP packet into zero or more events.  Malformed packets decode to nothing.
+pub fn decode(packet: &[u8]) -> Vec<ControlEvent> {
+    let mut out = Vec::new();
+    decode_into(packet, &mut out);
+    out
+}
+
+fn decode_into(packet: &[u8], out: &mut Vec<ControlEvent>) -> Option<()> {
+    let mut r = Reader {
+        buf: packet,
+        pos: 0,
+    };
+    let address = r.string()?;
+    if address == "#bundle" {
+        r.take(8)?; // time tag
+        while r.pos < packet.len() {
+            let size = r.i32()? as usize;
+            decode_into(r.take(size)?, out);
+        }
+        return Some(());
+    }
+    if !address.starts_with('/') {
+        return None;
+    }
+    // Very old senders omit the type tags entirely.  There is nothing to read in that case.
+    let tags = if r.pos < packet.len() {
+        r.string()?
+    } else {
+        ","
+    };
+    let mut args = Vec::new();
+    for tag in tags.strip_prefix(',')?.bytes() {
+        match tag {
+            b'i' => args.push(OscArg::Int(r.i32()?)),
+            b'f' => args.push(OscArg::Float(f32::from_bits(r.i32()? as u32))),
+            b'h' => args.push(OscArg::Long(i64::from_be_bytes(
+                r.take(8)?.try_into().ok()?,
+            ))),
+            b'd' => args.push(OscArg::Double(f64::from_be_bytes(
+                r.take(8)?.try_into().ok()?,
+            ))),
+            b'T' => args.push(OscArg::Bool(true)),
+            b'F' => args.push(OscArg::Bool(false)),
+            b's' | b'S' => {
+                r.string()?;
+            }
+            b'b' => {
+                let size = r.i32()? as usize;
+                r.take(size.next_multiple_of(4))?;
+            }
+            b't' => {
+                r.take(8)?;
+            }
+            b'c' | b'r' | b'm' => {
+                r.take(4)?;
+            }
+            b'N' | b'I' | b'[' | b']' => {}
+            _ => return None,
+        }
+    }
+    out.push(ControlEvent::Osc {
+        address: address.to_owned(),
+        args,
+    });
+    Some(())
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let bytes = self.buf.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(bytes)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        Some(i32::from_be_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    /// NUL terminated and padded to four bytes.
+    fn string(&mut self) -> Option<&'a str> {
+        let rest = self.buf.get(self.pos..)?;
+        let len = rest.iter().position(|&b| b == 0)?;
+        let s = std::str::from_utf8(&rest[..len]).ok()?;
+        self.take((len + 1).next_multiple_of(4))?;
+        Some(s)
+    }
+}
+
+/// Receives OSC on a UDP socket and forwards decoded events.
+pub struct OscListener {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl OscListener {
+    /// Bind `addr`, such as `0.0.0.0:9000`, and forward every decoded message to `events`.  The
+    /// thread exits when stopped or when the receiving side of `events` is dropped.
+    pub fn bind(
+        addr: impl ToSocketAddrs,
+        events: Sender<ControlEvent>,
+    ) -> Result<Self, MutateError> {
+        let socket = UdpSocket::bind(addr)
+            .and_then(|s| s.set_read_timeout(Some(POLL)).map(|_| s))
+            .map_err(|e| MutateError::ControlMapping(format!("OSC bind: {e}")))?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_thread = stop.clone();
+        let thread = std::thread::Builder::new()
+            .name("mutate-osc".into())
+            .spawn(move || {
+                let mut buf = [0u8; MAX_PACKET];
+                while !stop_thread.load(Ordering::Relaxed) {
+                    let n = match socket.recv(&mut buf) {
+                        Ok(n) => n,
+                        Err(e)
+                            if matches!(
+                                e.kind(),
+                                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                            ) =>
+                        {
+                            continue
+                        }
+                        Err(e) => {
+                            eprintln!("OSC receive: {e}");
+                            return;
+                        }
+                    };
+                    for event in decode(&buf[..n]) {
+                        if events.send(event).is_err() {
+                            return;
+                        }
+                    }
+                }
+            })
+            .map_err(|e| MutateError::ControlMapping(format!("OSC thread: {e}")))?;
+        Ok(Self { stop, thread })
+    }
+
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.thread.join();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pad(s: &str) -> Vec<u8> {
+        let mut v = s.as_bytes().to_vec();
+        v.push(0);
+        v.resize(v.len().next_multiple_of(4), 0);
+        v
+    }
+
+    #[test]
+    fn test_osc_decode() {
+        let mut msg = pad("/1/fader1");
+        msg.extend(pad(",fsT"));
+        msg.extend(0.5f32.to_be_bytes());
+        msg.extend(pad("ignored"));
+        assert_eq!(
+            decode(&msg),
+            [ControlEvent::Osc {
+                address: "/1/fader1".into(),
+                args: vec![OscArg::Float(0.5), OscArg::Bool(true)],
+            }]
+        );
+
+        let mut bundle = pad("#bundle");
+        bundle.extend([0u8; 8]);
+        bundle.extend((msg.len() as i32).to_be_bytes());
+        bundle.extend(&msg);
+        bundle.extend((msg.len() as i32).to_be_bytes());
+        bundle.extend(&msg);
+        assert_eq!(decode(&bundle).len(), 2);
+
+        // Truncated argument data.
+        assert!(decode(&msg[..msg.len() - 12]).is_empty());
+    }
+}
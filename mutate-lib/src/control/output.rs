@@ -0,0 +1,533 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # MIDI Output
+//!
+//! The reverse of the [mapping table](super::MappingTable).  Hardware synths and lighting
+//! controllers follow what is heard through MIDI clock, notes on beats, and notes or CCs from the
+//! energy of frequency bands.  [`MidiOutput`] turns analysis into [`MidiMessage`]s and leaves
+//! sending them to the caller, such as a [`MidiSender`](super::midi::MidiSender).
+//!
+//! ```toml
+//! clock = true           # 24 pulses per beat, with start and stop
+//!
+//! [[beat]]
+//! channel = 10           # 1-16
+//! note = 36
+//! velocity = 100         # optional, scaled by the beat's confidence
+//!
+//! [[band]]
+//! low = 20.0             # Hz
+//! high = 120.0
+//! channel = 1
+//! cc = 20                # the band's level from `floor_db` to 0 dB as 0-127
+//! floor_db = -60.0       # optional
+//!
+//! [[band]]
+//! low = 2000.0
+//! high = 8000.0
+//! channel = 1
+//! note = 60              # held while the band is over `threshold_db`
+//! threshold_db = -30.0
+//! ```
+//!
+//! All times are on the caller's clock, the same one given to the
+//! [`BeatPredictor`](crate::dsp::beat::BeatPredictor).
+
+// MAYBE clock pulses are only as even as the calls to `tick`.  At 60 calls per second, a 24 PPQN
+// clock at 150 BPM asks for one pulse per call and jitters by up to a frame.  Sending from a timer
+// thread with midir's timestamps would smooth it.
+
+use std::path::Path;
+
+use super::number;
+use crate::dsp::beat::{BeatEvent, BeatPhase};
+use crate::dsp::units::Seconds;
+use crate::MutateError;
+
+/// MIDI clock pulses per quarter note.
+pub const CLOCK_PPQN: u32 = 24;
+/// How long beat notes are held.  Drum modules ignore note-offs, but lighting desks do not.
+const NOTE_LENGTH: Seconds = Seconds(0.05);
+/// How far a band must fall below its threshold before its note is released.
+const HYSTERESIS_DB: f64 = 3.0;
+const DEFAULT_VELOCITY: u8 = 100;
+const DEFAULT_FLOOR_DB: f64 = -60.0;
+
+/// One outgoing message.  `channel` is `1..=16` as printed on hardware.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MidiMessage {
+    NoteOn {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        channel: u8,
+        note: u8,
+    },
+    Cc {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    Clock,
+    Start,
+    Stop,
+}
+
+impl MidiMessage {
+    /// The message on the wire.
+    pub fn bytes(&self) -> Vec<u8> {
+        let status = |kind: u8, channel: u8| kind | (channel.clamp(1, 16) - 1);
+        match *self {
+            MidiMessage::NoteOn {
+                channel,
+                note,
+                velocity,
+            } => vec![status(0x90, channel), note & 0x7F, velocity & 0x7F],
+            MidiMessage::NoteOff { channel, note } => vec![status(0x80, channel), note & 0x7F, 0],
+            MidiMessage::Cc {
+                channel,
+                controller,
+                value,
+            } => vec![status(0xB0, channel), controller & 0x7F, value & 0x7F],
+            MidiMessage::Clock => vec![0xF8],
+            MidiMessage::Start => vec![0xFA],
+            MidiMessage::Stop => vec![0xFC],
+        }
+    }
+}
+
+/// A note played on every beat.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeatNote {
+    pub channel: u8,
+    pub note: u8,
+    /// Velocity of a fully confident beat.
+    pub velocity: u8,
+}
+
+/// What a band sends as its level changes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BandAction {
+    /// The level from `floor_db` to 0 dB, as `0..=127`.
+    Cc { controller: u8, floor_db: f64 },
+    /// A note held while the level is over `threshold_db`.
+    Note {
+        note: u8,
+        velocity: u8,
+        threshold_db: f64,
+    },
+}
+
+/// Energy between `low` and `high` Hz, taken from the loudest bin between them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BandOut {
+    pub low: f64,
+    pub high: f64,
+    pub channel: u8,
+    pub action: BandAction,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutputMap {
+    pub clock: bool,
+    pub beats: Vec<BeatNote>,
+    pub bands: Vec<BandOut>,
+}
+
+impl OutputMap {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MutateError> {
+        let text = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            MutateError::ControlMapping(format!("{}: {e}", path.as_ref().display()))
+        })?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, MutateError> {
+        let bad = |msg: String| MutateError::ControlMapping(msg);
+        let table: toml::Table = text.parse().map_err(|e| bad(format!("{e}")))?;
+
+        let mut map = Self::default();
+        for (key, value) in &table {
+            if key == "clock" {
+                map.clock = value
+                    .as_bool()
+                    .ok_or_else(|| bad("`clock` must be true or false".into()))?;
+                continue;
+            }
+            let entries = value
+                .as_array()
+                .ok_or_else(|| bad(format!("`{key}` must be an array of tables")))?;
+            for entry in entries {
+                let entry = entry
+                    .as_table()
+                    .ok_or_else(|| bad(format!("`{key}` entries must be tables")))?;
+                let byte = |name: &str| match entry.get(name).map(|v| v.as_integer()) {
+                    Some(Some(v @ 0..=127)) => Ok(Some(v as u8)),
+                    Some(_) => Err(bad(format!("{key} `{name}` must be in 0..=127"))),
+                    None => Ok(None),
+                };
+                let channel = match entry.get("channel").and_then(|v| v.as_integer()) {
+                    Some(ch @ 1..=16) => ch as u8,
+                    Some(ch) => return Err(bad(format!("midi channel {ch} not in 1..=16"))),
+                    None => return Err(bad(format!("{key} output needs a `channel`"))),
+                };
+                let velocity = byte("velocity")?.unwrap_or(DEFAULT_VELOCITY);
+                let db = |name: &str, default: Option<f64>| match entry.get(name).map(number) {
+                    Some(Some(db)) if db <= 0.0 => Ok(db),
+                    Some(_) => Err(bad(format!("{key} `{name}` must be 0 dB or less"))),
+                    None => default.ok_or_else(|| bad(format!("{key} note needs `{name}`"))),
+                };
+                match key.as_str() {
+                    "beat" => {
+                        let note = byte("note")?
+                            .ok_or_else(|| bad("beat output needs a `note`".into()))?;
+                        map.beats.push(BeatNote {
+                            channel,
+                            note,
+                            velocity,
+                        });
+                    }
+                    "band" => {
+                        let hz = |name: &str| {
+                            entry
+                                .get(name)
+                                .and_then(number)
+                                .filter(|hz| *hz >= 0.0)
+                                .ok_or_else(|| bad(format!("band output needs `{name}` in Hz")))
+                        };
+                        let (low, high) = (hz("low")?, hz("high")?);
+                        if low >= high {
+                            return Err(bad(format!("band {low}-{high} Hz is empty")));
+                        }
+                        let action = match (byte("cc")?, byte("note")?) {
+                            (Some(controller), None) => BandAction::Cc {
+                                controller,
+                                floor_db: db("floor_db", Some(DEFAULT_FLOOR_DB))?,
+                            },
+                            (None, Some(note)) => BandAction::Note {
+                                note,
+                                velocity,
+                                threshold_db: db("threshold_db", None)?,
+                            },
+                            _ => return Err(bad("band output needs one of `cc` or `note`".into())),
+                        };
+                        map.bands.push(BandOut {
+                            low,
+                            high,
+                            channel,
+                            action,
+                        });
+                    }
+                    other => return Err(bad(format!("unknown output kind `{other}`"))),
+                }
+            }
+        }
+        Ok(map)
+    }
+}
+
+/// Turns beats, beat phase, and band levels into MIDI according to an [`OutputMap`].
+pub struct MidiOutput {
+    map: OutputMap,
+    /// Beat notes to release, and when.
+    releases: Vec<(Seconds, u8, u8)>,
+    /// Pulse within the beat that was last sent while the clock runs.
+    pulse: Option<u32>,
+    /// Last value sent per band, a CC value or whether the note is held.
+    bands: Vec<Option<u8>>,
+}
+
+impl MidiOutput {
+    pub fn new(map: OutputMap) -> Self {
+        let bands = vec![None; map.bands.len()];
+        Self {
+            map,
+            releases: Vec::new(),
+            pulse: None,
+            bands,
+        }
+    }
+
+    pub fn map(&self) -> &OutputMap {
+        &self.map
+    }
+
+    /// Play the beat notes for `event`.  Send at `event.fire_at`, which already leads the beat by the
+    /// output latency.
+    pub fn beat(&mut self, now: Seconds, event: &BeatEvent, out: &mut Vec<MidiMessage>) {
+        for beat in &self.map.beats {
+            let velocity = (beat.velocity as f64 * event.confidence.clamp(0.0, 1.0)).round();
+            out.push(MidiMessage::NoteOn {
+                channel: beat.channel,
+                note: beat.note,
+                velocity: (velocity as u8).max(1),
+            });
+            self.releases
+                .push((now + NOTE_LENGTH, beat.channel, beat.note));
+        }
+    }
+
+    /// Release beat notes that are due and advance the clock.  Call often, at least once per
+    /// analysis frame.  The clock runs while `phase` is known and stops when it is lost.
+    pub fn tick(&mut self, now: Seconds, phase: Option<BeatPhase>, out: &mut Vec<MidiMessage>) {
+        self.releases.retain(|&(at, channel, note)| {
+            let due = at <= now;
+            if due {
+                out.push(MidiMessage::NoteOff { channel, note });
+            }
+            !due
+        });
+        if !self.map.clock {
+            return;
+        }
+        match (phase, self.pulse) {
+            (Some(phase), None) => {
+                out.push(MidiMessage::Start);
+                out.push(MidiMessage::Clock);
+                self.pulse = Some(Self::pulse_of(phase));
+            }
+            (Some(phase), Some(last)) => {
+                let pulse = Self::pulse_of(phase);
+                let due = (pulse + CLOCK_PPQN - last) % CLOCK_PPQN;
+                out.extend((0..due).map(|_| MidiMessage::Clock));
+                self.pulse = Some(pulse);
+            }
+            (None, Some(_)) => {
+                out.push(MidiMessage::Stop);
+                self.pulse = None;
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn pulse_of(phase: BeatPhase) -> u32 {
+        ((phase.phase * CLOCK_PPQN as f64) as u32).min(CLOCK_PPQN - 1)
+    }
+
+    /// Send band levels that changed.  `levels` are amplitudes of the bins at `centers` Hz, as in
+    /// the daemon's frames.
+    pub fn bands(&mut self, centers: &[f32], levels: &[f32], out: &mut Vec<MidiMessage>) {
+        for (band, last) in self.map.bands.iter().zip(self.bands.iter_mut()) {
+            let peak = centers
+                .iter()
+                .zip(levels)
+                .filter(|(hz, _)| (band.low..=band.high).contains(&(**hz as f64)))
+                .map(|(_, level)| *level as f64)
+                .fold(0.0, f64::max);
+            let db = 20.0 * peak.max(1e-10).log10();
+            match band.action {
+                BandAction::Cc {
+                    controller,
+                    floor_db,
+                } => {
+                    let value = ((db - floor_db) / -floor_db).clamp(0.0, 1.0) * 127.0;
+                    let value = value.round() as u8;
+                    if *last != Some(value) {
+                        *last = Some(value);
+                        out.push(MidiMessage::Cc {
+                            channel: band.channel,
+                            controller,
+                            value,
+                        });
+                    }
+                }
+                BandAction::Note {
+                    note,
+                    velocity,
+                    threshold_db,
+                } => {
+                    let held = last.is_some();
+                    if !held && db > threshold_db {
+                        *last = Some(note);
+                        out.push(MidiMessage::NoteOn {
+                            channel: band.channel,
+                            note,
+                            velocity,
+                        });
+                    } else if held && db < threshold_db - HYSTERESIS_DB {
+                        *last = None;
+                        out.push(MidiMessage::NoteOff {
+                            channel: band.channel,
+                            note,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Release every held note and stop the clock, such as before exiting or when the caller's clock
+    /// starts over.
+    pub fn stop(&mut self, out: &mut Vec<MidiMessage>) {
+        for (_, channel, note) in self.releases.drain(..) {
+            out.push(MidiMessage::NoteOff { channel, note });
+        }
+        for (band, last) in self.map.bands.iter().zip(self.bands.iter_mut()) {
+            if let (BandAction::Note { note, .. }, Some(_)) = (&band.action, last.take()) {
+                out.push(MidiMessage::NoteOff {
+                    channel: band.channel,
+                    note: *note,
+                });
+            }
+        }
+        if self.pulse.take().is_some() {
+            out.push(MidiMessage::Stop);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAP: &str = r#"
+        clock = true
+
+        [[beat]]
+        channel = 10
+        note = 36
+
+        [[band]]
+        low = 20
+        high = 120
+        channel = 1
+        cc = 20
+
+        [[band]]
+        low = 2000.0
+        high = 8000.0
+        channel = 2
+        note = 60
+        threshold_db = -30.0
+    "#;
+
+    #[test]
+    fn test_output_map() {
+        let map = OutputMap::parse(MAP).unwrap();
+        assert!(map.clock);
+        assert_eq!(
+            map.beats,
+            vec![BeatNote {
+                channel: 10,
+                note: 36,
+                velocity: DEFAULT_VELOCITY
+            }]
+        );
+        assert_eq!(
+            map.bands[0].action,
+            BandAction::Cc {
+                controller: 20,
+                floor_db: DEFAULT_FLOOR_DB
+            }
+        );
+
+        assert!(OutputMap::parse("[[beat]]\nchannel = 0\nnote = 36").is_err());
+        assert!(OutputMap::parse("[[beat]]\nchannel = 1\nnote = 128").is_err());
+        assert!(OutputMap::parse("[[beat]]\nnote = 36").is_err());
+        assert!(OutputMap::parse("[[band]]\nlow = 9\nhigh = 5\nchannel = 1\ncc = 1").is_err());
+        assert!(OutputMap::parse("[[band]]\nlow = 1\nhigh = 5\nchannel = 1").is_err());
+        assert!(OutputMap::parse("[[band]]\nlow = 1\nhigh = 5\nchannel = 1\nnote = 1").is_err());
+        assert!(OutputMap::parse("[[dmx]]\nchannel = 1").is_err());
+        assert!(OutputMap::parse("clock = 1").is_err());
+    }
+
+    #[test]
+    fn test_midi_output() {
+        let mut output = MidiOutput::new(OutputMap::parse(MAP).unwrap());
+        let mut out = Vec::new();
+
+        let event = BeatEvent {
+            fire_at: Seconds(1.0),
+            beat_at: Seconds(1.02),
+            confidence: 0.5,
+        };
+        output.beat(Seconds(1.0), &event, &mut out);
+        assert_eq!(
+            std::mem::take(&mut out),
+            vec![MidiMessage::NoteOn {
+                channel: 10,
+                note: 36,
+                velocity: 50
+            }]
+        );
+        output.tick(Seconds(1.01), None, &mut out);
+        assert!(out.is_empty());
+        output.tick(Seconds(1.1), None, &mut out);
+        assert_eq!(
+            std::mem::take(&mut out),
+            vec![MidiMessage::NoteOff {
+                channel: 10,
+                note: 36
+            }]
+        );
+
+        // Start on the first phase, then one pulse per 1/24 of a beat, across the wrap.
+        let phase = |phase| {
+            Some(BeatPhase {
+                period: Seconds(0.5),
+                phase,
+            })
+        };
+        output.tick(Seconds(2.0), phase(0.0), &mut out);
+        assert_eq!(out, vec![MidiMessage::Start, MidiMessage::Clock]);
+        out.clear();
+        output.tick(Seconds(2.1), phase(0.2), &mut out);
+        assert_eq!(out.len(), 4);
+        out.clear();
+        output.tick(Seconds(2.5), phase(0.05), &mut out);
+        assert_eq!(out.len(), 21);
+        out.clear();
+        output.tick(Seconds(2.6), None, &mut out);
+        assert_eq!(out, vec![MidiMessage::Stop]);
+        out.clear();
+
+        let centers = [50.0, 100.0, 4000.0];
+        output.bands(&centers, &[0.001, 0.1, 0.1], &mut out);
+        assert_eq!(
+            std::mem::take(&mut out),
+            vec![
+                MidiMessage::Cc {
+                    channel: 1,
+                    controller: 20,
+                    value: 85
+                },
+                MidiMessage::NoteOn {
+                    channel: 2,
+                    note: 60,
+                    velocity: DEFAULT_VELOCITY
+                },
+            ]
+        );
+        // Unchanged levels send nothing, and the note holds within the hysteresis.
+        output.bands(&centers, &[0.001, 0.1, 0.03], &mut out);
+        assert!(out.is_empty());
+        output.stop(&mut out);
+        assert_eq!(
+            out,
+            vec![MidiMessage::NoteOff {
+                channel: 2,
+                note: 60
+            }]
+        );
+    }
+
+    #[test]
+    fn test_midi_message_bytes() {
+        let on = MidiMessage::NoteOn {
+            channel: 10,
+            note: 36,
+            velocity: 100,
+        };
+        assert_eq!(on.bytes(), vec![0x99, 36, 100]);
+        let cc = MidiMessage::Cc {
+            channel: 1,
+            controller: 20,
+            value: 127,
+        };
+        assert_eq!(cc.bytes(), vec![0xB0, 20, 127]);
+        assert_eq!(MidiMessage::Clock.bytes(), vec![0xF8]);
+    }
+}
@@ -0,0 +1,592 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Daemon
+//!
+//! Applications that cannot embed this crate, such as OBS scripts and lighting rigs, read the
+//! analysis from the `mutate-daemon` binary instead.  It listens to one source and publishes an
+//! [`AnalysisFrame`] for every `1 / fps` seconds of audio to each client of a Unix socket, one JSON
+//! object per line.  Nothing here touches the GPU.
+//!
+//! The socket is `mutate/analysis.sock` in `$XDG_RUNTIME_DIR` unless given.  Clients read a `hello`
+//! line first and then `frame` and `beat` lines.  Another `hello` follows whenever the source's
+//! format changes, since the bins are designed for the sample rate.
+//!
+//! ```text
+//! {"kind":"hello","version":1,"source":"Firefox","rate":48000,"channels":2,"fps":60.0,"centers":[24.0,25.63,...]}
+//! {"kind":"frame","t":1.25,"rms":[0.121,0.1187],"peak":[0.2174,0.2051],"lufs":{"momentary":-14.21,"short_term":-15.02,"integrated":null},"bins":[0.0012,...],"beat":{"bpm":120.0,"phase":0.4833,"confidence":0.93,"next":1.5083}}
+//! {"kind":"beat","t":1.2667,"bpm":120.0,"confidence":0.94}
+//! ```
+//!
+//! - `t` seconds of audio since the last `hello`
+//! - `rms` per channel over the frame, where 1.0 is full scale
+//! - `peak` the [true peak](crate::dsp::peak) per channel over the frame, on the same scale
+//! - `lufs` see [`LoudnessMeter`].  `null` until enough audio was measured, or in silence.
+//! - `bins` amplitudes of the filter bank at the hello's `centers` in Hz, lowest first
+//! - `beat` the tempo, where `t` falls in the beat from 0.0 on the beat, how well recent beats agree
+//!   with the tempo, and the `t` of the next beat.  `null` until they agree well enough, see
+//!   [`BeatPredictor`].
+//!
+//! A `beat` line follows the frame that a beat was heard in, whether or not it agreed with the
+//! tempo, so that clients may gate on `confidence` themselves.  Beats are reported as heard.
+//! Clients that play or draw them early enough to land on the beat lead by their own latency.  The
+//! daemon's own MIDI output leads by the latency given to [`Analyzer::set_latency`].
+//!
+//! Numbers are rounded to a few places.  Anything that is not a number, such as the bins of a
+//! broken source, is `null`.
+//!
+//! A client that stops reading is disconnected once its backlog passes [`MAX_BACKLOG`], so that a
+//! stuck client cannot stall the analysis.
+//!
+//! ```sh
+//! socat - UNIX-CONNECT:$XDG_RUNTIME_DIR/mutate/analysis.sock
+//! ```
+
+// MAYBE protobuf for clients that read many bins at high frame rates.
+
+use std::io::{self, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Number, Value};
+
+use crate::dsp::bank::BankTable;
+use crate::dsp::beat::{BeatEvent, BeatPhase, BeatPredictor, BeatTracker, TrackedBeat};
+use crate::dsp::loudness::LoudnessMeter;
+use crate::dsp::peak::TruePeak;
+use crate::dsp::spectrogram::Spectrogram;
+use crate::dsp::units::{SampleRate, Samples, Seconds};
+use crate::MutateError;
+
+/// Bumped whenever a field changes meaning or goes away.  New fields do not bump it.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Bytes a client may fall behind before it is disconnected.
+pub const MAX_BACKLOG: usize = 1 << 20;
+const SOCKET_DIR: &str = "mutate";
+const SOCKET_FILE: &str = "analysis.sock";
+
+/// `mutate/analysis.sock` in `$XDG_RUNTIME_DIR`, or in the temporary directory without one.
+pub fn default_socket() -> PathBuf {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|d| d.is_absolute())
+        .unwrap_or_else(std::env::temp_dir);
+    dir.join(SOCKET_DIR).join(SOCKET_FILE)
+}
+
+/// One frame of analysis.  See the [module](self) docs for the fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnalysisFrame<'a> {
+    pub t: Seconds,
+    pub rms: &'a [f32],
+    pub peak: &'a [f32],
+    pub momentary: Option<f64>,
+    pub short_term: Option<f64>,
+    pub integrated: Option<f64>,
+    pub bins: &'a [f32],
+    /// Where `t` falls in the beat.  `None` until the beats agree.
+    pub phase: Option<BeatPhase>,
+    /// The next beat.  `None` until the beats agree.
+    pub next: Option<BeatEvent>,
+    /// A beat heard in this frame, and the confidence after it.
+    pub heard: Option<(TrackedBeat, f64)>,
+}
+
+impl AnalysisFrame<'_> {
+    /// The frame as a line of JSON, without the newline.
+    pub fn to_json(&self) -> String {
+        let lufs = |x: Option<f64>| x.map_or(Value::Null, |x| number(x, 2));
+        let beat = match (self.phase, self.next) {
+            (Some(phase), Some(next)) => json!({
+                "bpm": number(60.0 / phase.period.get(), 2),
+                "phase": number(phase.phase, 4),
+                "confidence": number(next.confidence, 2),
+                "next": number(next.beat_at.get(), 4),
+            }),
+            _ => Value::Null,
+        };
+        json!({
+            "kind": "frame",
+            "t": number(self.t.get(), 4),
+            "rms": list(self.rms, 4),
+            "peak": list(self.peak, 4),
+            "lufs": {
+                "momentary": lufs(self.momentary),
+                "short_term": lufs(self.short_term),
+                "integrated": lufs(self.integrated),
+            },
+            "bins": list(self.bins, 4),
+            "beat": beat,
+        })
+        .to_string()
+    }
+
+    /// The beat heard in this frame as a line of JSON, without the newline.
+    pub fn beat_json(&self) -> Option<String> {
+        let (beat, confidence) = self.heard?;
+        let line = json!({
+            "kind": "beat",
+            "t": number(beat.at.get(), 4),
+            "bpm": number(beat.bpm, 2),
+            "confidence": number(confidence, 2),
+        });
+        Some(line.to_string())
+    }
+}
+
+/// Analysis that [`Publisher`] clients receive, for one stream format.
+pub struct Analyzer {
+    fs: SampleRate,
+    channels: usize,
+    fps: f64,
+    /// Frames per analysis frame.
+    hop: usize,
+    /// Frames since the last analysis frame.
+    since: usize,
+    /// Frames analyzed.
+    total: u64,
+    squares: Vec<f64>,
+    rms: Vec<f32>,
+    detectors: Vec<TruePeak>,
+    peak: Vec<f32>,
+    meter: LoudnessMeter,
+    spectrogram: Spectrogram,
+    /// Center of each spectrogram column, in Hz.
+    centers: Vec<f32>,
+    tracker: BeatTracker,
+    /// Leads by [`set_latency`](Self::set_latency).  Only fire times move, so clients that read
+    /// beat times compensate their own.
+    predictor: BeatPredictor,
+}
+
+impl Analyzer {
+    /// Analyze `channels` interleaved channels at the rate of `table`, `fps` times per second of
+    /// audio.
+    pub fn new(table: BankTable, channels: usize, fps: f64) -> Self {
+        let fs = SampleRate(table.sample_rate as f64);
+        let channels = channels.max(1);
+        let hop = (fs.get() / fps.max(1.0)).round().max(1.0) as usize;
+        let mut centers: Vec<f32> = Vec::new();
+        for bin in &table.bins {
+            let output = bin.output as usize;
+            if centers.len() <= output {
+                centers.resize(output + 1, 0.0);
+            }
+            centers[output] = bin.center;
+        }
+        let spectrogram = Spectrogram::new(table, Samples(hop))
+            .with_channels(channels)
+            .without_rows();
+        let fps = fs.get() / hop as f64;
+        Self {
+            fs,
+            channels,
+            fps,
+            hop,
+            since: 0,
+            total: 0,
+            squares: vec![0.0; channels],
+            rms: vec![0.0; channels],
+            detectors: vec![TruePeak::new(); channels],
+            peak: vec![0.0; channels],
+            meter: LoudnessMeter::new(fs, channels),
+            spectrogram,
+            centers,
+            tracker: BeatTracker::new(fps),
+            predictor: BeatPredictor::new(Seconds(0.0)),
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> SampleRate {
+        self.fs
+    }
+
+    /// Fire predicted beats `latency` early, such as the delay of the audio read plus the time an
+    /// output takes to sound.  [`AnalysisFrame::next`] carries the fire time.
+    pub fn set_latency(&mut self, latency: Seconds) {
+        self.predictor.set_latency(latency);
+    }
+
+    /// Center frequencies of the bins in Hz, lowest first.
+    pub fn centers(&self) -> &[f32] {
+        &self.centers
+    }
+
+    /// Analysis frames per second, after rounding to whole audio frames.
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+
+    /// The line each client reads before any frame, naming the `source` analyzed.
+    pub fn hello(&self, source: &str) -> String {
+        json!({
+            "kind": "hello",
+            "version": PROTOCOL_VERSION,
+            "source": source,
+            "rate": self.fs.get().round() as u64,
+            "channels": self.channels,
+            "fps": number(self.fps, 2),
+            "centers": list(&self.centers, 2),
+        })
+        .to_string()
+    }
+
+    /// Feed interleaved frames, calling `each` every time a frame of analysis completes.
+    pub fn push(&mut self, frames: &[f32], mut each: impl FnMut(&AnalysisFrame)) {
+        let mut rest = &frames[..frames.len() - frames.len() % self.channels];
+        while !rest.is_empty() {
+            let take = ((self.hop - self.since) * self.channels).min(rest.len());
+            let (chunk, tail) = rest.split_at(take);
+            for frame in chunk.chunks_exact(self.channels) {
+                for ((sum, detector), &x) in
+                    self.squares.iter_mut().zip(&mut self.detectors).zip(frame)
+                {
+                    *sum += x as f64 * x as f64;
+                    detector.push(x);
+                }
+            }
+            self.meter.push(chunk);
+            self.spectrogram.push(chunk);
+            self.since += chunk.len() / self.channels;
+            self.total += (chunk.len() / self.channels) as u64;
+            if self.since == self.hop {
+                for (rms, sum) in self.rms.iter_mut().zip(&mut self.squares) {
+                    *rms = (*sum / self.hop as f64).sqrt() as f32;
+                    *sum = 0.0;
+                }
+                for (peak, detector) in self.peak.iter_mut().zip(&mut self.detectors) {
+                    *peak = detector.peak();
+                    detector.reset();
+                }
+                self.since = 0;
+                let t = Seconds(self.total as f64 / self.fs.get());
+                let bins = self.spectrogram.latest();
+                let heard = self.tracker.push(t, bins).map(|beat| {
+                    self.predictor.observe(beat.at, beat.bpm);
+                    (beat, self.predictor.confidence())
+                });
+                each(&AnalysisFrame {
+                    t,
+                    rms: &self.rms,
+                    peak: &self.peak,
+                    momentary: self.meter.momentary(),
+                    short_term: self.meter.short_term(),
+                    integrated: self.meter.integrated(),
+                    bins,
+                    phase: self.predictor.phase(t),
+                    next: self.predictor.schedule(t),
+                    heard,
+                });
+            }
+            rest = tail;
+        }
+    }
+}
+
+/// A client and the bytes it has yet to read.
+struct Client {
+    stream: UnixStream,
+    backlog: Vec<u8>,
+}
+
+impl Client {
+    /// Queue `bytes` and write what the socket takes.  `false` once the client should be dropped.
+    fn send(&mut self, bytes: &[u8]) -> bool {
+        if self.backlog.len() + bytes.len() > MAX_BACKLOG {
+            return false;
+        }
+        self.backlog.extend_from_slice(bytes);
+        match self.stream.write(&self.backlog) {
+            Ok(written) => {
+                self.backlog.drain(..written);
+                true
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => true,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Sends lines to every client of a Unix socket without ever blocking.  The socket file is removed
+/// on drop.
+pub struct Publisher {
+    path: PathBuf,
+    listener: UnixListener,
+    clients: Vec<Client>,
+    hello: String,
+}
+
+impl Publisher {
+    /// Listen at `path`, creating its directory.  A socket left behind by a daemon that did not
+    /// exit cleanly is replaced, but one that another daemon still answers is an error.
+    pub fn bind(path: &Path) -> Result<Self, MutateError> {
+        let error = |e: io::Error| MutateError::Daemon(format!("{}: {e}", path.display()));
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(error)?;
+        }
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(MutateError::Daemon(format!(
+                    "{}: another daemon is listening",
+                    path.display()
+                )));
+            }
+            std::fs::remove_file(path).map_err(error)?;
+        }
+        let listener = UnixListener::bind(path).map_err(error)?;
+        listener.set_nonblocking(true).map_err(error)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            listener,
+            clients: Vec::new(),
+            hello: String::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Clients connected as of the last [`publish`](Self::publish).
+    pub fn clients(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Set the line new clients read first and send it to the current ones.
+    pub fn set_hello(&mut self, line: String) {
+        self.broadcast(&line);
+        self.hello = line;
+    }
+
+    /// Accept waiting clients and send `line` to every client.
+    pub fn publish(&mut self, line: &str) {
+        self.accept();
+        self.broadcast(line);
+    }
+
+    fn accept(&mut self) {
+        loop {
+            let stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return,
+                Err(e) => {
+                    eprintln!("daemon: accept failed {e}");
+                    return;
+                }
+            };
+            if stream.set_nonblocking(true).is_err() {
+                continue;
+            }
+            let mut client = Client {
+                stream,
+                backlog: Vec::new(),
+            };
+            if self.hello.is_empty() || client.send(format!("{}\n", self.hello).as_bytes()) {
+                self.clients.push(client);
+            }
+        }
+    }
+
+    fn broadcast(&mut self, line: &str) {
+        let bytes = format!("{line}\n");
+        self.clients.retain_mut(|c| c.send(bytes.as_bytes()));
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// `x` rounded to `decimals` places, or `null` when it is not a finite number.
+fn number(x: f64, decimals: i32) -> Value {
+    let scale = 10f64.powi(decimals);
+    Number::from_f64((x * scale).round() / scale).map_or(Value::Null, Value::Number)
+}
+
+/// A JSON array of `values` rounded to `decimals` places.
+fn list(values: &[f32], decimals: i32) -> Value {
+    values.iter().map(|&x| number(x as f64, decimals)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader};
+
+    use super::*;
+    use crate::dsp::SineSweeper;
+    use crate::settings::DspSettings;
+
+    #[test]
+    fn test_daemon_analyzer() {
+        let fs = SampleRate(48_000.0);
+        let dsp = DspSettings {
+            bins: 32,
+            ..Default::default()
+        };
+        let table = dsp.design_table(fs);
+        let target = 16;
+        let center = table.bins[target].center as f64;
+        let mut analyzer = Analyzer::new(table, 2, 60.0);
+        assert_eq!(analyzer.fps(), 60.0);
+
+        // Half scale in the left channel only, for one second.
+        let frames: Vec<f32> = SineSweeper::new(center, fs.get())
+            .take(fs.get() as usize)
+            .flat_map(|x| [0.5 * x, 0.0])
+            .collect();
+        let mut count = 0;
+        let mut last = None;
+        for block in frames.chunks(1000) {
+            analyzer.push(block, |frame| {
+                count += 1;
+                last = Some((
+                    frame.t,
+                    frame.rms.to_vec(),
+                    frame.peak.to_vec(),
+                    frame.bins.to_vec(),
+                    frame.momentary,
+                ));
+            });
+        }
+        assert_eq!(count, 60);
+        let (t, rms, peak, bins, momentary) = last.unwrap();
+        assert_eq!(t, Seconds(1.0));
+        assert!((rms[0] - 0.5 / 2f32.sqrt()).abs() < 0.01, "{}", rms[0]);
+        assert_eq!(rms[1], 0.0);
+        assert!((peak[0] - 0.5).abs() < 0.01, "{}", peak[0]);
+        assert_eq!(peak[1], 0.0);
+        assert_eq!(bins.len(), 32);
+        assert!(bins[target] > 10.0 * bins[target + 4], "{bins:?}");
+        assert!(momentary.is_some());
+        assert!(analyzer.hello("sine").starts_with(
+            r#"{"kind":"hello","version":1,"source":"sine","rate":48000,"channels":2,"fps":60.0,"centers":["#
+        ));
+        assert!(analyzer
+            .hello(r#"a "quoted" name"#)
+            .contains(r#""source":"a \"quoted\" name""#));
+    }
+
+    #[test]
+    fn test_daemon_beats() {
+        let fs = SampleRate(48_000.0);
+        let dsp = DspSettings {
+            bins: 32,
+            ..Default::default()
+        };
+        let mut analyzer = Analyzer::new(dsp.design_table(fs), 1, 60.0);
+
+        // Decaying bursts of noise at 120 BPM for twelve seconds.
+        let mut seed = 1u32;
+        let frames: Vec<f32> = (0..12 * 48_000)
+            .map(|n| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let noise = (seed >> 8) as f32 / (1 << 23) as f32 - 1.0;
+                let since = (n % 24_000) as f32 / fs.get() as f32;
+                noise * (-since * 40.0).exp()
+            })
+            .collect();
+        let mut heard = Vec::new();
+        let mut last = None;
+        for block in frames.chunks(1000) {
+            analyzer.push(block, |frame| {
+                heard.extend(frame.beat_json());
+                last = Some((frame.phase, frame.next));
+            });
+        }
+        assert!(heard.len() > 8, "{heard:?}");
+        assert!(heard[0].starts_with(r#"{"kind":"beat","t":"#));
+        let (phase, next) = last.unwrap();
+        let period = phase.unwrap().period.get();
+        assert!((period - 0.5).abs() < 0.01, "{period}");
+        let next = next.unwrap();
+        assert!(next.confidence > 0.9);
+        assert_eq!(next.fire_at, next.beat_at);
+
+        // Latency moves fire times and leaves beat times alone.
+        analyzer.set_latency(Seconds(0.1));
+        let mut led = None;
+        analyzer.push(&frames[..1000], |frame| led = frame.next);
+        let led = led.unwrap();
+        assert!((led.beat_at - led.fire_at - Seconds(0.1)).get().abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_daemon_frame_json() {
+        let frame = AnalysisFrame {
+            t: Seconds(1.25),
+            rms: &[0.5, 0.25],
+            peak: &[0.7071, 0.5],
+            momentary: Some(-14.004),
+            short_term: Some(f64::NEG_INFINITY),
+            integrated: None,
+            bins: &[0.125, f32::NAN],
+            phase: None,
+            next: None,
+            heard: None,
+        };
+        assert_eq!(
+            frame.to_json(),
+            r#"{"kind":"frame","t":1.25,"rms":[0.5,0.25],"peak":[0.7071,0.5],"lufs":{"momentary":-14.0,"short_term":null,"integrated":null},"bins":[0.125,null],"beat":null}"#
+        );
+        assert_eq!(frame.beat_json(), None);
+
+        let beat = AnalysisFrame {
+            phase: Some(BeatPhase {
+                period: Seconds(0.5),
+                phase: 0.25,
+            }),
+            next: Some(BeatEvent {
+                fire_at: Seconds(1.375),
+                beat_at: Seconds(1.375),
+                confidence: 0.875,
+            }),
+            heard: Some((
+                TrackedBeat {
+                    at: Seconds(1.2333),
+                    bpm: 120.0,
+                },
+                0.875,
+            )),
+            ..frame
+        };
+        assert!(beat
+            .to_json()
+            .ends_with(r#""beat":{"bpm":120.0,"phase":0.25,"confidence":0.88,"next":1.375}}"#));
+        assert_eq!(
+            beat.beat_json().unwrap(),
+            r#"{"kind":"beat","t":1.2333,"bpm":120.0,"confidence":0.88}"#
+        );
+    }
+
+    #[test]
+    fn test_daemon_publisher() {
+        let dir = std::env::temp_dir().join(format!("mutate-daemon-{}", std::process::id()));
+        let path = dir.join(SOCKET_FILE);
+        let mut publisher = Publisher::bind(&path).unwrap();
+        assert!(Publisher::bind(&path).is_err());
+        publisher.set_hello("hello".to_owned());
+
+        let client = UnixStream::connect(&path).unwrap();
+        publisher.publish("one");
+        assert_eq!(publisher.clients(), 1);
+        publisher.set_hello("again".to_owned());
+        drop(publisher);
+        assert!(!path.exists());
+
+        let lines: Vec<String> = BufReader::new(client).lines().map(Result::unwrap).collect();
+        assert_eq!(lines, ["hello", "one", "again"]);
+
+        // A socket nobody answers is left over from a crash and is replaced.
+        let stale = UnixListener::bind(&path).unwrap();
+        drop(stale);
+        assert!(path.exists());
+        drop(Publisher::bind(&path).unwrap());
+        std::fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -9,16 +9,39 @@
 //! Certain design goals will always pull us towards multiple sub-banks and multiple output time
 //! slots.  This module contains the data structures necessary to describe our bank so that it may
 //! be hardcoded into GPU control logic for execution.
+//!
+//! ## Tables
+//!
+//! A [`BankTable`] is what the GPU consumes: one record per bin plus a pool of window weights that
+//! bins share by offset.  [`BankTable::to_bytes`] writes it little-endian with a fixed header and
+//! 32 byte records so that it can be uploaded as is.  [`BankTable::to_json`] is for reading it.
+//!
+//! | offset | field                                                |
+//! |--------|------------------------------------------------------|
+//! | 0      | magic `MTBK`                                         |
+//! | 4      | version, `u32`                                       |
+//! | 8      | sample rate, `f32`                                   |
+//! | 12     | bin count, `u32`                                     |
+//! | 16     | weight count, `u32`                                  |
+//! | 20     | reserved, zero                                       |
+//! | 32     | [`BankBin`] records in field order, `f32` or `u32`   |
+//! | ...    | weights, `f32`                                       |
 
+use std::collections::HashMap;
+
+use super::dft::Normalization;
 use super::iso226;
+use super::sizing::{Pick, WindowFit};
+use super::units::{Hertz, SampleRate, Seconds};
+use crate::MutateError;
 
 pub struct Bin {
     /// Minimum frequency
-    pub min: f64,
+    pub min: Hertz,
     /// Maximum frequency
-    pub max: f64,
+    pub max: Hertz,
     /// Center frequency
-    pub center: f64,
+    pub center: Hertz,
     /// iso226 gain correction summand (dB).  Add this to the bin measured dB for an
     /// iso-loud perceptually corrected dB.
     pub iso226_gain: f64,
@@ -26,7 +49,7 @@ pub struct Bin {
 
 impl Bin {
     /// Difference between minimum and maximum frequency.
-    pub fn bandwidth(&self) -> f64 {
+    pub fn bandwidth(&self) -> Hertz {
         self.max - self.min
     }
 
@@ -38,7 +61,7 @@ impl Bin {
 
 /// Return a list of bin spacings.  We use logarithmic spacing because it matches music and pretty
 /// closely matches human senses of tones.
-pub fn bins(min: f64, max: f64, count: usize) -> Vec<Bin> {
+pub fn bins(min: Hertz, max: Hertz, count: usize) -> Vec<Bin> {
     assert!(max > min);
     assert!(count > 1);
 
@@ -81,13 +104,13 @@ pub fn bins(min: f64, max: f64, count: usize) -> Vec<Bin> {
                 min: freq(i0),
                 center,
                 max: freq(i0 + 2),
-                iso226_gain: iso226::iso226_gain(center).unwrap(),
+                iso226_gain: iso226::iso226_gain(center.get()).unwrap(),
             }
         })
         .collect()
 }
 
-pub fn bin_lookup(min: f64, max: f64, count: usize, center: f64) -> Bin {
+pub fn bin_lookup(min: Hertz, max: Hertz, count: usize, center: Hertz) -> Bin {
     let mut bins = bins(min, max, count);
     let mut closest = 0usize;
     let mut min_dist = f64::MAX;
@@ -102,6 +125,234 @@ pub fn bin_lookup(min: f64, max: f64, count: usize, center: f64) -> Bin {
     bins.remove(closest)
 }
 
+/// Leading bytes of every table.
+pub const BANK_TABLE_MAGIC: &[u8; 4] = b"MTBK";
+/// Bumped whenever the layout changes.
+pub const BANK_TABLE_VERSION: u32 = 1;
+/// Header bytes, padded so that records start 16 byte aligned.
+const HEADER_BYTES: usize = 32;
+/// Bytes per [`BankBin`] record.
+const BIN_BYTES: usize = 32;
+/// Largest input decimation.  Low bins could go further, but the anti-aliasing filters get long.
+pub const MAX_DECIMATION: u32 = 64;
+/// Decimated rates keep at least this many samples per cycle of a bin's upper edge, leaving room
+/// for the anti-aliasing transition band above it.
+const DECIMATION_HEADROOM: f64 = 4.0;
+
+/// One bin as the GPU runs it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BankBin {
+    pub center: f32,
+    /// Predicted Q of the chosen window, which may fall short of the bin's when rise limited.
+    pub q: f32,
+    /// Index of the first weight of this bin's window in [`BankTable::weights`].
+    pub window_offset: u32,
+    /// Window length in decimated samples.
+    pub window_length: u32,
+    /// The bin reads every `decimation`th input sample of the anti-aliased input.
+    pub decimation: u32,
+    /// Decimated samples between window applications.
+    pub repeat: u32,
+    /// Output column this bin writes.
+    pub output: u32,
+    /// Scale from the magnitude of the windowed sum to an amplitude.  See [`Normalization`].
+    pub scale: f32,
+}
+
+/// A whole bank, ready to upload.  See the [module](self) docs for the binary layout.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BankTable {
+    pub sample_rate: f32,
+    pub bins: Vec<BankBin>,
+    /// Windows of every bin, back to back.  Bins with the same window share one copy.
+    pub weights: Vec<f32>,
+}
+
+impl BankTable {
+    /// Lay out `bins` with windows picked by `fit`.  Each bin runs at the lowest rate that still
+    /// has headroom above its upper edge, which keeps low bins' windows short.
+    pub fn design(bins: &[Bin], fit: &WindowFit, fs: SampleRate, max_rise: Seconds) -> Self {
+        Self::design_with(bins, std::slice::from_ref(fit), fs, |bin, rate| {
+            (0, fit.pick(bin, rate, max_rise))
+        })
+    }
+
+    /// Lay out `bins`, letting `choose` pick one of `fits` and its length for each bin at the bin's
+    /// decimated rate.
+    pub fn design_with(
+        bins: &[Bin],
+        fits: &[WindowFit],
+        fs: SampleRate,
+        mut choose: impl FnMut(&Bin, SampleRate) -> (usize, Pick),
+    ) -> Self {
+        let mut table = Self {
+            sample_rate: fs.get() as f32,
+            ..Self::default()
+        };
+        // Windows are shared by fit and length.
+        let mut windows: HashMap<(usize, usize), u32> = HashMap::new();
+        for (i, bin) in bins.iter().enumerate() {
+            let decimation = decimation(bin.max, fs);
+            let rate = SampleRate(fs.get() / decimation as f64);
+            let (choice, pick) = choose(bin, rate);
+            let window = fits[choice].window;
+            let length = pick.length;
+            let window_offset = *windows.entry((choice, length)).or_insert_with(|| {
+                let offset = table.weights.len() as u32;
+                table.weights.extend(window.make_window_32(length));
+                offset
+            });
+            table.bins.push(BankBin {
+                center: bin.center.get() as f32,
+                q: pick.q as f32,
+                window_offset,
+                window_length: length as u32,
+                decimation,
+                repeat: window.repeat(length),
+                output: i as u32,
+                scale: Normalization::Amplitude.scale(&window, length),
+            });
+        }
+        table
+    }
+
+    /// Weights of one bin's window.
+    pub fn window(&self, bin: &BankBin) -> &[f32] {
+        let start = bin.window_offset as usize;
+        &self.weights[start..start + bin.window_length as usize]
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out =
+            Vec::with_capacity(HEADER_BYTES + BIN_BYTES * self.bins.len() + 4 * self.weights.len());
+        out.extend_from_slice(BANK_TABLE_MAGIC);
+        out.extend_from_slice(&BANK_TABLE_VERSION.to_le_bytes());
+        out.extend_from_slice(&self.sample_rate.to_le_bytes());
+        out.extend_from_slice(&(self.bins.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(self.weights.len() as u32).to_le_bytes());
+        out.resize(HEADER_BYTES, 0);
+        for bin in &self.bins {
+            out.extend_from_slice(&bin.center.to_le_bytes());
+            out.extend_from_slice(&bin.q.to_le_bytes());
+            out.extend_from_slice(&bin.window_offset.to_le_bytes());
+            out.extend_from_slice(&bin.window_length.to_le_bytes());
+            out.extend_from_slice(&bin.decimation.to_le_bytes());
+            out.extend_from_slice(&bin.repeat.to_le_bytes());
+            out.extend_from_slice(&bin.output.to_le_bytes());
+            out.extend_from_slice(&bin.scale.to_le_bytes());
+        }
+        for w in &self.weights {
+            out.extend_from_slice(&w.to_le_bytes());
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MutateError> {
+        let bad = |msg: String| MutateError::BankTable(msg);
+        let word = |at: usize| -> Result<[u8; 4], MutateError> {
+            bytes
+                .get(at..at + 4)
+                .map(|w| w.try_into().unwrap())
+                .ok_or_else(|| bad(format!("truncated at byte {at}")))
+        };
+        let u32_at = |at: usize| word(at).map(u32::from_le_bytes);
+        let f32_at = |at: usize| word(at).map(f32::from_le_bytes);
+
+        if word(0)? != *BANK_TABLE_MAGIC {
+            return Err(bad("not a bank table".into()));
+        }
+        let version = u32_at(4)?;
+        if version != BANK_TABLE_VERSION {
+            return Err(bad(format!(
+                "version {version}, expected {BANK_TABLE_VERSION}"
+            )));
+        }
+        let sample_rate = f32_at(8)?;
+        let bin_count = u32_at(12)? as usize;
+        let weight_count = u32_at(16)? as usize;
+        let weights_at = HEADER_BYTES + BIN_BYTES * bin_count;
+        let expected = weights_at + 4 * weight_count;
+        if bytes.len() != expected {
+            return Err(bad(format!(
+                "{} bytes, header says {expected}",
+                bytes.len()
+            )));
+        }
+
+        let bins = (0..bin_count)
+            .map(|i| {
+                let at = HEADER_BYTES + BIN_BYTES * i;
+                let bin = BankBin {
+                    center: f32_at(at)?,
+                    q: f32_at(at + 4)?,
+                    window_offset: u32_at(at + 8)?,
+                    window_length: u32_at(at + 12)?,
+                    decimation: u32_at(at + 16)?,
+                    repeat: u32_at(at + 20)?,
+                    output: u32_at(at + 24)?,
+                    scale: f32_at(at + 28)?,
+                };
+                if bin.window_offset as usize + bin.window_length as usize > weight_count {
+                    return Err(bad(format!("bin {i} window is out of bounds")));
+                }
+                Ok(bin)
+            })
+            .collect::<Result<_, _>>()?;
+        let weights = (0..weight_count)
+            .map(|i| f32_at(weights_at + 4 * i))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            sample_rate,
+            bins,
+            weights,
+        })
+    }
+
+    /// The same contents as JSON, for reading and diffing rather than loading.
+    pub fn to_json(&self) -> String {
+        let bins = self
+            .bins
+            .iter()
+            .map(|b| {
+                format!(
+                    "    {{\"center\": {}, \"q\": {}, \"window_offset\": {}, \"window_length\": {}, \
+                     \"decimation\": {}, \"repeat\": {}, \"output\": {}, \"scale\": {}}}",
+                    b.center,
+                    b.q,
+                    b.window_offset,
+                    b.window_length,
+                    b.decimation,
+                    b.repeat,
+                    b.output,
+                    b.scale
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",\n");
+        let weights = self
+            .weights
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "{{\n  \"version\": {BANK_TABLE_VERSION},\n  \"sample_rate\": {},\n  \"bins\": [\n{bins}\n  ],\n  \"weights\": [{weights}]\n}}\n",
+            self.sample_rate
+        )
+    }
+}
+
+/// Largest power of two decimation that keeps [`DECIMATION_HEADROOM`] samples per cycle of `max`.
+fn decimation(max: Hertz, fs: SampleRate) -> u32 {
+    let mut decimation = 1;
+    while decimation < MAX_DECIMATION
+        && fs.per_cycle(max) / (2 * decimation) as f64 >= DECIMATION_HEADROOM
+    {
+        decimation *= 2;
+    }
+    decimation
+}
+
 #[cfg(test)]
 mod test {
 
@@ -112,8 +363,8 @@ mod test {
     #[test]
     fn test_bins_range() {
         let count = dsp::spectrogram::RESOLUTION_4K_WIDTH;
-        let freq_min = dsp::MIN_FREQ_CHEAP_DRIVERS;
-        let freq_max = dsp::MAX_FREQ_OLD_PEOPLE;
+        let freq_min = Hertz(dsp::MIN_FREQ_CHEAP_DRIVERS);
+        let freq_max = Hertz(dsp::MAX_FREQ_OLD_PEOPLE);
         let bins = bins(freq_min, freq_max, count);
 
         assert_eq!(bins.len(), count);
@@ -123,8 +374,8 @@ mod test {
 
         // Check that we covered the range by verifying that our interpolation hits the same
         // beginning and end points.
-        let min_ratio = min / dsp::MIN_FREQ_CHEAP_DRIVERS;
-        let max_ratio = max / dsp::MAX_FREQ_OLD_PEOPLE;
+        let min_ratio = min / freq_min;
+        let max_ratio = max / freq_max;
 
         // println!("min ratio: {:10.19}", min_ratio);
         // println!("max ratio: {:10.19}", max_ratio);
@@ -142,22 +393,20 @@ mod test {
         }
 
         // Bandwidth sum matches target spectrum
-        let mut sum = 0.0;
+        let mut sum = Hertz(0.0);
         for b in bins.iter() {
-            sum += b.bandwidth();
+            sum = sum + b.bandwidth();
         }
         assert!(((sum / (freq_max - freq_min)) - 1.0).abs() < 0.000000000000001);
     }
 
     #[test]
     fn test_bins_bin_lookup() {
-        let target = 4000.0;
-        let closest = bin_lookup(
-            dsp::MIN_FREQ_CHEAP_DRIVERS,
-            dsp::MAX_FREQ_OLD_PEOPLE,
-            dsp::spectrogram::RESOLUTION_4K_WIDTH,
-            target,
-        );
+        let min = Hertz(dsp::MIN_FREQ_CHEAP_DRIVERS);
+        let max = Hertz(dsp::MAX_FREQ_OLD_PEOPLE);
+        let count = dsp::spectrogram::RESOLUTION_4K_WIDTH;
+        let target = Hertz(4000.0);
+        let closest = bin_lookup(min, max, count, target);
 
         // NOTE bins do not have infinite resolution
 
@@ -165,23 +414,57 @@ mod test {
         assert!((ratio - 1.0).abs() < 0.01);
 
         // First bin will have the minimum frequency
-        let closest = bin_lookup(
-            dsp::MIN_FREQ_CHEAP_DRIVERS,
-            dsp::MAX_FREQ_OLD_PEOPLE,
-            dsp::spectrogram::RESOLUTION_4K_WIDTH,
-            0.0,
-        );
-        let ratio = closest.min / dsp::MIN_FREQ_CHEAP_DRIVERS;
+        let closest = bin_lookup(min, max, count, Hertz(0.0));
+        let ratio = closest.min / min;
         assert!((ratio - 1.0).abs() < 0.0000001);
 
         // Last bin will have the maximum frequency
-        let closest = bin_lookup(
-            dsp::MIN_FREQ_CHEAP_DRIVERS,
-            dsp::MAX_FREQ_OLD_PEOPLE,
-            dsp::spectrogram::RESOLUTION_4K_WIDTH,
-            100_000.0,
-        );
-        let ratio = closest.max / dsp::MAX_FREQ_OLD_PEOPLE;
+        let closest = bin_lookup(min, max, count, Hertz(100_000.0));
+        let ratio = closest.max / max;
         assert!((ratio - 1.0).abs() < 0.0000001);
     }
+
+    #[test]
+    fn test_bank_table_round_trip() {
+        let window = dsp::window::WindowFunction::DolphChebyshev {
+            attenuation_db: 60.0,
+        };
+        let fit = WindowFit {
+            window,
+            threshold_db: -3.0,
+            bandwidth_bins: 1.4,
+            spread: 0.0,
+            rise_fraction: 0.6,
+            side_lobe_db: -60.0,
+        };
+        let fs = SampleRate(48_000.0);
+        let bins = bins(
+            Hertz(dsp::MIN_FREQ_CHEAP_DRIVERS),
+            Hertz(dsp::MAX_FREQ_OLD_PEOPLE),
+            64,
+        );
+        let table = BankTable::design(&bins, &fit, fs, Seconds(0.016));
+        assert_eq!(table.bins.len(), 64);
+
+        // Low bins decimate and high bins do not.  Every window fits its rate.
+        assert_eq!(table.bins[0].decimation, MAX_DECIMATION);
+        assert_eq!(table.bins[63].decimation, 1);
+        for (bin, b) in bins.iter().zip(&table.bins) {
+            let rate = fs.get() / b.decimation as f64;
+            assert!(SampleRate(rate).nyquist() > bin.max);
+            assert_eq!(table.window(b).len(), b.window_length as usize);
+        }
+        // Rise limited bins share windows.
+        assert!(table.weights.len() < table.bins.iter().map(|b| b.window_length as usize).sum());
+
+        let bytes = table.to_bytes();
+        assert_eq!(bytes.len(), 32 + 32 * 64 + 4 * table.weights.len());
+        assert_eq!(BankTable::from_bytes(&bytes).unwrap(), table);
+        assert!(BankTable::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(BankTable::from_bytes(b"MTBX").is_err());
+
+        let json = table.to_json();
+        assert!(json.contains("\"decimation\": 64"));
+        assert_eq!(json.matches("\"center\"").count(), 64);
+    }
 }
@@ -0,0 +1,451 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Beat Prediction
+//!
+//! Visuals must never lag audio.  A beat that is detected is a beat that has already been heard, so
+//! any flash drawn in response to detection arrives at least one analysis window plus one frame
+//! late.  Instead, we extrapolate the next beat from the tempo estimate and schedule the visual
+//! event early enough that it reaches the viewer's eyes when the beat reaches their ears.
+//!
+//! The [`BeatPredictor`] consumes beats from a tracker and answers, "when should I fire the next
+//! beat visual?"  The answer is the predicted beat time minus the audio-to-photon latency.
+//!
+//! ## Confidence Gating
+//!
+//! A wrong flash is much worse than a missing flash.  Every observed beat is compared against the
+//! prediction made before it arrived.  Small errors raise confidence while large errors, or a jump in
+//! the tempo estimate, drop it.  Predictions below the gate are withheld until the predictor
+//! re-locks.
+//!
+//! ## Latency
+//!
+//! Callers measure the latency and hand it to [`BeatPredictor::set_latency`].  The visualizer
+//! leads by the audio to photon time of its `FrameStats`, from capture of the newest audio a frame
+//! read until the frame reached the display.  The daemon leads its MIDI output by the delay of the
+//! audio it read plus the output's own.
+//!
+//! ## Tracking
+//!
+//! The [`BeatTracker`] hears the beats that feed the predictor in spectrogram columns.  Onset
+//! strength is the rise in level from one column to the next, averaged across bins.  The tempo is
+//! the lag at which onset strength best repeats over the last few seconds, leaning toward
+//! [`PREFERRED_BPM`] when a multiple fits as well.  A beat is an onset peak that stands out from
+//! the onsets around it and lands on the grid of the last beat.
+
+// NEXT the spectrogram flux on the GPU can replace the host onset strength once columns stay on the
+// device.
+
+use std::collections::VecDeque;
+
+use crate::dsp::units::Seconds;
+
+/// Relative tempo change that is treated as a new tempo rather than drift.
+const TEMPO_JUMP: f64 = 0.04;
+/// Phase error, as a fraction of the beat period, at which a prediction counts as a miss.
+const MISS_FRACTION: f64 = 0.25;
+/// How many recent prediction errors contribute to confidence.
+const HISTORY: usize = 8;
+/// Slowest tempo the tracker considers, in BPM.
+const MIN_BPM: f64 = 60.0;
+/// Fastest tempo the tracker considers, in BPM.
+const MAX_BPM: f64 = 180.0;
+/// Tempo the tracker leans toward when a multiple of it fits as well, in BPM.
+pub const PREFERRED_BPM: f64 = 120.0;
+/// Onset strength kept for the tempo estimate.
+const ONSET_HISTORY: Seconds = Seconds(8.0);
+/// Onset strength needed before the first estimate, four beats at the slowest tempo.
+const WARMUP: Seconds = Seconds(4.0);
+/// Level that silence is clamped to, so that noise in silence is not heard as onsets.
+const FLOOR_DB: f32 = -100.0;
+/// Standard deviations above the mean onset strength that a peak must reach to be a beat.
+const PEAK_DEVIATIONS: f64 = 1.0;
+/// Beats without one heard after which any peak starts the grid over.
+const RELOCK_BEATS: f64 = 4.0;
+
+/// A beat visual that should be fired at `fire_at` so that it is seen at `beat_at`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BeatEvent {
+    /// When to start drawing, already compensated for latency.
+    pub fire_at: Seconds,
+    /// When the beat will be heard.
+    pub beat_at: Seconds,
+    /// `0.0..=1.0` agreement of recent beats with their predictions.
+    pub confidence: f64,
+}
+
+/// Position within the current beat, for effects that move with the tempo rather than fire on it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BeatPhase {
+    pub period: Seconds,
+    /// `0.0..1.0`, zero on the beat.
+    pub phase: f64,
+}
+
+/// A beat heard by the [`BeatTracker`], with the tempo at the time.  Feed it to
+/// [`BeatPredictor::observe`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrackedBeat {
+    pub at: Seconds,
+    pub bpm: f64,
+}
+
+/// Hears beats in spectrogram columns.  See the [module](self) docs.
+pub struct BeatTracker {
+    /// Columns per second.
+    rate: f64,
+    /// Levels of the previous column in dB.
+    last: Vec<f32>,
+    /// Onset strength of recent columns, oldest first.
+    onsets: VecDeque<f64>,
+    /// Columns of onset strength kept.
+    history: usize,
+    /// When the newest column ended.
+    now: Seconds,
+    /// Beat period from the tempo estimate.
+    period: Option<Seconds>,
+    /// Anchor of the grid.
+    last_beat: Option<Seconds>,
+}
+
+impl BeatTracker {
+    /// Hear columns that arrive `rate` times per second.
+    pub fn new(rate: f64) -> Self {
+        let rate = rate.max(1.0);
+        let history = (ONSET_HISTORY.get() * rate).ceil() as usize;
+        Self {
+            rate,
+            last: Vec::new(),
+            onsets: VecDeque::with_capacity(history),
+            history,
+            now: Seconds(0.0),
+            period: None,
+            last_beat: None,
+        }
+    }
+
+    /// The tempo estimate.  `None` until enough audio has arrived, or while nothing repeats.
+    pub fn bpm(&self) -> Option<f64> {
+        self.period.map(|period| 60.0 / period.get())
+    }
+
+    /// Feed the column of bin amplitudes that ended at `t`.  A peak is only known once the column
+    /// after it arrives, so a beat returned is the previous column's.
+    pub fn push(&mut self, t: Seconds, column: &[f32]) -> Option<TrackedBeat> {
+        if self.last.len() != column.len() {
+            self.last = vec![FLOOR_DB; column.len()];
+        }
+        let mut rise = 0.0;
+        for (last, &amplitude) in self.last.iter_mut().zip(column) {
+            let db = (20.0 * amplitude.max(f32::MIN_POSITIVE).log10()).max(FLOOR_DB);
+            rise += (db - *last).max(0.0) as f64;
+            *last = db;
+        }
+        let previous = std::mem::replace(&mut self.now, t);
+        if self.onsets.len() == self.history {
+            self.onsets.pop_front();
+        }
+        self.onsets.push_back(rise / column.len().max(1) as f64);
+        if (self.onsets.len() as f64) < WARMUP.get() * self.rate {
+            return None;
+        }
+        self.period = self.estimate();
+        self.pick(previous)
+    }
+
+    /// The lag at which onset strength best repeats, weighted toward [`PREFERRED_BPM`] by octaves.
+    fn estimate(&self) -> Option<Seconds> {
+        let n = self.onsets.len();
+        let mean = self.onsets.iter().sum::<f64>() / n as f64;
+        let centered: Vec<f64> = self.onsets.iter().map(|x| x - mean).collect();
+        let min_lag = ((60.0 / MAX_BPM * self.rate).floor() as usize).max(1);
+        let max_lag = ((60.0 / MIN_BPM * self.rate).ceil() as usize).min(n / 2);
+        if min_lag >= max_lag {
+            return None;
+        }
+        let scores: Vec<f64> = (min_lag..=max_lag)
+            .map(|lag| {
+                let sum: f64 = centered
+                    .iter()
+                    .zip(&centered[lag..])
+                    .map(|(a, b)| a * b)
+                    .sum();
+                let octaves = (60.0 * self.rate / lag as f64 / PREFERRED_BPM).log2();
+                sum / (n - lag) as f64 * (-0.5 * octaves * octaves).exp()
+            })
+            .collect();
+        let (i, &best) = scores
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        if best <= 0.0 {
+            return None;
+        }
+        // A parabola through the neighbors places the peak between lags.
+        let offset = match (i.checked_sub(1), scores.get(i + 1)) {
+            (Some(before), Some(&after)) => {
+                let before = scores[before];
+                let curve = before - 2.0 * best + after;
+                match curve < 0.0 {
+                    true => (0.5 * (before - after) / curve).clamp(-0.5, 0.5),
+                    false => 0.0,
+                }
+            }
+            _ => 0.0,
+        };
+        Some(Seconds(((min_lag + i) as f64 + offset) / self.rate))
+    }
+
+    /// The beat at `at` if the column before the newest is a peak that stands out and lands on the
+    /// grid.
+    fn pick(&mut self, at: Seconds) -> Option<TrackedBeat> {
+        let period = self.period?;
+        let n = self.onsets.len();
+        let (before, peak, after) = (self.onsets[n - 3], self.onsets[n - 2], self.onsets[n - 1]);
+        if peak <= before || peak < after {
+            return None;
+        }
+        let mean = self.onsets.iter().sum::<f64>() / n as f64;
+        let variance = self.onsets.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+        if peak < mean + PEAK_DEVIATIONS * variance.sqrt() {
+            return None;
+        }
+        if let Some(last) = self.last_beat {
+            let beats = (at - last) / period;
+            let off_grid = (beats - beats.round()).abs() > MISS_FRACTION;
+            if beats <= RELOCK_BEATS && (beats.round() < 1.0 || off_grid) {
+                return None;
+            }
+        }
+        self.last_beat = Some(at);
+        Some(TrackedBeat {
+            at,
+            bpm: 60.0 / period.get(),
+        })
+    }
+}
+
+/// Extrapolates beats from a tempo estimate and recent beat observations.  All times are on the
+/// same clock, typically seconds since the audio stream began.
+pub struct BeatPredictor {
+    /// Beat period from the tempo estimate.
+    period: Option<Seconds>,
+    /// Anchor for extrapolation.
+    last_beat: Option<Seconds>,
+    /// Recent prediction errors as fractions of a period.
+    errors: VecDeque<f64>,
+    /// Audio-to-photon latency.  Visuals are fired this far ahead of the beat.
+    latency: Seconds,
+    /// Minimum confidence to emit events.
+    gate: f64,
+}
+
+impl BeatPredictor {
+    pub fn new(latency: Seconds) -> Self {
+        Self {
+            period: None,
+            last_beat: None,
+            errors: VecDeque::with_capacity(HISTORY),
+            latency,
+            gate: 0.6,
+        }
+    }
+
+    /// Confidence required to emit events.  Defaults to `0.6`.
+    pub fn with_gate(mut self, gate: f64) -> Self {
+        self.gate = gate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Update the audio-to-photon latency, such as after the presentation pipeline changes depth.
+    pub fn set_latency(&mut self, latency: Seconds) {
+        self.latency = latency;
+    }
+
+    /// Feed one beat from the tracker along with its current tempo estimate.
+    pub fn observe(&mut self, beat: Seconds, bpm: f64) {
+        let period = Seconds(60.0 / bpm);
+
+        if let Some(old) = self.period {
+            if ((period / old) - 1.0).abs() > TEMPO_JUMP {
+                // A new tempo invalidates every error we measured against the old one.
+                self.errors.clear();
+            }
+        }
+
+        if let (Some(last), Some(old)) = (self.last_beat, self.period) {
+            // Error against the nearest predicted beat, allowing for skipped beats.
+            let beats = ((beat - last) / old).round().max(1.0);
+            let predicted = last + old * beats;
+            let error = ((beat - predicted) / old).abs();
+            if self.errors.len() == HISTORY {
+                self.errors.pop_front();
+            }
+            self.errors.push_back(error);
+        }
+
+        self.period = Some(period);
+        self.last_beat = Some(beat);
+    }
+
+    /// Agreement of recent predictions with observed beats.  Zero until at least two predictions
+    /// have been checked.
+    pub fn confidence(&self) -> f64 {
+        if self.errors.len() < 2 {
+            return 0.0;
+        }
+        let mean = self.errors.iter().sum::<f64>() / self.errors.len() as f64;
+        // Fill the history before trusting it completely.
+        let fill = self.errors.len() as f64 / HISTORY as f64;
+        (1.0 - mean / MISS_FRACTION).clamp(0.0, 1.0) * fill.sqrt()
+    }
+
+    /// The first predicted beat at or after `now`.
+    pub fn next_beat(&self, now: Seconds) -> Option<Seconds> {
+        let (last, period) = (self.last_beat?, self.period?);
+        let beats = ((now - last) / period).ceil().max(0.0);
+        Some(last + period * beats)
+    }
+
+    /// Where `now` falls within the current beat, or `None` when confidence is below the gate.
+    pub fn phase(&self, now: Seconds) -> Option<BeatPhase> {
+        if self.confidence() < self.gate {
+            return None;
+        }
+        let (last, period) = (self.last_beat?, self.period?);
+        let beats = (now - last) / period;
+        Some(BeatPhase {
+            period,
+            phase: beats - beats.floor(),
+        })
+    }
+
+    /// The next beat visual that still has time to be fired, or `None` when confidence is below the
+    /// gate.  If the latency-compensated fire time for the next beat has already passed, the beat
+    /// after it is returned.
+    pub fn schedule(&self, now: Seconds) -> Option<BeatEvent> {
+        let confidence = self.confidence();
+        if confidence < self.gate {
+            return None;
+        }
+        let period = self.period?;
+        let mut beat_at = self.next_beat(now)?;
+        if beat_at - self.latency < now {
+            beat_at = beat_at + period;
+        }
+        Some(BeatEvent {
+            fire_at: beat_at - self.latency,
+            beat_at,
+            confidence,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn feed(p: &mut BeatPredictor, start: f64, bpm: f64, count: usize) -> f64 {
+        let period = 60.0 / bpm;
+        let mut t = start;
+        for _ in 0..count {
+            p.observe(Seconds(t), bpm);
+            t += period;
+        }
+        t - period
+    }
+
+    #[test]
+    fn test_beat_steady_tempo() {
+        let latency = Seconds(0.040);
+        let mut p = BeatPredictor::new(latency);
+        assert!(p.schedule(Seconds(0.0)).is_none());
+
+        let last = feed(&mut p, 1.0, 120.0, 16);
+        assert!(p.confidence() > 0.99);
+
+        let event = p.schedule(Seconds(last + 0.1)).unwrap();
+        assert!((event.beat_at.get() - (last + 0.5)).abs() < 1e-9);
+        assert!((event.fire_at.get() - (last + 0.46)).abs() < 1e-9);
+
+        // Too late to fire for the next beat, so the one after is scheduled.
+        let event = p.schedule(Seconds(last + 0.48)).unwrap();
+        assert!((event.beat_at.get() - (last + 1.0)).abs() < 1e-9);
+
+        let phase = p.phase(Seconds(last + 0.125)).unwrap();
+        assert!((phase.phase - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_beat_tempo_change_gates() {
+        let mut p = BeatPredictor::new(Seconds(0.030));
+        let last = feed(&mut p, 0.0, 120.0, 16);
+        assert!(p.schedule(Seconds(last)).is_some());
+
+        // The tracker jumps to a new tempo.  No flashes until the new tempo has been confirmed.
+        p.observe(Seconds(last + 0.4), 150.0);
+        assert!(p.schedule(Seconds(last + 0.4)).is_none());
+
+        let last = feed(&mut p, last + 0.8, 150.0, 16);
+        assert!(p.schedule(Seconds(last)).is_some());
+    }
+
+    /// Columns at `rate` of a click at `bpm`, quiet between clicks.
+    fn clicks(rate: f64, bpm: f64, seconds: f64) -> impl Iterator<Item = (Seconds, Vec<f32>)> {
+        let period = 60.0 / bpm;
+        (0..(seconds * rate) as usize).map(move |n| {
+            let start = n as f64 / rate;
+            // Seconds since the last click began, at the start of this column.
+            let since = start % period;
+            let level = match since < 1.0 / rate {
+                true => 1.0,
+                false => 0.001 + 0.2 * (-since * 30.0).exp() as f32,
+            };
+            (Seconds((n + 1) as f64 / rate), vec![level; 16])
+        })
+    }
+
+    #[test]
+    fn test_beat_tracker() {
+        for (bpm, rate) in [(120.0, 60.0), (100.0, 60.0), (140.0, 30.0)] {
+            let mut tracker = BeatTracker::new(rate);
+            let mut predictor = BeatPredictor::new(Seconds(0.0));
+            let mut beats = Vec::new();
+            for (t, column) in clicks(rate, bpm, 12.0) {
+                if let Some(beat) = tracker.push(t, &column) {
+                    predictor.observe(beat.at, beat.bpm);
+                    beats.push(beat);
+                }
+            }
+            assert!(beats.len() > 8, "{bpm} BPM heard {}", beats.len());
+            let heard = tracker.bpm().unwrap();
+            assert!((heard - bpm).abs() < 2.0, "{bpm} BPM heard as {heard}");
+            let period = 60.0 / bpm;
+            for pair in beats.windows(2) {
+                let gap = (pair[1].at - pair[0].at).get();
+                assert!((gap - period).abs() < 1.5 / rate, "{bpm} BPM gap {gap}");
+            }
+            assert!(predictor.confidence() > 0.9, "{}", predictor.confidence());
+        }
+
+        // Nothing repeats in silence.
+        let mut tracker = BeatTracker::new(60.0);
+        for n in 0..600 {
+            assert!(tracker.push(Seconds(n as f64 / 60.0), &[0.0; 16]).is_none());
+        }
+        assert!(tracker.bpm().is_none());
+    }
+
+    #[test]
+    fn test_beat_jitter_lowers_confidence() {
+        let mut p = BeatPredictor::new(Seconds(0.0));
+        let period = 0.5;
+        for i in 0..16 {
+            // Alternate a tenth of a beat early and late.
+            let jitter = if i % 2 == 0 { 0.05 } else { -0.05 };
+            p.observe(Seconds(i as f64 * period + jitter), 120.0);
+        }
+        let confidence = p.confidence();
+        assert!(confidence > 0.0 && confidence < 0.9, "{confidence}");
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # SPL Calibration
+//!
+//! Digital levels are relative.  0dBFS is whatever the converter's full scale happens to be, so the
+//! same room can read -20dBFS on one interface and -32dBFS on another.  An installation with a
+//! measurement microphone can do better: hold an acoustic calibrator on the capsule, or play a
+//! 1kHz tone and read an SPL meter beside the mic, and the difference between the known SPL and the
+//! measured dBFS is an offset that turns every later reading into absolute dB SPL.
+//!
+//! ```text
+//! workbench calibrate --source "UMIK" --spl 94 --save calibration.toml
+//! ```
+//!
+//! The saved `[calibration]` table goes in the [settings](crate::settings) file as it is, where
+//! [`Settings::calibration`](crate::settings::Settings::calibration) reads it.
+//!
+//! The [`Calibrator`] measures only the reference tone.  Each block is demodulated at the reference
+//! frequency, so room noise and hum do not inflate the reading, and the measurement is rejected if
+//! the tone is unsteady or does not dominate the input.
+//!
+//! [`Levels`] is what displays consume.  Without a calibration it reports dBFS and perceptual dB
+//! relative to the 1kHz reference.  With one it reports dB SPL and loudness level in phons from the
+//! [ISO 226](crate::dsp::iso226) contours, which are only meaningful at absolute levels.  Program
+//! loudness stays in LUFS either way, and [`Levels::program`] adds the SPL it plays at when
+//! calibrated.  Displays show [`Levels::mode`] beside the readings so that nobody mistakes one
+//! scale for the other.
+//!
+//! ## File
+//!
+//! ```toml
+//! [calibration]
+//! offset_db = 113.8
+//! reference_hz = 1000.0
+//! reference_spl = 94.0
+//! ```
+
+// NEXT play the reference tone ourselves once the audio module can output.  For now the tone comes
+// from an acoustic calibrator or any tone generator.
+// LIES the microphone's own frequency response is ignored.  Measurement mics ship with correction
+// files, and applying them belongs in the same config entry.
+
+use std::path::Path;
+
+use crate::dsp::iso226::{iso226_gain, spl_to_phon};
+use crate::dsp::units::{Hertz, SampleRate, Seconds};
+use crate::MutateError;
+
+/// SPL of a class 1 acoustic calibrator, 1 Pa RMS.
+pub const CALIBRATOR_SPL: f64 = 94.0;
+/// Frequency of acoustic calibrators and of the gain staging reference.
+pub const REFERENCE_HZ: Hertz = Hertz(1000.0);
+/// Input ignored at the start while the calibrator is seated or the tone fades in.
+const SETTLE: Seconds = Seconds(0.5);
+/// Steady tone needed after settling.
+const MIN_MEASURE: Seconds = Seconds(1.0);
+/// Length of each demodulated block.
+const BLOCK: Seconds = Seconds(0.1);
+/// Blocks may not disagree by more than this.
+const MAX_SPREAD_DB: f64 = 1.0;
+/// Fraction of input power that must be the reference tone.
+const MIN_PURITY: f64 = 0.8;
+/// How far a sine's peak, which dBFS measures, sits above its RMS, which LUFS measure.
+const SINE_CREST_DB: f64 = 3.0103;
+
+/// Offset that turns dBFS into dB SPL for one input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Calibration {
+    /// dB SPL of a full-scale sine.
+    pub offset_db: f64,
+    pub reference_hz: f64,
+    pub reference_spl: f64,
+}
+
+impl Calibration {
+    /// dB SPL of a level measured in dBFS.
+    pub fn spl(&self, dbfs: f64) -> f64 {
+        dbfs + self.offset_db
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MutateError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| MutateError::Calibration(format!("{}: {e}", path.display())))?;
+        Self::parse(&text)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MutateError> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_toml())
+            .map_err(|e| MutateError::Calibration(format!("{}: {e}", path.display())))
+    }
+
+    /// Read the `[calibration]` table.
+    pub fn parse(text: &str) -> Result<Self, MutateError> {
+        let bad = |msg: String| MutateError::Calibration(msg);
+        let table: toml::Table = text.parse().map_err(|e| bad(format!("{e}")))?;
+        let cal = table
+            .get("calibration")
+            .and_then(|v| v.as_table())
+            .ok_or_else(|| bad("missing `[calibration]` table".into()))?;
+        let float = |key: &str| {
+            cal.get(key)
+                .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+                .ok_or_else(|| bad(format!("`{key}` must be a number")))
+        };
+        Ok(Self {
+            offset_db: float("offset_db")?,
+            reference_hz: float("reference_hz")?,
+            reference_spl: float("reference_spl")?,
+        })
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut cal = toml::Table::new();
+        cal.insert("offset_db".into(), self.offset_db.into());
+        cal.insert("reference_hz".into(), self.reference_hz.into());
+        cal.insert("reference_spl".into(), self.reference_spl.into());
+        let mut table = toml::Table::new();
+        table.insert("calibration".into(), cal.into());
+        table.to_string()
+    }
+}
+
+/// Measures a steady reference tone and derives a [`Calibration`].
+pub struct Calibrator {
+    reference: Hertz,
+    omega: f64,
+    settle: usize,
+    block: usize,
+    min_blocks: usize,
+    /// Samples seen, including settling.
+    n: usize,
+    re: f64,
+    im: f64,
+    power: f64,
+    /// Tone level and purity of each finished block.
+    blocks: Vec<(f64, f64)>,
+}
+
+impl Calibrator {
+    pub fn new(fs: SampleRate, reference: Hertz) -> Self {
+        // Whole cycles per block so that demodulation does not leak.
+        let cycles = (BLOCK.get() * reference.get()).round().max(1.0);
+        let block = fs.cycles(reference, cycles).get();
+        Self {
+            reference,
+            omega: fs.omega(reference),
+            settle: fs.samples(SETTLE).get(),
+            block,
+            min_blocks: (MIN_MEASURE.get() / BLOCK.get()).ceil() as usize,
+            n: 0,
+            re: 0.0,
+            im: 0.0,
+            power: 0.0,
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Feed mono input.
+    pub fn push(&mut self, samples: &[f32]) {
+        for &x in samples {
+            let n = self.n;
+            self.n += 1;
+            if n < self.settle {
+                continue;
+            }
+            let x = x as f64;
+            let phase = self.omega * (n - self.settle) as f64;
+            self.re += x * phase.cos();
+            self.im -= x * phase.sin();
+            self.power += x * x;
+            if (n - self.settle + 1).is_multiple_of(self.block) {
+                let len = self.block as f64;
+                let amplitude = 2.0 * self.re.hypot(self.im) / len;
+                let tone_power = amplitude * amplitude / 2.0;
+                let purity = tone_power / (self.power / len).max(f64::MIN_POSITIVE);
+                // 0dBFS is a full-scale sine.
+                let dbfs = 20.0 * amplitude.max(f64::MIN_POSITIVE).log10();
+                self.blocks.push((dbfs, purity));
+                (self.re, self.im, self.power) = (0.0, 0.0, 0.0);
+            }
+        }
+    }
+
+    /// Whether enough steady input has been seen to call [`finish`](Self::finish).
+    pub fn ready(&self) -> bool {
+        self.blocks.len() >= self.min_blocks
+    }
+
+    /// Mean tone level so far.
+    pub fn measured_dbfs(&self) -> Option<f64> {
+        (!self.blocks.is_empty())
+            .then(|| self.blocks.iter().map(|b| b.0).sum::<f64>() / self.blocks.len() as f64)
+    }
+
+    /// Derive the calibration, given the SPL the tone actually had at the microphone.
+    pub fn finish(self, reference_spl: f64) -> Result<Calibration, MutateError> {
+        let bad = |msg: String| Err(MutateError::Calibration(msg));
+        if !self.ready() {
+            return bad(format!(
+                "need {:.1}s of steady tone after {:.1}s of settling",
+                MIN_MEASURE.get(),
+                SETTLE.get()
+            ));
+        }
+        let purity = self
+            .blocks
+            .iter()
+            .map(|b| b.1)
+            .fold(f64::INFINITY, f64::min);
+        if purity < MIN_PURITY {
+            return bad(format!(
+                "only {:.0}% of the input is the {:.0}Hz tone",
+                purity * 100.0,
+                self.reference.get()
+            ));
+        }
+        let (lo, hi) = self
+            .blocks
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), b| {
+                (lo.min(b.0), hi.max(b.0))
+            });
+        if hi - lo > MAX_SPREAD_DB {
+            return bad(format!("tone level wandered by {:.1}dB", hi - lo));
+        }
+        // Checked by `ready`.
+        let dbfs = self.measured_dbfs().unwrap();
+        Ok(Calibration {
+            offset_db: reference_spl - dbfs,
+            reference_hz: self.reference.get(),
+            reference_spl,
+        })
+    }
+}
+
+/// A level in whatever absolute or relative unit is available.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Dbfs(f64),
+    Spl(f64),
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Dbfs(db) => write!(f, "{db:.1} dBFS"),
+            Level::Spl(db) => write!(f, "{db:.1} dB SPL"),
+        }
+    }
+}
+
+/// Perceived loudness of a tone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Loudness {
+    /// Perceptual dB relative to a full-scale 1kHz tone, the gain staging scale.
+    Relative(f64),
+    /// Loudness level on the ISO 226 contours.
+    Phon(f64),
+    /// Calibrated and below the threshold of hearing.
+    Inaudible,
+}
+
+impl std::fmt::Display for Loudness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Loudness::Relative(db) => write!(f, "{db:.1} dB rel"),
+            Loudness::Phon(phon) => write!(f, "{phon:.1} phon"),
+            Loudness::Inaudible => write!(f, "inaudible"),
+        }
+    }
+}
+
+/// Program loudness in LUFS, and how loud it plays when calibrated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Program {
+    pub lufs: f64,
+    /// dB SPL of a 1kHz sine in one channel that reads the same LUFS.
+    pub spl: Option<f64>,
+}
+
+impl std::fmt::Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.1} LUFS", self.lufs)?;
+        match self.spl {
+            Some(spl) => write!(f, " {spl:.1} dB SPL"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reports measured levels in absolute units when a calibration is available and relative units
+/// otherwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Levels {
+    calibration: Option<Calibration>,
+}
+
+impl Levels {
+    pub fn new(calibration: Option<Calibration>) -> Self {
+        Self { calibration }
+    }
+
+    pub fn is_calibrated(&self) -> bool {
+        self.calibration.is_some()
+    }
+
+    /// Short label for displays to show beside levels.
+    pub fn mode(&self) -> &'static str {
+        match self.calibration {
+            Some(_) => "calibrated",
+            None => "uncalibrated",
+        }
+    }
+
+    pub fn level(&self, dbfs: f64) -> Level {
+        match &self.calibration {
+            Some(cal) => Level::Spl(cal.spl(dbfs)),
+            None => Level::Dbfs(dbfs),
+        }
+    }
+
+    /// Perceived loudness of a tone at `freq` measured at `dbfs`.
+    pub fn loudness(&self, freq: Hertz, dbfs: f64) -> Result<Loudness, MutateError> {
+        Ok(match &self.calibration {
+            Some(cal) => spl_to_phon(freq.get(), cal.spl(dbfs))
+                .map(Loudness::Phon)
+                .unwrap_or(Loudness::Inaudible),
+            None => Loudness::Relative(dbfs + iso226_gain(freq.get())?),
+        })
+    }
+
+    /// Program loudness measured at `lufs`, such as by a
+    /// [`LoudnessMeter`](crate::dsp::loudness::LoudnessMeter).
+    pub fn program(&self, lufs: f64) -> Program {
+        Program {
+            lufs,
+            spl: self.calibration.map(|cal| cal.spl(lufs + SINE_CREST_DB)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dsp::iso226::phon_to_spl;
+
+    const FS: SampleRate = SampleRate(48_000.0);
+
+    /// A tone with a little deterministic noise and hum.
+    fn tone(amplitude: f64, seconds: f64) -> Vec<f32> {
+        let omega = FS.omega(REFERENCE_HZ);
+        let hum = FS.omega(Hertz(50.0));
+        let mut noise: u32 = 1;
+        (0..FS.samples(Seconds(seconds)).get())
+            .map(|n| {
+                noise = noise.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let white = noise as f64 / u32::MAX as f64 - 0.5;
+                let x = amplitude * (omega * n as f64).sin()
+                    + 0.001 * white
+                    + 0.002 * (hum * n as f64).sin();
+                x as f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_calibration_measures_tone() {
+        let mut cal = Calibrator::new(FS, REFERENCE_HZ);
+        let input = tone(0.1, 2.0);
+        // Arbitrary chunking must not matter.
+        for chunk in input.chunks(333) {
+            cal.push(chunk);
+        }
+        assert!(cal.ready());
+        assert!((cal.measured_dbfs().unwrap() + 20.0).abs() < 0.05);
+        let cal = cal.finish(CALIBRATOR_SPL).unwrap();
+        assert!((cal.offset_db - 114.0).abs() < 0.05, "{cal:?}");
+        assert!((cal.spl(-20.0) - CALIBRATOR_SPL).abs() < 0.05);
+
+        let back = Calibration::parse(&cal.to_toml()).unwrap();
+        assert_eq!(back, cal);
+    }
+
+    #[test]
+    fn test_calibration_rejects_bad_input() {
+        let mut short = Calibrator::new(FS, REFERENCE_HZ);
+        short.push(&tone(0.1, 1.0));
+        assert!(short.finish(CALIBRATOR_SPL).is_err());
+
+        // Hum and noise only.
+        let mut quiet = Calibrator::new(FS, REFERENCE_HZ);
+        quiet.push(&tone(0.0, 2.0));
+        assert!(quiet.finish(CALIBRATOR_SPL).is_err());
+
+        // Fades in after settling.
+        let mut fading = Calibrator::new(FS, REFERENCE_HZ);
+        let mut input = tone(0.1, 2.0);
+        input[..FS.samples(Seconds(1.0)).get()]
+            .iter_mut()
+            .for_each(|x| *x *= 0.5);
+        fading.push(&input);
+        assert!(fading.finish(CALIBRATOR_SPL).is_err());
+    }
+
+    #[test]
+    fn test_calibration_levels() {
+        let relative = Levels::default();
+        assert_eq!(relative.mode(), "uncalibrated");
+        assert_eq!(relative.level(-20.0).to_string(), "-20.0 dBFS");
+        assert_eq!(
+            relative.loudness(REFERENCE_HZ, -20.0).unwrap(),
+            Loudness::Relative(-20.0)
+        );
+
+        let levels = Levels::new(Some(Calibration {
+            offset_db: 114.0,
+            reference_hz: 1000.0,
+            reference_spl: CALIBRATOR_SPL,
+        }));
+        assert!(levels.is_calibrated());
+        assert_eq!(levels.level(-20.0).to_string(), "94.0 dB SPL");
+        match levels.loudness(REFERENCE_HZ, -54.0).unwrap() {
+            Loudness::Phon(phon) => assert!((phon_to_spl(1000.0, phon) - 60.0).abs() < 0.01),
+            other => panic!("{other:?}"),
+        }
+        // 20dB SPL at 31.5Hz is below hearing.
+        assert_eq!(
+            levels.loudness(Hertz(31.5), -94.0).unwrap(),
+            Loudness::Inaudible
+        );
+
+        // A sine at -20dBFS reads about -23 LUFS in one channel and plays at the calibrator's SPL.
+        assert_eq!(relative.program(-23.0).to_string(), "-23.0 LUFS");
+        let program = levels.program(-20.0 - SINE_CREST_DB);
+        assert!((program.spl.unwrap() - CALIBRATOR_SPL).abs() < 1e-9);
+        assert_eq!(program.to_string(), "-23.0 LUFS 94.0 dB SPL");
+    }
+}
@@ -0,0 +1,501 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Chroma
+//!
+//! Energy says how loud, not what key.  A chromagram folds every octave of a spectrogram row onto
+//! the twelve pitch classes, so that a C in the bass and a C in the lead both light up C.  Visuals
+//! can then pick color or geometry from harmony instead of from whichever band is loudest.
+//!
+//! Bank bins are rarely exactly one per semitone.  [`Chromagram`] splits each column's power
+//! between the two nearest pitch classes by how far its center sits between them, so banks with
+//! more or fewer bins per octave still fold without gaps or double counting.
+//!
+//! [`Chromagram::dominant`] tracks the strongest single pitch, interpolated between columns, with
+//! its deviation in cents.  It is only meaningful for monophonic or lead-dominated material.
+//!
+//! [`ChromaNode`] smooths the chromagram over time for the graph.  Built with
+//! [`on_spectrum`](ChromaNode::on_spectrum), it folds the rows of a [`SpectrogramNode`] or
+//! [`MultiDftNode`] and follows the graph's [configuration](crate::graph::config) as they do, so
+//! its columns always line up with theirs.
+//!
+//! [`SpectrogramNode`]: crate::dsp::spectrogram::SpectrogramNode
+//! [`MultiDftNode`]: crate::dsp::dft::MultiDftNode
+
+// NEXT fold on the GPU next to the bank output once render nodes want chroma per frame.
+
+use crate::dsp::bank::BankTable;
+use crate::dsp::staging::{GainStage, Reference};
+use crate::dsp::units::Hertz;
+use crate::graph::config::{Config, BINS, MAX_FREQ, MIN_FREQ};
+use crate::graph::{
+    Frame, GraphEvent, Node, ParamHandle, ParamKind, ParamSpec, ParamValue, Params, PortKind,
+    PortSpec,
+};
+use crate::settings::DspSettings;
+use crate::MutateError;
+
+/// Pitch classes per octave.
+pub const CLASSES: usize = 12;
+
+/// Names of the pitch classes, starting from C.
+pub const NAMES: [&str; CLASSES] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Concert pitch.
+pub const A4: Hertz = Hertz(440.0);
+
+/// Fractional semitones of `freq` above C0 when A4 is `tuning`.
+pub fn semitones(freq: Hertz, tuning: Hertz) -> f64 {
+    // A4 is 57 semitones above C0.
+    12.0 * (freq.get() / tuning.get()).log2() + 57.0
+}
+
+/// The strongest pitch in a row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pitch {
+    pub freq: Hertz,
+    /// Index into [`NAMES`] of the nearest pitch class.
+    pub class: usize,
+    /// Deviation from the nearest pitch class, `-50.0..=50.0`.
+    pub cents: f64,
+    /// Amplitude of the strongest column.
+    pub strength: f32,
+}
+
+impl Pitch {
+    pub fn name(&self) -> &'static str {
+        NAMES[self.class]
+    }
+}
+
+/// Folds rows of a spectrogram onto pitch classes.  See the [module](self) docs.
+#[derive(Clone, Debug)]
+pub struct Chromagram {
+    centers: Vec<Hertz>,
+    /// Lower pitch class of each column and the fraction of its power that goes to the class
+    /// above.
+    split: Vec<(usize, f32)>,
+}
+
+impl Chromagram {
+    /// `centers` are the center frequency of each column, in ascending order.
+    pub fn new(centers: Vec<Hertz>, tuning: Hertz) -> Self {
+        let split = centers
+            .iter()
+            .map(|&f| {
+                let s = semitones(f, tuning);
+                let below = s.floor();
+                (
+                    below.rem_euclid(CLASSES as f64) as usize,
+                    (s - below) as f32,
+                )
+            })
+            .collect();
+        Self { centers, split }
+    }
+
+    /// Column centers of `table`, ordered by output column.
+    pub fn from_table(table: &BankTable, tuning: Hertz) -> Self {
+        let width = table
+            .bins
+            .iter()
+            .map(|b| b.output as usize + 1)
+            .max()
+            .unwrap_or(0);
+        let mut centers = vec![Hertz(0.0); width];
+        for bin in &table.bins {
+            centers[bin.output as usize] = Hertz(bin.center as f64);
+        }
+        Self::new(centers, tuning)
+    }
+
+    pub fn centers(&self) -> &[Hertz] {
+        &self.centers
+    }
+
+    /// Power per pitch class, normalized so that the strongest class is `1.0`.  Silence is all
+    /// zero.
+    pub fn fold(&self, row: &[f32]) -> [f32; CLASSES] {
+        let mut chroma = [0.0; CLASSES];
+        for (&x, &(below, above)) in row.iter().zip(&self.split) {
+            let power = x * x;
+            chroma[below] += (1.0 - above) * power;
+            chroma[(below + 1) % CLASSES] += above * power;
+        }
+        let max = chroma.iter().fold(0.0f32, |a, &b| a.max(b));
+        if max > 0.0 {
+            chroma.iter_mut().for_each(|c| *c /= max);
+        }
+        chroma
+    }
+
+    /// Strongest pitch in `row`, interpolated between its neighbor columns in log frequency.
+    pub fn dominant(&self, row: &[f32], tuning: Hertz) -> Option<Pitch> {
+        let row = &row[..row.len().min(self.centers.len())];
+        let (peak, &strength) = row.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1))?;
+        if strength <= 0.0 {
+            return None;
+        }
+        let log = |i: usize| self.centers[i].get().log2();
+        let mut octave = log(peak);
+        if peak > 0 && peak + 1 < row.len() {
+            // Parabola through the peak and its neighbors, in dB, assuming even log spacing.
+            let db = |i: usize| 20.0 * (row[i].max(1e-9) as f64).log10();
+            let (l, c, r) = (db(peak - 1), db(peak), db(peak + 1));
+            let denominator = l - 2.0 * c + r;
+            if denominator < 0.0 {
+                let offset = (0.5 * (l - r) / denominator).clamp(-0.5, 0.5);
+                let spacing = 0.5 * (log(peak + 1) - log(peak - 1));
+                octave += offset * spacing;
+            }
+        }
+        let freq = Hertz(octave.exp2());
+        let s = semitones(freq, tuning);
+        let nearest = s.round();
+        Some(Pitch {
+            freq,
+            class: nearest.rem_euclid(CLASSES as f64) as usize,
+            cents: 100.0 * (s - nearest),
+            strength,
+        })
+    }
+}
+
+/// Folding as a [gain stage](crate::dsp::staging).  Columns enter as amplitudes and leave
+/// normalized to the strongest class, so a lone tone lights its class fully at any level.
+pub struct ChromaFolding;
+
+impl GainStage for ChromaFolding {
+    fn name(&self) -> &'static str {
+        "chroma folding"
+    }
+
+    fn input(&self) -> Reference {
+        Reference::dbfs(0.0)
+    }
+
+    fn output(&self) -> Reference {
+        Reference::brightness(1.0)
+    }
+
+    fn apply(&self, _level: f64, _freq: Hertz) -> f64 {
+        1.0
+    }
+}
+
+/// A [`Chromagram`] in the graph.  Smooths chroma over time and optionally tracks the dominant
+/// pitch.
+pub struct ChromaNode {
+    chromagram: Chromagram,
+    tuning: Hertz,
+    /// Rows of input per second.
+    rate: f64,
+    chroma: [f32; CLASSES],
+    pitch: Option<Pitch>,
+    params: Option<ParamHandle>,
+    /// What the columns were laid out from, when they follow the configuration.
+    dsp: Option<DspSettings>,
+}
+
+impl ChromaNode {
+    const SMOOTHING: usize = 0;
+    const TRACKING: usize = 1;
+    const TUNING: usize = 2;
+
+    /// `centers` are the column centers of the rows that will be processed, `rate` is rows per
+    /// second.
+    pub fn new(centers: Vec<Hertz>, rate: f64) -> Self {
+        Self {
+            chromagram: Chromagram::new(centers, A4),
+            tuning: A4,
+            rate,
+            chroma: [0.0; CLASSES],
+            pitch: None,
+            params: None,
+            dsp: None,
+        }
+    }
+
+    /// Fold the rows of a bank designed from `dsp`, laid out again when the configuration changes
+    /// the bank.  `rate` is rows per second.
+    pub fn on_spectrum(dsp: DspSettings, rate: f64) -> Self {
+        let node = Self::new(dsp.centers(), rate);
+        Self {
+            dsp: Some(dsp),
+            ..node
+        }
+    }
+
+    /// Attach the handle returned by [`Graph::register`](crate::graph::Graph::register).
+    pub fn with_params(mut self, params: ParamHandle) -> Self {
+        self.params = Some(params);
+        self
+    }
+
+    /// Fold one row.  Picks up a changed `tuning` first.
+    pub fn process(&mut self, row: &[f32]) {
+        let (smoothing, tracking, tuning) = match &self.params {
+            Some(p) => (
+                p.f64(Self::SMOOTHING),
+                p.get(Self::TRACKING).as_bool(),
+                Hertz(p.f64(Self::TUNING)),
+            ),
+            None => (0.25, false, A4),
+        };
+        if tuning != self.tuning {
+            let centers = std::mem::take(&mut self.chromagram.centers);
+            self.chromagram = Chromagram::new(centers, tuning);
+            self.tuning = tuning;
+        }
+
+        let alpha = match smoothing * self.rate {
+            rows if rows > 0.0 => 1.0 - (-1.0 / rows).exp() as f32,
+            _ => 1.0,
+        };
+        let folded = self.chromagram.fold(row);
+        for (c, f) in self.chroma.iter_mut().zip(folded) {
+            *c += alpha * (f - *c);
+        }
+        self.pitch = match tracking {
+            true => self.chromagram.dominant(row, tuning),
+            false => None,
+        };
+    }
+
+    /// Smoothed chroma.  Each class is `0.0..=1.0`.
+    pub fn chroma(&self) -> &[f32; CLASSES] {
+        &self.chroma
+    }
+
+    /// The pitch class with the most smoothed energy, a rough stand-in for the key.
+    pub fn strongest(&self) -> Option<usize> {
+        let (class, &level) = self
+            .chroma
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        (level > 0.0).then_some(class)
+    }
+
+    /// Dominant pitch of the last row, when `tracking` is on.
+    pub fn pitch(&self) -> Option<Pitch> {
+        self.pitch
+    }
+}
+
+impl Params for ChromaNode {
+    fn param_specs(&self) -> &'static [ParamSpec] {
+        &[
+            ParamSpec {
+                name: "smoothing",
+                description: "seconds for chroma to follow a change of harmony",
+                kind: ParamKind::Float { min: 0.0, max: 4.0 },
+                default: ParamValue::Float(0.25),
+            },
+            ParamSpec {
+                name: "tracking",
+                description: "follow the single strongest pitch",
+                kind: ParamKind::Bool,
+                default: ParamValue::Bool(false),
+            },
+            ParamSpec {
+                name: "tuning",
+                description: "frequency of A4, in Hz",
+                kind: ParamKind::Float {
+                    min: 415.0,
+                    max: 466.0,
+                },
+                default: ParamValue::Float(440.0),
+            },
+        ]
+    }
+}
+
+impl Node for ChromaNode {
+    fn inputs(&self) -> &'static [PortSpec] {
+        &[PortSpec {
+            name: "row",
+            kind: PortKind::Row,
+        }]
+    }
+
+    fn outputs(&self) -> &'static [PortSpec] {
+        &[
+            PortSpec {
+                name: "chroma",
+                kind: PortKind::Row,
+            },
+            PortSpec {
+                name: "pitch",
+                kind: PortKind::Scalar,
+            },
+        ]
+    }
+
+    fn gain_stage(&self) -> Option<&dyn GainStage> {
+        Some(&ChromaFolding)
+    }
+
+    fn watches(&self) -> &'static [&'static str] {
+        &[MIN_FREQ, MAX_FREQ, BINS]
+    }
+
+    fn attach(&mut self, params: ParamHandle) {
+        self.params = Some(params);
+    }
+
+    /// Lays the columns out again when built [`on_spectrum`](Self::on_spectrum).
+    fn update(&mut self, config: &Config, _changed: &[&str]) -> Result<(), MutateError> {
+        let Some(current) = &self.dsp else {
+            return Ok(());
+        };
+        let dsp = DspSettings {
+            min_freq: config.frequency(MIN_FREQ).unwrap_or(current.min_freq),
+            max_freq: config.frequency(MAX_FREQ).unwrap_or(current.max_freq),
+            bins: config.count(BINS).unwrap_or(current.bins),
+        };
+        if Some(&dsp) != self.dsp.as_ref() {
+            self.chromagram = Chromagram::new(dsp.centers(), self.tuning);
+            self.dsp = Some(dsp);
+        }
+        Ok(())
+    }
+
+    /// Emits `pitch` in Hz only while tracking finds one.
+    fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+        if let Some(GraphEvent::Row(row)) = frame.input(0) {
+            self.process(row);
+        }
+        frame.emit(0, GraphEvent::Row(self.chroma.to_vec().into()));
+        if let Some(pitch) = self.pitch {
+            frame.emit(1, GraphEvent::Scalar(pitch.freq.get()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::{ConfigValue, Graph};
+
+    /// Equal tempered semitones from C2 for three octaves.
+    fn semitone_centers() -> Vec<Hertz> {
+        (0..36)
+            .map(|i| Hertz(440.0 * ((i as f64 - 33.0) / 12.0).exp2()))
+            .collect()
+    }
+
+    #[test]
+    fn test_chroma_fold() {
+        assert_eq!(semitones(A4, A4), 57.0);
+        let chromagram = Chromagram::new(semitone_centers(), A4);
+        assert_eq!(chromagram.split[0], (0, 0.0));
+
+        // E in every octave, with a quieter G in one.
+        let mut row = vec![0.0; 36];
+        for octave in 0..3 {
+            row[12 * octave + 4] = 0.5;
+        }
+        row[19] = 0.5;
+        let chroma = chromagram.fold(&row);
+        assert_eq!(chroma[4], 1.0);
+        assert!((chroma[7] - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(chroma.iter().filter(|&&c| c > 0.0).count(), 2);
+
+        // Quarter tone banks split evenly.
+        let quarter = Chromagram::new(vec![Hertz(440.0 * (0.5f64 / 12.0).exp2())], A4);
+        assert_eq!(
+            quarter.fold(&[1.0]),
+            [0., 0., 0., 0., 0., 0., 0., 0., 0., 1., 1., 0.]
+        );
+    }
+
+    #[test]
+    fn test_chroma_dominant_pitch() {
+        let centers = semitone_centers();
+        let chromagram = Chromagram::new(centers.clone(), A4);
+        assert_eq!(chromagram.dominant(&[0.0; 36], A4), None);
+
+        // Symmetric neighbors put the peak exactly on A3.
+        let mut row = vec![0.0; 36];
+        row[21] = 1.0;
+        row[20] = 0.5;
+        row[22] = 0.5;
+        let pitch = chromagram.dominant(&row, A4).unwrap();
+        assert_eq!(pitch.name(), "A");
+        assert!(pitch.cents.abs() < 1e-6);
+        assert!((pitch.freq.get() - 220.0).abs() < 1e-6);
+
+        // A louder upper neighbor pulls it sharp.
+        row[22] = 0.8;
+        let pitch = chromagram.dominant(&row, A4).unwrap();
+        assert_eq!(pitch.class, 9);
+        assert!(pitch.cents > 10.0 && pitch.cents < 50.0, "{}", pitch.cents);
+
+        // Baroque tuning reads the same column as flat of A.
+        let pitch = chromagram.dominant(&row, Hertz(415.0)).unwrap();
+        assert_eq!(pitch.name(), "A#");
+    }
+
+    #[test]
+    fn test_chroma_node() {
+        let mut graph = Graph::new();
+        let node = ChromaNode::new(semitone_centers(), 100.0);
+        let (_, params) = graph.register("chroma", &node).unwrap();
+        let mut node = node.with_params(params);
+        assert_eq!(node.strongest(), None);
+
+        let mut row = vec![0.0; 36];
+        row[14] = 1.0;
+        node.process(&row);
+        assert_eq!(node.strongest(), Some(2));
+        assert!(node.chroma()[2] < 0.1, "smoothed over 25 rows");
+        assert_eq!(node.pitch(), None);
+
+        graph
+            .set("chroma/smoothing", ParamValue::Float(0.0))
+            .unwrap();
+        graph
+            .set("chroma/tracking", ParamValue::Bool(true))
+            .unwrap();
+        node.process(&row);
+        assert_eq!(node.chroma()[2], 1.0);
+        assert_eq!(node.pitch().unwrap().name(), "D");
+    }
+
+    #[test]
+    fn test_chroma_on_spectrum() {
+        let mut node = ChromaNode::on_spectrum(DspSettings::default(), 100.0);
+        assert_eq!(
+            node.chromagram.centers().len(),
+            DspSettings::default().bins as usize
+        );
+
+        // A bank of one column per semitone, as in `semitone_centers`.
+        let mut graph = Graph::new();
+        let c2 = 440.0 * (-33.0f64 / 12.0).exp2();
+        graph.configure(
+            MIN_FREQ,
+            ConfigValue::Frequency(c2 * (-1.0f64 / 24.0).exp2()),
+        );
+        graph.configure(
+            MAX_FREQ,
+            ConfigValue::Frequency(c2 * (71.0f64 / 24.0).exp2()),
+        );
+        graph.configure(BINS, ConfigValue::Count(36));
+        node.update(graph.config(), &[BINS]).unwrap();
+        let centers = node.chromagram.centers();
+        assert_eq!(centers.len(), 36);
+        for (center, semitone) in centers.iter().zip(semitone_centers()) {
+            assert!((center.get() / semitone.get()).log2().abs() < 1e-9);
+        }
+
+        let mut row = vec![0.0; 36];
+        row[14] = 1.0;
+        node.process(&row);
+        assert_eq!(node.strongest(), Some(2));
+    }
+}
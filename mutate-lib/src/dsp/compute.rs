@@ -0,0 +1,388 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Compute
+//!
+//! [`GpuSpectrogram`] runs a [`BankTable`] in a compute shader.  The table is uploaded once, as
+//! written by [`BankTable::to_bytes`].  Audio is anti-aliased and decimated on the host by the same
+//! front end as the CPU [`Spectrogram`](super::spectrogram::Spectrogram), then every call to
+//! [`upload`](GpuSpectrogram::upload) copies the newest decimated samples of each rate into a
+//! storage buffer.  One dispatch sums every bin's window and writes one amplitude per output
+//! column.
+//!
+//! The output buffer stays registered for as long as the spectrogram lives, so render nodes can
+//! read [`GpuSpectrogram::output_idx`] directly.  Like every registration, the buffers are usable
+//! after the next [`Descriptors::flush`](crate::gpu::device::descriptors::Descriptors::flush).  The workbench `gpu` command diffs it against the CPU
+//! reference.
+//!
+//! ## Async Compute
+//!
+//! Dispatches belong on [`Queues::async_compute`](crate::gpu::device::queue::Queues::async_compute)
+//! when the device has one, overlapping with rendering.  The output then has to move to the
+//! graphics family before drawing reads it, with the pair of barriers from
+//! [`output_transfer`](GpuSpectrogram::output_transfer).  Each dispatch overwrites every column,
+//! so the output never has to be handed back.
+//!
+//! ## Skipped Dispatches
+//!
+//! When the throttle halves the analysis rate, an [`OutputHistory`] keeps copies of the last two
+//! outputs and fills each skipped frame's output from them, on the device, the way
+//! [`extrapolate`](crate::graph::throttle::extrapolate) does on the host.
+//!
+//! ## Input Layout
+//!
+//! The input buffer is 32bit words.  Word `i` for each bin `i` is the word where that bin's window
+//! begins, or [`NOT_YET`] before the bin has summed.  Each rate's newest samples follow, oldest
+//! first.  Rates keep one `repeat` more than their longest window so that a bin can sum the window
+//! that ended at its last repeat, exactly as the CPU reference holds it.
+
+// NEXT decimation belongs on the device once the audio import ring feeds the bank directly.  The
+// host front end keeps the two implementations comparable while the shader is brought up.
+// NEXT sliding Goertzel terms instead of direct sums.  This shader is the ground truth for that.
+
+use ash::vk;
+
+use crate::dsp::bank::BankTable;
+use crate::dsp::spectrogram::Rate;
+use crate::gpu::device::descriptors::Handle;
+use crate::gpu::prelude::*;
+use crate::gpu::resource::buffer;
+use crate::MutateError;
+
+/// Window start for a bin that has not summed yet.
+pub const NOT_YET: u32 = u32::MAX;
+
+/// Threads per workgroup, from the shader.
+const WORKGROUP: u32 = 64;
+
+#[compute_pipeline(
+    compute = stage!("dsp/bank", Compute, c"main"),
+    push = push!(BankConstants {
+        pub table_idx: SsboIdx,
+        pub input_idx: SsboIdx,
+        pub output_idx: SsboIdx,
+        pub bin_count: UInt,
+    }),
+)]
+pub struct BankPipeline;
+
+#[compute_pipeline(
+    compute = stage!("dsp/extrapolate", Compute, c"main"),
+    push = push!(ExtrapolateConstants {
+        pub older_idx: SsboIdx,
+        pub newer_idx: SsboIdx,
+        pub output_idx: SsboIdx,
+        pub count: UInt,
+    }),
+)]
+pub struct ExtrapolatePipeline;
+
+/// A bank table running on the device.  See the [module](self) docs.
+pub struct GpuSpectrogram {
+    pipeline: ComputePipeline<BankPipeline>,
+    table: BankTable,
+    channels: usize,
+    rates: Vec<Rate>,
+    /// Rate of each bin.
+    bin_rates: Vec<usize>,
+    /// Word where each rate's samples begin in the input buffer.
+    rate_offsets: Vec<usize>,
+    /// Reused for resampler output.
+    scratch: Vec<f32>,
+
+    table_buffer: buffer::MappedAllocation<u8>,
+    table_idx: Handle<SsboIdx>,
+    input_buffer: buffer::MappedAllocation<u32>,
+    input_idx: Handle<SsboIdx>,
+    output_buffer: buffer::MappedAllocation<f32>,
+    output_idx: Handle<SsboIdx>,
+}
+
+impl GpuSpectrogram {
+    pub fn new(device: &Device, table: BankTable) -> Result<Self, MutateError> {
+        let (rates, bin_rates) = Rate::for_table(&table, |bin| {
+            (bin.window_length + bin.repeat.max(1) - 1) as usize
+        });
+        let mut rate_offsets = Vec::with_capacity(rates.len());
+        let mut words = table.bins.len();
+        for rate in &rates {
+            rate_offsets.push(words);
+            words += rate.recent.len();
+        }
+        let width = table
+            .bins
+            .iter()
+            .map(|b| b.output as usize + 1)
+            .max()
+            .unwrap_or(0);
+
+        let bytes = table.to_bytes();
+        let mut table_buffer = buffer::MappedAllocation::<u8>::new(bytes.len(), device)?;
+        table_buffer.set_name(device, "bank table");
+        table_buffer.as_mut_slice().copy_from_slice(&bytes);
+        table_buffer.flush(device)?;
+        let table_idx = table_buffer.register(device)?;
+
+        let mut input_buffer = buffer::MappedAllocation::<u32>::new(words.max(1), device)?;
+        input_buffer.set_name(device, "bank input");
+        input_buffer.as_mut_slice().fill(0);
+        input_buffer.as_mut_slice()[..table.bins.len()].fill(NOT_YET);
+        input_buffer.flush(device)?;
+        let input_idx = input_buffer.register(device)?;
+
+        let mut output_buffer = buffer::MappedAllocation::<f32>::new(width.max(1), device)?;
+        output_buffer.set_name(device, "bank output");
+        output_buffer.as_mut_slice().fill(0.0);
+        output_buffer.flush(device)?;
+        let output_idx = output_buffer.register(device)?;
+
+        Ok(Self {
+            pipeline: ComputePipeline::<BankPipeline>::new(device)?,
+            table,
+            channels: 1,
+            rates,
+            bin_rates,
+            rate_offsets,
+            scratch: Vec::new(),
+            table_buffer,
+            table_idx,
+            input_buffer,
+            input_idx,
+            output_buffer,
+            output_idx,
+        })
+    }
+
+    /// Frames have `channels` interleaved samples, averaged to mono.
+    pub fn with_channels(mut self, channels: usize) -> Self {
+        self.channels = channels.max(1);
+        self
+    }
+
+    /// Columns of output, one per bin output.
+    pub fn width(&self) -> usize {
+        self.output_buffer.len
+    }
+
+    /// Amplitudes of every column, written by each dispatch.
+    pub fn output_idx(&self) -> SsboIdx {
+        self.output_idx.index()
+    }
+
+    pub fn output_buffer(&self) -> vk::Buffer {
+        self.output_buffer.buffer
+    }
+
+    /// Move the output from the family that dispatched to the family that reads it.  Record the
+    /// release after [`record`](Self::record), with `COMPUTE_SHADER` and `SHADER_STORAGE_WRITE`,
+    /// and the acquire before the first read.  Both do nothing when the families match.
+    pub fn output_transfer(&self, src_family: u32, dst_family: u32) -> BufferTransfer {
+        BufferTransfer::new(self.output_buffer.buffer, src_family, dst_family)
+    }
+
+    /// Feed interleaved frames through the anti-aliasing front end.  Nothing reaches the device
+    /// until [`upload`](Self::upload).
+    pub fn push(&mut self, frames: &[f32]) {
+        let mono: Vec<f32> = frames
+            .chunks_exact(self.channels)
+            .map(|f| f.iter().sum::<f32>() / self.channels as f32)
+            .collect();
+        for rate in self.rates.iter_mut() {
+            rate.advance(&mono, &mut self.scratch, |_| {});
+        }
+    }
+
+    /// Copy the newest decimated samples and each bin's window start to the device.
+    pub fn upload(&mut self, device: &Device) -> Result<(), MutateError> {
+        let words = self.input_buffer.as_mut_slice();
+        for ((bin, &rate), start) in self
+            .table
+            .bins
+            .iter()
+            .zip(&self.bin_rates)
+            .zip(words.iter_mut())
+        {
+            let r = &self.rates[rate];
+            *start = match r.lag(bin.repeat) {
+                Some(lag) => {
+                    let end = self.rate_offsets[rate] + r.recent.len() - lag;
+                    (end - bin.window_length as usize) as u32
+                }
+                None => NOT_YET,
+            };
+        }
+        for (rate, &offset) in self.rates.iter().zip(&self.rate_offsets) {
+            let samples = &mut words[offset..offset + rate.recent.len()];
+            for (word, x) in samples.iter_mut().zip(&rate.recent) {
+                *word = x.to_bits();
+            }
+        }
+        self.input_buffer.flush(device)?;
+        Ok(())
+    }
+
+    /// Record the dispatch.  Buffers written by [`upload`](Self::upload) are made visible first.
+    pub fn record(&self, device: &Device, cb: vk::CommandBuffer) {
+        self.input_buffer.barrier_compute_pre(&cb, device);
+        let bin_count = self.table.bins.len() as u32;
+        self.pipeline.push(
+            device,
+            cb,
+            &BankConstants {
+                table_idx: self.table_idx.index(),
+                input_idx: self.input_idx.index(),
+                output_idx: self.output_idx.index(),
+                bin_count: bin_count.into(),
+            },
+        );
+        self.pipeline
+            .dispatch(device, cb, bin_count.div_ceil(WORKGROUP), 1, 1);
+    }
+
+    /// Read back the output after a recorded dispatch has retired.  The caller is responsible for
+    /// the barrier to host reads.
+    pub fn read_output(&mut self, device: &Device) -> Result<&[f32], MutateError> {
+        self.output_buffer.invalidate(device)?;
+        Ok(self.output_buffer.as_mut_slice())
+    }
+
+    pub fn destroy(self, device: &Device) -> Result<(), MutateError> {
+        self.pipeline.destroy(device);
+        device.descriptors.release(self.table_idx);
+        device.descriptors.release(self.input_idx);
+        device.descriptors.release(self.output_idx);
+        self.table_buffer.destroy(device)?;
+        self.input_buffer.destroy(device)?;
+        self.output_buffer.destroy(device)?;
+        Ok(())
+    }
+}
+
+/// The last two outputs of a [`GpuSpectrogram`], for frames that skip its dispatch.  See the
+/// [module](self) docs.
+pub struct OutputHistory {
+    pipeline: ComputePipeline<ExtrapolatePipeline>,
+    slots: [(buffer::MappedAllocation<f32>, Handle<SsboIdx>); 2],
+    /// Slot holding the newest output.
+    newest: usize,
+    /// Outputs kept, up to two.  Nothing is filled until both slots hold one.
+    kept: u32,
+}
+
+impl OutputHistory {
+    /// Slots as wide as the output of `bank`.
+    pub fn new(device: &Device, bank: &GpuSpectrogram) -> Result<Self, MutateError> {
+        let slot = |name: &str| -> Result<_, MutateError> {
+            let mut b = buffer::MappedAllocation::<f32>::new(bank.width().max(1), device)?;
+            b.set_name(device, name);
+            b.as_mut_slice().fill(0.0);
+            b.flush(device)?;
+            let idx = b.register(device)?;
+            Ok((b, idx))
+        };
+        Ok(Self {
+            pipeline: ComputePipeline::<ExtrapolatePipeline>::new(device)?,
+            slots: [slot("bank history 0")?, slot("bank history 1")?],
+            newest: 1,
+            kept: 0,
+        })
+    }
+
+    /// Record a copy of the output written by the dispatch just recorded.  The slot overwritten is
+    /// the older one, so reads by an earlier fill finish first.
+    pub fn record_keep(&mut self, device: &Device, cb: vk::CommandBuffer, bank: &GpuSpectrogram) {
+        let next = 1 - self.newest;
+        let slot = self.slots[next].0.buffer;
+        let barrier = |buffer, src_access_mask, dst_access_mask| vk::BufferMemoryBarrier {
+            src_access_mask,
+            dst_access_mask,
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            buffer,
+            size: vk::WHOLE_SIZE,
+            ..Default::default()
+        };
+        let before = [
+            barrier(
+                bank.output_buffer(),
+                vk::AccessFlags::SHADER_WRITE,
+                vk::AccessFlags::TRANSFER_READ,
+            ),
+            barrier(
+                slot,
+                vk::AccessFlags::SHADER_READ,
+                vk::AccessFlags::TRANSFER_WRITE,
+            ),
+        ];
+        let after = [barrier(
+            slot,
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::AccessFlags::SHADER_READ,
+        )];
+        let copy = vk::BufferCopy {
+            src_offset: 0,
+            dst_offset: 0,
+            size: (bank.width().max(1) * std::mem::size_of::<f32>()) as vk::DeviceSize,
+        };
+        unsafe {
+            let raw = device.as_raw();
+            raw.cmd_pipeline_barrier(
+                cb,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &before,
+                &[],
+            );
+            raw.cmd_copy_buffer(cb, bank.output_buffer(), slot, &[copy]);
+            raw.cmd_pipeline_barrier(
+                cb,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &after,
+                &[],
+            );
+        }
+        self.newest = next;
+        self.kept = (self.kept + 1).min(2);
+    }
+
+    /// Record the fill of the output of `bank` in place of its dispatch.  The caller makes the
+    /// output writable first, as for [`GpuSpectrogram::record`].  Until two outputs are kept, the
+    /// last output is left as it is.
+    pub fn record_extrapolate(
+        &self,
+        device: &Device,
+        cb: vk::CommandBuffer,
+        bank: &GpuSpectrogram,
+    ) {
+        if self.kept < 2 {
+            return;
+        }
+        let count = bank.width() as u32;
+        self.pipeline.push(
+            device,
+            cb,
+            &ExtrapolateConstants {
+                older_idx: self.slots[1 - self.newest].1.index(),
+                newer_idx: self.slots[self.newest].1.index(),
+                output_idx: bank.output_idx(),
+                count: count.into(),
+            },
+        );
+        self.pipeline
+            .dispatch(device, cb, count.div_ceil(WORKGROUP), 1, 1);
+    }
+
+    pub fn destroy(self, device: &Device) -> Result<(), MutateError> {
+        self.pipeline.destroy(device);
+        for (buffer, idx) in self.slots {
+            device.descriptors.release(idx);
+            buffer.destroy(device)?;
+        }
+        Ok(())
+    }
+}
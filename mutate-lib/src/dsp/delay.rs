@@ -0,0 +1,205 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Delay
+//!
+//! Sound and picture line up only if every bin's delay is known.  A filter that rises fast can
+//! still be late, and two filters with the same rise can disagree by several milliseconds.
+//!
+//! Linear filters have a phase response.  [`tone_response`] correlates a settled tone against the
+//! input to read gain and phase at one frequency.  [`group_delay`] differentiates phase across a
+//! small step in frequency, which is how long the envelope of a narrowband signal takes to come
+//! through.  Phase delay, `-phase / omega`, is reported alongside it but only matters for
+//! aligning carriers, not visuals.
+//!
+//! Filters that report magnitudes, like [`Dft`](super::dft::Dft), have no phase.
+//! [`envelope_delay`] measures them directly by comparing the energy centroid of a tone burst with
+//! the centroid of the output.  For linear filters it agrees with group delay when the burst is
+//! long enough to fit inside the passband.
+
+use std::f64::consts::{PI, TAU};
+
+use num_complex::Complex;
+
+use super::units::{Hertz, SampleRate, Samples};
+use super::Filter;
+
+/// Gain and phase of a filter at one frequency.
+#[derive(Clone, Copy, Debug)]
+pub struct ToneResponse {
+    pub freq: Hertz,
+    pub gain: f64,
+    /// Radians in `(-PI, PI]`.  Negative is late.
+    pub phase: f64,
+}
+
+impl ToneResponse {
+    /// Delay of the carrier, in samples.  Ambiguous by whole cycles.
+    pub fn phase_delay(&self, fs: SampleRate) -> f64 {
+        -self.phase / fs.omega(self.freq)
+    }
+}
+
+/// Drive `filter` with a sine at `freq` for `settle` samples, then correlate input and output for
+/// about `measure` more.  The correlation is rounded to whole cycles so that the tone's own
+/// harmonics cancel.
+pub fn tone_response(
+    filter: &mut dyn Filter,
+    freq: Hertz,
+    fs: SampleRate,
+    settle: Samples,
+    measure: Samples,
+) -> ToneResponse {
+    let omega = fs.omega(freq);
+    let cycles = fs.waves(freq, measure).round().max(1.0);
+    let measure = fs.cycles(freq, cycles).get();
+    let mut input = Complex::new(0.0, 0.0);
+    let mut output = Complex::new(0.0, 0.0);
+    for n in 0..settle.get() + measure {
+        let x = (omega * n as f64).sin();
+        let y = filter.process(x as f32) as f64;
+        if n >= settle.get() {
+            let rotor = Complex::from_polar(1.0, -omega * n as f64);
+            input += rotor * x;
+            output += rotor * y;
+        }
+    }
+    let h = output / input;
+    ToneResponse {
+        freq,
+        gain: h.norm(),
+        phase: h.arg(),
+    }
+}
+
+/// Group delay at `freq`, in samples, from the phase at `freq - step` and `freq + step`.  `make`
+/// builds a fresh filter for each tone.  `step` should be well inside the passband.
+pub fn group_delay(
+    mut make: impl FnMut() -> Box<dyn Filter>,
+    freq: Hertz,
+    step: Hertz,
+    fs: SampleRate,
+    settle: Samples,
+    measure: Samples,
+) -> f64 {
+    let mut phase = |f: f64| tone_response(make().as_mut(), Hertz(f), fs, settle, measure).phase;
+    let below = phase(freq.get() - step.get());
+    let above = phase(freq.get() + step.get());
+    // Unwrap the smaller of the two possible differences.
+    let dphase = (above - below + PI).rem_euclid(TAU) - PI;
+    let domega = fs.omega(Hertz(2.0 * step.get()));
+    -dphase / domega
+}
+
+/// Delay of the output's energy centroid behind a Hann shaped tone burst of `burst` samples, in
+/// samples.  Output is collected for `tail` samples after the burst ends.
+pub fn envelope_delay(
+    filter: &mut dyn Filter,
+    freq: Hertz,
+    fs: SampleRate,
+    burst: Samples,
+    tail: Samples,
+) -> f64 {
+    let omega = fs.omega(freq);
+    let len = burst.get().max(2);
+    let centroid = |acc: (f64, f64)| acc.0 / acc.1;
+    let mut input = (0.0, 0.0);
+    let mut output = (0.0, 0.0);
+    for n in 0..len + tail.get() {
+        let x = match n < len {
+            true => {
+                let envelope = 0.5 - 0.5 * (TAU * n as f64 / (len - 1) as f64).cos();
+                envelope * (omega * n as f64).sin()
+            }
+            false => 0.0,
+        };
+        let y = filter.process(x as f32) as f64;
+        input = (input.0 + n as f64 * x * x, input.1 + x * x);
+        output = (output.0 + n as f64 * y * y, output.1 + y * y);
+    }
+    centroid(output) - centroid(input)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dsp::dft::Dft;
+    use crate::dsp::iir::Biquad;
+    use crate::dsp::window::WindowFunction;
+    use crate::dsp::FilterArgs;
+
+    /// Whole samples of pure delay.
+    struct Late(std::collections::VecDeque<f32>);
+
+    impl Filter for Late {
+        fn process(&mut self, sample: f32) -> f32 {
+            self.0.push_back(sample);
+            self.0.pop_front().unwrap()
+        }
+
+        fn from_args(_args: &FilterArgs) -> Self {
+            unimplemented!()
+        }
+    }
+
+    fn late(n: usize) -> Box<dyn Filter> {
+        Box::new(Late(std::iter::repeat_n(0.0, n).collect()))
+    }
+
+    #[test]
+    fn test_delay_of_delay_line() {
+        let fs = SampleRate(48_000.0);
+        let settle = Samples(64);
+        let measure = Samples(4800);
+        let tone = tone_response(late(7).as_mut(), Hertz(1000.0), fs, settle, measure);
+        assert!((tone.gain - 1.0).abs() < 1e-4);
+        assert!((tone.phase_delay(fs) - 7.0).abs() < 1e-3);
+
+        let gd = group_delay(|| late(7), Hertz(1000.0), Hertz(10.0), fs, settle, measure);
+        assert!((gd - 7.0).abs() < 1e-3, "{gd}");
+
+        let env = envelope_delay(
+            late(7).as_mut(),
+            Hertz(1000.0),
+            fs,
+            Samples(960),
+            Samples(64),
+        );
+        assert!((env - 7.0).abs() < 1e-3, "{env}");
+    }
+
+    #[test]
+    fn test_delay_of_bandpass() {
+        // One resonant stage at the center is late by 2Q / omega.
+        let fs = SampleRate(48_000.0);
+        let args = FilterArgs {
+            q: 8.0,
+            center: Hertz(1000.0),
+            fs,
+            ..Default::default()
+        };
+        let expected = 2.0 * args.q / fs.omega(args.center);
+        let settle = fs.cycles(args.center, 200.0);
+        let measure = fs.cycles(args.center, 50.0);
+        let make = || Box::new(Biquad::<f32>::from_args(&args)) as Box<dyn Filter>;
+        let gd = group_delay(make, args.center, Hertz(1.0), fs, settle, measure);
+        assert!((gd / expected - 1.0).abs() < 0.02, "{gd} vs {expected}");
+
+        // A long burst stays inside the passband, so the envelope agrees.
+        let burst = fs.cycles(args.center, 400.0);
+        let env = envelope_delay(make().as_mut(), args.center, fs, burst, settle);
+        assert!((env / expected - 1.0).abs() < 0.05, "{env} vs {expected}");
+
+        // A DFT is late by at least half its window, plus some of the re-summing cadence.
+        let length = 960;
+        let mut dft = Dft::new(1000.0, fs.get(), length, WindowFunction::Hamming);
+        let env = envelope_delay(
+            &mut dft,
+            args.center,
+            fs,
+            Samples(8 * length),
+            Samples(length),
+        );
+        assert!(env > 0.5 * length as f64 && env < length as f64, "{env}");
+    }
+}
@@ -4,9 +4,11 @@
 //! # Discrete Fourier Transform
 //!
 //! This module contains a basic CPU implementation for a single DFT and several window functions
-//! for engineering the bins of filter banks for implementation on the GPU.
+//! for engineering the bins of filter banks for implementation on the GPU.  [`MultiDftNode`] runs
+//! the multi-window layout across a whole bank in the graph.
 
 use std::f64::consts::{PI as PI64, TAU as TAU64};
+use std::sync::Arc;
 
 use num_complex::Complex;
 use num_traits::Zero;
@@ -14,7 +16,15 @@ use num_traits::Zero;
 use crate::tree::TreeSum;
 use mutate_slide::SlidingWindow;
 
+use crate::dsp::staging::{GainStage, WindowNormalization};
 use crate::dsp::{self, window};
+use crate::graph::config::{Config, BINS, MAX_FREQ, MIN_FREQ};
+use crate::graph::{
+    Frame, GraphEvent, Node, Offload, ParamHandle, ParamSpec, Params, PortKind, PortSpec, Priority,
+    WorkerPool,
+};
+use crate::settings::{DspSettings, BANK_WINDOW};
+use crate::MutateError;
 
 /// ## Discrete Fourier Transform
 ///
@@ -37,16 +47,18 @@ use crate::dsp::{self, window};
 ///
 /// The output is effectively an amplitude, as if we have seen a constant tone for the duration
 /// between updates.  This will produce roughly usable peaks, RMS, and derived measurements like
-/// rise time etc.
+/// rise time etc.  See [`Normalization`] for making outputs of different windows comparable.
 pub struct Dft {
     /// Do the right thing and choose either Dolph-Chebyshev or write a new window and combine it
     /// with a a pre-filter.
     window_choice: window::WindowFunction,
-    /// Multiplying the ring buffer by the window yields an output.  This CPU DFT only implements
-    /// one window.  Parallelization takes place on the GPU implementation.
+    /// Multiplying the ring buffer by the window yields an output.  This DFT only implements one
+    /// window.  See [`MultiDft`] for several windows sharing one ring, as on the GPU.
     window_factors: Vec<f32>,
-    /// Normalize output by the weight sum of the window.
-    window_norm: f32,
+    /// How outputs are made comparable across windows.
+    normalization: Normalization,
+    /// Scale from the windowed sum to the output, from the window's normalization factors.
+    output_scale: f32,
     /// The window of complex numbers resulting from the Goertzel algorithm application.  This must
     /// be windowed and summed to produce an output.
     goertzel_terms: SlidingWindow<Vec<Complex<f32>>>,
@@ -89,7 +101,7 @@ impl dsp::Filter for Dft {
                 .zip(self.window_factors.iter())
                 .map(|(g, w)| g.scale(*w))
                 .tree_sum();
-            self.last_output = 2.0 * sum.norm() / self.window_norm;
+            self.last_output = sum.norm() * self.output_scale;
 
             // Normalize the phase to prevent drift over time.
             let norm = (self.phase.re * self.phase.re + self.phase.im * self.phase.im).sqrt();
@@ -101,9 +113,9 @@ impl dsp::Filter for Dft {
     }
 
     fn from_args(args: &dsp::FilterArgs) -> Self {
-        let length = (args.q * args.fs / args.center).ceil() as usize;
+        let length = (args.q * args.fs.per_cycle(args.center)).ceil() as usize;
 
-        Dft::new(args.center, args.fs, length, args.window_choice)
+        Dft::new(args.center.get(), args.fs.get(), length, args.window_choice)
     }
 }
 
@@ -123,11 +135,13 @@ impl Dft {
             re: cos as f32,
             im: sin as f32,
         };
-        let window_norm = window_factors.iter().sum();
+        let normalization = Normalization::default();
+        let output_scale = normalization.scale(&window_choice, length);
         Self {
             center: center as f32,
             window_choice,
-            window_norm,
+            normalization,
+            output_scale,
             goertzel_terms,
             window_factors,
             gain: 1.0,
@@ -140,15 +154,317 @@ impl Dft {
         }
     }
 
+    pub fn with_normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = normalization;
+        self.output_scale = normalization.scale(&self.window_choice, self.length());
+        self
+    }
+
+    pub fn normalization(&self) -> Normalization {
+        self.normalization
+    }
+
     /// Reutrn the number of samples that must be processed to completely saturate the window.
     pub fn length(&self) -> usize {
         self.window_factors.len()
     }
 }
 
+/// ## Multi-Window DFT
+///
+/// The GPU layout for [`Dft`].  One ring of Goertzel terms is bound only by the center frequency,
+/// so any number of windows can re-sum it.  Each window reads the newest terms, as many as its
+/// length, and keeps its own COLA cadence and output.  Bins that want several Q or noise
+/// tolerances at one center share the ring instead of repeating the rotation.
+///
+/// Every output matches a [`Dft`] with the same center, length, and window.
+pub struct MultiDft {
+    /// Long enough for the longest window.
+    goertzel_terms: SlidingWindow<Vec<Complex<f32>>>,
+    center: f32,
+    phase: Complex<f32>,
+    velocity: Complex<f32>,
+    resums: Vec<Resum>,
+    /// Latest output of each window, in the order they were given.
+    outputs: Vec<f32>,
+}
+
+/// One window over the shared ring.
+struct Resum {
+    window_choice: window::WindowFunction,
+    window_factors: Vec<f32>,
+    output_scale: f32,
+    window_repeat: u32,
+    repeated: u32,
+}
+
+impl MultiDft {
+    /// # Panics
+    ///
+    /// `windows` must not be empty.
+    pub fn new(center: f64, sample_rate: f64, windows: &[(usize, window::WindowFunction)]) -> Self {
+        assert!(!windows.is_empty(), "a MultiDft needs at least one window");
+        let longest = windows.iter().map(|(length, _)| *length).max().unwrap();
+        let normalization = Normalization::default();
+        let resums = windows
+            .iter()
+            .map(|&(length, window_choice)| Resum {
+                window_choice,
+                window_factors: window_choice.make_window_32(length),
+                output_scale: normalization.scale(&window_choice, length),
+                window_repeat: window_choice.repeat(length),
+                repeated: 0,
+            })
+            .collect();
+        let (sin, cos) = ((TAU64 * center) / sample_rate).sin_cos();
+        Self {
+            goertzel_terms: SlidingWindow::<Vec<Complex<f32>>>::new_heap(longest),
+            center: center as f32,
+            phase: Complex { re: 1.0, im: 0.0 },
+            velocity: Complex {
+                re: cos as f32,
+                im: sin as f32,
+            },
+            resums,
+            outputs: vec![0.0; windows.len()],
+        }
+    }
+
+    /// Outputs of every window.  As with [`Dft`], each repeats until its window is re-summed.
+    pub fn process(&mut self, sample: f32) -> &[f32] {
+        self.goertzel_terms.push(Complex {
+            re: sample * self.phase.re,
+            im: -sample * self.phase.im,
+        });
+        self.phase = Complex {
+            re: self.phase.re * self.velocity.re - self.phase.im * self.velocity.im,
+            im: self.phase.re * self.velocity.im + self.phase.im * self.velocity.re,
+        };
+
+        let longest = self.goertzel_terms.len();
+        let mut resummed = false;
+        for (resum, output) in self.resums.iter_mut().zip(self.outputs.iter_mut()) {
+            if resum.repeated == resum.window_repeat {
+                resum.repeated = 0;
+                resummed = true;
+                // Shorter windows cover only the newest terms.
+                let skip = longest - resum.window_factors.len();
+                let sum: Complex<f32> = self
+                    .goertzel_terms
+                    .iter()
+                    .skip(skip)
+                    .zip(resum.window_factors.iter())
+                    .map(|(g, w)| g.scale(*w))
+                    .tree_sum();
+                *output = sum.norm() * resum.output_scale;
+            }
+            resum.repeated += 1;
+        }
+        if resummed {
+            let norm = (self.phase.re * self.phase.re + self.phase.im * self.phase.im).sqrt();
+            self.phase.re /= norm;
+            self.phase.im /= norm;
+        }
+        &self.outputs
+    }
+
+    /// Number of windows, and of outputs.
+    pub fn windows(&self) -> usize {
+        self.resums.len()
+    }
+
+    /// Length of the ring, which is the longest window.
+    pub fn length(&self) -> usize {
+        self.goertzel_terms.len()
+    }
+}
+
+/// Periods of each column's center that the short and long windows of a [`MultiDftNode`] span.
+pub const NODE_CYCLES: [f64; 2] = [8.0, 32.0];
+
+/// A [`MultiDft`] at every column of the bank the graph's [configuration](crate::graph::config)
+/// describes.  Each column re-sums one ring with a short window that follows attacks and a long
+/// window that resolves pitch, so its rows line up with a
+/// [`SpectrogramNode`](crate::dsp::spectrogram::SpectrogramNode)'s.  The bank is designed again
+/// when the configuration or the format of the audio arriving changes.
+///
+/// Each frame's audio is analyzed on the pool at [`Priority::Frame`] while the rest of the frame
+/// runs, so the rows emitted are the ones for the audio of the frame before.
+pub struct MultiDftNode {
+    dsp: DspSettings,
+    cycles: [f64; 2],
+    /// With the rate and channels it was designed for.
+    work: Offload<Option<(u32, usize, Vec<MultiDft>)>>,
+}
+
+impl MultiDftNode {
+    /// Windows span `cycles` periods of each column's center, short then long.
+    pub fn new(dsp: DspSettings, cycles: [f64; 2]) -> Self {
+        Self {
+            dsp,
+            cycles,
+            work: Offload::new(None),
+        }
+    }
+
+    /// Feed interleaved frames of `channels` at `rate`, on `pool` if there is one.  Channels are
+    /// averaged to mono.
+    pub fn process(
+        &mut self,
+        frames: Arc<[f32]>,
+        channels: usize,
+        rate: u32,
+        pool: Option<&WorkerPool>,
+    ) -> Result<(), MutateError> {
+        let (dsp, cycles) = (self.dsp.clone(), self.cycles);
+        let channels = channels.max(1);
+        self.work.submit(pool, Priority::Frame, move |designed| {
+            let dfts = match designed {
+                Some((r, c, dfts)) if *r == rate && *c == channels => dfts,
+                slot => {
+                    let fs = rate as f64;
+                    let shape = BANK_WINDOW;
+                    let dfts = dsp
+                        .centers()
+                        .iter()
+                        .map(|center| {
+                            let windows = cycles.map(|n| {
+                                let length = (n * fs / center.get()).round().max(1.0);
+                                (length as usize, shape)
+                            });
+                            MultiDft::new(center.get(), fs, &windows)
+                        })
+                        .collect();
+                    &mut slot.insert((rate, channels, dfts)).2
+                }
+            };
+            let mono: Vec<f32> = frames
+                .chunks_exact(channels)
+                .map(|f| f.iter().sum::<f32>() / channels as f32)
+                .collect();
+            for dft in dfts.iter_mut() {
+                for &x in &mono {
+                    dft.process(x);
+                }
+            }
+        })
+    }
+
+    /// The newest output of each column through the short and the long windows, once audio has
+    /// arrived.
+    pub fn latest(&mut self) -> Result<Option<[Arc<[f32]>; 2]>, MutateError> {
+        let designed = self.work.lock()?;
+        let Some((_, _, dfts)) = designed.as_ref() else {
+            return Ok(None);
+        };
+        Ok(Some(
+            [0, 1].map(|w| dfts.iter().map(|d| d.outputs[w]).collect()),
+        ))
+    }
+}
+
+impl Params for MultiDftNode {
+    fn param_specs(&self) -> &'static [ParamSpec] {
+        &[]
+    }
+}
+
+impl Node for MultiDftNode {
+    fn inputs(&self) -> &'static [PortSpec] {
+        &[PortSpec {
+            name: "input",
+            kind: PortKind::Samples,
+        }]
+    }
+
+    fn outputs(&self) -> &'static [PortSpec] {
+        &[
+            PortSpec {
+                name: "short",
+                kind: PortKind::Row,
+            },
+            PortSpec {
+                name: "long",
+                kind: PortKind::Row,
+            },
+        ]
+    }
+
+    fn gain_stage(&self) -> Option<&dyn GainStage> {
+        Some(&WindowNormalization::BANK)
+    }
+
+    fn watches(&self) -> &'static [&'static str] {
+        &[MIN_FREQ, MAX_FREQ, BINS]
+    }
+
+    fn attach(&mut self, _params: ParamHandle) {}
+
+    fn update(&mut self, config: &Config, _changed: &[&str]) -> Result<(), MutateError> {
+        let dsp = DspSettings {
+            min_freq: config.frequency(MIN_FREQ).unwrap_or(self.dsp.min_freq),
+            max_freq: config.frequency(MAX_FREQ).unwrap_or(self.dsp.max_freq),
+            bins: config.count(BINS).unwrap_or(self.dsp.bins),
+        };
+        if dsp != self.dsp {
+            self.dsp = dsp;
+            *self.work.lock()? = None;
+        }
+        Ok(())
+    }
+
+    /// Emits `short` and `long` every frame once audio has arrived.
+    fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+        let latest = self.latest()?;
+        if let Some(GraphEvent::Samples {
+            frames,
+            channels,
+            rate,
+        }) = frame.input(0)
+        {
+            let (channels, rate) = (*channels, *rate);
+            self.process(frames.clone(), channels, rate, frame.pool())?;
+        }
+        if let Some([short, long]) = latest {
+            frame.emit(0, GraphEvent::Row(short));
+            frame.emit(1, GraphEvent::Row(long));
+        }
+        Ok(())
+    }
+}
+
+/// ## Normalization
+///
+/// Every window passes less of a tone than a `BoxCar` and more noise per unit of tone.  Which one
+/// to correct depends on what is being compared.  See section 9 of the
+/// [GH_FFT](https://holometer.fnal.gov/GH_FFT.pdf) notes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Normalization {
+    /// Correct coherent gain.  A bin-centered sine of amplitude `A` reads `A` through any window.
+    /// Broadband noise reads higher through wider windows.
+    #[default]
+    Amplitude,
+    /// Also correct equivalent noise bandwidth.  White noise reads the same through any window,
+    /// as if measured with one bin of a `BoxCar`.  Tones read lower through wider windows.
+    Density,
+}
+
+impl Normalization {
+    /// Scale from the magnitude of a windowed sum of `length` terms to the output.
+    pub(crate) fn scale(&self, window: &window::WindowFunction, length: usize) -> f32 {
+        // Both sides of the spectrum, over a BoxCar's weight sum.
+        let amplitude = 2.0 / length as f32 * window.amplitude_norm_factor(length);
+        match self {
+            Self::Amplitude => amplitude,
+            // The factor is for power.
+            Self::Density => amplitude * window.bandwidth_norm_factor(length).sqrt(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::dsp::Filter;
+    use crate::dsp::{units::Hertz, Filter};
 
     use super::*;
 
@@ -159,7 +475,7 @@ mod test {
             attenuation_db: 80.0,
         };
         args.q = 32.0;
-        args.center = 400.0;
+        args.center = Hertz(400.0);
         let mut sg = args.sine_gen();
         let mut dft = Dft::from_args(&args);
         let nsamples = dft.length() * 4;
@@ -190,4 +506,120 @@ mod test {
 
         assert!(off_center_peak < peak);
     }
+
+    /// Settled output for `input` through a 480 sample window.
+    fn settle(window: window::WindowFunction, normalization: Normalization, input: &[f32]) -> f32 {
+        let mut dft = Dft::new(1000.0, 48_000.0, 480, window).with_normalization(normalization);
+        input.iter().fold(0.0, |_, &x| dft.process(x))
+    }
+
+    #[test]
+    fn test_dft_normalization() {
+        let windows = [
+            window::WindowFunction::BoxCar,
+            window::WindowFunction::Hamming,
+            window::WindowFunction::DolphChebyshev {
+                attenuation_db: 80.0,
+            },
+            window::WindowFunction::kaiser(60.0),
+        ];
+
+        // A bin-centered tone reads its amplitude through every window.
+        let tone: Vec<f32> = dsp::SineSweeper::new(1000.0, 48_000.0)
+            .take(480 * 3)
+            .map(|x| x * 0.5)
+            .collect();
+        for w in windows {
+            let out = settle(w, Normalization::Amplitude, &tone);
+            assert!((out - 0.5).abs() < 0.005, "{w}: {out}");
+        }
+
+        // White noise reads the same power through every window, on average.  Each hop of the
+        // window is another draw, so average many of them.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let noise: Vec<f32> = (0..480 * 400)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+            })
+            .collect();
+        let power = |w: window::WindowFunction| {
+            let mut dft =
+                Dft::new(1000.0, 48_000.0, 480, w).with_normalization(Normalization::Density);
+            let outputs: Vec<f32> = noise
+                .iter()
+                .map(|&x| dft.process(x))
+                .skip(480)
+                .step_by(480)
+                .collect();
+            outputs.iter().map(|y| y * y).sum::<f32>() / outputs.len() as f32
+        };
+        let boxcar = power(window::WindowFunction::BoxCar);
+        for w in &windows[1..] {
+            let ratio = power(*w) / boxcar;
+            assert!((ratio - 1.0).abs() < 0.1, "{w}: {ratio}");
+        }
+    }
+
+    #[test]
+    fn test_multi_dft_matches_dft() {
+        let windows = [
+            (480, window::WindowFunction::Hamming),
+            (960, window::WindowFunction::kaiser(60.0)),
+            (
+                240,
+                window::WindowFunction::DolphChebyshev {
+                    attenuation_db: 40.0,
+                },
+            ),
+        ];
+        let mut multi = MultiDft::new(1000.0, 48_000.0, &windows);
+        let mut singles: Vec<Dft> = windows
+            .iter()
+            .map(|&(length, w)| Dft::new(1000.0, 48_000.0, length, w))
+            .collect();
+        assert_eq!(multi.windows(), 3);
+        assert_eq!(multi.length(), 960);
+
+        // A tone that wanders past the center exercises every window's skirt.
+        let mut sg = dsp::SineSweeper::new(990.0, 48_000.0);
+        for n in 0..960 * 6 {
+            if n % 960 == 0 {
+                sg.set_frequency(990.0 + n as f64 / 480.0);
+            }
+            let x = sg.next().unwrap();
+            let outputs = multi.process(x).to_vec();
+            for (dft, out) in singles.iter_mut().zip(outputs) {
+                let expected = dft.process(x);
+                assert!((out - expected).abs() <= 1e-4 * expected.max(1e-3), "{n}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_dft_node() {
+        let dsp = DspSettings {
+            min_freq: 200.0,
+            max_freq: 5000.0,
+            bins: 12,
+        };
+        let centers = dsp.centers();
+        let mut node = MultiDftNode::new(dsp, NODE_CYCLES);
+        assert!(node.latest().unwrap().is_none());
+
+        // A quarter second of 1kHz in stereo, on the pool.
+        let pool = WorkerPool::new(&Default::default()).unwrap();
+        let frames: Arc<[f32]> = dsp::SineSweeper::new(1000.0, 48_000.0)
+            .take(12_000)
+            .flat_map(|x| [0.5 * x, 0.5 * x])
+            .collect();
+        node.process(frames, 2, 48_000, Some(&pool)).unwrap();
+        for row in node.latest().unwrap().unwrap() {
+            assert_eq!(row.len(), 12);
+            let loudest = (0..12).max_by(|&a, &b| row[a].total_cmp(&row[b])).unwrap();
+            assert!((centers[loudest].get() / 1000.0).log2().abs() < 0.25);
+        }
+    }
 }
@@ -0,0 +1,218 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Fast Fourier Transform
+//!
+//! The filter banks are judged by their magnitude responses, and a response measured by the same
+//! code that produced it proves little.  [`Fft`] is a plain textbook transform to check against.
+//! The workbench compares swept filter gains with the FFT of each filter's impulse response, and
+//! tests build reference spectrograms with [`spectrogram`].
+//!
+//! Transforms are in place on power of two lengths, in `f32` or `f64`.  Stages are radix-4, with
+//! one radix-2 stage first when the length is an odd power of two.  Nothing here is tuned for
+//! speed.  Reach for a dedicated crate before putting it on a hot path.
+
+use num_complex::Complex;
+use num_traits::{Float, FloatConst};
+
+/// A transform of one length, with its twiddles and bit reversal table.
+#[derive(Clone, Debug)]
+pub struct Fft<T> {
+    /// `W_n^k` for `k` in `0..n`.
+    twiddles: Vec<Complex<T>>,
+    /// Index each input moves to before the first stage.
+    reversed: Vec<usize>,
+}
+
+impl<T: Float + FloatConst> Fft<T> {
+    /// # Panics
+    ///
+    /// `len` must be a power of two.
+    pub fn new(len: usize) -> Self {
+        assert!(
+            len.is_power_of_two(),
+            "FFT length {len} is not a power of two"
+        );
+        let bits = len.trailing_zeros();
+        let step = -T::TAU() / T::from(len).unwrap();
+        let twiddles = (0..len)
+            .map(|k| Complex::from_polar(T::one(), step * T::from(k).unwrap()))
+            .collect();
+        let reversed = (0..len)
+            .map(|i| match bits {
+                0 => 0,
+                b => i.reverse_bits() >> (usize::BITS - b),
+            })
+            .collect();
+        Self { twiddles, reversed }
+    }
+
+    pub fn len(&self) -> usize {
+        self.twiddles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.twiddles.is_empty()
+    }
+
+    /// Forward transform in place, unnormalized.
+    pub fn forward(&self, data: &mut [Complex<T>]) {
+        let n = self.len();
+        assert_eq!(
+            data.len(),
+            n,
+            "FFT of length {n} given {} points",
+            data.len()
+        );
+        for (i, &r) in self.reversed.iter().enumerate() {
+            if i < r {
+                data.swap(i, r);
+            }
+        }
+
+        // Sub-transforms of length `m` are contiguous in bit reversed order.
+        let mut m = 1;
+        if n.trailing_zeros() % 2 == 1 {
+            for pair in data.chunks_exact_mut(2) {
+                let (a, b) = (pair[0], pair[1]);
+                pair[0] = a + b;
+                pair[1] = a - b;
+            }
+            m = 2;
+        }
+        // Two radix-2 stages at once: four length `m` blocks become one of length `4m`.
+        while m < n {
+            let stride = n / (4 * m);
+            for block in data.chunks_exact_mut(4 * m) {
+                for k in 0..m {
+                    let w1 = self.twiddles[k * stride];
+                    let w2 = self.twiddles[2 * k * stride];
+                    let a = block[k];
+                    let b = w2 * block[k + m];
+                    let c = block[k + 2 * m];
+                    let d = w2 * block[k + 3 * m];
+                    let (p0, p1) = (a + b, a - b);
+                    let q0 = w1 * (c + d);
+                    // Times -i, which is `W_4m^m`.
+                    let q1 = w1 * (c - d);
+                    let q1 = Complex::new(q1.im, -q1.re);
+                    block[k] = p0 + q0;
+                    block[k + m] = p1 + q1;
+                    block[k + 2 * m] = p0 - q0;
+                    block[k + 3 * m] = p1 - q1;
+                }
+            }
+            m *= 4;
+        }
+    }
+
+    /// Inverse transform in place, scaled by `1 / n` so that it undoes [`forward`](Self::forward).
+    pub fn inverse(&self, data: &mut [Complex<T>]) {
+        data.iter_mut().for_each(|x| *x = x.conj());
+        self.forward(data);
+        let scale = T::one() / T::from(self.len()).unwrap();
+        data.iter_mut().for_each(|x| *x = x.conj() * scale);
+    }
+
+    /// Amplitude of each bin from DC to Nyquist, `n / 2 + 1` of them, for real `samples` shaped by
+    /// `window`.  Normalized by the window sum so that a sine centered on a bin reads its own
+    /// amplitude.  Short inputs are zero padded.
+    pub fn amplitudes(&self, samples: &[T], window: &[T]) -> Vec<T> {
+        let n = self.len();
+        let mut data = vec![Complex::new(T::zero(), T::zero()); n];
+        for ((d, &s), &w) in data.iter_mut().zip(samples).zip(window) {
+            d.re = s * w;
+        }
+        self.forward(&mut data);
+        let sum = window.iter().take(n).fold(T::zero(), |acc, &w| acc + w);
+        let two = T::one() + T::one();
+        data[..=n / 2]
+            .iter()
+            .enumerate()
+            .map(|(k, x)| {
+                // Every bin but DC and Nyquist also has a negative frequency twin.
+                let sides = if k == 0 || k == n / 2 { T::one() } else { two };
+                x.norm() * sides / sum
+            })
+            .collect()
+    }
+}
+
+/// Frequency of bin `k` of an `n` point transform at `rate`.
+pub fn bin_frequency(k: usize, n: usize, rate: f64) -> f64 {
+    k as f64 * rate / n as f64
+}
+
+/// Amplitudes of every `hop` samples of `samples`, one column per hop, as with
+/// [`Fft::amplitudes`].  `window` sets the transform length.
+pub fn spectrogram<T: Float + FloatConst>(samples: &[T], window: &[T], hop: usize) -> Vec<Vec<T>> {
+    let fft = Fft::new(window.len());
+    let hop = hop.max(1);
+    (0..=samples.len().saturating_sub(window.len()))
+        .step_by(hop)
+        .map(|start| fft.amplitudes(&samples[start..start + window.len()], window))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dsp::window::WindowFunction;
+
+    /// The definition, for comparison.
+    fn naive(x: &[Complex<f64>]) -> Vec<Complex<f64>> {
+        let n = x.len();
+        (0..n)
+            .map(|k| {
+                x.iter()
+                    .enumerate()
+                    .fold(Complex::new(0.0, 0.0), |acc, (j, &v)| {
+                        let angle = -std::f64::consts::TAU * (j * k) as f64 / n as f64;
+                        acc + v * Complex::from_polar(1.0, angle)
+                    })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fft_matches_definition() {
+        // Odd and even powers of two exercise the radix-2 stage and pure radix-4.
+        for n in [1, 2, 4, 8, 32, 64, 512] {
+            let x: Vec<Complex<f64>> = (0..n)
+                .map(|i| Complex::new((i as f64 * 0.37).sin(), (i as f64 * 1.3).cos()))
+                .collect();
+            let mut fast = x.clone();
+            let fft = Fft::new(n);
+            fft.forward(&mut fast);
+            for (a, b) in fast.iter().zip(naive(&x)) {
+                assert!((a - b).norm() < 1e-9 * n as f64, "n = {n}");
+            }
+            fft.inverse(&mut fast);
+            for (a, b) in fast.iter().zip(&x) {
+                assert!((a - b).norm() < 1e-12 * n as f64, "n = {n}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_amplitudes() {
+        let n = 1024;
+        let rate = 48_000.0;
+        // Centered on bin 100, at half scale.
+        let freq = bin_frequency(100, n, rate);
+        let x: Vec<f32> = crate::dsp::SineSweeper::new(freq, rate)
+            .take(n)
+            .map(|s| s * 0.5)
+            .collect();
+        let window = WindowFunction::Hamming.make_window_32(n);
+        let amplitudes = Fft::<f32>::new(n).amplitudes(&x, &window);
+        assert_eq!(amplitudes.len(), n / 2 + 1);
+        assert!((amplitudes[100] - 0.5).abs() < 0.01);
+        assert!(amplitudes[200] < 1e-3);
+
+        let short = WindowFunction::Hamming.make_window_32(256);
+        let columns = spectrogram(&x, &short, 128);
+        assert_eq!(columns.len(), 7);
+        assert!(columns.iter().all(|c| c.len() == 129 && c[25] > 0.4));
+    }
+}
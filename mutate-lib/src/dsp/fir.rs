@@ -242,6 +242,11 @@ mod test {
             WindowFunction::DolphChebyshev {
                 attenuation_db: 80.0,
             },
+            WindowFunction::kaiser(80.0),
+            WindowFunction::Ultraspherical {
+                mu: 0.5,
+                attenuation_db: 80.0,
+            },
             WindowFunction::Literal {
                 weights: &TEST_FILTER,
             },
@@ -276,7 +281,7 @@ mod test {
             }
             let measure = input.nsamples(128.0);
             let mut peak: f32 = 0.0;
-            for _ in 0..measure {
+            for _ in 0..measure.get() {
                 peak = peak.max(filter.process(input.next().unwrap()).abs());
             }
             // In the pass runs, we're looking for
@@ -289,7 +294,7 @@ mod test {
             }
             let measure = input.nsamples(128.0);
             let mut peak: f32 = 0.0;
-            for _ in 0..measure {
+            for _ in 0..measure.get() {
                 peak = peak.max(filter.process(input.next().unwrap()).abs());
             }
             /// NOTE the "winners" can be a little unpredictable due to ripple in the stop bands.
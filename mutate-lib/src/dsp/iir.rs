@@ -22,9 +22,9 @@
 //! The `Cascade` implementation is generic over SoS and supports Butterworth Q ratios and detuning
 //! the center frequency to reduce ringing.
 //!
-//! 32bit precision is preferred since this is what is available on GPUs, but 64bit variants may be
-//! used to quickly determine the presence or nature of numerical stability issues in GPU-bound
-//! implementations.  The initialization is 64bit and truncates after calculating constants.
+//! 32bit precision is preferred since this is what is available on GPUs, but 64bit variants, such as
+//! `Biquad64`, may be used to quickly determine the presence or nature of numerical stability
+//! issues in GPU-bound implementations.  The workbench `precision` command runs them side by side.  The initialization is 64bit and truncates after calculating constants.
 //!
 //! Tests for this crate merely check for sanity, NaN errors on on-bin input or excessive noise at
 //! off-center pitches.  Use the workbench bin for any real tuning or evaluation.  So far most
@@ -43,53 +43,67 @@
 use std::f64::consts::{PI as PI64, TAU as TAU64};
 
 use num_complex::{Complex, Complex64};
-use num_traits::Zero;
+use num_traits::{Float, Zero};
 
 use super::{Filter, FilterArgs, FilterMode};
 
 /// First order complex resonator, one of the simplest IIRs
+///
+/// The state spins at the center frequency and decays by the bandwidth.  [`process`](Self::process)
+/// returns the real part so that stages cascade linearly.  [`magnitude`](Self::magnitude) is the
+/// envelope for free.
 pub struct ComplexResonator<T> {
     pole: Complex<T>,
     state: Complex<T>,
     gain: T,
 }
 
-// FIXME new is always f64!
-// Cannot use this filter (for comparison, it's just a toy implementation) until the constructor is
-// fixed.  Good place to make the floating point generic while creating 1st order section.
-impl<T: num_traits::Float> ComplexResonator<T> {
-    pub fn new(f0: T, fs: T, q: T, mode: FilterMode) -> Self {
+impl<T: Float> ComplexResonator<T> {
+    /// Coefficients are computed in `f64` regardless of `T`, then rounded once.
+    pub fn new(f0: f64, fs: f64, q: f64, mode: FilterMode) -> Self {
         assert_eq!(FilterMode::BandPass, mode);
 
-        let pi = T::from(PI64).unwrap();
-        let k = pi * f0 / fs;
+        // Bandwidth is f0 / q, and the pole radius sets it.
+        let decay = PI64 * f0 / (fs * q);
+        let w0 = TAU64 * f0 / fs;
+        let pole = Complex64::from_polar(1.0 - decay, w0);
+        // The real part responds to both the positive and negative frequency of a real input.  At
+        // high Q the negative side vanishes and the gain is close to twice the decay.
+        let response = |w: f64| 1.0 / (1.0 - pole * Complex64::from_polar(1.0, -w));
+        let center = (response(w0) + response(-w0).conj()) * 0.5;
 
         Self {
-            pole: Complex::from_polar(T::one() - k / q, T::from(2.0).unwrap() * k),
+            pole: Complex::new(cast(pole.re), cast(pole.im)),
             state: Complex::zero(),
-            gain: k / q,
+            gain: cast(1.0 / center.norm()),
         }
     }
 
     #[inline]
     pub fn process(&mut self, x: T) -> T {
         self.state = self.pole * self.state + self.gain * x;
+        self.state.re
+    }
+
+    /// Envelope of the most recent output.
+    #[inline]
+    pub fn magnitude(&self) -> T {
         self.state.norm()
     }
 }
 
 /// DF2T 2nd order biquad.
-pub struct Biquad {
-    s1: f32,
-    s2: f32,
-    a1: f32,
-    a2: f32,
-    b0: f32,
-    b1: f32,
-    b2: f32,
+pub struct Biquad<T = f32> {
+    s1: T,
+    s2: T,
+    a1: T,
+    a2: T,
+    b0: T,
+    b1: T,
+    b2: T,
 }
 
-impl Biquad {
+impl<T: Float> Biquad<T> {
     pub fn new(f0: f64, fs: f64, q: f64, mode: FilterMode) -> Self {
         let w0 = TAU64 * f0 / fs;
         let alpha = w0.sin() / (2.0 * q);
@@ -122,19 +136,24 @@ impl Biquad {
             _ => todo!(),
         };
 
+        Self::from_coefficients([b0, b1, b2], [a0, a1, a2])
+    }
+
+    /// Direct coefficients, for designs that are specified that way.  Normalized by `a[0]`.
+    pub fn from_coefficients(b: [f64; 3], a: [f64; 3]) -> Self {
         Self {
-            s1: 0.0,
-            s2: 0.0,
-            b0: (b0 / a0) as f32,
-            b1: (b1 / a0) as f32,
-            b2: (b2 / a0) as f32,
-            a1: (a1 / a0) as f32,
-            a2: (a2 / a0) as f32,
+            s1: T::zero(),
+            s2: T::zero(),
+            b0: cast(b[0] / a[0]),
+            b1: cast(b[1] / a[0]),
+            b2: cast(b[2] / a[0]),
+            a1: cast(a[1] / a[0]),
+            a2: cast(a[2] / a[0]),
         }
     }
 
     #[inline]
-    pub fn process(&mut self, x: f32) -> f32 {
+    pub fn process(&mut self, x: T) -> T {
         let y = self.b0.mul_add(x, self.s1);
         self.s1 = self.b1.mul_add(x, self.s2 - self.a1 * y);
         self.s2 = self.b2.mul_add(x, -self.a2 * y);
@@ -143,17 +162,17 @@ impl Biquad {
 }
 
 /// Simple state variable filter, using the Toplogy Preserving Transform
-pub struct Svf {
-    g: f32, // Pre-warped frequency
-    k: f32, // 1/Q (damping)
-    a1: f32,
+pub struct Svf<T = f32> {
+    g: T, // Pre-warped frequency
+    k: T, // 1/Q (damping)
+    a1: T,
     mode: FilterMode,
 
-    s1: f32, // Integrator 1 state
-    s2: f32, // Integrator 2 state
+    s1: T, // Integrator 1 state
+    s2: T, // Integrator 2 state
 }
 
-impl Svf {
+impl<T: Float> Svf<T> {
     /// f0 = cutoff/center frequency (Hz)
     /// fs = sample rate (Hz)
     /// q  = quality factor
@@ -164,18 +183,18 @@ impl Svf {
         let den = 1.0 + g * (g + k);
 
         Self {
-            g: g as f32,
-            k: k as f32,
-            a1: (1.0 / den) as f32,
+            g: cast(g),
+            k: cast(k),
+            a1: cast(1.0 / den),
             mode,
 
-            s1: 0.0,
-            s2: 0.0,
+            s1: T::zero(),
+            s2: T::zero(),
         }
     }
 
     #[inline]
-    pub fn process(&mut self, x: f32) -> f32 {
+    pub fn process(&mut self, x: T) -> T {
         let hp = (x - self.s1.mul_add(self.k + self.g, self.s2)) * self.a1;
 
         let bp = self.g.mul_add(hp, self.s1);
@@ -194,24 +213,24 @@ impl Svf {
 }
 
 /// Cytomic derivation of the SVF is said to be very precise even at high Qs and low frequencies.
-pub struct CytomicSvf {
-    k: f32,
-    a1: f32,
-    a2: f32,
-    a3: f32,
-    ic1eq: f32,
-    ic2eq: f32,
+pub struct CytomicSvf<T = f32> {
+    k: T,
+    a1: T,
+    a2: T,
+    a3: T,
+    ic1eq: T,
+    ic2eq: T,
     mode: FilterMode,
 
-    m0: f32,
-    m1: f32,
-    m2: f32,
+    m0: T,
+    m1: T,
+    m2: T,
 
-    s1: f32,
-    s2: f32,
+    s1: T,
+    s2: T,
 }
 
-impl CytomicSvf {
+impl<T: Float> CytomicSvf<T> {
     pub fn new(f0: f64, fs: f64, q: f64, mode: FilterMode) -> Self {
         let g = (std::f64::consts::PI * f0 / fs).tan();
         let k = 1.0 / q;
@@ -228,34 +247,34 @@ impl CytomicSvf {
 
         let (m0, m1, m2) = match mode {
             FilterMode::LowPass => (0.0, 0.0, 1.0),
-            FilterMode::BandPass => (0.0, k as f32, 0.0),
-            FilterMode::HighPass => (1.0, -(k as f32), -1.0),
+            FilterMode::BandPass => (0.0, k, 0.0),
+            FilterMode::HighPass => (1.0, -k, -1.0),
             // XXX Untested
-            FilterMode::Notch => (1.0, -(k as f32), 0.0),
-            FilterMode::AllPass => (1.0, (-2.0 * k) as f32, 0.0),
+            FilterMode::Notch => (1.0, -k, 0.0),
+            FilterMode::AllPass => (1.0, -2.0 * k, 0.0),
         };
 
         Self {
-            k: k as f32,
-            a1: a1 as f32,
-            a2: a2 as f32,
-            a3: a3 as f32,
-            ic1eq: ic1eq as f32,
-            ic2eq: ic2eq as f32,
+            k: cast(k),
+            a1: cast(a1),
+            a2: cast(a2),
+            a3: cast(a3),
+            ic1eq: cast(ic1eq),
+            ic2eq: cast(ic2eq),
 
             mode,
 
-            m0,
-            m1,
-            m2,
+            m0: cast(m0),
+            m1: cast(m1),
+            m2: cast(m2),
 
-            s1: 0.0,
-            s2: 0.0,
+            s1: T::zero(),
+            s2: T::zero(),
         }
     }
 
     #[inline]
-    pub fn process(&mut self, x: f32) -> f32 {
+    pub fn process(&mut self, x: T) -> T {
         let s1 = self.s1;
         let s2 = self.s2;
 
@@ -265,8 +284,9 @@ impl CytomicSvf {
         let v2 = self.a2.mul_add(s1, self.a3 * v3) + s2;
 
         // Pre-scale contributions to reduce dynamic range
-        let v3_2a2 = 2.0 * self.a2 * v3;
-        let v3_2a3 = 2.0 * self.a3 * v3;
+        let two = T::one() + T::one();
+        let v3_2a2 = two * self.a2 * v3;
+        let v3_2a3 = two * self.a3 * v3;
 
         self.s1 = self.ic1eq.mul_add(s1, v3_2a2);
         self.s2 = self.ic2eq.mul_add(s1, s2 + v3_2a3);
@@ -319,7 +339,7 @@ impl<T: SoS> Filter for Cascade<T> {
                 } else {
                     args.q * q_norm
                 };
-                T::new(f0, args.fs, q, mode)
+                T::new(f0.get(), args.fs.get(), q, mode)
             })
             .collect();
 
@@ -340,38 +360,55 @@ impl<T: SoS> Filter for Cascade<T> {
 }
 
 macro_rules! impl_sos {
-    ($t:ty) => {
-        impl SoS for $t {
+    ($t:ident) => {
+        impl<T: Float> SoS for $t<T> {
             fn new(center: f64, fs: f64, q: f64, mode: FilterMode) -> Self {
-                <$t>::new(center, fs, q, mode)
+                $t::new(center, fs, q, mode)
             }
         }
     };
 }
 
+impl_sos!(ComplexResonator);
 impl_sos!(Biquad);
 impl_sos!(Svf);
 impl_sos!(CytomicSvf);
 
+// NOTE `Filter` speaks f32, so 64bit sections round between stages of a `Cascade`.  The rounding is
+// far below the error that builds up inside an f32 section's state.
 macro_rules! impl_filter {
-    ($ty:ty) => {
-        impl Filter for $ty {
+    ($t:ident) => {
+        impl<T: Float> Filter for $t<T> {
             #[inline]
             fn process(&mut self, sample: f32) -> f32 {
-                <$ty>::process(self, sample)
+                $t::process(self, cast(sample as f64)).to_f32().unwrap()
             }
 
             fn from_args(args: &FilterArgs) -> Self {
-                <$ty>::new(args.center, args.fs, args.q, args.mode)
+                $t::new(args.center.get(), args.fs.get(), args.q, args.mode)
             }
         }
     };
 }
 
+impl_filter!(ComplexResonator);
 impl_filter!(Biquad);
 impl_filter!(Svf);
 impl_filter!(CytomicSvf);
 
+/// Biquad in 64bit, to check the precision of the 32bit one.
+pub type Biquad64 = Biquad<f64>;
+/// Svf in 64bit, to check the precision of the 32bit one.
+pub type Svf64 = Svf<f64>;
+/// CytomicSvf in 64bit, to check the precision of the 32bit one.
+pub type CytomicSvf64 = CytomicSvf<f64>;
+
+/// Round a constant computed in 64bit.
+#[inline]
+fn cast<T: Float>(x: f64) -> T {
+    T::from(x).unwrap()
+}
+
 /// Use order, not number of stages, usually 2 * stages.
 fn butterworth_q_factors(order: usize) -> Vec<f64> {
     assert!(order % 2 == 0, "Order must be even");
@@ -413,6 +450,8 @@ fn stagger_factors(stages: usize, scale: f64) -> Vec<f64> {
 
 #[cfg(test)]
 mod test {
+    use crate::dsp::units::{Hertz, SampleRate};
+
     use super::*;
 
     const TOL: f64 = 0.01;
@@ -491,6 +530,66 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_complex_resonator_unity_gain() {
+        let f0 = 440.0;
+        let fs = 48_000.0;
+        let q = 10.0;
+        let mut single = ComplexResonator::<f32>::new(f0, fs, q, FilterMode::BandPass);
+        let mut double = ComplexResonator::<f64>::new(f0, fs, q, FilterMode::BandPass);
+        let mut peaks = (0.0f32, 0.0f64);
+        let sine = crate::dsp::SineSweeper::new(f0, fs);
+        for (n, x) in sine.take(fs as usize).enumerate() {
+            let y32 = ComplexResonator::process(&mut single, x);
+            let y64 = ComplexResonator::process(&mut double, x as f64);
+            if n > fs as usize / 2 {
+                peaks = (peaks.0.max(y32.abs()), peaks.1.max(y64.abs()));
+            }
+        }
+        assert!((peaks.0 - 1.0).abs() < 0.05, "{}", peaks.0);
+        assert!((peaks.1 - 1.0).abs() < 0.05, "{}", peaks.1);
+        assert!((single.magnitude() - 1.0).abs() < 0.1);
+
+        // One pole rolls off slowly, but an octave away is still well outside f0 / q.
+        let mut off = ComplexResonator::<f32>::from_args(&FilterArgs {
+            q,
+            center: Hertz(f0),
+            fs: SampleRate(fs),
+            ..Default::default()
+        });
+        let sine = crate::dsp::SineSweeper::new(2.0 * f0, fs);
+        let peak = sine
+            .take(fs as usize)
+            .skip(fs as usize / 2)
+            .map(|x| Filter::process(&mut off, x).abs())
+            .fold(0.0f32, f32::max);
+        assert!(peak < 0.2, "{peak}");
+    }
+
+    #[test]
+    fn test_iir_precision_parity() {
+        // At a comfortable center and Q, 32bit sections should track their 64bit twins closely.
+        fn parity<A: SoS, B: SoS>() -> f32 {
+            let args = FilterArgs {
+                q: 10.0,
+                center: Hertz(1000.0),
+                fs: SampleRate(48_000.0),
+                stages: 2,
+                ..Default::default()
+            };
+            let mut single = Cascade::<A>::from_args(&args);
+            let mut double = Cascade::<B>::from_args(&args);
+            args.sine_gen()
+                .take(48_000)
+                .map(|x| (single.process(x) - double.process(x)).abs())
+                .fold(0.0, f32::max)
+        }
+        assert!(parity::<Biquad, Biquad64>() < 1e-3);
+        assert!(parity::<Svf, Svf64>() < 1e-3);
+        assert!(parity::<CytomicSvf, CytomicSvf64>() < 1e-3);
+        assert!(parity::<ComplexResonator<f32>, ComplexResonator<f64>>() < 1e-3);
+    }
+
     #[test]
     fn test_iir_cascading_sos() {
         let f0 = 32.0;
@@ -498,8 +597,8 @@ mod test {
         for q in [0.5, 1.0, 2.0, 5.0, 10.0, 100.0, 1000.0] {
             let args = FilterArgs {
                 q,
-                center: f0,
-                fs,
+                center: Hertz(f0),
+                fs: SampleRate(fs),
                 gain_factor: 1.0,
                 butterworth: true,
                 stagger: None,
@@ -531,8 +630,8 @@ mod test {
 
         let args = FilterArgs {
             q: q,
-            center: f0,
-            fs,
+            center: Hertz(f0),
+            fs: SampleRate(fs),
             gain_factor: 1.0,
             butterworth: true,
             stagger: Some(1.02), // Some(1.0005),
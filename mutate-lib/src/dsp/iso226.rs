@@ -11,17 +11,31 @@
 //! weights for bins, such as those used for a CQT.  If you need amplitude domain mapping,
 //! look for something with computationally simple rules like the K weights filter, which just uses
 //! a high-pass and a shelf.
+//!
+//! The whole contour family is available.  [`phon_to_spl`] and [`spl_to_phon`] follow the 2023
+//! equation for any loudness level, and frequencies between the standard's one-third octaves are
+//! interpolated in log frequency.  [`contour_gain`] corrects along one chosen contour, while
+//! [`level_gain`] follows whichever contour each measured SPL lies on.
+//!
+//! With a [calibrated](crate::dsp::calibration) input, [`spl_to_phon`] reports absolute loudness
+//! level instead of a relative correction.
 
 use crate::prelude::*;
 
 /// 70.0 phons was chosen as a hardcode because this was used in the ITU-R BS.1770-5 standard and
 /// stated to be the usual TV listening volume.  Americans measure TV volumes in gallons per slug or
 /// something, so we used the EU standard.
-// LIES technically, we should be using a different curve for each input SPL because the 70 phon
-// curve is only correct for SPLs on the 70 phon curve.  SPLs off of the 70 phon curve need a
-// different correction from a different curve, but how would we choose that curve?
+// LIES the 70 phon curve is only correct for SPLs on the 70 phon curve.  Uncalibrated input has no
+// absolute level to choose another contour with.  Calibrated input should use `level_gain`.
 const CURVE_PHONS: f64 = 70.0;
 
+/// Exponent of loudness perception at 1kHz, `α_r`.
+const ALPHA_R: f64 = 0.300;
+/// Hearing threshold at 1kHz in dB, `T_r`.
+const THRESHOLD_R: f64 = 2.4;
+/// Square of the ratio of the 20µPa reference pressure to 1Pa, `(p_a / p_0)²`.
+const PRESSURE_RATIO: f64 = 4e-10;
+
 /// Return signal gain dB necessary to map perceptually iso-loud tones (which follow a curve in
 /// measured SPL) to flat, perceptually scaled outputs.
 ///
@@ -35,26 +49,54 @@ const CURVE_PHONS: f64 = 70.0;
 /// combined with very large gain corrections to produce spurious signals after gain that are well
 /// above the chosen noise floor.
 pub fn iso226_gain(freq: f64) -> Result<f64, MutateError> {
-    let ref_spl = iso226_phon2spl(1000.0);
-    let target_spl = iso226_phon2spl(freq);
+    Ok(contour_gain(freq, CURVE_PHONS))
+}
 
-    // Convert this to a correction, how much gain should be applied relative to 1kHz to obtain a
-    // value relative to the iso-loud value on the 70 phon curve?
-    Ok(ref_spl - target_spl)
+/// Gain in dB that moves a tone at `freq` on the `phons` contour to the level of 1kHz on the same
+/// contour.
+pub fn contour_gain(freq: f64, phons: f64) -> f64 {
+    // The 1kHz SPL equals `phons` but is evaluated the same way so that 1kHz cancels exactly.
+    phon_to_spl(1000.0, phons) - phon_to_spl(freq, phons)
 }
 
-// This is the C implementation without indirection.
-fn iso226_phon2spl(freq: f64) -> f64 {
+/// Gain in dB that maps a tone at `freq` measured at `spl` to its loudness level, following the
+/// contour that passes through it.  Quiet bass gets more correction than loud bass.  Tones below
+/// the hearing threshold return `None`.
+pub fn level_gain(freq: f64, spl: f64) -> Option<f64> {
+    spl_to_phon(freq, spl).map(|phons| phons - spl)
+}
+
+/// SPL in dB of a tone at `freq` that is as loud as `phons`, equation 1 of ISO 226:2023.
+pub fn phon_to_spl(freq: f64, phons: f64) -> f64 {
     let (af, tf, lu) = interpolate_table(freq);
 
-    let af_part = (0.4 * 10f64.powf(((tf + lu) / 10.0) - 9.0)).powf(af);
-    let af_total = 4.47 / 1000.0 * (10f64.powf(0.025 * CURVE_PHONS) - 1.15) + af_part;
+    let loudness = 10f64.powf(ALPHA_R * phons / 10.0) - 10f64.powf(ALPHA_R * THRESHOLD_R / 10.0);
+    let threshold = 10f64.powf(af * (tf + lu) / 10.0);
+    (10.0 / af) * (PRESSURE_RATIO.powf(ALPHA_R - af) * loudness + threshold).log10() - lu
+}
 
-    ((10.0 / af) * af_total.log10()) - lu + 94.0
+/// Loudness level in phons of a tone at `freq` measured at `spl` dB.  The inverse of
+/// [`phon_to_spl`].  Tones below the hearing threshold have no loudness level and return `None`.
+pub fn spl_to_phon(freq: f64, spl: f64) -> Option<f64> {
+    let (af, tf, lu) = interpolate_table(freq);
+
+    if spl < tf {
+        return None;
+    }
+    let above = 10f64.powf(af * (spl + lu) / 10.0) - 10f64.powf(af * (tf + lu) / 10.0);
+    let loudness =
+        above / PRESSURE_RATIO.powf(ALPHA_R - af) + 10f64.powf(ALPHA_R * THRESHOLD_R / 10.0);
+    Some((10.0 / ALPHA_R) * loudness.log10())
+}
+
+/// The `phons` contour at each of the standard's frequencies, as `(freq, spl)`.
+pub fn contour(phons: f64) -> impl Iterator<Item = (f64, f64)> {
+    FREQ.iter().map(move |&f| (f, phon_to_spl(f, phons)))
 }
 
 /// Interpolate ISO226 table values to return constants.  Return values are (AF, TF, and LU), in the
-/// same order as the source table columns.
+/// same order as the source table columns.  The table is in one-third octaves, so interpolation is
+/// in log frequency.
 fn interpolate_table(freq: f64) -> (f64, f64, f64) {
     // Clamp frequencies outside the table to the first/last table values
     if freq <= FREQ[0] {
@@ -67,7 +109,7 @@ fn interpolate_table(freq: f64) -> (f64, f64, f64) {
     // Find the interval [FREQ[i], FREQ[i+1]] that contains freq
     for i in 0..28 {
         if freq >= FREQ[i] && freq < FREQ[i + 1] {
-            let k = (freq - FREQ[i]) / (FREQ[i + 1] - FREQ[i]);
+            let k = (freq / FREQ[i]).ln() / (FREQ[i + 1] / FREQ[i]).ln();
             let af_interp = (AF[i + 1] - AF[i]) * k + AF[i];
             let tf_interp = (TF[i + 1] - TF[i]) * k + TF[i];
             let lu_interp = (LU[i + 1] - LU[i]) * k + LU[i];
@@ -135,12 +177,12 @@ pub mod test {
         // the target frequency SPL to a value that is instead relative to the iso-loud SPL on the
         // 70 phon curve.  If we get 80dB at 20Hz and 60dB at 40Hz, the 60dB will produce a larger
         // perceived volume, and this correction will approximate that.
-        let expected: [f64; 32] = [
-            -42.125954, -37.151352, -32.474823, -28.164536, -24.230034, -20.573692, -17.254478,
-            -14.266739, -11.596863, -9.248718, -7.047577, -5.185562, -3.613426, -2.271393,
-            -1.181274, -0.264160, 0.294724, -0.150620, -2.466782, -3.531448, -0.326263, 2.199837,
-            2.970627, 1.579132, -1.993591, -7.124924, -11.449837, -11.762466, -6.538109, -6.538109,
-            -6.538109, -6.538109,
+        let expected: [f64; 31] = [
+            -44.121505, -39.024927, -34.237570, -29.826481, -25.756891, -21.975475, -18.537667,
+            -15.403116, -12.586866, -10.117342, -7.765407, -5.759493, -4.060313, -2.599852,
+            -1.398131, -0.383684, 0.258577, -0.162911, -2.411287, -3.410227, -0.173295, 2.346404,
+            3.107051, 1.706477, -1.903170, -7.092736, -11.507434, -11.999570, -7.104798, -7.104798,
+            -7.104798,
         ];
 
         // Tests
@@ -155,4 +197,49 @@ pub mod test {
             );
         }
     }
+
+    #[test]
+    fn test_iso226_published_values() {
+        // Loudness level equals SPL at 1kHz by definition.
+        for phons in [10.0, 40.0, 70.0, 100.0] {
+            assert!((phon_to_spl(1000.0, phons) - phons).abs() < 1e-9);
+        }
+        // The threshold contour passes through the standard's T_f column.
+        for (&freq, &tf) in FREQ.iter().zip(&TF) {
+            let spl = phon_to_spl(freq, THRESHOLD_R);
+            assert!((spl - tf).abs() < 1e-9, "{freq}Hz: {spl} vs {tf}");
+        }
+        // Contours rise with loudness level and flatten in the bass as they do.
+        let quiet: Vec<_> = contour(20.0).collect();
+        let loud: Vec<_> = contour(80.0).collect();
+        assert!(quiet.iter().zip(&loud).all(|(q, l)| l.1 > q.1));
+        assert!(contour_gain(20.0, 20.0) < contour_gain(20.0, 80.0) - 20.0);
+    }
+
+    #[test]
+    fn test_iso226_level_gain() {
+        assert!(level_gain(1000.0, 60.0).unwrap().abs() < 1e-9);
+        // Each level follows its own contour.
+        for spl in [40.0, 60.0, 90.0] {
+            let gain = level_gain(100.0, spl).unwrap();
+            let phons = spl + gain;
+            assert!((contour_gain(100.0, phons) - gain).abs() < 1e-9);
+        }
+        // Bass at 50dB is much quieter than it measures.  At 90dB, less so.
+        assert!(level_gain(100.0, 50.0).unwrap() < level_gain(100.0, 90.0).unwrap() - 5.0);
+        assert_eq!(level_gain(100.0, 10.0), None);
+    }
+
+    #[test]
+    fn test_iso226_phon_round_trip() {
+        for freq in [31.5, 100.0, 1000.0, 4000.0, 10_000.0] {
+            for phons in [20.0, 40.0, 70.0, 90.0] {
+                let spl = phon_to_spl(freq, phons);
+                let back = spl_to_phon(freq, spl).unwrap();
+                assert!((back - phons).abs() < 0.01, "{freq}Hz {phons}: {back}");
+            }
+        }
+        // 20dB at 31.5Hz is far below hearing.
+        assert_eq!(spl_to_phon(31.5, 20.0), None);
+    }
 }
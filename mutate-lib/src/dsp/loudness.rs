@@ -0,0 +1,516 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Loudness
+//!
+//! Peak and RMS levels say little about how loud music sounds.  A bass-heavy mix can read hot on a
+//! meter while sounding quiet, and a mastered track can sit 10dB above an old recording at the same
+//! peak.  Visuals that scale against a running average of level chase the mix instead of the
+//! listener.  [`LoudnessMeter`] measures perceived program loudness per ITU-R BS.1770 as gated by
+//! EBU R128, in LUFS.
+//!
+//! Every channel is K-weighted, a high shelf for the head followed by a high-pass that ignores
+//! rumble, then squared and weighted by position.  Power is summed in 100ms steps:
+//!
+//! - **Momentary** is the mean of the last 400ms.
+//! - **Short-term** is the mean of the last 3s.
+//! - **Integrated** is the mean of every 400ms block since the last reset, gated twice.  Blocks
+//!   below [`ABSOLUTE_GATE`] are silence.  Blocks more than 10 LU below the mean of what remains are
+//!   quiet passages and also ignored, so that a fade does not drag the program level down.
+//!
+//! The meter also holds the [true peak](crate::dsp::peak) of every channel since the last reset,
+//! which R128 reports alongside the program loudness.
+//!
+//! [`LoudnessNode`] wraps a meter for the graph.  It turns the chosen measurement into a gain that
+//! brings the program to a `target` loudness, which is what visuals should scale by.
+
+use std::collections::VecDeque;
+
+use crate::dsp::iir::Biquad64;
+use crate::dsp::peak::{amplitude_db, TruePeak};
+use crate::dsp::units::SampleRate;
+use crate::graph::{
+    Frame, GraphEvent, Node, ParamHandle, ParamKind, ParamSpec, ParamValue, Params, PortKind,
+    PortSpec,
+};
+use crate::MutateError;
+
+/// Integrated loudness ignores blocks quieter than this, in LUFS.
+pub const ABSOLUTE_GATE: f64 = -70.0;
+/// Integrated loudness ignores blocks this far below the absolutely gated mean, in LU.
+pub const RELATIVE_GATE: f64 = -10.0;
+
+/// K-weighting gain at 997Hz, removed so that a sine near 1kHz reads its RMS level.
+const OFFSET: f64 = -0.691;
+/// Blocks are stepped at this interval.
+const STEP: f64 = 0.1;
+/// Steps per momentary block, 400ms.
+const MOMENTARY_STEPS: usize = 4;
+/// Steps per short-term window, 3s.
+const SHORT_TERM_STEPS: usize = 30;
+/// Resolution of the integrated block histogram, in LU.
+const HISTOGRAM_LU: f64 = 0.1;
+/// Histogram bins from the absolute gate to +10 LUFS.  Louder blocks land in the top bin.
+const HISTOGRAM_BINS: usize = 800;
+
+/// Loudness of a mean square power.  Silence is negative infinity.
+pub fn lufs(power: f64) -> f64 {
+    OFFSET + 10.0 * power.log10()
+}
+
+/// Mean square power of a loudness.
+pub fn power(lufs: f64) -> f64 {
+    10f64.powf((lufs - OFFSET) / 10.0)
+}
+
+/// The two K-weighting stages at `fs`, shelf then high-pass.  Designed from the analog prototype
+/// so that rates other than 48kHz match the published 48kHz coefficients.
+pub fn k_weighting(fs: SampleRate) -> [Biquad64; 2] {
+    let fs = fs.get();
+
+    // Head shelf, about +4dB above 2kHz.
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let shelf = Biquad64::from_coefficients(
+        [
+            vh + vb * k / q + k * k,
+            2.0 * (k * k - vh),
+            vh - vb * k / q + k * k,
+        ],
+        [
+            1.0 + k / q + k * k,
+            2.0 * (k * k - 1.0),
+            1.0 - k / q + k * k,
+        ],
+    );
+
+    // RLB high-pass, ignoring rumble below about 40Hz.
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    // The numerator is not normalized in the standard.
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad64::from_coefficients(
+        [a0, -2.0 * a0, a0],
+        [a0, 2.0 * (k * k - 1.0), 1.0 - k / q + k * k],
+    );
+
+    [shelf, high_pass]
+}
+
+/// Position weight of `channel`.  Five-point-one layouts, front left, right, center, LFE, then
+/// surrounds, weight the surrounds up and drop the LFE.  Everything else is equally weighted.
+pub fn channel_weight(channel: usize, channels: usize) -> f64 {
+    match (channels, channel) {
+        (6, 3) => 0.0,
+        (6, 4 | 5) => 1.41,
+        _ => 1.0,
+    }
+}
+
+/// Gated program loudness per BS.1770 and EBU R128.  See the [module](self) docs.
+pub struct LoudnessMeter {
+    channels: usize,
+    filters: Vec<[Biquad64; 2]>,
+    weights: Vec<f64>,
+    peaks: Vec<TruePeak>,
+    /// Frames per step.
+    step: usize,
+    /// Frames and weighted power summed so far in the current step.
+    filled: usize,
+    sum: f64,
+    /// Mean power of recent steps, oldest first.
+    steps: VecDeque<f64>,
+    /// Count and summed power of gated blocks, by loudness.
+    // NOTE binning bounds memory for long sessions.  Only the bin that straddles the relative gate
+    // is decided by its mean, which is within 0.1 LU of exact.
+    histogram: Vec<(u64, f64)>,
+}
+
+impl LoudnessMeter {
+    pub fn new(fs: SampleRate, channels: usize) -> Self {
+        let channels = channels.max(1);
+        Self {
+            channels,
+            filters: (0..channels).map(|_| k_weighting(fs)).collect(),
+            weights: (0..channels).map(|c| channel_weight(c, channels)).collect(),
+            peaks: vec![TruePeak::new(); channels],
+            step: (fs.get() * STEP).round() as usize,
+            filled: 0,
+            sum: 0.0,
+            steps: VecDeque::with_capacity(SHORT_TERM_STEPS),
+            histogram: vec![(0, 0.0); HISTOGRAM_BINS],
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Measure interleaved frames.
+    pub fn push(&mut self, frames: &[f32]) {
+        for frame in frames.chunks_exact(self.channels) {
+            let channels = frame.iter().zip(&mut self.filters).zip(&self.weights);
+            for (((&x, [shelf, high_pass]), &weight), peak) in channels.zip(&mut self.peaks) {
+                let y = high_pass.process(shelf.process(x as f64));
+                self.sum += weight * y * y;
+                peak.push(x);
+            }
+            self.filled += 1;
+            if self.filled == self.step {
+                self.finish_step();
+            }
+        }
+    }
+
+    fn finish_step(&mut self) {
+        if self.steps.len() == SHORT_TERM_STEPS {
+            self.steps.pop_front();
+        }
+        self.steps.push_back(self.sum / self.step as f64);
+        self.filled = 0;
+        self.sum = 0.0;
+
+        // Every step completes a new, overlapping momentary block.
+        if let Some(block) = self.mean(MOMENTARY_STEPS) {
+            let loudness = lufs(block);
+            if loudness > ABSOLUTE_GATE {
+                let bin = ((loudness - ABSOLUTE_GATE) / HISTOGRAM_LU) as usize;
+                let bin = &mut self.histogram[bin.min(HISTOGRAM_BINS - 1)];
+                bin.0 += 1;
+                bin.1 += block;
+            }
+        }
+    }
+
+    /// Mean power of the last `steps` steps, if there have been that many.
+    fn mean(&self, steps: usize) -> Option<f64> {
+        let recent = self.steps.len().checked_sub(steps)?;
+        Some(self.steps.range(recent..).sum::<f64>() / steps as f64)
+    }
+
+    /// Loudness of the last 400ms, in LUFS.
+    pub fn momentary(&self) -> Option<f64> {
+        self.mean(MOMENTARY_STEPS).map(lufs)
+    }
+
+    /// Loudness of the last 3s, in LUFS.
+    pub fn short_term(&self) -> Option<f64> {
+        self.mean(SHORT_TERM_STEPS).map(lufs)
+    }
+
+    /// Gated loudness since the last reset, in LUFS.  `None` until a block is above the absolute
+    /// gate.
+    pub fn integrated(&self) -> Option<f64> {
+        let (count, sum) = self
+            .histogram
+            .iter()
+            .fold((0, 0.0), |(n, s), &(count, sum)| (n + count, s + sum));
+        if count == 0 {
+            return None;
+        }
+        let gate = lufs(sum / count as f64) + RELATIVE_GATE;
+        let (count, sum) = self
+            .histogram
+            .iter()
+            .filter(|(count, sum)| *count > 0 && lufs(sum / *count as f64) > gate)
+            .fold((0, 0.0), |(n, s), &(count, sum)| (n + count, s + sum));
+        (count > 0).then(|| lufs(sum / count as f64))
+    }
+
+    /// Highest true peak of any channel since the last reset, linear.
+    pub fn true_peak(&self) -> f32 {
+        self.peaks.iter().map(TruePeak::peak).fold(0.0, f32::max)
+    }
+
+    /// [`true_peak`](Self::true_peak) in dBTP.  Silence is negative infinity.
+    pub fn true_peak_db(&self) -> f32 {
+        amplitude_db(self.true_peak())
+    }
+
+    /// Forget everything, such as when the track changes.  Filter state is kept so that the next
+    /// step does not start with a transient.
+    pub fn reset(&mut self) {
+        self.peaks.iter_mut().for_each(TruePeak::reset);
+        self.filled = 0;
+        self.sum = 0.0;
+        self.steps.clear();
+        self.histogram.fill((0, 0.0));
+    }
+}
+
+/// A [`LoudnessMeter`] in the graph.  Reports the gain that brings the program to `target`.
+pub struct LoudnessNode {
+    meter: LoudnessMeter,
+    rate: SampleRate,
+    params: Option<ParamHandle>,
+}
+
+impl LoudnessNode {
+    const REFERENCE: usize = 0;
+    const TARGET: usize = 1;
+    const MAX_GAIN: usize = 2;
+
+    pub fn new(fs: SampleRate, channels: usize) -> Self {
+        Self {
+            meter: LoudnessMeter::new(fs, channels),
+            rate: fs,
+            params: None,
+        }
+    }
+
+    /// Attach the handle returned by [`Graph::register`](crate::graph::Graph::register).
+    pub fn with_params(mut self, params: ParamHandle) -> Self {
+        self.params = Some(params);
+        self
+    }
+
+    pub fn meter(&self) -> &LoudnessMeter {
+        &self.meter
+    }
+
+    /// Re-provision for a new source format.  Measurements start over.
+    pub fn set_input(&mut self, fs: SampleRate, channels: usize) {
+        self.meter = LoudnessMeter::new(fs, channels);
+        self.rate = fs;
+    }
+
+    /// Start a new program, such as when the track changes.
+    pub fn reset(&mut self) {
+        self.meter.reset();
+    }
+
+    pub fn process(&mut self, frames: &[f32]) {
+        self.meter.push(frames);
+    }
+
+    /// The measurement chosen by `reference`, in LUFS.
+    pub fn loudness(&self) -> Option<f64> {
+        let reference = match &self.params {
+            Some(p) => p.f64(Self::REFERENCE) as usize,
+            None => 1,
+        };
+        match reference {
+            0 => self.meter.momentary(),
+            1 => self.meter.short_term(),
+            _ => self.meter.integrated(),
+        }
+    }
+
+    /// Linear gain to apply to levels so that the program draws as if it were at `target`.  Unity
+    /// until there is a measurement.
+    pub fn gain(&self) -> f64 {
+        let (target, max_gain) = match &self.params {
+            Some(p) => (p.f64(Self::TARGET), p.f64(Self::MAX_GAIN)),
+            None => (-23.0, 20.0),
+        };
+        match self.loudness() {
+            Some(loudness) => 10f64.powf((target - loudness).min(max_gain) / 20.0),
+            None => 1.0,
+        }
+    }
+}
+
+impl Params for LoudnessNode {
+    fn param_specs(&self) -> &'static [ParamSpec] {
+        &[
+            ParamSpec {
+                name: "reference",
+                description: "which measurement visuals scale against",
+                kind: ParamKind::Choice(&["momentary", "short-term", "integrated"]),
+                default: ParamValue::Choice(1),
+            },
+            ParamSpec {
+                name: "target",
+                description: "program loudness, in LUFS, that draws at unity",
+                kind: ParamKind::Float {
+                    min: -40.0,
+                    max: 0.0,
+                },
+                default: ParamValue::Float(-23.0),
+            },
+            ParamSpec {
+                name: "max_gain",
+                description: "dB of boost allowed for quiet programs",
+                kind: ParamKind::Float {
+                    min: 0.0,
+                    max: 40.0,
+                },
+                default: ParamValue::Float(20.0),
+            },
+        ]
+    }
+}
+
+impl Node for LoudnessNode {
+    fn inputs(&self) -> &'static [PortSpec] {
+        &[PortSpec {
+            name: "input",
+            kind: PortKind::Samples,
+        }]
+    }
+
+    fn outputs(&self) -> &'static [PortSpec] {
+        &[
+            PortSpec {
+                name: "loudness",
+                kind: PortKind::Scalar,
+            },
+            PortSpec {
+                name: "gain",
+                kind: PortKind::Scalar,
+            },
+            PortSpec {
+                name: "true_peak",
+                kind: PortKind::Scalar,
+            },
+        ]
+    }
+
+    fn attach(&mut self, params: ParamHandle) {
+        self.params = Some(params);
+    }
+
+    /// Re-provisions when the format arriving changes.  Emits `loudness` only once measured, and
+    /// `true_peak` in dBTP only once anything but silence arrived.
+    fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+        if let Some(GraphEvent::Samples {
+            frames,
+            channels,
+            rate,
+        }) = frame.input(0)
+        {
+            let rate = SampleRate(*rate as f64);
+            if *channels != self.meter.channels() || rate != self.rate {
+                self.set_input(rate, *channels);
+            }
+            self.process(frames);
+        }
+        if let Some(loudness) = self.loudness() {
+            frame.emit(0, GraphEvent::Scalar(loudness));
+        }
+        frame.emit(1, GraphEvent::Scalar(self.gain()));
+        let peak = self.meter.true_peak();
+        if peak > 0.0 {
+            frame.emit(2, GraphEvent::Scalar(amplitude_db(peak) as f64));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dsp::SineSweeper;
+    use crate::graph::Graph;
+
+    /// Stereo 997Hz sine, as EBU Tech 3341 uses, with `dbfs` peak level in both channels.
+    fn tone(dbfs: f64, seconds: f64) -> Vec<f32> {
+        let amplitude = 10f64.powf(dbfs / 20.0) as f32;
+        SineSweeper::new(997.0, 48_000.0)
+            .take((48_000.0 * seconds) as usize)
+            .flat_map(|x| [amplitude * x, amplitude * x])
+            .collect()
+    }
+
+    #[test]
+    fn test_k_weighting_response() {
+        // About +0.7dB near 1kHz, which the offset removes, +4dB of shelf above, and rumble cut.
+        let gain_db = |freq: f64| {
+            let [mut shelf, mut high_pass] = k_weighting(SampleRate(48_000.0));
+            let x: Vec<f64> = SineSweeper::new(freq, 48_000.0)
+                .take(96_000)
+                .map(|x| high_pass.process(shelf.process(x as f64)))
+                .collect();
+            let settled = &x[48_000..];
+            let power = settled.iter().map(|y| y * y).sum::<f64>() / settled.len() as f64;
+            10.0 * (2.0 * power).log10()
+        };
+        assert!((gain_db(997.0) + OFFSET).abs() < 0.01);
+        assert!((gain_db(10_000.0) - 4.0).abs() < 0.1);
+        assert!(gain_db(20.0) < -10.0);
+    }
+
+    #[test]
+    fn test_loudness_of_sine() {
+        // A stereo 997Hz sine at -23dBFS is -23 LUFS, EBU Tech 3341 case 1.
+        let mut meter = LoudnessMeter::new(SampleRate(48_000.0), 2);
+        assert_eq!(meter.momentary(), None);
+        meter.push(&tone(-23.0, 20.0));
+        for measured in [meter.momentary(), meter.short_term(), meter.integrated()] {
+            let measured = measured.unwrap();
+            assert!((measured + 23.0).abs() < 0.1, "{measured}");
+        }
+    }
+
+    #[test]
+    fn test_loudness_gates() {
+        // Silence is below the absolute gate.
+        let mut meter = LoudnessMeter::new(SampleRate(48_000.0), 2);
+        meter.push(&tone(-23.0, 10.0));
+        meter.push(&tone(-90.0, 10.0));
+        let integrated = meter.integrated().unwrap();
+        assert!((integrated + 23.0).abs() < 0.1, "{integrated}");
+
+        // A quiet passage is below the relative gate.  EBU Tech 3341 case 3.
+        meter.reset();
+        meter.push(&tone(-36.0, 10.0));
+        meter.push(&tone(-23.0, 60.0));
+        meter.push(&tone(-36.0, 10.0));
+        let integrated = meter.integrated().unwrap();
+        assert!((integrated + 23.0).abs() < 0.1, "{integrated}");
+        assert!(meter.short_term().unwrap() < -30.0);
+    }
+
+    #[test]
+    fn test_loudness_true_peak() {
+        // A quarter-rate sine sampled 45° off its crest reads 3dB low by sample peak.
+        let mut meter = LoudnessMeter::new(SampleRate(48_000.0), 2);
+        assert_eq!(meter.true_peak(), 0.0);
+        let crest = std::f32::consts::FRAC_PI_4;
+        let frames: Vec<f32> = (0..4_800)
+            .flat_map(|n| {
+                let x = 0.5 * (std::f32::consts::FRAC_PI_2 * n as f32 + crest).sin();
+                [x, 0.25 * x]
+            })
+            .collect();
+        meter.push(&frames);
+        let db = meter.true_peak_db();
+        let expected = amplitude_db(0.5);
+        assert!((-0.4..=0.2).contains(&(db - expected)), "{db}");
+
+        meter.reset();
+        assert_eq!(meter.true_peak(), 0.0);
+    }
+
+    #[test]
+    fn test_loudness_node_gain() {
+        let mut graph = Graph::new();
+        let node = LoudnessNode::new(SampleRate(48_000.0), 2);
+        let (_, params) = graph.register("loudness", &node).unwrap();
+        let mut node = node.with_params(params);
+        assert_eq!(node.gain(), 1.0);
+
+        node.process(&tone(-29.0, 4.0));
+        let gain = node.gain();
+        assert!((20.0 * gain.log10() - 6.0).abs() < 0.1, "{gain}");
+
+        graph
+            .set("loudness/reference", ParamValue::Choice(2))
+            .unwrap();
+        graph
+            .set("loudness/target", ParamValue::Float(-20.0))
+            .unwrap();
+        let gain = node.gain();
+        assert!((20.0 * gain.log10() - 9.0).abs() < 0.1, "{gain}");
+
+        // Quiet programs are not boosted without bound.
+        node.reset();
+        node.process(&tone(-60.0, 4.0));
+        assert!((20.0 * node.gain().log10() - 20.0).abs() < 1e-9);
+    }
+}
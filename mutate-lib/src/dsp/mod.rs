@@ -62,24 +62,49 @@ use std::f64::consts::{SQRT_2 as SQRT_2_64, TAU as TAU64};
 use num_complex::Complex;
 
 pub mod bank;
+pub mod beat;
+pub mod calibration;
+pub mod chroma;
+#[cfg(feature = "vulkan")]
+pub mod compute;
+pub mod delay;
 pub mod dft;
+pub mod fft;
 pub mod fir;
 pub mod iir;
 pub mod iso226;
+pub mod loudness;
+pub mod peak;
+pub mod resample;
+pub mod segment;
+pub mod sizing;
 pub mod spectrogram;
+pub mod staging;
+pub use crate::units;
 pub mod window;
 
+pub mod prelude {
+    pub use super::units::{Frames, Hertz, SampleRate, Samples, Seconds};
+    pub use super::{Filter, FilterArgs, FilterMode};
+}
+
+use units::{Hertz, SampleRate, Samples};
+
 /// Old people and rock stars cannot hear above certain frequencies.  Even if the sampling rate will
 /// allow us to resolve higher frequencies, there is little visually interesting above them, and
 /// only trouble makers who carry on and talk back seem to respond to them anyway.  🦕🦕🦕🦕
-// NEXT user setting
+///
+/// Default of the `dsp.max_freq` [setting](crate::settings).
+// NEXT analysis nodes read `graph::config::MAX_FREQ` instead.
 // XXX remove from CQT
 pub const MAX_FREQ_OLD_PEOPLE: f64 = 12_333.0;
 /// Unless you have some $2000 headphones or a room built to collect energy at 20Hz, there is little
 /// to perceive and thus little to draw below this frequency.  It is also very difficult to measure
 /// very slow waves since they are almost entirely smooth DC that will always take a while for any
 /// detector to phase-lock on while high-cutting literally everything else.
-// NEXT user setting
+///
+/// Default of the `dsp.min_freq` [setting](crate::settings).
+// NEXT analysis nodes read `graph::config::MIN_FREQ` instead.
 // XXX remove from CQT
 pub const MIN_FREQ_CHEAP_DRIVERS: f64 = 24.0;
 
@@ -112,9 +137,9 @@ pub struct FilterArgs {
     /// Quality factor equal to `center` / `bandwidth`.
     pub q: f64,
     /// Frequency where the peak gain is located.
-    pub center: f64,
+    pub center: Hertz,
     /// Frequency of the sample rate.
-    pub fs: f64,
+    pub fs: SampleRate,
     /// A final gain factor.  This is applied to the output of an individual filter or to the final
     /// output of any cascade of filters.  Individual filters should be gain normalized where
     /// possible to make gain leveling easier for banks of filters.
@@ -144,14 +169,12 @@ impl FilterArgs {
     /// Return a `SineSweeper` for the center frequency.  You can modulate the sine wave before
     /// reading if you want another center frequency.
     pub fn sine_gen(&self) -> SineSweeper {
-        // DEBT we are throwing around Center and Sample frequencies a bit too haphazardly, and it's
-        // goint to bite us.
         SineSweeper::new(self.center, self.fs)
     }
 
     /// Return the number of samples required to complete `nwaves` cycles at the center frequency.
-    pub fn nsamples(&self, nwaves: f64) -> usize {
-        (self.fs / self.center * nwaves).ceil() as usize
+    pub fn nsamples(&self, nwaves: f64) -> Samples {
+        self.fs.cycles(self.center, nwaves)
     }
 }
 
@@ -159,8 +182,8 @@ impl Default for FilterArgs {
     fn default() -> Self {
         FilterArgs {
             q: 10.0,
-            center: 1000.0,
-            fs: 48_000.0,
+            center: Hertz(1000.0),
+            fs: SampleRate(48_000.0),
             gain_factor: 1.0,
             butterworth: false,
             mode: FilterMode::BandPass,
@@ -299,19 +322,21 @@ pub trait RealDownsampler<T>: Resampler<Input = T, Output = Option<T>> {}
 
 /// Sine wave generator with frequency modulation.  Use to generate rough chirps to quickly look for
 /// changes in filter response.
+#[derive(Clone, Debug)]
 pub struct SineSweeper {
     re: f64,
     im: f64,
     omega: f64,
     cos: f64,
     sin: f64,
-    fs: f64,
-    f0: f64,
+    fs: SampleRate,
+    f0: Hertz,
 }
 
 impl SineSweeper {
-    pub fn new(f0: f64, fs: f64) -> Self {
-        let omega = TAU64 * f0 / fs;
+    pub fn new(f0: impl Into<Hertz>, fs: impl Into<SampleRate>) -> Self {
+        let (f0, fs) = (f0.into(), fs.into());
+        let omega = fs.omega(f0);
         Self {
             re: 1.0,
             im: 0.0,
@@ -325,20 +350,20 @@ impl SineSweeper {
 
     /// Update the frequency on the fly.  Does not modify the current phase, only the angular
     /// velocity.
-    pub fn set_frequency(&mut self, f0: f64) {
-        self.omega = TAU64 * f0 / self.fs;
+    pub fn set_frequency(&mut self, f0: impl Into<Hertz>) {
+        self.omega = self.fs.omega(f0.into());
         self.cos = self.omega.cos();
         self.sin = self.omega.sin();
     }
 
     /// Read the current center frequency.
-    pub fn center(&self) -> f64 {
+    pub fn center(&self) -> Hertz {
         self.f0
     }
 
     /// Return the number of samples required to cover `nwaves` full cycles.
-    pub fn nsamples(&self, nwaves: f64) -> usize {
-        (self.fs / self.f0 * nwaves).ceil() as usize
+    pub fn nsamples(&self, nwaves: f64) -> Samples {
+        self.fs.cycles(self.f0, nwaves)
     }
 }
 
@@ -0,0 +1,204 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # True Peak
+//!
+//! Sample peaks understate the real signal peak.  When the waveform crests between two samples, the
+//! reconstructed analog signal overshoots every stored value.  A sine at a quarter of the sample
+//! rate with a 45° phase offset never lands a sample on its crest and reads 3dB low.  Clipping
+//! indicators and loudness range statistics need the intersample peak.
+//!
+//! [`TruePeak`] follows ITU-R BS.1770-4 Annex 2: oversample by four with a 48 tap polyphase FIR,
+//! take absolute values, and hold the maximum.  The FIR coefficients are the ones printed in the
+//! recommendation so that readings agree with other compliant meters.  The recommendation's 12.04dB
+//! pre-attenuation only exists to protect fixed-point headroom and is omitted for floats.
+//!
+//! ## Streaming
+//!
+//! The detector keeps the last 12 input samples, so blocks of any length can be fed without seams.
+//! Each block returns its own true peak while [`TruePeak::peak`] holds the running maximum until
+//! [`TruePeak::reset`].
+
+/// Oversampling factor from BS.1770-4.
+pub const OVERSAMPLE: usize = 4;
+/// Taps per polyphase branch.
+const PHASE_TAPS: usize = 12;
+
+/// BS.1770-4 Annex 2 interpolation filter, split into its four phases.  Coefficients are applied
+/// oldest sample first.
+const PHASES: [[f32; PHASE_TAPS]; OVERSAMPLE] = [
+    [
+        0.0017089843750,
+        0.0109863281250,
+        -0.0196533203125,
+        0.0332031250000,
+        -0.0594482421875,
+        0.1373291015625,
+        0.9721679687500,
+        -0.1022949218750,
+        0.0476074218750,
+        -0.0266113281250,
+        0.0148925781250,
+        -0.0083007812500,
+    ],
+    [
+        -0.0291748046875,
+        0.0292968750000,
+        -0.0517578125000,
+        0.0891113281250,
+        -0.1665039062500,
+        0.4650878906250,
+        0.7797851562500,
+        -0.2003173828125,
+        0.1015625000000,
+        -0.0582275390625,
+        0.0330810546875,
+        -0.0189208984375,
+    ],
+    [
+        -0.0189208984375,
+        0.0330810546875,
+        -0.0582275390625,
+        0.1015625000000,
+        -0.2003173828125,
+        0.7797851562500,
+        0.4650878906250,
+        -0.1665039062500,
+        0.0891113281250,
+        -0.0517578125000,
+        0.0292968750000,
+        -0.0291748046875,
+    ],
+    [
+        -0.0083007812500,
+        0.0148925781250,
+        -0.0266113281250,
+        0.0476074218750,
+        -0.1022949218750,
+        0.9721679687500,
+        0.1373291015625,
+        -0.0594482421875,
+        0.0332031250000,
+        -0.0196533203125,
+        0.0109863281250,
+        0.0017089843750,
+    ],
+];
+
+/// Streaming 4x oversampled true-peak detector for one channel.
+#[derive(Clone, Debug, Default)]
+pub struct TruePeak {
+    /// Most recent inputs, oldest first.
+    history: [f32; PHASE_TAPS],
+    peak: f32,
+}
+
+impl TruePeak {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Push one sample and return the largest of its four interpolated values.
+    pub fn push(&mut self, sample: f32) -> f32 {
+        self.history.copy_within(1.., 0);
+        self.history[PHASE_TAPS - 1] = sample;
+        let max = PHASES
+            .iter()
+            .map(|phase| {
+                phase
+                    .iter()
+                    .zip(&self.history)
+                    .map(|(c, x)| c * x)
+                    .sum::<f32>()
+                    .abs()
+            })
+            .fold(0.0, f32::max);
+        self.peak = self.peak.max(max);
+        max
+    }
+
+    /// Feed a block and return its true peak as linear amplitude.
+    pub fn process(&mut self, block: &[f32]) -> f32 {
+        block.iter().fold(0.0, |m, &x| m.max(self.push(x)))
+    }
+
+    /// Highest true peak since creation or the last [`reset`](Self::reset).
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    /// [`peak`](Self::peak) in dBTP, decibels relative to full scale.
+    pub fn peak_db(&self) -> f32 {
+        amplitude_db(self.peak)
+    }
+
+    /// Forget the held peak.  Filter history is kept so the stream stays seamless.
+    pub fn reset(&mut self) {
+        self.peak = 0.0;
+    }
+}
+
+/// Linear amplitude to decibels.  Silence is negative infinity.
+pub fn amplitude_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.log10()
+}
+
+/// True peak of a whole buffer, linear.
+pub fn true_peak(samples: &[f32]) -> f32 {
+    TruePeak::new().process(samples)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_4, TAU};
+
+    fn sine(freq: f32, fs: f32, phase: f32, amplitude: f32, n: usize) -> Vec<f32> {
+        (0..n)
+            .map(|i| amplitude * (TAU * freq * i as f32 / fs + phase).sin())
+            .collect()
+    }
+
+    fn sample_peak(x: &[f32]) -> f32 {
+        x.iter().fold(0.0, |m, v| m.max(v.abs()))
+    }
+
+    // EBU Tech 3341 tolerance for true-peak meters is +0.2dB / -0.4dB.
+    fn assert_within(measured_db: f32, expected_db: f32) {
+        let err = measured_db - expected_db;
+        assert!(
+            (-0.4..=0.2).contains(&err),
+            "measured {measured_db} dB, expected {expected_db} dB"
+        );
+    }
+
+    #[test]
+    fn test_true_peak_quarter_rate() {
+        // fs/4 with 45° phase never samples the crest.  Samples read -9.03dBFS for a -6dB sine.
+        let x = sine(12_000.0, 48_000.0, FRAC_PI_4, 0.5, 4800);
+        assert_within(amplitude_db(sample_peak(&x)), -9.03);
+        let mut tp = TruePeak::new();
+        tp.process(&x);
+        assert_within(tp.peak_db(), -6.02);
+    }
+
+    #[test]
+    fn test_true_peak_low_frequency() {
+        // Well below fs/4 the samples already land near the crest.
+        let x = sine(997.0, 48_000.0, 0.0, 1.0, 48_000);
+        assert_within(amplitude_db(true_peak(&x)), 0.0);
+    }
+
+    #[test]
+    fn test_true_peak_streaming_matches_whole() {
+        let x = sine(11_025.0, 44_100.0, 0.3, 0.8, 4410);
+        let whole = true_peak(&x);
+        let mut tp = TruePeak::new();
+        for block in x.chunks(37) {
+            tp.process(block);
+        }
+        assert_eq!(tp.peak(), whole);
+        tp.reset();
+        assert_eq!(tp.peak(), 0.0);
+    }
+}
@@ -0,0 +1,519 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Song Segmentation
+//!
+//! Visuals that follow the beat but ignore the song's form feel like a screensaver.  When the
+//! chorus hits, viewers expect the picture to change with it.  The [`Segmenter`] watches a slow
+//! stream of feature vectors, such as chroma or MFCC frames a few times per second, and reports
+//! section boundaries along with whether the new section repeats an earlier one.
+//!
+//! ## Novelty
+//!
+//! Frames are compared by cosine similarity.  Within a section, frames resemble each other.  Across
+//! a boundary, they resemble each other much less.  Sliding a checkerboard kernel along the diagonal
+//! of the self-similarity matrix measures exactly this contrast (Foote, 2000).  The kernel is
+//! tapered with a Gaussian so that frames near the center dominate.  Peaks of the resulting novelty
+//! curve are boundaries.
+//!
+//! A peak is only confirmed once the curve has fallen on both sides, so boundaries are reported
+//! `kernel + kernel / 4` frames after they happen.  At four frames per second and the default
+//! kernel, that is five seconds.  Section changes are slow enough that the delay reads as a
+//! deliberate transition.  Use [`SectionEvent::at`], not the time of arrival, when lining events up
+//! with the audio.
+//!
+//! ## Repetition
+//!
+//! Each section is summarized by the average of its opening `kernel` frames.  A new section whose
+//! summary matches an earlier one takes the earlier section's [`SectionLabel`], so verse, chorus,
+//! verse, chorus reads as `A B A B`.  Deciding which label is the chorus is left to consumers.  The
+//! most repeated label is usually a good guess.
+//!
+//! ## Graph
+//!
+//! The [`SegmentNode`] averages rows, such as the chroma node's, down to [`FRAME_RATE`] and emits
+//! the label of the section playing.  The visualizer's `drive` node changes scenes on it.  The
+//! segmenter runs on the graph's [worker pool](crate::graph::pool) when there is one.
+
+// NEXT MFCC frames, which tell sections apart by timbre where chroma only hears harmony.
+// NEXT palettes change on sections too.  See `color`.
+
+use std::collections::VecDeque;
+
+use crate::dsp::units::Seconds;
+use crate::graph::{
+    Frame, GraphEvent, Node, Offload, ParamHandle, ParamSpec, Params, PortKind, PortSpec, Priority,
+    WorkerPool,
+};
+use crate::MutateError;
+
+/// Default half-width of the checkerboard kernel in frames.
+const KERNEL: usize = 16;
+/// Gaussian taper width as a fraction of the kernel half-width.
+const TAPER: f64 = 0.5;
+/// Cosine similarity at which a section's opening counts as a repeat of an earlier one.
+const REPEAT: f32 = 0.9;
+/// Feature frames per second the [`SegmentNode`] hands its segmenter.
+pub const FRAME_RATE: f64 = 4.0;
+
+/// Sections that sound alike share a label.  Displays as `A`, `B`, ... `Z`, `AA`, ...
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SectionLabel(pub u32);
+
+impl std::fmt::Display for SectionLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let letter = (b'A' + (self.0 % 26) as u8) as char;
+        for _ in 0..=self.0 / 26 {
+            write!(f, "{letter}")?;
+        }
+        Ok(())
+    }
+}
+
+/// One section of the song so far.
+#[derive(Clone, Debug)]
+pub struct Section {
+    pub start: Seconds,
+    pub label: SectionLabel,
+    /// Unit-length average of the section's opening frames.
+    signature: Vec<f32>,
+}
+
+/// A section boundary was found.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SectionEvent {
+    /// When the new section began.
+    pub at: Seconds,
+    /// Position of the new section in [`Segmenter::sections`].
+    pub index: usize,
+    pub label: SectionLabel,
+    /// Earlier section that this one repeats, if any.
+    pub repeat_of: Option<usize>,
+    /// Height of the novelty peak, `0.0..=1.0`.  Larger means a more abrupt change.
+    pub novelty: f32,
+}
+
+/// Novelty measured at one frame, waiting to be confirmed as a peak or not.
+struct Candidate {
+    at: Seconds,
+    novelty: f32,
+    /// Average of the `kernel` frames starting here, the signature if this becomes a boundary.
+    ahead: Vec<f32>,
+}
+
+/// Detects section boundaries and repetition in a stream of feature vectors.
+pub struct Segmenter {
+    kernel: usize,
+    /// Checkerboard weights, `2 * kernel` squared, normalized to unit absolute sum.
+    weights: Vec<f32>,
+    /// Unit-length frames, at most `2 * kernel`.
+    frames: VecDeque<(Seconds, Vec<f32>)>,
+    /// Novelty around the peak being tested, at most `2 * (kernel / 4) + 1`.
+    candidates: VecDeque<Candidate>,
+    sections: Vec<Section>,
+    threshold: f32,
+    /// Minimum frames between boundaries.
+    min_section: usize,
+    /// Frames since the last boundary was confirmed.
+    since_boundary: usize,
+}
+
+impl Segmenter {
+    /// `kernel` is the half-width of the novelty kernel in frames.  Changes that are shorter than
+    /// the kernel are smoothed over.  Defaults to 16.
+    pub fn new(kernel: usize) -> Self {
+        let kernel = kernel.max(2);
+        let size = 2 * kernel;
+        let sigma = TAPER * kernel as f64;
+        let mut weights = Vec::with_capacity(size * size);
+        for i in 0..size {
+            for j in 0..size {
+                // Offsets from the center, between frames `kernel - 1` and `kernel`.
+                let di = i as f64 - kernel as f64 + 0.5;
+                let dj = j as f64 - kernel as f64 + 0.5;
+                let sign = if (i < kernel) == (j < kernel) {
+                    1.0
+                } else {
+                    -1.0
+                };
+                let taper = (-(di * di + dj * dj) / (2.0 * sigma * sigma)).exp();
+                weights.push(sign * taper);
+            }
+        }
+        let total = weights.iter().map(|w| w.abs()).sum::<f64>();
+        let weights = weights.into_iter().map(|w| (w / total) as f32).collect();
+
+        Self {
+            kernel,
+            weights,
+            frames: VecDeque::with_capacity(size),
+            candidates: VecDeque::new(),
+            sections: Vec::new(),
+            threshold: 0.15,
+            min_section: kernel,
+            since_boundary: 0,
+        }
+    }
+
+    /// Novelty a peak must reach to count as a boundary.  Defaults to `0.15`.
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Minimum length of a section in frames.  Defaults to the kernel half-width.
+    pub fn with_min_section(mut self, frames: usize) -> Self {
+        self.min_section = frames;
+        self
+    }
+
+    /// Sections found so far, including the opening section once `kernel` frames have arrived.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Feed one feature frame.  Frames must arrive at a steady rate with the same length.
+    pub fn push(&mut self, at: Seconds, features: &[f32]) -> Option<SectionEvent> {
+        if self.frames.len() == 2 * self.kernel {
+            self.frames.pop_front();
+        }
+        self.frames.push_back((at, unit(features.iter().copied())));
+        self.since_boundary += 1;
+
+        if self.sections.is_empty() && self.frames.len() == self.kernel {
+            let signature = self.mean(0..self.kernel);
+            self.sections.push(Section {
+                start: self.frames[0].0,
+                label: SectionLabel(0),
+                signature,
+            });
+        }
+        if self.frames.len() < 2 * self.kernel {
+            return None;
+        }
+
+        let candidate = Candidate {
+            at: self.frames[self.kernel].0,
+            novelty: self.novelty(),
+            ahead: self.mean(self.kernel..2 * self.kernel),
+        };
+        let reach = self.kernel / 4;
+        if self.candidates.len() == 2 * reach + 1 {
+            self.candidates.pop_front();
+        }
+        self.candidates.push_back(candidate);
+        if self.candidates.len() < 2 * reach + 1 {
+            return None;
+        }
+
+        // The center is a peak when nothing before it is as high and nothing after it is higher.
+        let center = &self.candidates[reach];
+        let is_peak = self.candidates.iter().enumerate().all(|(i, c)| match i {
+            i if i < reach => c.novelty < center.novelty,
+            i if i > reach => c.novelty <= center.novelty,
+            _ => true,
+        });
+        // The boundary happened `reach` frames ago, so that is how long the section has been.
+        if !is_peak
+            || center.novelty < self.threshold
+            || self.since_boundary < self.min_section + reach
+        {
+            return None;
+        }
+        self.since_boundary = reach;
+        Some(self.begin_section())
+    }
+
+    /// Start a new section at the confirmed center candidate.
+    fn begin_section(&mut self) -> SectionEvent {
+        let center = &self.candidates[self.kernel / 4];
+        let best = self
+            .sections
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (i, dot(&s.signature, &center.ahead)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        let repeat_of = best.filter(|&(_, sim)| sim >= REPEAT).map(|(i, _)| i);
+        let label = match repeat_of {
+            Some(i) => self.sections[i].label,
+            None => SectionLabel(
+                self.sections
+                    .iter()
+                    .map(|s| s.label.0 + 1)
+                    .max()
+                    .unwrap_or(0),
+            ),
+        };
+        let event = SectionEvent {
+            at: center.at,
+            index: self.sections.len(),
+            label,
+            repeat_of,
+            novelty: center.novelty,
+        };
+        self.sections.push(Section {
+            start: center.at,
+            label,
+            signature: center.ahead.clone(),
+        });
+        event
+    }
+
+    /// Checkerboard kernel correlated with the self-similarity of the current frames.
+    fn novelty(&self) -> f32 {
+        let size = 2 * self.kernel;
+        let mut sum = 0.0;
+        for i in 0..size {
+            for j in 0..size {
+                let sim = dot(&self.frames[i].1, &self.frames[j].1);
+                sum += self.weights[i * size + j] * sim;
+            }
+        }
+        sum.max(0.0)
+    }
+
+    /// Unit-length average of a range of frames.
+    fn mean(&self, range: std::ops::Range<usize>) -> Vec<f32> {
+        let len = self.frames[range.start].1.len();
+        let mut acc = vec![0.0; len];
+        for (_, f) in self.frames.range(range) {
+            for (a, x) in acc.iter_mut().zip(f) {
+                *a += x;
+            }
+        }
+        unit(acc.into_iter())
+    }
+}
+
+impl Default for Segmenter {
+    fn default() -> Self {
+        Self::new(KERNEL)
+    }
+}
+
+/// A [`Segmenter`] in the graph.  See the [module](self) docs.
+///
+/// The segmenter runs on the pool at [`Priority::Background`], since sections are found seconds
+/// late anyway.  Frames averaged while it runs wait for its next job.
+pub struct SegmentNode {
+    work: Offload<Segmenter>,
+    /// Averaged frames waiting for the segmenter.
+    pending: Vec<(Seconds, Vec<f32>)>,
+    /// The section playing as of the last job collected.
+    label: Option<SectionLabel>,
+    /// Rows of input per second.
+    rate: f64,
+    /// Rows processed.
+    rows: u64,
+    /// Rows summed since the last frame.
+    sum: Vec<f32>,
+    summed: usize,
+}
+
+impl SegmentNode {
+    /// `rate` is rows of input per second.
+    pub fn new(rate: f64) -> Self {
+        Self {
+            work: Offload::new(Segmenter::default()),
+            pending: Vec::new(),
+            label: None,
+            rate: rate.max(FRAME_RATE),
+            rows: 0,
+            sum: Vec::new(),
+            summed: 0,
+        }
+    }
+
+    /// The section playing, once the opening has been heard.
+    pub fn label(&self) -> Option<SectionLabel> {
+        self.label
+    }
+
+    /// Add one row, handing the segmenter their average on `pool` once a frame's worth has
+    /// arrived.  Rows of a new width start over.
+    pub fn process(&mut self, row: &[f32], pool: Option<&WorkerPool>) -> Result<(), MutateError> {
+        if row.len() != self.sum.len() {
+            *self = Self::new(self.rate);
+            self.sum = vec![0.0; row.len()];
+        }
+        for (s, x) in self.sum.iter_mut().zip(row) {
+            *s += x;
+        }
+        self.summed += 1;
+        self.rows += 1;
+        let per_frame = (self.rate / FRAME_RATE).round() as usize;
+        if self.summed == per_frame {
+            let at = Seconds((self.rows - self.summed as u64) as f64 / self.rate);
+            let frame = std::mem::replace(&mut self.sum, vec![0.0; row.len()]);
+            self.summed = 0;
+            self.pending.push((at, frame));
+        }
+        self.collect(pool)
+    }
+
+    /// Unless the segmenter is busy, read its label and hand it the frames waiting.
+    fn collect(&mut self, pool: Option<&WorkerPool>) -> Result<(), MutateError> {
+        if !self.work.ready()? {
+            return Ok(());
+        }
+        self.label = self.work.lock()?.sections().last().map(|s| s.label);
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let frames = std::mem::take(&mut self.pending);
+        self.work
+            .submit(pool, Priority::Background, move |segmenter| {
+                for (at, frame) in frames {
+                    segmenter.push(at, &frame);
+                }
+            })?;
+        // Without a pool, the frames were just segmented.
+        if self.work.ready()? {
+            self.label = self.work.lock()?.sections().last().map(|s| s.label);
+        }
+        Ok(())
+    }
+}
+
+impl Params for SegmentNode {
+    fn param_specs(&self) -> &'static [ParamSpec] {
+        &[]
+    }
+}
+
+impl Node for SegmentNode {
+    fn inputs(&self) -> &'static [PortSpec] {
+        &[PortSpec {
+            name: "features",
+            kind: PortKind::Row,
+        }]
+    }
+
+    fn outputs(&self) -> &'static [PortSpec] {
+        &[PortSpec {
+            name: "section",
+            kind: PortKind::Scalar,
+        }]
+    }
+
+    fn attach(&mut self, _params: ParamHandle) {}
+
+    /// Emits `section`, the label's number, every frame once the opening has been heard.
+    fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+        match frame.input(0) {
+            Some(GraphEvent::Row(row)) => self.process(row, frame.pool())?,
+            _ => self.collect(frame.pool())?,
+        }
+        if let Some(label) = self.label() {
+            frame.emit(0, GraphEvent::Scalar(label.0 as f64));
+        }
+        Ok(())
+    }
+}
+
+/// Scale to unit length.  Silence stays zero and is similar to nothing.
+fn unit(features: impl Iterator<Item = f32>) -> Vec<f32> {
+    let v: Vec<f32> = features.collect();
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > f32::EPSILON {
+        v.into_iter().map(|x| x / norm).collect()
+    } else {
+        v
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RATE: f64 = 4.0;
+
+    /// A chroma-like frame with energy on three pitch classes plus deterministic noise.
+    fn frame(chord: [usize; 3], seed: &mut u32) -> Vec<f32> {
+        (0..12)
+            .map(|i| {
+                *seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                let noise = (*seed >> 8) as f32 / (1u32 << 24) as f32 * 0.3;
+                if chord.contains(&i) {
+                    1.0 + noise
+                } else {
+                    noise
+                }
+            })
+            .collect()
+    }
+
+    fn run(form: &[([usize; 3], usize)]) -> (Vec<SectionEvent>, Segmenter) {
+        let mut seg = Segmenter::default();
+        let mut seed = 7;
+        let mut n = 0;
+        let mut events = Vec::new();
+        for &(chord, frames) in form {
+            for _ in 0..frames {
+                let at = Seconds(n as f64 / RATE);
+                events.extend(seg.push(at, &frame(chord, &mut seed)));
+                n += 1;
+            }
+        }
+        (events, seg)
+    }
+
+    const A: [usize; 3] = [0, 4, 7];
+    const B: [usize; 3] = [5, 9, 0];
+    const C: [usize; 3] = [7, 11, 2];
+
+    #[test]
+    fn test_segment_boundaries_and_repeats() {
+        let (events, seg) = run(&[(A, 64), (B, 64), (A, 64), (C, 64), (B, 64)]);
+        let starts: Vec<f64> = events.iter().map(|e| e.at.get() * RATE).collect();
+        assert_eq!(events.len(), 4, "{starts:?}");
+        for (e, expected) in events.iter().zip([64.0, 128.0, 192.0, 256.0]) {
+            assert!((e.at.get() * RATE - expected).abs() <= 2.0, "{starts:?}");
+        }
+
+        let labels: String = seg.sections().iter().map(|s| s.label.to_string()).collect();
+        assert_eq!(labels, "ABACB");
+        assert_eq!(events[1].repeat_of, Some(0));
+        assert_eq!(events[2].repeat_of, None);
+        assert_eq!(events[3].repeat_of, Some(1));
+    }
+
+    #[test]
+    fn test_segment_steady_has_no_boundaries() {
+        let (events, seg) = run(&[(A, 300)]);
+        assert!(events.is_empty());
+        assert_eq!(seg.sections().len(), 1);
+    }
+
+    #[test]
+    fn test_segment_node() {
+        // Fifteen rows to a frame, so each section is 64 frames as above.
+        let mut node = SegmentNode::new(60.0);
+        let mut seed = 7;
+        let mut labels = Vec::new();
+        for chord in [A, B, A] {
+            for _ in 0..64 * 15 {
+                node.process(&frame(chord, &mut seed), None).unwrap();
+                if labels.last() != Some(&node.label()) {
+                    labels.push(node.label());
+                }
+            }
+        }
+        let [a, b] = [Some(SectionLabel(0)), Some(SectionLabel(1))];
+        assert_eq!(labels, [None, a, b, a]);
+
+        node.process(&[1.0; 3], None).unwrap();
+        assert_eq!(node.label(), None);
+    }
+
+    #[test]
+    fn test_segment_label_display() {
+        assert_eq!(SectionLabel(0).to_string(), "A");
+        assert_eq!(SectionLabel(25).to_string(), "Z");
+        assert_eq!(SectionLabel(27).to_string(), "BB");
+    }
+}
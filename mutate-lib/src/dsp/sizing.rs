@@ -0,0 +1,366 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Window Sizing
+//!
+//! Every bin of the bank wants the shortest window that still resolves its Q.  Longer windows are
+//! sharper but slower to rise, and rise time is what makes visuals feel late.  Guessing lengths by
+//! hand means iterating on each window type and attenuation.
+//!
+//! For a fixed window shape, a DFT's bandwidth is a constant number of bins, where a bin is
+//! `fs / length`.  Achieved Q is therefore proportional to the number of cycles in the window, and
+//! rise time is a constant fraction of its length.  [`sweep`] measures real [`Dft`] filters at
+//! several lengths, [`fit`] reduces the measurements to those two constants, and the resulting
+//! [`WindowFit`] picks lengths for the bank in closed form.
+//!
+//! Choosing between windows is a matter of taste.  [`WindowFit::score`] puts rise time, missed Q,
+//! and side lobes above a noise goal on one scale so that an optimizer can compare them per bin.
+//!
+//! The measurements are empirical on purpose.  They run the same `f32` window weights and the same
+//! COLA re-summing cadence that the bank will use, so the constants include every effect that a
+//! textbook equivalent noise bandwidth would miss.
+
+use crate::dsp::bank::Bin;
+use crate::dsp::dft::Dft;
+use crate::dsp::units::{Hertz, SampleRate, Samples, Seconds};
+use crate::dsp::window::WindowFunction;
+use crate::dsp::Filter;
+
+/// Fraction of the steady-state gain that counts as risen.
+pub const RISE_GOAL: f32 = 0.9;
+/// Frequency step, in bins, while walking out of the main lobe.
+const LOBE_STEP: f64 = 0.125;
+/// Walking further than this many bins means the threshold is below the side lobes.
+const LOBE_LIMIT: f64 = 16.0;
+/// Bisection steps after the threshold is bracketed.
+const BISECT: usize = 24;
+
+/// One measured window length.
+#[derive(Clone, Copy, Debug)]
+pub struct SweepPoint {
+    pub length: usize,
+    /// Window length in cycles of the center frequency.
+    pub cycles: f64,
+    /// Center over the full width at the threshold.
+    pub q: f64,
+    /// Full width at the threshold in bins of `fs / length`.
+    pub bandwidth_bins: f64,
+    /// Samples from silence until the output reaches [`RISE_GOAL`] of its steady-state gain.
+    pub rise: Samples,
+}
+
+/// Run a DFT on a steady tone and return its settled output.
+fn steady_gain(
+    window: WindowFunction,
+    center: Hertz,
+    fs: SampleRate,
+    length: usize,
+    tone: Hertz,
+) -> f32 {
+    let mut dft = Dft::new(center.get(), fs.get(), length, window);
+    let omega = fs.omega(tone);
+    let mut peak: f32 = 0.0;
+    for n in 0..3 * length {
+        let out = dft.process((omega * n as f64).sin() as f32);
+        // The first two lengths fill the window.  The third only holds repeated re-sums.
+        if n >= 2 * length {
+            peak = peak.max(out);
+        }
+    }
+    peak
+}
+
+/// Measure bandwidth and rise of one window length.  `threshold_db` is the amplitude drop that
+/// defines the band edge, such as `-3.0`.
+pub fn measure(
+    window: WindowFunction,
+    center: Hertz,
+    fs: SampleRate,
+    length: usize,
+    threshold_db: f64,
+) -> Option<SweepPoint> {
+    let bin = fs.get() / length as f64;
+    let on_center = steady_gain(window, center, fs, length, center);
+    let threshold = on_center * 10f32.powf(threshold_db.min(0.0) as f32 / 20.0);
+    let gain = |offset_bins: f64| {
+        steady_gain(
+            window,
+            center,
+            fs,
+            length,
+            Hertz(center.get() + offset_bins * bin),
+        )
+    };
+
+    // Walk out of the main lobe, then bisect the crossing.  Walking first keeps the bisection from
+    // landing on a side lobe.
+    let mut inside = 0.0;
+    let mut outside = LOBE_STEP;
+    while gain(outside) >= threshold {
+        inside = outside;
+        outside += LOBE_STEP;
+        if outside > LOBE_LIMIT {
+            return None;
+        }
+    }
+    for _ in 0..BISECT {
+        let mid = 0.5 * (inside + outside);
+        if gain(mid) >= threshold {
+            inside = mid;
+        } else {
+            outside = mid;
+        }
+    }
+    let bandwidth_bins = inside + outside;
+
+    let mut dft = Dft::new(center.get(), fs.get(), length, window);
+    let omega = fs.omega(center);
+    let rise = (0..8 * length)
+        .position(|n| dft.process((omega * n as f64).sin() as f32) >= RISE_GOAL * on_center)?;
+
+    Some(SweepPoint {
+        length,
+        cycles: fs.waves(center, Samples(length)),
+        q: center.get() / (bandwidth_bins * bin),
+        bandwidth_bins,
+        rise: Samples(rise + 1),
+    })
+}
+
+/// Measure every window length in `cycles` of the center frequency.  Lengths whose band edge could
+/// not be found are skipped.
+pub fn sweep(
+    window: WindowFunction,
+    center: Hertz,
+    fs: SampleRate,
+    cycles: &[f64],
+    threshold_db: f64,
+) -> Vec<SweepPoint> {
+    cycles
+        .iter()
+        .filter_map(|&c| measure(window, center, fs, fs.cycles(center, c).get(), threshold_db))
+        .collect()
+}
+
+/// Closed form for one window type, fitted from a sweep.
+#[derive(Clone, Copy, Debug)]
+pub struct WindowFit {
+    pub window: WindowFunction,
+    pub threshold_db: f64,
+    /// Mean bandwidth in bins.  Lengths are chosen from this.
+    pub bandwidth_bins: f64,
+    /// Worst relative deviation of any point from `bandwidth_bins`.  Large values mean the sweep
+    /// included lengths too short for the closed form to hold.
+    pub spread: f64,
+    /// Worst rise time as a fraction of the window length.
+    pub rise_fraction: f64,
+    /// Largest side lobe in dB, measured at the longest swept length.
+    pub side_lobe_db: f64,
+}
+
+/// Chosen length for one bin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pick {
+    pub length: usize,
+    /// Predicted Q at this length.
+    pub q: f64,
+    pub rise: Seconds,
+    /// The rise constraint shortened the window below what the Q required.
+    pub limited: bool,
+}
+
+/// What a bank is trying to achieve.  Every bin is scored against the same goals.
+#[derive(Clone, Copy, Debug)]
+pub struct Goals {
+    /// Rise time that costs as much as missing the Q by a factor of e.
+    pub max_rise: Seconds,
+    /// Side lobes below this are free.  Each 20dB above it costs as much as a full `max_rise`.
+    pub noise_floor_db: f64,
+}
+
+/// Costs of one window choice for one bin.  Lower is better and each term is dimensionless.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Score {
+    /// Rise time over the goal.
+    pub rise: f64,
+    /// Magnitude of the log ratio of achieved to wanted Q.  Too sharp costs as much as too blunt,
+    /// since sharper than the bin spacing leaves gaps between bins.
+    pub bandwidth: f64,
+    /// Decades of amplitude that side lobes sit above the noise floor goal.
+    pub noise: f64,
+}
+
+impl Score {
+    pub fn total(&self) -> f64 {
+        self.rise + self.bandwidth + self.noise
+    }
+}
+
+/// Reduce a sweep to its constants.  `None` without points.
+pub fn fit(window: WindowFunction, threshold_db: f64, points: &[SweepPoint]) -> Option<WindowFit> {
+    if points.is_empty() {
+        return None;
+    }
+    let bandwidth_bins = points.iter().map(|p| p.bandwidth_bins).sum::<f64>() / points.len() as f64;
+    let spread = points
+        .iter()
+        .map(|p| (p.bandwidth_bins / bandwidth_bins - 1.0).abs())
+        .fold(0.0, f64::max);
+    let rise_fraction = points
+        .iter()
+        .map(|p| p.rise.get() as f64 / p.length as f64)
+        .fold(0.0, f64::max);
+    let longest = points.iter().map(|p| p.length).max().unwrap();
+    Some(WindowFit {
+        window,
+        threshold_db,
+        bandwidth_bins,
+        spread,
+        rise_fraction,
+        side_lobe_db: window.side_lobe_db(longest),
+    })
+}
+
+impl WindowFit {
+    /// Shortest window achieving `q` at `center`.
+    pub fn length(&self, center: Hertz, fs: SampleRate, q: f64) -> usize {
+        (q * self.bandwidth_bins * fs.per_cycle(center)).ceil() as usize
+    }
+
+    /// Predicted Q of a window `length` samples long.
+    pub fn q(&self, center: Hertz, fs: SampleRate, length: usize) -> f64 {
+        length as f64 / (self.bandwidth_bins * fs.per_cycle(center))
+    }
+
+    /// Predicted rise time of a window `length` samples long.
+    pub fn rise(&self, fs: SampleRate, length: usize) -> Seconds {
+        Seconds(self.rise_fraction * length as f64 / fs.get())
+    }
+
+    /// The minimal window meeting the bin's Q, or the longest window meeting `max_rise` when both
+    /// cannot be met.
+    pub fn pick(&self, bin: &Bin, fs: SampleRate, max_rise: Seconds) -> Pick {
+        self.pick_q(bin.center, bin.q(), fs, max_rise)
+    }
+
+    /// [`pick`](Self::pick) for any `q`, such as a bin's Q scaled to overlap its neighbors more.
+    pub fn pick_q(&self, center: Hertz, q: f64, fs: SampleRate, max_rise: Seconds) -> Pick {
+        let wanted = self.length(center, fs, q);
+        let allowed = (max_rise.get() * fs.get() / self.rise_fraction).floor() as usize;
+        let length = wanted.min(allowed).max(1);
+        Pick {
+            length,
+            q: self.q(center, fs, length),
+            rise: self.rise(fs, length),
+            limited: length < wanted,
+        }
+    }
+
+    /// Cost of `pick` for a bin that wants `q`.
+    pub fn score(&self, pick: &Pick, q: f64, goals: &Goals) -> Score {
+        Score {
+            rise: pick.rise.get() / goals.max_rise.get(),
+            bandwidth: (pick.q / q).ln().abs(),
+            noise: ((self.side_lobe_db - goals.noise_floor_db) / 20.0).max(0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FS: SampleRate = SampleRate(48_000.0);
+    const CENTER: Hertz = Hertz(1000.0);
+
+    fn dolph() -> WindowFunction {
+        WindowFunction::DolphChebyshev {
+            attenuation_db: 60.0,
+        }
+    }
+
+    #[test]
+    fn test_sizing_scale_invariant() {
+        let points = sweep(dolph(), CENTER, FS, &[8.0, 16.0, 32.0], -3.0);
+        assert_eq!(points.len(), 3);
+        let fit = fit(dolph(), -3.0, &points).unwrap();
+        assert!(fit.spread < 0.02, "{fit:?}");
+        // Wider than a boxcar's 0.89 bins at -3dB, as any tapered window must be.
+        assert!(
+            fit.bandwidth_bins > 0.89 && fit.bandwidth_bins < 3.0,
+            "{fit:?}"
+        );
+        // A tapered window is risen before the tone fills it.
+        assert!(
+            fit.rise_fraction > 0.4 && fit.rise_fraction <= 1.0,
+            "{fit:?}"
+        );
+    }
+
+    #[test]
+    fn test_sizing_length_meets_q() {
+        let points = sweep(dolph(), CENTER, FS, &[8.0, 24.0], -3.0);
+        let fit = fit(dolph(), -3.0, &points).unwrap();
+        let length = fit.length(CENTER, FS, 20.0);
+        let measured = measure(dolph(), CENTER, FS, length, -3.0).unwrap();
+        assert!(measured.q >= 20.0 * 0.99, "{measured:?}");
+        // Minimal, not just sufficient.
+        let shorter = measure(dolph(), CENTER, FS, length * 9 / 10, -3.0).unwrap();
+        assert!(shorter.q < 20.0, "{shorter:?}");
+    }
+
+    #[test]
+    fn test_sizing_pick_limited_by_rise() {
+        let fit = WindowFit {
+            window: dolph(),
+            threshold_db: -3.0,
+            bandwidth_bins: 1.5,
+            spread: 0.0,
+            rise_fraction: 0.75,
+            side_lobe_db: -60.0,
+        };
+        let bin = Bin {
+            min: Hertz(990.0),
+            max: Hertz(1010.0),
+            center: Hertz(1000.0),
+            iso226_gain: 0.0,
+        };
+        let relaxed = fit.pick(&bin, FS, Seconds(1.0));
+        assert!(!relaxed.limited);
+        assert!(relaxed.q >= bin.q());
+        let tight = fit.pick(&bin, FS, Seconds(0.02));
+        assert!(tight.limited);
+        assert!(tight.rise.get() <= 0.02);
+        assert!(tight.q < bin.q());
+    }
+
+    #[test]
+    fn test_sizing_score() {
+        let points = sweep(dolph(), CENTER, FS, &[8.0, 16.0], -3.0);
+        let fit = fit(dolph(), -3.0, &points).unwrap();
+        assert!((fit.side_lobe_db + 60.0).abs() < 1.0, "{fit:?}");
+
+        let goals = Goals {
+            max_rise: Seconds(0.05),
+            noise_floor_db: -60.0,
+        };
+        // Exactly the wanted Q, within the rise goal, with side lobes at the floor.
+        let pick = fit.pick_q(CENTER, 10.0, FS, goals.max_rise);
+        let score = fit.score(&pick, 10.0, &goals);
+        assert!(score.bandwidth < 0.05, "{score:?}");
+        assert!(score.rise > 0.0 && score.rise <= 1.0, "{score:?}");
+        assert!(score.noise < 0.05, "{score:?}");
+
+        // A noisier goal is free, a quieter one is not.
+        let quiet = Goals {
+            noise_floor_db: -100.0,
+            ..goals
+        };
+        assert!((fit.score(&pick, 10.0, &quiet).noise - 2.0).abs() < 0.05);
+
+        // Rise limited picks pay for the missed Q.
+        let limited = fit.pick_q(CENTER, 200.0, FS, goals.max_rise);
+        assert!(limited.limited);
+        assert!(fit.score(&limited, 200.0, &goals).bandwidth > 1.0);
+    }
+}
@@ -7,8 +7,847 @@
 //!
 //! A spectrogram is a moving spectrograph.  This module covers the description of a filter bank so
 //! that it may be implemented in GPU logic.
+//!
+//! ## History & Posters
+//!
+//! The [`History`] keeps every column of bank output for an entire song so that it can be rendered
+//! as one tall, print-resolution image.  Rendering maps magnitudes through a palette and optionally
+//! draws octave and time guides.  Hosts with a device can instead render each tile there, stitch
+//! the pixels read back into a [`Poster`], and add the guides with [`Poster::draw_guides`].  Pixels
+//! have no text, so [`PosterAxes`] only says what each guide is called and where, for hosts that
+//! draw text to label them.
+//!
+//! ## CPU Reference
+//!
+//! [`Spectrogram`] runs a [`BankTable`] on the CPU the slow and obvious way.  Each decimation rate
+//! gets its own [`Resampler`] for anti-aliasing, and each bin evaluates its windowed DFT directly
+//! from the newest decimated samples every `repeat` samples.  Nothing is sliding or shared, so its
+//! output is ground truth for the GPU implementation and for headless tests.
+//!
+//! The [`SpectrogramNode`] runs it in the graph, designed from the `[dsp]` settings for whatever
+//! audio arrives, so that nodes reading rows, such as chroma and segmentation, have a source.  It
+//! runs on the graph's [worker pool](crate::graph::pool) when there is one.
+
+use std::collections::VecDeque;
+use std::f64::consts::TAU;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use num_complex::Complex;
+
+use crate::color::{Colormap, Palette};
+use crate::dsp::bank::{BankBin, BankTable};
+use crate::dsp::resample::Resampler;
+use crate::dsp::staging::{GainStage, WindowNormalization};
+use crate::dsp::units::{Hertz, SampleRate, Samples, Seconds};
+use crate::graph::config::{Config, BINS, MAX_FREQ, MIN_FREQ};
+use crate::graph::{
+    Frame, GraphEvent, Node, Offload, ParamHandle, ParamSpec, Params, PortKind, PortSpec, Priority,
+    WorkerPool,
+};
+use crate::settings::DspSettings;
+use crate::MutateError;
+
+/// Rows per second the [`SpectrogramNode`] computes, whatever the frame rate.
+const NODE_ROWS: f64 = 100.0;
 
 /// Width of a 4k monitor
 pub const RESOLUTION_4K_WIDTH: usize = 3840;
 /// Height of a 4k monitor
 pub const RESOLUTION_4K_HEIGHT: usize = 2160;
+
+/// Rows per tile.  Small enough that one tile of a 4k-wide bank fits comfortably in one offscreen
+/// image, large enough that a song is a few hundred tiles.
+pub const TILE_ROWS: usize = 1024;
+
+/// Where the columns and rows of a poster fall in pitch and time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PosterAxes {
+    /// Lowest edge of the first bin.
+    pub min: Hertz,
+    /// Highest edge of the last bin.
+    pub max: Hertz,
+    pub bins: usize,
+    /// How many rows arrive per second of audio.
+    pub rows_per_second: f64,
+}
+
+impl PosterAxes {
+    /// Column where `freq` lands.  `None` if outside the bank.
+    pub fn column_of(&self, freq: Hertz) -> Option<usize> {
+        if freq < self.min || freq >= self.max {
+            return None;
+        }
+        let pos = (freq / self.min).log2() / (self.max / self.min).log2();
+        Some((pos * self.bins as f64) as usize)
+    }
+
+    /// Every octave of A (27.5Hz, 55Hz, ...) inside the bank and its column, lowest first.
+    fn octaves(&self) -> impl Iterator<Item = (Hertz, usize)> + '_ {
+        std::iter::successors(Some(Hertz(27.5)), |&a| Some(a * 2.0))
+            .take_while(|&a| a < self.max)
+            .filter_map(|a| Some((a, self.column_of(a)?)))
+    }
+
+    /// Rows between ticks `interval` apart.  Zero if the interval is shorter than a row.
+    fn tick_rows(&self, interval: Seconds) -> usize {
+        (interval.get() * self.rows_per_second).round() as usize
+    }
+
+    /// Names of the octave guides, such as `55` or `1.8k` Hz, in row 0 of each guide's column.
+    pub fn frequency_labels(&self) -> Vec<AxisLabel> {
+        self.octaves()
+            .map(|(freq, column)| {
+                let f = freq.get();
+                let (value, unit) = if f >= 1000.0 {
+                    (f / 1000.0, "k")
+                } else {
+                    (f, "")
+                };
+                let value = format!("{value:.1}");
+                AxisLabel {
+                    text: format!("{}{unit}", value.strip_suffix(".0").unwrap_or(&value)),
+                    column,
+                    row: 0,
+                }
+            })
+            .collect()
+    }
+
+    /// Times of the ticks every `interval` down a poster `rows` tall, as `m:ss` in column 0 of each
+    /// tick's row.  The tick at the top is left for the frequencies.
+    pub fn time_labels(&self, rows: usize, interval: Seconds) -> Vec<AxisLabel> {
+        let every = self.tick_rows(interval);
+        if every == 0 {
+            return Vec::new();
+        }
+        (every..rows)
+            .step_by(every)
+            .map(|row| {
+                let s = (row as f64 / self.rows_per_second).round() as u64;
+                AxisLabel {
+                    text: format!("{}:{:02}", s / 60, s % 60),
+                    column: 0,
+                    row,
+                }
+            })
+            .collect()
+    }
+}
+
+/// What a guide is called and the pixel of the poster it starts at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AxisLabel {
+    pub text: String,
+    pub column: usize,
+    pub row: usize,
+}
+
+/// Every column of bank output for an entire song, kept so that it can be rendered as one tall
+/// image, time running down and pitch running across.  Storage is split into fixed height tiles so
+/// that tiles can be rendered independently and stitched.
+pub struct History {
+    axes: PosterAxes,
+    /// Time-major magnitudes in dB, `TILE_ROWS * bins` per full tile.
+    tiles: Vec<Vec<f32>>,
+    rows: usize,
+}
+
+/// A contiguous block of history rows.
+pub struct Tile<'a> {
+    /// Row of the poster where this tile begins.
+    pub first_row: usize,
+    pub rows: usize,
+    /// Time-major magnitudes, `rows * bins`.
+    pub data: &'a [f32],
+}
+
+/// How to turn a [`History`] into pixels.
+pub struct PosterOptions {
+    /// dB mapped to the bottom of the palette.  Anything lower is clamped.
+    pub floor_db: f32,
+    /// dB mapped to the top of the palette.
+    pub ceiling_db: f32,
+    /// Heat by default, a common choice for print because quiet regions stay dark.
+    pub palette: Palette,
+    /// Draw vertical guides at every octave of A (27.5Hz, 55Hz, ...).
+    pub octave_guides: bool,
+    /// Draw a tick on the left edge at this interval.
+    pub time_ticks: Option<Seconds>,
+}
+
+impl Default for PosterOptions {
+    fn default() -> Self {
+        PosterOptions {
+            floor_db: -90.0,
+            ceiling_db: 0.0,
+            palette: Colormap::Heat.into(),
+            octave_guides: true,
+            time_ticks: Some(Seconds(10.0)),
+        }
+    }
+}
+
+/// An RGB image, row-major, top row first.
+pub struct Poster {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl History {
+    /// Create an empty history for a bank of `bins` log-spaced bins spanning `min` to `max`, see
+    /// [`bins`](crate::dsp::bank::bins).
+    pub fn new(min: Hertz, max: Hertz, bins: usize, rows_per_second: f64) -> Self {
+        assert!(bins > 0);
+        assert!(max > min);
+        Self {
+            axes: PosterAxes {
+                min,
+                max,
+                bins,
+                rows_per_second,
+            },
+            tiles: Vec::new(),
+            rows: 0,
+        }
+    }
+
+    /// Append one column of bank output, magnitudes in dB.
+    pub fn push(&mut self, column: &[f32]) {
+        assert_eq!(column.len(), self.axes.bins);
+        if self.rows % TILE_ROWS == 0 {
+            self.tiles
+                .push(Vec::with_capacity(TILE_ROWS * self.axes.bins));
+        }
+        // Always present, pushed above.
+        self.tiles.last_mut().unwrap().extend_from_slice(column);
+        self.rows += 1;
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn bins(&self) -> usize {
+        self.axes.bins
+    }
+
+    pub fn axes(&self) -> &PosterAxes {
+        &self.axes
+    }
+
+    /// Iterate the history in tiles of at most [`TILE_ROWS`].  Only the last tile can be short.
+    pub fn tiles(&self) -> impl Iterator<Item = Tile<'_>> {
+        let bins = self.axes.bins;
+        self.tiles.iter().enumerate().map(move |(i, data)| Tile {
+            first_row: i * TILE_ROWS,
+            rows: data.len() / bins,
+            data,
+        })
+    }
+
+    /// Column of the poster where `freq` lands.  `None` if outside the bank.
+    pub fn column_of(&self, freq: Hertz) -> Option<usize> {
+        self.axes.column_of(freq)
+    }
+
+    /// Render every tile and stitch them into one image.
+    pub fn render(&self, opts: &PosterOptions) -> Poster {
+        let width = self.axes.bins;
+        let height = self.rows;
+        let mut pixels = vec![[0u8; 3]; width * height];
+        let range = (opts.ceiling_db - opts.floor_db).max(f32::EPSILON);
+
+        for tile in self.tiles() {
+            let out = &mut pixels[tile.first_row * width..(tile.first_row + tile.rows) * width];
+            for (px, db) in out.iter_mut().zip(tile.data.iter()) {
+                let x = ((db - opts.floor_db) / range).clamp(0.0, 1.0);
+                *px = opts.palette.rgb8(x);
+            }
+        }
+
+        let mut poster = Poster {
+            width,
+            height,
+            pixels,
+        };
+        poster.draw_guides(&self.axes, opts);
+        poster
+    }
+}
+
+impl Poster {
+    /// Draw the octave guides and time ticks that `opts` ask for over the pixels, one column per bin
+    /// of `axes`.
+    pub fn draw_guides(&mut self, axes: &PosterAxes, opts: &PosterOptions) {
+        let (width, height) = (self.width, self.height);
+        let pixels = &mut self.pixels;
+        let guide = [0x60, 0x60, 0x60];
+        if opts.octave_guides {
+            for (_, col) in axes.octaves().filter(|&(_, col)| col < width) {
+                for row in (0..height).step_by(2) {
+                    pixels[row * width + col] = guide;
+                }
+            }
+        }
+
+        if let Some(interval) = opts.time_ticks {
+            let every = axes.tick_rows(interval);
+            let tick_len = (width / 100).max(4).min(width);
+            if every > 0 {
+                for row in (0..height).step_by(every) {
+                    pixels[row * width..row * width + tick_len].fill([0xff; 3]);
+                }
+            }
+        }
+    }
+
+    /// Write a binary PPM.  Every image tool reads it, and it needs no encoder dependency.
+    pub fn write_ppm(&self, mut w: impl Write) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for px in &self.pixels {
+            w.write_all(px)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs a whole [`BankTable`] over audio and keeps every column of magnitudes.  See the
+/// [module](self) docs.
+// NOTE each rate's resampler delays its bins by a different amount.  The GPU bank will have to
+// agree on that delay before the two are compared sample for sample.
+pub struct Spectrogram {
+    table: BankTable,
+    channels: usize,
+    /// Input samples per column.
+    hop: usize,
+    /// Input samples since the last column.
+    since_column: usize,
+    rates: Vec<Rate>,
+    bins: Vec<BinState>,
+    /// Latest magnitude of each output column.
+    latest: Vec<f32>,
+    /// Whether rows are appended to `magnitudes`.
+    keep_rows: bool,
+    /// Time-major magnitudes, `width` per row.
+    magnitudes: Vec<f32>,
+    /// Reused for resampler output.
+    scratch: Vec<f32>,
+}
+
+/// Anti-aliased input at one decimation, shared by every bin that reads it.
+pub(crate) struct Rate {
+    pub(crate) decimation: u32,
+    resampler: Resampler,
+    /// The newest decimated samples, oldest first.  Zero until filled.
+    pub(crate) recent: VecDeque<f32>,
+    /// Decimated samples seen.  A bin sums whenever this reaches a multiple of its `repeat`.
+    pub(crate) total: u64,
+}
+
+impl Rate {
+    /// One rate per distinct decimation in `table`, each keeping the largest `keep` of its bins,
+    /// and the index of each bin's rate.
+    pub(crate) fn for_table(
+        table: &BankTable,
+        keep: impl Fn(&BankBin) -> usize,
+    ) -> (Vec<Rate>, Vec<usize>) {
+        let fs = table.sample_rate.round() as u32;
+        let mut rates: Vec<Rate> = Vec::new();
+        let mut capacities: Vec<usize> = Vec::new();
+        let indexes = table
+            .bins
+            .iter()
+            .map(|bin| {
+                let decimation = bin.decimation.max(1);
+                let i = match rates.iter().position(|r| r.decimation == decimation) {
+                    Some(i) => i,
+                    None => {
+                        rates.push(Rate {
+                            decimation,
+                            resampler: Resampler::new(fs, fs / decimation),
+                            recent: VecDeque::new(),
+                            total: 0,
+                        });
+                        capacities.push(0);
+                        rates.len() - 1
+                    }
+                };
+                capacities[i] = capacities[i].max(keep(bin));
+                i
+            })
+            .collect();
+        for (rate, capacity) in rates.iter_mut().zip(capacities) {
+            rate.recent = std::iter::repeat_n(0.0, capacity).collect();
+        }
+        (rates, indexes)
+    }
+
+    /// Anti-alias and decimate `chunk`, calling `each` after every decimated sample arrives.
+    pub(crate) fn advance(
+        &mut self,
+        chunk: &[f32],
+        scratch: &mut Vec<f32>,
+        mut each: impl FnMut(&Rate),
+    ) {
+        scratch.clear();
+        self.resampler.process(chunk, scratch);
+        for &x in scratch.iter() {
+            self.recent.pop_front();
+            self.recent.push_back(x);
+            self.total += 1;
+            each(self);
+        }
+    }
+
+    /// Whether a bin that sums every `repeat` samples has summed yet, and if so, how many samples
+    /// ago.
+    pub(crate) fn lag(&self, repeat: u32) -> Option<usize> {
+        let repeat = repeat.max(1) as u64;
+        (self.total >= repeat).then_some((self.total % repeat) as usize)
+    }
+}
+
+struct BinState {
+    /// Index into `rates`.
+    rate: usize,
+    /// Radians per decimated sample.
+    omega: f64,
+}
+
+impl Spectrogram {
+    /// Emit one column every `hop` input samples.  Input is mono until
+    /// [`with_channels`](Self::with_channels).
+    pub fn new(table: BankTable, hop: Samples) -> Self {
+        let (rates, indexes) = Rate::for_table(&table, |bin| bin.window_length as usize);
+        let bins = table
+            .bins
+            .iter()
+            .zip(indexes)
+            .map(|(bin, rate)| {
+                let decimated = table.sample_rate as f64 / rates[rate].decimation as f64;
+                BinState {
+                    rate,
+                    omega: TAU * bin.center as f64 / decimated,
+                }
+            })
+            .collect();
+        let width = table
+            .bins
+            .iter()
+            .map(|b| b.output as usize + 1)
+            .max()
+            .unwrap_or(0);
+        Self {
+            table,
+            channels: 1,
+            hop: hop.get().max(1),
+            since_column: 0,
+            rates,
+            bins,
+            latest: vec![0.0; width],
+            keep_rows: true,
+            magnitudes: Vec::new(),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Frames have `channels` interleaved samples, averaged to mono.
+    pub fn with_channels(mut self, channels: usize) -> Self {
+        self.channels = channels.max(1);
+        self
+    }
+
+    /// Keep no rows, for long running consumers that only read [`latest`](Self::latest).
+    pub fn without_rows(mut self) -> Self {
+        self.keep_rows = false;
+        self
+    }
+
+    /// Columns per row, one per bin output.
+    pub fn width(&self) -> usize {
+        self.latest.len()
+    }
+
+    pub fn rows(&self) -> usize {
+        self.magnitudes.len() / self.width().max(1)
+    }
+
+    /// Time-major amplitudes, [`width`](Self::width) per row.
+    pub fn magnitudes(&self) -> &[f32] {
+        &self.magnitudes
+    }
+
+    /// The most recent amplitude of each column, whether or not a row was kept.
+    pub fn latest(&self) -> &[f32] {
+        &self.latest
+    }
+
+    /// Amplitudes of one row.
+    pub fn row(&self, row: usize) -> &[f32] {
+        let width = self.width();
+        &self.magnitudes[row * width..(row + 1) * width]
+    }
+
+    /// Feed interleaved frames.  A row is appended every `hop` frames, holding each bin's most
+    /// recent sum.
+    pub fn push(&mut self, frames: &[f32]) {
+        let mono: Vec<f32> = frames
+            .chunks_exact(self.channels)
+            .map(|f| f.iter().sum::<f32>() / self.channels as f32)
+            .collect();
+        let mut rest = mono.as_slice();
+        while !rest.is_empty() {
+            let take = (self.hop - self.since_column).min(rest.len());
+            let (chunk, tail) = rest.split_at(take);
+            for i in 0..self.rates.len() {
+                self.advance(i, chunk);
+            }
+            self.since_column += take;
+            if self.since_column == self.hop {
+                self.since_column = 0;
+                if self.keep_rows {
+                    self.magnitudes.extend_from_slice(&self.latest);
+                }
+            }
+            rest = tail;
+        }
+    }
+
+    /// Copy every row into a [`History`] in dB, for rendering.  `min` and `max` are the bank's
+    /// outer edges.
+    pub fn history(&self, min: Hertz, max: Hertz, rows_per_second: f64) -> History {
+        let mut history = History::new(min, max, self.width(), rows_per_second);
+        for row in 0..self.rows() {
+            let db: Vec<f32> = self
+                .row(row)
+                .iter()
+                .map(|m| 20.0 * m.max(1e-9).log10())
+                .collect();
+            history.push(&db);
+        }
+        history
+    }
+
+    /// Run one rate's resampler over `chunk` and update the bins that read it.
+    fn advance(&mut self, rate: usize, chunk: &[f32]) {
+        let Self {
+            table,
+            rates,
+            bins,
+            latest,
+            scratch,
+            ..
+        } = self;
+        rates[rate].advance(chunk, scratch, |r| {
+            for (bin, state) in table.bins.iter().zip(bins.iter()) {
+                if state.rate != rate || r.lag(bin.repeat) != Some(0) {
+                    continue;
+                }
+                let window = table.window(bin);
+                let sum = r
+                    .recent
+                    .range(r.recent.len() - window.len()..)
+                    .zip(window)
+                    .enumerate()
+                    .fold(Complex::new(0.0, 0.0), |acc, (n, (&x, &w))| {
+                        let angle = -state.omega * n as f64;
+                        acc + Complex::from_polar((x * w) as f64, angle)
+                    });
+                latest[bin.output as usize] = sum.norm() as f32 * bin.scale;
+            }
+        });
+    }
+}
+
+/// A [`Spectrogram`] in the graph.  It watches the `min_freq`, `max_freq`, and `bins`
+/// [configuration](crate::graph::config) and is designed again when they or the format of the audio
+/// arriving change.
+///
+/// Each frame's audio is analyzed on the pool at [`Priority::Frame`] while the rest of the frame
+/// runs, so the row emitted is the one for the audio of the frame before.
+pub struct SpectrogramNode {
+    dsp: DspSettings,
+    /// With the rate and channels it was designed for.
+    work: Offload<Option<(u32, usize, Spectrogram)>>,
+}
+
+impl SpectrogramNode {
+    pub fn new(dsp: DspSettings) -> Self {
+        Self {
+            dsp,
+            work: Offload::new(None),
+        }
+    }
+
+    /// Feed interleaved frames of `channels` at `rate`, on `pool` if there is one.
+    pub fn process(
+        &mut self,
+        frames: Arc<[f32]>,
+        channels: usize,
+        rate: u32,
+        pool: Option<&WorkerPool>,
+    ) -> Result<(), MutateError> {
+        let dsp = self.dsp.clone();
+        self.work.submit(pool, Priority::Frame, move |designed| {
+            let spectrogram = match designed {
+                Some((r, c, spectrogram)) if *r == rate && *c == channels => spectrogram,
+                slot => {
+                    let table = dsp.design_table(SampleRate(rate as f64));
+                    let hop = (rate as f64 / NODE_ROWS).round().max(1.0) as usize;
+                    let spectrogram = Spectrogram::new(table, Samples(hop))
+                        .with_channels(channels)
+                        .without_rows();
+                    &mut slot.insert((rate, channels, spectrogram)).2
+                }
+            };
+            spectrogram.push(&frames);
+        })
+    }
+
+    /// The newest amplitude of each column, once audio has arrived.
+    pub fn latest(&mut self) -> Result<Option<Arc<[f32]>>, MutateError> {
+        let designed = self.work.lock()?;
+        Ok(designed.as_ref().map(|(_, _, s)| s.latest().into()))
+    }
+
+    /// Center of each column, once audio has arrived.
+    pub fn centers(&mut self) -> Result<Option<Vec<Hertz>>, MutateError> {
+        let designed = self.work.lock()?;
+        let Some((_, _, spectrogram)) = designed.as_ref() else {
+            return Ok(None);
+        };
+        let mut centers = vec![Hertz(0.0); spectrogram.width()];
+        for bin in &spectrogram.table.bins {
+            centers[bin.output as usize] = Hertz(bin.center as f64);
+        }
+        Ok(Some(centers))
+    }
+}
+
+impl Params for SpectrogramNode {
+    fn param_specs(&self) -> &'static [ParamSpec] {
+        &[]
+    }
+}
+
+impl Node for SpectrogramNode {
+    fn inputs(&self) -> &'static [PortSpec] {
+        &[PortSpec {
+            name: "input",
+            kind: PortKind::Samples,
+        }]
+    }
+
+    fn outputs(&self) -> &'static [PortSpec] {
+        &[PortSpec {
+            name: "row",
+            kind: PortKind::Row,
+        }]
+    }
+
+    fn gain_stage(&self) -> Option<&dyn GainStage> {
+        Some(&WindowNormalization::BANK)
+    }
+
+    fn watches(&self) -> &'static [&'static str] {
+        &[MIN_FREQ, MAX_FREQ, BINS]
+    }
+
+    fn attach(&mut self, _params: ParamHandle) {}
+
+    fn update(&mut self, config: &Config, _changed: &[&str]) -> Result<(), MutateError> {
+        let dsp = DspSettings {
+            min_freq: config.frequency(MIN_FREQ).unwrap_or(self.dsp.min_freq),
+            max_freq: config.frequency(MAX_FREQ).unwrap_or(self.dsp.max_freq),
+            bins: config.count(BINS).unwrap_or(self.dsp.bins),
+        };
+        if dsp != self.dsp {
+            self.dsp = dsp;
+            *self.work.lock()? = None;
+        }
+        Ok(())
+    }
+
+    /// Emits `row` every frame once audio has arrived.
+    fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+        let latest = self.latest()?;
+        if let Some(GraphEvent::Samples {
+            frames,
+            channels,
+            rate,
+        }) = frame.input(0)
+        {
+            let (channels, rate) = (*channels, *rate);
+            self.process(frames.clone(), channels, rate, frame.pool())?;
+        }
+        if let Some(row) = latest {
+            frame.emit(0, GraphEvent::Row(row));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_spectrogram_history_tiles() {
+        let bins = 16;
+        let mut history = History::new(Hertz(24.0), Hertz(12_000.0), bins, 100.0);
+        let rows = TILE_ROWS * 2 + 7;
+        for r in 0..rows {
+            history.push(&vec![-(r as f32 % 90.0); bins]);
+        }
+        let tiles: Vec<_> = history.tiles().collect();
+        assert_eq!(tiles.len(), 3);
+        assert_eq!(tiles[2].first_row, TILE_ROWS * 2);
+        assert_eq!(tiles[2].rows, 7);
+        assert_eq!(tiles.iter().map(|t| t.rows).sum::<usize>(), rows);
+
+        let poster = history.render(&PosterOptions {
+            octave_guides: false,
+            time_ticks: None,
+            ..Default::default()
+        });
+        assert_eq!(poster.pixels.len(), rows * bins);
+        // Row 0 is at the ceiling, row 90 wraps back to it.
+        assert_eq!(poster.pixels[0], [0xff; 3]);
+        assert_eq!(poster.pixels[90 * bins], [0xff; 3]);
+        assert_eq!(
+            poster.pixels[89 * bins],
+            Palette::from(Colormap::Heat).rgb8(1.0 / 90.0)
+        );
+
+        let mut ppm = Vec::new();
+        poster.write_ppm(&mut ppm).unwrap();
+        let header = format!("P6\n{} {}\n255\n", bins, rows);
+        assert!(ppm.starts_with(header.as_bytes()));
+        assert_eq!(ppm.len(), header.len() + rows * bins * 3);
+    }
+
+    #[test]
+    fn test_spectrogram_reference() {
+        use crate::dsp::bank::{bins, BankTable};
+        use crate::dsp::sizing::WindowFit;
+        use crate::dsp::units::SampleRate;
+        use crate::dsp::window::WindowFunction;
+
+        let fit = WindowFit {
+            window: WindowFunction::DolphChebyshev {
+                attenuation_db: 60.0,
+            },
+            threshold_db: -3.0,
+            bandwidth_bins: 1.4,
+            spread: 0.0,
+            rise_fraction: 0.6,
+            side_lobe_db: -60.0,
+        };
+        let fs = SampleRate(48_000.0);
+        let bank = bins(
+            Hertz(crate::dsp::MIN_FREQ_CHEAP_DRIVERS),
+            Hertz(12_000.0),
+            32,
+        );
+        let table = BankTable::design(&bank, &fit, fs, Seconds(0.05));
+        let hop = 480;
+        let mut spectrogram = Spectrogram::new(table.clone(), Samples(hop)).with_channels(2);
+
+        // Half scale, centered on a bin that decimates, in both channels.
+        let target = 12;
+        let center = table.bins[target].center as f64;
+        assert!(table.bins[target].decimation > 1);
+        let frames: Vec<f32> = crate::dsp::SineSweeper::new(center, fs.get())
+            .take(fs.get() as usize)
+            .flat_map(|x| [0.5 * x, 0.5 * x])
+            .collect();
+        for block in frames.chunks(1000) {
+            spectrogram.push(block);
+        }
+
+        assert_eq!(spectrogram.width(), 32);
+        assert_eq!(spectrogram.rows(), fs.get() as usize / hop);
+        let last = spectrogram.row(spectrogram.rows() - 1);
+        assert!((last[target] - 0.5).abs() < 0.05, "{}", last[target]);
+        assert!(last[target + 4] < 0.01);
+        assert!(last[target - 4] < 0.01);
+
+        let history = spectrogram.history(bank[0].min, bank[31].max, 100.0);
+        assert_eq!(history.rows(), spectrogram.rows());
+    }
+
+    #[test]
+    fn test_spectrogram_history_columns() {
+        let history = History::new(Hertz(27.5), Hertz(27.5 * 1024.0), 100, 100.0);
+        assert_eq!(history.column_of(Hertz(27.5)), Some(0));
+        assert_eq!(history.column_of(Hertz(27.5 * 32.0)), Some(50));
+        assert_eq!(history.column_of(Hertz(20.0)), None);
+        assert_eq!(history.column_of(Hertz(27.5 * 1024.0)), None);
+
+        // Pixels stitched elsewhere get the same guides.
+        let mut poster = Poster {
+            width: 100,
+            height: 200,
+            pixels: vec![[0; 3]; 100 * 200],
+        };
+        let opts = PosterOptions {
+            time_ticks: Some(Seconds(1.0)),
+            ..Default::default()
+        };
+        poster.draw_guides(history.axes(), &opts);
+        assert_eq!(poster.pixels[50], [0x60; 3]);
+        assert_eq!(poster.pixels[100 * 100], [0xff; 3]);
+        assert_eq!(poster.pixels[100 * 101 + 50], [0; 3]);
+    }
+
+    #[test]
+    fn test_spectrogram_axis_labels() {
+        let history = History::new(Hertz(27.5), Hertz(27.5 * 1024.0), 100, 100.0);
+        let axes = history.axes();
+        let freqs = axes.frequency_labels();
+        let texts: Vec<_> = freqs.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            ["27.5", "55", "110", "220", "440", "880", "1.8k", "3.5k", "7k", "14.1k"]
+        );
+        assert_eq!((freqs[1].column, freqs[1].row), (10, 0));
+
+        let times = axes.time_labels(6001, Seconds(10.0));
+        assert_eq!(times.len(), 6);
+        assert_eq!((times[0].text.as_str(), times[0].row), ("0:10", 1000));
+        assert_eq!(times[5].text, "1:00");
+        assert!(axes.time_labels(6001, Seconds(0.001)).is_empty());
+    }
+
+    #[test]
+    fn test_spectrogram_node() {
+        let mut node = SpectrogramNode::new(crate::settings::DspSettings {
+            bins: 32,
+            ..Default::default()
+        });
+        assert!(node.latest().unwrap().is_none());
+
+        // A quarter second of 1kHz in stereo, on the pool.
+        let pool = WorkerPool::new(&Default::default()).unwrap();
+        let frames: Arc<[f32]> = crate::dsp::SineSweeper::new(1000.0, 48_000.0)
+            .take(12_000)
+            .flat_map(|x| [0.5 * x, 0.5 * x])
+            .collect();
+        node.process(frames.clone(), 2, 48_000, Some(&pool))
+            .unwrap();
+        let latest = node.latest().unwrap().unwrap();
+        assert_eq!(latest.len(), 32);
+        let loudest = (0..32)
+            .max_by(|&a, &b| latest[a].total_cmp(&latest[b]))
+            .unwrap();
+        let centers = node.centers().unwrap().unwrap();
+        assert!((centers[loudest].get() / 1000.0).log2().abs() < 0.25);
+
+        // Another format is designed for again.
+        node.process(frames[..4800].into(), 1, 44_100, None)
+            .unwrap();
+        assert_eq!(node.latest().unwrap().unwrap().len(), 32);
+    }
+}
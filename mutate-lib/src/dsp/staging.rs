@@ -0,0 +1,327 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Gain Staging
+//!
+//! Between window normalization, ISO 226 correction, and palette mapping, a tone changes units
+//! three times before it becomes brightness.  Each step is simple, but it is easy to lose track of
+//! where absolute level went, and the symptom, a spectrogram that is a little too dark, points at
+//! nothing in particular.
+//!
+//! Every stage therefore declares a contract: the level at which a [`Reference`] tone enters and the
+//! level at which it leaves.  The reference tone is a full-scale 1kHz sine.  A [`GainChain`] is only
+//! built when each stage's output reference matches the next stage's input reference, so a stage
+//! that silently changes its normalization breaks the build of the chain instead of the picture.
+//!
+//! Graph nodes declare their stage with [`Node::gain_stage`](crate::graph::Node::gain_stage), and
+//! [`Graph::connect`](crate::graph::Graph::connect) refuses a `Row` edge between two nodes whose
+//! references disagree the same way, so a preset wired out of order fails to load.
+//!
+//! The tests in this module feed calibrated tones through the real filters and check that the final
+//! brightness lands where the declared references say it will.
+
+// NEXT whitening.  When it exists it sits between perceptual correction and the palette and must
+// declare the level that its long-term average is pulled toward.
+
+use crate::dsp::iso226::iso226_gain;
+use crate::dsp::units::Hertz;
+use crate::dsp::window::WindowFunction;
+use crate::settings::BANK_WINDOW;
+use crate::MutateError;
+
+/// Frequency of the reference tone.  ISO 226 corrections are relative to it.
+pub const REFERENCE_FREQ: Hertz = Hertz(1000.0);
+/// Declared references must agree to within this many dB.
+const TOLERANCE_DB: f64 = 0.01;
+
+/// The unit a stage's level is measured in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scale {
+    /// Decibels relative to a full-scale sine.
+    Dbfs,
+    /// Decibels relative to the perceived loudness of the reference tone.
+    Perceptual,
+    /// Linear `0.0..=1.0` drawing intensity.
+    Brightness,
+}
+
+/// Where the reference tone sits on a stage's input or output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reference {
+    pub scale: Scale,
+    pub level: f64,
+}
+
+impl Reference {
+    pub fn dbfs(level: f64) -> Self {
+        Self {
+            scale: Scale::Dbfs,
+            level,
+        }
+    }
+
+    pub fn perceptual(level: f64) -> Self {
+        Self {
+            scale: Scale::Perceptual,
+            level,
+        }
+    }
+
+    pub fn brightness(level: f64) -> Self {
+        Self {
+            scale: Scale::Brightness,
+            level,
+        }
+    }
+
+    fn agrees(&self, other: &Reference) -> bool {
+        let tolerance = match self.scale {
+            // Under a tenth of a dB for any practical palette range.
+            Scale::Brightness => 1e-3,
+            _ => TOLERANCE_DB,
+        };
+        self.scale == other.scale && (self.level - other.level).abs() <= tolerance
+    }
+}
+
+impl std::fmt::Display for Reference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.scale {
+            Scale::Dbfs => write!(f, "{:+.2} dBFS", self.level),
+            Scale::Perceptual => write!(f, "{:+.2} dB perceptual", self.level),
+            Scale::Brightness => write!(f, "{:.3} brightness", self.level),
+        }
+    }
+}
+
+/// One step of the path from audio to pixels.
+pub trait GainStage {
+    fn name(&self) -> &'static str;
+    /// Level of the reference tone entering this stage.
+    fn input(&self) -> Reference;
+    /// Level of the reference tone leaving this stage.
+    fn output(&self) -> Reference;
+    /// Map a level on the input scale to the output scale for a tone at `freq`.
+    fn apply(&self, level: f64, freq: Hertz) -> f64;
+}
+
+/// Check the handoff of the reference tone from one stage to the next.  Fails with both sides.
+pub fn handoff(from: &dyn GainStage, to: &dyn GainStage) -> Result<(), MutateError> {
+    match from.output().agrees(&to.input()) {
+        true => Ok(()),
+        false => Err(MutateError::GainStaging(format!(
+            "{} outputs the reference at {} but {} expects {}",
+            from.name(),
+            from.output(),
+            to.name(),
+            to.input(),
+        ))),
+    }
+}
+
+/// Windowed filter outputs normalized by the window's weight sum.  A sine of amplitude `A` reads as
+/// `A` once the window is full, so this stage is unity gain.
+pub struct WindowNormalization {
+    pub window: WindowFunction,
+}
+
+impl WindowNormalization {
+    /// The stage of banks designed from [`DspSettings`](crate::settings::DspSettings).
+    pub const BANK: Self = Self {
+        window: BANK_WINDOW,
+    };
+}
+
+impl GainStage for WindowNormalization {
+    fn name(&self) -> &'static str {
+        "window normalization"
+    }
+
+    fn input(&self) -> Reference {
+        Reference::dbfs(0.0)
+    }
+
+    fn output(&self) -> Reference {
+        Reference::dbfs(0.0)
+    }
+
+    fn apply(&self, level: f64, _freq: Hertz) -> f64 {
+        level
+    }
+}
+
+/// Adds the ISO 226 correction so that equally loud tones read equally.  Zero at the reference
+/// frequency by construction.
+pub struct PerceptualCorrection;
+
+impl GainStage for PerceptualCorrection {
+    fn name(&self) -> &'static str {
+        "ISO 226 correction"
+    }
+
+    fn input(&self) -> Reference {
+        Reference::dbfs(0.0)
+    }
+
+    fn output(&self) -> Reference {
+        Reference::perceptual(0.0)
+    }
+
+    fn apply(&self, level: f64, freq: Hertz) -> f64 {
+        level + iso226_gain(freq.get()).unwrap_or(0.0)
+    }
+}
+
+/// Maps a dB range linearly onto brightness.  Levels outside the range clip.
+pub struct PaletteMapping {
+    pub floor_db: f64,
+    pub ceiling_db: f64,
+}
+
+impl GainStage for PaletteMapping {
+    fn name(&self) -> &'static str {
+        "palette mapping"
+    }
+
+    fn input(&self) -> Reference {
+        Reference::perceptual(0.0)
+    }
+
+    fn output(&self) -> Reference {
+        Reference::brightness(self.apply(0.0, REFERENCE_FREQ))
+    }
+
+    fn apply(&self, level: f64, _freq: Hertz) -> f64 {
+        ((level - self.floor_db) / (self.ceiling_db - self.floor_db)).clamp(0.0, 1.0)
+    }
+}
+
+/// A validated sequence of stages.
+pub struct GainChain {
+    stages: Vec<Box<dyn GainStage>>,
+}
+
+impl GainChain {
+    /// Check every handoff.  Fails with both sides of the first disagreement.
+    pub fn new(stages: Vec<Box<dyn GainStage>>) -> Result<Self, MutateError> {
+        for pair in stages.windows(2) {
+            handoff(pair[0].as_ref(), pair[1].as_ref())?;
+        }
+        Ok(Self { stages })
+    }
+
+    /// Level of the reference tone entering the chain.
+    pub fn input(&self) -> Option<Reference> {
+        self.stages.first().map(|s| s.input())
+    }
+
+    /// Level of the reference tone leaving the chain.
+    pub fn output(&self) -> Option<Reference> {
+        self.stages.last().map(|s| s.output())
+    }
+
+    /// Predicted output for a tone of `level` on the input scale at `freq`.
+    pub fn apply(&self, level: f64, freq: Hertz) -> f64 {
+        self.stages.iter().fold(level, |l, s| s.apply(l, freq))
+    }
+
+    /// One line per stage showing where the reference tone enters and leaves.
+    pub fn budget(&self) -> String {
+        self.stages
+            .iter()
+            .map(|s| format!("{:<24} {} -> {}\n", s.name(), s.input(), s.output()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dsp::dft::Dft;
+    use crate::dsp::peak::amplitude_db;
+    use crate::dsp::Filter;
+
+    const FS: f64 = 48_000.0;
+    /// Brightness tolerance for the end-to-end check.  About a third of a dB with a 60dB palette.
+    const BRIGHTNESS_TOLERANCE: f64 = 0.005;
+
+    fn chain(window: WindowFunction) -> GainChain {
+        GainChain::new(vec![
+            Box::new(WindowNormalization { window }),
+            Box::new(PerceptualCorrection),
+            Box::new(PaletteMapping {
+                floor_db: -60.0,
+                ceiling_db: 0.0,
+            }),
+        ])
+        .unwrap()
+    }
+
+    /// Run a tone through a real DFT and measure the settled output in dBFS.
+    fn measure(freq: f64, dbfs: f64, window: WindowFunction) -> f64 {
+        let amplitude = 10f64.powf(dbfs / 20.0);
+        let length = (8.0 * FS / freq).ceil() as usize;
+        let mut dft = Dft::new(freq, FS, length, window);
+        let omega = std::f64::consts::TAU * freq / FS;
+        let mut out = 0.0;
+        for n in 0..length * 2 {
+            out = dft.process((amplitude * (omega * n as f64).sin()) as f32);
+        }
+        amplitude_db(out) as f64
+    }
+
+    #[test]
+    fn test_staging_end_to_end() {
+        let window = WindowFunction::DolphChebyshev {
+            attenuation_db: 80.0,
+        };
+        let chain = chain(window);
+        print!("{}", chain.budget());
+        assert_eq!(chain.output(), Some(Reference::brightness(1.0)));
+
+        for (freq, dbfs) in [
+            (1000.0, 0.0),
+            (1000.0, -12.0),
+            (100.0, -6.0),
+            (4000.0, -20.0),
+        ] {
+            let freq = Hertz(freq);
+            let measured = measure(freq.get(), dbfs, window);
+            let brightness = chain.stages[1..]
+                .iter()
+                .fold(measured, |l, s| s.apply(l, freq));
+            let expected = chain.apply(dbfs, freq);
+            assert!(
+                (brightness - expected).abs() < BRIGHTNESS_TOLERANCE,
+                "{freq} Hz at {dbfs} dBFS: brightness {brightness}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_staging_mismatch() {
+        struct Boosted;
+        impl GainStage for Boosted {
+            fn name(&self) -> &'static str {
+                "boosted"
+            }
+            fn input(&self) -> Reference {
+                Reference::dbfs(0.0)
+            }
+            fn output(&self) -> Reference {
+                Reference::dbfs(6.0)
+            }
+            fn apply(&self, level: f64, _freq: Hertz) -> f64 {
+                level + 6.0
+            }
+        }
+
+        let err = GainChain::new(vec![Box::new(Boosted), Box::new(PerceptualCorrection)]);
+        assert!(err.is_err());
+        let err = GainChain::new(vec![
+            Box::new(PerceptualCorrection),
+            Box::new(PerceptualCorrection),
+        ]);
+        assert!(err.is_err());
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Window Factors
+//!
+//! Every window trades gain and bandwidth against side lobes.  To compare filters across window
+//! choices, results are normalized by three properties of the weights:
+//!
+//! - **Coherent gain** is the mean weight, the amplitude a pure tone at the bin center comes out
+//!   with relative to a `BoxCar`.
+//! - **ENBW**, equivalent noise bandwidth, is the width in bins of the `BoxCar` that passes as much
+//!   white noise.  The `BoxCar` is exactly one bin.
+//! - **COLA overlap** is the least overlap at which shifted copies of the window sum flat to within
+//!   [`COLA_RIPPLE`].  Less overlap leaves some samples under-measured.
+//!
+//! Dolph-Chebyshev windows need an O(N²) IDFT, so the build script tabulates factors for common
+//! lengths and attenuations.  The build script includes this file directly, so it may only depend
+//! on [`super::shapes`] and `toml`.
+
+use super::shapes;
+
+/// Tabulated window lengths.  Factors converge quickly, so longer windows use the longest entry.
+pub const LENGTHS: &[usize] = &[16, 32, 64, 128, 256, 512, 1024];
+/// Tabulated Dolph-Chebyshev attenuations in dB.
+pub const ATTENUATIONS: &[f64] = &[
+    20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0, 100.0, 110.0, 120.0,
+];
+/// Overlaps tried when measuring COLA, least first.
+const OVERLAPS: &[f64] = &[0.5, 0.625, 2.0 / 3.0, 0.75, 0.8, 0.875, 0.9];
+/// Peak to peak ripple, relative to the mean, that counts as a constant overlap-add.
+pub const COLA_RIPPLE: f64 = 0.01;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowFactors {
+    pub coherent_gain: f64,
+    /// In bins.
+    pub enbw: f64,
+    /// Fraction of the window shared with the next application.
+    pub cola_overlap: f64,
+}
+
+impl WindowFactors {
+    /// Measure factors directly from weights.
+    pub fn measure(weights: &[f64]) -> Self {
+        let n = weights.len() as f64;
+        let sum: f64 = weights.iter().sum();
+        let sum_sq: f64 = weights.iter().map(|w| w * w).sum();
+        Self {
+            coherent_gain: sum / n,
+            enbw: n * sum_sq / (sum * sum),
+            cola_overlap: cola_overlap(weights),
+        }
+    }
+
+    /// Linear blend toward `other`.
+    pub fn lerp(&self, other: &Self, t: f64) -> Self {
+        let mix = |a: f64, b: f64| a + (b - a) * t;
+        Self {
+            coherent_gain: mix(self.coherent_gain, other.coherent_gain),
+            enbw: mix(self.enbw, other.enbw),
+            // Never interpolate toward less overlap than either neighbor needs.
+            cola_overlap: self.cola_overlap.max(other.cola_overlap),
+        }
+    }
+}
+
+/// The least overlap in [`OVERLAPS`] that sums flat, or the most overlap if none does.
+fn cola_overlap(weights: &[f64]) -> f64 {
+    let n = weights.len();
+    for &overlap in OVERLAPS {
+        let hop = ((n as f64 * (1.0 - overlap)).round() as usize).max(1);
+        // Every sample is covered by the windows starting at `i`, `i + hop`, ... behind it.
+        let sums: Vec<f64> = (0..hop)
+            .map(|i| weights.iter().skip(i).step_by(hop).sum())
+            .collect();
+        let max = sums.iter().copied().fold(f64::MIN, f64::max);
+        let min = sums.iter().copied().fold(f64::MAX, f64::min);
+        let mean = sums.iter().sum::<f64>() / hop as f64;
+        if (max - min) / mean <= COLA_RIPPLE {
+            return overlap;
+        }
+    }
+    OVERLAPS[OVERLAPS.len() - 1]
+}
+
+/// One row of the table.  `param` is the attenuation for Dolph-Chebyshev and zero otherwise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FactorRow {
+    pub family: String,
+    pub param: f64,
+    pub length: usize,
+    pub factors: WindowFactors,
+}
+
+/// Tabulated factors, parsed from the `[[window]]` rows the build script writes.
+#[derive(Clone, Debug, Default)]
+pub struct FactorTable {
+    rows: Vec<FactorRow>,
+}
+
+impl FactorTable {
+    /// Measure every tabulated window.
+    pub fn generate() -> Self {
+        let closed: [(&str, fn(f64) -> f64); 4] = [
+            ("boxcar", shapes::boxcar),
+            ("welch", shapes::welch),
+            ("bartlett", shapes::bartlett),
+            ("hamming", shapes::hamming),
+        ];
+        let mut rows = Vec::new();
+        for &length in LENGTHS {
+            for (family, shape) in closed {
+                let weights = shapes::bin_weights(&shape, length);
+                rows.push(FactorRow {
+                    family: family.to_owned(),
+                    param: 0.0,
+                    length,
+                    factors: WindowFactors::measure(&weights),
+                });
+            }
+            for &attenuation_db in ATTENUATIONS {
+                let weights = shapes::dolph_chebyshev_window(length, attenuation_db);
+                rows.push(FactorRow {
+                    family: "dolph_chebyshev".to_owned(),
+                    param: attenuation_db,
+                    length,
+                    factors: WindowFactors::measure(&weights),
+                });
+            }
+        }
+        Self { rows }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let table: toml::Table = text.parse().map_err(|e| format!("{e}"))?;
+        let rows = table
+            .get("window")
+            .and_then(|v| v.as_array())
+            .ok_or("missing `[[window]]` rows")?;
+        let rows = rows
+            .iter()
+            .map(|row| {
+                let float = |key: &str| {
+                    row.get(key)
+                        .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+                        .ok_or_else(|| format!("`{key}` must be a number"))
+                };
+                Ok(FactorRow {
+                    family: row
+                        .get("family")
+                        .and_then(|v| v.as_str())
+                        .ok_or("`family` must be a string")?
+                        .to_owned(),
+                    param: float("param")?,
+                    length: float("length")? as usize,
+                    factors: WindowFactors {
+                        coherent_gain: float("coherent_gain")?,
+                        enbw: float("enbw")?,
+                        cola_overlap: float("cola_overlap")?,
+                    },
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { rows })
+    }
+
+    pub fn to_toml(&self) -> String {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut t = toml::Table::new();
+                t.insert("family".into(), row.family.clone().into());
+                t.insert("param".into(), row.param.into());
+                t.insert("length".into(), (row.length as i64).into());
+                t.insert("coherent_gain".into(), row.factors.coherent_gain.into());
+                t.insert("enbw".into(), row.factors.enbw.into());
+                t.insert("cola_overlap".into(), row.factors.cola_overlap.into());
+                toml::Value::Table(t)
+            })
+            .collect::<Vec<_>>();
+        let mut table = toml::Table::new();
+        table.insert("window".into(), rows.into());
+        format!("# Generated by the mutate-lib build script.\n\n{table}")
+    }
+
+    /// Factors for `family` at `param`, interpolated over log length.  `None` when `param` is not
+    /// tabulated or `length` is shorter than the table.
+    pub fn lookup(&self, family: &str, param: f64, length: usize) -> Option<WindowFactors> {
+        let row = |length: usize| {
+            self.rows
+                .iter()
+                .find(|r| r.family == family && r.param == param && r.length == length)
+                .map(|r| r.factors)
+        };
+        let above = LENGTHS.iter().position(|&l| l >= length);
+        match above {
+            Some(0) if LENGTHS[0] == length => row(length),
+            Some(0) => None,
+            Some(i) => {
+                let (lo, hi) = (LENGTHS[i - 1], LENGTHS[i]);
+                let t = (length as f64 / lo as f64).log2() / (hi as f64 / lo as f64).log2();
+                Some(row(lo)?.lerp(&row(hi)?, t))
+            }
+            None => row(LENGTHS[LENGTHS.len() - 1]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_window_factors_known() {
+        let boxcar = WindowFactors::measure(&[1.0; 64]);
+        assert_eq!(boxcar.coherent_gain, 1.0);
+        assert_eq!(boxcar.enbw, 1.0);
+        assert_eq!(boxcar.cola_overlap, 0.5);
+
+        // The textbook Hamming is 0.54 and 1.36 bins.
+        let hamming = WindowFactors::measure(&shapes::bin_weights(&shapes::hamming, 1024));
+        assert!((hamming.coherent_gain - 0.54).abs() < 0.01);
+        assert!((hamming.enbw - 1.36).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_window_factors_table() {
+        let table = FactorTable::generate();
+        let parsed = FactorTable::parse(&table.to_toml()).unwrap();
+        assert_eq!(parsed.rows, table.rows);
+
+        assert!(table.lookup("hamming", 0.0, 8).is_none());
+        assert!(table.lookup("dolph_chebyshev", 45.0, 64).is_none());
+        let exact = table.lookup("dolph_chebyshev", 60.0, 64).unwrap();
+        let measured = WindowFactors::measure(&shapes::dolph_chebyshev_window(64, 60.0));
+        assert_eq!(exact, measured);
+
+        // Between tabulated lengths the factors stay close to measured.
+        let between = table.lookup("dolph_chebyshev", 60.0, 90).unwrap();
+        let measured = WindowFactors::measure(&shapes::dolph_chebyshev_window(90, 60.0));
+        assert!((between.enbw - measured.enbw).abs() < 0.02);
+        assert!((between.coherent_gain - measured.coherent_gain).abs() < 0.02);
+    }
+}
@@ -0,0 +1,390 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Window Functions
+//!
+//! This module contains window functions used in many filters, especially DFTs.  Gain, bandwidth,
+//! and overlap of each window are tabulated at build time.  See [`factors`].
+
+pub mod factors;
+mod shapes;
+
+use std::f64::consts::PI as PI64;
+use std::sync::OnceLock;
+
+use num_complex::Complex;
+
+use crate::dsp::fft::Fft;
+use crate::tree::TreeSum;
+
+use factors::FactorTable;
+pub use factors::WindowFactors;
+use shapes::{bartlett, boxcar, hamming, kaiser, welch};
+pub use shapes::{bin_weights, dolph_chebyshev_window, kaiser_beta, ultraspherical_window};
+
+/// Written by the build script.
+const FACTOR_TABLE: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/assets/tables/windows.toml"
+));
+
+fn factor_table() -> &'static FactorTable {
+    static TABLE: OnceLock<FactorTable> = OnceLock::new();
+    TABLE.get_or_init(|| FactorTable::parse(FACTOR_TABLE).expect("build script wrote the table"))
+}
+
+/// ## Window Choice
+///
+/// There are many deceptive ideas an engineer may have about window selection.  The Dolph-Chebyshev
+/// or its generalizations are the right answer, but let's explore why.
+///
+/// When we look at graphs of windows, we will note that windows like the Hamming and Triangle will
+/// not reach peak output until the first samples of a pure tone get to the middle of the window and
+/// begin to reach the higher weights.  We may longingly gaze at the high initial weights of the
+/// `Boxcar`.  A high-Q DFT already has slow attack.  Why make it slower?
+///
+/// The answer is that waiting until the middle is actually faster than the `Boxcar`, which must
+/// wait until samples begin reaching the very tail of the window due to its equal weighting
+/// everywhere.  A continuous pure tone will only saturate a fraction of a `Boxcar`'s weights while
+/// it will be making a lump-in-the-middle filter hum strongly.  Those narrow-lump filters will also
+/// peak higher for tones shorter than the window length while the `Boxcar` will peak low and then
+/// continue smearing the blip for the entire length of the window!
+///
+/// Worse yet, while we see more weight given to early samples in the `Boxcar`, we don't know if
+/// they are signal or noise yet.  The response is only faster because we are willing to admit
+/// terrible noise from the huge side lobes.  Not only are we blurring responses, but we don't even
+/// know if they are the right pitch!  We might be smearing pure noise.
+///
+/// The engineer desperate to register short blips is tempted to lament the length and slow rise in
+/// weights of many high-performance windows, but the blip will eventually enter the fat part of the
+/// window, twice as fast as the `Boxcar`, and we will know that it is the target pitch, not some
+/// unwanted side lobe  Because of the very high quality of the main lobes, we can listen to much
+/// weaker tones.  A small light on a dark night appears twice as bright.  They were right about
+/// choosing Shakuras.
+#[derive(Debug, Clone, Copy)]
+pub enum WindowFunction {
+    /// Also known as the Rectangle.  Very bad, not good at all, -13.3dB first side-lobe.  When you
+    /// account for needing to fill the entire window, response time is about double and in many
+    /// cases the window never fills.  Smears everything.  Avoid.
+    BoxCar,
+    /// In theory, the high weights means you see a response from the first samples sooner, but just
+    /// like the box car, the window takes forever to fill, and we don't know if the samples are
+    /// noise because the first side lobes are still -21.3dB.
+    Welch,
+    /// Aka the Triangle.  When better meets uncomplicated.  -26.5dB PSLL.
+    Bartlett,
+    /// The hamming cancels its first side lobe to a modestly usable -42.7dB.  Possibly less precise
+    /// in time resolution than some choices.
+    Hamming,
+    /// Optimal window for tuning noise floors and bandwidth tradeoffs in every way.  Combined with
+    /// window length, this window offers the engineer maximum control and performance at every goal
+    /// except possibly handling extremely high noise at extremely distant points, which can be
+    /// readily suppressed with other simple zero-delay filters.
+    DolphChebyshev { attenuation_db: f64 },
+    /// Another tunable window with slightly better noise decay at extreme pitch differences.  This
+    /// can save an un-filtered DFT from registering a sudden super-loud pitch at an unexpected,
+    /// distant frequency.  Higher `beta` lowers side lobes and widens the main lobe.  Use
+    /// [`WindowFunction::kaiser`] to choose `beta` by side lobe level.
+    Kaiser { beta: f64 },
+    /// Yet another tunable window.  `attenuation_db` places the largest side lobe like the
+    /// Dolph-Chebyshev while `mu`, above -0.5, tilts the rest.  Negative `mu` lets side lobes grow
+    /// as we get farther from the main lobe, buying a narrower main lobe.  Paired with another
+    /// filter to suppress the side lobe growth, this may offer a more precise main lobe.  Positive
+    /// `mu` decays like a Kaiser.  Parameterized to 0, it *is* the Dolph-Chebyshev window.
+    Ultraspherical { mu: f64, attenuation_db: f64 },
+    /// A literal window for pre-calculated weights or just any obtained from another method.  This
+    /// variant exists to enable using hardcoded windows in the same interface.
+    Literal { weights: &'static [f32] },
+}
+
+impl WindowFunction {
+    /// * `size` - Length of the window.
+    pub fn make_window(&self, size: usize) -> Vec<f64> {
+        match self {
+            Self::BoxCar => bin_weights(&boxcar, size),
+            Self::Bartlett => bin_weights(&bartlett, size),
+            Self::Welch => bin_weights(&welch, size),
+            Self::Hamming => bin_weights(&hamming, size),
+            Self::DolphChebyshev { attenuation_db } => {
+                dolph_chebyshev_window(size, *attenuation_db)
+            }
+            Self::Kaiser { beta } => bin_weights(&|x| kaiser(x, *beta), size),
+            Self::Ultraspherical { mu, attenuation_db } => {
+                ultraspherical_window(size, *mu, *attenuation_db)
+            }
+            Self::Literal { weights } => weights.iter().map(|w| *w as f64).collect(),
+        }
+    }
+
+    /// A Kaiser window with its largest side lobe `attenuation_db` down, for comparing against a
+    /// Dolph-Chebyshev of the same attenuation.
+    pub fn kaiser(attenuation_db: f64) -> Self {
+        Self::Kaiser {
+            beta: kaiser_beta(attenuation_db),
+        }
+    }
+
+    /// Create a windowed list of sinc weights for constructing an FIR lowpass filter.
+    /// * `size` - Length of the window.  Must be odd.
+    /// * `cutoff` - Fraction of Nyquist limit, in (0.0, 0.5].
+    pub fn make_windowed_sinc(&self, size: usize, cutoff: f64) -> Vec<f64> {
+        let window = self.make_window(size);
+        let sinc = sinc_weights(cutoff, size);
+        let weights: Vec<f64> = window.iter().zip(sinc.iter()).map(|(w, s)| w * s).collect();
+        let sum = weights.iter().copied().tree_sum();
+        let norm = 1.0 / sum;
+        weights.iter().map(|w| w * norm).collect()
+    }
+
+    /// Windows are calculated in f64 for accuracy, but on the GPU, we use f32, so we have to use
+    /// the truncated window to reproduce outcomes *in situ*.
+    pub fn make_window_32(&self, size: usize) -> Vec<f32> {
+        self.make_window(size)
+            .into_iter()
+            .map(|w| w as f32)
+            .collect()
+    }
+
+    /// How often should this window be applied to yield a fresh result to satisfy COLA?  The  lower
+    /// the repeat, the more often we will apply the window.  the more windows overlap, the more
+    /// equally we measure any data.
+    // Much was learned from this PDF:
+    // https://holometer.fnal.gov/GH_FFT.pdf
+    pub fn repeat(&self, length: usize) -> u32 {
+        match self {
+            Self::BoxCar => (length as f64 / 2.0).ceil() as u32,
+            Self::Bartlett => (length as f64 / 2.0).ceil() as u32,
+            Self::Welch => (length as f64 * 0.293).ceil() as u32,
+            Self::Hamming => (length as f64 / 2.0).ceil() as u32,
+            // MAYBE COLA values for Dolph Chebyshev need some experimental tuning.  Higher
+            // attenuation was said to demand more overlap.  `factors` measures it, but the banks
+            // were tuned with this repeat.
+            Self::DolphChebyshev { attenuation_db } => (length as f64 / 4.0).ceil() as u32,
+            // No bank was tuned with these yet, so trust the measurement.
+            Self::Kaiser { .. } | Self::Ultraspherical { .. } => {
+                let overlap = self.factors(length).cola_overlap;
+                ((length as f64 * (1.0 - overlap)).ceil() as u32).max(1)
+            }
+            Self::Literal { weights } => (length as f64 / 4.0).ceil() as u32,
+        }
+    }
+
+    /// Gain, bandwidth, and overlap of the window at `size`.  Read from the build time table when
+    /// it covers the window and measured from the weights otherwise.
+    pub fn factors(&self, size: usize) -> WindowFactors {
+        let tabulated = match self {
+            Self::BoxCar => factor_table().lookup("boxcar", 0.0, size),
+            Self::Welch => factor_table().lookup("welch", 0.0, size),
+            Self::Bartlett => factor_table().lookup("bartlett", 0.0, size),
+            Self::Hamming => factor_table().lookup("hamming", 0.0, size),
+            Self::DolphChebyshev { attenuation_db } => {
+                factor_table().lookup("dolph_chebyshev", *attenuation_db, size)
+            }
+            // NOTE Kaiser windows are cheap to measure.  Ultraspherical windows cost an IDFT like
+            // the Dolph-Chebyshev, but two parameters would square the table.
+            Self::Kaiser { .. } | Self::Ultraspherical { .. } | Self::Literal { .. } => None,
+        };
+        tabulated.unwrap_or_else(|| WindowFactors::measure(&self.make_window(size)))
+    }
+
+    /// Largest side lobe of the window at `size`.  See [`peak_side_lobe_db`].
+    pub fn side_lobe_db(&self, size: usize) -> f64 {
+        peak_side_lobe_db(&self.make_window(size))
+    }
+
+    /// Bandwidth normalization factor, restoring the noise power a window passes to what one bin
+    /// of a `BoxCar` passes.  The reciprocal of ENBW.  Applied by
+    /// [`Normalization::Density`](crate::dsp::dft::Normalization::Density).
+    // NOTE not a correction for Q.  A -3dB bandwidth can stay put while ENBW grows, as it does
+    // with the pedestals of long, low attenuation Dolph-Chebyshev windows.
+    pub fn bandwidth_norm_factor(&self, size: usize) -> f32 {
+        (1.0 / self.factors(size).enbw) as f32
+    }
+
+    /// Gain normalization factor, restoring a bin-centered tone to the amplitude a `BoxCar` reads.
+    /// The reciprocal of coherent gain.
+    pub fn amplitude_norm_factor(&self, size: usize) -> f32 {
+        (1.0 / self.factors(size).coherent_gain) as f32
+    }
+}
+
+/// Largest side lobe of `weights` in dB relative to the main lobe, from a zero padded FFT.  This is
+/// how far below a tone at the center a loud tone anywhere else can hide.
+pub fn peak_side_lobe_db(weights: &[f64]) -> f64 {
+    let n = (16 * weights.len()).next_power_of_two().max(1 << 12);
+    let mut response: Vec<Complex<f64>> = weights.iter().map(|&w| Complex::new(w, 0.0)).collect();
+    response.resize(n, Complex::new(0.0, 0.0));
+    Fft::new(n).forward(&mut response);
+    let response: Vec<f64> = response[..=n / 2].iter().map(|x| x.norm()).collect();
+    // Past the first null, everything is side lobe.
+    let Some(null) = response.windows(2).position(|w| w[1] > w[0]) else {
+        return f64::NEG_INFINITY;
+    };
+    let side = response[null..].iter().copied().fold(0.0, f64::max);
+    20.0 * (side / response[0]).log10()
+}
+
+impl std::fmt::Display for WindowFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::BoxCar => "BoxCar",
+            Self::Welch => "Welch",
+            Self::Bartlett => "Bartlett",
+            Self::Hamming => "Hamming",
+            Self::DolphChebyshev { attenuation_db } => "Dolph-Chebyshev",
+            Self::Kaiser { .. } => "Kaiser",
+            Self::Ultraspherical { .. } => "Ultraspherical",
+            Self::Literal { weights } => "Literal",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Default for WindowFunction {
+    fn default() -> Self {
+        Self::DolphChebyshev {
+            attenuation_db: 40.0,
+        }
+    }
+}
+
+/// Normalized sinc function
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let px = PI64 * x;
+        px.sin() / px
+    }
+}
+
+/// Generates a symmetric array of sinc weights.
+///
+/// # Arguments
+/// * `cutoff`     - Normalized cutoff frequency in (0.0, 0.5]. 0.5 = Nyquist frequency.
+/// * `num_taps`   - Filter taps (number of inputs that inform each output sample).
+///
+/// # Returns
+/// A `Vec<f64>` of length `num_taps` containing the raw sinc weights, unnormalized.
+///
+/// # Panics
+/// Panics if `num_taps` is zero, even or if `cutoff` is not in (0.0, 0.5].
+fn sinc_weights(cutoff: f64, num_taps: usize) -> Vec<f64> {
+    assert!(num_taps > 0, "num_taps must be greater than zero");
+    assert!(num_taps % 2 == 1, "num_taps must be odd");
+    assert!(
+        cutoff > 0.0 && cutoff <= 0.5,
+        "cutoff must be in (0.0, 0.5]"
+    );
+
+    let half = (num_taps as f64 - 1.0) / 2.0;
+    (0..num_taps)
+        .map(|i| {
+            let n = i as f64 - half;
+            // Scale argument so the first null of sinc(x) lands at the cutoff
+            sinc(2.0 * cutoff * n)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dsp::Filter;
+
+    use super::*;
+
+    // We're just demonstrating that the less trivial bins are sane.  These have been eyeballed.
+    // NEXT anyone want to clean these up?  Valid windows must get pretty close to 1.0.  The
+    // printing and everything could be checked via macro, but make printing vs not printing easy to
+    // toggle.  The workbench is the best place for real testing.
+
+    #[test]
+    fn test_window_function_hamming() {
+        let weights = bin_weights(&(hamming as fn(f64) -> f64), 25);
+        // weights.iter().enumerate().for_each(|(i, b)| {
+        //     println!("Hamming: {:3}: {:0.8}", i, b);
+        // });
+        assert!(weights.iter().all(|b| *b > 0.0 && *b <= 1.0));
+    }
+
+    #[test]
+    fn test_window_function_welch() {
+        let weights = bin_weights(&(welch as fn(f64) -> f64), 25);
+        // weights.iter().enumerate().for_each(|(i, b)| {
+        //   println!("Welch: {:3}: {:0.8}", i, b);
+        // });
+        assert!(weights.iter().all(|b| *b > 0.0 && *b <= 1.0));
+    }
+
+    #[test]
+    fn test_window_function_dolph_window() {
+        let weights = dolph_chebyshev_window(10, 60.0);
+        // println!("\nn = 10");
+        // weights.iter().enumerate().for_each(|(i, b)| {
+        //     println!("Chebyshev: {:5}: {:0.8}", i, b);
+        // });
+        assert!(weights.iter().all(|b| *b > 0.0 && *b <= 1.0));
+
+        let weights = dolph_chebyshev_window(11, 60.0);
+        // println!("\nn = 11");
+        // weights.iter().enumerate().for_each(|(i, b)| {
+        //     println!("Chebyshev: {:5}: {:0.8}", i, b);
+        // });
+        assert!(weights.iter().all(|b| *b > 0.0 && *b <= 1.0));
+
+        // Long window with deep attenuation calculation for more pitch-precise recognition of a
+        // very low frequency.
+        let weights = dolph_chebyshev_window(200, 80.0);
+        // println!("\nn = 200");
+        // weights.iter().enumerate().for_each(|(i, b)| {
+        //     println!("Chebyshev: {:5}: {:0.19}", i, b);
+        // });
+        assert!(weights.iter().all(|b| *b > 0.0 && *b <= 1.0));
+
+        // Look at the neat little steps!  Exactly like Matlab!
+        let weights = dolph_chebyshev_window(31, 40.0);
+        // println!("\nn = 31, attenuation = 40.0");
+        // weights.iter().enumerate().for_each(|(i, b)| {
+        //     println!("Chebyshev: {:5}: {:0.19}", i, b);
+        // });
+        assert!(weights.iter().all(|b| *b > 0.0 && *b <= 1.0));
+
+        // Weird settings obtain weird windows because the physical reality we are judging is weird.
+        let weights = dolph_chebyshev_window(12, 15.0);
+        // println!("\nn = 31, attenuation = 5.0");
+        // weights.iter().enumerate().for_each(|(i, b)| {
+        //     println!("Chebyshev: {:5}: {:0.19}", i, b);
+        // });
+        assert!(weights.iter().all(|b| *b > 0.0 && *b <= 1.0));
+    }
+
+    #[test]
+    fn test_window_function_kaiser() {
+        for attenuation_db in [30.0, 60.0, 90.0] {
+            let weights = WindowFunction::kaiser(attenuation_db).make_window(64);
+            assert!(weights.iter().all(|b| *b > 0.0 && *b <= 1.0));
+            let psll = peak_side_lobe_db(&weights);
+            assert!(
+                (psll + attenuation_db).abs() < 2.0,
+                "{attenuation_db}: {psll}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_window_function_ultraspherical() {
+        // The Dolph-Chebyshev is the limit at mu = 0.
+        let near = ultraspherical_window(31, 1e-6, 40.0);
+        let dolph = dolph_chebyshev_window(31, 40.0);
+        assert!(near.iter().zip(&dolph).all(|(a, b)| (a - b).abs() < 1e-4));
+
+        for mu in [-0.25, 0.5, 1.0] {
+            for n in [32, 33] {
+                let weights = ultraspherical_window(n, mu, 60.0);
+                assert!(weights.iter().all(|b| *b > 0.0 && *b <= 1.0));
+                let psll = peak_side_lobe_db(&weights);
+                assert!((psll + 60.0).abs() < 0.5, "mu {mu}, n {n}: {psll}");
+            }
+        }
+    }
+}
@@ -0,0 +1,389 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Window Shapes
+//!
+//! The weights themselves.  Nothing here depends on the rest of the crate so that the build script
+//! can include this file and tabulate window factors ahead of time.  See [`super::factors`].
+
+use std::f64::consts::{PI as PI64, TAU as TAU64};
+
+use num_complex::Complex;
+
+pub(super) fn boxcar(_x: f64) -> f64 {
+    1.0
+}
+
+pub(super) fn welch(x: f64) -> f64 {
+    let t = 2.0 * x - 1.0;
+    1.0 - t * t
+}
+
+pub(super) fn bartlett(x: f64) -> f64 {
+    if x < 0.5 {
+        2.0 * x
+    } else {
+        2.0 - (2.0 * x)
+    }
+}
+
+// Our integration samples really close to both endpoints, but technically we're supposed to reach a
+// specific toe value.  Anyway.  Better than boxcar.
+pub(super) fn hamming(x: f64) -> f64 {
+    // Source Wikipedia and a robot 🤖
+    const A0: f64 = 25.0 / 46.0;
+    A0 - (1.0 - A0) * (2.0 * PI64 * x).cos()
+}
+
+/// Kaiser-Bessel, approximating the prolate spheroid that puts the most energy in the main lobe.
+pub(super) fn kaiser(x: f64, beta: f64) -> f64 {
+    let t = 2.0 * x - 1.0;
+    bessel_i0(beta * (1.0 - t * t).max(0.0).sqrt()) / bessel_i0(beta)
+}
+
+/// The `beta` of a Kaiser window with its largest side lobe `attenuation_db` down.  Fit by Kaiser
+/// and Schafer, within a fraction of a dB.
+pub fn kaiser_beta(attenuation_db: f64) -> f64 {
+    let a = attenuation_db;
+    if a <= 13.26 {
+        0.0
+    } else if a <= 60.0 {
+        0.76609 * (a - 13.26).powf(0.4) + 0.09834 * (a - 13.26)
+    } else {
+        0.12438 * (a + 6.3)
+    }
+}
+
+/// Zeroth order modified Bessel function of the first kind, summing its power series until the
+/// terms no longer matter.
+fn bessel_i0(x: f64) -> f64 {
+    let q = 0.25 * x * x;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    let mut k = 1.0;
+    while term > sum * f64::EPSILON {
+        term *= q / (k * k);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+/// Integrates discrete bin weights given a window_fn.  Will automatically normalize windows where
+/// normalization in the window_fn is hard.
+pub fn bin_weights(window_fn: &impl Fn(f64) -> f64, bins: usize) -> Vec<f64> {
+    let samples_per_bin = 512;
+    let mut weights = Vec::with_capacity(bins);
+
+    for bin in 0..bins {
+        let bin_start = bin as f64 / bins as f64;
+        let bin_end = (bin + 1) as f64 / bins as f64;
+
+        let mut sum = 0.0;
+        let step = (bin_end - bin_start) / samples_per_bin as f64;
+        for s in 0..samples_per_bin {
+            let t = bin_start + (s as f64 + 0.5) * step;
+            sum += window_fn(t);
+        }
+        weights.push(sum / samples_per_bin as f64);
+    }
+    if !weights.iter().find(|x| **x == 1.0).is_some() {
+        let max = weights.iter().fold(0.0f64, |max, x| max.max(*x));
+        let norm = 1.0 / max;
+        weights.iter_mut().for_each(|x| {
+            *x = *x * norm;
+        });
+        weights
+    } else {
+        weights
+    }
+}
+
+// # The Dolph-Chebyshev Window
+//
+// Chebyshev created the equiripple polynomials and Dolph applied them to finding the minimum main
+// lobe width.
+//
+// The specific value of this window cannot be overstated.  We can make an optimal window to only
+// respond to one main lobe with all side lobes being equal.  The flatness of that floor enables us
+// to treat everything above it as as true tone at the target frequency.  Together with the window
+// length and parameterization of the window, we can choose the noise floor, Q, and time resolution,
+// right up to the Gabor limit in every single case.
+//
+// The limit cases so far seem actually useful.  If the noise floor is chosen to be high, the
+// weights can begin to resemble the Hamming window, demonstrating the same principled side lobe
+// suppression, increasing fast attack characteristics without jeopardizing the noise floor
+// behavior.
+//
+// The tradeoffs enable much additional information to be gained via interpretation.  If we are
+// listening to a signal with 70dB peaks and we use a narrow, short, -20dB filter, the last 20dB are
+// all usable signal.  If we use a longer window with -70dB side lobes, all of the 70dB are usable.
+// We can look for extremely quiet sounds with some delay and uncertainty around pitch, and we can
+// look for extremely loud tones at a specific pitch with the minimum window and maximum
+// selectivity.  It is truly the Dolph Lundgren of windows.
+//
+// ## Avoid Crappy Interpolations! 🙅‍♂️
+//
+// The goal of solving for the window at each bin is to find the exact solutions for each window
+// location instead of numerically integrating the shape and praying for the best.  The true
+// Chebyshev window cannot be usefully calculated in that way and will have unpredictable side-lobe
+// errors, either curving upward (leading to frequency responses at a distance!) or having
+// unexpectedly high first side lobes.  Different parameters lead to shapes that look nothing like
+// the smooth graph approximation.  This is a discrete problem!
+//
+// ## Specific Credits
+//
+// Fist thanks to Practical Cryptography for leaving a post up for posterity.  Th C implementation
+// found here was translated to Rust to first establish grounding with a working reference
+// implementation:
+//
+// http://practicalcryptography.com/miscellaneous/machine-learning/implementing-dolph-chebyshev-window/
+//
+// After conversion to Rust, opportunities for more precision were taken to minimize floating point
+// errors in summation and multiplications.  Nonetheless, the window edges were quite unstable for
+// window sizes relevant to our mission, and so the IDFT route was vibe coded together and verified
+// against reference implementations.
+//
+// Second thanks to Richard Lyons for posting this a while back:
+//
+// https://www.dsprelated.com/showarticle/42.php
+//
+// Their explicit procedure allowed zooming in on a very important detail that deserves microscopic
+// attention: the first (and last) index.  A proper Chebyshev window has "pedestals" in many
+// solutions, the first point being larger than the others.  This isn't crazy since we can imagine
+// an elaborate set of diffraction slits creating a flat interference pattern, exactly the kind of
+// flatness of side lobes we want in the Chebyshev window.  Because the farthest points in the
+// window are the first sample that will cancel very nearby waves that have only just begun to cycle
+// out of phase, this pedestal is a critical element, not to be treated as a mere artifact that
+// should be thrown away.
+//
+// The technique that Lyon outlines includes dividing the first index by two.  It is related to the
+// chosen method.  The Cosine Summation formula is said to have an asymmetry that the toy IDFT
+// method here does not.  Because of this, we do not divide the first index by two.
+//
+// ## Contributing to Theory via Practice
+//
+// Ultimately, the final word is owned by practice.  Show us a flatter side lobe on real data in
+// f32, and we will adapt our practice until the model and theory can catch up.
+
+#[deny(deprecated)]
+/// Never use this except to compare the IDFT implementation to the naive one.
+fn dolph_chebyshev_toy(x: f64, attenuation_db: f64) -> f64 {
+    let r = 10f64.powf(attenuation_db / 20.0);
+    let xc = x - 0.5;
+    ((r + (r.powi(2) - 1.0).sqrt()).ln() * (PI64 * xc).cos()).cosh()
+}
+
+/// A specific treatment of the Chebyshev polynomial calculation said to be more stable between -1.0
+/// and 1.0.
+fn chebyshev_t_clenshaw(n: usize, x: f64) -> f64 {
+    let mut b_kplus1 = 0.0;
+    let mut b_kplus2 = 0.0;
+    let two_x = 2.0 * x;
+    for k in (1..=n).rev() {
+        let b_k = (two_x).mul_add(b_kplus1, -b_kplus2 + if k == n { 1.0 } else { 0.0 });
+        b_kplus2 = b_kplus1;
+        b_kplus1 = b_k;
+    }
+    x * b_kplus1 - b_kplus2
+}
+
+/// The combined Chebyshev polynomial calculation, switching implementaitons based on accuracy and
+/// resulting stability over various domains.
+fn chebyshev_t(n: usize, x: f64) -> f64 {
+    if x.abs() <= 1.0 {
+        chebyshev_t_clenshaw(n, x)
+    } else if x >= 1.0 {
+        ((n as f64) * x.acosh()).cosh()
+    } else {
+        let sign = if n % 2 == 0 { 1.0 } else { -1.0 };
+        sign * ((n as f64) * (-x).acosh()).cosh()
+    }
+}
+
+/// Generates the frequency domain data for feeding into the IDFT.
+fn dolph_chebyshev_spectrum(n: usize, attenuation_db: f64) -> Vec<Complex<f64>> {
+    let m = (n - 1) as f64;
+    let tg = 10f64.powf(attenuation_db / 20.0);
+    let beta = (tg.acosh() / m).cosh();
+
+    // Pre-calculate the denominator T_m(beta)
+    let denom = chebyshev_t(n - 1, beta);
+
+    polynomial_spectrum(n, beta, |x| chebyshev_t(n - 1, x) / denom)
+}
+
+/// Sample an equiripple-style polynomial around the circle.  Side lobes come from where `x0 * cos`
+/// sweeps `poly` through its ripples in [-1, 1] and the main lobe from where it climbs to `x0`.
+fn polynomial_spectrum(n: usize, x0: f64, poly: impl Fn(f64) -> f64) -> Vec<Complex<f64>> {
+    (0..n)
+        .map(|k| {
+            // We sample the circle at 2*PI*k/N, then divide by 2 to get the cosine argument.
+            // This ensures the samples are perfectly symmetric around the Nyquist point.
+            let theta = (TAU64 * k as f64) / (2 * n) as f64;
+            let weight = poly(x0 * theta.cos());
+
+            // Apply the centering phase shift.
+            // NOTE Use (n-1) specifically to center the window across the N samples.
+            let shift = (n as f64 - 1.0) / 2.0;
+            let angle = -TAU64 * k as f64 * shift / n as f64;
+
+            Complex::from_polar(weight, angle)
+        })
+        .collect()
+}
+
+/// Inverse discrete Fourier transform. Convert frequency domain into time domain, and windows are
+/// weights over the time domain.
+fn idft(x: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let n = x.len();
+    let n_f64 = n as f64;
+    (0..n)
+        .map(|m| {
+            let sum = x
+                .iter()
+                .enumerate()
+                .fold(Complex::new(0.0, 0.0), |acc, (k, &xk)| {
+                    let angle = TAU64 * k as f64 * m as f64 / n_f64;
+                    acc + xk * Complex::from_polar(1.0, angle)
+                });
+            sum / n_f64
+        })
+        .collect()
+}
+
+/// Generate the Dolph Lundgren of window functions.  Set `attenuation_db` and then as long as you
+/// approximately expect the correct peak volume levels in your input, everything from peak to the
+/// attenuation dB is usable signal.  The peak is narrower with less attenuation.  This has
+/// interplay with the Q vs window length relationship.
+///
+/// The Dolph-Chebyshev window in this module deserves special mention.  While all windows mitigate
+/// the worse of DFT side lobe and noise floor problems, the Dolph-Chebyshev brings us into tightly
+/// controllable engineering.  The human ear is responsive over a range of about 100dB while music
+/// is often listened to with 70dB peaks.  If we want to know the difference between barely audible
+/// tone at the target frequency and noise from a neighboring side lobe or loud crashing cymbals at
+/// some other frequency, we need to suppress 60-80dB of noise at all other frequencies.  The
+/// Dolph-Chebyshev window lets us do that without stretching the window length to unacceptably slow
+/// filling lengths that would smear sounds in time.
+pub fn dolph_chebyshev_window(n: usize, attenuation_db: f64) -> Vec<f64> {
+    assert!(n >= 2, "Window lengths below 3 cannot suppress side lobes");
+    assert!(
+        attenuation_db > 0.0,
+        "Valid attenuation levels must be positive."
+    );
+    window_from_spectrum(&dolph_chebyshev_spectrum(n, attenuation_db))
+}
+
+/// Real, symmetric, peak-normalized weights from a centered spectrum.
+fn window_from_spectrum(spectrum: &[Complex<f64>]) -> Vec<f64> {
+    let n = spectrum.len();
+    let mut out: Vec<f64> = idft(spectrum).iter().map(|c| c.re).collect();
+
+    // enforce symmetry before normalization
+    for i in 0..n / 2 {
+        let avg = 0.5 * (out[i] + out[n - 1 - i]);
+        out[i] = avg;
+        out[n - 1 - i] = avg;
+    }
+
+    // normalize by max
+    let max_val = out.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    out.iter_mut().for_each(|v| *v /= max_val);
+    out
+}
+
+// # The Ultraspherical Window
+//
+// Swap the Chebyshev polynomial for the Gegenbauer (ultraspherical) polynomial C_n^mu and the side
+// lobes are no longer equal.  The extra parameter tilts them.  With mu > 0 they fall away from the
+// main lobe, which buys the distant rejection of a Kaiser.  With mu < 0 they rise toward Nyquist,
+// and the main lobe gets narrower than a Dolph-Chebyshev with the same first side lobe.  At mu = 0
+// the polynomial degenerates and its limit is the Chebyshev polynomial.
+//
+// Unlike the Chebyshev, there is no closed form for the point x0 that puts the main lobe a given
+// ratio above the side lobes, so it is solved numerically from the largest side lobe.  Credit to
+// Stuart Bergen and Andreas Antoniou, "Design of Ultraspherical Window Functions with Prescribed
+// Spectral Characteristics", for the recipe.
+
+/// Gegenbauer polynomial C_n^mu(x) by the three term recurrence.
+fn gegenbauer(n: usize, mu: f64, x: f64) -> f64 {
+    let mut c_km2 = 1.0;
+    if n == 0 {
+        return c_km2;
+    }
+    let mut c_km1 = 2.0 * mu * x;
+    for k in 2..=n {
+        let k_f = k as f64;
+        let c_k = (2.0 * x * (k_f + mu - 1.0) * c_km1 - (k_f + 2.0 * mu - 2.0) * c_km2) / k_f;
+        c_km2 = c_km1;
+        c_km1 = c_k;
+    }
+    c_km1
+}
+
+/// The largest zero of a degree `n` polynomial that is positive at 1 and the height of its largest
+/// side lobe, the largest |C| below that zero.
+fn side_lobe(n: usize, poly: impl Fn(f64) -> f64) -> (f64, f64) {
+    // Zeros are close to evenly spaced in angle, so search in angle.
+    let c = |phi: f64| poly(phi.cos());
+    let step = PI64 / (32 * n) as f64;
+    let mut lo = 0.0;
+    while c(lo + step) > 0.0 {
+        lo += step;
+    }
+    let mut hi = lo + step;
+    for _ in 0..60 {
+        let mid = 0.5 * (lo + hi);
+        if c(mid) > 0.0 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    // Both halves are mirror images, so only the right one is searched.
+    let zero = hi;
+    let steps = ((PI64 / 2.0 - zero) / step).ceil() as usize;
+    let peak = (0..=steps)
+        .map(|i| c((zero + i as f64 * step).min(PI64 / 2.0)).abs())
+        .fold(0.0, f64::max);
+    (zero.cos(), peak)
+}
+
+/// Generate an ultraspherical window with its largest side lobe `attenuation_db` below the main
+/// lobe and the rest tilted by `mu`.  `mu` must be above -0.5.  Zero is the Dolph-Chebyshev window.
+pub fn ultraspherical_window(n: usize, mu: f64, attenuation_db: f64) -> Vec<f64> {
+    assert!(n >= 3, "Window lengths below 3 cannot suppress side lobes");
+    assert!(mu > -0.5, "Ultraspherical windows need mu above -0.5");
+    assert!(
+        attenuation_db > 0.0,
+        "Valid attenuation levels must be positive."
+    );
+    // The polynomial vanishes at zero, although its shape converges on the Chebyshev.
+    if mu.abs() < 1e-9 {
+        return dolph_chebyshev_window(n, attenuation_db);
+    }
+
+    let degree = n - 1;
+    // Dividing by mu keeps the polynomial positive at 1 on both sides of zero.
+    let poly = |x: f64| gegenbauer(degree, mu, x) / mu;
+    let (zero, side_lobe) = side_lobe(degree, poly);
+    let target = side_lobe * 10f64.powf(attenuation_db / 20.0);
+    // The polynomial only climbs past its largest zero, so bracket x0 above it.
+    let mut lo = zero;
+    let mut hi = 1.0;
+    while poly(hi) < target {
+        lo = hi;
+        hi *= 2.0;
+    }
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if poly(mid) < target {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    let x0 = 0.5 * (lo + hi);
+
+    window_from_spectrum(&polynomial_spectrum(n, x0, |x| poly(x) / target))
+}
@@ -0,0 +1,252 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Overlay Analysis
+//!
+//! What the overlays show, measured on the host from the audio being exported.  An
+//! [`OverlayAnalysis`] is fed the audio one video frame at a time and steps its own clock through
+//! it, so what it reports does not depend on the video's frame rate.
+//!
+//! - **Beats** are heard by a [`BeatTracker`] over the filter bank's columns.  Their confidence is
+//!   how well recent beats agree with the tempo, from a [`BeatPredictor`].
+//! - **Sections** come from a [`Segmenter`] over chroma folded from the same columns, averaged to
+//!   [`SECTION_RATE`] frames per second.
+//! - **Loudness** is read from a [`LoudnessMeter`] every [`LOUDNESS_INTERVAL`].
+//!
+//! [`push`](OverlayAnalysis::push) reports [`OverlayEvent`]s for a
+//! [`SidecarWriter`](super::overlay::SidecarWriter).  [`reading`](OverlayAnalysis::reading) is what
+//! a burn-in draws over the current frame.
+
+use crate::dsp::bank::BankTable;
+use crate::dsp::beat::{BeatEvent, BeatPhase, BeatPredictor, BeatTracker};
+use crate::dsp::chroma::{Chromagram, A4, CLASSES};
+use crate::dsp::loudness::LoudnessMeter;
+use crate::dsp::segment::{SectionEvent, SectionLabel, Segmenter};
+use crate::dsp::spectrogram::Spectrogram;
+use crate::units::{SampleRate, Samples, Seconds};
+
+/// Columns of the bank per second, which is also the rate beats are tracked at.
+pub const COLUMN_RATE: f64 = 100.0;
+/// Chroma frames per second fed to the segmenter.
+pub const SECTION_RATE: f64 = 4.0;
+/// Time between loudness events.
+pub const LOUDNESS_INTERVAL: Seconds = Seconds(0.5);
+/// Half-width of the segmenter's kernel in chroma frames, four seconds at [`SECTION_RATE`].
+const SECTION_KERNEL: usize = 16;
+
+/// Something for the sidecar track.  See [`SidecarWriter::event`].
+///
+/// [`SidecarWriter::event`]: super::overlay::SidecarWriter::event
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OverlayEvent {
+    Beat(BeatEvent),
+    Section(SectionEvent),
+    /// Loudness in LUFS.
+    Loudness {
+        at: Seconds,
+        momentary: f64,
+        short_term: f64,
+    },
+}
+
+/// The analysis at the end of the audio pushed so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OverlayReading {
+    pub t: Seconds,
+    /// LUFS, `None` until measured and in silence.
+    pub momentary: Option<f64>,
+    pub short_term: Option<f64>,
+    pub integrated: Option<f64>,
+    /// Highest true peak of any channel so far, in dBTP.
+    pub true_peak: f32,
+    /// `None` until beats agree with a tempo.
+    pub bpm: Option<f64>,
+    /// Where `t` falls between beats, when `bpm` is known.
+    pub phase: Option<BeatPhase>,
+    /// When the last beat was heard.
+    pub beat: Option<Seconds>,
+    /// The section playing, once the segmenter has seen the opening.
+    pub section: Option<SectionLabel>,
+}
+
+/// See the [module](self) docs.
+pub struct OverlayAnalysis {
+    fs: SampleRate,
+    channels: usize,
+    /// Frames pushed.
+    total: u64,
+    spectrogram: Spectrogram,
+    /// Frames per column.
+    hop: usize,
+    /// Frames since the last column.
+    since: usize,
+    tracker: BeatTracker,
+    predictor: BeatPredictor,
+    chromagram: Chromagram,
+    /// Chroma summed over the columns since the last segmenter frame.
+    chroma: [f32; CLASSES],
+    columns: usize,
+    /// Columns per segmenter frame.
+    columns_per_section: usize,
+    segmenter: Segmenter,
+    meter: LoudnessMeter,
+    /// When loudness was last reported.
+    loudness_at: Seconds,
+    beat: Option<Seconds>,
+}
+
+impl OverlayAnalysis {
+    /// Analyze `channels` interleaved channels at the rate of `table`.
+    pub fn new(table: BankTable, channels: usize) -> Self {
+        let fs = SampleRate(table.sample_rate as f64);
+        let channels = channels.max(1);
+        let hop = (fs.get() / COLUMN_RATE).round().max(1.0) as usize;
+        let chromagram = Chromagram::from_table(&table, A4);
+        let spectrogram = Spectrogram::new(table, Samples(hop))
+            .with_channels(channels)
+            .without_rows();
+        let column_rate = fs.get() / hop as f64;
+        Self {
+            fs,
+            channels,
+            total: 0,
+            spectrogram,
+            hop,
+            since: 0,
+            tracker: BeatTracker::new(column_rate),
+            // An exported frame is seen exactly when its audio is heard, so nothing leads.
+            predictor: BeatPredictor::new(Seconds(0.0)),
+            chromagram,
+            chroma: [0.0; CLASSES],
+            columns: 0,
+            columns_per_section: (column_rate / SECTION_RATE).round().max(1.0) as usize,
+            segmenter: Segmenter::new(SECTION_KERNEL),
+            meter: LoudnessMeter::new(fs, channels),
+            loudness_at: Seconds(0.0),
+            beat: None,
+        }
+    }
+
+    /// Feed interleaved frames, calling `each` for every event they complete, in time order.
+    pub fn push(&mut self, frames: &[f32], mut each: impl FnMut(OverlayEvent)) {
+        let mut rest = &frames[..frames.len() - frames.len() % self.channels];
+        while !rest.is_empty() {
+            let take = ((self.hop - self.since) * self.channels).min(rest.len());
+            let (chunk, tail) = rest.split_at(take);
+            self.meter.push(chunk);
+            self.spectrogram.push(chunk);
+            self.since += chunk.len() / self.channels;
+            self.total += (chunk.len() / self.channels) as u64;
+            if self.since == self.hop {
+                self.since = 0;
+                self.column(&mut each);
+            }
+            rest = tail;
+        }
+    }
+
+    /// Step the trackers by the column just completed.
+    fn column(&mut self, each: &mut impl FnMut(OverlayEvent)) {
+        let t = Seconds(self.total as f64 / self.fs.get());
+        let column = self.spectrogram.latest();
+        if let Some(beat) = self.tracker.push(t, column) {
+            self.predictor.observe(beat.at, beat.bpm);
+            self.beat = Some(beat.at);
+            each(OverlayEvent::Beat(BeatEvent {
+                fire_at: beat.at,
+                beat_at: beat.at,
+                confidence: self.predictor.confidence(),
+            }));
+        }
+
+        for (sum, x) in self.chroma.iter_mut().zip(self.chromagram.fold(column)) {
+            *sum += x;
+        }
+        self.columns += 1;
+        if self.columns == self.columns_per_section {
+            let chroma = std::mem::take(&mut self.chroma);
+            self.columns = 0;
+            if let Some(section) = self.segmenter.push(t, &chroma) {
+                each(OverlayEvent::Section(section));
+            }
+        }
+
+        if t.get() - self.loudness_at.get() >= LOUDNESS_INTERVAL.get() {
+            self.loudness_at = t;
+            let measured = self.meter.momentary().zip(self.meter.short_term());
+            if let Some((momentary, short_term)) =
+                measured.filter(|(m, s)| m.is_finite() && s.is_finite())
+            {
+                each(OverlayEvent::Loudness {
+                    at: t,
+                    momentary,
+                    short_term,
+                });
+            }
+        }
+    }
+
+    pub fn reading(&self) -> OverlayReading {
+        let finite = |x: Option<f64>| x.filter(|x| x.is_finite());
+        let t = Seconds(self.total as f64 / self.fs.get());
+        let phase = self.predictor.phase(t);
+        OverlayReading {
+            t,
+            momentary: finite(self.meter.momentary()),
+            short_term: finite(self.meter.short_term()),
+            integrated: finite(self.meter.integrated()),
+            true_peak: self.meter.true_peak_db(),
+            bpm: phase.map(|p| 60.0 / p.period.get()),
+            phase,
+            beat: self.beat,
+            section: self.segmenter.sections().last().map(|s| s.label),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::settings::DspSettings;
+
+    #[test]
+    fn test_overlay_analysis() {
+        let fs = SampleRate(48_000.0);
+        let dsp = DspSettings {
+            bins: 32,
+            ..Default::default()
+        };
+        let mut analysis = OverlayAnalysis::new(dsp.design_table(fs), 1);
+
+        // Decaying bursts of noise at 120 BPM for twelve seconds, in video frames at 30fps.
+        let mut seed = 1u32;
+        let frames: Vec<f32> = (0..12 * 48_000)
+            .map(|n| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let noise = (seed >> 8) as f32 / (1 << 23) as f32 - 1.0;
+                let since = (n % 24_000) as f32 / fs.get() as f32;
+                0.5 * noise * (-since * 40.0).exp()
+            })
+            .collect();
+        let mut beats = Vec::new();
+        let mut loudness = Vec::new();
+        for chunk in frames.chunks(1600) {
+            analysis.push(chunk, |event| match event {
+                OverlayEvent::Beat(beat) => beats.push(beat),
+                OverlayEvent::Loudness { at, .. } => loudness.push(at),
+                OverlayEvent::Section(_) => {}
+            });
+        }
+        assert!(beats.len() > 8, "{beats:?}");
+        assert!(beats.last().unwrap().confidence > 0.9);
+        // Loudness needs 3s of audio, then arrives every half second.
+        assert!((18..=20).contains(&loudness.len()), "{loudness:?}");
+        assert!(loudness.windows(2).all(|w| w[1] > w[0]));
+
+        let reading = analysis.reading();
+        assert_eq!(reading.t, Seconds(12.0));
+        assert!((reading.bpm.unwrap() - 120.0).abs() < 2.0);
+        assert!(reading.momentary.is_some());
+        assert!(reading.true_peak < 0.0 && reading.true_peak > -12.0);
+    }
+}
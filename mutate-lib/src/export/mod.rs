@@ -0,0 +1,11 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Export
+//!
+//! Support for recording sessions to files.  Video frames come from the renderer.  Everything else
+//! that should travel with the video, such as analysis overlays, is produced here so that
+//! recording frontends do not need to know how the analysis is computed.
+
+pub mod analysis;
+pub mod overlay;
@@ -0,0 +1,368 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Analysis Overlays
+//!
+//! Audio engineers reviewing a recording want to see what the analysis saw: where beats landed,
+//! how loud each passage measured, and the spectrogram underneath the artistic render.  Overlays
+//! can travel with an exported video in two ways:
+//!
+//! - **Burn-in** composites the selected overlays into the video frames as a separate layer over
+//!   the visuals.  Anyone can watch the result, but it cannot be turned off.
+//! - **Sidecar** writes the analysis as a timed text track next to the video.  WebVTT is understood
+//!   by players and editors as subtitles.  JSON lines keep full precision for tooling.
+//!
+//! The [`SidecarWriter`] streams events in time order as the
+//! [analysis](super::analysis::OverlayAnalysis) produces them, so a long recording never holds its
+//! whole track in memory.
+//!
+//! ```text
+//! mutate-visualizer --file song.flac --export song.mp4 --overlays beats,loudness
+//! ```
+
+// NEXT the spectrogram strip.  It is burn-in only since a text track cannot carry it.
+
+use std::io::Write;
+use std::str::FromStr;
+
+use super::analysis::OverlayEvent;
+use crate::dsp::beat::BeatEvent;
+use crate::dsp::segment::SectionEvent;
+use crate::dsp::units::Seconds;
+use crate::MutateError;
+
+/// How long a beat marker cue stays on screen.
+const BEAT_CUE: f64 = 0.1;
+/// How long a section change cue stays on screen.
+const SECTION_CUE: f64 = 2.0;
+
+/// Which analysis overlays to export.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Overlays {
+    pub spectrogram: bool,
+    pub beats: bool,
+    pub sections: bool,
+    pub loudness: bool,
+}
+
+impl Overlays {
+    pub fn all() -> Self {
+        Self {
+            spectrogram: true,
+            beats: true,
+            sections: true,
+            loudness: true,
+        }
+    }
+
+    /// Parse a comma separated list such as `beats,loudness`, or `all`.
+    pub fn parse(list: &str) -> Result<Self, MutateError> {
+        let mut overlays = Self::default();
+        for name in list.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match name {
+                "all" => overlays = Self::all(),
+                "spectrogram" => overlays.spectrogram = true,
+                "beats" => overlays.beats = true,
+                "sections" => overlays.sections = true,
+                "loudness" | "lufs" => overlays.loudness = true,
+                _ => return Err(MutateError::UnknownParam(format!("overlay `{name}`"))),
+            }
+        }
+        Ok(overlays)
+    }
+}
+
+impl FromStr for Overlays {
+    type Err = MutateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Where overlays end up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverlayMode {
+    /// Composited into the video frames.
+    BurnIn,
+    /// Written to a timed text track beside the video.
+    #[default]
+    Sidecar,
+}
+
+impl FromStr for OverlayMode {
+    type Err = MutateError;
+
+    /// `burn-in` or `sidecar`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "burn-in" => Ok(Self::BurnIn),
+            "sidecar" => Ok(Self::Sidecar),
+            _ => Err(MutateError::UnknownParam(format!("overlay mode `{s}`"))),
+        }
+    }
+}
+
+/// Text format of a sidecar track.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SidecarFormat {
+    #[default]
+    WebVtt,
+    JsonLines,
+}
+
+impl SidecarFormat {
+    /// File extension for the sidecar, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SidecarFormat::WebVtt => "vtt",
+            SidecarFormat::JsonLines => "jsonl",
+        }
+    }
+}
+
+impl FromStr for SidecarFormat {
+    type Err = MutateError;
+
+    /// `vtt` or `jsonl`, as the extension.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vtt" => Ok(Self::WebVtt),
+            "jsonl" => Ok(Self::JsonLines),
+            _ => Err(MutateError::UnknownParam(format!("sidecar format `{s}`"))),
+        }
+    }
+}
+
+/// Everything the exporter needs to know about overlays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OverlayOptions {
+    pub overlays: Overlays,
+    pub mode: OverlayMode,
+    pub format: SidecarFormat,
+}
+
+/// A loudness reading waiting for the next one to know when its cue ends.
+struct Reading {
+    at: Seconds,
+    momentary: f64,
+    short_term: f64,
+}
+
+/// Streams analysis events to a sidecar track.  Events of each kind must arrive in time order.
+/// Events of kinds not selected in [`Overlays`] are dropped.
+pub struct SidecarWriter<W: Write> {
+    out: W,
+    format: SidecarFormat,
+    overlays: Overlays,
+    pending: Option<Reading>,
+}
+
+impl<W: Write> SidecarWriter<W> {
+    pub fn new(mut out: W, format: SidecarFormat, overlays: Overlays) -> std::io::Result<Self> {
+        if format == SidecarFormat::WebVtt {
+            writeln!(out, "WEBVTT\n")?;
+        }
+        Ok(Self {
+            out,
+            format,
+            overlays,
+            pending: None,
+        })
+    }
+
+    /// Write whichever kind of event `event` is.
+    pub fn event(&mut self, event: &OverlayEvent) -> std::io::Result<()> {
+        match event {
+            OverlayEvent::Beat(beat) => self.beat(beat),
+            OverlayEvent::Section(section) => self.section(section),
+            OverlayEvent::Loudness {
+                at,
+                momentary,
+                short_term,
+            } => self.loudness(*at, *momentary, *short_term),
+        }
+    }
+
+    pub fn beat(&mut self, beat: &BeatEvent) -> std::io::Result<()> {
+        if !self.overlays.beats {
+            return Ok(());
+        }
+        let at = beat.beat_at;
+        match self.format {
+            SidecarFormat::WebVtt => self.cue(at, at + Seconds(BEAT_CUE), "♩ beat"),
+            SidecarFormat::JsonLines => writeln!(
+                self.out,
+                r#"{{"t":{:.4},"kind":"beat","confidence":{:.3}}}"#,
+                at.get(),
+                beat.confidence
+            ),
+        }
+    }
+
+    pub fn section(&mut self, section: &SectionEvent) -> std::io::Result<()> {
+        if !self.overlays.sections {
+            return Ok(());
+        }
+        let at = section.at;
+        match self.format {
+            SidecarFormat::WebVtt => {
+                let text = match section.repeat_of {
+                    Some(i) => format!("section {} (repeats #{})", section.label, i + 1),
+                    None => format!("section {}", section.label),
+                };
+                self.cue(at, at + Seconds(SECTION_CUE), &text)
+            }
+            SidecarFormat::JsonLines => {
+                let repeat = section
+                    .repeat_of
+                    .map_or("null".to_owned(), |i| i.to_string());
+                writeln!(
+                    self.out,
+                    r#"{{"t":{:.4},"kind":"section","label":"{}","repeat_of":{},"novelty":{:.3}}}"#,
+                    at.get(),
+                    section.label,
+                    repeat,
+                    section.novelty
+                )
+            }
+        }
+    }
+
+    /// Record a loudness reading in LUFS.  In WebVTT each reading stays up until the next one.
+    pub fn loudness(
+        &mut self,
+        at: Seconds,
+        momentary: f64,
+        short_term: f64,
+    ) -> std::io::Result<()> {
+        if !self.overlays.loudness {
+            return Ok(());
+        }
+        match self.format {
+            SidecarFormat::WebVtt => {
+                self.flush_reading(at)?;
+                self.pending = Some(Reading {
+                    at,
+                    momentary,
+                    short_term,
+                });
+                Ok(())
+            }
+            SidecarFormat::JsonLines => writeln!(
+                self.out,
+                r#"{{"t":{:.4},"kind":"loudness","momentary":{:.2},"short_term":{:.2}}}"#,
+                at.get(),
+                momentary,
+                short_term
+            ),
+        }
+    }
+
+    /// Close the track at `end`, the duration of the recording, and return the writer.
+    pub fn finish(mut self, end: Seconds) -> std::io::Result<W> {
+        self.flush_reading(end)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn flush_reading(&mut self, until: Seconds) -> std::io::Result<()> {
+        if let Some(r) = self.pending.take() {
+            let text = format!("M {:.1} LUFS  S {:.1} LUFS", r.momentary, r.short_term);
+            self.cue(r.at, until, &text)?;
+        }
+        Ok(())
+    }
+
+    fn cue(&mut self, start: Seconds, end: Seconds, text: &str) -> std::io::Result<()> {
+        writeln!(
+            self.out,
+            "{} --> {}\n{text}\n",
+            timestamp(start),
+            timestamp(end)
+        )
+    }
+}
+
+/// WebVTT `hh:mm:ss.mmm`.
+fn timestamp(t: Seconds) -> String {
+    let ms = (t.get().max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dsp::segment::SectionLabel;
+
+    fn beat(at: f64) -> BeatEvent {
+        BeatEvent {
+            fire_at: Seconds(at - 0.04),
+            beat_at: Seconds(at),
+            confidence: 0.9,
+        }
+    }
+
+    #[test]
+    fn test_overlay_webvtt() {
+        let overlays = Overlays::parse("beats, lufs").unwrap();
+        let mut w = SidecarWriter::new(Vec::new(), SidecarFormat::WebVtt, overlays).unwrap();
+        w.loudness(Seconds(0.0), -14.04, -15.0).unwrap();
+        w.beat(&beat(0.5)).unwrap();
+        w.loudness(Seconds(3661.25), -9.0, -10.0).unwrap();
+        let section = SectionEvent {
+            at: Seconds(1.0),
+            index: 1,
+            label: SectionLabel(1),
+            repeat_of: None,
+            novelty: 0.5,
+        };
+        // Not selected, so dropped.
+        w.section(&section).unwrap();
+        let text = String::from_utf8(w.finish(Seconds(3662.0)).unwrap()).unwrap();
+        assert_eq!(
+            text,
+            "WEBVTT\n\n\
+             00:00:00.500 --> 00:00:00.600\n♩ beat\n\n\
+             00:00:00.000 --> 01:01:01.250\nM -14.0 LUFS  S -15.0 LUFS\n\n\
+             01:01:01.250 --> 01:01:02.000\nM -9.0 LUFS  S -10.0 LUFS\n\n"
+        );
+    }
+
+    #[test]
+    fn test_overlay_json_lines() {
+        let mut w =
+            SidecarWriter::new(Vec::new(), SidecarFormat::JsonLines, Overlays::all()).unwrap();
+        w.beat(&beat(1.0)).unwrap();
+        w.event(&OverlayEvent::Section(SectionEvent {
+            at: Seconds(2.0),
+            index: 2,
+            label: SectionLabel(0),
+            repeat_of: Some(0),
+            novelty: 0.25,
+        }))
+        .unwrap();
+        let text = String::from_utf8(w.finish(Seconds(3.0)).unwrap()).unwrap();
+        assert_eq!(
+            text,
+            "{\"t\":1.0000,\"kind\":\"beat\",\"confidence\":0.900}\n\
+             {\"t\":2.0000,\"kind\":\"section\",\"label\":\"A\",\"repeat_of\":0,\"novelty\":0.250}\n"
+        );
+        assert!(Overlays::parse("beats,sparkles").is_err());
+        assert_eq!(
+            "burn-in".parse::<OverlayMode>().unwrap(),
+            OverlayMode::BurnIn
+        );
+        assert_eq!(
+            "jsonl".parse::<SidecarFormat>().unwrap(),
+            SidecarFormat::JsonLines
+        );
+        assert!("srt".parse::<SidecarFormat>().is_err());
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Actions
+//!
+//! Named commands from the user, such as `pause` or `screenshot`, usually bound to keys with
+//! [`input::Bindings`](crate::input::Bindings).  A [`Node`](super::Node) lists the actions it
+//! handles in [`actions`](super::Node::actions), and the host hands each action to the graph with
+//! [`Graph::act`], which calls [`act`](super::Node::act) on those nodes in run order.
+//!
+//! Hosts handle their own actions too.  A node that handles `pause` pauses along with the frontend
+//! rather than instead of it.  Actions that neither the host nor any node handles are worth a
+//! warning, since they usually come from a typo in a binding.
+
+use super::Graph;
+use crate::MutateError;
+
+impl Graph {
+    /// Every action some node handles, sorted and without repeats.
+    pub fn actions(&self) -> Vec<&'static str> {
+        let mut actions: Vec<&'static str> = self
+            .nodes
+            .iter()
+            .filter_map(|n| n.runner.as_ref())
+            .flat_map(|runner| runner.actions().iter().copied())
+            .collect();
+        actions.sort_unstable();
+        actions.dedup();
+        actions
+    }
+
+    /// Hand `action` to every node that handles it, in run order.  Returns how many did.  Stops at
+    /// the first node that fails.
+    pub fn act(&mut self, action: &str) -> Result<usize, MutateError> {
+        let mut handled = 0;
+        for id in self.order()? {
+            let Some(runner) = self.nodes[id.0].runner.as_mut() else {
+                continue;
+            };
+            if runner.actions().contains(&action) {
+                runner.act(action)?;
+                handled += 1;
+            }
+        }
+        Ok(handled)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::{Frame, GraphEvent, Node, ParamHandle, ParamSpec, Params, PortKind, PortSpec};
+
+    use std::sync::{Arc, Mutex};
+
+    /// Records the actions it receives.
+    struct Actor {
+        name: &'static str,
+        actions: &'static [&'static str],
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Params for Actor {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[]
+        }
+    }
+
+    impl Node for Actor {
+        fn inputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "in",
+                kind: PortKind::Scalar,
+            }]
+        }
+
+        fn outputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "out",
+                kind: PortKind::Scalar,
+            }]
+        }
+
+        fn actions(&self) -> &'static [&'static str] {
+            self.actions
+        }
+
+        fn attach(&mut self, _params: ParamHandle) {}
+
+        fn act(&mut self, action: &str) -> Result<(), MutateError> {
+            if action == "fail" {
+                return Err(MutateError::InvalidNode(self.name.to_owned()));
+            }
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} {action}", self.name));
+            Ok(())
+        }
+
+        fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+            frame.emit(0, GraphEvent::Scalar(0.0));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_act_in_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut graph = Graph::new();
+        let node = |name, actions| Actor {
+            name,
+            actions,
+            log: log.clone(),
+        };
+        // Added downstream first so that run order differs from insertion order.
+        let draw = graph
+            .add("draw", node("draw", &["pause", "screenshot", "fail"]))
+            .unwrap();
+        let source = graph.add("source", node("source", &["pause"])).unwrap();
+        graph.connect(source, "out", draw, "in").unwrap();
+
+        assert_eq!(graph.actions(), ["fail", "pause", "screenshot"]);
+        assert_eq!(graph.act("pause").unwrap(), 2);
+        assert_eq!(graph.act("screenshot").unwrap(), 1);
+        assert_eq!(graph.act("fullscreen").unwrap(), 0);
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["source pause", "draw pause", "draw screenshot"]
+        );
+        assert!(graph.act("fail").is_err());
+    }
+}
@@ -0,0 +1,317 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Configuration
+//!
+//! Parameters are tuned while running and read every frame.  Configuration is what nodes are
+//! provisioned against: the window resolution, the audio source and its rate, the device.  It
+//! changes rarely, and when it does, nodes rebuild buffers, pipelines, or filters.
+//!
+//! A [`Node`](super::Node) lists the keys it depends on in [`watches`](super::Node::watches).  The
+//! host writes keys with [`Graph::configure`], and before the next frame the graph calls
+//! [`update`](super::Node::update) on every node watching a changed key, in the same topological
+//! order frames run in.  Upstream nodes have always re-provisioned by the time a downstream node
+//! sees the change, so a resize or device switch never tears down the graph.
+//!
+//! Writing a value equal to the current one is not a change.  When the value stays the same but what
+//! it names does not, such as a device rebuilt after it was lost, [`Graph::invalidate`] the key.
+
+// MAYBE a node whose output format changed could mark its downstream nodes affected, so that only
+// the source of a change needs to watch for it.
+
+use std::collections::BTreeMap;
+
+use super::Graph;
+use crate::MutateError;
+
+/// Surface size in pixels, as an [`Extent`](ConfigValue::Extent).
+pub const RESOLUTION: &str = "resolution";
+/// Name of the audio source, as [`Text`](ConfigValue::Text).
+pub const SOURCE: &str = "source";
+/// Sample rate of the audio source, as a [`Rate`](ConfigValue::Rate).
+pub const SOURCE_RATE: &str = "source_rate";
+/// Name of the device nodes provision on, as [`Text`](ConfigValue::Text).
+pub const DEVICE: &str = "device";
+/// Samples per pixel render nodes ask for, as a [`Count`](ConfigValue::Count).  Nodes round down to
+/// what the device supports.
+pub const SAMPLES: &str = "samples";
+/// Lowest frequency analysis nodes resolve, as a [`Frequency`](ConfigValue::Frequency).
+pub const MIN_FREQ: &str = "min_freq";
+/// Highest frequency analysis nodes resolve, as a [`Frequency`](ConfigValue::Frequency).
+pub const MAX_FREQ: &str = "max_freq";
+/// Frequency bins between [`MIN_FREQ`] and [`MAX_FREQ`], as a [`Count`](ConfigValue::Count).
+pub const BINS: &str = "bins";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigValue {
+    Extent { width: u32, height: u32 },
+    Rate(u32),
+    Count(u32),
+    /// Hertz.
+    Frequency(f64),
+    Text(String),
+}
+
+/// Current configuration, keyed by name.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    values: BTreeMap<&'static str, ConfigValue>,
+}
+
+impl Config {
+    pub fn get(&self, key: &str) -> Option<&ConfigValue> {
+        self.values.get(key)
+    }
+
+    /// Width and height under `key`, if it holds an extent.
+    pub fn extent(&self, key: &str) -> Option<(u32, u32)> {
+        match self.get(key)? {
+            ConfigValue::Extent { width, height } => Some((*width, *height)),
+            _ => None,
+        }
+    }
+
+    pub fn rate(&self, key: &str) -> Option<u32> {
+        match self.get(key)? {
+            ConfigValue::Rate(rate) => Some(*rate),
+            _ => None,
+        }
+    }
+
+    pub fn count(&self, key: &str) -> Option<u32> {
+        match self.get(key)? {
+            ConfigValue::Count(count) => Some(*count),
+            _ => None,
+        }
+    }
+
+    pub fn frequency(&self, key: &str) -> Option<f64> {
+        match self.get(key)? {
+            ConfigValue::Frequency(hz) => Some(*hz),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            ConfigValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Store `value`.  Returns whether it changed.
+    fn set(&mut self, key: &'static str, value: ConfigValue) -> bool {
+        match self.values.insert(key, value.clone()) {
+            Some(old) => old != value,
+            None => true,
+        }
+    }
+}
+
+impl Graph {
+    /// Current configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Write a configuration key.  Nodes watching it are updated before the next frame runs, or
+    /// right away with [`reconfigure`](Self::reconfigure).  Returns whether the value changed.
+    pub fn configure(&mut self, key: &'static str, value: ConfigValue) -> bool {
+        let changed = self.config.set(key, value);
+        if changed && !self.changed.contains(&key) {
+            self.changed.push(key);
+        }
+        changed
+    }
+
+    /// Treat `key` as changed without writing it, so that nodes watching it re-provision against the
+    /// same value, such as a device with the same name created again after it was lost.
+    pub fn invalidate(&mut self, key: &'static str) {
+        if !self.changed.contains(&key) {
+            self.changed.push(key);
+        }
+    }
+
+    /// Update every node watching a key changed since the last call, in run order.  Each node is
+    /// updated once with all of its changed keys.  Stops at the first node that fails, and the
+    /// changes are consumed either way.
+    pub fn reconfigure(&mut self) -> Result<(), MutateError> {
+        if self.changed.is_empty() {
+            return Ok(());
+        }
+        let changed = std::mem::take(&mut self.changed);
+        for id in self.order()? {
+            let Some(runner) = self.nodes[id.0].runner.as_mut() else {
+                continue;
+            };
+            let keys: Vec<&str> = changed
+                .iter()
+                .copied()
+                .filter(|k| runner.watches().contains(k))
+                .collect();
+            if !keys.is_empty() {
+                runner.update(&self.config, &keys)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::{
+        Frame, GraphEvent, Node, ParamHandle, ParamSpec, Params, PortKind, PortSpec,
+    };
+
+    use std::sync::{Arc, Mutex};
+
+    /// Records the updates it receives.
+    struct Watcher {
+        name: &'static str,
+        watches: &'static [&'static str],
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Params for Watcher {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[]
+        }
+    }
+
+    impl Node for Watcher {
+        fn inputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "in",
+                kind: PortKind::Scalar,
+            }]
+        }
+
+        fn outputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "out",
+                kind: PortKind::Scalar,
+            }]
+        }
+
+        fn watches(&self) -> &'static [&'static str] {
+            self.watches
+        }
+
+        fn attach(&mut self, _params: ParamHandle) {}
+
+        fn update(&mut self, config: &Config, changed: &[&str]) -> Result<(), MutateError> {
+            if changed.contains(&RESOLUTION) && config.extent(RESOLUTION).is_none() {
+                return Err(MutateError::InvalidNode(self.name.to_owned()));
+            }
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", self.name, changed.join(",")));
+            Ok(())
+        }
+
+        fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+            frame.emit(0, GraphEvent::Scalar(0.0));
+            Ok(())
+        }
+    }
+
+    fn graph(log: &Arc<Mutex<Vec<String>>>) -> Graph {
+        let mut graph = Graph::new();
+        let node = |name, watches| Watcher {
+            name,
+            watches,
+            log: log.clone(),
+        };
+        // Added downstream first so that run order differs from insertion order.
+        let draw = graph
+            .add("draw", node("draw", &[RESOLUTION, DEVICE]))
+            .unwrap();
+        let analysis = graph
+            .add("analysis", node("analysis", &[SOURCE_RATE]))
+            .unwrap();
+        let source = graph.add("source", node("source", &[SOURCE])).unwrap();
+        graph.connect(source, "out", analysis, "in").unwrap();
+        graph.connect(analysis, "out", draw, "in").unwrap();
+        graph
+    }
+
+    #[test]
+    fn test_updates_watchers_in_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut graph = graph(&log);
+
+        assert!(graph.configure(DEVICE, ConfigValue::Text("gpu".into())));
+        assert!(graph.configure(SOURCE_RATE, ConfigValue::Rate(44_100)));
+        assert!(graph.configure(
+            RESOLUTION,
+            ConfigValue::Extent {
+                width: 640,
+                height: 480
+            }
+        ));
+        assert!(graph.configure(SOURCE, ConfigValue::Text("mic".into())));
+        graph.run_frame().unwrap();
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "source source",
+                "analysis source_rate",
+                "draw device,resolution"
+            ]
+        );
+
+        // Equal values are not changes.
+        log.lock().unwrap().clear();
+        assert!(!graph.configure(SOURCE_RATE, ConfigValue::Rate(44_100)));
+        assert!(graph.configure(SOURCE_RATE, ConfigValue::Rate(48_000)));
+        graph.run_frame().unwrap();
+        assert_eq!(*log.lock().unwrap(), ["analysis source_rate"]);
+        assert_eq!(graph.config().rate(SOURCE_RATE), Some(48_000));
+
+        log.lock().unwrap().clear();
+        graph.run_frame().unwrap();
+        assert!(log.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut graph = graph(&log);
+        graph.configure(DEVICE, ConfigValue::Text("gpu".into()));
+        graph.reconfigure().unwrap();
+        log.lock().unwrap().clear();
+
+        assert!(!graph.configure(DEVICE, ConfigValue::Text("gpu".into())));
+        graph.invalidate(DEVICE);
+        graph.invalidate(DEVICE);
+        graph.run_frame().unwrap();
+        assert_eq!(*log.lock().unwrap(), ["draw device"]);
+    }
+
+    #[test]
+    fn test_update_failure() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut graph = graph(&log);
+        graph.configure(RESOLUTION, ConfigValue::Rate(60));
+        assert!(graph.reconfigure().is_err());
+        // The change was consumed.
+        assert!(graph.run_frame().is_ok());
+    }
+
+    #[test]
+    fn test_added_after_configure() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut graph = Graph::new();
+        graph.configure(SOURCE_RATE, ConfigValue::Rate(48_000));
+        graph.reconfigure().unwrap();
+        let node = Watcher {
+            name: "late",
+            watches: &[SOURCE_RATE, DEVICE],
+            log: log.clone(),
+        };
+        graph.add("late", node).unwrap();
+        assert_eq!(*log.lock().unwrap(), ["late source_rate"]);
+    }
+}
@@ -0,0 +1,538 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Context
+//!
+//! The graph runs once per displayed frame, and every frame has three deadlines, in the order they
+//! fall due:
+//!
+//! - **audio deadline**: the last moment to read audio that will be heard when the frame is seen.
+//!   Analysis runs between here and the submit deadline.
+//! - **submit deadline**: the last moment to submit GPU work and still make the present target.
+//! - **present target**: when the frame is expected to reach the display, aligned to vblank once a
+//!   present has been observed.
+//!
+//! [`FrameTiming`] turns the refresh period and the measured present latency into the
+//! [`FramePhases`] of each frame.  Nodes read them from [`Frame::phases`](super::Frame::phases).
+//!
+//! ## Pacing
+//!
+//! Frames are drawn late on purpose.  Rather than start the next frame as soon as the last was
+//! submitted, the host sleeps until just before the audio deadline with
+//! [`FramePhases::sleep_until_audio`].  Audio read after waking is then as fresh as it can be for
+//! the vblank the frame targets, and the GPU idles instead of queueing frames that only add
+//! latency.
+//!
+//! On a fixed refresh display, present targets fall on the vblank grid and a late frame waits for
+//! the next vblank.  A variable refresh display presents a late frame as soon as it is ready, so
+//! the target is only held to the maximum refresh rate.  [`Refresh::Detect`] tells the two apart by
+//! whether observed presents land on the grid.
+//!
+//! [`GraphContext`] owns the device and everything nodes need to provision GPU resources against
+//! it: command pools that cycle with frames in flight, a [`TransientPool`] of per-frame scratch
+//! memory that cycles with them, and a [`DeletionQueue`] that holds destroyed resources until the
+//! last frame that may use them has retired.  Nodes run by [`Graph::run_frame_in`] allocate scratch
+//! with [`Frame::transient`].  The most scratch any frame used is reported in the [`FrameStats`].
+//!
+//! [`TransientPool`]: crate::gpu::resource::transient::TransientPool
+//! [`Graph::run_frame_in`]: super::Graph::run_frame_in
+//! [`Frame::transient`]: super::Frame::transient
+//! [`FrameStats`]: super::FrameStats
+
+// NEXT nodes record into the frame's command pool through `Frame` once render nodes move into the
+// graph.  Until then the host records with the pool from `GraphContext::begin_frame`.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "vulkan")]
+use crate::gpu::prelude::*;
+#[cfg(feature = "vulkan")]
+use crate::graph::FrameStats;
+#[cfg(feature = "vulkan")]
+use crate::MutateError;
+
+/// Frames that may be recorded or executing at once.  Resources used by a frame are only safe to
+/// destroy this many frames later.
+pub const FRAMES_IN_FLIGHT: usize = 2;
+
+/// Bytes of scratch memory each frame may allocate.
+pub const TRANSIENT_CAPACITY: u64 = 16 << 20;
+
+/// Time reserved for analysis between the audio deadline and the submit deadline.
+pub const DEFAULT_AUDIO_LEAD: Duration = Duration::from_millis(4);
+
+/// How early to wake before the audio deadline.  Sleeps on desktop schedulers overshoot by up to
+/// about a millisecond.
+pub const WAKE_MARGIN: Duration = Duration::from_millis(1);
+
+/// Distance from the vblank grid, as a fraction of the period, within which a present counts as
+/// landing on it.  Present wait returns with some scheduler jitter.
+const GRID_TOLERANCE: f64 = 0.15;
+/// Smoothing for the fraction of presents off the grid.
+const GRID_ALPHA: f64 = 1.0 / 30.0;
+/// Fraction of presents off the grid above which a display is considered variable refresh.
+const VARIABLE_THRESHOLD: f64 = 0.5;
+
+/// Deadlines of one frame.  See the [module docs](self).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FramePhases {
+    /// When the phases were computed, normally the start of the frame.
+    pub start: Instant,
+    pub audio_deadline: Instant,
+    pub submit_deadline: Instant,
+    pub present_target: Instant,
+}
+
+impl FramePhases {
+    /// Time left at `now` before the submit deadline.  Zero once it has passed.
+    pub fn budget(&self, now: Instant) -> Duration {
+        self.submit_deadline.saturating_duration_since(now)
+    }
+
+    /// How far audio read at the audio deadline is ahead of when the frame is seen.
+    pub fn audio_latency(&self) -> Duration {
+        self.present_target - self.audio_deadline
+    }
+
+    /// When to wake to read audio, [`WAKE_MARGIN`] before the audio deadline.
+    pub fn wake_at(&self) -> Instant {
+        self.audio_deadline
+            .checked_sub(WAKE_MARGIN)
+            .map_or(self.start, |wake| wake.max(self.start))
+    }
+
+    /// Sleep until [`wake_at`](Self::wake_at).  Returns at once if it has passed.
+    pub fn sleep_until_audio(&self) {
+        let delay = self.wake_at().saturating_duration_since(Instant::now());
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+/// Whether present targets are aligned to a fixed vblank grid.  See the [module docs](self#pacing).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Refresh {
+    /// Decide from observed presents.  Fixed until presents say otherwise.
+    #[default]
+    Detect,
+    Fixed,
+    Variable,
+}
+
+/// Estimates frame deadlines from the refresh period and observed presents.
+#[derive(Clone, Debug)]
+pub struct FrameTiming {
+    period: Duration,
+    /// Queue to display.  Starts at one period until a present is observed.
+    present_latency: Duration,
+    audio_lead: Duration,
+    /// A vblank that was observed, to align present targets to.
+    vblank: Option<Instant>,
+    refresh: Refresh,
+    /// Smoothed fraction of observed presents that missed the vblank grid.
+    off_grid: f64,
+}
+
+impl FrameTiming {
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            present_latency: period,
+            audio_lead: DEFAULT_AUDIO_LEAD,
+            vblank: None,
+            refresh: Refresh::Detect,
+            off_grid: 0.0,
+        }
+    }
+
+    /// Timing for a display refresh rate in Hz.
+    pub fn from_refresh(hz: f64) -> Self {
+        Self::new(Duration::from_secs_f64(1.0 / hz))
+    }
+
+    pub fn with_audio_lead(mut self, lead: Duration) -> Self {
+        self.audio_lead = lead;
+        self
+    }
+
+    /// Skip detection when the display is known.
+    pub fn with_refresh(mut self, refresh: Refresh) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    pub fn present_latency(&self) -> Duration {
+        self.present_latency
+    }
+
+    /// Whether present targets follow frames rather than the vblank grid.
+    pub fn is_variable(&self) -> bool {
+        match self.refresh {
+            Refresh::Detect => self.off_grid > VARIABLE_THRESHOLD,
+            Refresh::Fixed => false,
+            Refresh::Variable => true,
+        }
+    }
+
+    /// Use a new refresh period, such as after moving to another display.  Forgets the vblank and
+    /// what was detected about the display.
+    pub fn set_period(&mut self, period: Duration) {
+        self.period = period;
+        self.vblank = None;
+        self.off_grid = 0.0;
+    }
+
+    /// Record a present reaching the display at `at`, `latency` after it was queued, such as from
+    /// `PresentRing::stats`.  Observing the same present again changes nothing.
+    pub fn observe_present(&mut self, at: Instant, latency: Duration) {
+        if let Some(vblank) = self.vblank.filter(|v| at > *v && !self.period.is_zero()) {
+            let periods = (at - vblank).as_secs_f64() / self.period.as_secs_f64();
+            let off = (periods - periods.round()).abs() > GRID_TOLERANCE || periods.round() == 0.0;
+            let off = if off { 1.0 } else { 0.0 };
+            self.off_grid += GRID_ALPHA * (off - self.off_grid);
+        }
+        self.vblank = Some(at);
+        self.present_latency = latency;
+    }
+
+    /// Deadlines for a frame starting at `now`.  The present target is the first vblank that
+    /// leaves room for the audio lead and the present latency.  Slack before it goes to audio,
+    /// which is read later and so sits closer to what is heard.  On a variable refresh display the
+    /// target is as soon as that room allows, but no sooner than a period after the last present.
+    pub fn phases(&self, now: Instant) -> FramePhases {
+        let earliest = now + self.audio_lead + self.present_latency;
+        let present_target = match self.vblank {
+            // The display waits for the frame, but no longer than its fastest refresh.
+            Some(vblank) if self.is_variable() => earliest.max(vblank + self.period),
+            Some(vblank) if vblank <= earliest && !self.period.is_zero() => {
+                let period = self.period.as_nanos();
+                let periods = (earliest - vblank).as_nanos().div_ceil(period);
+                vblank + Duration::from_nanos((periods * period) as u64)
+            }
+            _ => earliest,
+        };
+        let submit_deadline = present_target - self.present_latency;
+        FramePhases {
+            start: now,
+            audio_deadline: submit_deadline - self.audio_lead,
+            submit_deadline,
+            present_target,
+        }
+    }
+}
+
+/// Destruction deferred until no frame in flight can use the resource.  Entries are tagged with the
+/// number of frames begun when they were queued and run once all of those frames have retired.
+///
+/// `C` is whatever the destructors need, normally the [`Device`].
+pub struct DeletionQueue<C> {
+    pending: VecDeque<(u64, Destroy<C>)>,
+}
+
+type Destroy<C> = Box<dyn FnOnce(&C)>;
+
+impl<C> Default for DeletionQueue<C> {
+    fn default() -> Self {
+        Self {
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<C> DeletionQueue<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue `destroy` to run once frames before `frames` have retired.
+    pub fn defer(&mut self, frames: u64, destroy: impl FnOnce(&C) + 'static) {
+        debug_assert!(self.pending.back().is_none_or(|(f, _)| *f <= frames));
+        self.pending.push_back((frames, Box::new(destroy)));
+    }
+
+    /// Run every entry queued with `frames <= retired`.  Returns how many ran.
+    pub fn retire(&mut self, retired: u64, context: &C) -> usize {
+        let mut ran = 0;
+        while self.pending.front().is_some_and(|(f, _)| *f <= retired) {
+            let (_, destroy) = self.pending.pop_front().expect("checked above");
+            destroy(context);
+            ran += 1;
+        }
+        ran
+    }
+
+    /// Run everything.  Only valid once the device is idle.
+    pub fn flush(&mut self, context: &C) -> usize {
+        self.retire(u64::MAX, context)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Device, command allocation, deferred deletion, and frame timing for the nodes of a graph.
+#[cfg(feature = "vulkan")]
+pub struct GraphContext {
+    device: Device,
+    commands: PoolRing<Compute, FRAMES_IN_FLIGHT>,
+    transients: TransientPool<FRAMES_IN_FLIGHT>,
+    deletions: DeletionQueue<Device>,
+    timing: FrameTiming,
+    phases: FramePhases,
+    stats: FrameStats,
+    /// Frames begun.  The current frame is one less.
+    frames: u64,
+}
+
+#[cfg(feature = "vulkan")]
+impl GraphContext {
+    pub fn new(device: Device, timing: FrameTiming) -> Result<Self, MutateError> {
+        // SAFETY: the ring is destroyed in `destroy`, before the device it came from.
+        let queue = device.queues.compute(QueuePriority::High).queue_ref();
+        let commands = PoolRing::new(&device, &queue)?;
+        let transients = match TransientPool::new(&device, TRANSIENT_CAPACITY) {
+            Ok(transients) => transients,
+            Err(e) => {
+                commands.destroy(&device);
+                return Err(e.into());
+            }
+        };
+        let phases = timing.phases(Instant::now());
+        Ok(Self {
+            device,
+            commands,
+            transients,
+            deletions: DeletionQueue::new(),
+            timing,
+            phases,
+            stats: FrameStats::new(),
+            frames: 0,
+        })
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn device_mut(&mut self) -> &mut Device {
+        &mut self.device
+    }
+
+    pub fn timing(&self) -> &FrameTiming {
+        &self.timing
+    }
+
+    /// Update with observed presents so that phases track the display.
+    pub fn timing_mut(&mut self) -> &mut FrameTiming {
+        &mut self.timing
+    }
+
+    /// Phases of the current frame.
+    pub fn phases(&self) -> FramePhases {
+        self.phases
+    }
+
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    /// Update with queued frames and observed presents.
+    pub fn stats_mut(&mut self) -> &mut FrameStats {
+        &mut self.stats
+    }
+
+    /// Frames begun so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Begin the next frame.  Waits up to `timeout` nanoseconds for the frame that last used the
+    /// command slot, runs deletions that wait proved safe, rewinds the frame's scratch memory, and
+    /// computes the new phases.
+    ///
+    /// The [`SignalIntent`] **must** be signaled by the last submission of the frame, as with
+    /// [`PoolRing::acquire`].
+    pub fn begin_frame(
+        &mut self,
+        timeout: u64,
+    ) -> Result<(&mut CommandPool<Compute>, SignalIntent, FramePhases), MutateError> {
+        let (pool, intent) = self.commands.acquire(&self.device, timeout)?;
+        // The slot wait retired every frame up to this one less the frames in flight.
+        let retired = (self.frames + 1).saturating_sub(FRAMES_IN_FLIGHT as u64);
+        self.deletions.retire(retired, &self.device);
+        // Scratch retires with the same submission, so this does not wait again.
+        self.stats.observe_transients(self.transients.stats());
+        self.transients
+            .begin_frame(&self.device, intent.wait_value(), timeout)?;
+        self.frames += 1;
+        self.phases = self.timing.phases(Instant::now());
+        Ok((pool, intent, self.phases))
+    }
+
+    /// The scratch memory of the current frame, valid until the frame retires.
+    pub fn transients(&mut self) -> &mut TransientPool<FRAMES_IN_FLIGHT> {
+        &mut self.transients
+    }
+
+    /// Destroy a resource once no frame in flight can be using it.
+    pub fn defer_destroy(&mut self, destroy: impl FnOnce(&Device) + 'static) {
+        self.deletions.defer(self.frames, destroy);
+    }
+
+    /// Wait for the device, run all deferred deletions, and destroy the device.  Every submission
+    /// from [`begin_frame`](Self::begin_frame) must have been made.  A lost device is destroyed all
+    /// the same, and then reported.
+    pub fn destroy(mut self) -> Result<(), MutateError> {
+        // Waits on a lost device fail at once, but destroying what was created from it is valid.
+        let lost = match self.device.wait_idle() {
+            Err(e) if e.is_device_lost() => Some(e),
+            waited => {
+                waited?;
+                None
+            }
+        };
+        self.deletions.flush(&self.device);
+        self.transients.destroy(&self.device);
+        self.commands.destroy(&self.device);
+        self.device.destroy();
+        match lost {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const PERIOD: Duration = Duration::from_micros(16_667);
+
+    #[test]
+    fn test_phases_in_order() {
+        let timing = FrameTiming::new(PERIOD);
+        let now = Instant::now();
+        let phases = timing.phases(now);
+        assert_eq!(phases.start, now);
+        // Without a vblank, the frame gets just the audio lead.
+        assert_eq!(phases.audio_deadline, now);
+        assert_eq!(phases.submit_deadline, now + DEFAULT_AUDIO_LEAD);
+        assert_eq!(phases.present_target, now + DEFAULT_AUDIO_LEAD + PERIOD);
+        assert_eq!(phases.budget(now), DEFAULT_AUDIO_LEAD);
+        assert_eq!(phases.budget(phases.present_target), Duration::ZERO);
+        assert_eq!(phases.audio_latency(), DEFAULT_AUDIO_LEAD + PERIOD);
+    }
+
+    #[test]
+    fn test_phases_align_to_vblank() {
+        let mut timing = FrameTiming::new(PERIOD);
+        let vblank = Instant::now();
+        let latency = Duration::from_millis(20);
+        timing.observe_present(vblank, latency);
+
+        let now = vblank + Duration::from_millis(3);
+        let phases = timing.phases(now);
+        // 27ms out rounds up to the second vblank.
+        assert_eq!(phases.present_target, vblank + PERIOD * 2);
+        assert_eq!(phases.submit_deadline, phases.present_target - latency);
+        assert_eq!(
+            phases.audio_deadline,
+            phases.submit_deadline - DEFAULT_AUDIO_LEAD
+        );
+        // The slack went to audio.
+        assert!(phases.audio_deadline > now);
+
+        // Exactly on a vblank stays there.
+        let now = vblank + PERIOD * 3 - latency - DEFAULT_AUDIO_LEAD;
+        let phases = timing.phases(now);
+        assert_eq!(phases.present_target, vblank + PERIOD * 3);
+        assert_eq!(phases.audio_deadline, now);
+    }
+
+    #[test]
+    fn test_audio_lead() {
+        let lead = Duration::from_millis(10);
+        let timing = FrameTiming::from_refresh(120.0).with_audio_lead(lead);
+        let now = Instant::now();
+        let phases = timing.phases(now);
+        assert_eq!(phases.submit_deadline - phases.audio_deadline, lead);
+        assert_eq!(phases.budget(now), lead);
+    }
+
+    #[test]
+    fn test_wake_before_audio() {
+        let mut timing = FrameTiming::new(PERIOD);
+        let vblank = Instant::now();
+        timing.observe_present(vblank, Duration::from_millis(5));
+        let now = vblank + Duration::from_millis(1);
+        let phases = timing.phases(now);
+        assert_eq!(phases.wake_at(), phases.audio_deadline - WAKE_MARGIN);
+        assert!(phases.wake_at() > now);
+        // Without slack there is nothing to sleep through.
+        let phases = FrameTiming::new(PERIOD).phases(now);
+        assert_eq!(phases.wake_at(), now);
+    }
+
+    #[test]
+    fn test_detect_variable_refresh() {
+        let latency = Duration::from_millis(5);
+        let mut timing = FrameTiming::new(PERIOD);
+        let mut at = Instant::now();
+        // Fixed refresh presents land on the grid, sometimes skipping a vblank.
+        for i in 0..120 {
+            at += PERIOD * (1 + (i % 7 == 0) as u32) + Duration::from_micros(300);
+            timing.observe_present(at, latency);
+            timing.observe_present(at, latency);
+        }
+        assert!(!timing.is_variable());
+        // Variable refresh presents follow the frames.
+        for _ in 0..120 {
+            at += PERIOD + PERIOD / 3;
+            timing.observe_present(at, latency);
+        }
+        assert!(timing.is_variable());
+
+        // A late frame is not held to the next vblank.
+        let now = at + PERIOD / 2;
+        let phases = timing.phases(now);
+        assert_eq!(phases.present_target, now + DEFAULT_AUDIO_LEAD + latency);
+        // An early one waits out the fastest refresh.
+        let phases = timing.phases(at);
+        assert_eq!(phases.present_target, at + PERIOD);
+
+        let timing = timing.with_refresh(Refresh::Fixed);
+        assert!(!timing.is_variable());
+    }
+
+    #[test]
+    fn test_deletion_waits_for_frames() {
+        let destroyed = Rc::new(RefCell::new(Vec::new()));
+        let mut queue = DeletionQueue::<()>::new();
+        for (frames, name) in [(0, "a"), (1, "b"), (1, "c"), (3, "d")] {
+            let destroyed = destroyed.clone();
+            queue.defer(frames, move |_| destroyed.borrow_mut().push(name));
+        }
+
+        assert_eq!(queue.retire(0, &()), 1);
+        assert_eq!(queue.retire(0, &()), 0);
+        assert_eq!(queue.retire(2, &()), 2);
+        assert_eq!(*destroyed.borrow(), ["a", "b", "c"]);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.flush(&()), 1);
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,361 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Lanes
+//!
+//! Not every node runs at the frame rate.  Loudness and onset detection want every audio chunk,
+//! drawing wants every video frame, and feature extraction for training can take as long as it
+//! likes.  Each [`Node`](super::Node) declares its [`Lane`], the cadence it runs at:
+//!
+//! - [`Lane::Audio`] runs once per audio chunk, driven by whoever imports audio.
+//! - [`Lane::Frame`] runs once per video frame, driven by the render loop.  The default.
+//! - [`Lane::Background`] runs on its own thread, as often as it can up to a period.
+//!
+//! [`Graph::run_lane`] runs only the nodes of one lane.  [`Graph::run_frame`] still runs every node,
+//! which keeps single-threaded hosts and tests simple.
+//!
+//! ## Handoff
+//!
+//! An edge between nodes of different lanes carries a [`GraphBuffer`] instead of handing events
+//! over directly.  The producer publishes each event it emits, and the consumer reads whatever was
+//! published last, however many ticks of either lane ago.  A tick that emits nothing publishes
+//! nothing, so the consumer keeps seeing the last event rather than an empty input.
+//!
+//! ## Threads
+//!
+//! Once wired, [`Graph::into_lanes`] splits the graph into one [`Graph`] per lane.  Each keeps
+//! every node name, parameter, and edge, but only runs its own lane's nodes, so node ids and
+//! parameter paths stay valid in all of them.  The audio and frame lanes move to the threads that
+//! drive them.  [`LaneThread`] drives the background lane.
+
+// NEXT the audio lane is driven from the import callback once analysis nodes move into the graph.
+// MAYBE background nodes submit to the `WorkerPool` at `Priority::Background` instead of sharing
+// one thread, once there are enough of them to matter.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::pool::Priority;
+use super::{Graph, GraphEvent};
+use crate::MutateError;
+
+/// Cadence a node runs at.  See the [module docs](self).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Lane {
+    Audio,
+    #[default]
+    Frame,
+    Background,
+}
+
+impl Lane {
+    pub const ALL: [Lane; 3] = [Lane::Audio, Lane::Frame, Lane::Background];
+
+    pub(super) fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Pool priority for jobs submitted by nodes of this lane.
+    pub fn priority(self) -> Priority {
+        match self {
+            Lane::Audio => Priority::Audio,
+            Lane::Frame => Priority::Frame,
+            Lane::Background => Priority::Background,
+        }
+    }
+}
+
+/// Latest event crossing from one lane to another.  Publishing writes the back slot and then flips
+/// it to the front, so a reader only ever holds the front slot and never sees a half-written
+/// event.  Publishing only waits on a reader still cloning an event two publishes old.
+#[derive(Clone, Default)]
+pub struct GraphBuffer {
+    shared: Arc<Shared>,
+}
+
+#[derive(Default)]
+struct Shared {
+    slots: [Mutex<Option<GraphEvent>>; 2],
+    front: AtomicUsize,
+    published: AtomicU64,
+}
+
+impl GraphBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the latest event.  Only one thread may publish to a buffer.
+    pub fn publish(&self, event: GraphEvent) {
+        let back = 1 - self.shared.front.load(Ordering::Acquire);
+        *self.shared.slots[back]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(event);
+        self.shared.front.store(back, Ordering::Release);
+        self.shared.published.fetch_add(1, Ordering::Release);
+    }
+
+    /// The latest event, or `None` before the first publish.
+    pub fn read(&self) -> Option<GraphEvent> {
+        let front = self.shared.front.load(Ordering::Acquire);
+        self.shared.slots[front]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Events published so far.  Readers compare it to tell a new event from one already seen.
+    pub fn published(&self) -> u64 {
+        self.shared.published.load(Ordering::Acquire)
+    }
+}
+
+/// A graph split by [`Graph::into_lanes`].
+pub struct Lanes {
+    pub audio: Graph,
+    pub frame: Graph,
+    pub background: Graph,
+}
+
+impl Graph {
+    /// Split into one graph per lane.  Wire every node and edge first.  The split graphs can no
+    /// longer connect to nodes of other lanes.
+    pub fn into_lanes(mut self) -> Result<Lanes, MutateError> {
+        self.order()?;
+        let mut split = |lane: Lane| {
+            let mut graph = Graph {
+                nodes: Vec::with_capacity(self.nodes.len()),
+                edges: self.edges.clone(),
+                order: self.order.clone(),
+                ticks: self.ticks,
+                config: self.config.clone(),
+                changed: self.changed.clone(),
+                pool: self.pool.clone(),
+            };
+            for entry in &mut self.nodes {
+                let runner = match entry.lane == lane {
+                    true => entry.runner.take(),
+                    false => None,
+                };
+                graph.nodes.push(super::NodeEntry {
+                    name: entry.name.clone(),
+                    params: entry.params.clone(),
+                    lane: entry.lane,
+                    runner,
+                    outputs: entry.outputs.clone(),
+                    previous: entry.previous.clone(),
+                });
+            }
+            graph
+        };
+        Ok(Lanes {
+            audio: split(Lane::Audio),
+            frame: split(Lane::Frame),
+            background: split(Lane::Background),
+        })
+    }
+}
+
+/// Runs a graph on its own thread, at most once per period, until stopped.  Meant for the
+/// background lane from [`Graph::into_lanes`].
+pub struct LaneThread {
+    graph: Arc<Mutex<Graph>>,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Result<(), MutateError>>,
+}
+
+impl LaneThread {
+    pub fn spawn(graph: Graph, period: Duration) -> Result<Self, MutateError> {
+        let graph = Arc::new(Mutex::new(graph));
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = std::thread::Builder::new()
+            .name("mutate-lane".into())
+            .spawn({
+                let graph = graph.clone();
+                let stop = stop.clone();
+                move || {
+                    while !stop.load(Ordering::Acquire) {
+                        let start = Instant::now();
+                        graph.lock()?.run_frame()?;
+                        std::thread::sleep(period.saturating_sub(start.elapsed()));
+                    }
+                    Ok(())
+                }
+            })
+            .map_err(|_| MutateError::InvalidPool("lane thread failed to spawn".into()))?;
+        Ok(Self {
+            graph,
+            stop,
+            handle,
+        })
+    }
+
+    /// Borrow the graph between ticks, such as to configure it or read outputs.
+    pub fn with<T>(&self, f: impl FnOnce(&mut Graph) -> T) -> Result<T, MutateError> {
+        Ok(f(&mut *self.graph.lock()?))
+    }
+
+    /// Whether the thread stopped on its own after a node failed.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Stop after the current tick and hand the graph back, or the error that stopped it.
+    pub fn stop(self) -> Result<Graph, MutateError> {
+        self.stop.store(true, Ordering::Release);
+        self.handle.join().map_err(|_| MutateError::JobPanicked)??;
+        Arc::try_unwrap(self.graph)
+            .map_err(|_| MutateError::Poison)?
+            .into_inner()
+            .map_err(MutateError::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::{Frame, Node, NodeId, ParamHandle, ParamSpec, Params, PortKind, PortSpec};
+
+    /// Emits its tick count on `lane`.
+    struct Ticker(Lane);
+
+    /// Emits its input, or -1 when there is none.
+    struct Probe(Lane);
+
+    impl Params for Ticker {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[]
+        }
+    }
+
+    impl Node for Ticker {
+        fn outputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "tick",
+                kind: PortKind::Scalar,
+            }]
+        }
+
+        fn lane(&self) -> Lane {
+            self.0
+        }
+
+        fn attach(&mut self, _params: ParamHandle) {}
+
+        fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+            frame.emit(0, GraphEvent::Scalar(frame.number() as f64));
+            Ok(())
+        }
+    }
+
+    impl Params for Probe {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[]
+        }
+    }
+
+    impl Node for Probe {
+        fn inputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "in",
+                kind: PortKind::Scalar,
+            }]
+        }
+
+        fn outputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "out",
+                kind: PortKind::Scalar,
+            }]
+        }
+
+        fn lane(&self) -> Lane {
+            self.0
+        }
+
+        fn attach(&mut self, _params: ParamHandle) {}
+
+        fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+            let value = match frame.input(0) {
+                Some(GraphEvent::Scalar(x)) => *x,
+                _ => -1.0,
+            };
+            frame.emit(0, GraphEvent::Scalar(value));
+            Ok(())
+        }
+    }
+
+    fn scalar(graph: &Graph, id: NodeId, port: &str) -> Option<f64> {
+        match graph.output(id, port)? {
+            GraphEvent::Scalar(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_buffer_keeps_latest() {
+        let buffer = GraphBuffer::new();
+        assert_eq!(buffer.read(), None);
+        for x in 0..5 {
+            buffer.publish(GraphEvent::Scalar(x as f64));
+        }
+        assert_eq!(buffer.read(), Some(GraphEvent::Scalar(4.0)));
+        assert_eq!(buffer.read(), Some(GraphEvent::Scalar(4.0)));
+        assert_eq!(buffer.published(), 5);
+    }
+
+    #[test]
+    fn test_lanes_tick_separately() {
+        let mut graph = Graph::new();
+        let audio = graph.add("audio", Ticker(Lane::Audio)).unwrap();
+        let probe = graph.add("probe", Probe(Lane::Frame)).unwrap();
+        graph.connect(audio, "tick", probe, "in").unwrap();
+
+        // Frame lane before audio ever ran sees nothing.
+        graph.run_lane(Lane::Frame).unwrap();
+        assert_eq!(scalar(&graph, probe, "out"), Some(-1.0));
+
+        // Four audio chunks per video frame.  The frame lane sees the last.
+        for _ in 0..4 {
+            graph.run_lane(Lane::Audio).unwrap();
+        }
+        graph.run_lane(Lane::Frame).unwrap();
+        assert_eq!(scalar(&graph, probe, "out"), Some(3.0));
+
+        // A whole frame runs both lanes in order.
+        graph.run_frame().unwrap();
+        assert_eq!(scalar(&graph, probe, "out"), Some(4.0));
+    }
+
+    #[test]
+    fn test_background_thread() {
+        let mut graph = Graph::new();
+        let slow = graph.add("slow", Ticker(Lane::Background)).unwrap();
+        let probe = graph.add("probe", Probe(Lane::Frame)).unwrap();
+        graph.connect(slow, "tick", probe, "in").unwrap();
+        let Lanes {
+            mut frame,
+            background,
+            ..
+        } = graph.into_lanes().unwrap();
+        assert!(frame.node("slow").is_some());
+
+        let thread = LaneThread::spawn(background, Duration::from_millis(1)).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut seen = -1.0;
+        while seen < 2.0 && Instant::now() < deadline {
+            frame.run_frame().unwrap();
+            seen = scalar(&frame, probe, "out").unwrap();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(seen >= 2.0);
+        // The frame lane never runs the background node itself.
+        assert_eq!(scalar(&frame, slow, "tick"), None);
+
+        let background = thread.stop().unwrap();
+        assert!(scalar(&background, slow, "tick").unwrap() >= 2.0);
+        assert_eq!(scalar(&background, probe, "out"), None);
+    }
+}
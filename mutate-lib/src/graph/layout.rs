@@ -0,0 +1,381 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Layout
+//!
+//! Several render nodes can share one window, each drawing into its own tile of a grid, such as a
+//! waterfall across the top with a scope and bars underneath.  The [`Compositor`] owns the
+//! [`Layout`], turns it into pixel rectangles whenever the window extent changes, and routes input
+//! focus so that parameter tweaks land on the tile under the pointer.
+//!
+//! Layouts come from the graph TOML:
+//!
+//! ```toml
+//! [layout]
+//! rows = 2
+//! cols = 2
+//! gap = 4
+//!
+//! [[layout.tile]]
+//! node = "waterfall"
+//! col_span = 2
+//!
+//! [[layout.tile]]
+//! node = "scope"
+//! row = 1
+//!
+//! [[layout.tile]]
+//! node = "bars"
+//! row = 1
+//! col = 1
+//! ```
+//!
+//! Cell edges are rounded down to whole pixels, so cells differ by at most one pixel and always
+//! cover the window exactly.
+//!
+//! Render nodes draw into a tile by taking its [`Rect::scissor`] as their render area and setting
+//! the viewport to match.
+
+use crate::MutateError;
+
+/// A pixel rectangle within the window.  Convertible to a Vulkan viewport or scissor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x as f64
+            && y >= self.y as f64
+            && x < (self.x + self.width) as f64
+            && y < (self.y + self.height) as f64
+    }
+
+    pub fn scissor(&self) -> ash::vk::Rect2D {
+        ash::vk::Rect2D {
+            offset: ash::vk::Offset2D {
+                x: self.x as i32,
+                y: self.y as i32,
+            },
+            extent: ash::vk::Extent2D {
+                width: self.width,
+                height: self.height,
+            },
+        }
+    }
+
+    pub fn viewport(&self) -> ash::vk::Viewport {
+        ash::vk::Viewport {
+            x: self.x as f32,
+            y: self.y as f32,
+            width: self.width as f32,
+            height: self.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }
+    }
+}
+
+/// One child node and the grid cells it covers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tile {
+    pub node: String,
+    pub row: u32,
+    pub col: u32,
+    pub row_span: u32,
+    pub col_span: u32,
+}
+
+/// A grid of tiles.  Cells not covered by any tile are left undrawn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    pub rows: u32,
+    pub cols: u32,
+    /// Pixels between adjacent tiles.
+    pub gap: u32,
+    pub tiles: Vec<Tile>,
+}
+
+impl Layout {
+    /// A single node covering the whole window.
+    pub fn single(node: &str) -> Self {
+        Self::grid(1, 1, &[node])
+    }
+
+    /// Fill a `rows` by `cols` grid in reading order, one cell per node.
+    pub fn grid(rows: u32, cols: u32, nodes: &[&str]) -> Self {
+        let tiles = nodes
+            .iter()
+            .take((rows * cols) as usize)
+            .enumerate()
+            .map(|(i, node)| Tile {
+                node: node.to_string(),
+                row: i as u32 / cols,
+                col: i as u32 % cols,
+                row_span: 1,
+                col_span: 1,
+            })
+            .collect();
+        Self {
+            rows,
+            cols,
+            gap: 0,
+            tiles,
+        }
+    }
+
+    /// Read the `[layout]` table of a graph file.
+    pub fn parse(table: &toml::Table) -> Result<Self, MutateError> {
+        let bad = |msg: String| MutateError::InvalidLayout(msg);
+        let uint = |t: &toml::Table, key: &str, default: u32| match t.get(key) {
+            None => Ok(default),
+            Some(v) => v
+                .as_integer()
+                .and_then(|i| u32::try_from(i).ok())
+                .ok_or_else(|| bad(format!("`{key}` must be a non-negative integer"))),
+        };
+        let rows = uint(table, "rows", 1)?;
+        let cols = uint(table, "cols", 1)?;
+        let gap = uint(table, "gap", 0)?;
+
+        let mut tiles = Vec::new();
+        if let Some(entries) = table.get("tile") {
+            let entries = entries
+                .as_array()
+                .ok_or_else(|| bad("`tile` must be an array of tables".into()))?;
+            for entry in entries {
+                let t = entry
+                    .as_table()
+                    .ok_or_else(|| bad("`tile` entries must be tables".into()))?;
+                let node = t
+                    .get("node")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| bad("tile needs a `node`".into()))?;
+                tiles.push(Tile {
+                    node: node.to_owned(),
+                    row: uint(t, "row", 0)?,
+                    col: uint(t, "col", 0)?,
+                    row_span: uint(t, "row_span", 1)?,
+                    col_span: uint(t, "col_span", 1)?,
+                });
+            }
+        }
+
+        let layout = Self {
+            rows,
+            cols,
+            gap,
+            tiles,
+        };
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    /// Tiles must have a size, lie within the grid, and not overlap.
+    pub fn validate(&self) -> Result<(), MutateError> {
+        let bad = |msg: String| MutateError::InvalidLayout(msg);
+        if self.rows == 0 || self.cols == 0 {
+            return Err(bad("grid must have at least one row and column".into()));
+        }
+        let mut covered = vec![false; (self.rows * self.cols) as usize];
+        for t in &self.tiles {
+            if t.row_span == 0
+                || t.col_span == 0
+                || t.row + t.row_span > self.rows
+                || t.col + t.col_span > self.cols
+            {
+                return Err(bad(format!("tile `{}` does not fit the grid", t.node)));
+            }
+            for r in t.row..t.row + t.row_span {
+                for c in t.col..t.col + t.col_span {
+                    let cell = &mut covered[(r * self.cols + c) as usize];
+                    if *cell {
+                        return Err(bad(format!("tile `{}` overlaps another tile", t.node)));
+                    }
+                    *cell = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Pixel rectangle of every tile, in tile order, for a window of `width` by `height`.
+    pub fn rects(&self, width: u32, height: u32) -> Vec<Rect> {
+        // Cell edges before the gaps are taken out.
+        let edge = |i: u32, n: u32, size: u32| (size as u64 * i as u64 / n as u64) as u32;
+        self.tiles
+            .iter()
+            .map(|t| {
+                let x0 = edge(t.col, self.cols, width);
+                let x1 = edge(t.col + t.col_span, self.cols, width);
+                let y0 = edge(t.row, self.rows, height);
+                let y1 = edge(t.row + t.row_span, self.rows, height);
+                // Each interior edge gives half the gap to either side.
+                let inset = |start: u32, end: u32, first: bool, last: bool| {
+                    let lead = if first { 0 } else { self.gap / 2 };
+                    let trail = if last { 0 } else { self.gap - self.gap / 2 };
+                    let start = (start + lead).min(end);
+                    (start, end.saturating_sub(trail).max(start))
+                };
+                let (x0, x1) = inset(x0, x1, t.col == 0, t.col + t.col_span == self.cols);
+                let (y0, y1) = inset(y0, y1, t.row == 0, t.row + t.row_span == self.rows);
+                Rect {
+                    x: x0,
+                    y: y0,
+                    width: x1 - x0,
+                    height: y1 - y0,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Places child nodes into a window and tracks which one has input focus.
+pub struct Compositor {
+    layout: Layout,
+    rects: Vec<Rect>,
+    extent: (u32, u32),
+    focused: Option<usize>,
+}
+
+impl Compositor {
+    pub fn new(layout: Layout) -> Result<Self, MutateError> {
+        layout.validate()?;
+        let focused = (!layout.tiles.is_empty()).then_some(0);
+        Ok(Self {
+            layout,
+            rects: Vec::new(),
+            extent: (0, 0),
+            focused,
+        })
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Recompute tile rectangles.  Call on every swapchain resize.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.extent = (width, height);
+        self.rects = self.layout.rects(width, height);
+    }
+
+    /// Each child node name with the rectangle to draw it into, in tile order.
+    pub fn tiles(&self) -> impl Iterator<Item = (&str, Rect)> {
+        self.layout
+            .tiles
+            .iter()
+            .zip(&self.rects)
+            .map(|(t, r)| (t.node.as_str(), *r))
+    }
+
+    /// Index of the tile under a window position.
+    pub fn tile_at(&self, x: f64, y: f64) -> Option<usize> {
+        self.rects.iter().position(|r| r.contains(x, y))
+    }
+
+    /// Focus the tile under the pointer.  Positions in gaps keep the current focus.
+    pub fn focus_at(&mut self, x: f64, y: f64) -> Option<&str> {
+        if let Some(i) = self.tile_at(x, y) {
+            self.focused = Some(i);
+        }
+        self.focused_node()
+    }
+
+    /// Move focus to the next tile, wrapping around.
+    pub fn focus_next(&mut self) -> Option<&str> {
+        let n = self.layout.tiles.len();
+        self.focused = self.focused.map(|i| (i + 1) % n);
+        self.focused_node()
+    }
+
+    pub fn focused_node(&self) -> Option<&str> {
+        self.focused.map(|i| self.layout.tiles[i].node.as_str())
+    }
+
+    /// The graph path of `param` on the focused node, for routing parameter tweaks.
+    pub fn focused_path(&self, param: &str) -> Option<String> {
+        self.focused_node().map(|node| format!("{node}/{param}"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const LAYOUT: &str = r#"
+        rows = 2
+        cols = 2
+        gap = 4
+
+        [[tile]]
+        node = "waterfall"
+        col_span = 2
+
+        [[tile]]
+        node = "scope"
+        row = 1
+
+        [[tile]]
+        node = "bars"
+        row = 1
+        col = 1
+    "#;
+
+    #[test]
+    fn test_layout_rects() {
+        let layout = Layout::parse(&LAYOUT.parse().unwrap()).unwrap();
+        let rects = layout.rects(1001, 600);
+        assert_eq!(
+            rects[0],
+            Rect {
+                x: 0,
+                y: 0,
+                width: 1001,
+                height: 298
+            }
+        );
+        assert_eq!(
+            rects[1],
+            Rect {
+                x: 0,
+                y: 302,
+                width: 498,
+                height: 298
+            }
+        );
+        assert_eq!(
+            rects[2],
+            Rect {
+                x: 502,
+                y: 302,
+                width: 499,
+                height: 298
+            }
+        );
+    }
+
+    #[test]
+    fn test_layout_invalid() {
+        let overlap = "rows = 1\ncols = 2\n[[tile]]\nnode = \"a\"\ncol_span = 2\n\
+                       [[tile]]\nnode = \"b\"\ncol = 1";
+        assert!(Layout::parse(&overlap.parse().unwrap()).is_err());
+        let outside = "rows = 1\ncols = 1\n[[tile]]\nnode = \"a\"\nrow = 1";
+        assert!(Layout::parse(&outside.parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_compositor_focus() {
+        let mut c = Compositor::new(Layout::grid(2, 2, &["a", "b", "c"])).unwrap();
+        c.resize(200, 100);
+        assert_eq!(c.focused_node(), Some("a"));
+        assert_eq!(c.focus_at(150.0, 75.0), Some("a"));
+        assert_eq!(c.focus_at(50.0, 75.0), Some("c"));
+        assert_eq!(c.focus_next(), Some("a"));
+        assert_eq!(c.focused_path("gain").as_deref(), Some("a/gain"));
+    }
+}
@@ -0,0 +1,284 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Graph
+//!
+//! Audio analysis and drawing are arranged as nodes.  The graph is where nodes are registered so
+//! that generic tooling can find them.  A settings overlay, remote control, or preset file should
+//! never need to know the concrete type of a node in order to change how it behaves.
+//!
+//! ## Parameters
+//!
+//! Nodes that implement [`Params`] are registered with [`Graph::register`], which returns the
+//! [`ParamHandle`] the node reads from.  Everything else addresses parameters by path,
+//! `<node>/<param>`, through [`Graph::set`], [`Graph::get`], and [`Graph::params`].
+//!
+//! ## Scheduling
+//!
+//! Nodes that implement [`Node`] are owned by the graph once added with [`Graph::add`].  Their
+//! typed ports are connected with [`Graph::connect`], and [`Graph::run_frame`] runs every node once
+//! in dependency order, handing [`GraphEvent`]s along edges.  Cycles are only allowed through
+//! [`Graph::feedback`] edges, which deliver the previous frame's event.  See [`schedule`].
+//! `Samples` edges can also keep a [`SampleWindow`] of recent history for their consumer.  See
+//! [`window`].
+//!
+//! ## Presets
+//!
+//! Which nodes run, how they connect, and their parameters can be described in the graph TOML and
+//! built by name with [`preset::Preset`].
+//!
+//! ## Lanes
+//!
+//! Nodes run at the cadence of their [`Lane`]: per audio chunk, per video frame, or in the
+//! background.  Edges between lanes hand events over through a [`GraphBuffer`], and
+//! [`Graph::into_lanes`] splits a wired graph so each lane can run on its own thread.  See [`lane`].
+//!
+//! ## Configuration
+//!
+//! Nodes are provisioned against configuration such as resolution, audio source, and device.
+//! [`Graph::configure`] writes a key, and nodes that [`watch`](Node::watches) it are
+//! [updated](Node::update) in run order before the next frame.  See [`config`].
+//!
+//! ## Actions
+//!
+//! Named user commands such as `pause` reach nodes that list them in [`Node::actions`] through
+//! [`Graph::act`].  See [`action`].
+//!
+//! ## Timing
+//!
+//! Each frame has an audio deadline, a submit deadline, and a present target, computed by
+//! [`FrameTiming`] and handed to nodes through [`Graph::run_frame_at`].  Hosts sleep until just
+//! before the audio deadline so that frames draw the freshest audio.  With the `vulkan` feature,
+//! `GraphContext` owns the device, per-frame command pools, and a [`DeletionQueue`] so nodes can
+//! provision GPU resources without outliving the frames that use them.  See [`context`].  Frames
+//! run with `Graph::run_frame_in` give nodes per-frame scratch memory from its transient pool.  It
+//! also keeps `FrameStats` on recent frames: host time, audio to photon latency, dropped frames,
+//! and the scratch high water mark.
+//!
+//! ## Work
+//!
+//! CPU-heavy nodes submit jobs to a shared [`pool::WorkerPool`] by priority rather than running on
+//! the audio or render threads.  Hosts hand the pool to the graph with [`Graph::set_pool`].
+
+// NEXT resource ownership.  Buffers and images on edges are borrowed from their producer for one
+// frame.  Nodes share a `WorkerPool` but own their own jobs.
+
+pub mod action;
+pub mod config;
+pub mod context;
+pub mod lane;
+pub mod layout;
+pub mod param;
+pub mod pool;
+pub mod preset;
+pub mod schedule;
+#[cfg(feature = "vulkan")]
+pub mod stats;
+pub mod throttle;
+pub mod window;
+
+pub use config::{Config, ConfigValue};
+#[cfg(feature = "vulkan")]
+pub use context::GraphContext;
+pub use context::{DeletionQueue, FramePhases, FrameTiming, Refresh};
+pub use lane::{GraphBuffer, Lane};
+pub use param::{ParamHandle, ParamKind, ParamSpec, ParamValue, Params};
+pub use pool::{Offload, Priority, WorkerPool};
+pub use schedule::{Frame, GraphEvent, Node, PortKind, PortSpec};
+#[cfg(feature = "vulkan")]
+pub use stats::FrameStats;
+pub use window::{SampleWindow, Windowing};
+
+pub mod prelude {
+    pub use super::{
+        Config, ConfigValue, Frame, FramePhases, FrameTiming, Graph, GraphEvent, Lane, Node,
+        NodeId, ParamHandle, ParamKind, ParamSpec, ParamValue, Params, PortKind, PortSpec, Refresh,
+    };
+    #[cfg(feature = "vulkan")]
+    pub use super::{FrameStats, GraphContext};
+}
+
+use std::sync::Arc;
+
+use crate::MutateError;
+
+/// Index of a node within its [`Graph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(pub(crate) usize);
+
+struct NodeEntry {
+    name: String,
+    params: ParamHandle,
+    lane: Lane,
+    /// Nodes added with [`Graph::add`] are run by the graph.  Registered nodes are not.
+    runner: Option<Box<dyn Node>>,
+    /// Latest event on each output port.
+    outputs: Vec<Option<GraphEvent>>,
+    /// Events of the frame before, for feedback edges.
+    previous: Vec<Option<GraphEvent>>,
+}
+
+/// One row of a parameter listing.
+pub struct ParamInfo<'a> {
+    pub node: &'a str,
+    pub spec: &'static ParamSpec,
+    pub value: ParamValue,
+}
+
+impl ParamInfo<'_> {
+    /// The `<node>/<param>` path used to address this parameter.
+    pub fn path(&self) -> String {
+        format!("{}/{}", self.node, self.spec.name)
+    }
+}
+
+#[derive(Default)]
+pub struct Graph {
+    nodes: Vec<NodeEntry>,
+    edges: Vec<schedule::Edge>,
+    /// Execution order, recomputed after nodes or edges change.
+    order: Option<Vec<usize>>,
+    /// Runs of each lane, indexed by [`Lane`].
+    ticks: [u64; 3],
+    config: Config,
+    /// Keys written since the last [`reconfigure`](Graph::reconfigure).
+    changed: Vec<&'static str>,
+    /// Handed to nodes through [`Frame::pool`].
+    pool: Option<Arc<WorkerPool>>,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a node under `name`.  The returned handle shares storage with the graph, so writes
+    /// through the graph are visible to the node on its next read.  Names must be unique and must
+    /// not contain `/`.
+    pub fn register(
+        &mut self,
+        name: &str,
+        node: &(impl Params + ?Sized),
+    ) -> Result<(NodeId, ParamHandle), MutateError> {
+        if name.contains('/') || self.nodes.iter().any(|n| n.name == name) {
+            return Err(MutateError::InvalidNode(name.to_owned()));
+        }
+        let params = ParamHandle::new(node.param_specs());
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(NodeEntry {
+            name: name.to_owned(),
+            params: params.clone(),
+            lane: Lane::default(),
+            runner: None,
+            outputs: Vec::new(),
+            previous: Vec::new(),
+        });
+        self.order = None;
+        Ok((id, params))
+    }
+
+    /// Look up a node by name.
+    pub fn node(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|n| n.name == name).map(NodeId)
+    }
+
+    /// Name of a registered node.
+    pub fn node_name(&self, id: NodeId) -> &str {
+        &self.nodes[id.0].name
+    }
+
+    /// Run the heavy work of nodes on `pool` from the next frame on.
+    pub fn set_pool(&mut self, pool: Arc<WorkerPool>) {
+        self.pool = Some(pool);
+    }
+
+    pub fn pool(&self) -> Option<&Arc<WorkerPool>> {
+        self.pool.as_ref()
+    }
+
+    /// Every parameter of every node, in registration order.
+    pub fn params(&self) -> impl Iterator<Item = ParamInfo<'_>> {
+        self.nodes.iter().flat_map(|n| {
+            n.params
+                .specs()
+                .iter()
+                .enumerate()
+                .map(move |(i, spec)| ParamInfo {
+                    node: &n.name,
+                    spec,
+                    value: n.params.get(i),
+                })
+        })
+    }
+
+    /// Resolve a `<node>/<param>` path.
+    pub fn resolve(&self, path: &str) -> Result<(&ParamHandle, usize), MutateError> {
+        let unknown = || MutateError::UnknownParam(path.to_owned());
+        let (node, param) = path.split_once('/').ok_or_else(unknown)?;
+        let entry = self
+            .nodes
+            .iter()
+            .find(|n| n.name == node)
+            .ok_or_else(unknown)?;
+        let index = entry.params.index_of(param).ok_or_else(unknown)?;
+        Ok((&entry.params, index))
+    }
+
+    pub fn get(&self, path: &str) -> Result<ParamValue, MutateError> {
+        let (handle, index) = self.resolve(path)?;
+        Ok(handle.get(index))
+    }
+
+    /// Validate and write a parameter.  Returns the value actually stored after clamping.
+    pub fn set(&self, path: &str, value: ParamValue) -> Result<ParamValue, MutateError> {
+        let (handle, index) = self.resolve(path)?;
+        handle.set(index, value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Scope;
+
+    impl Params for Scope {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[
+                ParamSpec {
+                    name: "gain",
+                    description: "vertical gain",
+                    kind: ParamKind::Float {
+                        min: 0.0,
+                        max: 10.0,
+                    },
+                    default: ParamValue::Float(1.0),
+                },
+                ParamSpec {
+                    name: "trigger",
+                    description: "lock to rising zero crossings",
+                    kind: ParamKind::Bool,
+                    default: ParamValue::Bool(true),
+                },
+            ]
+        }
+    }
+
+    #[test]
+    fn test_graph_params_by_path() {
+        let mut graph = Graph::new();
+        let (id, handle) = graph.register("scope", &Scope).unwrap();
+        assert_eq!(graph.node("scope"), Some(id));
+        assert!(graph.register("scope", &Scope).is_err());
+        assert!(graph.register("a/b", &Scope).is_err());
+
+        let stored = graph.set("scope/gain", ParamValue::Float(12.0)).unwrap();
+        assert_eq!(stored, ParamValue::Float(10.0));
+        assert_eq!(handle.f64(0), 10.0);
+        assert!(graph.set("scope/nope", ParamValue::Bool(true)).is_err());
+        assert!(graph.get("nope").is_err());
+
+        let paths: Vec<String> = graph.params().map(|p| p.path()).collect();
+        assert_eq!(paths, ["scope/gain", "scope/trigger"]);
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Parameters
+//!
+//! Nodes describe their tunable values with a static list of [`ParamSpec`].  The graph creates one
+//! [`UntornCell`] per parameter and hands a [`ParamHandle`] back to the node.  The node reads its
+//! current values once per frame.  Anything else holding the graph, a settings overlay, OSC input,
+//! or the preset loader, can list and write the same cells by path without knowing the node type.
+//!
+//! Writes are validated against the spec.  Out of range numbers are clamped rather than rejected
+//! because controllers routinely overshoot.  Wrong types are rejected.
+
+// MAYBE the cells are written from arbitrary threads, so `UntornCell` is used instead of the split
+// writer.  If only the graph thread ever writes, the split flavor is a little faster.
+
+use mutate_untorn::UntornCell;
+
+use crate::MutateError;
+
+/// The type and legal range of a parameter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamKind {
+    Float {
+        min: f64,
+        max: f64,
+    },
+    Int {
+        min: i64,
+        max: i64,
+    },
+    Bool,
+    /// One of several named options.  Values are indexes into the list.
+    Choice(&'static [&'static str]),
+}
+
+/// A parameter value.  Must match the [`ParamKind`] of its spec.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamValue {
+    Float(f64),
+    Int(i64),
+    Bool(bool),
+    Choice(usize),
+}
+
+/// Everything a generic UI needs to draw and validate one parameter.
+#[derive(Clone, Copy, Debug)]
+pub struct ParamSpec {
+    /// Short identifier, unique within its node.  Used in paths such as `ring/gain`.
+    pub name: &'static str,
+    /// One line for tooltips and help output.
+    pub description: &'static str,
+    pub kind: ParamKind,
+    pub default: ParamValue,
+}
+
+/// Implemented by nodes that expose parameters.  The list must be the same every time it is called
+/// because indexes into it are used as handles.
+pub trait Params {
+    fn param_specs(&self) -> &'static [ParamSpec];
+}
+
+impl ParamSpec {
+    /// Coerce `value` into this parameter's range.  Numbers are clamped.  Mismatched types are an
+    /// error.
+    pub fn validate(&self, value: ParamValue) -> Result<ParamValue, MutateError> {
+        let invalid = |reason: &'static str| MutateError::InvalidParam {
+            name: self.name,
+            reason,
+        };
+        match (self.kind, value) {
+            (ParamKind::Float { min, max }, ParamValue::Float(v)) => {
+                if v.is_nan() {
+                    return Err(invalid("NaN"));
+                }
+                Ok(ParamValue::Float(v.clamp(min, max)))
+            }
+            // Controllers and text input commonly send integers for float parameters.
+            (ParamKind::Float { min, max }, ParamValue::Int(v)) => {
+                Ok(ParamValue::Float((v as f64).clamp(min, max)))
+            }
+            (ParamKind::Int { min, max }, ParamValue::Int(v)) => {
+                Ok(ParamValue::Int(v.clamp(min, max)))
+            }
+            (ParamKind::Bool, ParamValue::Bool(b)) => Ok(ParamValue::Bool(b)),
+            (ParamKind::Choice(options), ParamValue::Choice(i)) => {
+                if i < options.len() {
+                    Ok(ParamValue::Choice(i))
+                } else {
+                    Err(invalid("choice out of range"))
+                }
+            }
+            _ => Err(invalid("wrong type")),
+        }
+    }
+
+    /// Map a value onto `0.0..=1.0`, the shape most external controllers speak.
+    pub fn to_normalized(&self, value: ParamValue) -> f64 {
+        match (self.kind, value) {
+            (ParamKind::Float { min, max }, ParamValue::Float(v)) => {
+                ((v - min) / (max - min)).clamp(0.0, 1.0)
+            }
+            (ParamKind::Int { min, max }, ParamValue::Int(v)) => {
+                ((v - min) as f64 / (max - min) as f64).clamp(0.0, 1.0)
+            }
+            (ParamKind::Bool, ParamValue::Bool(b)) => b as u8 as f64,
+            (ParamKind::Choice(options), ParamValue::Choice(i)) if options.len() > 1 => {
+                i as f64 / (options.len() - 1) as f64
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Inverse of [`to_normalized`](Self::to_normalized).  Integers and choices round to the
+    /// nearest step.  Booleans switch at one half.
+    pub fn from_normalized(&self, x: f64) -> ParamValue {
+        let x = x.clamp(0.0, 1.0);
+        match self.kind {
+            ParamKind::Float { min, max } => ParamValue::Float(min + x * (max - min)),
+            ParamKind::Int { min, max } => {
+                ParamValue::Int(min + (x * (max - min) as f64).round() as i64)
+            }
+            ParamKind::Bool => ParamValue::Bool(x >= 0.5),
+            ParamKind::Choice(options) => {
+                let last = options.len().saturating_sub(1);
+                ParamValue::Choice((x * last as f64).round() as usize)
+            }
+        }
+    }
+}
+
+impl ParamValue {
+    pub fn as_f64(self) -> f64 {
+        match self {
+            ParamValue::Float(v) => v,
+            ParamValue::Int(v) => v as f64,
+            ParamValue::Bool(b) => b as u8 as f64,
+            ParamValue::Choice(i) => i as f64,
+        }
+    }
+
+    pub fn as_bool(self) -> bool {
+        match self {
+            ParamValue::Bool(b) => b,
+            other => other.as_f64() != 0.0,
+        }
+    }
+}
+
+impl std::fmt::Display for ParamValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamValue::Float(v) => write!(f, "{v}"),
+            ParamValue::Int(v) => write!(f, "{v}"),
+            ParamValue::Bool(b) => write!(f, "{b}"),
+            ParamValue::Choice(i) => write!(f, "#{i}"),
+        }
+    }
+}
+
+/// A node's view of its own parameter values.  Cheap to read every frame.
+#[derive(Clone)]
+pub struct ParamHandle {
+    pub(crate) specs: &'static [ParamSpec],
+    pub(crate) cells: Vec<UntornCell<ParamValue>>,
+}
+
+impl ParamHandle {
+    pub(crate) fn new(specs: &'static [ParamSpec]) -> Self {
+        let cells = specs.iter().map(|s| UntornCell::new(s.default)).collect();
+        Self { specs, cells }
+    }
+
+    /// Current value of the parameter at `index` in the node's [`Params::param_specs`].
+    pub fn get(&self, index: usize) -> ParamValue {
+        self.cells[index].read()
+    }
+
+    /// Current value as `f64`.  Convenient for float parameters.
+    pub fn f64(&self, index: usize) -> f64 {
+        self.get(index).as_f64()
+    }
+
+    /// Validate and write the parameter at `index`.  Returns the value actually stored.
+    pub fn set(&self, index: usize, value: ParamValue) -> Result<ParamValue, MutateError> {
+        let value = self.specs[index].validate(value)?;
+        self.cells[index].write(value);
+        Ok(value)
+    }
+
+    /// Restore every parameter to its default.
+    pub fn reset(&self) {
+        for (cell, spec) in self.cells.iter().zip(self.specs) {
+            cell.write(spec.default);
+        }
+    }
+
+    pub fn specs(&self) -> &'static [ParamSpec] {
+        self.specs
+    }
+
+    /// Index of the parameter named `name`.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.specs.iter().position(|s| s.name == name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SPECS: &[ParamSpec] = &[
+        ParamSpec {
+            name: "gain",
+            description: "output gain",
+            kind: ParamKind::Float { min: 0.0, max: 4.0 },
+            default: ParamValue::Float(1.0),
+        },
+        ParamSpec {
+            name: "mode",
+            description: "drawing mode",
+            kind: ParamKind::Choice(&["lines", "dots", "bars"]),
+            default: ParamValue::Choice(0),
+        },
+    ];
+
+    #[test]
+    fn test_param_validate() {
+        let gain = &SPECS[0];
+        assert_eq!(
+            gain.validate(ParamValue::Float(9.0)).unwrap(),
+            ParamValue::Float(4.0)
+        );
+        assert_eq!(
+            gain.validate(ParamValue::Int(2)).unwrap(),
+            ParamValue::Float(2.0)
+        );
+        assert!(gain.validate(ParamValue::Bool(true)).is_err());
+        assert!(gain.validate(ParamValue::Float(f64::NAN)).is_err());
+        assert!(SPECS[1].validate(ParamValue::Choice(3)).is_err());
+    }
+
+    #[test]
+    fn test_param_normalized() {
+        let gain = &SPECS[0];
+        assert_eq!(gain.from_normalized(0.5), ParamValue::Float(2.0));
+        assert_eq!(gain.to_normalized(ParamValue::Float(1.0)), 0.25);
+        let mode = &SPECS[1];
+        assert_eq!(mode.from_normalized(0.74), ParamValue::Choice(1));
+        assert_eq!(mode.from_normalized(1.0), ParamValue::Choice(2));
+        assert_eq!(mode.to_normalized(ParamValue::Choice(2)), 1.0);
+    }
+
+    #[test]
+    fn test_param_handle_shared() {
+        let handle = ParamHandle::new(SPECS);
+        let remote = handle.clone();
+        assert_eq!(handle.f64(0), 1.0);
+        remote.set(0, ParamValue::Float(3.0)).unwrap();
+        assert_eq!(handle.f64(0), 3.0);
+        handle.reset();
+        assert_eq!(remote.f64(0), 1.0);
+        assert_eq!(handle.index_of("mode"), Some(1));
+    }
+}
@@ -0,0 +1,571 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Worker Pool
+//!
+//! CPU-heavy nodes, such as a CQT reference path or feature extraction for training, must not run
+//! on whichever thread happened to create them.  Audio import and frame recording are on deadlines
+//! and a long analysis job in their way causes dropouts and stutter.  Nodes submit work to a
+//! [`WorkerPool`] instead, tagged with a [`Priority`]:
+//!
+//! - [`Priority::Audio`] is work the audio path waits on.  Always dequeued first.
+//! - [`Priority::Frame`] must finish before the frame it feeds is recorded.
+//! - [`Priority::Background`] is everything that can be late, such as training.
+//!
+//! Workers always take the most urgent job queued.  Because a running job cannot be preempted,
+//! [`PoolConfig::reserve`] workers never start background work so that a burst of training jobs
+//! cannot occupy every worker right before a deadline.
+//!
+//! ## Nodes
+//!
+//! A graph given a pool with [`Graph::set_pool`](super::Graph::set_pool) hands it to its nodes
+//! through [`Frame::pool`](super::Frame::pool).  A node keeps the state its work needs in an
+//! [`Offload`] and submits each frame's work on it.  The work then overlaps the nodes after it and
+//! the recording of the frame, and the node collects it on its next run.  Without a pool, the work
+//! runs right away.
+//!
+//! ## Configuration
+//!
+//! ```toml
+//! [pool]
+//! workers = 4
+//! reserve = 1
+//! # Optional.  Worker `i` is pinned to `pin[i]`.
+//! pin = [2, 3, 4, 5]
+//! ```
+//!
+//! ## Instrumentation
+//!
+//! Time spent queued is the measure of whether the pool is big enough.  [`WorkerPool::stats`]
+//! reports wait times per priority.
+
+// NEXT the profiler overlay shows `stats` next to frame times once it exists.
+// NEXT OS thread priorities.  Workers are shared between classes, so only dedicated workers could
+// carry a real-time priority.
+// MAYBE work stealing per worker if the single queue lock ever shows up in the wait times.
+
+use std::collections::VecDeque;
+use std::panic::AssertUnwindSafe;
+use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::MutateError;
+
+/// Urgency of a job, most urgent first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Audio,
+    Frame,
+    Background,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::Audio, Priority::Frame, Priority::Background];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Priority::Audio => "audio",
+            Priority::Frame => "frame",
+            Priority::Background => "background",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolConfig {
+    pub workers: usize,
+    /// Workers that never start background jobs.
+    pub reserve: usize,
+    /// Core for each worker, by worker index.
+    pub pin: Option<Vec<usize>>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        let cores = std::thread::available_parallelism().map_or(2, |n| n.get());
+        // Leave a core for the audio and render threads.
+        let workers = cores.saturating_sub(1).max(1);
+        Self {
+            workers,
+            // A lone worker must take background work too.
+            reserve: workers.min(2) - 1,
+            pin: None,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Read the `[pool]` table of a config file.  Missing keys keep their defaults.
+    pub fn parse(table: &toml::Table) -> Result<Self, MutateError> {
+        let mut config = Self::default();
+        config.apply(table)?;
+        Ok(config)
+    }
+
+    /// Layer a `[pool]` table over this config.  Nothing changes unless the result is valid.
+    pub fn apply(&mut self, table: &toml::Table) -> Result<(), MutateError> {
+        let bad = |msg: String| MutateError::InvalidPool(msg);
+        let uint = |v: &toml::Value, key: &str| {
+            v.as_integer()
+                .and_then(|i| usize::try_from(i).ok())
+                .ok_or_else(|| bad(format!("`{key}` must be a non-negative integer")))
+        };
+        let mut config = self.clone();
+        for (key, v) in table {
+            match key.as_str() {
+                "workers" => config.workers = uint(v, "workers")?,
+                "reserve" => config.reserve = uint(v, "reserve")?,
+                "pin" => {
+                    let cores = v
+                        .as_array()
+                        .ok_or_else(|| bad("`pin` must be an array of core indexes".into()))?;
+                    config.pin = Some(
+                        cores
+                            .iter()
+                            .map(|c| uint(c, "pin"))
+                            .collect::<Result<_, _>>()?,
+                    );
+                }
+                key => return Err(bad(format!("unknown setting `{key}`"))),
+            }
+        }
+        config.validate()?;
+        *self = config;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), MutateError> {
+        let bad = |msg: String| MutateError::InvalidPool(msg);
+        if self.workers == 0 {
+            return Err(bad("need at least one worker".into()));
+        }
+        if self.reserve >= self.workers {
+            return Err(bad(format!(
+                "reserving {} of {} workers leaves none for background work",
+                self.reserve, self.workers
+            )));
+        }
+        let pinned = self.pin.as_ref().map_or(self.workers, |pin| pin.len());
+        if pinned != self.workers {
+            return Err(bad(format!(
+                "`pin` has {pinned} cores for {} workers",
+                self.workers
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Queue wait times for one priority since the pool started.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WaitStats {
+    pub jobs: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl WaitStats {
+    pub fn mean(&self) -> Duration {
+        if self.jobs == 0 {
+            Duration::ZERO
+        } else {
+            self.total / self.jobs as u32
+        }
+    }
+
+    fn record(&mut self, wait: Duration) {
+        self.jobs += 1;
+        self.total += wait;
+        self.max = self.max.max(wait);
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Queued {
+    job: Job,
+    queued_at: Instant,
+}
+
+#[derive(Default)]
+struct Queues {
+    queues: [VecDeque<Queued>; 3],
+    /// Workers running a background job.
+    background: usize,
+    stats: [WaitStats; 3],
+    closed: bool,
+}
+
+struct Shared {
+    queues: Mutex<Queues>,
+    ready: Condvar,
+    /// Workers allowed to run background jobs at once.
+    background_limit: usize,
+}
+
+impl Shared {
+    /// Block until there is a job this worker may run, or the pool closes.
+    fn next(&self) -> Option<(Priority, Job)> {
+        let mut q = self.queues.lock().ok()?;
+        loop {
+            for priority in Priority::ALL {
+                if priority == Priority::Background && q.background >= self.background_limit {
+                    continue;
+                }
+                if let Some(queued) = q.queues[priority.index()].pop_front() {
+                    q.stats[priority.index()].record(queued.queued_at.elapsed());
+                    if priority == Priority::Background {
+                        q.background += 1;
+                    }
+                    return Some((priority, queued.job));
+                }
+            }
+            if q.closed {
+                return None;
+            }
+            q = self.ready.wait(q).ok()?;
+        }
+    }
+
+    fn finished(&self, priority: Priority) {
+        if priority == Priority::Background {
+            if let Ok(mut q) = self.queues.lock() {
+                q.background -= 1;
+            }
+            // A background slot opened up for whoever is waiting.
+            self.ready.notify_one();
+        }
+    }
+}
+
+/// The result of a submitted job.
+pub struct Ticket<T> {
+    rx: mpsc::Receiver<T>,
+}
+
+impl<T> Ticket<T> {
+    /// Block until the job finishes.
+    pub fn wait(self) -> Result<T, MutateError> {
+        self.rx.recv().map_err(|_| MutateError::JobPanicked)
+    }
+
+    /// The result if the job has finished.
+    pub fn try_wait(&self) -> Option<Result<T, MutateError>> {
+        match self.rx.try_recv() {
+            Ok(v) => Some(Ok(v)),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(MutateError::JobPanicked)),
+        }
+    }
+}
+
+/// Fixed set of worker threads.  Dropping the pool finishes every queued job and joins the workers.
+pub struct WorkerPool {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    pub fn new(config: &PoolConfig) -> Result<Self, MutateError> {
+        config.validate()?;
+        let shared = Arc::new(Shared {
+            queues: Mutex::new(Queues::default()),
+            ready: Condvar::new(),
+            background_limit: config.workers - config.reserve,
+        });
+        let workers = (0..config.workers)
+            .map(|i| {
+                let shared = shared.clone();
+                let core = config.pin.as_ref().map(|pin| pin[i]);
+                std::thread::Builder::new()
+                    .name(format!("µTate worker {i}"))
+                    .spawn(move || {
+                        if let Some(Err((core, e))) = core.map(|c| pin(c).map_err(|e| (c, e))) {
+                            eprintln!("worker {i} not pinned to core {core}: {e}");
+                        }
+                        while let Some((priority, job)) = shared.next() {
+                            // A panic drops the ticket's sender, which is how the submitter
+                            // learns of it.  The worker lives on.
+                            let _ = std::panic::catch_unwind(AssertUnwindSafe(job));
+                            shared.finished(priority);
+                        }
+                    })
+                    .map_err(|e| MutateError::InvalidPool(format!("spawning worker: {e}")))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { shared, workers })
+    }
+
+    /// Queue `job` behind every job of equal or higher priority.
+    pub fn submit<T, F>(&self, priority: Priority, job: F) -> Ticket<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (tx, rx) = mpsc::sync_channel(1);
+        let job: Job = Box::new(move || {
+            let _ = tx.send(job());
+        });
+        match self.shared.queues.lock() {
+            Ok(mut q) => q.queues[priority.index()].push_back(Queued {
+                job,
+                queued_at: Instant::now(),
+            }),
+            // The ticket reports the job as lost.
+            Err(_) => return Ticket { rx },
+        }
+        self.shared.ready.notify_one();
+        Ticket { rx }
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Jobs queued and not yet started, per priority.
+    pub fn queued(&self) -> [usize; 3] {
+        match self.shared.queues.lock() {
+            Ok(q) => q.queues.each_ref().map(|q| q.len()),
+            Err(_) => [0; 3],
+        }
+    }
+
+    /// Queue wait times per priority, indexed like [`Priority::ALL`].
+    pub fn stats(&self) -> [WaitStats; 3] {
+        self.shared
+            .queues
+            .lock()
+            .map(|q| q.stats)
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        if let Ok(mut q) = self.shared.queues.lock() {
+            q.closed = true;
+        }
+        self.shared.ready.notify_all();
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                eprintln!("pool worker panicked");
+            }
+        }
+    }
+}
+
+/// State a node works on in the pool.  See the [module](self) docs.
+pub struct Offload<T> {
+    state: Arc<Mutex<T>>,
+    /// The job working on `state`, if it has not been collected.
+    ticket: Option<Ticket<()>>,
+}
+
+impl<T: Send + 'static> Offload<T> {
+    pub fn new(state: T) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            ticket: None,
+        }
+    }
+
+    /// Whether no job is working on the state, collecting one that finished.  A job that panicked
+    /// is reported once and leaves the state where it stopped.
+    pub fn ready(&mut self) -> Result<bool, MutateError> {
+        let Some(ticket) = &self.ticket else {
+            return Ok(true);
+        };
+        let finished = ticket.try_wait();
+        if finished.is_some() {
+            self.ticket = None;
+        }
+        finished.map_or(Ok(false), |f| f.map(|()| true))
+    }
+
+    /// The state, after waiting for the job working on it.  Errors like [`ready`](Self::ready).
+    pub fn lock(&mut self) -> Result<MutexGuard<'_, T>, MutateError> {
+        let finished = self.ticket.take().map_or(Ok(()), Ticket::wait);
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        finished.map(|()| state)
+    }
+
+    /// Work on the state with `job` on `pool` at `priority`, or right away without a pool.  Waits
+    /// for the job before it.
+    pub fn submit(
+        &mut self,
+        pool: Option<&WorkerPool>,
+        priority: Priority,
+        job: impl FnOnce(&mut T) + Send + 'static,
+    ) -> Result<(), MutateError> {
+        let Some(pool) = pool else {
+            job(&mut *self.lock()?);
+            return Ok(());
+        };
+        drop(self.lock()?);
+        let state = self.state.clone();
+        self.ticket = Some(pool.submit(priority, move || {
+            job(&mut state.lock().unwrap_or_else(PoisonError::into_inner))
+        }));
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn pin(core: usize) -> Result<(), std::io::Error> {
+    // Safety: the set is zeroed and filled with the libc macros, and `0` is the calling thread.
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut set);
+        libc::CPU_SET(core, &mut set);
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin(_core: usize) -> Result<(), std::io::Error> {
+    Err(std::io::Error::other(
+        "pinning is only implemented on Linux",
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config(workers: usize, reserve: usize) -> PoolConfig {
+        PoolConfig {
+            workers,
+            reserve,
+            pin: None,
+        }
+    }
+
+    #[test]
+    fn test_pool_priority_order() {
+        let pool = WorkerPool::new(&config(2, 1)).unwrap();
+        let (gate_tx, gate_rx) = mpsc::channel::<()>();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        // Hold both workers so that everything after queues up.
+        let gate_rx = Arc::new(Mutex::new(gate_rx));
+        let holds: Vec<_> = (0..2)
+            .map(|_| {
+                let gate_rx = gate_rx.clone();
+                pool.submit(Priority::Audio, move || {
+                    gate_rx.lock().unwrap().recv().unwrap();
+                })
+            })
+            .collect();
+        while pool.queued()[0] > 0 {
+            std::thread::yield_now();
+        }
+
+        let tickets: Vec<_> = [Priority::Background, Priority::Frame, Priority::Audio]
+            .into_iter()
+            .map(|p| {
+                let order = order.clone();
+                pool.submit(p, move || order.lock().unwrap().push(p))
+            })
+            .collect();
+        // Release one worker at a time so that the order is deterministic.
+        gate_tx.send(()).unwrap();
+        for t in tickets {
+            t.wait().unwrap();
+        }
+        gate_tx.send(()).unwrap();
+        for h in holds {
+            h.wait().unwrap();
+        }
+        assert_eq!(
+            *order.lock().unwrap(),
+            [Priority::Audio, Priority::Frame, Priority::Background]
+        );
+
+        let stats = pool.stats();
+        assert_eq!(stats[Priority::Audio.index()].jobs, 3);
+        assert_eq!(stats[Priority::Background.index()].jobs, 1);
+        assert!(stats[Priority::Background.index()].max > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_pool_reserve_blocks_background() {
+        let pool = WorkerPool::new(&config(2, 1)).unwrap();
+        let (gate_tx, gate_rx) = mpsc::channel::<()>();
+        let hold = pool.submit(Priority::Background, move || gate_rx.recv().unwrap());
+        while pool.queued()[2] > 0 {
+            std::thread::yield_now();
+        }
+        // The only other worker is reserved.
+        let second = pool.submit(Priority::Background, || ());
+        let urgent = pool.submit(Priority::Frame, || 7);
+        assert_eq!(urgent.wait().unwrap(), 7);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(second.try_wait().is_none());
+
+        gate_tx.send(()).unwrap();
+        hold.wait().unwrap();
+        second.wait().unwrap();
+    }
+
+    #[test]
+    fn test_pool_panic_reported() {
+        let pool = WorkerPool::new(&config(1, 0)).unwrap();
+        let ticket = pool.submit(Priority::Frame, || -> u32 { panic!("job failed") });
+        assert!(matches!(ticket.wait(), Err(MutateError::JobPanicked)));
+        // The worker survived.
+        assert_eq!(pool.submit(Priority::Frame, || 1).wait().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_pool_offload() {
+        let pool = WorkerPool::new(&config(1, 0)).unwrap();
+        let mut offload = Offload::new(Vec::new());
+        for i in 0..4 {
+            offload
+                .submit(Some(&pool), Priority::Frame, move |v| v.push(i))
+                .unwrap();
+        }
+        assert_eq!(*offload.lock().unwrap(), [0, 1, 2, 3]);
+        assert!(offload.ready().unwrap());
+
+        // Without a pool, jobs run before `submit` returns.
+        offload
+            .submit(None, Priority::Frame, |v| v.clear())
+            .unwrap();
+        assert!(offload.lock().unwrap().is_empty());
+
+        offload
+            .submit(Some(&pool), Priority::Frame, |v| {
+                v.push(1);
+                panic!("job failed");
+            })
+            .unwrap();
+        assert!(matches!(offload.lock(), Err(MutateError::JobPanicked)));
+        assert_eq!(*offload.lock().unwrap(), [1]);
+    }
+
+    #[test]
+    fn test_pool_config_parse() {
+        PoolConfig::default().validate().unwrap();
+        let table: toml::Table = "workers = 3\nreserve = 1\npin = [0, 1, 2]".parse().unwrap();
+        let config = PoolConfig::parse(&table).unwrap();
+        assert_eq!(config.workers, 3);
+        assert_eq!(config.pin, Some(vec![0, 1, 2]));
+
+        let table: toml::Table = "workers = 2\npin = [0]".parse().unwrap();
+        assert!(PoolConfig::parse(&table).is_err());
+        let table: toml::Table = "workers = 2\nreserve = 2".parse().unwrap();
+        assert!(PoolConfig::parse(&table).is_err());
+    }
+}
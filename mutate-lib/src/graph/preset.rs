@@ -0,0 +1,691 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Presets
+//!
+//! A visualization is data: which nodes run, how they connect, and where their parameters sit.  A
+//! preset describes all of it in the graph TOML, and [`Preset::build`] turns it into a wired
+//! [`Graph`], so a new arrangement never needs a rebuild.
+//!
+//! ```toml
+//! [[node]]
+//! name = "resample"
+//! kind = "resample"
+//! input_rate = 44100      # anything besides name, kind, and params is for the kind's factory
+//!
+//! [[node]]
+//! name = "loudness"
+//! kind = "loudness"
+//! params = { target = -18.0, reference = "short-term" }
+//!
+//! [[edge]]
+//! from = "resample.output"
+//! to = "loudness.input"
+//! window = 4096           # optional frames, see `Graph::window`
+//! windowing = "ring"      # optional, "flat" by default
+//!
+//! [layout]                # optional, see `layout`
+//!
+//! [palette]               # optional, see `color`
+//! ember = { stops = ["#000000", "#ff8000", "#ffffe0"] }
+//! ```
+//!
+//! Edges name ports as `<node>.<port>`.  `feedback = true` makes an edge a [`Graph::feedback`]
+//! edge.  Edges are checked as [`Graph::connect`] checks them, [gain stages](crate::dsp::staging)
+//! included.  Choice parameters take the option's name.  Out of range numbers are clamped like any
+//! other parameter write.
+//!
+//! Palettes are named for nodes to look up with [`Preset::palette`], which also knows the built-in
+//! colormaps.
+//!
+//! Node kinds are looked up in a [`NodeRegistry`].  [`NodeRegistry::builtin`] knows the library's
+//! nodes, and hosts register their own kinds on top.
+
+// NEXT presets are loaded from an explicit path.  They merge with the user config once there is
+// one, so that a preset can be a small override of a shared base.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::layout::Layout;
+use super::window::Windowing;
+use super::{Graph, Node, ParamKind, ParamSpec, ParamValue};
+use crate::color::Palette;
+use crate::units::Frames;
+use crate::MutateError;
+
+fn bad(msg: String) -> MutateError {
+    MutateError::Preset(msg)
+}
+
+/// Construction arguments of one node: the keys of its `[[node]]` table.
+pub struct NodeArgs<'a> {
+    name: &'a str,
+    table: &'a toml::Table,
+}
+
+impl NodeArgs<'_> {
+    /// Name the node will be registered under.
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    pub fn float(&self, key: &str, default: f64) -> Result<f64, MutateError> {
+        match self.table.get(key) {
+            None => Ok(default),
+            Some(v) => number(v).ok_or_else(|| self.wrong(key, "a number")),
+        }
+    }
+
+    pub fn uint(&self, key: &str, default: u64) -> Result<u64, MutateError> {
+        match self.table.get(key) {
+            None => Ok(default),
+            Some(v) => v
+                .as_integer()
+                .and_then(|i| u64::try_from(i).ok())
+                .ok_or_else(|| self.wrong(key, "a non-negative integer")),
+        }
+    }
+
+    pub fn str<'b>(&'b self, key: &str, default: &'b str) -> Result<&'b str, MutateError> {
+        match self.table.get(key) {
+            None => Ok(default),
+            Some(v) => v.as_str().ok_or_else(|| self.wrong(key, "a string")),
+        }
+    }
+
+    fn wrong(&self, key: &str, what: &str) -> MutateError {
+        bad(format!("node `{}`: `{key}` must be {what}", self.name))
+    }
+}
+
+type Factory = Box<dyn Fn(&NodeArgs) -> Result<Box<dyn Node>, MutateError> + Send + Sync>;
+
+/// Node kinds by name, for building presets.
+#[derive(Default)]
+pub struct NodeRegistry {
+    kinds: BTreeMap<String, Factory>,
+}
+
+impl NodeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every node kind the library provides.
+    pub fn builtin() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = Self::new();
+        #[cfg(feature = "dsp")]
+        {
+            use crate::dsp::chroma::ChromaNode;
+            use crate::dsp::dft::{MultiDftNode, NODE_CYCLES};
+            use crate::dsp::loudness::LoudnessNode;
+            use crate::dsp::resample::ResampleNode;
+            use crate::dsp::segment::SegmentNode;
+            use crate::dsp::spectrogram::SpectrogramNode;
+            use crate::dsp::units::SampleRate;
+            use crate::settings::DspSettings;
+
+            registry.register("resample", |args| {
+                let rate = args.uint("input_rate", 48_000)? as u32;
+                let channels = args.uint("channels", 2)? as usize;
+                Ok(ResampleNode::new(rate, channels))
+            });
+            registry.register("loudness", |args| {
+                let rate = args.float("rate", SampleRate::default().get())?;
+                let channels = args.uint("channels", 2)? as usize;
+                Ok(LoudnessNode::new(SampleRate(rate), channels))
+            });
+            // Banks are designed from the graph's configuration, which `Settings::configure`
+            // writes, and chroma folds their rows by the same configuration.
+            registry.register("chroma", |args| {
+                Ok(ChromaNode::on_spectrum(
+                    DspSettings::default(),
+                    args.float("rows_per_second", 60.0)?,
+                ))
+            });
+            registry.register("spectrogram", |_| {
+                Ok(SpectrogramNode::new(DspSettings::default()))
+            });
+            registry.register("dft", |args| {
+                let short = args.float("short_cycles", NODE_CYCLES[0])?;
+                let long = args.float("long_cycles", NODE_CYCLES[1])?;
+                Ok(MultiDftNode::new(DspSettings::default(), [short, long]))
+            });
+            registry.register("segment", |args| {
+                Ok(SegmentNode::new(args.float("rows_per_second", 60.0)?))
+            });
+        }
+        registry
+    }
+
+    /// Add or replace the kind `kind`.
+    pub fn register<N: Node + 'static>(
+        &mut self,
+        kind: &str,
+        factory: impl Fn(&NodeArgs) -> Result<N, MutateError> + Send + Sync + 'static,
+    ) {
+        let factory: Factory = Box::new(move |args| Ok(Box::new(factory(args)?)));
+        self.kinds.insert(kind.to_owned(), factory);
+    }
+
+    /// Registered kinds, sorted.
+    pub fn kinds(&self) -> impl Iterator<Item = &str> {
+        self.kinds.keys().map(String::as_str)
+    }
+
+    fn make(&self, kind: &str, args: &NodeArgs) -> Result<Box<dyn Node>, MutateError> {
+        let factory = self
+            .kinds
+            .get(kind)
+            .ok_or_else(|| bad(format!("node `{}`: unknown kind `{kind}`", args.name)))?;
+        factory(args)
+    }
+}
+
+/// One `[[node]]` entry.
+#[derive(Clone, Debug)]
+pub struct NodeDecl {
+    pub name: String,
+    pub kind: String,
+    pub params: toml::Table,
+    /// Everything else, handed to the kind's factory.
+    pub args: toml::Table,
+}
+
+/// One `[[edge]]` entry.  Ports are `(node, port)`.
+#[derive(Clone, Debug)]
+pub struct EdgeDecl {
+    pub from: (String, String),
+    pub to: (String, String),
+    pub feedback: bool,
+    pub window: Option<(Frames, Windowing)>,
+}
+
+/// A parsed graph file.  See the [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct Preset {
+    pub nodes: Vec<NodeDecl>,
+    pub edges: Vec<EdgeDecl>,
+    pub layout: Option<Layout>,
+    pub palettes: BTreeMap<String, Palette>,
+}
+
+impl Preset {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MutateError> {
+        let text = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| bad(format!("{}: {e}", path.as_ref().display())))?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, MutateError> {
+        let table: toml::Table = text.parse().map_err(|e| bad(format!("{e}")))?;
+        let entries = |key: &str| -> Result<Vec<&toml::Table>, MutateError> {
+            let Some(entries) = table.get(key) else {
+                return Ok(Vec::new());
+            };
+            entries
+                .as_array()
+                .ok_or_else(|| bad(format!("`{key}` must be an array of tables")))?
+                .iter()
+                .map(|e| {
+                    e.as_table()
+                        .ok_or_else(|| bad(format!("`{key}` entries must be tables")))
+                })
+                .collect()
+        };
+
+        let mut nodes = Vec::new();
+        for entry in entries("node")? {
+            let text = |key: &str| {
+                entry
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| bad(format!("node needs a `{key}`")))
+            };
+            let name = text("name")?.to_owned();
+            let kind = text("kind")?.to_owned();
+            let params = match entry.get("params") {
+                None => toml::Table::new(),
+                Some(p) => p
+                    .as_table()
+                    .cloned()
+                    .ok_or_else(|| bad(format!("node `{name}`: `params` must be a table")))?,
+            };
+            let mut args = entry.clone();
+            for key in ["name", "kind", "params"] {
+                args.remove(key);
+            }
+            nodes.push(NodeDecl {
+                name,
+                kind,
+                params,
+                args,
+            });
+        }
+
+        let mut edges = Vec::new();
+        for entry in entries("edge")? {
+            let port = |key: &str| {
+                entry
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .and_then(|p| p.rsplit_once('.'))
+                    .map(|(n, p)| (n.to_owned(), p.to_owned()))
+                    .ok_or_else(|| bad(format!("edge needs `{key}` as \"<node>.<port>\"")))
+            };
+            let (from, to) = (port("from")?, port("to")?);
+            let path = format!("{}.{} -> {}.{}", from.0, from.1, to.0, to.1);
+            let feedback = match entry.get("feedback") {
+                None => false,
+                Some(v) => v
+                    .as_bool()
+                    .ok_or_else(|| bad(format!("{path}: `feedback` must be a boolean")))?,
+            };
+            let windowing = match entry.get("windowing").map(|v| v.as_str()) {
+                None | Some(Some("flat")) => Windowing::Flat,
+                Some(Some("ring")) => Windowing::Ring,
+                Some(_) => {
+                    return Err(bad(format!(
+                        "{path}: `windowing` must be \"flat\" or \"ring\""
+                    )))
+                }
+            };
+            let window = match entry.get("window") {
+                None => None,
+                Some(v) => Some((
+                    v.as_integer()
+                        .and_then(|i| usize::try_from(i).ok())
+                        .filter(|&n| n > 0)
+                        .map(Frames)
+                        .ok_or_else(|| {
+                            bad(format!(
+                                "{path}: `window` must be a positive count of frames"
+                            ))
+                        })?,
+                    windowing,
+                )),
+            };
+            edges.push(EdgeDecl {
+                from,
+                to,
+                feedback,
+                window,
+            });
+        }
+
+        let layout = match table.get("layout") {
+            None => None,
+            Some(v) => Some(Layout::parse(
+                v.as_table()
+                    .ok_or_else(|| bad("`layout` must be a table".into()))?,
+            )?),
+        };
+
+        let mut palettes = BTreeMap::new();
+        if let Some(v) = table.get("palette") {
+            let entries = v
+                .as_table()
+                .ok_or_else(|| bad("`palette` must be a table".into()))?;
+            for (name, value) in entries {
+                let palette =
+                    Palette::parse(value).map_err(|e| bad(format!("palette `{name}`: {e}")))?;
+                palettes.insert(name.clone(), palette);
+            }
+        }
+
+        Ok(Self {
+            nodes,
+            edges,
+            layout,
+            palettes,
+        })
+    }
+
+    /// The palette declared as `name`, or else the built-in colormap of that name.
+    pub fn palette(&self, name: &str) -> Result<Palette, MutateError> {
+        match self.palettes.get(name) {
+            Some(palette) => Ok(palette.clone()),
+            None => Ok(Palette::Map(name.parse()?)),
+        }
+    }
+
+    /// Instantiate every node with `registry`, set its parameters, and wire the edges.
+    pub fn build(&self, registry: &NodeRegistry) -> Result<Graph, MutateError> {
+        let mut graph = Graph::new();
+        for decl in &self.nodes {
+            let args = NodeArgs {
+                name: &decl.name,
+                table: &decl.args,
+            };
+            let node = registry.make(&decl.kind, &args)?;
+            graph.add_boxed(&decl.name, node)?;
+            for (param, value) in &decl.params {
+                let path = format!("{}/{param}", decl.name);
+                let (handle, index) = graph.resolve(&path)?;
+                let value = param_value(&handle.specs()[index], value)
+                    .ok_or_else(|| bad(format!("{path}: wrong type")))?;
+                handle.set(index, value)?;
+            }
+        }
+
+        let find = |graph: &Graph, (node, _): &(String, String)| {
+            graph
+                .node(node)
+                .ok_or_else(|| bad(format!("edge names unknown node `{node}`")))
+        };
+        for edge in &self.edges {
+            let (from, to) = (find(&graph, &edge.from)?, find(&graph, &edge.to)?);
+            match edge.feedback {
+                true => graph.feedback(from, &edge.from.1, to, &edge.to.1)?,
+                false => graph.connect(from, &edge.from.1, to, &edge.to.1)?,
+            }
+            if let Some((frames, windowing)) = edge.window {
+                graph.window(to, &edge.to.1, frames, windowing)?;
+            }
+        }
+
+        let mut tiles = self.layout.iter().flat_map(|l| &l.tiles);
+        if let Some(tile) = tiles.find(|t| graph.node(&t.node).is_none()) {
+            return Err(bad(format!(
+                "layout tile names unknown node `{}`",
+                tile.node
+            )));
+        }
+        Ok(graph)
+    }
+}
+
+/// Read a TOML value as the kind of `spec`.
+fn param_value(spec: &ParamSpec, value: &toml::Value) -> Option<ParamValue> {
+    match spec.kind {
+        ParamKind::Float { .. } => number(value).map(ParamValue::Float),
+        ParamKind::Int { .. } => value.as_integer().map(ParamValue::Int),
+        ParamKind::Bool => value.as_bool().map(ParamValue::Bool),
+        ParamKind::Choice(options) => match value {
+            toml::Value::String(s) => options.iter().position(|o| o == s),
+            v => v.as_integer().and_then(|i| usize::try_from(i).ok()),
+        }
+        .map(ParamValue::Choice),
+    }
+}
+
+fn number(value: &toml::Value) -> Option<f64> {
+    value
+        .as_float()
+        .or_else(|| value.as_integer().map(|i| i as f64))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::color::Colormap;
+    use crate::graph::{Frame, GraphEvent, ParamHandle, Params, PortKind, PortSpec};
+
+    /// Emits its `value` parameter.
+    struct Constant(Option<ParamHandle>);
+
+    /// Emits the sum of its inputs.
+    struct Add;
+
+    impl Params for Constant {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[
+                ParamSpec {
+                    name: "value",
+                    description: "emitted every frame",
+                    kind: ParamKind::Float {
+                        min: -10.0,
+                        max: 10.0,
+                    },
+                    default: ParamValue::Float(0.0),
+                },
+                ParamSpec {
+                    name: "sign",
+                    description: "sign of the value",
+                    kind: ParamKind::Choice(&["plus", "minus"]),
+                    default: ParamValue::Choice(0),
+                },
+            ]
+        }
+    }
+
+    impl Node for Constant {
+        fn outputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "out",
+                kind: PortKind::Scalar,
+            }]
+        }
+
+        fn attach(&mut self, params: ParamHandle) {
+            self.0 = Some(params);
+        }
+
+        fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+            let params = self.0.as_ref().unwrap();
+            let sign = [1.0, -1.0][params.get(1).as_f64() as usize];
+            frame.emit(0, GraphEvent::Scalar(sign * params.f64(0)));
+            Ok(())
+        }
+    }
+
+    impl Params for Add {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[]
+        }
+    }
+
+    impl Node for Add {
+        fn inputs(&self) -> &'static [PortSpec] {
+            &[
+                PortSpec {
+                    name: "a",
+                    kind: PortKind::Scalar,
+                },
+                PortSpec {
+                    name: "b",
+                    kind: PortKind::Scalar,
+                },
+            ]
+        }
+
+        fn outputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "sum",
+                kind: PortKind::Scalar,
+            }]
+        }
+
+        fn attach(&mut self, _params: ParamHandle) {}
+
+        fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+            let value = |port| match frame.input(port) {
+                Some(GraphEvent::Scalar(x)) => *x,
+                _ => 0.0,
+            };
+            let sum = value(0) + value(1);
+            frame.emit(0, GraphEvent::Scalar(sum));
+            Ok(())
+        }
+    }
+
+    fn registry() -> NodeRegistry {
+        let mut registry = NodeRegistry::new();
+        registry.register("constant", |_| Ok(Constant(None)));
+        registry.register("add", |_| Ok(Add));
+        registry
+    }
+
+    const PRESET: &str = r##"
+        [[node]]
+        name = "two"
+        kind = "constant"
+        params = { value = 2 }
+
+        [[node]]
+        name = "three"
+        kind = "constant"
+        params = { value = 3.0, sign = "minus" }
+
+        [[node]]
+        name = "sum"
+        kind = "add"
+
+        [[edge]]
+        from = "two.out"
+        to = "sum.a"
+
+        [[edge]]
+        from = "three.out"
+        to = "sum.b"
+
+        [layout]
+        [[layout.tile]]
+        node = "sum"
+
+        [palette]
+        night = "magma"
+        ember = { stops = ["#000000", "#ff8000"] }
+    "##;
+
+    #[test]
+    fn test_build() {
+        let preset = Preset::parse(PRESET).unwrap();
+        assert_eq!(preset.nodes.len(), 3);
+        assert_eq!(preset.layout.as_ref().unwrap().tiles[0].node, "sum");
+        assert_eq!(preset.palette("ember").unwrap().rgb8(0.0), [0, 0, 0]);
+        assert_eq!(preset.palette("night").unwrap(), Colormap::Magma.into());
+        assert_eq!(preset.palette("turbo").unwrap(), Colormap::Turbo.into());
+        assert!(preset.palette("sepia").is_err());
+        let mut graph = preset.build(&registry()).unwrap();
+        assert_eq!(graph.get("three/sign").unwrap(), ParamValue::Choice(1));
+        graph.run_frame().unwrap();
+        let sum = graph.node("sum").unwrap();
+        assert_eq!(graph.output(sum, "sum"), Some(&GraphEvent::Scalar(-1.0)));
+    }
+
+    #[test]
+    fn test_rejects() {
+        let build = |text: &str| Preset::parse(text)?.build(&registry());
+        let node = |kind: &str, params: &str| {
+            format!("[[node]]\nname = \"n\"\nkind = \"{kind}\"\nparams = {{ {params} }}\n")
+        };
+        assert!(build(&node("blur", "")).is_err());
+        assert!(build(&node("constant", "gain = 1.0")).is_err());
+        assert!(build(&node("constant", "value = true")).is_err());
+        assert!(build(&node("constant", "sign = \"sideways\"")).is_err());
+        assert!(build(&format!(
+            "{}[[edge]]\nfrom = \"n.out\"\nto = \"m.a\"",
+            node("constant", "")
+        ))
+        .is_err());
+        assert!(build("[[edge]]\nfrom = \"nodot\"\nto = \"n.a\"").is_err());
+        assert!(build("[layout]\n[[layout.tile]]\nnode = \"ghost\"").is_err());
+        assert!(build("[palette]\nember = { stops = [\"red\"] }").is_err());
+        // Out of range is clamped, not rejected.
+        let graph = build(&node("constant", "value = 99.0")).unwrap();
+        assert_eq!(graph.get("n/value").unwrap(), ParamValue::Float(10.0));
+    }
+
+    #[cfg(feature = "dsp")]
+    #[test]
+    fn test_builtin() {
+        let preset = Preset::parse(
+            r#"
+            [[node]]
+            name = "resample"
+            kind = "resample"
+            input_rate = 44100
+
+            [[node]]
+            name = "loudness"
+            kind = "loudness"
+            params = { reference = "integrated", target = -18.0 }
+
+            [[edge]]
+            from = "resample.output"
+            to = "loudness.input"
+            window = 4800
+            windowing = "ring"
+
+            [[node]]
+            name = "dft"
+            kind = "dft"
+            long_cycles = 16.0
+
+            [[node]]
+            name = "chroma"
+            kind = "chroma"
+
+            [[node]]
+            name = "segment"
+            kind = "segment"
+
+            [[edge]]
+            from = "resample.output"
+            to = "dft.input"
+
+            [[edge]]
+            from = "dft.long"
+            to = "chroma.row"
+
+            [[edge]]
+            from = "chroma.chroma"
+            to = "segment.features"
+            "#,
+        )
+        .unwrap();
+        let registry = NodeRegistry::builtin();
+        assert!(registry.kinds().any(|k| k == "chroma"));
+        assert!(registry.kinds().any(|k| k == "dft"));
+        assert!(registry.kinds().any(|k| k == "segment"));
+        assert!(registry.kinds().any(|k| k == "spectrogram"));
+        let graph = preset.build(&registry).unwrap();
+        assert_eq!(
+            graph.get("loudness/target").unwrap(),
+            ParamValue::Float(-18.0)
+        );
+        assert_eq!(
+            graph.get("loudness/reference").unwrap(),
+            ParamValue::Choice(2)
+        );
+    }
+
+    #[cfg(feature = "dsp")]
+    #[test]
+    fn test_gain_staging() {
+        let preset = |from: &str| {
+            Preset::parse(&format!(
+                r#"
+                [[node]]
+                name = "spectrogram"
+                kind = "spectrogram"
+
+                [[node]]
+                name = "chroma"
+                kind = "chroma"
+
+                [[node]]
+                name = "refold"
+                kind = "chroma"
+
+                [[edge]]
+                from = "spectrogram.row"
+                to = "chroma.row"
+
+                [[edge]]
+                from = "{from}"
+                to = "refold.row"
+                "#
+            ))
+            .unwrap()
+        };
+        let registry = NodeRegistry::builtin();
+        assert!(preset("spectrogram.row").build(&registry).is_ok());
+        // Chroma is normalized, not amplitude.
+        let err = preset("chroma.chroma").build(&registry).err().unwrap();
+        assert!(matches!(err, MutateError::GainStaging(_)), "{err}");
+    }
+}
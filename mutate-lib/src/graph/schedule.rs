@@ -0,0 +1,766 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Scheduling
+//!
+//! A [`Node`] declares named input and output ports, each with a [`PortKind`].  Edges only join an
+//! output to an input of the same kind, and each input takes at most one edge.  Outputs fan out to
+//! any number of inputs.
+//!
+//! Every [`Graph::run_frame`] runs each node once, in topological order, so that a node always sees
+//! this frame's output of everything upstream.  Ties keep the order nodes were added, which makes
+//! runs reproducible.  Events are handed along edges by cloning, and every [`GraphEvent`] that
+//! carries data holds it behind an `Arc`, so fan-out never copies.
+//!
+//! ## Feedback
+//!
+//! A cycle of ordinary edges has no order and is rejected.  Feedback, such as a trail effect that
+//! reads its own last output, goes through [`Graph::feedback`] instead.  A feedback edge is left out
+//! of the sort and delivers the event its source emitted on the previous frame, which unrolls the
+//! cycle across frames.  On the first frame, feedback inputs are empty.
+
+// NEXT nodes that record GPU work need the frame's command buffer.  `Frame` is the place for it
+// once render nodes move into the graph.  Post-processing chains pass `Image` events between them
+// today, with each producer recording its own transitions.
+// NEXT independent nodes of one frame can run on the `WorkerPool` in parallel.  The order already
+// tells which ones are independent.
+
+use std::sync::Arc;
+
+use super::config::Config;
+use super::context::FramePhases;
+#[cfg(feature = "vulkan")]
+use super::context::{GraphContext, FRAMES_IN_FLIGHT};
+use super::lane::{GraphBuffer, Lane};
+use super::pool::WorkerPool;
+use super::window::SampleWindow;
+use super::{Graph, NodeEntry, NodeId, ParamHandle, Params};
+#[cfg(feature = "dsp")]
+use crate::dsp::staging::{self, GainStage};
+#[cfg(feature = "vulkan")]
+use crate::gpu::resource::target::OffscreenTarget;
+#[cfg(feature = "vulkan")]
+use crate::gpu::resource::transient::{TransientPool, TransientRange};
+use crate::MutateError;
+
+/// What an edge carries.  Ports only connect to ports of the same kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortKind {
+    /// Interleaved audio frames.
+    Samples,
+    /// One vector per frame, such as a spectrogram row.
+    Row,
+    /// One number per frame.
+    Scalar,
+    /// A storage buffer on the device.
+    Buffer,
+    /// A sampled image on the device, such as an offscreen render target.
+    Image,
+}
+
+/// One input or output of a node.
+#[derive(Debug)]
+pub struct PortSpec {
+    /// Addresses the port in [`Graph::connect`].  Unique among the node's inputs or outputs.
+    pub name: &'static str,
+    pub kind: PortKind,
+}
+
+/// Data handed from one node to the next.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphEvent {
+    Samples {
+        frames: Arc<[f32]>,
+        channels: usize,
+        rate: u32,
+    },
+    Row(Arc<[f32]>),
+    Scalar(f64),
+    /// Bindless storage buffer index and element count.  The producer owns the buffer.  Consumers
+    /// may only read it during the frame it arrives.
+    Buffer {
+        index: u32,
+        len: usize,
+    },
+    /// Bindless sampled image index and size.  The producer owns the image and has readied it for
+    /// sampling.  Consumers may only read it during the frame it arrives.
+    Image {
+        index: u32,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl GraphEvent {
+    pub fn kind(&self) -> PortKind {
+        match self {
+            GraphEvent::Samples { .. } => PortKind::Samples,
+            GraphEvent::Row(_) => PortKind::Row,
+            GraphEvent::Scalar(_) => PortKind::Scalar,
+            GraphEvent::Buffer { .. } => PortKind::Buffer,
+            GraphEvent::Image { .. } => PortKind::Image,
+        }
+    }
+
+    /// An `Image` event for `target`, which must be readied with
+    /// [`OffscreenTarget::sample`] earlier in this frame's recording.
+    #[cfg(feature = "vulkan")]
+    pub fn image(target: &OffscreenTarget) -> Self {
+        debug_assert_eq!(
+            target.layout(),
+            ash::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+        );
+        let extent = target.extent();
+        GraphEvent::Image {
+            index: target.handle().index().raw(),
+            width: extent.width,
+            height: extent.height,
+        }
+    }
+}
+
+/// A node the graph runs.  Port indices are positions in [`inputs`](Node::inputs) and
+/// [`outputs`](Node::outputs), usually named by associated constants like parameters are.
+pub trait Node: Params + Send {
+    fn inputs(&self) -> &'static [PortSpec] {
+        &[]
+    }
+
+    fn outputs(&self) -> &'static [PortSpec] {
+        &[]
+    }
+
+    /// Cadence this node runs at.  See [`lane`](super::lane).
+    fn lane(&self) -> Lane {
+        Lane::Frame
+    }
+
+    /// Configuration keys this node is provisioned against.  See [`config`](super::config).
+    fn watches(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Actions this node handles, such as `"pause"`.  See [`action`](super::action).
+    fn actions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Where the reference tone sits on this node's `Row` ports, when they carry levels.  Rows
+    /// between two nodes that declare a stage must agree.  See [`staging`](crate::dsp::staging).
+    #[cfg(feature = "dsp")]
+    fn gain_stage(&self) -> Option<&dyn GainStage> {
+        None
+    }
+
+    /// Receive the handle from registration.  Called once by [`Graph::add`].
+    fn attach(&mut self, params: ParamHandle);
+
+    /// Re-provision after watched keys in `changed` were written.  Called before the next frame,
+    /// after every upstream node has updated, and once from [`Graph::add`] with the watched keys
+    /// already set.
+    fn update(&mut self, config: &Config, changed: &[&str]) -> Result<(), MutateError> {
+        let _ = (config, changed);
+        Ok(())
+    }
+
+    /// Handle `action`, one of [`actions`](Node::actions).  Called between frames by
+    /// [`Graph::act`].
+    fn act(&mut self, action: &str) -> Result<(), MutateError> {
+        let _ = action;
+        Ok(())
+    }
+
+    /// Run once for this frame.  Outputs that are not emitted are empty downstream.
+    fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError>;
+}
+
+/// One node's view of the current frame.
+pub struct Frame<'a> {
+    number: u64,
+    phases: Option<FramePhases>,
+    node: usize,
+    edges: &'a [Edge],
+    inputs: &'a [Option<GraphEvent>],
+    outputs: &'a mut [Option<GraphEvent>],
+    specs: &'static [PortSpec],
+    pool: Option<&'a WorkerPool>,
+    #[cfg(feature = "vulkan")]
+    transients: Option<&'a mut TransientPool<FRAMES_IN_FLIGHT>>,
+}
+
+impl Frame<'_> {
+    /// Frames run since the graph was created, starting from zero.
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+
+    /// Deadlines of this frame, when the host runs the graph with [`Graph::run_frame_at`].
+    pub fn phases(&self) -> Option<&FramePhases> {
+        self.phases.as_ref()
+    }
+
+    /// Where to run heavy work, when the host gave the graph a pool.  See [`pool`](super::pool).
+    pub fn pool(&self) -> Option<&WorkerPool> {
+        self.pool
+    }
+
+    /// `size` bytes of scratch memory on the device for this frame's work, from the pool of the
+    /// [`GraphContext`] the frame runs in.  See [`Graph::run_frame_in`].
+    #[cfg(feature = "vulkan")]
+    pub fn transient(&mut self, size: u64) -> Result<TransientRange, MutateError> {
+        let transients = self
+            .transients
+            .as_deref_mut()
+            .ok_or(crate::gpu::VulkanError::NotInFrame("transient alloc"))?;
+        Ok(transients.alloc(size)?)
+    }
+
+    /// Event arriving on input `port`, if it is connected and its source emitted.
+    pub fn input(&self, port: usize) -> Option<&GraphEvent> {
+        self.inputs.get(port)?.as_ref()
+    }
+
+    /// History of samples on input `port`, if [`Graph::window`] keeps one.  Already includes this
+    /// frame's input.
+    pub fn window(&self, port: usize) -> Option<&SampleWindow> {
+        self.edges
+            .iter()
+            .find(|e| e.to == (self.node, port))?
+            .window
+            .as_ref()
+    }
+
+    /// Emit `event` on output `port`, replacing anything emitted on it earlier this frame.
+    pub fn emit(&mut self, port: usize, event: GraphEvent) {
+        debug_assert_eq!(
+            self.specs[port].kind,
+            event.kind(),
+            "{}",
+            self.specs[port].name
+        );
+        self.outputs[port] = Some(event);
+    }
+}
+
+/// Scratch memory for the nodes of a frame run in a [`GraphContext`].
+#[cfg(feature = "vulkan")]
+type Transients<'a> = Option<&'a mut TransientPool<FRAMES_IN_FLIGHT>>;
+#[cfg(not(feature = "vulkan"))]
+type Transients<'a> = Option<&'a mut ()>;
+
+/// An output port feeding an input port.
+#[derive(Clone)]
+pub(super) struct Edge {
+    from: (usize, usize),
+    pub(super) to: (usize, usize),
+    /// Delivers the previous frame's event and is left out of the sort.
+    feedback: bool,
+    /// Carries events between nodes of different lanes.
+    handoff: Option<GraphBuffer>,
+    /// History of `Samples` arriving, kept for the consumer.  See [`window`](super::window).
+    pub(super) window: Option<SampleWindow>,
+}
+
+impl Graph {
+    /// Add a node that the graph owns and runs.  Its parameters are registered under `name` just
+    /// like [`Graph::register`].
+    pub fn add(&mut self, name: &str, node: impl Node + 'static) -> Result<NodeId, MutateError> {
+        self.add_boxed(name, Box::new(node))
+    }
+
+    /// Add a node whose type is only known at runtime, such as one built from a preset.
+    pub fn add_boxed(
+        &mut self,
+        name: &str,
+        mut node: Box<dyn Node>,
+    ) -> Result<NodeId, MutateError> {
+        let (id, params) = self.register(name, &*node)?;
+        node.attach(params);
+        let set: Vec<&str> = node
+            .watches()
+            .iter()
+            .copied()
+            .filter(|k| self.config.get(k).is_some())
+            .collect();
+        if !set.is_empty() {
+            node.update(&self.config, &set)?;
+        }
+        let outputs = node.outputs().len();
+        let entry = &mut self.nodes[id.0];
+        entry.lane = node.lane();
+        entry.outputs = vec![None; outputs];
+        entry.previous = vec![None; outputs];
+        entry.runner = Some(node);
+        Ok(id)
+    }
+
+    /// Connect output `output` of `from` to input `input` of `to`.  Fails if the kinds differ, the
+    /// input is taken, the edge would close a cycle, or the rows it carries leave one
+    /// [gain stage](Node::gain_stage) at a level the next does not expect.
+    pub fn connect(
+        &mut self,
+        from: NodeId,
+        output: &str,
+        to: NodeId,
+        input: &str,
+    ) -> Result<(), MutateError> {
+        self.add_edge(from, output, to, input, false)?;
+        if let Err(e) = self.sort() {
+            self.edges.pop();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Connect like [`connect`](Self::connect), but deliver the event from the previous frame.
+    /// Feedback edges may close cycles.
+    pub fn feedback(
+        &mut self,
+        from: NodeId,
+        output: &str,
+        to: NodeId,
+        input: &str,
+    ) -> Result<(), MutateError> {
+        self.add_edge(from, output, to, input, true)
+    }
+
+    fn add_edge(
+        &mut self,
+        from: NodeId,
+        output: &str,
+        to: NodeId,
+        input: &str,
+        feedback: bool,
+    ) -> Result<(), MutateError> {
+        let path = format!(
+            "{}.{output} -> {}.{input}",
+            self.node_name(from),
+            self.node_name(to)
+        );
+        let invalid = |reason: &str| MutateError::InvalidEdge(format!("{path}: {reason}"));
+        let port = |id: NodeId, name: &str, outputs: bool| {
+            let runner = self.nodes[id.0].runner.as_ref()?;
+            let specs = match outputs {
+                true => runner.outputs(),
+                false => runner.inputs(),
+            };
+            specs
+                .iter()
+                .position(|p| p.name == name)
+                .map(|i| (i, specs[i].kind))
+        };
+        let (out_port, out_kind) = port(from, output, true).ok_or_else(|| invalid("no output"))?;
+        let (in_port, in_kind) = port(to, input, false).ok_or_else(|| invalid("no input"))?;
+        if out_kind != in_kind {
+            return Err(invalid(&format!("{out_kind:?} into {in_kind:?}")));
+        }
+        if self.edges.iter().any(|e| e.to == (to.0, in_port)) {
+            return Err(invalid("input already connected"));
+        }
+        #[cfg(feature = "dsp")]
+        {
+            let stage = |id: NodeId| self.nodes[id.0].runner.as_ref()?.gain_stage();
+            let stages = (out_kind == PortKind::Row).then(|| stage(from).zip(stage(to)));
+            if let Some((out_stage, in_stage)) = stages.flatten() {
+                staging::handoff(out_stage, in_stage).map_err(|e| match e {
+                    MutateError::GainStaging(reason) => {
+                        MutateError::GainStaging(format!("{path}: {reason}"))
+                    }
+                    e => e,
+                })?;
+            }
+        }
+        let crosses = self.nodes[from.0].lane != self.nodes[to.0].lane;
+        self.edges.push(Edge {
+            from: (from.0, out_port),
+            to: (to.0, in_port),
+            feedback,
+            handoff: crosses.then(GraphBuffer::new),
+            window: None,
+        });
+        self.order = None;
+        Ok(())
+    }
+
+    /// Topological order over ordinary edges.  Ties keep insertion order.
+    fn sort(&self) -> Result<Vec<usize>, MutateError> {
+        let mut pending = vec![0usize; self.nodes.len()];
+        for e in self.edges.iter().filter(|e| !e.feedback) {
+            pending[e.to.0] += 1;
+        }
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut ready: Vec<usize> = (0..self.nodes.len()).filter(|&i| pending[i] == 0).collect();
+        while let Some(&next) = ready.first() {
+            ready.remove(0);
+            order.push(next);
+            for e in self
+                .edges
+                .iter()
+                .filter(|e| !e.feedback && e.from.0 == next)
+            {
+                pending[e.to.0] -= 1;
+                if pending[e.to.0] == 0 {
+                    let at = ready.partition_point(|&i| i < e.to.0);
+                    ready.insert(at, e.to.0);
+                }
+            }
+        }
+        if order.len() < self.nodes.len() {
+            let stuck: Vec<&str> = (0..self.nodes.len())
+                .filter(|&i| pending[i] > 0)
+                .map(|i| self.nodes[i].name.as_str())
+                .collect();
+            return Err(MutateError::InvalidEdge(format!(
+                "cycle through {}, use feedback",
+                stuck.join(", ")
+            )));
+        }
+        Ok(order)
+    }
+
+    /// Nodes in the order [`run_frame`](Self::run_frame) runs them.
+    pub fn order(&mut self) -> Result<Vec<NodeId>, MutateError> {
+        if self.order.is_none() {
+            self.order = Some(self.sort()?);
+        }
+        Ok(self.order.iter().flatten().map(|&i| NodeId(i)).collect())
+    }
+
+    /// Run every added node once, after [`reconfigure`](Self::reconfigure).  Stops at the first
+    /// node that fails.
+    pub fn run_frame(&mut self) -> Result<(), MutateError> {
+        self.run_phased(None, None, None)
+    }
+
+    /// Run like [`run_frame`](Self::run_frame), with deadlines nodes can read from
+    /// [`Frame::phases`].
+    pub fn run_frame_at(&mut self, phases: FramePhases) -> Result<(), MutateError> {
+        self.run_phased(None, Some(phases), None)
+    }
+
+    /// Run like [`run_frame_at`](Self::run_frame_at) with the phases of the frame `context` began,
+    /// handing nodes its scratch memory through [`Frame::transient`].
+    #[cfg(feature = "vulkan")]
+    pub fn run_frame_in(&mut self, context: &mut GraphContext) -> Result<(), MutateError> {
+        let phases = context.phases();
+        self.run_phased(None, Some(phases), Some(context.transients()))
+    }
+
+    /// Run only the nodes of `lane`, once, in order.
+    pub fn run_lane(&mut self, lane: Lane) -> Result<(), MutateError> {
+        self.run_phased(Some(lane), None, None)
+    }
+
+    fn run_phased(
+        &mut self,
+        lane: Option<Lane>,
+        phases: Option<FramePhases>,
+        mut transients: Transients<'_>,
+    ) -> Result<(), MutateError> {
+        self.reconfigure()?;
+        if self.order.is_none() {
+            self.order = Some(self.sort()?);
+        }
+        let runs = |entry: &NodeEntry| lane.is_none_or(|l| entry.lane == l);
+        for entry in self.nodes.iter_mut().filter(|e| runs(e)) {
+            std::mem::swap(&mut entry.outputs, &mut entry.previous);
+            entry.outputs.iter_mut().for_each(|o| *o = None);
+        }
+
+        let order = self.order.take().unwrap_or_default();
+        let result = order.iter().try_for_each(|&i| match runs(&self.nodes[i]) {
+            true => self.run_node(i, phases, transients.as_deref_mut()),
+            false => Ok(()),
+        });
+        self.order = Some(order);
+        for l in Lane::ALL
+            .into_iter()
+            .filter(|&l| lane.is_none_or(|x| x == l))
+        {
+            self.ticks[l.index()] += 1;
+        }
+        result
+    }
+
+    fn run_node(
+        &mut self,
+        i: usize,
+        phases: Option<FramePhases>,
+        transients: Transients<'_>,
+    ) -> Result<(), MutateError> {
+        #[cfg(not(feature = "vulkan"))]
+        let _ = transients;
+        let Some(runner) = &self.nodes[i].runner else {
+            return Ok(());
+        };
+        let mut inputs = vec![None; runner.inputs().len()];
+        for e in self.edges.iter().filter(|e| e.to.0 == i) {
+            let source = &self.nodes[e.from.0];
+            inputs[e.to.1] = match (&e.handoff, e.feedback) {
+                (Some(handoff), _) => handoff.read(),
+                (None, true) => source.previous[e.from.1].clone(),
+                (None, false) => source.outputs[e.from.1].clone(),
+            };
+        }
+        for e in self.edges.iter_mut().filter(|e| e.to.0 == i) {
+            if let (
+                Some(window),
+                Some(GraphEvent::Samples {
+                    frames, channels, ..
+                }),
+            ) = (&mut e.window, &inputs[e.to.1])
+            {
+                window.push(frames, *channels);
+            }
+        }
+
+        let NodeEntry {
+            runner,
+            outputs,
+            lane,
+            ..
+        } = &mut self.nodes[i];
+        let runner = runner.as_mut().expect("checked above");
+        let mut frame = Frame {
+            number: self.ticks[lane.index()],
+            phases,
+            node: i,
+            edges: &self.edges,
+            inputs: &inputs,
+            outputs,
+            specs: runner.outputs(),
+            pool: self.pool.as_deref(),
+            #[cfg(feature = "vulkan")]
+            transients,
+        };
+        runner.run(&mut frame)?;
+        for e in self.edges.iter_mut().filter(|e| e.to.0 == i) {
+            if let Some(window) = &mut e.window {
+                window.mark_seen();
+            }
+        }
+
+        for e in self.edges.iter().filter(|e| e.from.0 == i) {
+            if let (Some(handoff), Some(event)) = (&e.handoff, &self.nodes[i].outputs[e.from.1]) {
+                handoff.publish(event.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Latest event on an output port, such as for the host to read the end of a chain.
+    pub fn output(&self, id: NodeId, port: &str) -> Option<&GraphEvent> {
+        let entry = &self.nodes[id.0];
+        let index = entry
+            .runner
+            .as_ref()?
+            .outputs()
+            .iter()
+            .position(|p| p.name == port)?;
+        entry.outputs[index].as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::ParamSpec;
+
+    /// Emits the frame number.
+    struct Counter;
+
+    /// Adds its inputs.  A missing input counts as zero.
+    struct Sum;
+
+    impl Params for Counter {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[]
+        }
+    }
+
+    impl Node for Counter {
+        fn outputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "count",
+                kind: PortKind::Scalar,
+            }]
+        }
+
+        fn attach(&mut self, _params: ParamHandle) {}
+
+        fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+            frame.emit(0, GraphEvent::Scalar(frame.number() as f64));
+            Ok(())
+        }
+    }
+
+    impl Params for Sum {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[]
+        }
+    }
+
+    impl Node for Sum {
+        fn inputs(&self) -> &'static [PortSpec] {
+            &[
+                PortSpec {
+                    name: "a",
+                    kind: PortKind::Scalar,
+                },
+                PortSpec {
+                    name: "b",
+                    kind: PortKind::Scalar,
+                },
+                PortSpec {
+                    name: "row",
+                    kind: PortKind::Row,
+                },
+            ]
+        }
+
+        fn outputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "sum",
+                kind: PortKind::Scalar,
+            }]
+        }
+
+        fn attach(&mut self, _params: ParamHandle) {}
+
+        fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+            let value = |port| match frame.input(port) {
+                Some(GraphEvent::Scalar(x)) => *x,
+                _ => 0.0,
+            };
+            let sum = value(0) + value(1);
+            frame.emit(0, GraphEvent::Scalar(sum));
+            Ok(())
+        }
+    }
+
+    fn scalar(graph: &Graph, id: NodeId, port: &str) -> Option<f64> {
+        match graph.output(id, port) {
+            Some(GraphEvent::Scalar(x)) => Some(*x),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_schedule_order_and_edges() {
+        let mut graph = Graph::new();
+        // Added downstream first so that only the edges can put them in order.
+        let total = graph.add("total", Sum).unwrap();
+        let double = graph.add("double", Sum).unwrap();
+        let count = graph.add("count", Counter).unwrap();
+
+        graph.connect(count, "count", double, "a").unwrap();
+        graph.connect(count, "count", double, "b").unwrap();
+        graph.connect(double, "sum", total, "a").unwrap();
+        graph.connect(count, "count", total, "b").unwrap();
+        assert_eq!(graph.order().unwrap(), [count, double, total]);
+
+        assert!(graph.connect(count, "count", total, "b").is_err(), "taken");
+        assert!(graph.connect(count, "count", total, "row").is_err(), "kind");
+        assert!(graph.connect(count, "nope", total, "row").is_err());
+
+        for _ in 0..3 {
+            graph.run_frame().unwrap();
+        }
+        // Frame two: 2 + 2, plus 2.
+        assert_eq!(scalar(&graph, total, "sum"), Some(6.0));
+    }
+
+    #[test]
+    fn test_schedule_rejects_cycles() {
+        let mut graph = Graph::new();
+        let a = graph.add("a", Sum).unwrap();
+        let b = graph.add("b", Sum).unwrap();
+        graph.connect(a, "sum", b, "a").unwrap();
+        let e = graph.connect(b, "sum", a, "a").unwrap_err();
+        assert!(e.to_string().contains("use feedback"), "{e}");
+        // The rejected edge is gone and the graph still runs.
+        graph.run_frame().unwrap();
+    }
+
+    #[test]
+    fn test_schedule_feedback() {
+        // A running total of the frame numbers, fed back into itself.
+        let mut graph = Graph::new();
+        let count = graph.add("count", Counter).unwrap();
+        let total = graph.add("total", Sum).unwrap();
+        graph.connect(count, "count", total, "a").unwrap();
+        graph.feedback(total, "sum", total, "b").unwrap();
+
+        let mut totals = Vec::new();
+        for _ in 0..5 {
+            graph.run_frame().unwrap();
+            totals.push(scalar(&graph, total, "sum").unwrap());
+        }
+        assert_eq!(totals, [0.0, 1.0, 3.0, 6.0, 10.0]);
+    }
+
+    /// Stereo 997Hz at -29dBFS and 44.1kHz, 10ms per frame.
+    struct Tone(crate::dsp::SineSweeper);
+
+    impl Params for Tone {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[]
+        }
+    }
+
+    impl Node for Tone {
+        fn outputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "output",
+                kind: PortKind::Samples,
+            }]
+        }
+
+        fn attach(&mut self, _params: ParamHandle) {}
+
+        fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+            let amplitude = 10f32.powf(-29.0 / 20.0);
+            let frames: Vec<f32> = (&mut self.0)
+                .take(441)
+                .flat_map(|x| [amplitude * x, amplitude * x])
+                .collect();
+            frame.emit(
+                0,
+                GraphEvent::Samples {
+                    frames: frames.into(),
+                    channels: 2,
+                    rate: 44_100,
+                },
+            );
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_schedule_audio_chain() {
+        use crate::dsp::loudness::LoudnessNode;
+        use crate::dsp::resample::ResampleNode;
+        use crate::dsp::units::SampleRate;
+
+        let mut graph = Graph::new();
+        let loudness = graph
+            .add("loudness", LoudnessNode::new(SampleRate(48_000.0), 1))
+            .unwrap();
+        let resample = graph.add("resample", ResampleNode::new(48_000, 1)).unwrap();
+        let tone = graph
+            .add("tone", Tone(crate::dsp::SineSweeper::new(997.0, 44_100.0)))
+            .unwrap();
+        graph.connect(tone, "output", resample, "input").unwrap();
+        graph
+            .connect(resample, "output", loudness, "input")
+            .unwrap();
+
+        for _ in 0..400 {
+            graph.run_frame().unwrap();
+        }
+        let measured = scalar(&graph, loudness, "loudness").unwrap();
+        assert!((measured + 29.0).abs() < 0.2, "{measured}");
+        let gain = scalar(&graph, loudness, "gain").unwrap();
+        assert!((20.0 * gain.log10() - 6.0).abs() < 0.2, "{gain}");
+    }
+}
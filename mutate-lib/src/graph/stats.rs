@@ -0,0 +1,203 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Frame Statistics
+//!
+//! [`FrameStats`] follows frames from the host to the display:
+//!
+//! - **cpu**: host time spent on a frame, from waking for its audio until its submission returned.
+//!   The pacing sleep before waking is not work and is not counted.
+//! - **audio to photon**: from capture of the newest audio a frame read until the frame reached the
+//!   display.  This is what a viewer perceives as the visuals lagging the sound.
+//! - **dropped**: frames that reached the display more than half a period after their present
+//!   target, and so missed the vblank they were drawn for.
+//! - **scratch**: the most [transient](crate::gpu::resource::transient) memory any frame used,
+//!   against the capacity of the pool, for tuning it.
+//!
+//! GPU time and present latency are measured by the `PresentRing`, in its
+//! [`PresentStats`](crate::gpu::present::stats::PresentStats).
+//!
+//! Frames are matched to presents by present ID.  Present wait trails by a frame or two and may
+//! miss presents entirely, so frames it never reports count toward neither latency nor drops.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::gpu::present::stats::{DurationWindow, PresentSample};
+use crate::gpu::resource::transient::TransientStats;
+use crate::graph::FramePhases;
+
+/// Frames remembered while waiting for present wait to report them.
+const PENDING: usize = 8;
+
+/// A frame queued for presentation that present wait has not reported yet.
+#[derive(Clone, Copy, Debug)]
+struct Pending {
+    id: u64,
+    target: Instant,
+    audio_captured: Option<Instant>,
+}
+
+/// Host time, audio to photon latency, and dropped frames.  See the [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct FrameStats {
+    cpu: DurationWindow,
+    audio_to_photon: DurationWindow,
+    pending: VecDeque<Pending>,
+    frames: u64,
+    dropped: u64,
+    /// Usage of the transient pool as of the last frame that reported it.
+    transients: Option<TransientStats>,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a frame queued with present ID `id`.  `cpu` is the host time spent on it, and
+    /// `audio_captured` is when the newest audio it read was captured, if known.
+    pub fn observe_frame(
+        &mut self,
+        id: u64,
+        phases: &FramePhases,
+        cpu: Duration,
+        audio_captured: Option<Instant>,
+    ) {
+        if self.pending.len() == PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(Pending {
+            id,
+            target: phases.present_target,
+            audio_captured,
+        });
+        self.cpu.push(cpu);
+        self.frames += 1;
+    }
+
+    /// Record a present reported by present wait, such as `PresentStats::last`.  Reporting the
+    /// same present again changes nothing.
+    pub fn observe_present(&mut self, sample: PresentSample, period: Duration) {
+        // Frames before this one were missed by present wait.
+        while self.pending.front().is_some_and(|p| p.id < sample.id) {
+            self.pending.pop_front();
+        }
+        let Some(frame) = self.pending.front().filter(|p| p.id == sample.id).copied() else {
+            return;
+        };
+        self.pending.pop_front();
+        if sample.presented > frame.target + period / 2 {
+            self.dropped += 1;
+        }
+        if let Some(captured) = frame.audio_captured {
+            self.audio_to_photon
+                .push(sample.presented.saturating_duration_since(captured));
+        }
+    }
+
+    /// Record the transient pool's usage at the end of a frame.
+    pub fn observe_transients(&mut self, stats: TransientStats) {
+        self.transients = Some(stats);
+    }
+
+    pub fn cpu(&self) -> &DurationWindow {
+        &self.cpu
+    }
+
+    pub fn audio_to_photon(&self) -> &DurationWindow {
+        &self.audio_to_photon
+    }
+
+    /// Frames recorded so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Frames that missed their present target so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Usage of the transient pool, once a frame has reported it.
+    pub fn transients(&self) -> Option<TransientStats> {
+        self.transients
+    }
+}
+
+impl std::fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |d: Option<Duration>| d.unwrap_or_default().as_secs_f64() * 1e3;
+        write!(
+            f,
+            "cpu median {:.2}ms, audio to photon median {:.2}ms, {} of {} frames dropped",
+            ms(self.cpu.quantile(0.5)),
+            ms(self.audio_to_photon.quantile(0.5)),
+            self.dropped,
+            self.frames
+        )?;
+        if let Some(t) = self.transients {
+            let kib = |bytes: u64| bytes.div_ceil(1024);
+            write!(
+                f,
+                ", scratch high water {} of {} KiB",
+                kib(t.high_water),
+                kib(t.capacity)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PERIOD: Duration = Duration::from_micros(16_667);
+
+    #[test]
+    fn test_match_presents() {
+        let start = Instant::now();
+        let mut stats = FrameStats::new();
+        let phases = |id: u32| FramePhases {
+            start,
+            audio_deadline: start + PERIOD * id,
+            submit_deadline: start + PERIOD * id,
+            present_target: start + PERIOD * (id + 1),
+        };
+        let cpu = Duration::from_millis(2);
+        for id in 1..=4 {
+            stats.observe_frame(id as u64, &phases(id), cpu, Some(start + PERIOD * id));
+        }
+        let present = |id: u32, presented: Instant| PresentSample {
+            id: id as u64,
+            presented,
+            latency: PERIOD,
+        };
+
+        // On target.
+        stats.observe_present(present(1, start + PERIOD * 2), PERIOD);
+        stats.observe_present(present(1, start + PERIOD * 2), PERIOD);
+        assert_eq!(stats.audio_to_photon().len(), 1);
+        assert_eq!(stats.audio_to_photon().last(), Some(PERIOD));
+        assert_eq!(stats.dropped(), 0);
+
+        // Present wait missed frame 2, and frame 3 was a vblank late.
+        stats.observe_present(present(3, start + PERIOD * 5), PERIOD);
+        assert_eq!(stats.dropped(), 1);
+        assert_eq!(stats.audio_to_photon().len(), 2);
+
+        assert_eq!(stats.frames(), 4);
+        assert_eq!(stats.cpu().quantile(0.5), Some(cpu));
+
+        assert!(!stats.to_string().contains("scratch"));
+        stats.observe_transients(TransientStats {
+            capacity: 1 << 20,
+            used: 0,
+            high_water: 3000,
+        });
+        assert!(stats
+            .to_string()
+            .ends_with("scratch high water 3 of 1024 KiB"));
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Throttling
+//!
+//! Long sessions on small GPUs heat up and clock down.  Frame times creep past the refresh budget
+//! and presentation starts missing vblanks, which reads as stutter.  Stutter is the worst outcome
+//! because motion is what viewers follow.  The [`ThrottleGovernor`] watches frame work times and
+//! sheds work in an order that keeps motion smooth:
+//!
+//! 1. [`Degradation::HalfRateAnalysis`] runs spectrogram compute on every other frame.  Skipped
+//!    frames extrapolate from the last two outputs as [`extrapolate`] does, on the device through
+//!    `dsp::compute::OutputHistory` when analysis runs there.  Analysis is the heaviest GPU load,
+//!    so this roughly halves it.
+//! 2. [`Degradation::ReducedResolution`] additionally renders at a lower internal resolution.
+//!
+//! ## Hysteresis
+//!
+//! Shedding happens after frame times have been over budget for a sustained stretch so that a
+//! single hitch, such as a window resize, never downgrades anything.  Restoring requires a much
+//! longer stretch comfortably under budget.  A throttled GPU that recovers the moment load drops
+//! would otherwise oscillate between levels every few seconds.
+//!
+//! The visualizer feeds each window's governor the GPU time its present ring measures, skips the
+//! filter bank's dispatch on frames without analysis in favor of the extrapolated fill, and shows
+//! the level in a corner while work is shed.
+
+use std::time::Duration;
+
+/// Smoothing for the frame time average.  About one second of history at 60Hz.
+const ALPHA: f64 = 1.0 / 60.0;
+/// Average frame time, relative to budget, considered degraded.
+const DEGRADED: f64 = 1.15;
+/// Average frame time, relative to budget, considered to have headroom for more work.
+const HEADROOM: f64 = 0.7;
+/// Frames of sustained degradation before shedding work.
+const SHED_FRAMES: u32 = 180;
+/// Frames of sustained headroom before restoring work.
+const RESTORE_FRAMES: u32 = 1200;
+
+/// How much work is currently being shed, least to most.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Degradation {
+    #[default]
+    Full,
+    HalfRateAnalysis,
+    ReducedResolution,
+}
+
+impl Degradation {
+    fn worse(self) -> Self {
+        match self {
+            Degradation::Full => Degradation::HalfRateAnalysis,
+            _ => Degradation::ReducedResolution,
+        }
+    }
+
+    fn better(self) -> Self {
+        match self {
+            Degradation::ReducedResolution => Degradation::HalfRateAnalysis,
+            _ => Degradation::Full,
+        }
+    }
+}
+
+impl std::fmt::Display for Degradation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Degradation::Full => "full rate",
+            Degradation::HalfRateAnalysis => "half-rate analysis",
+            Degradation::ReducedResolution => "half-rate analysis, reduced resolution",
+        })
+    }
+}
+
+/// Decides, frame by frame, how much work to shed.
+pub struct ThrottleGovernor {
+    budget: Duration,
+    average: Option<f64>,
+    /// Consecutive frames past the degraded or headroom threshold.
+    streak: u32,
+    level: Degradation,
+    frame: u64,
+}
+
+impl ThrottleGovernor {
+    /// `budget` is the frame period to hold, normally the display refresh period.
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            average: None,
+            streak: 0,
+            level: Degradation::Full,
+            frame: 0,
+        }
+    }
+
+    /// Change the budget, such as after the window moves to a display with another refresh rate.
+    pub fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+        self.streak = 0;
+    }
+
+    /// Record how long the last frame's work took.  Returns the new level when it changes.
+    ///
+    /// This must be work time, such as the span between GPU timestamps at the start and end of the
+    /// frame.  Present intervals are pinned to the refresh period under FIFO, so they can show
+    /// degradation but never headroom, and nothing would ever be restored.
+    pub fn observe(&mut self, frame_time: Duration) -> Option<Degradation> {
+        self.frame += 1;
+        let t = frame_time.as_secs_f64();
+        let average = match self.average {
+            Some(a) => a + ALPHA * (t - a),
+            None => t,
+        };
+        self.average = Some(average);
+
+        let ratio = average / self.budget.as_secs_f64();
+        let next = if ratio > DEGRADED && self.level != Degradation::ReducedResolution {
+            self.streak += 1;
+            (self.streak >= SHED_FRAMES).then(|| self.level.worse())
+        } else if ratio < HEADROOM && self.level != Degradation::Full {
+            self.streak += 1;
+            (self.streak >= RESTORE_FRAMES).then(|| self.level.better())
+        } else {
+            self.streak = 0;
+            None
+        };
+
+        let next = next?;
+        self.level = next;
+        self.streak = 0;
+        // Start measuring the new level from scratch rather than from an average dominated by the
+        // old one.
+        self.average = None;
+        Some(next)
+    }
+
+    pub fn level(&self) -> Degradation {
+        self.level
+    }
+
+    /// Whether analysis compute should be dispatched this frame.
+    pub fn run_analysis(&self) -> bool {
+        self.level == Degradation::Full || self.frame % 2 == 0
+    }
+
+    /// Scale applied to the internal render resolution.
+    pub fn resolution_scale(&self) -> f32 {
+        match self.level {
+            Degradation::ReducedResolution => 0.75,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Fill a skipped analysis frame by continuing the motion from `older` to `newer` by half a step.
+/// Visuals must not lag the audio, so we extrapolate forward instead of interpolating backward.
+/// Magnitudes never go negative.
+pub fn extrapolate(older: &[f32], newer: &[f32], out: &mut [f32]) {
+    for ((o, n), out) in older.iter().zip(newer).zip(out) {
+        *out = (n + 0.5 * (n - o)).max(0.0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BUDGET: f64 = 1.0 / 60.0;
+
+    fn run(g: &mut ThrottleGovernor, frame_time: f64, frames: u32) -> Vec<Degradation> {
+        (0..frames)
+            .filter_map(|_| g.observe(Duration::from_secs_f64(frame_time)))
+            .collect()
+    }
+
+    #[test]
+    fn test_throttle_hitch_ignored() {
+        let mut g = ThrottleGovernor::new(Duration::from_secs_f64(BUDGET));
+        run(&mut g, BUDGET, 100);
+        // A few very long frames move the average but are not sustained.
+        assert!(run(&mut g, 0.1, 5).is_empty());
+        assert!(run(&mut g, BUDGET, 600).is_empty());
+        assert_eq!(g.level(), Degradation::Full);
+    }
+
+    #[test]
+    fn test_throttle_shed_and_restore() {
+        let mut g = ThrottleGovernor::new(Duration::from_secs_f64(BUDGET));
+        // Missing every other vblank.
+        let changes = run(&mut g, 2.0 * BUDGET, 400);
+        assert_eq!(
+            changes,
+            [
+                Degradation::HalfRateAnalysis,
+                Degradation::ReducedResolution
+            ]
+        );
+        let runs = (0..10)
+            .filter(|_| {
+                g.observe(Duration::from_secs_f64(2.0 * BUDGET));
+                g.run_analysis()
+            })
+            .count();
+        assert_eq!(runs, 5);
+
+        // Back on budget is not enough headroom to restore.
+        assert!(run(&mut g, BUDGET, 2000).is_empty());
+        let changes = run(&mut g, 0.5 * BUDGET, 2500);
+        assert_eq!(changes, [Degradation::HalfRateAnalysis, Degradation::Full]);
+        assert!(g.run_analysis());
+    }
+
+    #[test]
+    fn test_throttle_extrapolate() {
+        let mut out = [0.0; 3];
+        extrapolate(&[1.0, 2.0, 1.0], &[2.0, 2.0, 0.1], &mut out);
+        assert_eq!(out, [2.5, 2.0, 0.0]);
+    }
+}
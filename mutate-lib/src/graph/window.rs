@@ -0,0 +1,343 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Windows
+//!
+//! A [`Samples`](super::GraphEvent::Samples) event is one chunk.  Nodes such as spectral analysis
+//! want the last few thousand samples instead, every frame.  Rather than each node accumulating its
+//! own history, a `Samples` edge can keep a [`SampleWindow`] with [`Graph::window`].  The graph
+//! pushes every chunk arriving on the edge into the window before the consumer runs, and the
+//! consumer reads it with [`Frame::window`](super::Frame::window).
+//!
+//! Windows come in two layouts, chosen per edge with [`Windowing`]:
+//!
+//! - [`Windowing::Flat`] keeps the window contiguous, oldest first, by shifting it on every push.
+//!   Simple nodes that hand one slice to an FFT want this.  The shift moves the whole window, which
+//!   adds up for long windows at high frame rates.
+//! - [`Windowing::Ring`] writes in place and never moves anything.  Reads come back as up to two
+//!   slices, split where the ring wraps.
+//!
+//! Either way, [`fresh`](SampleWindow::fresh) is what arrived since the consumer last ran and
+//! [`recycled`](SampleWindow::recycled) is the rest of the window, which it has seen before.
+//! Both are oldest first.  Nodes that only need to process new samples, such as an overlapped
+//! transform, can skip the recycled part.
+//!
+//! Windows are sized in [`Frames`] and hold that many frames of however many channels arrive.  The
+//! slices are interleaved, so a window of `n` frames over stereo reads `2 * n` samples.  When the
+//! channel count changes the window starts over in silence.
+
+use super::schedule::Edge;
+use super::{Graph, NodeId, PortKind};
+use crate::units::Frames;
+use crate::MutateError;
+
+/// Layout of a [`SampleWindow`].  See the [module docs](self).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Windowing {
+    #[default]
+    Flat,
+    Ring,
+}
+
+/// The most recent samples to arrive on an edge.  Starts out full of silence.
+#[derive(Clone, Debug)]
+pub struct SampleWindow {
+    /// Interleaved, `frames` times `channels` long.
+    data: Box<[f32]>,
+    frames: Frames,
+    channels: usize,
+    windowing: Windowing,
+    /// Ring only.  Index of the oldest sample, which is also where the next one is written.
+    head: usize,
+    /// Samples pushed since [`mark_seen`](Self::mark_seen), at most the window length.
+    fresh: usize,
+}
+
+impl SampleWindow {
+    /// A window of `frames` mono frames until something else arrives.
+    pub fn new(frames: Frames, windowing: Windowing) -> Self {
+        Self::silent(frames, 1, windowing)
+    }
+
+    fn silent(frames: Frames, channels: usize, windowing: Windowing) -> Self {
+        Self {
+            data: vec![0.0; frames.interleaved(channels)].into_boxed_slice(),
+            frames,
+            channels,
+            windowing,
+            head: 0,
+            fresh: 0,
+        }
+    }
+
+    pub fn frames(&self) -> Frames {
+        self.frames
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Interleaved samples held.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn windowing(&self) -> Windowing {
+        self.windowing
+    }
+
+    /// Append interleaved frames of `channels` samples, dropping the oldest.  Only the last window
+    /// length of a long push is kept.
+    pub fn push(&mut self, samples: &[f32], channels: usize) {
+        debug_assert_eq!(samples.len() % channels.max(1), 0);
+        if channels != self.channels {
+            *self = Self::silent(self.frames, channels, self.windowing);
+        }
+        let len = self.data.len();
+        let samples = &samples[samples.len().saturating_sub(len)..];
+        let n = samples.len();
+        match self.windowing {
+            Windowing::Flat => {
+                self.data.copy_within(n.., 0);
+                self.data[len - n..].copy_from_slice(samples);
+            }
+            Windowing::Ring => {
+                let first = n.min(len - self.head);
+                self.data[self.head..self.head + first].copy_from_slice(&samples[..first]);
+                self.data[..n - first].copy_from_slice(&samples[first..]);
+                self.head = (self.head + n) % len.max(1);
+            }
+        }
+        self.fresh = (self.fresh + n).min(len);
+    }
+
+    /// The whole window, oldest first.  The second slice is empty for [`Windowing::Flat`].
+    pub fn as_slices(&self) -> (&[f32], &[f32]) {
+        let (newer, older) = self.data.split_at(self.head);
+        (older, newer)
+    }
+
+    /// Samples pushed since the consumer last ran, oldest first.
+    pub fn fresh(&self) -> (&[f32], &[f32]) {
+        self.range(self.len() - self.fresh, self.len())
+    }
+
+    /// The part of the window the consumer has seen before, oldest first.
+    pub fn recycled(&self) -> (&[f32], &[f32]) {
+        self.range(0, self.len() - self.fresh)
+    }
+
+    /// Samples pushed since the consumer last ran.
+    pub fn fresh_len(&self) -> usize {
+        self.fresh
+    }
+
+    /// Start counting fresh samples over.  The graph calls this after the consumer runs.
+    pub fn mark_seen(&mut self) {
+        self.fresh = 0;
+    }
+
+    /// Window positions `start..end`, counted from the oldest sample.
+    fn range(&self, start: usize, end: usize) -> (&[f32], &[f32]) {
+        let (a, b) = self.as_slices();
+        match (start < a.len(), end <= a.len()) {
+            (true, true) => (&a[start..end], &[]),
+            (true, false) => (&a[start..], &b[..end - a.len()]),
+            (false, _) => (&b[start - a.len()..end - a.len()], &[]),
+        }
+    }
+}
+
+impl Graph {
+    /// Keep a window of the last `frames` arriving at input `input` of `to`, which must be
+    /// connected to a [`Samples`](PortKind::Samples) output.  Replaces any window already there.
+    pub fn window(
+        &mut self,
+        to: NodeId,
+        input: &str,
+        frames: Frames,
+        windowing: Windowing,
+    ) -> Result<(), MutateError> {
+        let name = self.node_name(to).to_owned();
+        let invalid = |reason: &str| MutateError::InvalidEdge(format!("{name}.{input}: {reason}"));
+        let inputs = self.nodes[to.0]
+            .runner
+            .as_ref()
+            .map_or(&[][..], |r| r.inputs());
+        let port = inputs
+            .iter()
+            .position(|p| p.name == input)
+            .ok_or_else(|| invalid("no input"))?;
+        if inputs[port].kind != PortKind::Samples {
+            return Err(invalid("windows need samples"));
+        }
+        let edge: &mut Edge = self
+            .edges
+            .iter_mut()
+            .find(|e| e.to == (to.0, port))
+            .ok_or_else(|| invalid("not connected"))?;
+        edge.window = Some(SampleWindow::new(frames, windowing));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::{Frame, GraphEvent, Node, ParamHandle, ParamSpec, Params, PortSpec};
+
+    use std::sync::Arc;
+
+    fn collect((a, b): (&[f32], &[f32])) -> Vec<f32> {
+        a.iter().chain(b).copied().collect()
+    }
+
+    #[test]
+    fn test_layouts_agree() {
+        let mut flat = SampleWindow::new(Frames(8), Windowing::Flat);
+        let mut ring = SampleWindow::new(Frames(8), Windowing::Ring);
+        let mut next = 1.0;
+        for n in [3, 5, 2, 0, 7, 20, 1] {
+            let chunk: Vec<f32> = (0..n).map(|i| next + i as f32).collect();
+            next += n as f32;
+            flat.mark_seen();
+            ring.mark_seen();
+            flat.push(&chunk, 1);
+            ring.push(&chunk, 1);
+
+            assert_eq!(collect(flat.as_slices()), collect(ring.as_slices()));
+            assert_eq!(collect(flat.fresh()), collect(ring.fresh()));
+            assert_eq!(collect(flat.recycled()), collect(ring.recycled()));
+            assert!(flat.as_slices().1.is_empty());
+            // Fresh is the newest samples, up to the window length.
+            let kept = n.min(8);
+            let fresh: Vec<f32> = (0..kept).map(|i| next - (kept - i) as f32).collect();
+            assert_eq!(collect(ring.fresh()), fresh);
+        }
+        let all = collect(ring.as_slices());
+        assert_eq!(
+            all,
+            (0..8).map(|i| next - 8.0 + i as f32).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_fresh_accumulates() {
+        let mut ring = SampleWindow::new(Frames(4), Windowing::Ring);
+        ring.push(&[1.0, 2.0, 3.0], 1);
+        ring.mark_seen();
+        ring.push(&[4.0], 1);
+        ring.push(&[5.0], 1);
+        assert_eq!(ring.fresh_len(), 2);
+        assert_eq!(collect(ring.fresh()), [4.0, 5.0]);
+        assert_eq!(collect(ring.recycled()), [2.0, 3.0]);
+        // The ring wrapped, so the window comes back in two pieces.
+        assert_eq!(ring.as_slices(), (&[2.0, 3.0, 4.0][..], &[5.0][..]));
+    }
+
+    #[test]
+    fn test_window_channels() {
+        // Frames are kept whole, and a new channel count starts over in silence.
+        let mut ring = SampleWindow::new(Frames(2), Windowing::Ring);
+        ring.push(&[1.0], 1);
+        ring.push(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2);
+        assert_eq!(ring.channels(), 2);
+        assert_eq!(ring.len(), 4);
+        assert_eq!(collect(ring.as_slices()), [3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(ring.fresh_len(), 4);
+    }
+
+    /// Emits a chunk counting up from where the last one ended.
+    struct Ramp(f32);
+
+    /// Emits the sum of fresh samples in its window.
+    struct FreshSum;
+
+    impl Params for Ramp {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[]
+        }
+    }
+
+    impl Node for Ramp {
+        fn outputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "out",
+                kind: PortKind::Samples,
+            }]
+        }
+
+        fn attach(&mut self, _params: ParamHandle) {}
+
+        fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+            let frames: Arc<[f32]> = (0..3).map(|i| self.0 + i as f32).collect();
+            self.0 += 3.0;
+            frame.emit(
+                0,
+                GraphEvent::Samples {
+                    frames,
+                    channels: 1,
+                    rate: 48_000,
+                },
+            );
+            Ok(())
+        }
+    }
+
+    impl Params for FreshSum {
+        fn param_specs(&self) -> &'static [ParamSpec] {
+            &[]
+        }
+    }
+
+    impl Node for FreshSum {
+        fn inputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "in",
+                kind: PortKind::Samples,
+            }]
+        }
+
+        fn outputs(&self) -> &'static [PortSpec] {
+            &[PortSpec {
+                name: "sum",
+                kind: PortKind::Scalar,
+            }]
+        }
+
+        fn attach(&mut self, _params: ParamHandle) {}
+
+        fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), MutateError> {
+            if let Some(window) = frame.window(0) {
+                let sum = collect(window.fresh()).iter().sum::<f32>();
+                frame.emit(0, GraphEvent::Scalar(sum as f64));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_edge_window() {
+        let mut graph = Graph::new();
+        let ramp = graph.add("ramp", Ramp(0.0)).unwrap();
+        let sum = graph.add("sum", FreshSum).unwrap();
+        assert!(graph
+            .window(sum, "in", Frames(16), Windowing::Ring)
+            .is_err());
+        graph.connect(ramp, "out", sum, "in").unwrap();
+        graph
+            .window(sum, "in", Frames(16), Windowing::Ring)
+            .unwrap();
+
+        graph.run_frame().unwrap();
+        assert_eq!(graph.output(sum, "sum"), Some(&GraphEvent::Scalar(3.0)));
+        // Only the new chunk is fresh on the next frame.
+        graph.run_frame().unwrap();
+        assert_eq!(graph.output(sum, "sum"), Some(&GraphEvent::Scalar(12.0)));
+    }
+}
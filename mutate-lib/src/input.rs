@@ -0,0 +1,144 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Input
+//!
+//! Keys are bound to named actions so that the frontend, presets, and nodes agree on what a key
+//! does without knowing about each other.  [`Bindings`] maps key names to action names.  Frontends
+//! name the physical key that was pressed, look up its action, and handle it themselves or hand it
+//! to [`Graph::act`](crate::graph::Graph::act).
+//!
+//! Key names are those of the physical key on a US layout: letters and digits as printed, `F` or
+//! `1`, and otherwise names such as `Escape`, `Space`, `Tab`, `ArrowUp`, or `F12`.  Names match
+//! ignoring case.
+//!
+//! Bindings are user-edited TOML, a table of key names to actions layered over the frontend's
+//! defaults.  Binding a key to [`UNBOUND`] removes its default.
+//!
+//! ```toml
+//! P = "pause"
+//! F12 = "screenshot"
+//! Q = "none"
+//! ```
+
+use crate::MutateError;
+
+/// Action that removes a key's binding.
+pub const UNBOUND: &str = "none";
+
+/// Key names and the actions they are bound to.  Each key has at most one action, and an action
+/// may have several keys.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bindings {
+    keys: Vec<(String, String)>,
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_binding(mut self, key: &str, action: &str) -> Self {
+        self.bind(key, action);
+        self
+    }
+
+    /// Bind `key` to `action`, replacing what it was bound to.  Binding to [`UNBOUND`] removes the
+    /// binding.
+    pub fn bind(&mut self, key: &str, action: &str) {
+        self.keys.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+        if action != UNBOUND {
+            self.keys.push((key.to_owned(), action.to_owned()));
+        }
+    }
+
+    /// The action bound to `key`.
+    pub fn action(&self, key: &str) -> Option<&str> {
+        self.keys
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, a)| a.as_str())
+    }
+
+    /// Keys bound to `action`, in the order they were bound.
+    pub fn keys<'a>(&'a self, action: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.keys
+            .iter()
+            .filter(move |(_, a)| a == action)
+            .map(|(k, _)| k.as_str())
+    }
+
+    /// Every binding as key and action, in the order they were bound.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.keys.iter().map(|(k, a)| (k.as_str(), a.as_str()))
+    }
+
+    /// Layer a table of key names to actions over these bindings.  Nothing is bound unless the
+    /// whole table is valid.
+    pub fn apply(&mut self, table: &toml::Table) -> Result<(), MutateError> {
+        let mut parsed = Vec::with_capacity(table.len());
+        for (key, value) in table {
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(MutateError::Bindings(format!("invalid key name `{key}`")));
+            }
+            let action = value
+                .as_str()
+                .filter(|a| !a.trim().is_empty())
+                .ok_or_else(|| MutateError::Bindings(format!("`{key}` must name an action")))?;
+            parsed.push((key, action));
+        }
+        for (key, action) in parsed {
+            self.bind(key, action);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn defaults() -> Bindings {
+        Bindings::new()
+            .with_binding("Q", "quit")
+            .with_binding("Escape", "quit")
+            .with_binding("F", "fullscreen")
+    }
+
+    #[test]
+    fn test_lookup() {
+        let keys = defaults();
+        assert_eq!(keys.action("q"), Some("quit"));
+        assert_eq!(keys.action("ESCAPE"), Some("quit"));
+        assert_eq!(keys.action("P"), None);
+        assert_eq!(keys.keys("quit").collect::<Vec<_>>(), ["Q", "Escape"]);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut keys = defaults();
+        let table: toml::Table = r#"
+            P = "pause"
+            f = "screenshot"
+            Q = "none"
+        "#
+        .parse()
+        .unwrap();
+        keys.apply(&table).unwrap();
+        assert_eq!(keys.action("P"), Some("pause"));
+        assert_eq!(keys.action("F"), Some("screenshot"));
+        assert_eq!(keys.action("Q"), None);
+        assert_eq!(keys.action("Escape"), Some("quit"));
+        assert_eq!(keys.keys("fullscreen").count(), 0);
+    }
+
+    #[test]
+    fn test_apply_rejects() {
+        for text in ["P = 1", "P = \"\"", "\"Page Up\" = \"pause\""] {
+            let mut keys = defaults();
+            let table: toml::Table = text.parse().unwrap();
+            assert!(keys.apply(&table).is_err(), "{text}");
+            assert_eq!(keys, defaults(), "{text}");
+        }
+    }
+}
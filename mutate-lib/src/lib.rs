@@ -6,13 +6,31 @@
 //! Core µTate audio and video recognition & transformation capabilities. Alternative frontends
 //! and applications may be interested in obtaining raw inputs to drive behaviors besides
 //! visualization.  This crate is kept separate so that µTate behaviors can be embedded directly
-//! into 3rd party applications without the need to run a separate daemon.
+//! into 3rd party applications without the need to run a separate daemon.  Applications that would
+//! rather not link it can read the analysis from the **daemon** binary.
 //!
 //! ## Workbench
 //!
 //! This crate also contains the engineering support to design and hardcode filter banks, which is
 //! behind the **workbench** feature.  See the workbench binary and most of its functionality,
 //! within the dsp module.
+//!
+//! ## Modules
+//!
+//! Applications should only need these paths.  Each has a `prelude`, and [`prelude`] gathers
+//! whichever of them the enabled features provide.
+//!
+//! - [`audio`] capture from the audio server, Linux only for now
+//! - [`color`] palettes shared by every node that colors by level
+//! - [`daemon`] analysis published over a Unix socket, behind **daemon**
+//! - [`dsp`] filters and the filter bank, behind **dsp**
+//! - [`gpu`] the Vulkan runtime, behind **vulkan**
+//! - [`graph`] nodes, parameters, and layouts
+//! - [`input`] key bindings to named actions
+//! - [`settings`] the settings file and command line overrides, behind **dsp**
+//! - [`units`] samples, frames, seconds, and Hertz, which `dsp` re-exports
+//!
+//! [`assets`] and [`shutdown`] round out what binaries need.
 // XXX Re-deNY
 #![allow(dead_code)]
 #![allow(unused)]
@@ -20,28 +38,63 @@
 // Just a little fast, accurate tree-sum helper that might find its way into another crate later.
 pub mod tree;
 
+pub mod graph;
+
+pub mod color;
+
+pub mod input;
+
+pub mod shutdown;
+
+pub mod units;
+
+#[cfg(feature = "control")]
+pub mod control;
+
 // You need to break up the audio module into per-platform modules and implement AudioContext.  Only
 // Linux via pipewire is supported right now.  You will support it.  Welcome to open source
 // development.
 #[cfg(target_os = "linux")]
 pub mod audio;
 
+#[cfg(all(feature = "daemon", target_os = "linux"))]
+pub mod daemon;
 #[cfg(feature = "dsp")]
 pub mod dsp;
+#[cfg(feature = "dsp")]
+pub mod export;
+#[cfg(feature = "dsp")]
+pub mod lyrics;
+#[cfg(feature = "dsp")]
+pub mod settings;
 #[cfg(target_os = "linux")]
 use pipewire as pw;
 
 pub use mutate_assets as assets;
+
+/// Embed this crate's assets, such as its shaders, for lookups that miss the filesystem.  Binaries
+/// that ship as one file call this before creating a device.  See [`assets::pack`].
+#[cfg(feature = "embed-assets")]
+pub fn embed_assets() -> Result<(), MutateError> {
+    assets::pack::embed(assets::include_assets!()?);
+    Ok(())
+}
 #[cfg(feature = "vulkan")]
-pub mod vulkan {
-    // NOTE includes __ for macro emissions to resolve via `mutate_lib::vulkan::__` paths.
+pub mod gpu {
+    // NOTE includes __ for macro emissions to resolve via `mutate_lib::gpu::__` paths.
     pub use mutate_vulkan::*;
 }
 
 pub mod prelude {
     pub use crate::MutateError;
+
+    #[cfg(target_os = "linux")]
+    pub use crate::audio::prelude::*;
+    #[cfg(feature = "dsp")]
+    pub use crate::dsp::prelude::*;
     #[cfg(feature = "vulkan")]
-    pub use mutate_vulkan::prelude::*;
+    pub use crate::gpu::prelude::*;
+    pub use crate::graph::prelude::*;
 }
 
 // NEXT Audio will be its own kind of error that must fit into the MutateError hierarchy.
@@ -58,24 +111,111 @@ pub enum MutateError {
     #[error("cannot use dropped audio connection")]
     Dropped,
 
-    #[error("audio connection error: {0}")]
-    AudioConnect(&'static str),
+    /// The thread that owns the audio server connection has exited, so nothing sent to it will be
+    /// answered.
+    #[error("audio thread is gone")]
+    AudioThreadGone,
+    /// A context that should offer a source, such as a file or the demo, offered none.
+    #[error("no audio source to connect to")]
+    NoAudioSource,
+    /// Setting up or retargeting a stream failed below us.
+    #[error("audio stream: {context}: {source}")]
+    AudioStream {
+        context: &'static str,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// A source's format cannot be read, such as a file without channels.
+    #[error("audio format: {0}")]
+    AudioFormat(String),
     #[error("audio thread termination error")]
     AudioTerminate,
 
     #[error("Timeout: {0}")]
     Timeout(&'static str),
 
+    #[error("unknown parameter: {0}")]
+    UnknownParam(String),
+    #[error("invalid value for {name}: {reason}")]
+    InvalidParam {
+        name: &'static str,
+        reason: &'static str,
+    },
+    #[error("invalid or duplicate node name: {0}")]
+    InvalidNode(String),
+    #[error("invalid edge: {0}")]
+    InvalidEdge(String),
+    #[error("gain staging: {0}")]
+    GainStaging(String),
+    #[error("invalid layout: {0}")]
+    InvalidLayout(String),
+    #[error("preset: {0}")]
+    Preset(String),
+    #[error("control mapping: {0}")]
+    ControlMapping(String),
+    #[error("calibration: {0}")]
+    Calibration(String),
+    #[error("bank table: {0}")]
+    BankTable(String),
+    #[error("lyrics: {0}")]
+    Lyrics(String),
+    #[error("palette: {0}")]
+    Palette(String),
+    #[error("key bindings: {0}")]
+    Bindings(String),
+    #[error("config file: {0}")]
+    Config(String),
+    #[error("export: {0}")]
+    Export(String),
+    #[error("layer shell: {0}")]
+    LayerShell(String),
+    #[error("daemon: {0}")]
+    Daemon(String),
+    #[error("invalid pool: {0}")]
+    InvalidPool(String),
+    #[error("pool job panicked or was dropped")]
+    JobPanicked,
+    #[error("signal handler: {0}")]
+    Signal(#[from] ctrlc::Error),
+
     #[error("Assets: {0}")]
     AssetError(#[from] assets::AssetError),
 
     #[cfg(feature = "vulkan")]
     #[error("Vulkan: {0}")]
-    VulkanError(#[from] vulkan::VulkanError),
+    VulkanError(#[from] gpu::VulkanError),
 
-    // NEXT most of these are actually going to be VulkanError
-    #[error("Ash (replace with VulkanError): {0}")]
-    Ash(#[from] ash::vk::Result),
+    /// A raw result from a build without the `vulkan` feature.  With it, results convert into
+    /// [`VulkanError`](gpu::VulkanError) instead so that they can be matched.
+    #[error("Ash: {0}")]
+    Ash(ash::vk::Result),
+}
+
+impl From<ash::vk::Result> for MutateError {
+    fn from(r: ash::vk::Result) -> Self {
+        #[cfg(feature = "vulkan")]
+        return MutateError::VulkanError(r.into());
+        #[cfg(not(feature = "vulkan"))]
+        return MutateError::Ash(r);
+    }
+}
+
+impl MutateError {
+    /// Whether the GPU or host ran out of memory.  See [`VulkanError::is_out_of_memory`].
+    ///
+    /// [`VulkanError::is_out_of_memory`]: gpu::VulkanError::is_out_of_memory
+    pub fn is_out_of_memory(&self) -> bool {
+        match self {
+            #[cfg(feature = "vulkan")]
+            MutateError::VulkanError(e) => e.is_out_of_memory(),
+            MutateError::Ash(r) => matches!(
+                *r,
+                ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY
+                    | ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY
+            ),
+            _ => false,
+        }
+    }
 }
 
 impl<T> From<std::sync::PoisonError<T>> for MutateError {
@@ -0,0 +1,447 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Lyrics
+//!
+//! Timestamped lyrics shown over the visuals, one line at a time, with a karaoke sweep across the
+//! current line.  Lyrics come from LRC files:
+//!
+//! ```text
+//! [ar:Artist]
+//! [offset:+250]
+//! [00:12.00]First line
+//! [00:15.50][01:02.00]A chorus line that repeats
+//! [00:19.20]<00:19.20>Word <00:19.80>timed <00:20.40>line
+//! ```
+//!
+//! Lines with word timestamps, the enhanced LRC format, sweep word by word.  Plain lines sweep in
+//! steps of one beat when the [`BeatPredictor`](crate::dsp::beat::BeatPredictor) is locked and
+//! evenly across the line otherwise.  A line ends where the next begins.  Empty lines are gaps in
+//! which nothing is shown.
+//!
+//! All times are track position, seconds since the start of the song.  The visualizer draws the
+//! [`LyricCue`] of `--lyrics` with its text node, sung text in the bright end of the palette.
+
+// NEXT align with the media clock once it exists.  Callers currently pass track position, which is
+// only known for files and does not survive seeks.
+// NEXT MPRIS players that publish `xesam:asText` only provide unsynced text.  They can seed a
+// `Lyrics` with one untimed line per verse until a synced source is found.
+
+use std::path::Path;
+
+use crate::dsp::beat::BeatPhase;
+use crate::dsp::units::Seconds;
+use crate::graph::{ParamHandle, ParamKind, ParamSpec, ParamValue, Params};
+use crate::MutateError;
+
+/// How long the last line stays up when the file has no `[length:]` tag.
+const LAST_LINE_HOLD: Seconds = Seconds(5.0);
+
+/// One timed word of an enhanced LRC line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Word {
+    pub start: Seconds,
+    /// Byte offset into the line text where this word begins.
+    pub offset: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LyricLine {
+    pub start: Seconds,
+    pub end: Seconds,
+    pub text: String,
+    /// Empty unless the line has word timestamps.
+    pub words: Vec<Word>,
+}
+
+/// The line to display and how much of it has been sung.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LyricCue<'a> {
+    pub index: usize,
+    pub line: &'a LyricLine,
+    /// `0.0..=1.0` fraction of the text, by bytes, that has been sung.
+    pub progress: f64,
+}
+
+impl LyricCue<'_> {
+    /// Split the text into sung and unsung parts at a character boundary.
+    pub fn split(&self) -> (&str, &str) {
+        let text = &self.line.text;
+        let mut at = (self.progress * text.len() as f64).round() as usize;
+        while !text.is_char_boundary(at) {
+            at += 1;
+        }
+        text.split_at(at)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Lyrics {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    /// Sorted by start time.
+    pub lines: Vec<LyricLine>,
+}
+
+impl Lyrics {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MutateError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| MutateError::Lyrics(format!("{}: {e}", path.display())))?;
+        Self::parse(&text)
+    }
+
+    /// Parse LRC text.  Untagged lines and unknown tags are ignored, as players do.
+    pub fn parse(text: &str) -> Result<Self, MutateError> {
+        let mut lyrics = Lyrics::default();
+        // Milliseconds to shift every timestamp earlier.
+        let mut offset = 0.0;
+        let mut length = None;
+        let mut lines = Vec::new();
+
+        for (n, raw) in text.lines().enumerate() {
+            let bad = |msg: &str| MutateError::Lyrics(format!("line {}: {msg}", n + 1));
+            let mut rest = raw.trim();
+            let mut starts = Vec::new();
+            while let Some(tag) = rest.strip_prefix('[') {
+                let close = tag.find(']').ok_or_else(|| bad("unclosed tag"))?;
+                let (tag, after) = (&tag[..close], &tag[close + 1..]);
+                rest = after;
+                if let Some(t) = parse_timestamp(tag) {
+                    starts.push(t);
+                    continue;
+                }
+                let Some((key, value)) = tag.split_once(':') else {
+                    return Err(bad("malformed tag"));
+                };
+                let value = value.trim();
+                match key.trim() {
+                    "ti" => lyrics.title = Some(value.to_owned()),
+                    "ar" => lyrics.artist = Some(value.to_owned()),
+                    "offset" => {
+                        offset = value.parse::<f64>().map_err(|_| bad("bad offset"))?;
+                    }
+                    "length" => length = parse_timestamp(value),
+                    _ if key.trim().chars().all(|c| c.is_ascii_digit()) => {
+                        return Err(bad("malformed timestamp"));
+                    }
+                    _ => {}
+                }
+            }
+            if starts.is_empty() {
+                continue;
+            }
+            let (text, words) = parse_words(rest).ok_or_else(|| bad("malformed word timestamp"))?;
+            for start in starts {
+                lines.push(LyricLine {
+                    start,
+                    end: start,
+                    text: text.clone(),
+                    words: words.clone(),
+                });
+            }
+        }
+
+        if lines.is_empty() {
+            return Err(MutateError::Lyrics("no timed lines".into()));
+        }
+        // NOTE a positive offset makes lyrics appear sooner.
+        let shift = Seconds(offset / 1000.0);
+        for line in &mut lines {
+            line.start = line.start - shift;
+            for word in &mut line.words {
+                word.start = word.start - shift;
+            }
+        }
+        lines.sort_by(|a, b| a.start.0.total_cmp(&b.start.0));
+
+        let last = lines.len() - 1;
+        for i in 0..last {
+            lines[i].end = lines[i + 1].start;
+        }
+        let hold = lines[last].start + LAST_LINE_HOLD;
+        lines[last].end = match length {
+            Some(length) if length - shift > lines[last].start => length - shift,
+            _ => hold,
+        };
+        lyrics.lines = lines;
+        Ok(lyrics)
+    }
+
+    /// Index of the line showing at `now`, skipping gaps.
+    pub fn line_at(&self, now: Seconds) -> Option<usize> {
+        let after = self.lines.partition_point(|l| l.start <= now);
+        let index = after.checked_sub(1)?;
+        let line = &self.lines[index];
+        (now < line.end && !line.text.is_empty()).then_some(index)
+    }
+
+    /// The current line and its sweep.  `beat` quantizes the sweep of lines without word timing.
+    pub fn cue(&self, now: Seconds, beat: Option<BeatPhase>) -> Option<LyricCue<'_>> {
+        let index = self.line_at(now)?;
+        let line = &self.lines[index];
+        let progress = if line.words.is_empty() {
+            line_progress(line, now, beat)
+        } else {
+            word_progress(line, now)
+        };
+        Some(LyricCue {
+            index,
+            line,
+            progress,
+        })
+    }
+}
+
+/// Sweep in beat steps, easing into each step right after the beat lands.
+fn line_progress(line: &LyricLine, now: Seconds, beat: Option<BeatPhase>) -> f64 {
+    let duration = line.end - line.start;
+    let elapsed = now - line.start;
+    let Some(BeatPhase { period, phase }) = beat else {
+        return (elapsed / duration).clamp(0.0, 1.0);
+    };
+    let beats = (duration / period).round().max(1.0);
+    // Whole beats since the line began, counted against the predictor's phase so steps land on the
+    // beat rather than on the line's own timestamp.
+    let whole = (elapsed / period - phase).round().max(0.0);
+    let step = 1.0 - (1.0 - phase).powi(3);
+    ((whole + step) / beats).clamp(0.0, 1.0)
+}
+
+/// Sweep each word linearly between its timestamp and the next.
+fn word_progress(line: &LyricLine, now: Seconds) -> f64 {
+    let len = line.text.len().max(1) as f64;
+    let words = &line.words;
+    let after = words.partition_point(|w| w.start <= now);
+    let Some(current) = after.checked_sub(1) else {
+        return 0.0;
+    };
+    let (from, to, end) = match words.get(current + 1) {
+        Some(next) => (words[current].offset, next.offset, next.start),
+        None => (words[current].offset, line.text.len(), line.end),
+    };
+    let span = end - words[current].start;
+    let within = if span.0 > 0.0 {
+        ((now - words[current].start) / span).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    (from as f64 + within * (to - from) as f64) / len
+}
+
+/// `mm:ss.xx`, `mm:ss.xxx`, or `mm:ss`.
+fn parse_timestamp(tag: &str) -> Option<Seconds> {
+    let (minutes, seconds) = tag.trim().split_once(':')?;
+    if minutes.is_empty() || !minutes.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    if !seconds.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    let minutes: f64 = minutes.parse().ok()?;
+    let seconds: f64 = seconds.parse().ok()?;
+    Some(Seconds(minutes * 60.0 + seconds))
+}
+
+/// Strip `<mm:ss.xx>` word stamps, recording where each word begins in the remaining text.
+fn parse_words(raw: &str) -> Option<(String, Vec<Word>)> {
+    let mut text = String::with_capacity(raw.len());
+    let mut words = Vec::new();
+    let mut rest = raw;
+    while let Some(open) = rest.find('<') {
+        let close = rest[open..].find('>')? + open;
+        let start = parse_timestamp(&rest[open + 1..close])?;
+        text.push_str(&rest[..open]);
+        words.push(Word {
+            start,
+            offset: text.len(),
+        });
+        rest = &rest[close + 1..];
+    }
+    text.push_str(rest);
+
+    // Stamps are usually followed or preceded by the spaces between words.
+    let trimmed = text.trim_start();
+    let lead = text.len() - trimmed.len();
+    let trimmed = trimmed.trim_end().to_owned();
+    for word in &mut words {
+        word.offset = word.offset.saturating_sub(lead).min(trimmed.len());
+    }
+    Some((trimmed, words))
+}
+
+/// Lyrics display.  Holds the loaded lyrics and reads its parameters once per frame.
+pub struct LyricsNode {
+    lyrics: Lyrics,
+    params: Option<ParamHandle>,
+}
+
+impl LyricsNode {
+    const OFFSET: usize = 0;
+    const BEAT_SYNC: usize = 1;
+
+    pub fn new(lyrics: Lyrics) -> Self {
+        Self {
+            lyrics,
+            params: None,
+        }
+    }
+
+    /// Attach the handle returned by [`Graph::register`](crate::graph::Graph::register).
+    pub fn with_params(mut self, params: ParamHandle) -> Self {
+        self.params = Some(params);
+        self
+    }
+
+    pub fn lyrics(&self) -> &Lyrics {
+        &self.lyrics
+    }
+
+    /// Swap lyrics, such as when the track changes.
+    pub fn set_lyrics(&mut self, lyrics: Lyrics) {
+        self.lyrics = lyrics;
+    }
+
+    /// What to draw at track position `now`.
+    pub fn cue(&self, now: Seconds, beat: Option<BeatPhase>) -> Option<LyricCue<'_>> {
+        let (offset, beat_sync) = match &self.params {
+            Some(p) => (p.f64(Self::OFFSET), p.get(Self::BEAT_SYNC).as_bool()),
+            None => (0.0, true),
+        };
+        let beat = beat.filter(|_| beat_sync);
+        self.lyrics.cue(now + Seconds(offset), beat)
+    }
+}
+
+impl Params for LyricsNode {
+    fn param_specs(&self) -> &'static [ParamSpec] {
+        &[
+            ParamSpec {
+                name: "offset",
+                description: "seconds to show lyrics early, negative for late",
+                kind: ParamKind::Float {
+                    min: -5.0,
+                    max: 5.0,
+                },
+                default: ParamValue::Float(0.0),
+            },
+            ParamSpec {
+                name: "beat_sync",
+                description: "sweep untimed lines in beat steps",
+                kind: ParamKind::Bool,
+                default: ParamValue::Bool(true),
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dsp::beat::BeatPredictor;
+    use crate::graph::Graph;
+
+    const SONG: &str = "\
+[ti:Test Song]
+[ar:Nobody]
+[offset:+500]
+[00:10.50]First line
+[00:14.50][00:30.00]Chorus
+
+[00:20.00]
+[00:22.00]<00:22.00>One <00:23.00>two <00:24.00>three
+some credits without a tag
+[length:00:35.00]
+";
+
+    #[test]
+    fn test_lyrics_parse() {
+        let lyrics = Lyrics::parse(SONG).unwrap();
+        assert_eq!(lyrics.title.as_deref(), Some("Test Song"));
+        assert_eq!(lyrics.artist.as_deref(), Some("Nobody"));
+        let starts: Vec<f64> = lyrics.lines.iter().map(|l| l.start.0).collect();
+        // Offset shifts everything half a second earlier and the repeated chorus is sorted in.
+        assert_eq!(starts, [10.0, 14.0, 19.5, 21.5, 29.5]);
+        assert_eq!(lyrics.lines[0].end, Seconds(14.0));
+        assert_eq!(lyrics.lines[4].end, Seconds(34.5));
+
+        let words = &lyrics.lines[3];
+        assert_eq!(words.text, "One two three");
+        let offsets: Vec<usize> = words.words.iter().map(|w| w.offset).collect();
+        assert_eq!(offsets, [0, 4, 8]);
+
+        assert!(Lyrics::parse("no timestamps").is_err());
+        assert!(Lyrics::parse("[00:1x.00]bad").is_err());
+        assert!(Lyrics::parse("[00:01.00]<00:02.00 unclosed").is_err());
+    }
+
+    #[test]
+    fn test_lyrics_line_at() {
+        let lyrics = Lyrics::parse(SONG).unwrap();
+        assert_eq!(lyrics.line_at(Seconds(5.0)), None);
+        assert_eq!(lyrics.line_at(Seconds(10.0)), Some(0));
+        assert_eq!(lyrics.line_at(Seconds(13.9)), Some(0));
+        // The empty line is a gap.
+        assert_eq!(lyrics.line_at(Seconds(20.0)), None);
+        assert_eq!(lyrics.line_at(Seconds(30.0)), Some(4));
+        assert_eq!(lyrics.line_at(Seconds(40.0)), None);
+    }
+
+    #[test]
+    fn test_lyrics_progress() {
+        let lyrics = Lyrics::parse(SONG).unwrap();
+
+        // Untimed line without a beat sweeps evenly.
+        let cue = lyrics.cue(Seconds(12.0), None).unwrap();
+        assert!((cue.progress - 0.5).abs() < 1e-9);
+
+        // Four beats in a four second line.  Just after the second beat lands the sweep has eased
+        // most of the way into its second step.
+        let beat = BeatPhase {
+            period: Seconds(1.0),
+            phase: 0.5,
+        };
+        let cue = lyrics.cue(Seconds(11.5), Some(beat)).unwrap();
+        let expected = (1.0 + 1.0 - 0.5f64.powi(3)) / 4.0;
+        assert!((cue.progress - expected).abs() < 1e-9);
+
+        // Word timing wins over the beat.  Halfway through "two".
+        let cue = lyrics.cue(Seconds(23.0), Some(beat)).unwrap();
+        assert_eq!(cue.index, 3);
+        let expected = (4.0 + 0.5 * 4.0) / 13.0;
+        assert!((cue.progress - expected).abs() < 1e-9);
+        let (sung, unsung) = cue.split();
+        assert_eq!(format!("{sung}{unsung}"), "One two three");
+    }
+
+    #[test]
+    fn test_lyrics_follow_predicted_beats() {
+        let lyrics = Lyrics::parse(SONG).unwrap();
+        // 120 BPM heard before the first line, which starts between beats.
+        let mut predictor = BeatPredictor::new(Seconds(0.0));
+        for k in 0..10 {
+            predictor.observe(Seconds(5.35 + 0.5 * k as f64), 120.0);
+        }
+        let progress = |now: Seconds| lyrics.cue(now, predictor.phase(now)).unwrap().progress;
+        // Eight beats in the four second line.  Each predicted beat steps the sweep by an eighth,
+        // which holds until the next beat.
+        for k in 0..7 {
+            let beat = Seconds(10.35 + 0.5 * k as f64);
+            let before = progress(beat - Seconds(0.02));
+            let after = progress(beat + Seconds(0.4));
+            assert!((before - (k + 1) as f64 / 8.0).abs() < 0.01, "{k} {before}");
+            assert!((after - (k + 2) as f64 / 8.0).abs() < 0.01, "{k} {after}");
+        }
+    }
+
+    #[test]
+    fn test_lyrics_node_params() {
+        let mut graph = Graph::new();
+        let node = LyricsNode::new(Lyrics::parse(SONG).unwrap());
+        let (_, handle) = graph.register("lyrics", &node).unwrap();
+        let node = node.with_params(handle);
+        assert!(node.cue(Seconds(9.5), None).is_none());
+        graph.set("lyrics/offset", ParamValue::Float(1.0)).unwrap();
+        assert_eq!(node.cue(Seconds(9.5), None).unwrap().index, 0);
+    }
+}
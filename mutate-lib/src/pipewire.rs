@@ -37,9 +37,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let running = Arc::new(atomic::AtomicBool::new(true));
 
     let handle = std::thread::spawn(move || {
-        let mut window_buffer = [0u8; 6400];
+        let mut window_buffer = [[0.0f32; 2]; 800];
         let mut window_index = 0usize; // Windex
-        let window_size = 6400; // one 60FPS frame at 48kHz and 8 bytes per frame
+        let window_size = 800; // one 60FPS frame at 48kHz
 
         let mut wrote = false;
 
@@ -83,7 +83,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let avail = rx.occupied();
             if avail > 0 {
                 let slice = &mut window_buffer[window_index..window_size];
-                window_index += rx.read(slice).unwrap();
+                window_index += rx.read_frames(slice).unwrap();
 
                 // If we filled up the entire slice, we can "display" a visual frame.
                 if window_index == window_size {
@@ -91,12 +91,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
                     let (mut last_l, mut last_r) = (0.0, 0.0);
                     let (left_sum, right_sum) = window_buffer
-                        .chunks_exact(8) // 2 samples per frame × 4 bytes = 8 bytes per frame
-                        .map(|frame| {
-                            let left = f32::from_le_bytes(frame[0..4].try_into().unwrap());
-                            let right = f32::from_le_bytes(frame[4..8].try_into().unwrap());
-                            (left, right)
-                        })
+                        .iter()
+                        .map(|&[left, right]| (left, right))
                         .fold((0.0f32, 0.0f32), |(acc_l, acc_r), (l, r)| {
                             // absolute delta + absolute amplitude
                             let accum = (
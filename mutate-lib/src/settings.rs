@@ -0,0 +1,715 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Settings
+//!
+//! Settings that outlive a run, read from a user-edited TOML file.  [`Settings`] holds the sections
+//! this crate understands and passes the frontend's own sections, such as its key bindings, through
+//! as tables.
+//!
+//! ```toml
+//! [audio]
+//! source = "Firefox"
+//! latency = "low"
+//!
+//! [video]
+//! fullscreen = true
+//! vsync = false
+//!
+//! [dsp]
+//! min_freq = 30
+//! max_freq = 16000.0
+//! bins = 96
+//!
+//! [pool]
+//! workers = 4
+//!
+//! [calibration]
+//! offset_db = 113.8
+//! ```
+//!
+//! - `audio.source` the source to listen to, by name or part of one
+//! - `audio.latency` `low`, `balanced`, `relaxed`, or frames per quantum.  See
+//!   [`LatencyHint`](crate::audio::LatencyHint).
+//! - `video.fullscreen` start in fullscreen
+//! - `video.vsync` wait for vertical blank, on unless turned off.  Off asks for `immediate`.
+//! - `video.present_mode` `auto`, `fifo`, `relaxed`, `mailbox`, or `immediate`, over `vsync`
+//! - `dsp.min_freq` and `dsp.max_freq` the range analysis resolves, in Hz
+//! - `dsp.bins` frequency bins across that range
+//! - `pool` the [worker pool](crate::graph::pool) that graph nodes run heavy work on
+//! - `calibration` the [SPL calibration](crate::dsp::calibration) of the input, as `workbench
+//!   calibrate` saves it.  `reference_hz` and `reference_spl` default to a class 1 calibrator.
+//!
+//! ## Layers
+//!
+//! Each [`apply`](Settings::apply) layers a table over what is already set, so the file layers over
+//! the defaults and the command line layers over the file.  Frontends turn their flags into a table
+//! rather than writing fields, so that flags and the file are checked alike.  Frontend sections
+//! layer key by key.
+//!
+//! ## Reloading
+//!
+//! [`search`] finds the file in the XDG config directories, and a [`SettingsWatcher`] notices when
+//! it is edited.  Frontends then read it again, layer their flags over it again, and
+//! [`configure`](Settings::configure) the [`Graph`].  Nodes that watch the DSP keys re-provision
+//! before the next frame.  Audio and video settings are only read when a source is connected or a
+//! window is created, and the pool when the graph is built.
+
+// MAYBE watch the search path when there is no file yet, so that creating one is noticed.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+#[cfg(target_os = "linux")]
+use crate::audio::LatencyHint;
+use crate::dsp::bank::{self, BankTable};
+use crate::dsp::calibration::{Calibration, Levels, CALIBRATOR_SPL, REFERENCE_HZ};
+use crate::dsp::sizing::WindowFit;
+use crate::dsp::units::{Hertz, SampleRate, Seconds};
+use crate::dsp::window::WindowFunction;
+#[cfg(feature = "vulkan")]
+use crate::gpu::present::surface::PresentPreference;
+use crate::graph::pool::PoolConfig;
+use crate::graph::{config, ConfigValue, Graph};
+use crate::MutateError;
+
+/// Bins when `dsp.bins` is not set.
+pub const DEFAULT_BINS: u32 = 64;
+/// Longest rise time of a bank designed from the settings.  Bins are sharper than visuals need in
+/// exchange.
+const MAX_RISE: Seconds = Seconds(0.05);
+
+/// Everything read from the settings file and the command line.  See the [module docs](self).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub audio: AudioSettings,
+    pub video: VideoSettings,
+    pub dsp: DspSettings,
+    pub pool: PoolConfig,
+    /// `None` until the file holds a `[calibration]`.
+    pub calibration: Option<Calibration>,
+    /// Names of the sections the frontend reads.
+    frontend: &'static [&'static str],
+    sections: BTreeMap<String, toml::Table>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AudioSettings {
+    pub source: Option<String>,
+    #[cfg(target_os = "linux")]
+    pub latency: LatencyHint,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VideoSettings {
+    pub fullscreen: bool,
+    pub vsync: bool,
+    #[cfg(feature = "vulkan")]
+    pub present_mode: Option<PresentPreference>,
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            vsync: true,
+            #[cfg(feature = "vulkan")]
+            present_mode: None,
+        }
+    }
+}
+
+impl VideoSettings {
+    /// The present mode to ask for.  `vsync` picks one unless `present_mode` is set.
+    #[cfg(feature = "vulkan")]
+    pub fn present_preference(&self) -> PresentPreference {
+        match (self.present_mode, self.vsync) {
+            (Some(mode), _) => mode,
+            (None, true) => PresentPreference::Auto,
+            (None, false) => PresentPreference::Immediate,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DspSettings {
+    /// Hertz.
+    pub min_freq: f64,
+    /// Hertz.
+    pub max_freq: f64,
+    pub bins: u32,
+}
+
+impl Default for DspSettings {
+    fn default() -> Self {
+        Self {
+            min_freq: crate::dsp::MIN_FREQ_CHEAP_DRIVERS,
+            max_freq: crate::dsp::MAX_FREQ_OLD_PEOPLE,
+            bins: DEFAULT_BINS,
+        }
+    }
+}
+
+impl Settings {
+    /// The defaults.  The frontend names the extra `sections` it reads, and any other section is an
+    /// error.
+    pub fn new(sections: &'static [&'static str]) -> Self {
+        Self {
+            audio: AudioSettings::default(),
+            video: VideoSettings::default(),
+            dsp: DspSettings::default(),
+            pool: PoolConfig::default(),
+            calibration: None,
+            frontend: sections,
+            sections: BTreeMap::new(),
+        }
+    }
+
+    /// The frontend section `name`, as layered so far.
+    pub fn section(&self, name: &str) -> Option<&toml::Table> {
+        self.sections.get(name)
+    }
+
+    /// Layer the file at `path` over these settings.  Errors name the file.
+    pub fn read(&mut self, path: &Path) -> Result<(), MutateError> {
+        let error = |e: String| MutateError::Config(format!("{}: {e}", path.display()));
+        let text = std::fs::read_to_string(path).map_err(|e| error(format!("{e}")))?;
+        self.apply_str(&text).map_err(|e| match e {
+            MutateError::Config(e) => error(e),
+            e => e,
+        })
+    }
+
+    /// Layer TOML `text` over these settings.
+    pub fn apply_str(&mut self, text: &str) -> Result<(), MutateError> {
+        let table: toml::Table = text
+            .parse()
+            .map_err(|e| MutateError::Config(format!("{e}")))?;
+        self.apply(&table)
+    }
+
+    /// Layer a table of sections over these settings.  Nothing changes unless the whole table is
+    /// valid.
+    pub fn apply(&mut self, table: &toml::Table) -> Result<(), MutateError> {
+        let mut layered = self.clone();
+        for (name, value) in table {
+            let toml::Value::Table(section) = value else {
+                return Err(MutateError::Config(format!("`{name}` must be a table")));
+            };
+            match name.as_str() {
+                "audio" => layered.audio.apply(section)?,
+                "video" => layered.video.apply(section)?,
+                "dsp" => layered.dsp.apply(section)?,
+                "pool" => layered.pool.apply(section).map_err(|e| match e {
+                    MutateError::InvalidPool(e) => MutateError::Config(format!("`pool`: {e}")),
+                    e => e,
+                })?,
+                "calibration" => {
+                    layered.calibration = Some(calibration(layered.calibration, section)?)
+                }
+                name if self.frontend.contains(&name) => layered
+                    .sections
+                    .entry(name.to_owned())
+                    .or_default()
+                    .extend(section.clone()),
+                name => return Err(MutateError::Config(format!("unknown setting `{name}`"))),
+            }
+        }
+        if layered.dsp.min_freq >= layered.dsp.max_freq {
+            return Err(MutateError::Config(
+                "`dsp.min_freq` must be below `dsp.max_freq`".into(),
+            ));
+        }
+        *self = layered;
+        Ok(())
+    }
+
+    /// Levels in the units the calibration allows.
+    pub fn levels(&self) -> Levels {
+        Levels::new(self.calibration)
+    }
+
+    /// Write the DSP settings to `graph`, whose nodes watching them update before its next frame.
+    /// Returns whether any of them changed.
+    pub fn configure(&self, graph: &mut Graph) -> bool {
+        let min = graph.configure(config::MIN_FREQ, ConfigValue::Frequency(self.dsp.min_freq));
+        let max = graph.configure(config::MAX_FREQ, ConfigValue::Frequency(self.dsp.max_freq));
+        let bins = graph.configure(config::BINS, ConfigValue::Count(self.dsp.bins));
+        min | max | bins
+    }
+}
+
+impl AudioSettings {
+    fn apply(&mut self, table: &toml::Table) -> Result<(), MutateError> {
+        for (key, value) in table {
+            let name = format!("audio.{key}");
+            match key.as_str() {
+                "source" => self.source = Some(text(&name, value)?.to_owned()),
+                #[cfg(target_os = "linux")]
+                "latency" => {
+                    self.latency = match value {
+                        toml::Value::String(s) if s == "low" => LatencyHint::Low,
+                        toml::Value::String(s) if s == "balanced" => LatencyHint::Balanced,
+                        toml::Value::String(s) if s == "relaxed" => LatencyHint::Relaxed,
+                        toml::Value::Integer(frames) if *frames > 0 => {
+                            LatencyHint::Frames(u32::try_from(*frames).unwrap_or(u32::MAX))
+                        }
+                        _ => {
+                            let what = "`low`, `balanced`, `relaxed`, or frames";
+                            return Err(expected(&name, what, value));
+                        }
+                    }
+                }
+                _ => return Err(unknown(&name)),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl VideoSettings {
+    fn apply(&mut self, table: &toml::Table) -> Result<(), MutateError> {
+        for (key, value) in table {
+            let name = format!("video.{key}");
+            match key.as_str() {
+                "fullscreen" => self.fullscreen = boolean(&name, value)?,
+                "vsync" => self.vsync = boolean(&name, value)?,
+                #[cfg(feature = "vulkan")]
+                "present_mode" => {
+                    let what = "`auto`, `fifo`, `relaxed`, `mailbox`, or `immediate`";
+                    let mode = value.as_str().and_then(|s| s.parse().ok());
+                    self.present_mode = Some(mode.ok_or_else(|| expected(&name, what, value))?);
+                }
+                _ => return Err(unknown(&name)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The window of every bank designed from [`DspSettings`].
+pub const BANK_WINDOW: WindowFunction = WindowFunction::DolphChebyshev {
+    attenuation_db: 60.0,
+};
+
+impl DspSettings {
+    /// A bank across the range at `fs`.  Tables tuned with the workbench can be loaded with
+    /// [`BankTable::from_bytes`] instead.
+    pub fn design_table(&self, fs: SampleRate) -> BankTable {
+        let fit = WindowFit {
+            window: BANK_WINDOW,
+            threshold_db: -3.0,
+            bandwidth_bins: 1.4,
+            spread: 0.0,
+            rise_fraction: 0.6,
+            side_lobe_db: -60.0,
+        };
+        let bins = bank::bins(
+            Hertz(self.min_freq),
+            Hertz(self.max_freq),
+            self.bins as usize,
+        );
+        BankTable::design(&bins, &fit, fs, MAX_RISE)
+    }
+
+    /// Center of each column of the bank [`design_table`](Self::design_table) designs at any rate.
+    pub fn centers(&self) -> Vec<Hertz> {
+        let bins = bank::bins(
+            Hertz(self.min_freq),
+            Hertz(self.max_freq),
+            self.bins as usize,
+        );
+        bins.iter().map(|b| b.center).collect()
+    }
+
+    fn apply(&mut self, table: &toml::Table) -> Result<(), MutateError> {
+        for (key, value) in table {
+            let name = format!("dsp.{key}");
+            match key.as_str() {
+                "min_freq" => self.min_freq = hertz(&name, value)?,
+                "max_freq" => self.max_freq = hertz(&name, value)?,
+                "bins" => {
+                    self.bins = value
+                        .as_integer()
+                        .and_then(|bins| u32::try_from(bins).ok())
+                        .filter(|bins| *bins > 1)
+                        .ok_or_else(|| expected(&name, "a count above one", value))?;
+                }
+                _ => return Err(unknown(&name)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Layer the `calibration` section over `current`.  `offset_db` is needed the first time.
+fn calibration(
+    current: Option<Calibration>,
+    table: &toml::Table,
+) -> Result<Calibration, MutateError> {
+    let mut cal = current.unwrap_or(Calibration {
+        offset_db: f64::NAN,
+        reference_hz: REFERENCE_HZ.get(),
+        reference_spl: CALIBRATOR_SPL,
+    });
+    for (key, value) in table {
+        let name = format!("calibration.{key}");
+        match key.as_str() {
+            "offset_db" => cal.offset_db = decibels(&name, value)?,
+            "reference_hz" => cal.reference_hz = hertz(&name, value)?,
+            "reference_spl" => cal.reference_spl = decibels(&name, value)?,
+            _ => return Err(unknown(&name)),
+        }
+    }
+    if cal.offset_db.is_nan() {
+        return Err(MutateError::Config(
+            "`calibration.offset_db` must be set".into(),
+        ));
+    }
+    Ok(cal)
+}
+
+fn unknown(name: &str) -> MutateError {
+    MutateError::Config(format!("unknown setting `{name}`"))
+}
+
+fn expected(name: &str, what: &str, value: &toml::Value) -> MutateError {
+    MutateError::Config(format!("`{name}` must be {what}, not {value}"))
+}
+
+fn text<'a>(name: &str, value: &'a toml::Value) -> Result<&'a str, MutateError> {
+    value
+        .as_str()
+        .ok_or_else(|| expected(name, "a string", value))
+}
+
+fn boolean(name: &str, value: &toml::Value) -> Result<bool, MutateError> {
+    value
+        .as_bool()
+        .ok_or_else(|| expected(name, "true or false", value))
+}
+
+fn hertz(name: &str, value: &toml::Value) -> Result<f64, MutateError> {
+    let hz = match value {
+        toml::Value::Integer(i) => *i as f64,
+        toml::Value::Float(f) => *f,
+        _ => -1.0,
+    };
+    match hz > 0.0 && hz.is_finite() {
+        true => Ok(hz),
+        false => Err(expected(name, "a frequency in Hz", value)),
+    }
+}
+
+fn decibels(name: &str, value: &toml::Value) -> Result<f64, MutateError> {
+    let db = match value {
+        toml::Value::Integer(i) => *i as f64,
+        toml::Value::Float(f) => *f,
+        _ => f64::NAN,
+    };
+    match db.is_finite() {
+        true => Ok(db),
+        false => Err(expected(name, "a level in dB", value)),
+    }
+}
+
+/// The first `file` in an `app` directory of the XDG config directories.  The user's
+/// `$XDG_CONFIG_HOME`, or `~/.config`, comes before the system's `$XDG_CONFIG_DIRS`, or `/etc/xdg`.
+pub fn search(app: &str, file: &str) -> Option<PathBuf> {
+    config_dirs(|name| std::env::var_os(name))
+        .into_iter()
+        .map(|dir| dir.join(app).join(file))
+        .find(|path| path.is_file())
+}
+
+/// Config directories in search order, reading the environment with `var`.
+fn config_dirs(var: impl Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    // NOTE the XDG spec treats empty variables as unset and says to ignore relative paths.
+    let var = |name: &str| var(name).filter(|v| !v.is_empty());
+    let mut dirs = Vec::new();
+    match var("XDG_CONFIG_HOME") {
+        Some(home) => dirs.push(PathBuf::from(home)),
+        None => dirs.extend(var("HOME").map(|home| Path::new(&home).join(".config"))),
+    }
+    let system = var("XDG_CONFIG_DIRS").unwrap_or_else(|| "/etc/xdg".into());
+    dirs.extend(std::env::split_paths(&system));
+    dirs.retain(|dir| dir.is_absolute());
+    dirs
+}
+
+/// Polls a settings file for edits.
+pub struct SettingsWatcher {
+    path: PathBuf,
+    interval: Duration,
+    last: Option<Instant>,
+    modified: Option<SystemTime>,
+}
+
+impl SettingsWatcher {
+    /// [`poll`](Self::poll) checks at most once per `interval`.
+    pub fn new(path: PathBuf, interval: Duration) -> Self {
+        Self {
+            modified: modified(&path),
+            path,
+            interval,
+            last: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file was written since the last check, unless the last check was less than an
+    /// interval ago.  A missing file is not an edit, so that editors that save by replacing the file
+    /// report one edit once the new file is in place.
+    ///
+    /// An edit can be caught half-written.  Keep the previous settings when the file does not read,
+    /// and the next write reports another edit.
+    pub fn poll(&mut self) -> bool {
+        let now = Instant::now();
+        if self.last.is_some_and(|last| now < last + self.interval) {
+            return false;
+        }
+        self.last = Some(now);
+        let modified = modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SECTIONS: &[&str] = &["keys"];
+
+    #[test]
+    fn test_layers() {
+        let mut settings = Settings::new(SECTIONS);
+        assert_eq!(settings.dsp, DspSettings::default());
+        assert!(settings.video.vsync);
+        settings
+            .apply_str(
+                r#"
+                [audio]
+                source = "Firefox"
+
+                [video]
+                vsync = false
+
+                [dsp]
+                min_freq = 30
+                bins = 96
+
+                [pool]
+                workers = 3
+
+                [keys]
+                P = "pause"
+                Q = "quit"
+                "#,
+            )
+            .unwrap();
+
+        // The command line layers over the file, key by key.
+        let flags: toml::Table = toml::toml! {
+            [audio]
+            source = "mpv"
+
+            [dsp]
+            max_freq = 16000.5
+
+            [pool]
+            reserve = 2
+
+            [keys]
+            Q = "none"
+        };
+        settings.apply(&flags).unwrap();
+
+        assert_eq!(settings.audio.source.as_deref(), Some("mpv"));
+        assert!(!settings.video.vsync);
+        assert!(!settings.video.fullscreen);
+        assert_eq!(
+            settings.dsp,
+            DspSettings {
+                min_freq: 30.0,
+                max_freq: 16_000.5,
+                bins: 96,
+            }
+        );
+        assert_eq!((settings.pool.workers, settings.pool.reserve), (3, 2));
+        let keys = settings.section("keys").unwrap();
+        assert_eq!(keys["P"].as_str(), Some("pause"));
+        assert_eq!(keys["Q"].as_str(), Some("none"));
+        assert!(settings.section("scenes").is_none());
+    }
+
+    #[test]
+    fn test_rejects() {
+        let mut settings = Settings::new(SECTIONS);
+        let before = settings.clone();
+        for text in [
+            "[scenes]\nstart = \"ring\"",
+            "audio = 1",
+            "[audio]\nsorce = \"mic\"",
+            "[audio]\nsource = 1",
+            "[audio]\nlatency = \"slow\"",
+            "[video]\nfullscreen = \"yes\"",
+            "[dsp]\nmin_freq = -20",
+            "[dsp]\nbins = 1",
+            "[dsp]\nmin_freq = 20000",
+            "[pool]\nworkers = 0",
+            "[calibration]\nreference_spl = 94",
+            "[calibration]\noffset_db = \"loud\"",
+            "[calibration]\noffset_db = 110\ngain = 1",
+            // The valid section is not applied either.
+            "[video]\nfullscreen = true\n[dsp]\nmax_freq = \"high\"",
+        ] {
+            let error = settings.apply_str(text).unwrap_err();
+            assert!(matches!(error, MutateError::Config(_)), "{text}: {error}");
+        }
+        assert_eq!(settings, before);
+    }
+
+    #[test]
+    fn test_calibration() {
+        let mut settings = Settings::new(&[]);
+        assert!(!settings.levels().is_calibrated());
+        // What `workbench calibrate --save` writes goes in the file as it is.
+        let saved = Calibration {
+            offset_db: 113.8,
+            reference_hz: 1000.0,
+            reference_spl: 94.0,
+        };
+        settings.apply_str(&saved.to_toml()).unwrap();
+        assert_eq!(settings.calibration, Some(saved));
+        settings
+            .apply_str("[calibration]\noffset_db = 110")
+            .unwrap();
+        let layered = settings.calibration.unwrap();
+        assert_eq!((layered.offset_db, layered.reference_spl), (110.0, 94.0));
+        assert_eq!(settings.levels().mode(), "calibrated");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_latency() {
+        let mut settings = Settings::new(&[]);
+        assert_eq!(settings.audio.latency, LatencyHint::Balanced);
+        settings.apply_str("[audio]\nlatency = \"low\"").unwrap();
+        assert_eq!(settings.audio.latency, LatencyHint::Low);
+        settings.apply_str("[audio]\nlatency = 1024").unwrap();
+        assert_eq!(settings.audio.latency, LatencyHint::Frames(1024));
+        assert!(settings.apply_str("[audio]\nlatency = 0").is_err());
+    }
+
+    #[cfg(feature = "vulkan")]
+    #[test]
+    fn test_present_mode() {
+        let mut settings = Settings::new(&[]);
+        assert_eq!(settings.video.present_preference(), PresentPreference::Auto);
+        settings.apply_str("[video]\nvsync = false").unwrap();
+        assert_eq!(
+            settings.video.present_preference(),
+            PresentPreference::Immediate
+        );
+        settings
+            .apply_str("[video]\npresent_mode = \"mailbox\"")
+            .unwrap();
+        assert_eq!(
+            settings.video.present_preference(),
+            PresentPreference::Mailbox
+        );
+        assert!(settings
+            .apply_str("[video]\npresent_mode = \"tearing\"")
+            .is_err());
+    }
+
+    #[test]
+    fn test_configure() {
+        let mut graph = Graph::new();
+        let mut settings = Settings::new(&[]);
+        assert!(settings.configure(&mut graph));
+        assert!(!settings.configure(&mut graph));
+        settings.apply_str("[dsp]\nbins = 128").unwrap();
+        assert!(settings.configure(&mut graph));
+        let config = graph.config();
+        assert_eq!(config.count(config::BINS), Some(128));
+        assert_eq!(
+            config.frequency(config::MIN_FREQ),
+            Some(crate::dsp::MIN_FREQ_CHEAP_DRIVERS)
+        );
+    }
+
+    #[test]
+    fn test_config_dirs() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| OsString::from(v))
+            }
+        };
+        assert_eq!(
+            config_dirs(env(&[("HOME", "/home/mu")])),
+            [Path::new("/home/mu/.config"), Path::new("/etc/xdg")]
+        );
+        assert_eq!(
+            config_dirs(env(&[
+                ("HOME", "/home/mu"),
+                ("XDG_CONFIG_HOME", "/cfg"),
+                ("XDG_CONFIG_DIRS", "/a:relative:/b"),
+            ])),
+            [Path::new("/cfg"), Path::new("/a"), Path::new("/b")]
+        );
+        assert_eq!(
+            config_dirs(env(&[("XDG_CONFIG_HOME", ""), ("XDG_CONFIG_DIRS", "")])),
+            [Path::new("/etc/xdg")]
+        );
+    }
+
+    #[test]
+    fn test_watcher() {
+        let dir =
+            std::env::temp_dir().join(format!("mutate-settings-{}-watch", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mutate.toml");
+        // Make sure the modification time moves, even on coarse filesystems.
+        let write = |text: &str| {
+            let before = modified(&path);
+            std::fs::write(&path, text).unwrap();
+            while modified(&path) == before {
+                std::thread::sleep(Duration::from_millis(10));
+                std::fs::write(&path, text).unwrap();
+            }
+        };
+        write("[dsp]\nbins = 32\n");
+
+        let mut watcher = SettingsWatcher::new(path.clone(), Duration::ZERO);
+        assert!(!watcher.poll());
+        write("[dsp]\nbins = 48\n");
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(!watcher.poll());
+        write("[dsp]\nbins = 64\n");
+        assert!(watcher.poll());
+
+        let mut settings = Settings::new(&[]);
+        settings.read(watcher.path()).unwrap();
+        assert_eq!(settings.dsp.bins, 64);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Shutdown
+//!
+//! Killing the process outright skips joining the audio thread and destroying device objects,
+//! which occasionally leaves audio server streams wedged.  Binaries call [`install`] once at
+//! startup.  SIGINT and SIGTERM then only set a process-wide flag, and the program polls
+//! [`requested`] at a point where it can tear down in order.
+//!
+//! A second signal exits immediately, so a teardown that hangs can still be interrupted.
+
+// NEXT the visualizer relies on `AudioContext` drop to join the audio thread after teardown.  An
+// explicit shutdown on the context would let the order be spelled out where it matters.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::MutateError;
+
+/// Exit status for a second signal, the shell convention for SIGINT.
+const FORCED_EXIT: i32 = 130;
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Route SIGINT and SIGTERM to [`requested`].  Call once, before spawning threads.
+pub fn install() -> Result<(), MutateError> {
+    Ok(ctrlc::set_handler(|| {
+        if REQUESTED.swap(true, Ordering::AcqRel) {
+            eprintln!("second signal, exiting without cleanup");
+            std::process::exit(FORCED_EXIT);
+        }
+        eprintln!("shutting down, signal again to force");
+    })?)
+}
+
+/// Ask for the same orderly shutdown that a signal would, such as from a quit key.
+pub fn request() {
+    REQUESTED.store(true, Ordering::Release);
+}
+
+/// Whether a shutdown was requested.  Cheap enough to check every frame or loop iteration.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Acquire)
+}
@@ -0,0 +1,322 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Units
+//!
+//! Counting waves, samples, frames, and seconds with bare `f64` and `usize` invites the kind of bug
+//! where a wave count is divided by a sample count and the output looks *almost* right.  The
+//! newtypes in this module make the compiler check which quantity we are holding.
+//!
+//! - [`Samples`] counts scalar values in a single channel, which is what every [`Filter`] consumes.
+//! - [`Frames`] counts instants in a multi-channel stream.  One frame holds one sample per channel.
+//! - [`Seconds`] is wall time.
+//! - [`Hertz`] is a frequency such as a filter center or a tone being generated.
+//! - [`SampleRate`] is the rate of frames per second and owns all conversions between time, counts,
+//!   and frequency.
+//!
+//! Arithmetic is only implemented where the result keeps the same unit, scaling by a bare `f64` or
+//! adding like to like.  Anything that changes the unit goes through a named method on
+//! [`SampleRate`].
+//!
+//! These live outside `dsp` so that the graph can size its buffers with them.
+//!
+//! [`Filter`]: crate::dsp::Filter
+
+// NEXT audio imports size their device buffers with bare `u32` sample counts.  Those are frames.
+// MAYBE `Samples` and `Frames` are always whole.  Fractional counts only appear in intermediate
+// math and are kept as `f64` until rounded with an explicit policy.
+
+use std::ops::{Add, AddAssign, Div, Mul, Sub};
+
+/// A count of scalar values within one channel.  Interleaved buffers use [`Frames`] instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Samples(pub usize);
+
+/// A count of instants across all channels of a stream.  Interleaved storage for `n` frames of `c`
+/// channels holds `n * c` values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Frames(pub usize);
+
+/// Wall time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Seconds(pub f64);
+
+/// Cycles per second of some signal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Hertz(pub f64);
+
+/// Frames per second.  Each channel receives one sample per frame, so for a single channel this is
+/// also samples per second.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct SampleRate(pub f64);
+
+impl Samples {
+    pub fn get(self) -> usize {
+        self.0
+    }
+
+    /// Frames needed to hold this many samples in every channel.  One sample per frame.
+    pub fn frames(self) -> Frames {
+        Frames(self.0)
+    }
+}
+
+impl Frames {
+    pub fn get(self) -> usize {
+        self.0
+    }
+
+    /// Samples within one channel.  One per frame.
+    pub fn samples(self) -> Samples {
+        Samples(self.0)
+    }
+
+    /// Total scalar values when `channels` are interleaved.
+    pub fn interleaved(self, channels: usize) -> usize {
+        self.0 * channels
+    }
+}
+
+impl Seconds {
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+impl Hertz {
+    pub fn get(self) -> f64 {
+        self.0
+    }
+
+    /// Duration of one cycle.
+    pub fn period(self) -> Seconds {
+        Seconds(1.0 / self.0)
+    }
+}
+
+impl SampleRate {
+    pub fn get(self) -> f64 {
+        self.0
+    }
+
+    /// The rate expressed as a frequency, for filter math that wants `fs` as a bare `f64`.
+    pub fn hz(self) -> Hertz {
+        Hertz(self.0)
+    }
+
+    /// The highest frequency this rate can represent.
+    pub fn nyquist(self) -> Hertz {
+        Hertz(self.0 / 2.0)
+    }
+
+    /// Fractional samples in one cycle of `freq`.
+    pub fn per_cycle(self, freq: Hertz) -> f64 {
+        self.0 / freq.0
+    }
+
+    /// Whole samples required to cover `cycles` full waves of `freq`.  Rounds up so that the
+    /// requested number of waves always fits.
+    pub fn cycles(self, freq: Hertz, cycles: f64) -> Samples {
+        Samples((self.per_cycle(freq) * cycles).ceil() as usize)
+    }
+
+    /// How many cycles of `freq` pass during `samples`.
+    pub fn waves(self, freq: Hertz, samples: Samples) -> f64 {
+        samples.0 as f64 / self.per_cycle(freq)
+    }
+
+    /// Whole samples required to cover `time`.  Rounds up.
+    pub fn samples(self, time: Seconds) -> Samples {
+        Samples((time.0 * self.0).ceil() as usize)
+    }
+
+    /// Whole frames required to cover `time`.  Rounds up.
+    pub fn frames(self, time: Seconds) -> Frames {
+        Frames((time.0 * self.0).ceil() as usize)
+    }
+
+    /// Time spanned by `samples` in one channel.
+    pub fn seconds(self, samples: Samples) -> Seconds {
+        Seconds(samples.0 as f64 / self.0)
+    }
+
+    /// Angular velocity of `freq` in radians per sample.
+    pub fn omega(self, freq: Hertz) -> f64 {
+        std::f64::consts::TAU * freq.0 / self.0
+    }
+}
+
+impl Default for SampleRate {
+    fn default() -> Self {
+        SampleRate(48_000.0)
+    }
+}
+
+// Literals are easiest to write as bare floats.  These conversions let call sites opt in without
+// also letting a `Hertz` silently become a `SampleRate`.
+impl From<f64> for Hertz {
+    fn from(hz: f64) -> Self {
+        Hertz(hz)
+    }
+}
+
+impl From<f64> for SampleRate {
+    fn from(fs: f64) -> Self {
+        SampleRate(fs)
+    }
+}
+
+impl From<u32> for SampleRate {
+    fn from(fs: u32) -> Self {
+        SampleRate(fs as f64)
+    }
+}
+
+impl From<f64> for Seconds {
+    fn from(s: f64) -> Self {
+        Seconds(s)
+    }
+}
+
+impl From<std::time::Duration> for Seconds {
+    fn from(d: std::time::Duration) -> Self {
+        Seconds(d.as_secs_f64())
+    }
+}
+
+/// Same-unit arithmetic.  Scaling by `f64` and adding like quantities never changes the unit.
+macro_rules! impl_scalar_ops {
+    ($($ty:ty),*) => {
+        $(
+            impl Mul<f64> for $ty {
+                type Output = $ty;
+                fn mul(self, rhs: f64) -> $ty {
+                    Self(self.0 * rhs)
+                }
+            }
+
+            impl Div<f64> for $ty {
+                type Output = $ty;
+                fn div(self, rhs: f64) -> $ty {
+                    Self(self.0 / rhs)
+                }
+            }
+
+            /// The dimensionless ratio of two like quantities.
+            impl Div for $ty {
+                type Output = f64;
+                fn div(self, rhs: $ty) -> f64 {
+                    self.0 / rhs.0
+                }
+            }
+
+            impl Add for $ty {
+                type Output = $ty;
+                fn add(self, rhs: $ty) -> $ty {
+                    Self(self.0 + rhs.0)
+                }
+            }
+
+            impl Sub for $ty {
+                type Output = $ty;
+                fn sub(self, rhs: $ty) -> $ty {
+                    Self(self.0 - rhs.0)
+                }
+            }
+
+            impl std::fmt::Display for $ty {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    std::fmt::Display::fmt(&self.0, f)
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar_ops!(Seconds, Hertz, SampleRate);
+
+macro_rules! impl_count_ops {
+    ($($ty:ty),*) => {
+        $(
+            impl Add for $ty {
+                type Output = $ty;
+                fn add(self, rhs: $ty) -> $ty {
+                    Self(self.0 + rhs.0)
+                }
+            }
+
+            impl AddAssign for $ty {
+                fn add_assign(&mut self, rhs: $ty) {
+                    self.0 += rhs.0;
+                }
+            }
+
+            impl Sub for $ty {
+                type Output = $ty;
+                fn sub(self, rhs: $ty) -> $ty {
+                    Self(self.0 - rhs.0)
+                }
+            }
+
+            impl Mul<usize> for $ty {
+                type Output = $ty;
+                fn mul(self, rhs: usize) -> $ty {
+                    Self(self.0 * rhs)
+                }
+            }
+
+            impl std::fmt::Display for $ty {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    std::fmt::Display::fmt(&self.0, f)
+                }
+            }
+        )*
+    };
+}
+
+impl_count_ops!(Samples, Frames);
+
+/// Normalized frequency in cycles per sample.  This is the argument most filter designs want.
+impl Div<SampleRate> for Hertz {
+    type Output = f64;
+    fn div(self, rhs: SampleRate) -> f64 {
+        self.0 / rhs.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_units_round_trip() {
+        let fs = SampleRate(48_000.0);
+        let one = fs.samples(Seconds(1.0));
+        assert_eq!(one, Samples(48_000));
+        assert_eq!(fs.seconds(one), Seconds(1.0));
+        assert_eq!(fs.nyquist(), Hertz(24_000.0));
+
+        // A partial sample rounds up so the full duration fits.
+        assert_eq!(fs.samples(Seconds(1.0 / 96_000.0)), Samples(1));
+    }
+
+    #[test]
+    fn test_units_cycles() {
+        let fs = SampleRate(48_000.0);
+        let f0 = Hertz(1000.0);
+        assert_eq!(fs.per_cycle(f0), 48.0);
+        assert_eq!(fs.cycles(f0, 0.5), Samples(24));
+        assert_eq!(fs.cycles(Hertz(123.0), 1.0), Samples(391));
+        assert_eq!(fs.waves(f0, Samples(480)), 10.0);
+        assert_eq!(f0 / fs, 1.0 / 48.0);
+        assert_eq!(f0.period(), Seconds(0.001));
+    }
+
+    #[test]
+    fn test_units_frames() {
+        let frames = Frames(512);
+        assert_eq!(frames.interleaved(2), 1024);
+        assert_eq!(frames.samples(), Samples(512));
+        assert_eq!(SampleRate(48_000.0).frames(Seconds(0.5)), Frames(24_000));
+    }
+}
@@ -4,14 +4,14 @@
 #![cfg(feature = "vulkan")]
 
 use ash::vk;
-use mutate_lib::vulkan;
+use mutate_lib::gpu;
 
 #[test]
 fn test_context_lifecycle() {
-    vulkan::with_context!(|device| {});
+    gpu::with_context!(|device| {});
 }
 
 #[test]
 fn test_device_lifecycle() {
-    vulkan::with_context!(|instance, device| {});
+    gpu::with_context!(|instance, device| {});
 }
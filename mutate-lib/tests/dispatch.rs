@@ -31,7 +31,7 @@ fn dispatch_increment_read_back() {
         let mut output_buffer = buffer::MappedAllocation::<u32>::new(1, &device).unwrap();
         output_buffer.as_mut_slice()[0] = 41;
         output_buffer.flush(&device).unwrap();
-        let output_idx: SsboIdx = output_buffer.bound(&mut device);
+        let output_idx: SsboIdx = output_buffer.register(&device).unwrap().index();
 
         let pipeline = ComputePipeline::<IncrementPipeline>::new(&device).unwrap();
         pipeline.push(
@@ -60,6 +60,7 @@ fn dispatch_increment_read_back() {
 
         // Synchronize and submit
         let done = cb.end(&device).unwrap();
+        device.descriptors.flush(device.as_raw());
         let mut semaphore = device.make_timeline_semaphore().unwrap();
         let intent = semaphore.next_signal();
         let wait_value = intent.wait_value();
@@ -4,12 +4,12 @@
 #![cfg(feature = "vulkan")]
 
 use ash::vk;
-use mutate_lib::vulkan;
+use mutate_lib::gpu;
 
 #[test]
 fn image_lifecycle() {
-    use vulkan::resource::image;
-    vulkan::with_context!(|device| {
+    use gpu::resource::image;
+    gpu::with_context!(|device| {
         let extent = vk::Extent2D {
             width: 1,
             height: 1,
@@ -21,10 +21,59 @@ fn image_lifecycle() {
     })
 }
 
+#[test]
+fn image_register() {
+    use gpu::device::descriptors::RETIRE_FLUSHES;
+    use gpu::resource::image;
+    gpu::with_context!(|device| {
+        let extent = vk::Extent2D {
+            width: 4,
+            height: 4,
+        };
+        let format = vk::Format::R8G8B8A8_UNORM;
+        let flags = vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::STORAGE;
+        let image = image::Image::new(&device, extent, format, flags).unwrap();
+        let view = image.default_view(&device).unwrap();
+        let descriptors = &device.descriptors;
+
+        let sampled = descriptors
+            .register_sampled_image(view.view, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .unwrap();
+        let storage = descriptors.register_storage_image(view.view).unwrap();
+        descriptors.flush(device.as_raw());
+
+        // Released slots are held while earlier frames may still read them.
+        descriptors.release(sampled);
+        descriptors.release(storage);
+        let other = descriptors
+            .register_sampled_image(view.view, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .unwrap();
+        assert_ne!(other.index(), sampled.index());
+        for _ in 0..RETIRE_FLUSHES {
+            descriptors.flush(device.as_raw());
+        }
+        let reused = descriptors
+            .register_sampled_image(view.view, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .unwrap();
+        assert_eq!(reused.index(), sampled.index());
+        assert_ne!(reused.generation(), sampled.generation());
+        // The released handle no longer names the slot.
+        assert_eq!(descriptors.resolve(sampled), None);
+        assert_eq!(descriptors.resolve(reused), Some(reused.index()));
+        let storage_again = descriptors.register_storage_image(view.view).unwrap();
+        assert_eq!(storage_again.index(), storage.index());
+        descriptors.flush(device.as_raw());
+
+        device.wait_idle().unwrap();
+        view.destroy(&device).unwrap();
+        image.destroy(&device).unwrap();
+    })
+}
+
 #[test]
 fn buffer_lifecycle() {
-    use vulkan::resource::buffer;
-    vulkan::with_context!(|device| {
+    use gpu::resource::buffer;
+    gpu::with_context!(|device| {
         let buffer = buffer::MappedAllocation::<u8>::new(1, &device).unwrap();
         buffer.destroy(&device).unwrap();
     })
@@ -32,19 +81,21 @@ fn buffer_lifecycle() {
 
 #[test]
 fn buffer_bind() {
-    use vulkan::resource::buffer;
-    vulkan::with_context!(|device| {
+    use gpu::resource::buffer;
+    gpu::with_context!(|device| {
         let buffer = buffer::MappedAllocation::<u8>::new(1, &device).unwrap();
-        let index = buffer.bound(&mut device);
-        println!("buffer bound to descriptor slot: {:?}", index);
+        let handle = buffer.register(&device).unwrap();
+        println!("buffer registered to descriptor slot: {:?}", handle.index());
+        device.descriptors.flush(device.as_raw());
+        device.descriptors.release(handle);
         buffer.destroy(&device).unwrap();
     })
 }
 
 #[test]
 fn buffer_device_address() {
-    use vulkan::resource::buffer;
-    vulkan::with_context!(|device| {
+    use gpu::resource::buffer;
+    gpu::with_context!(|device| {
         let buffer = buffer::MappedAllocation::<u8>::new(1, &device).unwrap();
         let device_address = buffer.device_address(&device);
         buffer.destroy(&device).unwrap();
@@ -54,8 +105,8 @@ fn buffer_device_address() {
 // XXX incomplete without dispatch
 #[test]
 fn buffer_readback() {
-    use vulkan::resource::buffer;
-    vulkan::with_context!(|device| {
+    use gpu::resource::buffer;
+    gpu::with_context!(|device| {
         let mut buffer = buffer::MappedAllocation::<u8>::new(1, &device).unwrap();
         buffer.as_mut_slice()[0] = 255;
         buffer.flush(&device).unwrap();
@@ -69,7 +120,38 @@ fn buffer_readback() {
 
 #[test]
 fn shader_load() {
-    vulkan::with_context!(|device| {
-        let shader = vulkan::resource::shader::ShaderModule::load(&device, "test/compute");
+    gpu::with_context!(|device| {
+        let shader = gpu::resource::shader::ShaderModule::load(&device, "test/compute");
+    })
+}
+
+#[test]
+fn consumer_feeds_inlet() {
+    use mutate_lib::audio::{import::Consumer, node::AudioInlet};
+    use mutate_lib::graph::{Graph, GraphEvent};
+    gpu::with_context!(|device| {
+        let mut consumer = Consumer::<2>::offline(&device, 64, 48_000).unwrap();
+        let inlet = AudioInlet::new(2);
+        assert!(consumer.feed(&AudioInlet::new(1)).is_err());
+        consumer.feed(&inlet).unwrap();
+        consumer.feed(&inlet.clone()).unwrap();
+
+        let mut graph = Graph::new();
+        let audio = graph.add("audio", inlet.node()).unwrap();
+        consumer.push(&device, &[[0.25, -0.25]; 16]).unwrap();
+        graph.run_frame().unwrap();
+        match graph.output(audio, "output") {
+            Some(GraphEvent::Samples { frames, rate, .. }) => {
+                assert_eq!(frames.len(), 32);
+                assert_eq!(*rate, 48_000);
+            }
+            other => panic!("{other:?}"),
+        }
+
+        consumer.unfeed(&inlet).unwrap();
+        consumer.push(&device, &[[0.25, -0.25]; 16]).unwrap();
+        graph.run_frame().unwrap();
+        assert_eq!(graph.output(audio, "output"), None);
+        consumer.destroy(&device).unwrap();
     })
 }
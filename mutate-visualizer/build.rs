@@ -6,4 +6,5 @@ use mutate_assets as assets;
 fn main() {
     assets::build::set_asset_default_dir();
     assets::build::build_shaders();
+    assets::build::write_pack();
 }
@@ -0,0 +1,154 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Beats
+//!
+//! Beats heard in what plays, predicted far enough ahead to be seen when they are heard.  [`Beats`]
+//! reads its own [`AudioInlet`] and tracks beats over filter bank columns on the host, the way the
+//! export overlays do.  Each window hands it the audio to photon latency measured by its
+//! [`FrameStats`](utate::graph::FrameStats) before asking, so predictions lead by what that window
+//! actually shows.
+//!
+//! Times are seconds of audio pushed through the inlet.  The newest audio read is "now", and the
+//! frame drawn from it is seen one latency later.
+
+use std::time::Duration;
+
+use mutate_lib as utate;
+use utate::audio::node::AudioInlet;
+use utate::dsp::beat::{BeatEvent, BeatPhase, BeatPredictor, BeatTracker};
+use utate::dsp::spectrogram::Spectrogram;
+use utate::dsp::units::{SampleRate, Samples, Seconds};
+use utate::settings::DspSettings;
+
+/// Columns of the bank per second, which is also the rate beats are tracked at.
+const COLUMN_RATE: f64 = 100.0;
+
+/// The bank and trackers for one stream rate.
+struct Tracking {
+    rate: u32,
+    spectrogram: Spectrogram,
+    /// Frames per column.
+    hop: usize,
+    /// Frames since the last column.
+    since: usize,
+    /// Frames pushed.
+    total: u64,
+    tracker: BeatTracker,
+}
+
+pub struct Beats {
+    inlet: AudioInlet,
+    dsp: DspSettings,
+    tracking: Option<Tracking>,
+    predictor: BeatPredictor,
+    latency: Seconds,
+    /// The beat whose visual fired last.
+    fired: Option<Seconds>,
+}
+
+impl Beats {
+    /// Bins spaced as `dsp` asks.
+    pub fn new(dsp: &DspSettings) -> Self {
+        Self {
+            inlet: AudioInlet::new(2),
+            dsp: dsp.clone(),
+            tracking: None,
+            predictor: BeatPredictor::new(Seconds(0.0)),
+            latency: Seconds(0.0),
+            fired: None,
+        }
+    }
+
+    /// Where the host pushes what plays.  See [`Audio::feed`](super::Audio::feed).
+    pub fn inlet(&self) -> &AudioInlet {
+        &self.inlet
+    }
+
+    /// Track what arrived since the last call, starting over when the stream's rate changes.
+    pub fn update(&mut self) {
+        let Some((samples, rate)) = self.inlet.take() else {
+            return;
+        };
+        if self.tracking.as_ref().is_none_or(|t| t.rate != rate) {
+            let fs = SampleRate(rate as f64);
+            let hop = (fs.get() / COLUMN_RATE).round().max(1.0) as usize;
+            let spectrogram = Spectrogram::new(self.dsp.design_table(fs), Samples(hop))
+                .with_channels(self.inlet.channels())
+                .without_rows();
+            self.tracking = Some(Tracking {
+                rate,
+                spectrogram,
+                hop,
+                since: 0,
+                total: 0,
+                tracker: BeatTracker::new(fs.get() / hop as f64),
+            });
+            self.predictor = BeatPredictor::new(self.latency);
+            self.fired = None;
+        }
+        let Some(t) = &mut self.tracking else {
+            return;
+        };
+        let channels = self.inlet.channels();
+        let mut rest = &samples[..samples.len() - samples.len() % channels];
+        while !rest.is_empty() {
+            let take = ((t.hop - t.since) * channels).min(rest.len());
+            let (chunk, tail) = rest.split_at(take);
+            t.spectrogram.push(chunk);
+            t.since += chunk.len() / channels;
+            t.total += (chunk.len() / channels) as u64;
+            if t.since == t.hop {
+                t.since = 0;
+                let at = Seconds(t.total as f64 / t.rate as f64);
+                if let Some(beat) = t.tracker.push(at, t.spectrogram.latest()) {
+                    self.predictor.observe(beat.at, beat.bpm);
+                }
+            }
+            rest = tail;
+        }
+    }
+
+    /// Lead predictions by a window's audio to photon latency, such as the median of
+    /// [`FrameStats::audio_to_photon`](utate::graph::FrameStats::audio_to_photon).
+    pub fn set_latency(&mut self, latency: Duration) {
+        self.latency = Seconds(latency.as_secs_f64());
+        self.predictor.set_latency(self.latency);
+    }
+
+    /// How long after the frame drawn now is seen, as last set.
+    pub fn latency(&self) -> Seconds {
+        self.latency
+    }
+
+    /// The newest audio tracked.
+    fn now(&self) -> Seconds {
+        match &self.tracking {
+            Some(t) => Seconds(t.total as f64 / t.rate as f64),
+            None => Seconds(0.0),
+        }
+    }
+
+    /// Where the frame drawn now falls in the beat when it is seen.  `None` until the beats agree.
+    pub fn phase(&self) -> Option<BeatPhase> {
+        self.predictor.phase(self.now() + self.latency)
+    }
+
+    /// The predicted beat whose visual is due in the frame drawn now, once per beat.  A visual
+    /// fires in the last frame before its fire time, `frame` apart, leading the beat by up to a
+    /// frame rather than trailing it.
+    pub fn fire(&mut self, frame: Duration) -> Option<BeatEvent> {
+        let now = self.now();
+        let next = self.predictor.schedule(now)?;
+        let beat = self.predictor.phase(now)?.period;
+        // Predictions of one beat move a little as beats are heard.
+        let fired = self
+            .fired
+            .is_some_and(|at| (next.beat_at - at).get().abs() < beat.get() / 2.0);
+        if fired || next.fire_at >= now + Seconds(frame.as_secs_f64()) {
+            return None;
+        }
+        self.fired = Some(next.beat_at);
+        Some(next)
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Meter
+//!
+//! Program loudness and true peak of what plays, for the stats overlay.  Readings go through the
+//! [`Levels`] of the settings file's calibration, so a calibrated installation reads dB SPL beside
+//! its LUFS, and every readout says which scale it is in.
+
+use mutate_lib as utate;
+use utate::audio::node::AudioInlet;
+use utate::dsp::calibration::Levels;
+use utate::dsp::loudness::LoudnessMeter;
+use utate::dsp::units::SampleRate;
+
+pub struct Meter {
+    inlet: AudioInlet,
+    /// The meter and the rate it measures.
+    meter: Option<(u32, LoudnessMeter)>,
+    levels: Levels,
+}
+
+impl Meter {
+    pub fn new(levels: Levels) -> Self {
+        Self {
+            inlet: AudioInlet::new(2),
+            meter: None,
+            levels,
+        }
+    }
+
+    /// Where the host pushes what plays.  See [`Audio::feed`](super::Audio::feed).
+    pub fn inlet(&self) -> &AudioInlet {
+        &self.inlet
+    }
+
+    /// Report in new units, such as after the settings file was calibrated.
+    pub fn set_levels(&mut self, levels: Levels) {
+        self.levels = levels;
+    }
+
+    /// Measure what arrived since the last call, starting over when the stream's rate changes.
+    pub fn update(&mut self) {
+        let Some((samples, rate)) = self.inlet.take() else {
+            return;
+        };
+        if self.meter.as_ref().is_none_or(|(r, _)| *r != rate) {
+            let meter = LoudnessMeter::new(SampleRate(rate as f64), self.inlet.channels());
+            self.meter = Some((rate, meter));
+        }
+        if let Some((_, meter)) = &mut self.meter {
+            meter.push(&samples);
+        }
+    }
+
+    /// Short-term loudness and the true peak so far, followed by the mode of the levels.
+    pub fn readout(&self) -> String {
+        let mode = self.levels.mode();
+        let Some((_, meter)) = &self.meter else {
+            return format!("no audio | {mode}");
+        };
+        let program = meter
+            .short_term()
+            .filter(|lufs| lufs.is_finite())
+            .map_or("-.- LUFS".to_owned(), |lufs| {
+                self.levels.program(lufs).to_string()
+            });
+        let peak = self.levels.level(meter.true_peak_db() as f64);
+        format!("{program} | peak {peak} | {mode}")
+    }
+}
@@ -3,49 +3,288 @@
 
 //! # Audio
 //!
-//! Select a device.  Set up stream from server to device.
+//! Select a source and set up the stream from the server to the device.  Sources are chosen in the
+//! window with the [`picker`], or up front with `--source`.
+//!
+//! When there is nothing to listen to, the demo song plays instead so that the visuals still have
+//! something to show.  See [`audio::demo`].  A chosen source that goes silent stays connected under
+//! the demo, and takes over again as soon as it makes a sound.
+//!
+//! Whatever plays is also heard by [`beats`], which predicts beats for the windows to draw on, and
+//! by the [`meter`] the stats overlay reads levels from.
+
+pub mod beats;
+pub mod meter;
+pub mod picker;
+
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use mutate_lib::{self as utate, audio, prelude::*};
+use utate::dsp::units::Seconds;
+
+use picker::SourcePicker;
 
-use mutate_lib::{self as utate, audio, prelude::*, vulkan::prelude::*};
+use crate::video::ring::RingPosition;
+
+/// Silence this long on a real source falls back to the demo.
+pub const DEMO_AFTER_SILENCE: Duration = Duration::from_secs(10);
+
+/// Samples per channel in the device ring.
+const RING_SAMPLES: u32 = 6400;
 
 pub struct Audio {
-    context: audio::AudioContext,
+    /// Shared with the picker, which lists the same server's sources.
+    context: Rc<audio::AudioContext>,
     pub consumer: audio::import::Consumer<2>,
+    /// What the server reports about the stream, printed by [`log_events`](Self::log_events).
+    events: mpsc::Receiver<audio::AudioEvent>,
+    demo: bool,
+    /// Playing a file or test signal the user chose, which never falls back to the demo.
+    chosen: bool,
+    /// The silent source the demo plays in place of.  Its ring is drained each frame so that it can
+    /// tell when its signal resumes.
+    waiting: Option<Box<Audio>>,
+    /// Where the graph and the scenes read what is playing.  Handed on to whatever plays next.
+    inlets: Vec<audio::node::AudioInlet>,
+    /// Length of the file playing on a loop, for its [position](Self::position).
+    length: Option<Duration>,
 }
 
 impl Audio {
-    pub fn new(device: &Device) -> Result<Self, utate::MutateError> {
-        // NEXT choice is a dependency required by the node to be created.  Handle via config, then
-        // defaults, user input if necessary / specified on command line.
-        let context = audio::AudioContext::new()?;
-        println!("Choose the audio source:");
-        let mut first_choices = Vec::new();
-        let check = |choices: &[audio::AudioChoice]| {
-            first_choices.extend_from_slice(choices);
+    /// Listen to the audio server.  With a `source` setting, connect the first source it names.
+    /// Otherwise the demo plays under the picker until the user chooses.  Without a server, the
+    /// demo plays and there is nothing to pick.
+    pub fn listen(
+        device: &Device,
+        settings: &utate::settings::AudioSettings,
+    ) -> Result<(Self, Option<SourcePicker>), MutateError> {
+        // NEXT remember the last source once the visualizer has a config file.
+        let context = match audio::AudioContext::new() {
+            Ok(context) => Rc::new(context),
+            Err(e) => {
+                eprintln!("no audio server, playing the demo: {:?}", e);
+                return Ok((Self::demo(device)?, None));
+            }
         };
-        context.with_choices_blocking(check).unwrap();
-        let max_name_width = first_choices
-            .iter()
-            .map(|c| c.name().len())
-            .max()
-            .unwrap_or(0);
-        first_choices.iter().enumerate().for_each(|(i, c)| {
-            println!("[{}] {:<max_name_width$}  [{}]", i, c.name(), c.kind());
-        });
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input).unwrap();
+        let mut picker = SourcePicker::new(context.clone())?;
+        if let Some(name) = &settings.source {
+            let mut found = None;
+            context.with_choices_blocking(|choices| found = find_source(choices, name).cloned())?;
+            match found {
+                Some(choice) => {
+                    let audio = Self::connect(device, &context, &choice, settings.latency)?;
+                    picker.set_playing(Some(&choice));
+                    return Ok((audio, Some(picker)));
+                }
+                None => eprintln!("no audio source named {name}, choose one in the window"),
+            }
+        }
+        picker.show();
+        Ok((Self::demo(device)?, Some(picker)))
+    }
+
+    /// Listen to `choice`, one of the server's sources.
+    pub fn connect(
+        device: &Device,
+        context: &Rc<audio::AudioContext>,
+        choice: &audio::AudioChoice,
+        latency: audio::LatencyHint,
+    ) -> Result<Self, MutateError> {
+        // Subscribed first so that nothing the new stream reports is missed.
+        let events = context.events()?;
+        let options = audio::ConnectOptions::new("µTate").with_latency(latency);
+        let consumer = context.import_to_device_with(device, choice, RING_SAMPLES, &options)?;
+        Ok(Self {
+            context: context.clone(),
+            consumer,
+            events,
+            demo: false,
+            chosen: false,
+            waiting: None,
+            inlets: Vec::new(),
+            length: None,
+        })
+    }
+
+    /// Play the synthesized demo song through the normal import path.
+    pub fn demo(device: &Device) -> Result<Self, utate::MutateError> {
+        let context = audio::AudioContext::demo();
+        let mut choice = None;
+        context.with_choices_blocking(|choices| choice = choices.first().cloned())?;
+        let choice = choice.ok_or(MutateError::NoAudioSource)?;
+        let events = context.events()?;
+        let consumer = context.import_to_device(device, &choice, RING_SAMPLES, "µTate demo")?;
+        Ok(Self {
+            events,
+            context: Rc::new(context),
+            consumer,
+            demo: true,
+            chosen: false,
+            waiting: None,
+            inlets: Vec::new(),
+            length: None,
+        })
+    }
+
+    /// Loop a WAV or FLAC file through the normal import path.
+    pub fn file(device: &Device, path: &std::path::Path) -> Result<Self, utate::MutateError> {
+        let source = audio::file::FileSource::open(path)?.with_looping(true);
+        let length = source.duration();
+        let mut audio = Self::chosen(device, audio::AudioContext::file(source))?;
+        audio.length = Some(length);
+        Ok(audio)
+    }
+
+    /// Play a test signal through the normal import path.
+    pub fn signal(device: &Device, signal: audio::synthetic::Signal) -> Result<Self, MutateError> {
+        let source = audio::synthetic::SyntheticSource::new(signal, 48_000);
+        Self::chosen(device, audio::AudioContext::synthetic(source))
+    }
+
+    /// Connect to the only choice of a context the user asked for.
+    fn chosen(device: &Device, context: audio::AudioContext) -> Result<Self, MutateError> {
+        let mut choice = None;
+        context.with_choices_blocking(|choices| choice = choices.first().cloned())?;
+        let choice = choice.ok_or(MutateError::NoAudioSource)?;
+        let events = context.events()?;
+        let consumer = context.import_to_device(device, &choice, RING_SAMPLES, "µTate")?;
+        Ok(Self {
+            events,
+            context: Rc::new(context),
+            consumer,
+            demo: false,
+            chosen: true,
+            waiting: None,
+            inlets: Vec::new(),
+            length: None,
+        })
+    }
+
+    /// How far into a file the newest audio is, for timing its lyrics.  `None` for other sources.
+    pub fn position(&self) -> Option<Seconds> {
+        let length = self.length?.as_secs_f64();
+        let rate = self.consumer.sample_rate()? as f64;
+        let played = self.consumer.written() as f64 / rate;
+        Some(Seconds(played % length.max(f64::MIN_POSITIVE)))
+    }
+
+    pub fn is_demo(&self) -> bool {
+        self.demo
+    }
 
-        // FIXME handle invalid choices.
-        let choice_idx = input.trim().parse().unwrap();
-        let choice = first_choices.remove(choice_idx);
+    /// Print what the server reported since the last call.
+    pub fn log_events(&self) {
+        for event in self.events.try_iter() {
+            eprintln!("audio: {event}");
+        }
+    }
+
+    /// Push what plays into `inlet`, for the graph or a scene, until [`unfeed`](Self::unfeed).
+    pub fn feed(&mut self, inlet: &audio::node::AudioInlet) -> Result<(), MutateError> {
+        self.consumer.feed(inlet)?;
+        if !self.inlets.iter().any(|i| i.ptr_eq(inlet)) {
+            self.inlets.push(inlet.clone());
+        }
+        Ok(())
+    }
+
+    /// Stop pushing to `inlet`, such as when its window closes.
+    pub fn unfeed(&mut self, inlet: &audio::node::AudioInlet) -> Result<(), MutateError> {
+        self.consumer.unfeed(inlet)?;
+        self.inlets.retain(|i| !i.ptr_eq(inlet));
+        Ok(())
+    }
+
+    /// Move every inlet from `self` to `next`.
+    fn hand_over(&mut self, next: &mut Audio) -> Result<(), MutateError> {
+        for inlet in std::mem::take(&mut self.inlets) {
+            self.consumer.unfeed(&inlet)?;
+            next.feed(&inlet)?;
+        }
+        Ok(())
+    }
 
-        let consumer = context.import_to_device(device, &choice, 6400, "µTate")?;
+    /// A real source that has been silent for [`DEMO_AFTER_SILENCE`].
+    pub fn wants_demo(&self) -> bool {
+        !self.demo && !self.chosen && self.consumer.silent_for() >= DEMO_AFTER_SILENCE
+    }
 
-        Ok(Self { context, consumer })
+    /// Play the demo in place of a source that [wants it](Self::wants_demo).  The source stays
+    /// connected until it [resumes](Self::resume).
+    pub fn fall_back(&mut self, device: &Device) -> Result<(), MutateError> {
+        let demo = Self::demo(device)?;
+        println!(
+            "audio silent for {:?}, playing the demo",
+            DEMO_AFTER_SILENCE
+        );
+        let mut source = std::mem::replace(self, demo);
+        source.hand_over(self)?;
+        self.waiting = Some(Box::new(source));
+        Ok(())
+    }
+
+    /// The source under the demo made a sound.
+    pub fn resumed(&self) -> bool {
+        self.waiting
+            .as_ref()
+            .is_some_and(|w| w.consumer.silent_for() < DEMO_AFTER_SILENCE)
+    }
+
+    /// Stop the demo and listen to the source it played in place of again.
+    pub fn resume(&mut self, device: &Device) -> Result<(), MutateError> {
+        let Some(source) = self.waiting.take() else {
+            return Ok(());
+        };
+        println!("audio resumed, leaving the demo");
+        self.replace(device, *source)
+    }
+
+    /// Swap in `next` and destroy what was playing.  In-flight frames still read the old ring, so
+    /// the device is waited on first.  The graph reads `next` from now on.
+    pub fn replace(&mut self, device: &Device, mut next: Audio) -> Result<(), MutateError> {
+        self.hand_over(&mut next)?;
+        device.wait_idle()?;
+        let mut old = std::mem::replace(self, next);
+        old.destroy(device)
+    }
+
+    /// Discard the audio that nodes on the device do not read yet, and return where the newest is,
+    /// for drawing a frame.  The graph and scenes get their own copy through [inlets](Self::feed).
+    pub fn ring(&mut self) -> Result<RingPosition, MutateError> {
+        self.consumer
+            .advance_read(self.consumer.occupied_len().unwrap_or(0))?;
+        if let Some(waiting) = &self.waiting {
+            let consumer = &waiting.consumer;
+            consumer.advance_read(consumer.occupied_len().unwrap_or(0))?;
+        }
+        RingPosition::of(&self.consumer)
     }
 
     pub fn destroy(&mut self, device: &Device) -> Result<(), MutateError> {
+        if let Some(mut waiting) = self.waiting.take() {
+            waiting.destroy(device)?;
+        }
         self.consumer.destroy(device)?;
         // context has no vulkan resources and may just drop.
         Ok(())
     }
 }
+
+/// The source called `name`, or failing that, the first whose name contains it, ignoring case.
+/// Object serials match too.
+fn find_source<'a>(
+    choices: &'a [audio::AudioChoice],
+    name: &str,
+) -> Option<&'a audio::AudioChoice> {
+    let lower = name.to_lowercase();
+    choices
+        .iter()
+        .find(|c| c.name() == name || c.id() == name)
+        .or_else(|| {
+            choices
+                .iter()
+                .find(|c| c.name().to_lowercase().contains(&lower))
+        })
+}
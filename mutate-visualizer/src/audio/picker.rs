@@ -0,0 +1,222 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Source Picker
+//!
+//! A list of the server's audio sources drawn over the visuals, toggled with `A`.  Up and down move
+//! the cursor, enter listens to the source under it, and escape closes the list.  The list follows
+//! sources as they appear and disappear, so a device plugged in later shows up without a restart.
+//!
+//! Identically named sinks are common, so every row has a level meter showing which one has the
+//! music.  The meters connect a preview stream per source, and only while the list is shown.
+
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use mutate_lib::{self as utate, audio};
+
+use crate::video::text::{TextNode, TextStyle};
+
+/// How often the meters read new peaks.
+const LEVEL_PERIOD: Duration = Duration::from_millis(250);
+/// Level meter span and width.
+const METER_DB: f32 = 60.0;
+const METER_WIDTH: usize = 20;
+/// Longer names are cut to keep the meters on screen.
+const NAME_WIDTH: usize = 32;
+/// Distance from the window edges in pixels.
+const MARGIN: f32 = 16.0;
+
+const HEADER: &str = "Audio source: up and down to choose, enter to listen, esc to close";
+const DIM: [f32; 4] = [0.6, 0.6, 0.6, 1.0];
+
+pub struct SourcePicker {
+    context: Rc<audio::AudioContext>,
+    events: mpsc::Receiver<audio::ChoiceEvent>,
+    choices: Vec<audio::AudioChoice>,
+    /// Connected while visible.
+    probe: Option<audio::probe::LevelProbe>,
+    levels: Vec<Option<f32>>,
+    /// When the meters last read.
+    sampled: Instant,
+    /// Row under the cursor.
+    cursor: usize,
+    /// Id of the source being listened to.
+    playing: Option<String>,
+    visible: bool,
+}
+
+impl SourcePicker {
+    pub fn new(context: Rc<audio::AudioContext>) -> Result<Self, utate::MutateError> {
+        let events = context.choice_events()?;
+        Ok(Self {
+            context,
+            events,
+            choices: Vec::new(),
+            probe: None,
+            levels: Vec::new(),
+            sampled: Instant::now(),
+            cursor: 0,
+            playing: None,
+            visible: false,
+        })
+    }
+
+    /// The server the sources come from.
+    pub fn context(&self) -> &Rc<audio::AudioContext> {
+        &self.context
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn show(&mut self) {
+        self.visible = true;
+    }
+
+    /// Also disconnects the meters.
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.probe = None;
+        self.levels.clear();
+    }
+
+    pub fn toggle(&mut self) -> bool {
+        if self.visible {
+            self.hide();
+        } else {
+            self.show();
+        }
+        self.visible
+    }
+
+    pub fn up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn down(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.choices.len().saturating_sub(1));
+    }
+
+    /// The source under the cursor.
+    pub fn selected(&self) -> Option<&audio::AudioChoice> {
+        self.choices.get(self.cursor)
+    }
+
+    /// The source being listened to, if it is still there.
+    pub fn playing(&self) -> Option<&audio::AudioChoice> {
+        let id = self.playing.as_ref()?;
+        self.choices.iter().find(|c| &c.id() == id)
+    }
+
+    /// Mark `choice` as the one being listened to.  `None` when playing something else, such as
+    /// the demo.
+    pub fn set_playing(&mut self, choice: Option<&audio::AudioChoice>) {
+        self.playing = choice.map(audio::AudioChoice::id);
+    }
+
+    /// Take in sources that appeared or disappeared and read the meters.  Call once per frame.
+    pub fn update(&mut self) {
+        let selected = self.selected().map(audio::AudioChoice::id);
+        let mut changed = false;
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                audio::ChoiceEvent::Added(choice) => {
+                    if !self.choices.iter().any(|c| c.id() == choice.id()) {
+                        self.choices.push(choice);
+                    }
+                }
+                audio::ChoiceEvent::Removed(choice) => {
+                    self.choices.retain(|c| c.id() != choice.id());
+                }
+            }
+            changed = true;
+        }
+        if changed {
+            // The cursor stays on its source when rows move around it.
+            self.cursor = selected
+                .and_then(|id| self.choices.iter().position(|c| c.id() == id))
+                .unwrap_or(self.cursor)
+                .min(self.choices.len().saturating_sub(1));
+            // The meters are connected to the old list.
+            self.probe = None;
+            self.levels.clear();
+        }
+        if !self.visible {
+            return;
+        }
+        let probe = match &mut self.probe {
+            Some(probe) => probe,
+            None => {
+                self.sampled = Instant::now();
+                self.probe
+                    .insert(audio::probe::LevelProbe::new(&self.context, &self.choices))
+            }
+        };
+        if self.sampled.elapsed() >= LEVEL_PERIOD {
+            self.sampled = Instant::now();
+            self.levels = probe.levels();
+        }
+    }
+
+    /// Queue the list on `text` in the top left corner.
+    pub fn draw(&self, text: &mut TextNode) {
+        let style = TextStyle::default();
+        let dim = TextStyle {
+            color: DIM,
+            ..style
+        };
+        let line = text.line_height(&style);
+        let mut pos = [MARGIN, MARGIN];
+        text.draw_text(pos, HEADER, &style);
+        pos[1] += line * 1.5;
+        if self.choices.is_empty() {
+            text.draw_text(
+                pos,
+                "No sources yet.  The demo plays until one appears.",
+                &dim,
+            );
+            return;
+        }
+        let width = self
+            .choices
+            .iter()
+            .map(|c| c.name().chars().count().min(NAME_WIDTH))
+            .max()
+            .unwrap_or(0);
+        for (i, choice) in self.choices.iter().enumerate() {
+            let cursor = if i == self.cursor { '>' } else { ' ' };
+            let playing = if self.playing.as_ref() == Some(&choice.id()) {
+                '*'
+            } else {
+                ' '
+            };
+            let name: String = choice.name().chars().take(NAME_WIDTH).collect();
+            let level = self.levels.get(i).copied().flatten();
+            let row = format!(
+                "{cursor}{playing} {name:<width$}  {}  [{}]",
+                meter(level),
+                choice.kind()
+            );
+            let row_style = if i == self.cursor { &style } else { &dim };
+            text.draw_text(pos, &row, row_style);
+            pos[1] += line;
+        }
+    }
+}
+
+/// Peak level as a bar and a number.  Sources without a reading show a blank meter.
+fn meter(level: Option<f32>) -> String {
+    let Some(db) = level else {
+        return format!("{:METER_WIDTH$}  {:>10}", "", "n/a");
+    };
+    let filled = (((db + METER_DB) / METER_DB).clamp(0.0, 1.0) * METER_WIDTH as f32) as usize;
+    let bar = "#".repeat(filled) + &".".repeat(METER_WIDTH - filled);
+    if db <= audio::probe::FLOOR_DBFS {
+        format!("{bar}  {:>10}", "silent")
+    } else {
+        format!("{bar}  {db:>5.1} dBFS")
+    }
+}
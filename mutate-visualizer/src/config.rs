@@ -0,0 +1,145 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Config File
+//!
+//! Settings that outlive a run, read from `mutate/mutate.toml` in `$XDG_CONFIG_HOME` or
+//! `~/.config`, then in `$XDG_CONFIG_DIRS` or `/etc/xdg`, or from the file given with `--config`.
+//! Without a file the defaults are used.
+//!
+//! ```toml
+//! [keys]
+//! P = "pause"
+//! Space = "cycle"
+//! Escape = "none"
+//!
+//! [video]
+//! fullscreen = true
+//! ```
+//!
+//! The `keys` table layers over the [default bindings](crate::input::defaults).  See
+//! [`mutate_lib::input`] for key names.  The `scenes` table is described with
+//! [scenes](crate::video::scene), and the `control` table with [control](crate::control).  The
+//! `audio`, `video`, and `dsp` tables are described with [`mutate_lib::settings`], and the flags
+//! that share their names override them.
+//!
+//! Edits to the file are picked up while running.  Key bindings, the DSP settings, the scenes'
+//! palette, and the control mappings change at once, and the rest the next time a window opens or a
+//! source is connected.
+
+use std::path::{Path, PathBuf};
+
+use mutate_lib::{color::Palette, input::Bindings, settings::Settings, MutateError};
+
+use crate::input;
+use crate::video::scene::SceneSettings;
+
+const CONFIG_FILE: &str = "mutate.toml";
+/// Tables the visualizer reads beside those of [`Settings`].
+const SECTIONS: &[&str] = &["keys", "scenes", "control"];
+
+pub struct Config {
+    pub keys: Bindings,
+    pub scenes: SceneSettings,
+    #[cfg(feature = "control")]
+    pub control: crate::control::ControlSettings,
+    pub settings: Settings,
+    /// The file read, if any.
+    pub source: Option<PathBuf>,
+}
+
+impl Config {
+    /// Read `path`, or the config file in the usual places, and layer the command line's
+    /// `overrides` over it.  Only a missing `path` is an error.
+    pub fn load(path: Option<&Path>, overrides: &toml::Table) -> Result<Self, MutateError> {
+        let source = match path {
+            Some(p) => Some(p.to_path_buf()),
+            None => mutate_lib::settings::search("mutate", CONFIG_FILE),
+        };
+        let mut settings = Settings::new(SECTIONS);
+        if let Some(path) = &source {
+            settings.read(path)?;
+        }
+        settings.apply(overrides)?;
+        let mut config = Self {
+            keys: input::defaults(),
+            scenes: SceneSettings::default(),
+            #[cfg(feature = "control")]
+            control: Default::default(),
+            settings,
+            source,
+        };
+        config
+            .parse_sections()
+            .map_err(|e| match (e, &config.source) {
+                (MutateError::Config(e) | MutateError::Bindings(e), Some(path)) => {
+                    MutateError::Config(format!("{}: {e}", path.display()))
+                }
+                (e, _) => e,
+            })?;
+        Ok(config)
+    }
+
+    fn parse_sections(&mut self) -> Result<(), MutateError> {
+        if let Some(keys) = self.settings.section("keys") {
+            self.keys.apply(keys)?;
+        }
+        if let Some(scenes) = self.settings.section("scenes") {
+            parse_scenes(scenes, &mut self.scenes)?;
+        }
+        #[cfg(feature = "control")]
+        if let Some(control) = self.settings.section("control") {
+            self.control = crate::control::ControlSettings::parse(control)?;
+        }
+        #[cfg(not(feature = "control"))]
+        if self.settings.section("control").is_some() {
+            eprintln!("config: `control` needs a build with the control feature, ignoring it");
+        }
+        Ok(())
+    }
+}
+
+fn parse_scenes(table: &toml::Table, scenes: &mut SceneSettings) -> Result<(), MutateError> {
+    let seconds = |key: &str, value: &toml::Value| {
+        let seconds = match value {
+            toml::Value::Integer(i) => *i as f64,
+            toml::Value::Float(f) => *f,
+            _ => -1.0,
+        };
+        match seconds >= 0.0 && seconds.is_finite() {
+            true => Ok(std::time::Duration::from_secs_f64(seconds)),
+            false => Err(MutateError::Config(format!(
+                "`scenes.{key}` must be seconds, not {value}"
+            ))),
+        }
+    };
+    for (key, value) in table {
+        match key.as_str() {
+            "start" => {
+                scenes.start = value
+                    .as_str()
+                    .ok_or_else(|| MutateError::Config("`scenes.start` must name a scene".into()))?
+                    .parse()
+                    .map_err(MutateError::Config)?;
+            }
+            "rotate" => scenes.rotate = Some(seconds(key, value)?).filter(|d| !d.is_zero()),
+            "fade" => scenes.fade = seconds(key, value)?,
+            "palette" => {
+                scenes.palette = Palette::parse(value)
+                    .map_err(|e| MutateError::Config(format!("`scenes.palette`: {e}")))?;
+            }
+            "image" => {
+                let path = value
+                    .as_str()
+                    .ok_or_else(|| MutateError::Config("`scenes.image` must be a path".into()))?;
+                scenes.image = Some(path.into());
+            }
+            _ => {
+                return Err(MutateError::Config(format!(
+                    "unknown setting `scenes.{key}`"
+                )))
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Control
+//!
+//! Faders and lighting desks moving the `--graph` preset's parameters.  The `control` table of the
+//! config file says where to listen and holds the [mapping table](mutate_lib::control):
+//!
+//! ```toml
+//! [control]
+//! osc_address = "0.0.0.0:9000"   # receive OSC on this UDP address
+//! midi_port = "nanoKONTROL"      # MIDI input port by part of its name, "" for the first port
+//!
+//! [[control.midi]]
+//! cc = 74
+//! param = "loudness/target"
+//!
+//! [[control.osc]]
+//! address = "/1/fader1"
+//! param = "loudness/target"
+//! ```
+//!
+//! Listening needs a build with the `control` feature, and MIDI the `midi` feature.  Events that
+//! arrive before a frame move the parameters that frame's graph reads.  Mappings change when the
+//! config file is edited, and the listeners the next time the visualizer starts.
+
+use std::sync::mpsc;
+
+use mutate_lib::{self as utate, graph::Graph, MutateError};
+use utate::control::{osc::OscListener, ControlEvent, MappingTable};
+
+/// The `control` table of the config file.
+#[derive(Clone, Debug, Default)]
+pub struct ControlSettings {
+    /// UDP address to receive OSC on.
+    pub osc_address: Option<String>,
+    /// Part of the name of the MIDI input port, empty for the first port.
+    pub midi_port: Option<String>,
+    pub mappings: MappingTable,
+}
+
+impl ControlSettings {
+    pub fn parse(table: &toml::Table) -> Result<Self, MutateError> {
+        let mut mappings = table.clone();
+        let mut text = |key: &str| match mappings.remove(key) {
+            None => Ok(None),
+            Some(toml::Value::String(s)) => Ok(Some(s)),
+            Some(_) => Err(MutateError::Config(format!(
+                "`control.{key}` must be a string"
+            ))),
+        };
+        let osc_address = text("osc_address")?;
+        let midi_port = text("midi_port")?;
+        let mappings = MappingTable::from_table(&mappings)
+            .map_err(|e| MutateError::Config(format!("`control`: {e}")))?;
+        Ok(Self {
+            osc_address,
+            midi_port,
+            mappings,
+        })
+    }
+
+    /// Whether anything is to be listened to.
+    pub fn listens(&self) -> bool {
+        self.osc_address.is_some() || self.midi_port.is_some()
+    }
+}
+
+/// Listeners forwarding controller messages, and the mappings that route them to parameters.
+pub struct Control {
+    events: mpsc::Receiver<ControlEvent>,
+    mappings: MappingTable,
+    osc: Option<OscListener>,
+    #[cfg(feature = "midi")]
+    midi: Option<utate::control::midi::MidiListener>,
+}
+
+impl Control {
+    /// Start listening where `settings` say.  Every mapped parameter must be in `graph`.
+    pub fn start(settings: &ControlSettings, graph: &Graph) -> Result<Self, MutateError> {
+        settings.mappings.validate(graph)?;
+        let (sender, events) = mpsc::channel();
+        let osc = match &settings.osc_address {
+            Some(address) => Some(OscListener::bind(address.as_str(), sender.clone())?),
+            None => None,
+        };
+        #[cfg(feature = "midi")]
+        let midi = match settings.midi_port.as_deref() {
+            Some(port) => {
+                let port = Some(port).filter(|p| !p.is_empty());
+                Some(utate::control::midi::MidiListener::connect(port, sender)?)
+            }
+            None => None,
+        };
+        #[cfg(not(feature = "midi"))]
+        if settings.midi_port.is_some() {
+            eprintln!("control: `control.midi_port` needs a build with the midi feature");
+        }
+        Ok(Self {
+            events,
+            mappings: settings.mappings.clone(),
+            osc,
+            #[cfg(feature = "midi")]
+            midi,
+        })
+    }
+
+    /// Route to parameters by `mappings` from now on, keeping the old ones if any path is not in
+    /// `graph`.
+    pub fn remap(&mut self, mappings: &MappingTable, graph: &Graph) {
+        match mappings.validate(graph) {
+            Ok(()) => self.mappings = mappings.clone(),
+            Err(e) => eprintln!("control: mappings not reloaded, {e}"),
+        }
+    }
+
+    /// Write what arrived since the last frame to `graph`'s parameters.
+    pub fn apply(&self, graph: &Graph) {
+        for event in self.events.try_iter() {
+            if let Err(e) = self.mappings.apply(graph, &event) {
+                eprintln!("control: {event:?} failed {e}");
+            }
+        }
+    }
+
+    pub fn stop(self) {
+        if let Some(osc) = self.osc {
+            osc.stop();
+        }
+        #[cfg(feature = "midi")]
+        if let Some(midi) = self.midi {
+            midi.stop();
+        }
+    }
+}
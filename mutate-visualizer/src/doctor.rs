@@ -0,0 +1,224 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Doctor
+//!
+//! `--doctor` prints what this machine can do, in Markdown that can be pasted into a bug report.
+//! The Vulkan section is the same [`Report`](mutate_lib::gpu::instance::report::Report) that device
+//! selection reads, so the report and the running program cannot disagree.
+//!
+//! Present modes and present latency need a real window, so the doctor briefly opens one and
+//! presents black frames into it.
+
+use std::time::Duration;
+
+use ash::vk;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+use mutate_lib::{audio, gpu::resource::image, prelude::*};
+
+/// Frames presented while measuring latency.
+const PRESENTS: usize = 120;
+
+/// Print the report.  Sections print as soon as they are known so that a later crash still leaves
+/// something to paste.
+pub fn run(event_loop: EventLoop<()>, instance: Instance) -> Result<(), MutateError> {
+    println!("## µTate doctor\n");
+    println!("- version: {}", env!("CARGO_PKG_VERSION"));
+    println!(
+        "- platform: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+
+    println!("\n### Audio\n");
+    print_audio();
+
+    println!("\n### Vulkan\n");
+    match instance.report() {
+        Ok(report) => print!("{report}"),
+        Err(e) => println!("- probe failed: {e}"),
+    }
+    println!("- choices for `--gpu` and `MUTATE_GPU`:");
+    for device in instance.enumerate_devices() {
+        println!(
+            "  - {}: {} ({})",
+            device.index,
+            device.name,
+            device.type_name()
+        );
+    }
+
+    println!("\n### Presentation\n");
+    let mut doctor = Doctor {
+        instance,
+        presentation: None,
+    };
+    if let Err(e) = event_loop.run_app(&mut doctor) {
+        println!("- no event loop: {e}");
+    }
+    match doctor.presentation.take() {
+        Some(Ok(presentation)) => print!("{presentation}"),
+        Some(Err(e)) => println!("- not measured: {e}"),
+        None => println!("- not measured: the window never opened"),
+    }
+    doctor.instance.destroy();
+    Ok(())
+}
+
+fn print_audio() {
+    println!("- pipewire library: {}", audio::library_version());
+    let context = match audio::AudioContext::new() {
+        Ok(context) => context,
+        Err(e) => {
+            println!("- no audio server: {e}");
+            return;
+        }
+    };
+    let mut choices = Vec::new();
+    match context.with_choices_blocking(|c| choices.extend_from_slice(c)) {
+        Ok(()) => {
+            println!("- sources: {}", choices.len());
+            for choice in &choices {
+                println!(
+                    "  - {} (serial {}, {})",
+                    choice.name(),
+                    choice.id(),
+                    choice.kind()
+                );
+            }
+            if choices.is_empty() {
+                println!("  - nothing to listen to, the visualizer will play the demo");
+            }
+        }
+        Err(e) => println!("- sources not listed: {e}"),
+    }
+}
+
+struct Presentation {
+    device: String,
+    present_modes: Vec<vk::PresentModeKHR>,
+    selected: vk::PresentModeKHR,
+    /// Sorted.
+    latencies: Vec<Duration>,
+}
+
+impl std::fmt::Display for Presentation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "- device: {}", self.device)?;
+        writeln!(
+            f,
+            "- present modes: {:?} (selected {:?})",
+            self.present_modes, self.selected
+        )?;
+        if self.latencies.is_empty() {
+            return writeln!(
+                f,
+                "- present latency: not measured, present wait never caught two presents in a row"
+            );
+        }
+        let at = |q: f64| {
+            let i = ((self.latencies.len() - 1) as f64 * q).round() as usize;
+            self.latencies[i].as_secs_f64() * 1e3
+        };
+        writeln!(
+            f,
+            "- present latency: median {:.2}ms, p95 {:.2}ms, max {:.2}ms over {} presents",
+            at(0.5),
+            at(0.95),
+            at(1.0),
+            self.latencies.len()
+        )
+    }
+}
+
+struct Doctor {
+    instance: Instance,
+    presentation: Option<Result<Presentation, String>>,
+}
+
+impl Doctor {
+    fn measure(&self, event_loop: &ActiveEventLoop) -> Result<Presentation, String> {
+        let instance = &self.instance;
+        let window = event_loop
+            .create_window(Window::default_attributes().with_title("µTate doctor"))
+            .map_err(|e| format!("no window: {e}"))?;
+        let raw_surface = instance.surface(event_loop, &window);
+        let Some(selected) = instance
+            .supported_devices(&[])
+            .into_iter()
+            .find(|sd| sd.supports_surface(raw_surface, instance))
+        else {
+            unsafe { instance.surface_loader().destroy_surface(raw_surface, None) };
+            return Err("no usable device can present to a window".into());
+        };
+        let device_name = selected.name.clone();
+        let present_modes = selected
+            .present_modes(instance, raw_surface)
+            .map_err(|e| e.to_string())?;
+        let device = selected.into_logical(instance).map_err(|e| e.to_string())?;
+        let surface =
+            Surface::new(instance, &device, raw_surface, &window).map_err(|e| e.to_string())?;
+        let mut ring = PresentRing::new(&device, instance, &surface).map_err(|e| e.to_string())?;
+
+        let mut latencies = Vec::with_capacity(PRESENTS);
+        let mut last_id = None;
+        for _ in 0..PRESENTS {
+            let recorded = ring.record(
+                &device,
+                compute_present(&device, |device, cb, acquired_image| unsafe {
+                    device.as_raw().cmd_clear_color_image(
+                        **cb,
+                        acquired_image.image,
+                        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                        &vk::ClearColorValue::default(),
+                        &[image::range()],
+                    );
+                }),
+                || window.pre_present_notify(),
+            );
+            if let Err(e) = recorded {
+                // The window is never resized, so any failure ends the measurement.
+                eprintln!("doctor: present failed {e:?}");
+                break;
+            }
+            // Present wait trails, so the same present is often read more than once.
+            let latest = ring
+                .present_latency()
+                .filter(|(id, _)| last_id != Some(*id));
+            if let Some((id, latency)) = latest {
+                latencies.push(latency);
+                last_id = Some(id);
+            }
+        }
+        latencies.sort();
+
+        let selected_mode = surface.present_mode();
+        let _ = device.wait_idle();
+        ring.destroy(&device);
+        surface.destroy();
+        device.destroy();
+        Ok(Presentation {
+            device: device_name,
+            present_modes,
+            selected: selected_mode,
+            latencies,
+        })
+    }
+}
+
+impl ApplicationHandler for Doctor {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.presentation.is_none() {
+            self.presentation = Some(self.measure(event_loop));
+        }
+        event_loop.exit();
+    }
+
+    fn window_event(&mut self, _: &ActiveEventLoop, _: WindowId, _: WindowEvent) {}
+}
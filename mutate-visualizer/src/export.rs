@@ -0,0 +1,590 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Export
+//!
+//! Render a WAV or FLAC file to a video without a window:
+//!
+//! ```text
+//! mutate-visualizer --file song.flac --export song.mp4 --fps 60 --size 1920x1080
+//! ```
+//!
+//! Frames are drawn offscreen at a fixed rate.  Before each frame, the audio ring is fed exactly
+//! the samples up to that frame's time, and scenes rotate and fade on the same clock, so the same
+//! file and settings always give the same video however fast the GPU is.  Each frame is read back
+//! and piped to `ffmpeg`, which muxes it with the file's audio and picks the container and codecs
+//! from the output's extension.  `ffmpeg` must be on the `PATH`.
+//!
+//! `--overlays` adds the [analysis overlays](utate::export::overlay), measured from the same audio
+//! on the same clock:
+//!
+//! ```text
+//! mutate-visualizer --file song.flac --export song.mp4 --overlays all --overlay-mode burn-in
+//! ```
+//!
+//! A sidecar is written beside the output with the format's extension, such as `song.vtt`.  Burn-in
+//! draws the input's name and section at the top left, the timecode at the bottom left, the
+//! loudness meters at the top right, and the tempo with a marker on each beat at the bottom right.
+//! The meters read dB SPL beside LUFS when the settings file has a `[calibration]`.
+//!
+//! [Lyrics](video::lyrics) are drawn on the video's clock.  Lines without word timing sweep in beat
+//! steps when `--overlays` track the beat.
+
+// MAYBE a feature-gated encoder crate for systems without ffmpeg.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use ash::vk;
+use mutate_lib::{self as utate, audio, prelude::*};
+use utate::dsp::calibration::Levels;
+use utate::dsp::units::{SampleRate, Seconds};
+use utate::export::analysis::OverlayAnalysis;
+use utate::export::overlay::{OverlayMode, OverlayOptions, Overlays, SidecarWriter};
+use utate::gpu::resource::{buffer, image};
+use utate::graph::DeletionQueue;
+use utate::settings::Settings;
+
+use crate::video;
+use crate::video::text::{TextNode, TextStyle};
+use crate::Args;
+
+/// Samples per channel in the device ring at least.  One frame's audio must fit with room for the
+/// scenes that look back further, so low frame rates get a longer ring.  See [`ring_samples`].
+const RING_SAMPLES: u32 = 6400;
+/// Channels are stored blue first, which ffmpeg calls `bgra`.
+const FORMAT: vk::Format = vk::Format::B8G8R8A8_UNORM;
+/// How long a frame may take on the device before the export gives up.
+const FRAME_TIMEOUT: u64 = 5_000_000_000;
+/// How long the burned-in beat marker stays lit.
+const BEAT_FLASH: Seconds = Seconds(0.1);
+/// Color of the burned-in beat marker.
+const BEAT_COLOR: [f32; 4] = [1.0, 0.3, 0.2, 1.0];
+
+/// Parse `WIDTHxHEIGHT` for `--size`.
+pub fn parse_size(s: &str) -> Result<vk::Extent2D, String> {
+    let error = || format!("expected a size such as 1920x1080, not `{s}`");
+    let (width, height) = s.split_once(['x', 'X']).ok_or_else(error)?;
+    let width: u32 = width.trim().parse().map_err(|_| error())?;
+    let height: u32 = height.trim().parse().map_err(|_| error())?;
+    match width > 0 && height > 0 {
+        true => Ok(vk::Extent2D { width, height }),
+        false => Err(error()),
+    }
+}
+
+/// Samples per channel for frames at `fps` of audio at `rate`.  Each frame pushes up to
+/// `rate / fps` samples, and twice that keeps the previous frame's audio for the scenes that look
+/// back.
+fn ring_samples(rate: u32, fps: u32) -> u32 {
+    rate.div_ceil(fps).saturating_mul(2).max(RING_SAMPLES)
+}
+
+/// Render `--file` to `--export` and wait for the encoder to finish.  Overlays are analyzed with
+/// the bank the settings' `dsp` describes, and report levels through their calibration.
+pub fn run(
+    args: &Args,
+    settings: &Settings,
+    scenes: &video::scene::SceneSettings,
+    options: &video::scene::SceneOptions,
+    debug: DebugOptions,
+) -> Result<(), MutateError> {
+    let (Some(output), Some(input)) = (&args.export, &args.file) else {
+        return Err(MutateError::Export("--export needs --file".to_owned()));
+    };
+    if args.fps == 0 {
+        return Err(MutateError::Export("--fps must be at least one".to_owned()));
+    }
+    let source = audio::file::FileSource::open(input)?;
+    let frames = stereo(&source);
+    let rate = source.format().rate;
+    let total = (source.duration().as_secs_f64() * args.fps as f64).ceil() as u64;
+    let overlay = match args.overlays {
+        Some(overlays) => Some(Overlay::new(args, overlays, settings, rate)?),
+        None => None,
+    };
+    let lyrics = video::lyrics::LyricsView::load(args, &scenes.palette)?;
+
+    let instance = Instance::headless_with_debug(debug)?;
+    let result = select_device(&instance, args).and_then(|device| {
+        println!("exporting {total} frames to {}", output.display());
+        let samples = ring_samples(rate, args.fps);
+        let exporting = Export::new(&device, args.size, rate, samples, options, overlay, lyrics);
+        let result = exporting.and_then(|mut export| {
+            let mut encoder = spawn_encoder(args, input, output)?;
+            let rendered = export.render(&device, &frames, args.fps, total, scenes, &mut encoder);
+            let overlaid = export.finish_overlay(Seconds(frames.len() as f64 / rate as f64));
+            export.destroy(&device);
+            let finished = finish_encoder(encoder);
+            rendered.and(overlaid).and(finished)
+        });
+        device.destroy();
+        result
+    });
+    instance.destroy();
+    if result.is_ok() {
+        println!("exported {}", output.display());
+    }
+    result
+}
+
+/// Interleaved frames of any channel count as left and right.
+pub fn stereo(source: &audio::file::FileSource) -> Vec<[f32; 2]> {
+    let channels = source.format().channels as usize;
+    let map = audio::channel::ChannelMap::standard(channels as u32);
+    source
+        .samples()
+        .chunks_exact(channels)
+        .map(|frame| match channels {
+            1 => [frame[0], frame[0]],
+            _ if map.is_surround() => map.downmix(frame),
+            _ => [frame[0], frame[1]],
+        })
+        .collect()
+}
+
+/// Select a device by `--gpu`, `MUTATE_GPU`, and preference.  Nothing is presented, so any device
+/// will do.
+pub fn select_device(instance: &Instance, args: &Args) -> Result<Device, MutateError> {
+    let mut selector = DeviceSelector::new().prefer_discrete().with_env();
+    if let Some(choice) = &args.gpu {
+        selector = selector.with_choice(choice);
+    }
+    let selected = selector.select(instance.supported_devices(&[]))?;
+    println!("device selected: {}", selected.name);
+    Ok(selected.into_logical(instance)?)
+}
+
+fn spawn_encoder(args: &Args, input: &Path, output: &Path) -> Result<Child, MutateError> {
+    let size = format!("{}x{}", args.size.width, args.size.height);
+    let fps = args.fps.to_string();
+    Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(["-f", "rawvideo", "-pix_fmt", "bgra"])
+        .args(["-s", &size, "-r", &fps, "-i", "-"])
+        .arg("-i")
+        .arg(input)
+        .args(["-map", "0:v", "-map", "1:a"])
+        .args(["-pix_fmt", "yuv420p", "-shortest"])
+        .arg(output)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| MutateError::Export(format!("could not run ffmpeg: {e}")))
+}
+
+/// Close the pipe and wait for the encoder to write the rest of the file.
+fn finish_encoder(mut encoder: Child) -> Result<(), MutateError> {
+    drop(encoder.stdin.take());
+    let status = encoder
+        .wait()
+        .map_err(|e| MutateError::Export(format!("ffmpeg: {e}")))?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(MutateError::Export(format!("ffmpeg exited with {status}"))),
+    }
+}
+
+/// `HH:MM:SS:FF` of video frame `n` at `fps`.
+fn timecode(n: u64, fps: u64) -> String {
+    let seconds = n / fps;
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        n % fps
+    )
+}
+
+/// The analysis behind `--overlays`, and where it goes.
+struct Overlay {
+    options: OverlayOptions,
+    analysis: OverlayAnalysis,
+    /// Written as the analysis goes in sidecar mode.
+    sidecar: Option<(PathBuf, SidecarWriter<BufWriter<File>>)>,
+    /// The input's name, burned in at the top.
+    title: String,
+    /// Burned in loudness is reported through these.
+    levels: Levels,
+}
+
+impl Overlay {
+    /// Analyze stereo audio at `rate` with the bank the settings' `dsp` describes, and open the
+    /// sidecar beside `--export` in sidecar mode.
+    fn new(
+        args: &Args,
+        overlays: Overlays,
+        settings: &Settings,
+        rate: u32,
+    ) -> Result<Self, MutateError> {
+        let (Some(output), Some(input)) = (&args.export, &args.file) else {
+            return Err(MutateError::Export("--overlays needs --export".to_owned()));
+        };
+        let options = OverlayOptions {
+            overlays,
+            mode: args.overlay_mode,
+            format: args.sidecar_format,
+        };
+        if overlays.spectrogram {
+            eprintln!("export: the spectrogram overlay is not drawn yet");
+        }
+        let sidecar = match options.mode {
+            OverlayMode::Sidecar => {
+                let path = output.with_extension(options.format.extension());
+                let opened = File::create(&path).and_then(|file| {
+                    SidecarWriter::new(BufWriter::new(file), options.format, overlays)
+                });
+                let writer = opened
+                    .map_err(|e| MutateError::Export(format!("writing {}: {e}", path.display())))?;
+                Some((path, writer))
+            }
+            OverlayMode::BurnIn => None,
+        };
+        let table = settings.dsp.design_table(SampleRate(rate as f64));
+        let title = input.file_stem().unwrap_or(input.as_os_str());
+        Ok(Self {
+            options,
+            analysis: OverlayAnalysis::new(table, 2),
+            sidecar,
+            title: title.to_string_lossy().into_owned(),
+            levels: settings.levels(),
+        })
+    }
+
+    fn burns_in(&self) -> bool {
+        self.options.mode == OverlayMode::BurnIn
+    }
+
+    /// Analyze the audio of the next video frame and write what it completes to the sidecar.
+    fn push(&mut self, frames: &[[f32; 2]]) -> Result<(), MutateError> {
+        let Self {
+            analysis, sidecar, ..
+        } = self;
+        let mut written = Ok(());
+        analysis.push(frames.as_flattened(), |event| {
+            if let (Some((_, writer)), true) = (sidecar.as_mut(), written.is_ok()) {
+                written = writer.event(&event);
+            }
+        });
+        written.map_err(|e| MutateError::Export(format!("writing the sidecar: {e}")))
+    }
+
+    /// Queue the burn-in of video frame `n` at `fps`, over frames of `extent`.
+    fn queue(&self, text: &mut TextNode, n: u64, fps: u64, extent: vk::Extent2D) {
+        let style = TextStyle {
+            scale: (extent.height / 360).max(1),
+            ..Default::default()
+        };
+        let margin = text.line_height(&style);
+        let [width, height] = [extent.width as f32, extent.height as f32];
+        let overlays = self.options.overlays;
+        let reading = self.analysis.reading();
+
+        let mut heading = self.title.clone();
+        if let Some(label) = reading.section.filter(|_| overlays.sections) {
+            heading += &format!("\nsection {label}");
+        }
+        text.draw_text([margin, margin], &heading, &style);
+        let timecode = timecode(n, fps);
+        let [_, h] = text.measure(&timecode, &style);
+        text.draw_text([margin, height - margin - h], &timecode, &style);
+
+        if overlays.loudness {
+            let levels = &self.levels;
+            let lufs =
+                |x: Option<f64>| x.map_or("-.- LUFS".to_owned(), |x| levels.program(x).to_string());
+            let meters = format!(
+                "M {}\nS {}\nI {}\nTP {:5.1} dBTP\n{}",
+                lufs(reading.momentary),
+                lufs(reading.short_term),
+                lufs(reading.integrated),
+                reading.true_peak,
+                levels.mode()
+            );
+            let [w, _] = text.measure(&meters, &style);
+            text.draw_text([width - margin - w, margin], &meters, &style);
+        }
+
+        if overlays.beats {
+            let bpm = reading
+                .bpm
+                .map_or("--- BPM".to_owned(), |bpm| format!("{bpm:3.0} BPM"));
+            let [w, h] = text.measure(&bpm, &style);
+            let y = height - margin - h;
+            text.draw_text([width - margin - w, y], &bpm, &style);
+            let lit = reading
+                .beat
+                .is_some_and(|at| (reading.t.get() - at.get()) < BEAT_FLASH.get());
+            if lit {
+                let marker = TextStyle {
+                    color: BEAT_COLOR,
+                    ..style
+                };
+                let [w, _] = text.measure("BEAT", &marker);
+                text.draw_text([width - margin - w, y - h], "BEAT", &marker);
+            }
+        }
+    }
+
+    /// Close the sidecar at `end`, the duration of the audio.
+    fn finish(self, end: Seconds) -> Result<(), MutateError> {
+        let Some((path, writer)) = self.sidecar else {
+            return Ok(());
+        };
+        let finished = writer.finish(end);
+        finished.map_err(|e| MutateError::Export(format!("writing {}: {e}", path.display())))?;
+        println!("wrote {}", path.display());
+        Ok(())
+    }
+}
+
+/// Everything on the device for drawing frames one at a time.
+struct Export {
+    consumer: audio::import::Consumer<2>,
+    nodes: video::scene::SceneNodes,
+    image: image::Image,
+    view: image::ImageView,
+    /// Each frame is copied here and read by the host.
+    readback: buffer::MappedAllocation<[u8; 4]>,
+    pool: CommandPool<Graphics, OneTime>,
+    semaphore: TimelineSemaphore,
+    deletions: DeletionQueue<Device>,
+    overlay: Option<Overlay>,
+    lyrics: Option<video::lyrics::LyricsView>,
+    /// Draws the burn-in and lyrics.
+    text: Option<TextNode>,
+}
+
+impl Export {
+    /// Frames of `extent` from a ring of `samples` per channel at `rate`, with `overlay` over or
+    /// beside them and `lyrics` over them.
+    fn new(
+        device: &Device,
+        extent: vk::Extent2D,
+        rate: u32,
+        samples: u32,
+        options: &video::scene::SceneOptions,
+        overlay: Option<Overlay>,
+        lyrics: Option<video::lyrics::LyricsView>,
+    ) -> Result<Self, MutateError> {
+        let usage = vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | vk::ImageUsageFlags::TRANSFER_SRC
+            | vk::ImageUsageFlags::TRANSFER_DST;
+        // DEBT partial construction leaks on error, as everywhere else a device is torn down
+        // after failure.
+        let consumer = audio::import::Consumer::offline(device, samples, rate)?;
+        let mut nodes = video::scene::SceneNodes::new(device, FORMAT, usage, options)?;
+        consumer.feed(nodes.spectrum.inlet())?;
+        let mut deletions = DeletionQueue::new();
+        nodes.provision(device, extent, &mut deletions, 0)?;
+        let image = image::Image::new(device, extent, FORMAT, usage)?;
+        image.set_name(device, "export frame");
+        let view = image.default_view(device)?;
+        let readback =
+            buffer::MappedAllocation::new((extent.width * extent.height) as usize, device)?;
+        readback.set_name(device, "export readback");
+        let queue = device.queues.graphics_offscreen(QueuePriority::High);
+        let pool = CommandPool::transient(device, &queue.queue_ref())?;
+        let semaphore = device.make_timeline_semaphore()?;
+        let burns_in = overlay.as_ref().is_some_and(Overlay::burns_in);
+        let text = match burns_in || lyrics.is_some() {
+            true => Some(TextNode::new(device, FORMAT)?),
+            false => None,
+        };
+        Ok(Self {
+            consumer,
+            nodes,
+            image,
+            view,
+            readback,
+            pool,
+            semaphore,
+            deletions,
+            overlay,
+            lyrics,
+            text,
+        })
+    }
+
+    /// Draw and encode `total` video frames at `fps` from the audio `frames`.
+    fn render(
+        &mut self,
+        device: &Device,
+        frames: &[[f32; 2]],
+        fps: u32,
+        total: u64,
+        settings: &video::scene::SceneSettings,
+        encoder: &mut Child,
+    ) -> Result<(), MutateError> {
+        let rate = self.consumer.sample_rate().unwrap_or(1) as u64;
+        let fps = fps as u64;
+        let stdin = encoder
+            .stdin
+            .as_mut()
+            .ok_or_else(|| MutateError::Export("ffmpeg has no input".to_owned()))?;
+        // Scenes run on the video's clock, which starts now and advances one frame at a time.
+        let start = Instant::now();
+        let mut scenes = video::scene::Scenes::new(settings, start);
+        let mut pushed = 0;
+        for n in 0..total {
+            if utate::shutdown::requested() {
+                return Err(MutateError::Export("interrupted".to_owned()));
+            }
+            let at = start + Duration::from_secs_f64(n as f64 / fps as f64);
+            // The frame shows the audio up to its own time.
+            let end = ((n + 1) * rate / fps).min(frames.len() as u64) as usize;
+            self.consumer.advance_read(self.consumer.occupied_len()?)?;
+            let from = pushed;
+            pushed += self.consumer.push(device, &frames[from..end])?;
+            if let Some(overlay) = &mut self.overlay {
+                overlay.push(&frames[from..pushed])?;
+            }
+            if let Some(text) = &mut self.text {
+                let extent = self.image.extent;
+                let overlay = self.overlay.as_ref();
+                if let Some(overlay) = overlay.filter(|o| o.burns_in()) {
+                    overlay.queue(text, n, fps, extent);
+                }
+                if let Some(lyrics) = &self.lyrics {
+                    let beat = overlay.and_then(|o| o.analysis.reading().phase);
+                    lyrics.queue(text, Seconds(n as f64 / fps as f64), beat, extent);
+                }
+            }
+
+            let (current, leaving) = scenes.frame(at, &mut self.nodes);
+            self.nodes
+                .update(device, at, current, leaving, &mut self.deletions, n)?;
+            self.draw(device, current, leaving)?;
+            self.deletions.retire(n + 1, device);
+            self.write(device, stdin)?;
+            if n % (fps * 10) == 0 {
+                println!("export: {}s of {}s", n / fps, total / fps);
+            }
+        }
+        Ok(())
+    }
+
+    /// Record and submit one frame and wait for it, so that the ring and readback may be reused.
+    fn draw(
+        &mut self,
+        device: &Device,
+        current: video::scene::Scene,
+        leaving: Option<(video::scene::Scene, f32)>,
+    ) -> Result<(), MutateError> {
+        let ring = video::ring::RingPosition::of(&self.consumer)?;
+        let acquired_image =
+            AcquiredImage::offscreen(self.image.image, self.view.view, self.image.extent);
+        let cb = self.pool.primary(device)?;
+        image::transition_layout(
+            acquired_image.image,
+            &cb,
+            image::range(),
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            device,
+        );
+        self.nodes
+            .draw(device, &cb, &acquired_image, &ring, current, leaving);
+        if let Some(text) = &mut self.text {
+            text.draw(device, &cb, &acquired_image)?;
+        }
+        self.copy_back(device, *cb);
+        let recorded = cb.end(device)?;
+        // Descriptors registered while recording must be written before the work is submitted.
+        device.descriptors.flush(device.as_raw());
+        let intent = self.semaphore.next_signal();
+        let wait = intent.wait_value();
+        device
+            .queues
+            .graphics_offscreen(QueuePriority::High)
+            .queue_ref()
+            .submission()
+            .execute(recorded)
+            .signal(intent, vk::PipelineStageFlags2::ALL_COMMANDS)
+            .submit(device, vk::Fence::null())?;
+        wait.wait(device, FRAME_TIMEOUT)?;
+        // The only buffer from this pool just retired.
+        unsafe { self.pool.reset(device, false)? };
+        Ok(())
+    }
+
+    /// Copy the frame, left in `TRANSFER_DST_OPTIMAL` by the nodes, into the readback buffer.
+    fn copy_back(&self, device: &Device, cb: vk::CommandBuffer) {
+        let to_source = vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .dst_access_mask(vk::AccessFlags2::TRANSFER_READ)
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .image(self.image.image)
+            .subresource_range(image::range());
+        let to_host = vk::BufferMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::HOST)
+            .dst_access_mask(vk::AccessFlags2::HOST_READ)
+            .buffer(self.readback.buffer)
+            .size(vk::WHOLE_SIZE);
+        unsafe {
+            let raw = device.as_raw();
+            raw.cmd_pipeline_barrier2(
+                cb,
+                &vk::DependencyInfo::default().image_memory_barriers(&[to_source]),
+            );
+            raw.cmd_copy_image_to_buffer(
+                cb,
+                self.image.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                self.readback.buffer,
+                &[buffer::buffer_image_copy_full(self.image.extent)],
+            );
+            raw.cmd_pipeline_barrier2(
+                cb,
+                &vk::DependencyInfo::default().buffer_memory_barriers(&[to_host]),
+            );
+        }
+    }
+
+    /// Hand the frame just drawn to the encoder.
+    fn write(&mut self, device: &Device, stdin: &mut ChildStdin) -> Result<(), MutateError> {
+        self.readback.invalidate(device)?;
+        let pixels = self.readback.as_mut_slice().as_flattened();
+        stdin
+            .write_all(pixels)
+            .map_err(|e| MutateError::Export(format!("writing to ffmpeg failed: {e}")))
+    }
+
+    /// Close the overlay's sidecar, if it has one, at `end`.
+    fn finish_overlay(&mut self, end: Seconds) -> Result<(), MutateError> {
+        self.overlay
+            .take()
+            .map_or(Ok(()), |overlay| overlay.finish(end))
+    }
+
+    /// The last frame was waited on, so nothing is in flight.
+    fn destroy(mut self, device: &Device) {
+        self.deletions.flush(device);
+        self.nodes.destroy(device);
+        if let Some(Err(e)) = self.text.take().map(|text| text.destroy(device)) {
+            eprintln!("export: text destruction failed {:?}", e);
+        }
+        if let Err(e) = self.consumer.destroy(device) {
+            eprintln!("export: audio ring destruction failed {:?}", e);
+        }
+        if let Err(e) = self.readback.destroy(device) {
+            eprintln!("export: readback destruction failed {:?}", e);
+        }
+        if let Err(e) = self.view.destroy(device) {
+            eprintln!("export: view destruction failed {:?}", e);
+        }
+        if let Err(e) = self.image.destroy(device) {
+            eprintln!("export: image destruction failed {:?}", e);
+        }
+        self.semaphore.destroy(device);
+        self.pool.destroy(device);
+    }
+}
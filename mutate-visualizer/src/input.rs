@@ -0,0 +1,94 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Input
+//!
+//! The visualizer's actions and their default keys.  Keys are rebound in the
+//! [config file](crate::config).  Actions the visualizer does not know are handed to the graph,
+//! where nodes may handle them.
+//!
+//! | Key           | Action             | Does                                         |
+//! |---------------|--------------------|----------------------------------------------|
+//! | `F`           | `fullscreen`       | toggle fullscreen                            |
+//! | `S`           | `stats`            | toggle the stats overlay                     |
+//! | `E`           | `feedback`         | show echo trails or the ring                 |
+//! | `O`           | `scope`            | show the oscilloscope or the ring            |
+//! | `V`           | `vectorscope`      | show the vectorscope or the ring             |
+//! | `L`           | `vectorscope-axes` | mid-side or left-right vectorscope axes      |
+//! | `W`           | `waterfall`        | show the waterfall or the ring               |
+//! | `D`           | `particles`        | show the particle fountain or the ring       |
+//! | `I`           | `image`            | show the picture or the ring                 |
+//! | `Tab`         | `cycle`            | crossfade to the next scene                  |
+//! | `A`           | `sources`          | toggle the audio source picker               |
+//! | `P`           | `pause`            | stop and resume drawing                      |
+//! | `F12`         | `screenshot`       | save the next frame in the working directory |
+//! | `Q`, `Escape` | `quit`             | exit                                         |
+
+use mutate_lib::input::Bindings;
+use winit::keyboard as kb;
+
+pub const FULLSCREEN: &str = "fullscreen";
+pub const STATS: &str = "stats";
+pub const FEEDBACK: &str = "feedback";
+pub const SCOPE: &str = "scope";
+pub const VECTORSCOPE: &str = "vectorscope";
+pub const AXES: &str = "vectorscope-axes";
+pub const WATERFALL: &str = "waterfall";
+pub const PARTICLES: &str = "particles";
+pub const IMAGE: &str = "image";
+pub const CYCLE: &str = "cycle";
+pub const SOURCES: &str = "sources";
+pub const PAUSE: &str = "pause";
+pub const SCREENSHOT: &str = "screenshot";
+pub const QUIT: &str = "quit";
+
+/// Every action the visualizer handles itself.
+pub const ACTIONS: &[&str] = &[
+    FULLSCREEN,
+    STATS,
+    FEEDBACK,
+    SCOPE,
+    VECTORSCOPE,
+    AXES,
+    WATERFALL,
+    PARTICLES,
+    IMAGE,
+    CYCLE,
+    SOURCES,
+    PAUSE,
+    SCREENSHOT,
+    QUIT,
+];
+
+pub fn defaults() -> Bindings {
+    Bindings::new()
+        .with_binding("F", FULLSCREEN)
+        .with_binding("S", STATS)
+        .with_binding("E", FEEDBACK)
+        .with_binding("O", SCOPE)
+        .with_binding("V", VECTORSCOPE)
+        .with_binding("L", AXES)
+        .with_binding("W", WATERFALL)
+        .with_binding("D", PARTICLES)
+        .with_binding("I", IMAGE)
+        .with_binding("Tab", CYCLE)
+        .with_binding("A", SOURCES)
+        .with_binding("P", PAUSE)
+        .with_binding("F12", SCREENSHOT)
+        .with_binding("Q", QUIT)
+        .with_binding("Escape", QUIT)
+}
+
+/// Name of a physical key as bindings spell it.  `None` for keys winit cannot identify.
+pub fn key_name(key: kb::PhysicalKey) -> Option<String> {
+    let kb::PhysicalKey::Code(code) = key else {
+        return None;
+    };
+    // LIES the names are winit's `KeyCode` variants, which are stable in practice but not promised.
+    let name = format!("{code:?}");
+    let short = name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .filter(|s| s.len() == 1);
+    Some(short.unwrap_or(&name).to_owned())
+}
@@ -0,0 +1,459 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Layer Shell
+//!
+//! Draw the scenes as a live wallpaper, or over the desktop, on Wayland compositors with the
+//! wlr-layer-shell protocol, such as Sway, Hyprland, river, and KDE.  winit only makes ordinary
+//! windows, so this mode talks to the compositor itself and hands the surface to Vulkan by raw
+//! handle.
+//!
+//! ```text
+//! mutate-visualizer --layer background --output DP-1 --scene vectorscope --rotate 60
+//! ```
+//!
+//! The surface covers the whole output and never takes keyboard focus, so there are no key
+//! bindings.  Pointer input passes through to whatever is underneath.  Scenes rotate as configured.
+//! The buffer follows the output's scale, so the visuals stay sharp on scaled displays.
+
+// MAYBE one surface per output when `--output` is not given.
+// MAYBE stop drawing while the surface is fully hidden, once the compositor tells us.
+
+use std::ptr::NonNull;
+use std::str::FromStr;
+use std::time::Instant;
+
+use ash::vk;
+use raw_window_handle::{
+    RawDisplayHandle, RawWindowHandle, WaylandDisplayHandle, WaylandWindowHandle,
+};
+use smithay_client_toolkit::{
+    compositor::{CompositorHandler, CompositorState, Region},
+    delegate_compositor, delegate_layer, delegate_output, delegate_registry,
+    output::{OutputHandler, OutputState},
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+    shell::{
+        wlr_layer::{
+            Anchor, KeyboardInteractivity, Layer, LayerShell, LayerShellHandler, LayerSurface,
+            LayerSurfaceConfigure,
+        },
+        WaylandSurface,
+    },
+};
+use wayland_client::{
+    globals::registry_queue_init,
+    protocol::{wl_output, wl_surface},
+    Connection, EventQueue, Proxy, QueueHandle,
+};
+
+use mutate_lib::{self as utate, prelude::*};
+use utate::graph::{context::FRAMES_IN_FLIGHT, DeletionQueue};
+
+use crate::config::Config;
+use crate::{video, Args};
+
+/// Which layer of the desktop to draw in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// Under everything, as a wallpaper.
+    Background,
+    /// Over the wallpaper, under windows.
+    Bottom,
+    /// Over windows, under fullscreen windows.
+    Top,
+    /// Over everything.
+    Overlay,
+}
+
+impl From<Placement> for Layer {
+    fn from(placement: Placement) -> Self {
+        match placement {
+            Placement::Background => Layer::Background,
+            Placement::Bottom => Layer::Bottom,
+            Placement::Top => Layer::Top,
+            Placement::Overlay => Layer::Overlay,
+        }
+    }
+}
+
+impl FromStr for Placement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "background" => Ok(Placement::Background),
+            "bottom" => Ok(Placement::Bottom),
+            "top" => Ok(Placement::Top),
+            "overlay" => Ok(Placement::Overlay),
+            _ => Err(format!(
+                "unknown layer `{s}`, expected background, bottom, top, or overlay"
+            )),
+        }
+    }
+}
+
+/// Wayland state, updated by the compositor's events.
+struct Shell {
+    registry: RegistryState,
+    outputs: OutputState,
+    /// Surface size in logical pixels, once configured.
+    size: Option<(u32, u32)>,
+    scale: u32,
+    /// The size or scale changed since the swapchain was made.
+    resized: bool,
+    closed: bool,
+}
+
+impl Shell {
+    /// The swapchain extent in buffer pixels.
+    fn extent(&self) -> vk::Extent2D {
+        let (width, height) = self.size.unwrap_or((1, 1));
+        vk::Extent2D {
+            width: width * self.scale,
+            height: height * self.scale,
+        }
+    }
+}
+
+impl CompositorHandler for Shell {
+    fn scale_factor_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        surface: &wl_surface::WlSurface,
+        new_factor: i32,
+    ) {
+        let scale = new_factor.max(1) as u32;
+        if scale != self.scale {
+            surface.set_buffer_scale(scale as i32);
+            self.scale = scale;
+            self.resized = true;
+        }
+    }
+
+    fn transform_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_transform: wl_output::Transform,
+    ) {
+    }
+
+    fn frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _time: u32,
+    ) {
+    }
+
+    fn surface_enter(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+
+    fn surface_leave(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl OutputHandler for Shell {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.outputs
+    }
+
+    fn new_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn update_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn output_destroyed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl LayerShellHandler for Shell {
+    /// Also sent when the output goes away.
+    fn closed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _layer: &LayerSurface) {
+        self.closed = true;
+    }
+
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _layer: &LayerSurface,
+        configure: LayerSurfaceConfigure,
+        _serial: u32,
+    ) {
+        // Anchored to every edge, so both are set by the compositor.
+        let (width, height) = configure.new_size;
+        let size = Some((width.max(1), height.max(1)));
+        if size != self.size {
+            self.size = size;
+            self.resized = true;
+        }
+    }
+}
+
+impl ProvidesRegistryState for Shell {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry
+    }
+    registry_handlers![OutputState];
+}
+
+delegate_compositor!(Shell);
+delegate_output!(Shell);
+delegate_layer!(Shell);
+delegate_registry!(Shell);
+
+/// Draw on the desktop until the surface is closed or the process is signaled.
+pub fn run(
+    args: &Args,
+    placement: Placement,
+    config: &Config,
+    debug: DebugOptions,
+) -> Result<(), MutateError> {
+    let error = |e: String| MutateError::LayerShell(e);
+    let conn = Connection::connect_to_env().map_err(|e| error(format!("{e}")))?;
+    let (globals, mut queue) = registry_queue_init(&conn).map_err(|e| error(format!("{e}")))?;
+    let qh = queue.handle();
+    let compositor = CompositorState::bind(&globals, &qh).map_err(|e| error(format!("{e}")))?;
+    let layer_shell = LayerShell::bind(&globals, &qh).map_err(|e| {
+        error(format!(
+            "the compositor does not support wlr-layer-shell: {e}"
+        ))
+    })?;
+    let mut shell = Shell {
+        registry: RegistryState::new(&globals),
+        outputs: OutputState::new(&globals, &qh),
+        size: None,
+        scale: 1,
+        resized: false,
+        closed: false,
+    };
+    // Outputs announce their names in the first round trip.
+    queue
+        .roundtrip(&mut shell)
+        .map_err(|e| error(format!("{e}")))?;
+    let output = match &args.output {
+        Some(name) => Some(find_output(&shell.outputs, name)?),
+        None => None,
+    };
+    let layer = layer_shell.create_layer_surface(
+        &qh,
+        compositor.create_surface(&qh),
+        placement.into(),
+        Some("mutate"),
+        output.as_ref(),
+    );
+    layer.set_anchor(Anchor::all());
+    // Cover panels too instead of fitting between them.
+    layer.set_exclusive_zone(-1);
+    layer.set_keyboard_interactivity(KeyboardInteractivity::None);
+    let passthrough = Region::new(&compositor).map_err(|e| error(format!("{e}")))?;
+    layer.set_input_region(Some(passthrough.wl_region()));
+    layer.commit();
+    while shell.size.is_none() && !shell.closed {
+        queue
+            .blocking_dispatch(&mut shell)
+            .map_err(|e| error(format!("{e}")))?;
+    }
+
+    let display = NonNull::new(conn.backend().display_ptr().cast())
+        .ok_or_else(|| error("no display pointer".to_owned()))?;
+    let display = RawDisplayHandle::Wayland(WaylandDisplayHandle::new(display));
+    let window = NonNull::new(layer.wl_surface().id().as_ptr().cast())
+        .ok_or_else(|| error("no surface pointer".to_owned()))?;
+    let window = RawWindowHandle::Wayland(WaylandWindowHandle::new(window));
+
+    let instance = Instance::with_raw_display_debug(display, &[], debug)?;
+    // SAFETY: the connection and layer surface outlive the Vulkan surface, which is destroyed
+    // with the drawing below.
+    let raw_surface = unsafe { instance.surface_from_raw(display, window)? };
+    let result = crate::select_device(&instance, args, raw_surface).and_then(|mut device| {
+        let result = draw(
+            &instance,
+            &mut device,
+            raw_surface,
+            args,
+            config,
+            &mut queue,
+            &mut shell,
+        );
+        device.destroy();
+        result
+    });
+    instance.destroy();
+    result
+}
+
+fn find_output(outputs: &OutputState, name: &str) -> Result<wl_output::WlOutput, MutateError> {
+    let named: Vec<_> = outputs
+        .outputs()
+        .filter_map(|output| Some((outputs.info(&output)?.name?, output)))
+        .collect();
+    named
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, output)| output.clone())
+        .ok_or_else(|| {
+            let names: Vec<_> = named.iter().map(|(n, _)| n.as_str()).collect();
+            MutateError::LayerShell(format!(
+                "no output named `{name}`, expected one of {}",
+                names.join(", ")
+            ))
+        })
+}
+
+/// The render loop.  Returns after destroying what it made on `device`.
+fn draw(
+    instance: &Instance,
+    device: &mut Device,
+    raw_surface: vk::SurfaceKHR,
+    args: &Args,
+    config: &Config,
+    queue: &mut EventQueue<Shell>,
+    shell: &mut Shell,
+) -> Result<(), MutateError> {
+    let (mut audio, _) = crate::open_audio(device, args, &config.settings.audio)?;
+    let present = config.settings.video.present_preference();
+    let mut surface = Surface::new(instance, device, raw_surface, shell.extent())
+        .and_then(|s| s.with_present_preference(device, present))?;
+    let mut present_ring = PresentRing::new(device, instance, &surface)?;
+    let settings = args.scenes(&config.scenes);
+    let options = args.scene_options(&config.settings, &settings);
+    let mut nodes = video::scene::SceneNodes::new(
+        device,
+        surface.format(),
+        surface.caps.image_usage,
+        &options,
+    )?;
+    audio.feed(nodes.spectrum.inlet())?;
+    let mut deletions = DeletionQueue::new();
+    nodes.provision(device, surface.extent(), &mut deletions, 0)?;
+    let mut scenes = video::scene::Scenes::new(&settings, Instant::now());
+    shell.resized = false;
+    let mut frames = 0u64;
+
+    let result = loop {
+        if shell.closed || utate::shutdown::requested() {
+            break Ok(());
+        }
+        if let Err(e) = dispatch(queue, shell) {
+            break Err(e);
+        }
+        audio.log_events();
+        if audio.wants_demo() {
+            if let Err(e) = audio.fall_back(device) {
+                eprintln!("layer: demo fallback failed {:?}", e);
+            }
+        } else if audio.resumed() {
+            if let Err(e) = audio.resume(device) {
+                eprintln!("layer: resuming audio failed {:?}", e);
+            }
+        }
+        if shell.resized {
+            shell.resized = false;
+            let resized = present_ring
+                .update_swapchain(device, &mut surface, shell.extent())
+                .map_err(MutateError::from)
+                .and_then(|size| nodes.provision(device, size, &mut deletions, frames));
+            if let Err(e) = resized {
+                eprintln!("layer: resize failed {:?}", e);
+            }
+        }
+
+        // black hole the data to check the ring tracking
+        let ring = match audio.ring() {
+            Ok(ring) => ring,
+            Err(e) => break Err(e),
+        };
+        let now = Instant::now();
+        let (current, leaving) = scenes.frame(now, &mut nodes);
+        if let Err(e) = nodes.update(device, now, current, leaving, &mut deletions, frames) {
+            eprintln!("layer: updating scenes failed {:?}", e);
+        }
+        let recorded = present_ring.record(
+            device,
+            compute_present(device, |device, cb, acquired_image| {
+                nodes.draw(device, cb, acquired_image, &ring, current, leaving);
+            }),
+            || {},
+        );
+        match recorded {
+            Ok(()) => {
+                frames += 1;
+                deletions.retire(frames.saturating_sub(FRAMES_IN_FLIGHT as u64), device);
+            }
+            Err(e) if e.needs_swapchain_recreation() => shell.resized = true,
+            Err(e) if e.is_device_lost() => break Err(e.into()),
+            Err(e) => {
+                eprintln!("layer: draw failed {:?}", e);
+                // The abandoned frame may have moved the targets of the nodes that draw offscreen.
+                nodes.reset();
+            }
+        }
+    };
+
+    // Waits on a lost device fail at once, and then destruction is all that is left to do.
+    if let Err(e) = present_ring.drain(device) {
+        if !e.is_device_lost() {
+            eprintln!("layer: drain failed {:?}", e);
+        }
+    }
+    deletions.flush(device);
+    nodes.destroy(device);
+    if let Err(e) = audio.destroy(device) {
+        eprintln!("layer: audio teardown failed {:?}", e);
+    }
+    present_ring.destroy(device);
+    surface.destroy();
+    result
+}
+
+/// Handle whatever the compositor sent without waiting for more.
+fn dispatch(queue: &mut EventQueue<Shell>, shell: &mut Shell) -> Result<(), MutateError> {
+    let error = |e: String| MutateError::LayerShell(e);
+    queue.flush().map_err(|e| error(format!("{e}")))?;
+    if let Some(guard) = queue.prepare_read() {
+        match guard.read() {
+            Ok(_) => {}
+            Err(wayland_client::backend::WaylandError::Io(e))
+                if e.kind() == std::io::ErrorKind::WouldBlock => {}
+            Err(e) => return Err(error(format!("{e}"))),
+        }
+    }
+    queue
+        .dispatch_pending(shell)
+        .map_err(|e| error(format!("{e}")))?;
+    Ok(())
+}
@@ -10,10 +10,19 @@
 //!   stream.
 
 mod audio;
+mod config;
+#[cfg(feature = "control")]
+mod control;
+mod doctor;
+mod export;
+mod input;
+mod layer;
+mod poster;
 mod video;
 mod window;
 
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 use ash::vk;
 use clap::Parser;
@@ -27,7 +36,11 @@ use winit::{
 };
 
 use mutate_lib::{self as utate, prelude::*};
+use utate::assets::ShaderWatcher;
+use utate::graph::throttle::{Degradation, ThrottleGovernor};
+use utate::graph::{context::FRAMES_IN_FLIGHT, DeletionQueue, FrameStats, FrameTiming, GraphContext};
 
+use video::scene::Scene;
 use window::WindowExt;
 
 #[derive(Parser, Debug)]
@@ -35,8 +48,253 @@ struct Args {
     /// Start in fullscreen mode
     #[arg(short = 'f', long = "fullscreen")]
     fullscreen: bool,
+
+    /// Draw on the desktop instead of in a window, in the `background` as a live wallpaper or in the
+    /// `bottom`, `top`, or `overlay` layer.  Needs a Wayland compositor with wlr-layer-shell.  Input
+    /// passes through, so there are no key bindings.
+    #[arg(long, value_name = "LAYER", conflicts_with_all = ["fullscreen", "export", "poster"])]
+    layer: Option<layer::Placement>,
+
+    /// The output to draw on with `--layer`, such as `DP-1`.  Defaults to the compositor's choice.
+    #[arg(long, value_name = "NAME", requires = "layer")]
+    output: Option<String>,
+
+    /// Play a synthesized demo song instead of listening to an audio source.  Also used when there
+    /// are no sources or the chosen source stays silent.
+    #[arg(long)]
+    demo: bool,
+
+    /// Listen to the audio source with this name instead of choosing one in the window.  Part of a
+    /// name is enough, ignoring case.  Press `A` to choose another.
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["demo", "file", "signal"])]
+    source: Option<String>,
+
+    /// Play a WAV or FLAC file on a loop instead of listening to an audio source.
+    #[arg(long, value_name = "PATH")]
+    file: Option<std::path::PathBuf>,
+
+    /// Show the timed lyrics of an LRC file over `--file`.  An LRC file beside `--file` with the
+    /// same name is shown without this.
+    #[arg(long, value_name = "PATH", requires = "file")]
+    lyrics: Option<std::path::PathBuf>,
+
+    /// Play a test signal instead of listening to an audio source, such as `sine:440`,
+    /// `chirp:20:20000:10`, `white`, `pink`, or `impulse:0.5`.
+    #[arg(long, value_name = "SIGNAL", conflicts_with = "file")]
+    signal: Option<utate::audio::synthetic::Signal>,
+
+    /// Render `--file` to a video at this path without opening a window, and exit.  Frames are
+    /// piped to `ffmpeg`, which picks the format from the extension.
+    #[arg(long, value_name = "PATH", requires = "file", conflicts_with = "record")]
+    export: Option<std::path::PathBuf>,
+
+    /// Frames per second of `--export`.
+    #[arg(long, value_name = "FPS", default_value_t = 60, requires = "export")]
+    fps: u32,
+
+    /// Size of `--export` frames in pixels, such as `1280x720`.
+    #[arg(
+        long,
+        value_name = "WIDTHxHEIGHT",
+        default_value = "1920x1080",
+        value_parser = export::parse_size,
+        requires = "export"
+    )]
+    size: vk::Extent2D,
+
+    /// Analysis overlays for `--export` from `spectrogram`, `beats`, `sections`, and `loudness`,
+    /// such as `beats,loudness`, or `all`.
+    #[arg(long, value_name = "LIST", requires = "export")]
+    overlays: Option<utate::export::overlay::Overlays>,
+
+    /// Where `--overlays` go.  `sidecar` writes a timed text track beside the video.  `burn-in`
+    /// draws them over the frames with the title and timecode.
+    #[arg(
+        long,
+        value_name = "MODE",
+        default_value = "sidecar",
+        requires = "overlays"
+    )]
+    overlay_mode: utate::export::overlay::OverlayMode,
+
+    /// Format of the `--overlays` sidecar, `vtt` or `jsonl`.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "vtt",
+        requires = "overlays"
+    )]
+    sidecar_format: utate::export::overlay::SidecarFormat,
+
+    /// Draw all of `--file` as one tall spectrogram image at this path without opening a window,
+    /// and exit.  Time runs down and pitch across.  The image is a binary PPM.
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "file",
+        conflicts_with_all = ["export", "record"]
+    )]
+    poster: Option<std::path::PathBuf>,
+
+    /// Rows of `--poster` per second of audio.
+    #[arg(long, value_name = "N", default_value_t = 100.0, requires = "poster")]
+    rows_per_second: f64,
+
+    /// Leave the octave guides and time ticks off `--poster`.
+    #[arg(long, requires = "poster")]
+    no_guides: bool,
+
+    /// Write frequencies across the top of `--poster` and times down its left edge.
+    #[arg(long, requires = "poster")]
+    axis_labels: bool,
+
+    /// Record the audio the visuals see to a WAV file, with arrival times in a `.csv` beside it.
+    /// Attach the files to bug reports and replay them with `--file`.
+    #[arg(long, value_name = "PATH")]
+    record: Option<std::path::PathBuf>,
+
+    /// Build the node graph from a preset file, which names the nodes to run, their edges, and
+    /// their parameters.  Its `audio` nodes hear what is playing, its `drive` node steers the
+    /// scenes, and its layout tiles its `scene` nodes across each window.  `song` names the preset
+    /// built in.
+    #[arg(long, value_name = "PATH")]
+    graph: Option<std::path::PathBuf>,
+
+    /// Read settings such as key bindings from this file instead of `mutate/mutate.toml` in
+    /// `$XDG_CONFIG_HOME`, `~/.config`, or `$XDG_CONFIG_DIRS`.  Edits are picked up while running.
+    #[arg(long, value_name = "PATH")]
+    config: Option<std::path::PathBuf>,
+
+    /// Print a capability report for bug reports and exit.
+    #[arg(long)]
+    doctor: bool,
+
+    /// Print each window's presentation and frame statistics when it closes.  The stats overlay
+    /// shows them while running.
+    #[arg(long)]
+    stats: bool,
+
+    /// Choose the GPU by type (`discrete`, `integrated`, `virtual`, `cpu`), by index, or by part of
+    /// its name.  Overrides `MUTATE_GPU`.  `--doctor` lists the devices.
+    #[arg(long, value_name = "GPU")]
+    gpu: Option<String>,
+
+    /// Vulkan validation: `on`, `off`, or `verbose`.  Overrides `MUTATE_VALIDATION`.  Defaults to on
+    /// in debug builds.
+    #[arg(long, value_name = "MODE")]
+    validation: Option<String>,
+
+    /// Samples per pixel for render nodes: 1, 2, 4, or 8.  Rounded down to what the GPU supports.
+    #[arg(long, value_name = "SAMPLES", default_value_t = 4)]
+    msaa: u32,
+
+    /// Presentation: `auto`, `fifo`, `relaxed`, `mailbox`, or `immediate`.  Overrides the config
+    /// file, where `vsync = false` means `immediate`.  Falls back to `auto` when the display does
+    /// not support the mode.
+    #[arg(long, value_name = "MODE")]
+    present_mode: Option<PresentPreference>,
+
+    /// Start on this scene: `ring`, `feedback`, `scope`, `vectorscope`, `waterfall`, `particles`,
+    /// or `image`.  Overrides the config file.  `Tab` switches to the next one.
+    #[arg(long, value_name = "NAME")]
+    scene: Option<video::scene::Scene>,
+
+    /// Switch to the next scene every this many seconds, or never with zero.  Overrides the config
+    /// file.
+    #[arg(long, value_name = "SECONDS")]
+    rotate: Option<f64>,
+
+    /// Picture the `image` scene shows, a binary PPM such as a screenshot.  Overrides the config
+    /// file.
+    #[arg(long, value_name = "PATH")]
+    image: Option<std::path::PathBuf>,
+
+    /// Start with echo trails of previous frames under the current one.  Same as `--scene
+    /// feedback`.  Toggle with `E`.
+    #[arg(long, conflicts_with = "scene")]
+    feedback: bool,
+
+    /// Start with an oscilloscope of the raw audio in place of the ring.  Same as `--scene scope`.
+    /// Toggle with `O`.
+    #[arg(long, conflicts_with = "scene")]
+    scope: bool,
+
+    /// Start with a vectorscope of left against right in place of the ring.  Same as `--scene
+    /// vectorscope`.  Toggle with `V` and switch between mid-side and left-right axes with `L`.
+    #[arg(long, conflicts_with = "scene")]
+    vectorscope: bool,
 }
 
+impl Args {
+    /// Flags that override the config file, as a table of the same shape.
+    fn overrides(&self) -> toml::Table {
+        let mut audio = toml::Table::new();
+        if let Some(source) = &self.source {
+            audio.insert("source".into(), source.clone().into());
+        }
+        let mut video = toml::Table::new();
+        if self.fullscreen {
+            video.insert("fullscreen".into(), true.into());
+        }
+        if let Some(mode) = self.present_mode {
+            video.insert("present_mode".into(), mode.to_string().into());
+        }
+        let mut table = toml::Table::new();
+        table.insert("audio".into(), audio.into());
+        table.insert("video".into(), video.into());
+        table
+    }
+
+    /// Scene settings from the command line over those of the config file.
+    fn scenes(&self, config: &video::scene::SceneSettings) -> video::scene::SceneSettings {
+        let start = match self.scene {
+            Some(scene) => scene,
+            None if self.scope => Scene::Scope,
+            None if self.vectorscope => Scene::Vectorscope,
+            None if self.feedback => Scene::Feedback,
+            None => config.start,
+        };
+        let rotate = match self.rotate {
+            Some(seconds) if seconds > 0.0 && seconds.is_finite() => {
+                Some(Duration::from_secs_f64(seconds))
+            }
+            Some(_) => None,
+            None => config.rotate,
+        };
+        video::scene::SceneSettings {
+            start,
+            rotate,
+            fade: config.fade,
+            palette: config.palette.clone(),
+            image: self.image.clone().or_else(|| config.image.clone()),
+        }
+    }
+
+    /// How scene nodes are built, from `--msaa`, the `[dsp]` settings, and the picture and palette
+    /// of `scenes`.
+    fn scene_options(
+        &self,
+        settings: &utate::settings::Settings,
+        scenes: &video::scene::SceneSettings,
+    ) -> video::scene::SceneOptions {
+        video::scene::SceneOptions {
+            msaa: self.msaa,
+            dsp: settings.dsp.clone(),
+            image: scenes.image.clone(),
+            palette: scenes.palette.clone(),
+        }
+    }
+}
+
+/// How often to check for rebuilt shaders.
+const SHADER_POLL: std::time::Duration = std::time::Duration::from_millis(500);
+/// How often to check for edits to the config file.
+const CONFIG_POLL: Duration = Duration::from_secs(1);
+/// How often the stats overlay updates the numbers in the window title.
+const TITLE_STATS: Duration = Duration::from_millis(500);
+/// Nanoseconds to wait for the graph frame that last used a command slot.
+const GRAPH_TIMEOUT: u64 = 5_000_000_000;
+
 /// Each time we construct a window, we need a surface and swapchain to run the render loop for that
 /// window.
 struct WindowContext {
@@ -48,7 +306,33 @@ struct WindowContext {
     // device can be shared per window that the device supports.  Rare device-per-window cases, if
     // they still exist, would require only one audio downstream per device and then that data can
     // be reused for all windows.
-    renderer: video::ring::RawRingDraw,
+    nodes: video::scene::SceneNodes,
+    scenes: video::scene::Scenes,
+    /// Scenes side by side from the preset's layout, drawn in place of `scenes`.
+    tiles: Option<video::tiles::Tiles>,
+    /// Where the pointer last was, for focusing the tile clicked.
+    cursor: (f64, f64),
+    /// Rebuilds the renderer's pipelines when their shaders are rebuilt.
+    shaders: ShaderWatcher,
+    /// Pipelines replaced by hot reload and outputs replaced by resizing, waiting on the frames that
+    /// used them.
+    deletions: DeletionQueue<Device>,
+    /// Paces frames to wake just before their audio deadline.
+    timing: FrameTiming,
+    stats: FrameStats,
+    /// Sheds work when the GPU time of frames runs over the refresh period.
+    governor: ThrottleGovernor,
+    overlay: video::overlay::StatsOverlay,
+    text: video::text::TextNode,
+    screenshot: video::screenshot::Screenshot,
+    /// Stats drawn in the top right corner while the overlay is visible.  Refreshed along with the title.
+    stats_text: String,
+    /// Levels of what plays, drawn under the stats and refreshed with them.
+    levels_text: String,
+    /// When the title last showed stats.
+    titled: Instant,
+    /// Frames recorded.
+    frames: u64,
 }
 
 impl WindowContext {
@@ -57,70 +341,347 @@ impl WindowContext {
         device: &mut Device,
         window: winit::window::Window,
         raw_surface: vk::SurfaceKHR,
-    ) -> Self {
-        let surface = Surface::new(instance, device, raw_surface, &window).unwrap();
-        let present_ring = PresentRing::new(device, instance, &surface).unwrap();
-        let mut renderer = video::ring::RawRingDraw::new(device);
-        renderer.provision(device, surface.extent()).unwrap();
-        Self {
+        present: PresentPreference,
+        scenes: &video::scene::SceneSettings,
+        options: &video::scene::SceneOptions,
+    ) -> Result<Self, MutateError> {
+        let surface = Surface::new(instance, device, raw_surface, &window)
+            .and_then(|s| s.with_present_preference(device, present))?;
+        let present_ring = PresentRing::new(device, instance, &surface)?;
+        let format = surface.format();
+        let mut nodes =
+            video::scene::SceneNodes::new(device, format, surface.caps.image_usage, options)?;
+        let mut deletions = DeletionQueue::new();
+        nodes.provision(device, surface.extent(), &mut deletions, 0)?;
+        let mut shaders = ShaderWatcher::new(SHADER_POLL);
+        for name in nodes.ring.shaders() {
+            if let Err(e) = shaders.watch(&device.assets, name) {
+                eprintln!("application: not watching {name} {:?}", e);
+            }
+        }
+        let timing = FrameTiming::new(window.refresh_period());
+        let governor = ThrottleGovernor::new(timing.period());
+        let overlay = video::overlay::StatsOverlay::new(device)?;
+        let text = video::text::TextNode::new(device, surface.format())?;
+        let screenshot = video::screenshot::Screenshot::new(surface.caps.image_usage);
+        Ok(Self {
             window,
             surface,
             present_ring,
-            renderer,
-        }
+            nodes,
+            scenes: video::scene::Scenes::new(scenes, Instant::now()),
+            tiles: None,
+            cursor: (0.0, 0.0),
+            shaders,
+            deletions,
+            timing,
+            stats: FrameStats::new(),
+            governor,
+            overlay,
+            text,
+            screenshot,
+            stats_text: String::new(),
+            levels_text: String::new(),
+            titled: Instant::now(),
+            frames: 0,
+        })
     }
 
-    fn draw_frame(&mut self, device: &mut Device, audio: &mut audio::Audio) {
+    /// Returns only errors the window cannot recover from by itself, such as a lost device.
+    fn draw_frame(
+        &mut self,
+        device: &mut Device,
+        audio: &mut audio::Audio,
+        beats: &mut audio::beats::Beats,
+        meter: &audio::meter::Meter,
+        picker: Option<&audio::picker::SourcePicker>,
+        lyrics: Option<&video::lyrics::LyricsView>,
+    ) -> Result<(), VulkanError> {
+        // Between frames is the only time pipelines can be swapped.
+        for name in self.shaders.poll() {
+            println!("reloading shader {name}");
+            if let Err(e) = self
+                .nodes
+                .ring
+                .reload(device, &name, &mut self.deletions, self.frames)
+            {
+                eprintln!("application: reloading {name} failed {:?}", e);
+            }
+        }
+        // Draw late so the frame reads the freshest audio for the vblank it targets.
+        // MAYBE each window sleeps on the event loop in turn.  Windows on displays with different
+        // phases need their own render threads.
+        let phases = self.timing.phases(Instant::now());
+        phases.sleep_until_audio();
+        let woke = Instant::now();
+
+        // NEXT the ring renderer becomes a graph node once `graph::Frame` carries the command
+        // buffer.  Until then the consumer's channels are wired to it here.
         // black hole the data to check the ring tracking
-        audio
-            .consumer
-            .advance_read(audio.consumer.occupied_len().unwrap_or(0))
-            .unwrap();
-        let channels = unsafe { audio.consumer.channels().unwrap() };
-        let left_channel = channels[0];
-        let right_channel = channels[1];
-        let capacity = audio.consumer.capacity();
-        self.present_ring
+        let ring = match audio.ring() {
+            Ok(ring) => ring,
+            Err(MutateError::VulkanError(e)) if e.is_device_lost() => return Err(e),
+            Err(e) => {
+                eprintln!("application: reading the audio ring failed {:?}", e);
+                return Ok(());
+            }
+        };
+        let captured = audio.consumer.newest_captured_at().unwrap_or(None);
+        let position = audio.position();
+        let period = self.timing.period();
+        // Beats are predicted for when this window's frames are seen.
+        if let Some(latency) = self.stats.audio_to_photon().quantile(0.5) {
+            beats.set_latency(latency);
+        }
+        let beat = beats.fire(period);
+        let (beat_phase, seen) = (beats.phase(), beats.latency());
+        let placed = self.tiles.as_ref().map(video::tiles::Tiles::placed);
+        let (current, leaving) = match placed {
+            Some(_) => (Scene::Ring, None),
+            None => self.scenes.frame(woke, &mut self.nodes),
+        };
+        let throttled =
+            self.nodes
+                .throttle(device, &self.governor, &mut self.deletions, self.frames);
+        if let Err(e) = throttled {
+            eprintln!("application: throttling failed {:?}", e);
+        }
+        let (deletions, frames) = (&mut self.deletions, self.frames);
+        let updated = match &placed {
+            Some(placed) => self
+                .nodes
+                .update_tiles(device, woke, placed, deletions, frames),
+            None => self
+                .nodes
+                .update(device, woke, current, leaving, deletions, frames),
+        };
+        if let Err(e) = updated {
+            eprintln!("application: updating scenes failed {:?}", e);
+        }
+        let overlay = &mut self.overlay;
+        let text = &mut self.text;
+        let screenshot = &mut self.screenshot;
+        let format = self.surface.format();
+        let stats_text = &self.stats_text;
+        let levels_text = &self.levels_text;
+        let level = self.governor.level();
+        let nodes = &mut self.nodes;
+        let recorded = self
+            .present_ring
             .record(
                 device,
                 compute_present(device, |device, cb, acquired_image| {
-                    self.renderer.draw(
-                        device,
-                        cb,
-                        acquired_image,
-                        left_channel,
-                        right_channel,
-                        capacity,
-                    );
+                    match &placed {
+                        Some(placed) => {
+                            nodes.draw_tiles(device, cb, acquired_image, &ring, placed)
+                        }
+                        None => nodes.draw(device, cb, acquired_image, &ring, current, leaving),
+                    }
+                    if let Err(e) = overlay.draw(device, cb, acquired_image, period) {
+                        eprintln!("application: overlay failed {:?}", e);
+                    }
+                    if let (Some(lyrics), Some(now)) = (lyrics, position) {
+                        // Sung as the frame is seen, stepping on the beat predicted for then.
+                        lyrics.queue(text, now + seen, beat_phase, acquired_image.extent);
+                    }
+                    if let Some(picker) = picker.filter(|p| p.is_visible()) {
+                        picker.draw(text);
+                    }
+                    if overlay.is_visible() {
+                        let style = video::text::TextStyle::default();
+                        let [width, height] = text.measure(stats_text, &style);
+                        let x = acquired_image.extent.width as f32 - width - 8.0;
+                        text.draw_text([x, 8.0], stats_text, &style);
+                        let [width, _] = text.measure(levels_text, &style);
+                        let x = acquired_image.extent.width as f32 - width - 8.0;
+                        text.draw_text([x, 8.0 + height], levels_text, &style);
+                    }
+                    if level != Degradation::Full {
+                        let style = video::text::TextStyle::default();
+                        let shed = format!("throttled: {level}");
+                        let [_, height] = text.measure(&shed, &style);
+                        let y = acquired_image.extent.height as f32 - height - 8.0;
+                        text.draw_text([8.0, y], &shed, &style);
+                    }
+                    if let Err(e) = text.draw(device, cb, acquired_image) {
+                        eprintln!("application: text failed {:?}", e);
+                    }
+                    if let Err(e) = screenshot.record(device, cb, acquired_image, format) {
+                        eprintln!("application: screenshot failed {:?}", e);
+                    }
                 }),
                 || self.window.pre_present_notify(),
-            )
-            .map_err(|e| match e {
-                utate::vulkan::VulkanError::SwapchainOutOfDate
-                | utate::vulkan::VulkanError::SwapchainSuboptimal => {
-                    self.handle_resize(device);
+            );
+        match recorded {
+            Ok(()) => {
+                let cpu = woke.elapsed();
+                if let Some(id) = self.present_ring.queued_id() {
+                    self.stats.observe_frame(id, &phases, cpu, captured);
                 }
-                _ => {
-                    eprintln!("application: draw failed {:?}", e);
+                let stats = self.present_ring.stats();
+                let dropped = self.stats.dropped();
+                if let Some(last) = stats.last() {
+                    // Plan around a high quantile so jitter in latency does not miss the vblank.
+                    let latency = stats.latency().quantile(0.9).unwrap_or(last.latency);
+                    self.timing.observe_present(last.presented, latency);
+                    self.stats.observe_present(last, period);
                 }
-            });
+                let gpu = stats.gpu().last();
+                let missed = self.stats.dropped() > dropped;
+                self.overlay.push(cpu, gpu, missed, beat.is_some());
+                if let Some(level) = gpu.and_then(|gpu| self.governor.observe(gpu)) {
+                    println!("application: {level}");
+                }
+                self.update_title(meter);
+                // Recording waited on the pool from two frames ago, so those frames have retired.
+                self.frames += 1;
+                self.screenshot.finish(&mut self.deletions, self.frames);
+                self.deletions.retire(
+                    self.frames.saturating_sub(FRAMES_IN_FLIGHT as u64),
+                    device,
+                );
+            }
+            Err(e) if e.needs_swapchain_recreation() => {
+                self.screenshot.abandon(&mut self.deletions, self.frames);
+                if let Err(e) = self.handle_resize(device) {
+                    eprintln!("application: swapchain recreation failed {:?}", e);
+                }
+            }
+            Err(e) if e.is_device_lost() => return Err(e),
+            Err(e) => {
+                eprintln!("application: draw failed {:?}", e);
+                self.screenshot.abandon(&mut self.deletions, self.frames);
+                // The abandoned frame may have moved the targets of the nodes that draw offscreen.
+                self.nodes.reset();
+            }
+        }
+        Ok(())
+    }
+
+    /// Show the stats in the title and the corner while the overlay is visible.
+    fn update_title(&mut self, meter: &audio::meter::Meter) {
+        if !self.overlay.is_visible() || self.titled.elapsed() < TITLE_STATS {
+            return;
+        }
+        self.titled = Instant::now();
+        let ms = |d: Option<Duration>| d.unwrap_or_default().as_secs_f64() * 1e3;
+        let present = self.present_ring.stats();
+        self.stats_text = format!(
+            "cpu {:.1}ms gpu {:.1}ms audio to photon {:.1}ms | {} dropped",
+            ms(self.stats.cpu().quantile(0.5)),
+            ms(present.gpu().quantile(0.5)),
+            ms(self.stats.audio_to_photon().quantile(0.5)),
+            self.stats.dropped(),
+        );
+        self.levels_text = meter.readout();
+        self.window
+            .set_title(&format!("{} | {}", window::TITLE, self.stats_text));
+    }
+
+    fn toggle_stats(&mut self) {
+        if !self.overlay.toggle() {
+            self.window.set_title(window::TITLE);
+        }
     }
 
+    /// Handle an action for this window.  Returns whether it was one of the window's.
+    fn act(&mut self, action: &str) -> bool {
+        match action {
+            input::FULLSCREEN => self.window.toggle_fullscreen(),
+            input::STATS => self.toggle_stats(),
+            input::FEEDBACK => self.toggle_scene(Scene::Feedback),
+            input::SCOPE => self.toggle_scene(Scene::Scope),
+            input::VECTORSCOPE => self.toggle_scene(Scene::Vectorscope),
+            input::WATERFALL => self.toggle_scene(Scene::Waterfall),
+            input::PARTICLES => self.toggle_scene(Scene::Particles),
+            input::IMAGE => self.toggle_scene(Scene::Image),
+            input::AXES => {
+                self.nodes.vectorscope.toggle_orientation();
+            }
+            input::CYCLE => {
+                let scene = match &mut self.tiles {
+                    Some(tiles) => tiles.next(),
+                    None => Some(self.scenes.next(Instant::now())),
+                };
+                if let Some(scene) = scene {
+                    self.nodes.enter(scene);
+                }
+            }
+            input::SCREENSHOT => self.screenshot.request(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Show `scene`, or the ring when `scene` is already showing.  With tiles, only the focused
+    /// tile changes.
+    fn toggle_scene(&mut self, scene: Scene) {
+        let scene = match &mut self.tiles {
+            Some(tiles) => tiles.toggle(scene),
+            None => Some(self.scenes.toggle(scene, Instant::now())),
+        };
+        if let Some(scene) = scene {
+            self.nodes.enter(scene);
+        }
+    }
+
+    /// Show the scene of the song's `section`.  Tiles keep the scenes they were given.
+    fn follow_section(&mut self, section: u32) {
+        if self.tiles.is_some() {
+            return;
+        }
+        if let Some(scene) = self.scenes.section(section, Instant::now()) {
+            self.nodes.enter(scene);
+        }
+    }
+
+    /// Draw `tiles` in place of the scene rotation.
+    fn set_tiles(&mut self, mut tiles: video::tiles::Tiles) {
+        tiles.resize(self.surface.extent());
+        self.tiles = Some(tiles);
+    }
+
+    /// Frames in flight keep drawing into the old output until they retire.
     fn handle_resize(&mut self, device: &mut Device) -> Result<(), MutateError> {
         let new_size = self
             .present_ring
-            .maybe_update_swapchain(device, &mut self.surface, &self.window)
-            .unwrap();
-        self.renderer.provision(device, new_size)?;
+            .update_swapchain(device, &mut self.surface, &self.window)?;
+        self.nodes
+            .provision(device, new_size, &mut self.deletions, self.frames)?;
+        if let Some(tiles) = &mut self.tiles {
+            tiles.resize(new_size);
+        }
         self.window.request_redraw();
         Ok(())
     }
 
-    /// Consumes self; call only after the device queue is idle for this window.
-    fn destroy(self, device: &mut Device) {
-        self.renderer.destroy(device);
+    /// Waits for this window's frames only, so other windows keep drawing.  With `report`, the
+    /// window's stats are printed first.
+    fn destroy(self, device: &mut Device, report: bool) {
+        if report {
+            println!("presentation: {}", self.present_ring.stats());
+            println!("frames: {}", self.stats);
+        }
+        self.release(device);
+    }
+
+    /// Destroy everything but the window, which can get a new surface on another device.
+    fn release(mut self, device: &mut Device) -> winit::window::Window {
+        // Waits on a lost device fail at once, and then destruction is all that is left to do.
+        if let Err(e) = self.present_ring.drain(device) {
+            if !e.is_device_lost() {
+                eprintln!("application: window drain failed {:?}", e);
+            }
+        }
+        self.deletions.flush(device);
+        self.overlay.destroy(device);
+        if let Err(e) = self.text.destroy(device) {
+            eprintln!("application: text destruction failed {:?}", e);
+        }
+        self.nodes.destroy(device);
         self.present_ring.destroy(device);
         self.surface.destroy();
+        self.window
     }
 }
 
@@ -129,42 +690,265 @@ impl WindowContext {
 // for different roles.
 struct ActiveApp {
     audio: audio::Audio,
-    device: Device,
+    /// Lists the server's sources.  `None` when playing a file, a test signal, or the demo by
+    /// request, or when there is no server.
+    picker: Option<audio::picker::SourcePicker>,
+    /// Finishes the recording when dropped.
+    recorder: Option<utate::audio::record::Recorder>,
+    /// Drawn over every window while a file plays.
+    lyrics: Option<video::lyrics::LyricsView>,
+    /// Predicted beats, for every window.
+    beats: audio::beats::Beats,
+    /// Levels of what plays, for every window's stats overlay.
+    meter: audio::meter::Meter,
+    // NEXT draw the graph's render nodes into the preset's layout tiles.  Until then the graph runs
+    // beside the scenes, steers them through `drive`, and tiles them through `scene` nodes.
+    graph: Option<Graph>,
+    /// Where the graph's `audio` nodes read what is playing.  `None` without a graph.
+    inlet: Option<utate::audio::node::AudioInlet>,
+    /// What the graph's `drive` node hands the scenes.
+    drive: video::drive::Drive,
+    /// Controllers moving the graph's parameters.  `None` without a graph or anything to listen to.
+    #[cfg(feature = "control")]
+    control: Option<control::Control>,
+    /// The preset's layout, when it has one, and the scenes its tiles start with.
+    layout: Option<(utate::graph::layout::Layout, video::tiles::TileScenes)>,
+    /// Whether the graph ran since the event loop last went idle, so that windows redrawn together
+    /// share one graph frame.
+    graph_ran: bool,
+    /// The device, and the scratch memory and phases the graph's nodes run with.
+    context: GraphContext,
     windows: HashMap<WindowId, WindowContext>,
+    /// Key names to actions, from the config file over the defaults.
+    keys: utate::input::Bindings,
+    /// The config file with the command line over it.
+    settings: utate::settings::Settings,
+    /// Notices edits to the config file, if there is one.
+    watcher: Option<utate::settings::SettingsWatcher>,
+    /// Windows stop drawing until resumed.
+    paused: bool,
+    /// How windows start and rotate scenes.
+    scenes: video::scene::SceneSettings,
+    /// Print window stats when windows close.
+    report_stats: bool,
 }
 
 impl ActiveApp {
     fn new(
         instance: &Instance,
         args: &Args,
+        config: &config::Config,
         event_loop: &ActiveEventLoop,
     ) -> Result<Self, MutateError> {
-        let window = Window::from_args(args, event_loop);
+        let window = Window::from_settings(&config.settings.video, event_loop);
         let raw_surface = instance.surface(event_loop, &window);
 
-        let supported_devices: Vec<SupportedDevice> = instance
-            .supported_devices(&[])
-            .into_iter()
-            .filter(|sd| sd.supports_surface(raw_surface, instance))
-            .collect();
-        if supported_devices.is_empty() {
-            panic!("ActiveApp::new: no Vulkan device supports the created surface.");
+        let mut device = select_device(instance, args, raw_surface)?;
+        let (mut audio, picker) = open_audio(&device, args, &config.settings.audio)?;
+        let beats = audio::beats::Beats::new(&config.settings.dsp);
+        audio.feed(beats.inlet())?;
+        let meter = audio::meter::Meter::new(config.settings.levels());
+        audio.feed(meter.inlet())?;
+        let drive = video::drive::Drive::new();
+        let tile_scenes = video::tiles::TileScenes::new();
+        let mut layout = None;
+        let (graph, inlet) = match &args.graph {
+            Some(path) => {
+                let inlet = utate::audio::node::AudioInlet::new(2);
+                let mut registry = utate::graph::preset::NodeRegistry::builtin();
+                let node = inlet.clone();
+                registry.register("audio", move |_| Ok(node.node()));
+                let node = drive.clone();
+                registry.register("drive", move |_| Ok(node.node()));
+                tile_scenes.register(&mut registry);
+                let preset = video::drive::preset(path)?;
+                let mut graph = preset.build(&registry)?;
+                layout = preset.layout.map(|layout| (layout, tile_scenes));
+                graph.configure(
+                    utate::graph::config::SAMPLES,
+                    utate::graph::ConfigValue::Count(args.msaa),
+                );
+                config.settings.configure(&mut graph);
+                let pool = utate::graph::WorkerPool::new(&config.settings.pool)?;
+                graph.set_pool(std::sync::Arc::new(pool));
+                audio.feed(&inlet)?;
+                (Some(graph), Some(inlet))
+            }
+            None => (None, None),
+        };
+        let recorder = match &args.record {
+            Some(path) => Some(utate::audio::record::Recorder::start(path)?),
+            None => None,
+        };
+        if let Some(recorder) = &recorder {
+            audio.consumer.record(recorder)?;
         }
-        // NEXT: read a preferred device from config instead of always picking index 0.
-        let selected = supported_devices[0].clone();
-        println!("device selected: {}", selected.name);
-        let mut device = selected.into_logical(instance);
-        let audio = audio::Audio::new(&device)?;
 
-        let wc = WindowContext::new(instance, &mut device, window, raw_surface);
+        warn_unhandled(&config.keys, graph.as_ref());
+        #[cfg(feature = "control")]
+        let control = match (&graph, &config.control) {
+            (Some(graph), settings) if settings.listens() => {
+                Some(control::Control::start(settings, graph)?)
+            }
+            (None, settings) if settings.listens() => {
+                eprintln!("control: nothing to drive without --graph, not listening");
+                None
+            }
+            _ => None,
+        };
+
+        let scenes = args.scenes(&config.scenes);
+        let lyrics = video::lyrics::LyricsView::load(args, &scenes.palette)?;
+        let present = config.settings.video.present_preference();
+        let options = args.scene_options(&config.settings, &scenes);
+        let mut wc = WindowContext::new(
+            instance,
+            &mut device,
+            window,
+            raw_surface,
+            present,
+            &scenes,
+            &options,
+        )?;
+        if let Some((layout, scenes)) = &layout {
+            wc.set_tiles(scenes.tiles(layout)?);
+        }
+        audio.feed(wc.nodes.spectrum.inlet())?;
         let window_id = wc.window.id();
+        let context = GraphContext::new(device, wc.timing.clone())?;
         let mut windows = HashMap::new();
         windows.insert(window_id, wc);
 
         Ok(Self {
             audio,
-            device,
+            picker,
+            recorder,
+            lyrics,
+            beats,
+            meter,
+            graph,
+            inlet,
+            drive,
+            #[cfg(feature = "control")]
+            control,
+            layout,
+            graph_ran: false,
+            context,
             windows,
+            keys: config.keys.clone(),
+            settings: config.settings.clone(),
+            watcher: config
+                .source
+                .clone()
+                .map(|path| utate::settings::SettingsWatcher::new(path, CONFIG_POLL)),
+            paused: false,
+            scenes,
+            report_stats: args.stats,
+        })
+    }
+
+    /// Rebuild everything on the GPU after the device was lost.  Windows, the graph, and the
+    /// recording survive.  The device is selected again, which picks another one if the lost one is
+    /// gone for good.
+    fn recover(
+        mut self,
+        instance: &Instance,
+        args: &Args,
+        event_loop: &ActiveEventLoop,
+    ) -> Result<Self, MutateError> {
+        eprintln!("application: device lost, rebuilding");
+        // Waits on a lost device fail at once, but destroying what was created from it is valid.
+        let _ = self.context.device().wait_idle();
+        let windows: Vec<winit::window::Window> = self
+            .windows
+            .drain()
+            .map(|(_, wc)| wc.release(self.context.device_mut()))
+            .collect();
+        if let Err(e) = self.audio.destroy(self.context.device()) {
+            eprintln!("application: audio teardown failed {:?}", e);
+        }
+        // The graph keeps its phases on the next device.
+        let timing = self.context.timing().clone();
+        if let Err(e) = self.context.destroy() {
+            eprintln!("application: graph context teardown failed {:?}", e);
+        }
+
+        let surfaces: Vec<vk::SurfaceKHR> = windows
+            .iter()
+            .map(|window| instance.surface(event_loop, window))
+            .collect();
+        let mut device = select_device(instance, args, surfaces[0])?;
+        // The server outlives the device, so the source being listened to stays chosen.
+        let (mut audio, picker) = match self.picker.take() {
+            Some(picker) => {
+                let audio = match picker.playing() {
+                    Some(choice) => {
+                        let latency = self.settings.audio.latency;
+                        audio::Audio::connect(&device, picker.context(), choice, latency)?
+                    }
+                    None => audio::Audio::demo(&device)?,
+                };
+                (audio, Some(picker))
+            }
+            None => open_audio(&device, args, &self.settings.audio)?,
+        };
+        if let Some(recorder) = &self.recorder {
+            audio.consumer.record(recorder)?;
+        }
+        audio.feed(self.beats.inlet())?;
+        audio.feed(self.meter.inlet())?;
+        if let Some(inlet) = &self.inlet {
+            audio.feed(inlet)?;
+        }
+        let mut contexts = HashMap::new();
+        let present = self.settings.video.present_preference();
+        let options = args.scene_options(&self.settings, &self.scenes);
+        for (window, raw_surface) in windows.into_iter().zip(surfaces) {
+            let mut wc = WindowContext::new(
+                instance,
+                &mut device,
+                window,
+                raw_surface,
+                present,
+                &self.scenes,
+                &options,
+            )?;
+            if let Some((layout, scenes)) = &self.layout {
+                wc.set_tiles(scenes.tiles(layout)?);
+            }
+            audio.feed(wc.nodes.spectrum.inlet())?;
+            wc.window.request_redraw();
+            contexts.insert(wc.window.id(), wc);
+        }
+        // Nodes provisioned on the lost device provision again, even on a device of the same name.
+        let mut graph = self.graph;
+        if let Some(graph) = &mut graph {
+            graph.invalidate(utate::graph::config::DEVICE);
+        }
+        let context = GraphContext::new(device, timing)?;
+
+        Ok(Self {
+            audio,
+            picker,
+            recorder: self.recorder,
+            lyrics: self.lyrics,
+            beats: self.beats,
+            meter: self.meter,
+            graph,
+            inlet: self.inlet,
+            drive: self.drive,
+            #[cfg(feature = "control")]
+            control: self.control,
+            layout: self.layout,
+            graph_ran: false,
+            context,
+            windows: contexts,
+            keys: self.keys,
+            settings: self.settings,
+            watcher: self.watcher,
+            paused: self.paused,
+            scenes: self.scenes,
+            report_stats: self.report_stats,
         })
     }
 
@@ -174,30 +958,110 @@ impl ActiveApp {
         window_id: WindowId,
         event: WindowEvent,
         instance: &Instance,
-    ) {
+    ) -> Result<(), VulkanError> {
         match event {
             // MAYBE do they get before matching the variant?
+            WindowEvent::RedrawRequested if self.paused => {}
             WindowEvent::RedrawRequested => {
+                if self.audio.wants_demo() {
+                    if let Err(e) = self.audio.fall_back(self.context.device()) {
+                        eprintln!("application: demo fallback failed {:?}", e);
+                    }
+                } else if self.audio.resumed() {
+                    if let Err(e) = self.audio.resume(self.context.device()) {
+                        eprintln!("application: resuming audio failed {:?}", e);
+                    }
+                }
+                if let Some(picker) = &mut self.picker {
+                    picker.update();
+                }
+                if let Some(graph) = self.graph.as_mut().filter(|_| !self.graph_ran) {
+                    #[cfg(feature = "control")]
+                    if let Some(control) = &self.control {
+                        control.apply(graph);
+                    }
+                    // Nodes read the phases of the window that asked for the frame.
+                    if let Some(wc) = self.windows.get(&window_id) {
+                        *self.context.timing_mut() = wc.timing.clone();
+                    }
+                    if let Err(e) = run_graph(graph, &mut self.context) {
+                        eprintln!("application: graph frame failed {:?}", e);
+                    }
+                    self.graph_ran = true;
+                }
+                self.beats.update();
+                self.meter.update();
                 if let Some(wc) = self.windows.get_mut(&window_id) {
-                    wc.draw_frame(&mut self.device, &mut self.audio);
+                    if let Some(scratch) = self.context.stats().transients() {
+                        wc.stats.observe_transients(scratch);
+                    }
+                    let driven = self.drive.latest();
+                    driven.apply(&mut wc.nodes);
+                    if let Some(section) = driven.section {
+                        wc.follow_section(section);
+                    }
+                    wc.draw_frame(
+                        self.context.device_mut(),
+                        &mut self.audio,
+                        &mut self.beats,
+                        &self.meter,
+                        self.picker.as_ref(),
+                        self.lyrics.as_ref(),
+                    )?;
                     wc.window.request_redraw();
                 }
             }
             WindowEvent::Resized(size) if size.width > 0 && size.height > 0 => {
+                if let Some(graph) = &mut self.graph {
+                    graph.configure(
+                        utate::graph::config::RESOLUTION,
+                        utate::graph::ConfigValue::Extent {
+                            width: size.width,
+                            height: size.height,
+                        },
+                    );
+                }
                 if let Some(wc) = self.windows.get_mut(&window_id) {
-                    wc.handle_resize(&mut self.device);
+                    if let Err(e) = wc.handle_resize(self.context.device_mut()) {
+                        eprintln!("application: resize failed {:?}", e);
+                    }
                 }
             }
             WindowEvent::KeyboardInput { event, .. } => {
-                if let Some(wc) = self.windows.get(&window_id) {
-                    handle_keyboard(&event, wc, event_loop);
+                if self.handle_picker_keyboard(&event) {
+                    return Ok(());
+                }
+                if event.repeat || event.state != winit::event::ElementState::Pressed {
+                    return Ok(());
+                }
+                let action = input::key_name(event.physical_key)
+                    .and_then(|key| self.keys.action(&key).map(str::to_owned));
+                if let Some(action) = action {
+                    self.handle_action(&action, window_id, event_loop);
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                if let Some(wc) = self.windows.get_mut(&window_id) {
+                    wc.cursor = (position.x, position.y);
+                }
+            }
+            WindowEvent::MouseInput {
+                state: winit::event::ElementState::Pressed,
+                ..
+            } => {
+                if let Some(wc) = self.windows.get_mut(&window_id) {
+                    let (x, y) = wc.cursor;
+                    if let Some(tiles) = &mut wc.tiles {
+                        tiles.focus_at(x, y);
+                    }
                 }
             }
             WindowEvent::CloseRequested => {
                 if let Some(wc) = self.windows.remove(&window_id) {
-                    // NEXT expose pool epoch for more precise waiting.
-                    self.device.wait_idle();
-                    wc.destroy(&mut self.device);
+                    if let Err(e) = self.audio.unfeed(wc.nodes.spectrum.inlet()) {
+                        eprintln!("application: unfeeding the window failed {:?}", e);
+                    }
+                    wc.destroy(self.context.device_mut(), self.report_stats);
                 }
                 if self.windows.is_empty() {
                     event_loop.exit();
@@ -205,28 +1069,197 @@ impl ActiveApp {
             }
             _ => {}
         }
+        Ok(())
+    }
+
+    /// Nodes that handle `action` get it first, and then the visualizer handles its own.  Window
+    /// actions go to the window with focus.
+    fn handle_action(&mut self, action: &str, window_id: WindowId, event_loop: &ActiveEventLoop) {
+        if let Some(graph) = &mut self.graph {
+            if let Err(e) = graph.act(action) {
+                eprintln!("application: {action} failed in the graph {:?}", e);
+            }
+        }
+        match action {
+            input::SOURCES => {
+                if let Some(picker) = &mut self.picker {
+                    picker.toggle();
+                }
+            }
+            input::PAUSE => {
+                self.paused = !self.paused;
+                // Paused windows stopped asking to draw.
+                if !self.paused {
+                    for wc in self.windows.values() {
+                        wc.window.request_redraw();
+                    }
+                }
+            }
+            input::QUIT => event_loop.exit(),
+            _ => {
+                if let Some(wc) = self.windows.get_mut(&window_id) {
+                    wc.act(action);
+                }
+            }
+        }
     }
-}
 
-fn handle_keyboard(
-    event: &winit::event::KeyEvent,
-    wc: &WindowContext,
-    event_loop: &ActiveEventLoop,
-) {
-    if !event.repeat && event.state == winit::event::ElementState::Pressed {
+    /// Keys for the source picker, which takes the keys it uses while it is shown.  Returns
+    /// whether the key was taken.
+    fn handle_picker_keyboard(&mut self, event: &winit::event::KeyEvent) -> bool {
+        let Some(picker) = &mut self.picker else {
+            return false;
+        };
+        if event.state != winit::event::ElementState::Pressed {
+            return false;
+        }
+        if !picker.is_visible() {
+            return false;
+        }
         match event.physical_key {
-            kb::PhysicalKey::Code(kb::KeyCode::KeyF) => {
-                wc.window.toggle_fullscreen();
+            kb::PhysicalKey::Code(kb::KeyCode::ArrowUp) => picker.up(),
+            kb::PhysicalKey::Code(kb::KeyCode::ArrowDown) => picker.down(),
+            kb::PhysicalKey::Code(kb::KeyCode::Enter)
+            | kb::PhysicalKey::Code(kb::KeyCode::NumpadEnter) => {
+                if let Err(e) = self.pick() {
+                    eprintln!("application: switching sources failed {:?}", e);
+                }
             }
-            kb::PhysicalKey::Code(kb::KeyCode::KeyQ)
-            | kb::PhysicalKey::Code(kb::KeyCode::Escape) => {
-                event_loop.exit();
+            kb::PhysicalKey::Code(kb::KeyCode::Escape) => picker.hide(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Listen to the source under the picker's cursor in place of what is playing.
+    fn pick(&mut self) -> Result<(), MutateError> {
+        let Some(picker) = &mut self.picker else {
+            return Ok(());
+        };
+        let Some(choice) = picker.selected().cloned() else {
+            return Ok(());
+        };
+        // The preview streams disconnect before the chosen source connects.
+        picker.hide();
+        let latency = self.settings.audio.latency;
+        let device = self.context.device();
+        let audio = audio::Audio::connect(device, picker.context(), &choice, latency)?;
+        picker.set_playing(Some(&choice));
+        println!("audio source: {}", choice.name());
+        self.replace_audio(audio)
+    }
+
+    /// Read the config file again after it was edited, keeping the previous settings if it does not
+    /// read.  See [`config`] for what changes at once.
+    fn reload(&mut self, args: &Args) {
+        let Some(path) = self.watcher.as_ref().map(|w| w.path().to_owned()) else {
+            return;
+        };
+        let config = match config::Config::load(Some(&path), &args.overrides()) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("config: not reloaded, {e}");
+                return;
+            }
+        };
+        println!("config: reloaded {}", path.display());
+        if let Some(graph) = &mut self.graph {
+            config.settings.configure(graph);
+            #[cfg(feature = "control")]
+            if let Some(control) = &mut self.control {
+                control.remap(&config.control.mappings, graph);
+            }
+        }
+        warn_unhandled(&config.keys, self.graph.as_ref());
+        self.keys = config.keys;
+        self.settings = config.settings;
+        self.meter.set_levels(self.settings.levels());
+        self.scenes = args.scenes(&config.scenes);
+        if let Some(lyrics) = &mut self.lyrics {
+            lyrics.set_palette(&self.scenes.palette);
+        }
+        for wc in self.windows.values_mut() {
+            let palette = &self.scenes.palette;
+            let nodes = &mut wc.nodes;
+            let device = self.context.device();
+            if let Err(e) = nodes.set_palette(device, palette, &mut wc.deletions, wc.frames) {
+                eprintln!("config: palette not applied, {e}");
             }
-            _ => {}
+        }
+    }
+
+    /// Swap in `audio`, recording it if recording.  The demo standing in for a silent source is not
+    /// recorded, since the source it stands in for still is.
+    fn replace_audio(&mut self, audio: audio::Audio) -> Result<(), MutateError> {
+        if let Some(recorder) = &self.recorder {
+            audio.consumer.record(recorder)?;
+        }
+        self.audio.replace(self.context.device(), audio)
+    }
+}
+
+/// Run one graph frame in `context`, whose scratch memory the nodes allocate from.
+fn run_graph(graph: &mut Graph, context: &mut GraphContext) -> Result<(), MutateError> {
+    let (_, intent, _) = context.begin_frame(GRAPH_TIMEOUT)?;
+    let ran = graph.run_frame_in(context);
+    // Nodes record nothing yet, so the frame is over once they return.
+    intent.try_consume(context.device(), GRAPH_TIMEOUT)?;
+    ran
+}
+
+/// Warn about bindings to actions that neither the visualizer nor the graph handles.  A typo in a
+/// binding would otherwise do nothing without a word.
+fn warn_unhandled(keys: &utate::input::Bindings, graph: Option<&Graph>) {
+    let handled = graph.map(Graph::actions).unwrap_or_default();
+    for (key, action) in keys.iter() {
+        if !input::ACTIONS.contains(&action) && !handled.contains(&action) {
+            eprintln!("config: {key} is bound to `{action}`, which nothing handles");
         }
     }
 }
 
+/// Select the device for a window by `--gpu`, `MUTATE_GPU`, and preference.
+fn select_device(
+    instance: &Instance,
+    args: &Args,
+    raw_surface: vk::SurfaceKHR,
+) -> Result<Device, MutateError> {
+    let supported_devices: Vec<SupportedDevice> = instance
+        .supported_devices(&[])
+        .into_iter()
+        .filter(|sd| sd.supports_surface(raw_surface, instance))
+        .collect();
+    if supported_devices.is_empty() {
+        return Err(VulkanError::NoSuitableDevice(
+            "no device can present to the window".to_owned(),
+        )
+        .into());
+    }
+    // NEXT a `gpu` setting in the config file.
+    let mut selector = DeviceSelector::new().prefer_discrete().with_env();
+    if let Some(choice) = &args.gpu {
+        selector = selector.with_choice(choice);
+    }
+    let selected = selector.select(supported_devices)?;
+    println!("device selected: {}", selected.name);
+    Ok(selected.into_logical(instance)?)
+}
+
+/// Open the audio source the arguments ask for, importing it to `device`.  Listening to the server
+/// also returns the picker for its sources.
+fn open_audio(
+    device: &Device,
+    args: &Args,
+    settings: &utate::settings::AudioSettings,
+) -> Result<(audio::Audio, Option<audio::picker::SourcePicker>), MutateError> {
+    match (&args.file, &args.signal) {
+        (Some(path), _) => Ok((audio::Audio::file(device, path)?, None)),
+        (None, Some(signal)) => Ok((audio::Audio::signal(device, signal.clone())?, None)),
+        (None, None) if args.demo => Ok((audio::Audio::demo(device)?, None)),
+        (None, None) => audio::Audio::listen(device, settings),
+    }
+}
+
 /// Represents the possible states of construction as variants of a single type so that the
 /// MutateApp can be a single type updating a field to go through transitions.
 enum AppState {
@@ -238,6 +1271,7 @@ enum AppState {
 /// delegates to the state appropriately.
 struct MutateApp {
     args: Args,
+    config: config::Config,
     instance: Instance,
     state: AppState,
 }
@@ -247,8 +1281,13 @@ impl ApplicationHandler for MutateApp {
         // Transition Dormant -> Active by creating the first window.
         // Device selection happens here once; subsequent windows reuse it.
         debug_assert!(matches!(self.state, AppState::Dormant));
-        let active = ActiveApp::new(&self.instance, &self.args, event_loop).unwrap();
-        self.state = AppState::Active(active);
+        match ActiveApp::new(&self.instance, &self.args, &self.config, event_loop) {
+            Ok(active) => self.state = AppState::Active(active),
+            Err(e) => {
+                eprintln!("application: could not start {e}");
+                event_loop.exit();
+            }
+        }
     }
 
     fn window_event(
@@ -260,30 +1299,106 @@ impl ApplicationHandler for MutateApp {
         let AppState::Active(active) = &mut self.state else {
             return;
         };
-        active.handle_window_event(event_loop, window_id, event, &self.instance);
+        let Err(e) = active.handle_window_event(event_loop, window_id, event, &self.instance) else {
+            return;
+        };
+        // Only a lost device comes back out.  Rebuild or give up.
+        let AppState::Active(active) = std::mem::replace(&mut self.state, AppState::Dormant) else {
+            unreachable!()
+        };
+        debug_assert!(e.is_device_lost());
+        match active.recover(&self.instance, &self.args, event_loop) {
+            Ok(active) => self.state = AppState::Active(active),
+            Err(e) => {
+                eprintln!("application: could not recover from a lost device {:?}", e);
+                event_loop.exit();
+            }
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // SIGINT and SIGTERM leave through the same path as closing the last window.
+        if utate::shutdown::requested() {
+            event_loop.exit();
+        }
+        if let AppState::Active(active) = &mut self.state {
+            active.graph_ran = false;
+            active.audio.log_events();
+            if active.watcher.as_mut().is_some_and(|w| w.poll()) {
+                active.reload(&self.args);
+            }
+        }
     }
 
     // handles all exit paths
     fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
-        let AppState::Active(active) = &mut self.state else {
+        let AppState::Active(mut active) = std::mem::replace(&mut self.state, AppState::Dormant)
+        else {
             return;
         };
-        active.device.wait_idle().unwrap();
+        #[cfg(feature = "control")]
+        if let Some(control) = active.control.take() {
+            control.stop();
+        }
+        match active.context.device().wait_idle() {
+            Ok(()) => {}
+            // Nothing on a lost device can be waited on, and the process is leaving anyway.
+            Err(e) if e.is_device_lost() => {
+                eprintln!("application: device lost while exiting, skipping GPU teardown");
+                return;
+            }
+            Err(e) => eprintln!("application: waiting for the device failed {:?}", e),
+        }
         for (_, wc) in active.windows.drain() {
-            wc.destroy(&mut active.device);
+            wc.destroy(active.context.device_mut(), active.report_stats);
+        }
+        if let Err(e) = active.audio.destroy(active.context.device()) {
+            eprintln!("application: audio teardown failed {:?}", e);
+        }
+        if let Err(e) = active.context.destroy() {
+            eprintln!("application: graph context teardown failed {:?}", e);
         }
-        active.audio.destroy(&mut active.device);
-        active.device.destroy();
     }
 }
 
 fn main() -> Result<(), MutateError> {
     let args = Args::parse();
+    utate::shutdown::install()?;
+    #[cfg(feature = "embed-assets")]
+    {
+        utate::embed_assets()?;
+        utate::assets::pack::embed(utate::assets::include_assets!()?);
+    }
+    let mut debug = DebugOptions::from_env();
+    if let Some(mode) = &args.validation {
+        debug = debug.with_choice(mode);
+    }
+    let config = config::Config::load(args.config.as_deref(), &args.overrides())?;
+    if let Some(path) = &config.source {
+        println!("config: {}", path.display());
+    }
+    if args.export.is_some() {
+        let scenes = args.scenes(&config.scenes);
+        let options = args.scene_options(&config.settings, &scenes);
+        return export::run(&args, &config.settings, &scenes, &options, debug);
+    }
+    if args.poster.is_some() {
+        let scenes = args.scenes(&config.scenes);
+        return poster::run(&args, &config.settings.dsp, &scenes.palette, debug);
+    }
+    if let Some(placement) = args.layer {
+        return layer::run(&args, placement, &config, debug);
+    }
     let event_loop = EventLoop::builder().build().unwrap();
     event_loop.set_control_flow(ControlFlow::Poll);
+    let instance = Instance::with_display_debug(&event_loop, &[], debug)?;
+    if args.doctor {
+        return doctor::run(event_loop, instance);
+    }
     let mut app = MutateApp {
-        instance: Instance::with_display(&event_loop, &[]),
+        instance,
         args,
+        config,
         state: AppState::Dormant,
     };
     event_loop.run_app(&mut app).unwrap();
@@ -0,0 +1,459 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Poster
+//!
+//! Render a whole WAV or FLAC file as one tall spectrogram image without a window:
+//!
+//! ```text
+//! mutate-visualizer --file song.flac --poster song.ppm --rows-per-second 100
+//! ```
+//!
+//! Time runs down and pitch runs across, one column per bin of the `[dsp]` settings, colored by the
+//! scenes' palette.  The filter bank runs on the device one row at a time, and the rows collect in
+//! a [`WaterfallNode`] [`TILE_ROWS`] tall.  Each full tile is drawn into an [`OffscreenTarget`],
+//! read back, and stitched under the tiles before it, so the bins never visit the host.  Octave
+//! guides and time ticks are drawn over the stitched pixels unless `--no-guides`.  The image is a
+//! binary PPM, which every image tool reads.
+//!
+//! With `--axis-labels`, the [`TextNode`] names the octaves across the top and the time of each
+//! tick down the left edge.  Blocks of the stitched poster with labels in them go back up into the
+//! tile to be written on and read back again.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use ash::vk;
+use mutate_lib::{self as utate, audio, prelude::*};
+use utate::color::Palette;
+use utate::dsp::compute::GpuSpectrogram;
+use utate::dsp::spectrogram::{Poster, PosterAxes, PosterOptions, TILE_ROWS};
+use utate::dsp::units::{Hertz, SampleRate, Seconds};
+use utate::gpu::resource::{buffer, image};
+use utate::settings::DspSettings;
+
+use crate::export;
+use crate::video::text::{TextNode, TextStyle};
+use crate::video::waterfall::{WaterfallNode, MAX_BINS};
+use crate::Args;
+
+/// Read back red first, the order [`Poster`] pixels are in.
+const FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+/// How long one submission may take on the device before the poster gives up.
+const TIMEOUT: u64 = 5_000_000_000;
+/// Pixels between a label and its guide.
+const LABEL_MARGIN: usize = 2;
+
+/// Render `--file` to `--poster` with the bank `dsp` describes.
+pub fn run(
+    args: &Args,
+    dsp: &DspSettings,
+    palette: &Palette,
+    debug: DebugOptions,
+) -> Result<(), MutateError> {
+    let (Some(output), Some(input)) = (&args.poster, &args.file) else {
+        return Err(MutateError::Export("--poster needs --file".to_owned()));
+    };
+    if !args.rows_per_second.is_finite() || args.rows_per_second <= 0.0 {
+        return Err(MutateError::Export(
+            "--rows-per-second must be positive".to_owned(),
+        ));
+    }
+    if dsp.bins > MAX_BINS {
+        return Err(MutateError::Export(format!(
+            "posters are at most {MAX_BINS} bins wide, not {}",
+            dsp.bins
+        )));
+    }
+    let source = audio::file::FileSource::open(input)?;
+    let frames = export::stereo(&source);
+    let rate = source.format().rate;
+    // Rows fall on whole samples, so the guides use the rate that actually results.
+    let hop = (rate as f64 / args.rows_per_second).round().max(1.0) as usize;
+    let rows = frames.len().div_ceil(hop);
+    let guides = PosterOptions::default();
+    // Labels name where the guides would be, drawn or not.
+    let ticks = guides.time_ticks;
+    let opts = PosterOptions {
+        octave_guides: !args.no_guides,
+        time_ticks: guides.time_ticks.filter(|_| !args.no_guides),
+        ..guides
+    };
+
+    let instance = Instance::headless_with_debug(debug)?;
+    let result = export::select_device(&instance, args).and_then(|device| {
+        println!("drawing {rows} rows to {}", output.display());
+        let render = Render::new(&device, dsp, rate, palette, args.axis_labels);
+        let result = render.and_then(|mut render| {
+            let drawn = render.song(&device, &frames, hop).and_then(|mut poster| {
+                let axes = PosterAxes {
+                    min: Hertz(dsp.min_freq),
+                    max: Hertz(dsp.max_freq),
+                    bins: poster.width,
+                    rows_per_second: rate as f64 / hop as f64,
+                };
+                poster.draw_guides(&axes, &opts);
+                render.label(&device, &mut poster, &axes, ticks)?;
+                Ok(poster)
+            });
+            render.destroy(&device);
+            drawn
+        });
+        device.destroy();
+        result
+    });
+    instance.destroy();
+    let poster = result?;
+
+    let written = File::create(output).and_then(|file| {
+        let mut w = BufWriter::new(file);
+        poster.write_ppm(&mut w)?;
+        w.flush()
+    });
+    written.map_err(|e| MutateError::Export(format!("writing {}: {e}", output.display())))?;
+    println!("drew {}", output.display());
+    Ok(())
+}
+
+/// Everything on the device for drawing a tile of rows at a time.
+struct Render {
+    bank: GpuSpectrogram,
+    waterfall: WaterfallNode,
+    /// Single sampled, so the waterfall draws straight into the tile.
+    msaa: MsaaTarget,
+    tile: OffscreenTarget,
+    /// Each tile is copied here and read by the host.
+    readback: buffer::MappedAllocation<[u8; 4]>,
+    pool: CommandPool<Graphics, OneTime>,
+    semaphore: TimelineSemaphore,
+    /// Only with `--axis-labels`.
+    text: Option<TextNode>,
+}
+
+impl Render {
+    /// A bank designed from `dsp` for audio at `rate`, drawn through `palette`.  Text is only made
+    /// for `labels`.
+    fn new(
+        device: &Device,
+        dsp: &DspSettings,
+        rate: u32,
+        palette: &Palette,
+        labels: bool,
+    ) -> Result<Self, MutateError> {
+        // DEBT partial construction leaks on error, as everywhere else a device is torn down
+        // after failure.
+        let table = dsp.design_table(SampleRate(rate as f64));
+        let bank = GpuSpectrogram::new(device, table)?.with_channels(2);
+        let extent = vk::Extent2D {
+            width: bank.width() as u32,
+            height: TILE_ROWS as u32,
+        };
+        let samples = vk::SampleCountFlags::TYPE_1;
+        let waterfall = WaterfallNode::new(
+            device,
+            FORMAT,
+            samples,
+            extent.width,
+            extent.height,
+            palette,
+        )?;
+        let msaa = MsaaTarget::new(device, extent, FORMAT, 1)?;
+        let tile = OffscreenTarget::new(device, extent, FORMAT)?;
+        tile.set_name(device, "poster tile");
+        let readback =
+            buffer::MappedAllocation::new((extent.width * extent.height) as usize, device)?;
+        readback.set_name(device, "poster readback");
+        let queue = device.queues.graphics_offscreen(QueuePriority::High);
+        let pool = CommandPool::transient(device, &queue.queue_ref())?;
+        let semaphore = device.make_timeline_semaphore()?;
+        let text = match labels {
+            true => Some(TextNode::new(device, FORMAT)?),
+            false => None,
+        };
+        Ok(Self {
+            bank,
+            waterfall,
+            msaa,
+            tile,
+            readback,
+            pool,
+            semaphore,
+            text,
+        })
+    }
+
+    /// Draw a row for every `hop` of the audio `frames` and stitch the tiles, oldest at the top.
+    fn song(
+        &mut self,
+        device: &Device,
+        frames: &[[f32; 2]],
+        hop: usize,
+    ) -> Result<Poster, MutateError> {
+        let width = self.waterfall.bins() as usize;
+        let rows = frames.len().div_ceil(hop);
+        let mut pixels = Vec::with_capacity(width * rows);
+        for (n, chunk) in frames.chunks(hop).enumerate() {
+            if utate::shutdown::requested() {
+                return Err(MutateError::Export("interrupted".to_owned()));
+            }
+            self.bank.push(chunk.as_flattened());
+            self.row(device)?;
+            let drawn = n % TILE_ROWS + 1;
+            if drawn == TILE_ROWS || n + 1 == rows {
+                self.tile(device, drawn, &mut pixels)?;
+            }
+            if (n + 1) % (TILE_ROWS * 10) == 0 {
+                println!("poster: {} rows of {rows}", n + 1);
+            }
+        }
+        Ok(Poster {
+            width,
+            height: rows,
+            pixels,
+        })
+    }
+
+    /// Run the bank over what was pushed and copy its output into the next row.
+    fn row(&mut self, device: &Device) -> Result<(), MutateError> {
+        self.bank.upload(device)?;
+        let cb = self.pool.primary(device)?;
+        self.bank.record(device, *cb);
+        self.waterfall
+            .record_row(device, *cb, self.bank.output_buffer(), 0);
+        let recorded = cb.end(device)?;
+        self.submit(device, recorded)
+    }
+
+    /// Draw the `rows` rows since the last tile, newest at the top, and append them to `pixels`
+    /// oldest first.  The next row starts a new tile.
+    fn tile(
+        &mut self,
+        device: &Device,
+        rows: usize,
+        pixels: &mut Vec<[u8; 3]>,
+    ) -> Result<(), MutateError> {
+        let extent = self.tile.extent();
+        let area = vk::Rect2D {
+            offset: vk::Offset2D::default(),
+            extent,
+        };
+        let acquired = AcquiredImage::offscreen(self.tile.image.image, self.tile.view.view, extent);
+        let cb = self.pool.primary(device)?;
+        // Only for the transition.  The waterfall covers the tile and brings its own attachment.
+        let _ = self.tile.color_attachment(device, *cb);
+        self.waterfall
+            .draw(device, &cb, &acquired, &self.msaa, area);
+        self.tile.copy_to_buffer(device, *cb, self.readback.buffer);
+        let to_host = vk::BufferMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::HOST)
+            .dst_access_mask(vk::AccessFlags2::HOST_READ)
+            .buffer(self.readback.buffer)
+            .size(vk::WHOLE_SIZE);
+        unsafe {
+            device.as_raw().cmd_pipeline_barrier2(
+                *cb,
+                &vk::DependencyInfo::default().buffer_memory_barriers(&[to_host]),
+            );
+        }
+        let recorded = cb.end(device)?;
+        self.submit(device, recorded)?;
+
+        self.readback.invalidate(device)?;
+        let width = extent.width as usize;
+        let drawn = self.readback.as_mut_slice();
+        for age in (0..rows).rev() {
+            let row = &drawn[age * width..(age + 1) * width];
+            pixels.extend(row.iter().map(|&[r, g, b, _]| [r, g, b]));
+        }
+        self.waterfall.reset();
+        Ok(())
+    }
+
+    /// Write the names of the guides on `axes` over `poster`, timing ticks `ticks` apart.  Labels
+    /// that would run into the one before or off the poster are left out.  Does nothing without
+    /// `--axis-labels`.
+    fn label(
+        &mut self,
+        device: &Device,
+        poster: &mut Poster,
+        axes: &PosterAxes,
+        ticks: Option<Seconds>,
+    ) -> Result<(), MutateError> {
+        let Some(mut text) = self.text.take() else {
+            return Ok(());
+        };
+        let style = TextStyle {
+            scale: 1,
+            ..Default::default()
+        };
+        let mut placed = Vec::new();
+        let mut right = 0;
+        for label in axes.frequency_labels() {
+            let x = label.column + LABEL_MARGIN;
+            let width = text.measure(&label.text, &style)[0] as usize;
+            if x >= right && x + width <= poster.width {
+                right = x + width + LABEL_MARGIN;
+                placed.push(([x, LABEL_MARGIN], label.text));
+            }
+        }
+        let height = text.line_height(&style) as usize;
+        for label in ticks.map_or(Vec::new(), |t| axes.time_labels(poster.height, t)) {
+            let y = label.row + LABEL_MARGIN;
+            if y + height <= poster.height {
+                placed.push(([LABEL_MARGIN, y], label.text));
+            }
+        }
+
+        let mut labeled = Ok(());
+        for start in (0..poster.height).step_by(TILE_ROWS) {
+            let rows = TILE_ROWS.min(poster.height - start);
+            let block = &mut poster.pixels[start * poster.width..(start + rows) * poster.width];
+            // Labels straddling two blocks are drawn in both.
+            let mut inside = placed
+                .iter()
+                .filter(|([_, y], _)| *y < start + rows && y + height > start)
+                .peekable();
+            if inside.peek().is_none() {
+                continue;
+            }
+            for ([x, y], label) in inside {
+                let pos = [*x as f32, *y as f32 - start as f32];
+                text.draw_text(pos, label, &style);
+            }
+            labeled = self.label_block(device, &mut text, block);
+            if labeled.is_err() {
+                break;
+            }
+        }
+        self.text = Some(text);
+        labeled
+    }
+
+    /// Upload `block`, the top rows of the tile, draw the queued text over it, and read it back.
+    fn label_block(
+        &mut self,
+        device: &Device,
+        text: &mut TextNode,
+        block: &mut [[u8; 3]],
+    ) -> Result<(), MutateError> {
+        let extent = self.tile.extent();
+        for (px, &[r, g, b]) in self.readback.as_mut_slice().iter_mut().zip(block.iter()) {
+            *px = [r, g, b, 0xff];
+        }
+        self.readback.flush(device)?;
+
+        let cb = self.pool.primary(device)?;
+        // Written over by the upload, whatever the waterfall left.
+        self.tile.discard();
+        self.tile.image.transition_layout(
+            *cb,
+            image::range(),
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            device,
+        );
+        unsafe {
+            device.as_raw().cmd_copy_buffer_to_image(
+                *cb,
+                self.readback.buffer,
+                self.tile.image.image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[buffer::buffer_image_copy_full(extent)],
+            );
+        }
+        let acquired = AcquiredImage::offscreen(self.tile.image.image, self.tile.view.view, extent);
+        text.draw(device, &cb, &acquired)?;
+        let to_source = vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .dst_access_mask(vk::AccessFlags2::TRANSFER_READ)
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .image(self.tile.image.image)
+            .subresource_range(image::range());
+        let to_host = vk::BufferMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::HOST)
+            .dst_access_mask(vk::AccessFlags2::HOST_READ)
+            .buffer(self.readback.buffer)
+            .size(vk::WHOLE_SIZE);
+        unsafe {
+            let raw = device.as_raw();
+            raw.cmd_pipeline_barrier2(
+                *cb,
+                &vk::DependencyInfo::default().image_memory_barriers(&[to_source]),
+            );
+            raw.cmd_copy_image_to_buffer(
+                *cb,
+                self.tile.image.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                self.readback.buffer,
+                &[buffer::buffer_image_copy_full(extent)],
+            );
+            raw.cmd_pipeline_barrier2(
+                *cb,
+                &vk::DependencyInfo::default().buffer_memory_barriers(&[to_host]),
+            );
+        }
+        let recorded = cb.end(device)?;
+        self.submit(device, recorded)?;
+
+        self.readback.invalidate(device)?;
+        for (px, &[r, g, b, _]) in block.iter_mut().zip(self.readback.as_mut_slice().iter()) {
+            *px = [r, g, b];
+        }
+        Ok(())
+    }
+
+    /// Submit and wait, so that the bank's input and the readback may be reused.
+    fn submit(
+        &mut self,
+        device: &Device,
+        recorded: ExecutableBuffer<Graphics, OneTime>,
+    ) -> Result<(), MutateError> {
+        // Descriptors registered while recording must be written before the work is submitted.
+        device.descriptors.flush(device.as_raw());
+        let intent = self.semaphore.next_signal();
+        let wait = intent.wait_value();
+        device
+            .queues
+            .graphics_offscreen(QueuePriority::High)
+            .queue_ref()
+            .submission()
+            .execute(recorded)
+            .signal(intent, vk::PipelineStageFlags2::ALL_COMMANDS)
+            .submit(device, vk::Fence::null())?;
+        wait.wait(device, TIMEOUT)?;
+        // The only buffer from this pool just retired.
+        unsafe { self.pool.reset(device, false)? };
+        Ok(())
+    }
+
+    /// The last submission was waited on, so nothing is in flight.
+    fn destroy(self, device: &Device) {
+        if let Err(e) = self.bank.destroy(device) {
+            eprintln!("poster: bank destruction failed {:?}", e);
+        }
+        if let Err(e) = self.waterfall.destroy(device) {
+            eprintln!("poster: waterfall destruction failed {:?}", e);
+        }
+        if let Err(e) = self.msaa.destroy(device) {
+            eprintln!("poster: target destruction failed {:?}", e);
+        }
+        if let Err(e) = self.tile.destroy(device) {
+            eprintln!("poster: tile destruction failed {:?}", e);
+        }
+        if let Err(e) = self.readback.destroy(device) {
+            eprintln!("poster: readback destruction failed {:?}", e);
+        }
+        if let Some(Err(e)) = self.text.map(|text| text.destroy(device)) {
+            eprintln!("poster: text destruction failed {:?}", e);
+        }
+        self.semaphore.destroy(device);
+        self.pool.destroy(device);
+    }
+}
@@ -0,0 +1,325 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Crossfade
+//!
+//! Blends the outgoing scene over the incoming one while [scenes](super::scene) switch.  The
+//! outgoing scene draws into the acquired image first, and [`CrossfadeNode::capture`] copies the
+//! result into an [`OffscreenTarget`].  The incoming scene then draws over the acquired image as
+//! usual, and [`CrossfadeNode::draw`] lays the captured frame back over it, fading out.
+//!
+//! Capturing copies out of the swapchain, which surfaces only allow where they support
+//! `TRANSFER_SRC`.  Elsewhere scenes cut.
+
+use ash::vk;
+use mutate_lib::{self as utate, prelude::*};
+use utate::gpu::resource::image;
+use utate::graph::DeletionQueue;
+
+pub struct CrossfadeNode {
+    pipeline_layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+    /// The swapchain's, so that captures copy in untouched.
+    format: vk::Format,
+    /// Whether swapchain images can be copied from.
+    readable: bool,
+    /// Holds the outgoing scene's frame.
+    target: Option<OffscreenTarget>,
+}
+
+impl CrossfadeNode {
+    /// Draws into swapchain images of `format` created with `usage`.
+    pub fn new(
+        device: &Device,
+        format: vk::Format,
+        usage: vk::ImageUsageFlags,
+    ) -> Result<Self, VulkanError> {
+        let (pipeline_layout, pipeline) = Self::pipeline(device, format)?;
+        Ok(Self {
+            pipeline_layout,
+            pipeline,
+            format,
+            readable: usage.contains(vk::ImageUsageFlags::TRANSFER_SRC),
+            target: None,
+        })
+    }
+
+    /// Whether frames can be captured, so that fades can be drawn.
+    pub fn is_ready(&self) -> bool {
+        self.readable && self.target.is_some()
+    }
+
+    fn pipeline(
+        device: &Device,
+        format: vk::Format,
+    ) -> Result<(vk::PipelineLayout, vk::Pipeline), VulkanError> {
+        let vert = super::shader_module(device, "crossfade/vertex")?;
+        let frag = super::shader_module(device, "crossfade/fragment")?;
+
+        let stages = [
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::VERTEX)
+                .module(vert)
+                .name(c"main"),
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::FRAGMENT)
+                .module(frag)
+                .name(c"main"),
+        ];
+
+        let push_range = vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            offset: 0,
+            size: std::mem::size_of::<[u32; 2]>() as u32,
+        };
+        let layout_ci = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(device.descriptors.layout())
+            .push_constant_ranges(std::slice::from_ref(&push_range));
+        let pipeline_layout = unsafe { device.as_raw().create_pipeline_layout(&layout_ci, None)? };
+
+        let vertex_input = vk::PipelineVertexInputStateCreateInfo::default();
+        let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::default()
+            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+        let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
+        let rasterizer = vk::PipelineRasterizationStateCreateInfo::default()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .line_width(1.0);
+        let multisampling = vk::PipelineMultisampleStateCreateInfo::default()
+            .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+        // The captured frame's opacity mixes it with the incoming scene.
+        let blend_attachment = vk::PipelineColorBlendAttachmentState::default()
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+            .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ZERO)
+            .dst_alpha_blend_factor(vk::BlendFactor::ONE)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .color_write_mask(vk::ColorComponentFlags::RGBA);
+        let color_blend = vk::PipelineColorBlendStateCreateInfo::default()
+            .attachments(std::slice::from_ref(&blend_attachment));
+        let color_formats = [format];
+        let mut rendering =
+            vk::PipelineRenderingCreateInfo::default().color_attachment_formats(&color_formats);
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state =
+            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+
+        let pipeline_ci = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input)
+            .input_assembly_state(&input_assembly)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterizer)
+            .multisample_state(&multisampling)
+            .color_blend_state(&color_blend)
+            .dynamic_state(&dynamic_state)
+            .layout(pipeline_layout)
+            .push_next(&mut rendering);
+        let pipeline = unsafe {
+            device
+                .as_raw()
+                .create_graphics_pipelines(device.pipeline_cache(), &[pipeline_ci], None)
+                .map_err(|e| e.1)?[0]
+        };
+
+        unsafe {
+            device.as_raw().destroy_shader_module(vert, None);
+            device.as_raw().destroy_shader_module(frag, None);
+        }
+        Ok((pipeline_layout, pipeline))
+    }
+
+    /// Provision the capture target for `size`.  A replaced target is queued on `deletions` behind
+    /// the frames already recorded with it.
+    pub fn provision(
+        &mut self,
+        device: &Device,
+        size: vk::Extent2D,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        if !self.readable {
+            return Ok(());
+        }
+        if let Some(existing) = self.target.take() {
+            deletions.defer(frames, move |device| {
+                if let Err(e) = existing.destroy(device) {
+                    eprintln!("crossfade: target destruction failed {:?}", e);
+                }
+            });
+        }
+        let target = OffscreenTarget::new(device, size, self.format)?;
+        target.set_name(device, "crossfade");
+        self.target = Some(target);
+        Ok(())
+    }
+
+    /// Copy the outgoing scene out of `acquired_image`, which must be in `TRANSFER_DST_OPTIMAL` and
+    /// is left that way.  Whatever draws next waits for everything before, so the incoming scene
+    /// may reuse the outgoing scene's buffers.
+    pub fn capture(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+    ) {
+        let Some(target) = &mut self.target else {
+            return;
+        };
+        let to_source = vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+            .src_access_mask(vk::AccessFlags2::MEMORY_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .dst_access_mask(vk::AccessFlags2::TRANSFER_READ)
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .image(acquired_image.image)
+            .subresource_range(image::range());
+        let to_destination = vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_READ)
+            .dst_stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+            .dst_access_mask(vk::AccessFlags2::MEMORY_WRITE)
+            .old_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .image(acquired_image.image)
+            .subresource_range(image::range());
+        // Scenes share buffers, such as the ring's output.
+        let everything = vk::MemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+            .src_access_mask(vk::AccessFlags2::MEMORY_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+            .dst_access_mask(vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE);
+        let raw = device.as_raw();
+        unsafe {
+            raw.cmd_pipeline_barrier2(
+                **cb,
+                &vk::DependencyInfo::default().image_memory_barriers(&[to_source]),
+            );
+        }
+        target.copy_from(device, **cb, acquired_image.image);
+        unsafe {
+            raw.cmd_pipeline_barrier2(
+                **cb,
+                &vk::DependencyInfo::default()
+                    .memory_barriers(&[everything])
+                    .image_memory_barriers(&[to_destination]),
+            );
+        }
+    }
+
+    /// Lay the captured frame over `acquired_image` at `opacity`.  The image must be in
+    /// `TRANSFER_DST_OPTIMAL` after transfer writes and is left that way.
+    pub fn draw(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        opacity: f32,
+    ) {
+        let Some(target) = &mut self.target else {
+            return;
+        };
+        let captured_idx = target.sample(device, **cb);
+        let raw = device.as_raw();
+
+        let to_attachment = vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+            .dst_access_mask(
+                vk::AccessFlags2::COLOR_ATTACHMENT_READ | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+            )
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .image(acquired_image.image)
+            .subresource_range(image::range());
+        let to_transfer = vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+            .src_access_mask(vk::AccessFlags2::COLOR_ATTACHMENT_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .dst_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .old_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .image(acquired_image.image)
+            .subresource_range(image::range());
+
+        let extent = acquired_image.extent;
+        let color_attachment = vk::RenderingAttachmentInfo::default()
+            .image_view(acquired_image.image_view)
+            .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::LOAD)
+            .store_op(vk::AttachmentStoreOp::STORE);
+        let render_area = vk::Rect2D {
+            offset: vk::Offset2D::default(),
+            extent,
+        };
+        let rendering_info = vk::RenderingInfo::default()
+            .render_area(render_area)
+            .layer_count(1)
+            .color_attachments(std::slice::from_ref(&color_attachment));
+        let viewport = vk::Viewport {
+            width: extent.width as f32,
+            height: extent.height as f32,
+            max_depth: 1.0,
+            ..Default::default()
+        };
+
+        let push: [u32; 2] = [captured_idx.index().raw(), opacity.to_bits()];
+        unsafe {
+            raw.cmd_pipeline_barrier2(
+                **cb,
+                &vk::DependencyInfo::default().image_memory_barriers(&[to_attachment]),
+            );
+            raw.cmd_begin_rendering(**cb, &rendering_info);
+            raw.cmd_bind_pipeline(**cb, vk::PipelineBindPoint::GRAPHICS, self.pipeline);
+            raw.cmd_bind_descriptor_sets(
+                **cb,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                0,
+                &[device.descriptors.set()],
+                &[],
+            );
+            raw.cmd_push_constants(
+                **cb,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                0,
+                bytemuck::cast_slice(&push),
+            );
+            raw.cmd_set_viewport(**cb, 0, &[viewport]);
+            raw.cmd_set_scissor(**cb, 0, &[render_area]);
+            raw.cmd_draw(**cb, 3, 1, 0, 0);
+            raw.cmd_end_rendering(**cb);
+            raw.cmd_pipeline_barrier2(
+                **cb,
+                &vk::DependencyInfo::default().image_memory_barriers(&[to_transfer]),
+            );
+        }
+    }
+
+    /// Forget the target's layout after a frame that drew it was abandoned.
+    pub fn reset(&mut self) {
+        if let Some(target) = &mut self.target {
+            target.discard();
+        }
+    }
+
+    /// Caller must drain work that drew the fade first.
+    pub fn destroy(self, device: &Device) -> Result<(), utate::MutateError> {
+        unsafe {
+            device.as_raw().destroy_pipeline(self.pipeline, None);
+            device
+                .as_raw()
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+        }
+        if let Some(target) = self.target {
+            target.destroy(device)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Drive
+//!
+//! Graph outputs that steer what the scenes draw.  A preset built with `--graph` may use two node
+//! kinds that the visualizer registers: `audio`, which emits what is playing, and `drive`, whose
+//! scalar inputs are applied to every window's [`SceneNodes`] and [`Scenes`] each frame.
+//!
+//! ```toml
+//! [[node]]
+//! name = "audio"
+//! kind = "audio"
+//!
+//! [[node]]
+//! name = "loudness"
+//! kind = "loudness"
+//!
+//! [[node]]
+//! name = "spectrogram"
+//! kind = "spectrogram"
+//!
+//! [[node]]
+//! name = "segment"
+//! kind = "segment"
+//! rows_per_second = 60    # the frame rate
+//!
+//! [[node]]
+//! name = "visuals"
+//! kind = "drive"
+//!
+//! [[edge]]
+//! from = "audio.output"
+//! to = "loudness.input"
+//!
+//! [[edge]]
+//! from = "loudness.gain"
+//! to = "visuals.level"
+//!
+//! [[edge]]
+//! from = "audio.output"
+//! to = "spectrogram.input"
+//!
+//! [[node]]
+//! name = "chroma"
+//! kind = "chroma"
+//! rows_per_second = 60
+//!
+//! [[edge]]
+//! from = "spectrogram.row"
+//! to = "chroma.row"
+//!
+//! [[edge]]
+//! from = "chroma.chroma"
+//! to = "segment.features"
+//!
+//! [[edge]]
+//! from = "segment.section"
+//! to = "visuals.section"
+//! ```
+//!
+//! - **level** scales the scope and vectorscope traces, such as by the loudness gain.
+//! - **zoom**, **rotate**, and **fade** replace the [feedback](super::feedback) trail's own.
+//! - **section** changes scenes when the song moves to another section, such as from a `segment`
+//!   node fed the chroma of a `spectrogram` or `dft` node.  A section that repeats returns to the
+//!   scene it showed.  See [`Scenes::section`].
+//!
+//! Inputs that are not connected, or whose source emitted nothing this frame, leave the scenes
+//! drawing as they would without a graph.
+//!
+//! `--graph` also takes the name of one of the [`PRESETS`] built in.  `--graph song` resamples what
+//! is playing, levels the traces by its loudness, and changes scenes by the sections of its
+//! harmony, folded from the long windows of a `dft` node.
+//!
+//! [`Scenes`]: super::scene::Scenes
+//! [`Scenes::section`]: super::scene::Scenes::section
+
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use mutate_lib::{self as utate, graph::prelude::*};
+use utate::graph::preset::Preset;
+
+use super::feedback::Feedback;
+use super::scene::SceneNodes;
+
+/// Presets `--graph` knows by name, for when no file of that name exists.
+pub const PRESETS: &[(&str, &str)] = &[("song", include_str!("../../presets/song.toml"))];
+
+/// The preset `--graph` names: a file, or else one of the [`PRESETS`].
+pub fn preset(graph: &Path) -> Result<Preset, utate::MutateError> {
+    let builtin = PRESETS
+        .iter()
+        .find(|(name, _)| graph.as_os_str() == *name && !graph.exists());
+    match builtin {
+        Some((_, text)) => Preset::parse(text),
+        None => Preset::load(graph),
+    }
+}
+
+/// The latest value of each input of a [`DriveNode`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Driven {
+    pub level: Option<f32>,
+    pub zoom: Option<f32>,
+    pub rotate: Option<f32>,
+    pub fade: Option<f32>,
+    /// Label of the section playing.
+    pub section: Option<u32>,
+}
+
+impl Driven {
+    /// Steer `nodes`, falling back to their defaults for values not driven.
+    pub fn apply(&self, nodes: &mut SceneNodes) {
+        let level = self.level.unwrap_or(1.0);
+        nodes.scope.level = level;
+        nodes.vectorscope.level = level;
+        let feedback = Feedback::default();
+        nodes.feedback.feedback = Feedback {
+            zoom: self.zoom.unwrap_or(feedback.zoom),
+            rotate: self.rotate.unwrap_or(feedback.rotate),
+            fade: self.fade.unwrap_or(feedback.fade).clamp(0.0, 1.0),
+        };
+    }
+}
+
+/// The host's end of a [`DriveNode`].  Clones read the same node.
+#[derive(Clone, Default)]
+pub struct Drive {
+    latest: Arc<Mutex<Driven>>,
+}
+
+impl Drive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A node whose inputs this reads.  Only one should be in a graph.
+    pub fn node(&self) -> DriveNode {
+        DriveNode {
+            latest: self.latest.clone(),
+        }
+    }
+
+    /// What the graph's last frame delivered.
+    pub fn latest(&self) -> Driven {
+        *self.latest.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Hands its inputs to a [`Drive`].  See the [module](self) docs.
+pub struct DriveNode {
+    latest: Arc<Mutex<Driven>>,
+}
+
+impl DriveNode {
+    const LEVEL: usize = 0;
+    const ZOOM: usize = 1;
+    const ROTATE: usize = 2;
+    const FADE: usize = 3;
+    const SECTION: usize = 4;
+}
+
+impl Params for DriveNode {
+    fn param_specs(&self) -> &'static [ParamSpec] {
+        &[]
+    }
+}
+
+impl Node for DriveNode {
+    fn inputs(&self) -> &'static [PortSpec] {
+        &[
+            PortSpec {
+                name: "level",
+                kind: PortKind::Scalar,
+            },
+            PortSpec {
+                name: "zoom",
+                kind: PortKind::Scalar,
+            },
+            PortSpec {
+                name: "rotate",
+                kind: PortKind::Scalar,
+            },
+            PortSpec {
+                name: "fade",
+                kind: PortKind::Scalar,
+            },
+            PortSpec {
+                name: "section",
+                kind: PortKind::Scalar,
+            },
+        ]
+    }
+
+    fn attach(&mut self, _params: ParamHandle) {}
+
+    fn run(&mut self, frame: &mut Frame<'_>) -> Result<(), utate::MutateError> {
+        let scalar = |port| match frame.input(port) {
+            Some(GraphEvent::Scalar(x)) if x.is_finite() => Some(*x as f32),
+            _ => None,
+        };
+        let driven = Driven {
+            level: scalar(Self::LEVEL),
+            zoom: scalar(Self::ZOOM),
+            rotate: scalar(Self::ROTATE),
+            fade: scalar(Self::FADE),
+            section: scalar(Self::SECTION)
+                .filter(|x| *x >= 0.0)
+                .map(|x| x as u32),
+        };
+        *self.latest.lock().unwrap_or_else(PoisonError::into_inner) = driven;
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Feedback
+//!
+//! Echo trails from the previous frame.  Each frame samples last frame's output, zoomed, rotated,
+//! and faded, and composites it under the ring's output wherever that is dark.  The result is
+//! copied to the swapchain and becomes the next frame's previous frame.  This is the feedback
+//! cycle of the [graph docs](mutate_lib::graph::schedule), unrolled across frames by hand.
+//!
+//! Two [`OffscreenTarget`]s trade places every frame, so the one being drawn is never the one being
+//! sampled.  Both are the swapchain's size and are provisioned again when it resizes.
+
+// NEXT a graph node with `zoom`, `rotate`, and `fade` parameters once render nodes move into the
+// graph.  The previous frame then arrives on a feedback edge as an `Image` event.
+// MAYBE draw with MSAA.  The trail is resampled every frame, so it is already soft.
+
+use ash::vk;
+use mutate_lib::{self as utate, prelude::*};
+use utate::gpu::device::descriptors::{samplers, Handle};
+use utate::graph::DeletionQueue;
+
+/// The targets hold whatever bytes the ring writes, so they are `UNORM` and copy to the swapchain
+/// untouched whatever its encoding.
+const FORMAT: vk::Format = vk::Format::B8G8R8A8_UNORM;
+
+/// How the previous frame is transformed before it is composited.
+#[derive(Clone, Copy, Debug)]
+pub struct Feedback {
+    /// Scale per frame.  Above one, trails grow outward.
+    pub zoom: f32,
+    /// Radians per frame.
+    pub rotate: f32,
+    /// Brightness kept per frame.
+    pub fade: f32,
+}
+
+impl Default for Feedback {
+    fn default() -> Self {
+        Self {
+            zoom: 1.01,
+            rotate: 0.005,
+            fade: 0.92,
+        }
+    }
+}
+
+pub struct FeedbackNode {
+    pipeline_layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+    pub feedback: Feedback,
+
+    /// Drawn into and sampled alternately.
+    targets: Option<[OffscreenTarget; 2]>,
+    /// Target drawn next frame.
+    current: usize,
+    /// Whether the other target holds a frame yet.
+    primed: bool,
+}
+
+impl FeedbackNode {
+    pub fn new(device: &Device) -> Result<Self, VulkanError> {
+        let (pipeline_layout, pipeline) = Self::pipeline(device)?;
+        Ok(Self {
+            pipeline_layout,
+            pipeline,
+            feedback: Feedback::default(),
+            targets: None,
+            current: 0,
+            primed: false,
+        })
+    }
+
+    fn pipeline(device: &Device) -> Result<(vk::PipelineLayout, vk::Pipeline), VulkanError> {
+        let vert = super::shader_module(device, "feedback/vertex")?;
+        let frag = super::shader_module(device, "feedback/fragment")?;
+
+        let stages = [
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::VERTEX)
+                .module(vert)
+                .name(c"main"),
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::FRAGMENT)
+                .module(frag)
+                .name(c"main"),
+        ];
+
+        let push_range = vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            offset: 0,
+            size: std::mem::size_of::<[u32; 7]>() as u32,
+        };
+        let layout_ci = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(device.descriptors.layout())
+            .push_constant_ranges(std::slice::from_ref(&push_range));
+        let pipeline_layout = unsafe { device.as_raw().create_pipeline_layout(&layout_ci, None)? };
+
+        let vertex_input = vk::PipelineVertexInputStateCreateInfo::default();
+        let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::default()
+            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+        let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
+        let rasterizer = vk::PipelineRasterizationStateCreateInfo::default()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .line_width(1.0);
+        let multisampling = vk::PipelineMultisampleStateCreateInfo::default()
+            .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+        let blend_attachment = vk::PipelineColorBlendAttachmentState::default()
+            .color_write_mask(vk::ColorComponentFlags::RGBA);
+        let color_blend = vk::PipelineColorBlendStateCreateInfo::default()
+            .attachments(std::slice::from_ref(&blend_attachment));
+        let color_formats = [FORMAT];
+        let mut rendering =
+            vk::PipelineRenderingCreateInfo::default().color_attachment_formats(&color_formats);
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state =
+            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+
+        let pipeline_ci = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input)
+            .input_assembly_state(&input_assembly)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterizer)
+            .multisample_state(&multisampling)
+            .color_blend_state(&color_blend)
+            .dynamic_state(&dynamic_state)
+            .layout(pipeline_layout)
+            .push_next(&mut rendering);
+        let pipeline = unsafe {
+            device
+                .as_raw()
+                .create_graphics_pipelines(device.pipeline_cache(), &[pipeline_ci], None)
+                .map_err(|e| e.1)?[0]
+        };
+
+        unsafe {
+            device.as_raw().destroy_shader_module(vert, None);
+            device.as_raw().destroy_shader_module(frag, None);
+        }
+        Ok((pipeline_layout, pipeline))
+    }
+
+    /// Provision targets for `size`.  Replaced targets are queued on `deletions` behind the frames
+    /// already recorded with them, and the trail starts over.
+    pub fn provision(
+        &mut self,
+        device: &Device,
+        size: vk::Extent2D,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        if let Some(existing) = self.targets.take() {
+            deletions.defer(frames, move |device| {
+                for target in existing {
+                    if let Err(e) = target.destroy(device) {
+                        eprintln!("feedback: target destruction failed {:?}", e);
+                    }
+                }
+            });
+        }
+
+        let first = OffscreenTarget::new(device, size, FORMAT)?;
+        let second = match OffscreenTarget::new(device, size, FORMAT) {
+            Ok(second) => second,
+            Err(e) => {
+                let _ = first.destroy(device);
+                return Err(e.into());
+            }
+        };
+        first.set_name(device, "feedback 0");
+        second.set_name(device, "feedback 1");
+        self.targets = Some([first, second]);
+        self.current = 0;
+        self.primed = false;
+        Ok(())
+    }
+
+    /// Draw the trail under `spectrum` and copy the result into `acquired_image`, which must be in
+    /// `TRANSFER_DST_OPTIMAL`.  `spectrum` was just written by a compute shader, such as
+    /// [`RawRingDraw::dispatch`](super::ring::RawRingDraw::dispatch), and holds packed BGRA pixels
+    /// of the acquired image's size.
+    pub fn draw(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        spectrum: (vk::Buffer, Handle<SsboIdx>),
+    ) {
+        let Some(targets) = &mut self.targets else {
+            return;
+        };
+        let extent = acquired_image.extent;
+        let (buffer, spectrum_idx) = spectrum;
+        let raw = device.as_raw();
+
+        let spectrum_barrier = vk::BufferMemoryBarrier {
+            src_access_mask: vk::AccessFlags::SHADER_WRITE,
+            dst_access_mask: vk::AccessFlags::SHADER_READ,
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            buffer,
+            offset: 0,
+            size: vk::WHOLE_SIZE,
+            ..Default::default()
+        };
+        unsafe {
+            raw.cmd_pipeline_barrier(
+                **cb,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[spectrum_barrier],
+                &[],
+            );
+        }
+
+        let [first, second] = targets;
+        let (current, previous) = match self.current {
+            0 => (first, second),
+            _ => (second, first),
+        };
+        // An unprimed previous target was never drawn, so it is left alone and the shader skips it.
+        let fade = match self.primed {
+            true => {
+                previous.sample(device, **cb);
+                self.feedback.fade
+            }
+            false => 0.0,
+        };
+        // The triangle covers the target, so nothing needs clearing.
+        let color_attachment = current
+            .color_attachment(device, **cb)
+            .load_op(vk::AttachmentLoadOp::DONT_CARE);
+        let render_area = vk::Rect2D {
+            offset: vk::Offset2D::default(),
+            extent,
+        };
+        let rendering_info = vk::RenderingInfo::default()
+            .render_area(render_area)
+            .layer_count(1)
+            .color_attachments(std::slice::from_ref(&color_attachment));
+        let viewport = vk::Viewport {
+            width: extent.width as f32,
+            height: extent.height as f32,
+            max_depth: 1.0,
+            ..Default::default()
+        };
+
+        let push: [u32; 7] = [
+            previous.handle().index().raw(),
+            samplers::LINEAR_CLAMP.raw(),
+            spectrum_idx.index().raw(),
+            extent.width,
+            self.feedback.zoom.to_bits(),
+            self.feedback.rotate.to_bits(),
+            fade.to_bits(),
+        ];
+        unsafe {
+            raw.cmd_begin_rendering(**cb, &rendering_info);
+            raw.cmd_bind_pipeline(**cb, vk::PipelineBindPoint::GRAPHICS, self.pipeline);
+            raw.cmd_bind_descriptor_sets(
+                **cb,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                0,
+                &[device.descriptors.set()],
+                &[],
+            );
+            raw.cmd_push_constants(
+                **cb,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                0,
+                bytemuck::cast_slice(&push),
+            );
+            raw.cmd_set_viewport(**cb, 0, &[viewport]);
+            raw.cmd_set_scissor(**cb, 0, &[render_area]);
+            raw.cmd_draw(**cb, 3, 1, 0, 0);
+            raw.cmd_end_rendering(**cb);
+        }
+        current.copy_to(device, **cb, acquired_image.image);
+
+        self.current = 1 - self.current;
+        self.primed = true;
+    }
+
+    /// Start the trail over, such as after a frame that drew it was abandoned or when its scene is
+    /// shown again.
+    pub fn reset(&mut self) {
+        if let Some(targets) = &mut self.targets {
+            targets.iter_mut().for_each(OffscreenTarget::discard);
+        }
+        self.primed = false;
+    }
+
+    /// Caller must drain work that drew the trail first.
+    pub fn destroy(self, device: &Device) -> Result<(), utate::MutateError> {
+        unsafe {
+            device.as_raw().destroy_pipeline(self.pipeline, None);
+            device
+                .as_raw()
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+        }
+        if let Some(targets) = self.targets {
+            for target in targets {
+                target.destroy(device)?;
+            }
+        }
+        Ok(())
+    }
+}
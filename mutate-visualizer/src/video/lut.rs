@@ -0,0 +1,66 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Palette Tables
+//!
+//! A [`Palette`] sampled into a storage buffer so that shaders color by level the same way the host
+//! does.  Shaders `import palette;` from `shaders/lib` and call `palette_color` with the table's
+//! buffer.
+//!
+//! A table is written once.  To change palettes, make a new table and queue the old one behind the
+//! frames that still read it.
+
+use mutate_lib::{self as utate, prelude::*};
+use utate::color::Palette;
+use utate::gpu::device::descriptors::Handle;
+use utate::gpu::resource::buffer;
+use utate::graph::DeletionQueue;
+
+/// Entries per table.  Matches `PALETTE_LEN` in the shader library.
+pub const LUT_LEN: usize = 256;
+
+pub struct PaletteLut {
+    table: buffer::MappedAllocation<[f32; 4]>,
+    idx: Handle<SsboIdx>,
+}
+
+impl PaletteLut {
+    pub fn new(device: &Device, palette: &Palette) -> Result<Self, utate::MutateError> {
+        let mut table = buffer::MappedAllocation::new(LUT_LEN, device)?;
+        table.set_name(device, "palette");
+        table.as_mut_slice().copy_from_slice(&palette.lut(LUT_LEN));
+        table.flush(device)?;
+        let idx = table.register(device)?;
+        Ok(Self { table, idx })
+    }
+
+    /// Bindless index for push constants.
+    pub fn index(&self) -> u32 {
+        self.idx.index().raw()
+    }
+
+    /// Swap in a table of `palette`.  The old one is queued on `deletions` behind the frames already
+    /// recorded with it.
+    pub fn replace(
+        &mut self,
+        device: &Device,
+        palette: &Palette,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        let old = std::mem::replace(self, Self::new(device, palette)?);
+        deletions.defer(frames, move |device| {
+            if let Err(e) = old.destroy(device) {
+                eprintln!("palette: table destruction failed {:?}", e);
+            }
+        });
+        Ok(())
+    }
+
+    /// Caller must drain work that read the table first.
+    pub fn destroy(self, device: &Device) -> Result<(), utate::MutateError> {
+        device.descriptors.release(self.idx);
+        self.table.destroy(device)?;
+        Ok(())
+    }
+}
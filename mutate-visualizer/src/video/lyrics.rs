@@ -0,0 +1,103 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Lyrics
+//!
+//! The current line of `--lyrics` centered near the bottom of the frame, with the
+//! [karaoke sweep](mutate_lib::lyrics) drawn by coloring the sung part of the line.  Without
+//! `--lyrics`, the LRC file beside `--file` is loaded when there is one.
+//!
+//! ```text
+//! mutate-visualizer --file song.flac --lyrics song.lrc
+//! ```
+//!
+//! Sung text takes the bright end of the palette and the rest its dark end.  Lines are timed by
+//! track position, which is only known when playing or exporting a file.  Lines without word timing
+//! sweep in steps of the beats that [`Beats`](crate::audio::beats::Beats) predicts.
+
+use std::path::PathBuf;
+
+use ash::vk;
+use mutate_lib::{self as utate, MutateError};
+use utate::color::Palette;
+use utate::dsp::beat::BeatPhase;
+use utate::dsp::units::Seconds;
+use utate::lyrics::{Lyrics, LyricsNode};
+
+use super::text::{TextNode, TextStyle};
+use crate::Args;
+
+/// Lines sit this many line heights above the bottom edge.
+const LINES_UP: f32 = 3.0;
+
+/// Where `--lyrics` come from: the flag, or else an LRC file beside `--file`.
+pub fn path(args: &Args) -> Option<PathBuf> {
+    let beside = || {
+        let lrc = args.file.as_ref()?.with_extension("lrc");
+        lrc.is_file().then_some(lrc)
+    };
+    args.lyrics.clone().or_else(beside)
+}
+
+/// Loaded lyrics and the colors to draw them in.
+pub struct LyricsView {
+    node: LyricsNode,
+    sung: [f32; 4],
+    unsung: [f32; 4],
+}
+
+impl LyricsView {
+    /// Load the lyrics of `args`, if there are any.
+    pub fn load(args: &Args, palette: &Palette) -> Result<Option<Self>, MutateError> {
+        let Some(path) = path(args) else {
+            return Ok(None);
+        };
+        let lyrics = Lyrics::load(&path)?;
+        println!("lyrics: {}", path.display());
+        let mut view = Self {
+            node: LyricsNode::new(lyrics),
+            sung: [1.0; 4],
+            unsung: [1.0; 4],
+        };
+        view.set_palette(palette);
+        Ok(Some(view))
+    }
+
+    pub fn set_palette(&mut self, palette: &Palette) {
+        let rgba = |x| {
+            let c = palette.sample(x);
+            [c.red, c.green, c.blue, 1.0]
+        };
+        self.sung = rgba(1.0);
+        self.unsung = rgba(0.0);
+    }
+
+    /// Queue the line showing at track position `now` over a frame of `extent`.  `beat` steps the
+    /// sweep of lines without word timing.
+    pub fn queue(
+        &self,
+        text: &mut TextNode,
+        now: Seconds,
+        beat: Option<BeatPhase>,
+        extent: vk::Extent2D,
+    ) {
+        let Some(cue) = self.node.cue(now, beat) else {
+            return;
+        };
+        let sung = TextStyle {
+            scale: (extent.height / 360).max(1),
+            color: self.sung,
+        };
+        let unsung = TextStyle {
+            color: self.unsung,
+            ..sung
+        };
+        let (before, after) = cue.split();
+        let [width, height] = text.measure(&cue.line.text, &sung);
+        let [swept, _] = text.measure(before, &sung);
+        let x = (extent.width as f32 - width) / 2.0;
+        let y = extent.height as f32 - height * LINES_UP;
+        text.draw_text([x, y], before, &sung);
+        text.draw_text([x + swept, y], after, &unsung);
+    }
+}
@@ -5,5 +5,102 @@
 //!
 //! Drawing and presentation go here.
 
+pub mod crossfade;
+pub mod drive;
+pub mod feedback;
+pub mod lut;
+pub mod lyrics;
+pub mod overlay;
+pub mod particles;
 pub mod ring;
+pub mod scene;
+pub mod scope;
+pub mod screenshot;
+pub mod spectrum;
+pub mod text;
+pub mod texture;
+pub mod tiles;
 pub mod triangle;
+pub mod vectorscope;
+pub mod waterfall;
+
+use ash::vk;
+use mutate_lib::{assets, gpu::resource::image, prelude::*};
+
+/// Create a module from the compiled shader `name`.  Destroy it once the pipelines that use it are
+/// created.
+// DEBT modules and layouts created before a later step fails are leaked.  Failures this early mean
+// a broken install or an exhausted device, and the node is never built.
+pub fn shader_module(device: &Device, name: &str) -> Result<vk::ShaderModule, VulkanError> {
+    let spv = assets::AssetDirs::new().find_bytes(name, assets::AssetKind::Shader)?;
+    let ci = vk::ShaderModuleCreateInfo {
+        code_size: spv.len(),
+        p_code: spv.as_ptr() as *const u32,
+        ..Default::default()
+    };
+    Ok(unsafe { device.as_raw().create_shader_module(&ci, None)? })
+}
+
+/// The whole of `acquired_image`, for nodes that draw into an area of it.
+pub fn full(acquired_image: &AcquiredImage) -> vk::Rect2D {
+    vk::Rect2D {
+        offset: vk::Offset2D::default(),
+        extent: acquired_image.extent,
+    }
+}
+
+/// A viewport covering `area`.
+pub fn viewport(area: vk::Rect2D) -> vk::Viewport {
+    vk::Viewport {
+        x: area.offset.x as f32,
+        y: area.offset.y as f32,
+        width: area.extent.width as f32,
+        height: area.extent.height as f32,
+        min_depth: 0.0,
+        max_depth: 1.0,
+    }
+}
+
+/// Record `draw` with `acquired_image` as a color attachment.  The image must be in
+/// `TRANSFER_DST_OPTIMAL` after transfer writes, as scenes leave it, and is left that way.
+pub fn as_attachment(
+    device: &Device,
+    cb: vk::CommandBuffer,
+    acquired_image: &AcquiredImage,
+    draw: impl FnOnce(),
+) {
+    let to_attachment = vk::ImageMemoryBarrier2::default()
+        .src_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+        .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+        .dst_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+        .dst_access_mask(
+            vk::AccessFlags2::COLOR_ATTACHMENT_READ | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+        )
+        .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+        .new_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .image(acquired_image.image)
+        .subresource_range(image::range());
+    let to_transfer = vk::ImageMemoryBarrier2::default()
+        .src_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+        .src_access_mask(vk::AccessFlags2::COLOR_ATTACHMENT_WRITE)
+        .dst_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+        .dst_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+        .old_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+        .image(acquired_image.image)
+        .subresource_range(image::range());
+    let raw = device.as_raw();
+    unsafe {
+        raw.cmd_pipeline_barrier2(
+            cb,
+            &vk::DependencyInfo::default().image_memory_barriers(&[to_attachment]),
+        );
+    }
+    draw();
+    unsafe {
+        raw.cmd_pipeline_barrier2(
+            cb,
+            &vk::DependencyInfo::default().image_memory_barriers(&[to_transfer]),
+        );
+    }
+}
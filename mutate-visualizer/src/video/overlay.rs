@@ -0,0 +1,207 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Stats Overlay
+//!
+//! A frame time graph in the bottom left corner, toggled with `S`.  Each column is a frame, newest
+//! on the right.  Host time is green, or red when it ran over the refresh period, and GPU time is
+//! drawn over it in blue.  A white line marks the refresh period and a red tick along the top marks
+//! a dropped frame.  A yellow tick along the bottom marks a frame that fired a predicted beat, so it
+//! should flash as the beat is heard when latency is compensated well.  The numbers go in the
+//! window title and, as text, the top right corner.
+//!
+//! The graph is painted on the host and copied into the acquired image after the frame is drawn, so
+//! it needs no pipeline.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use ash::vk;
+use mutate_lib::{self as utate, prelude::*};
+use utate::gpu::resource::buffer;
+use utate::graph::context::FRAMES_IN_FLIGHT;
+
+/// Graph size in pixels.  One column per frame.
+const WIDTH: u32 = 240;
+const HEIGHT: u32 = 64;
+/// Distance from the window edges.
+const MARGIN: u32 = 8;
+/// Graph height in refresh periods.
+const SCALE: f64 = 1.5;
+
+// Swapchain images are B8G8R8A8.
+const fn bgr(b: u8, g: u8, r: u8) -> rgb::Bgra<u8> {
+    rgb::Bgra { b, g, r, a: 255 }
+}
+const BACKGROUND: rgb::Bgra<u8> = bgr(16, 16, 16);
+const CPU: rgb::Bgra<u8> = bgr(64, 192, 64);
+const OVER: rgb::Bgra<u8> = bgr(48, 48, 224);
+const GPU: rgb::Bgra<u8> = bgr(224, 144, 48);
+const BUDGET: rgb::Bgra<u8> = bgr(255, 255, 255);
+const BEAT: rgb::Bgra<u8> = bgr(32, 224, 240);
+
+#[derive(Clone, Copy)]
+struct Column {
+    cpu: Duration,
+    gpu: Option<Duration>,
+    dropped: bool,
+    beat: bool,
+}
+
+pub struct StatsOverlay {
+    visible: bool,
+    columns: VecDeque<Column>,
+    /// One strip per frame in flight, so painting never races a copy.
+    strips: Vec<buffer::MappedAllocation<rgb::Bgra<u8>>>,
+    cursor: usize,
+}
+
+impl StatsOverlay {
+    pub fn new(device: &Device) -> Result<Self, utate::MutateError> {
+        let mut strips = Vec::with_capacity(FRAMES_IN_FLIGHT);
+        for _ in 0..FRAMES_IN_FLIGHT {
+            match buffer::MappedAllocation::new((WIDTH * HEIGHT) as usize, device) {
+                Ok(strip) => {
+                    strip.set_name(device, "stats overlay");
+                    strips.push(strip);
+                }
+                Err(e) => {
+                    for strip in strips {
+                        let _ = strip.destroy(device);
+                    }
+                    return Err(e.into());
+                }
+            }
+        }
+        Ok(Self {
+            visible: false,
+            columns: VecDeque::with_capacity(WIDTH as usize),
+            strips,
+            cursor: 0,
+        })
+    }
+
+    pub fn toggle(&mut self) -> bool {
+        self.visible = !self.visible;
+        self.visible
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Add a frame to the graph.  Frames are kept while hidden so the graph is full when shown.
+    pub fn push(&mut self, cpu: Duration, gpu: Option<Duration>, dropped: bool, beat: bool) {
+        if self.columns.len() == WIDTH as usize {
+            self.columns.pop_front();
+        }
+        self.columns.push_back(Column {
+            cpu,
+            gpu,
+            dropped,
+            beat,
+        });
+    }
+
+    /// Copy the graph into `acquired_image`, which must be in `TRANSFER_DST_OPTIMAL` after transfer
+    /// writes.  `period` is the refresh period.
+    pub fn draw(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        period: Duration,
+    ) -> Result<(), utate::MutateError> {
+        let extent = acquired_image.extent;
+        if !self.visible || extent.width < WIDTH + MARGIN || extent.height < HEIGHT + MARGIN {
+            return Ok(());
+        }
+        let slot = self.cursor;
+        self.cursor = (slot + 1) % self.strips.len();
+        let strip = &mut self.strips[slot];
+        paint(strip.as_mut_slice(), &self.columns, period);
+        strip.flush(device)?;
+
+        // The frame was copied into the same image.
+        let barrier = vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .dst_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .image(acquired_image.image)
+            .subresource_range(utate::gpu::resource::image::range());
+        let region = vk::BufferImageCopy {
+            image_offset: vk::Offset3D {
+                x: MARGIN as i32,
+                y: (extent.height - HEIGHT - MARGIN) as i32,
+                z: 0,
+            },
+            image_extent: vk::Extent3D {
+                width: WIDTH,
+                height: HEIGHT,
+                depth: 1,
+            },
+            ..buffer::buffer_image_copy_full(extent)
+        };
+        unsafe {
+            let raw = device.as_raw();
+            raw.cmd_pipeline_barrier2(
+                **cb,
+                &vk::DependencyInfo::default().image_memory_barriers(&[barrier]),
+            );
+            raw.cmd_copy_buffer_to_image(
+                **cb,
+                strip.buffer,
+                acquired_image.image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[region],
+            );
+        }
+        Ok(())
+    }
+
+    /// Caller must drain frames that drew the overlay first.
+    pub fn destroy(self, device: &Device) {
+        for strip in self.strips {
+            if let Err(e) = strip.destroy(device) {
+                eprintln!("overlay: strip destruction failed {:?}", e);
+            }
+        }
+    }
+}
+
+/// Paint `columns` into a `WIDTH` by `HEIGHT` image, right aligned.
+fn paint(pixels: &mut [rgb::Bgra<u8>], columns: &VecDeque<Column>, period: Duration) {
+    pixels.fill(BACKGROUND);
+    let full = period.as_secs_f64() * SCALE;
+    // Rows counted up from the bottom.
+    let rows = |d: Duration| ((d.as_secs_f64() / full * HEIGHT as f64) as u32).min(HEIGHT);
+    let budget = rows(period).min(HEIGHT - 1);
+    let mut set =
+        |x: u32, row: u32, color| pixels[((HEIGHT - 1 - row) * WIDTH + x) as usize] = color;
+    let first = WIDTH - columns.len() as u32;
+    for (x, column) in (first..).zip(columns) {
+        let cpu = if column.cpu > period { OVER } else { CPU };
+        for row in 0..rows(column.cpu) {
+            set(x, row, cpu);
+        }
+        for row in 0..column.gpu.map_or(0, rows) {
+            set(x, row, GPU);
+        }
+        if column.dropped {
+            for row in HEIGHT - 4..HEIGHT {
+                set(x, row, OVER);
+            }
+        }
+        if column.beat {
+            for row in 0..4 {
+                set(x, row, BEAT);
+            }
+        }
+    }
+    for x in 0..WIDTH {
+        set(x, budget, BUDGET);
+    }
+}
@@ -0,0 +1,450 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Particles
+//!
+//! A particle fountain played by the spectrum.  Three [`Band`]s of the filter bank's output set how
+//! many particles are born each second, how fast they leave the center, and where their color falls
+//! in the palette.  Particles live in a storage buffer.  A compute pass ages, moves, and respawns
+//! them, and an instanced draw turns each into a soft dot, so the particles never visit the host.
+//!
+//! Spawning is a race.  Every invocation works out the same budget for the frame from the emission
+//! level, and dead particles claim it through an atomic counter cleared before each pass.  The
+//! fraction of a particle left over is carried by chance, so quiet bands still trickle.
+//!
+//! The pipeline is created for the sample count of the [`MsaaTarget`] it draws into, so it must be
+//! rebuilt along with a target of a different count.
+
+// NEXT a graph node with the bands and rates as parameters, fed by a `Buffer` event.
+// MAYBE forces from further bands, such as swirl from the highs.
+
+use std::time::Duration;
+
+use ash::vk;
+use mutate_lib::{self as utate, prelude::*};
+use utate::color::Palette;
+use utate::gpu::device::descriptors::Handle;
+use utate::gpu::resource::buffer;
+use utate::graph::DeletionQueue;
+
+use super::lut::PaletteLut;
+
+/// Words per particle: position, velocity, age, life, shade, and one spare.
+const PARTICLE_WORDS: usize = 8;
+/// Threads per workgroup, from the shader.
+const WORKGROUP: u32 = 64;
+
+#[compute_pipeline(
+    compute = stage!("particles/simulate", Compute, c"main"),
+    push = push!(ParticleSimulateConstants {
+        pub particles_idx: SsboIdx,
+        pub spawned_idx: SsboIdx,
+        pub bands_idx: SsboIdx,
+        pub count: UInt,
+        pub frame: UInt,
+        pub dt: Float,
+        pub floor_db: Float,
+        pub ceiling_db: Float,
+        pub emission: UInt,
+        pub velocity: UInt,
+        pub color: UInt,
+        pub rate: Float,
+        pub speed: Float,
+        pub lifetime: Float,
+    }),
+)]
+pub struct ParticleSimulatePipeline;
+
+/// Consecutive bins of the filter bank's output, averaged into one level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Band {
+    pub first: u32,
+    pub count: u32,
+}
+
+impl Band {
+    /// As the shader takes it, the first bin in the low 16 bits and the count in the high 16, so
+    /// that the pass's constants pack into few enough fields.
+    fn packed(self) -> u32 {
+        self.first.min(0xffff) | self.count.min(0xffff) << 16
+    }
+}
+
+/// Which bands drive what.
+#[derive(Clone, Copy, Debug)]
+pub struct Modulation {
+    /// Births per second, up to [`ParticleNode::rate`].
+    pub emission: Band,
+    /// Launch speed, up to [`ParticleNode::speed`].
+    pub velocity: Band,
+    /// Shade at birth, a position in the palette.
+    pub color: Band,
+}
+
+impl Modulation {
+    /// Lows emit, mids launch, and highs color, each a quarter of `bins`.
+    pub fn spread(bins: u32) -> Self {
+        let quarter = (bins / 4).max(1);
+        let band = |first: u32| Band {
+            first: first.min(bins.saturating_sub(quarter)),
+            count: quarter.min(bins),
+        };
+        Self {
+            emission: band(0),
+            velocity: band((bins / 2).saturating_sub(quarter / 2)),
+            color: band(bins.saturating_sub(quarter)),
+        }
+    }
+}
+
+pub struct ParticleNode {
+    simulate: ComputePipeline<ParticleSimulatePipeline>,
+    pipeline_layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+
+    particles: buffer::MappedAllocation<[f32; PARTICLE_WORDS]>,
+    particles_idx: Handle<SsboIdx>,
+    /// Spawns claimed this frame.
+    spawned: buffer::MappedAllocation<u32>,
+    spawned_idx: Handle<SsboIdx>,
+    count: u32,
+    /// Width of the filter bank the bands were spread over.
+    bins: u32,
+    /// Passes recorded, for fresh randomness each frame.
+    frame: u32,
+    palette: PaletteLut,
+
+    pub modulation: Modulation,
+    /// Births per second at full emission level.
+    pub rate: f32,
+    /// Launch speed at full velocity level, in window heights per second.
+    pub speed: f32,
+    /// Longest life.  Each particle lives between half of this and all of it.
+    pub lifetime: Duration,
+    /// Dot radius in window heights.
+    pub size: f32,
+    /// dB of a silent band.  Anything quieter is clamped.
+    pub floor_db: f32,
+    /// dB of a band at full level.
+    pub ceiling_db: f32,
+}
+
+impl ParticleNode {
+    /// Simulates `count` particles modulated by a filter bank `bins` wide and colored by `palette`.
+    /// Draws into swapchain images of `format` through a target with `samples` per pixel.
+    pub fn new(
+        device: &Device,
+        format: vk::Format,
+        samples: vk::SampleCountFlags,
+        count: u32,
+        bins: u32,
+        palette: &Palette,
+    ) -> Result<Self, utate::MutateError> {
+        let simulate = ComputePipeline::<ParticleSimulatePipeline>::new(device)?;
+        let mut particles = buffer::MappedAllocation::new(count as usize, device)?;
+        particles.set_name(device, "particles");
+        // All dead.
+        particles.as_mut_slice().fill([0.0; PARTICLE_WORDS]);
+        particles.flush(device)?;
+        let particles_idx = particles.register(device)?;
+        let spawned = buffer::MappedAllocation::new(1, device)?;
+        spawned.set_name(device, "particles spawned");
+        let spawned_idx = spawned.register(device)?;
+        let palette = PaletteLut::new(device, palette)?;
+        let (pipeline_layout, pipeline) = Self::pipeline(device, format, samples)?;
+
+        Ok(Self {
+            simulate,
+            pipeline_layout,
+            pipeline,
+            particles,
+            particles_idx,
+            spawned,
+            spawned_idx,
+            count,
+            bins,
+            frame: 0,
+            palette,
+            modulation: Modulation::spread(bins),
+            rate: 4000.0,
+            speed: 0.6,
+            lifetime: Duration::from_secs(2),
+            size: 0.006,
+            floor_db: -90.0,
+            ceiling_db: 0.0,
+        })
+    }
+
+    fn pipeline(
+        device: &Device,
+        format: vk::Format,
+        samples: vk::SampleCountFlags,
+    ) -> Result<(vk::PipelineLayout, vk::Pipeline), VulkanError> {
+        let vert = super::shader_module(device, "particles/vertex")?;
+        let frag = super::shader_module(device, "particles/fragment")?;
+
+        let stages = [
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::VERTEX)
+                .module(vert)
+                .name(c"main"),
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::FRAGMENT)
+                .module(frag)
+                .name(c"main"),
+        ];
+
+        let push_range = vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::VERTEX,
+            offset: 0,
+            size: std::mem::size_of::<[u32; 4]>() as u32,
+        };
+        let layout_ci = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(device.descriptors.layout())
+            .push_constant_ranges(std::slice::from_ref(&push_range));
+        let pipeline_layout = unsafe { device.as_raw().create_pipeline_layout(&layout_ci, None)? };
+
+        let vertex_input = vk::PipelineVertexInputStateCreateInfo::default();
+        let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::default()
+            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+        let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
+        let rasterizer = vk::PipelineRasterizationStateCreateInfo::default()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .line_width(1.0);
+        let multisampling =
+            vk::PipelineMultisampleStateCreateInfo::default().rasterization_samples(samples);
+        // Dots add up where they overlap.
+        let blend_attachment = vk::PipelineColorBlendAttachmentState::default()
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::ONE)
+            .dst_color_blend_factor(vk::BlendFactor::ONE)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ONE)
+            .dst_alpha_blend_factor(vk::BlendFactor::ONE)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .color_write_mask(vk::ColorComponentFlags::RGBA);
+        let color_blend = vk::PipelineColorBlendStateCreateInfo::default()
+            .attachments(std::slice::from_ref(&blend_attachment));
+        let color_formats = [format];
+        let mut rendering =
+            vk::PipelineRenderingCreateInfo::default().color_attachment_formats(&color_formats);
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state =
+            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+
+        let pipeline_ci = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input)
+            .input_assembly_state(&input_assembly)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterizer)
+            .multisample_state(&multisampling)
+            .color_blend_state(&color_blend)
+            .dynamic_state(&dynamic_state)
+            .layout(pipeline_layout)
+            .push_next(&mut rendering);
+        let pipeline = unsafe {
+            device
+                .as_raw()
+                .create_graphics_pipelines(device.pipeline_cache(), &[pipeline_ci], None)
+                .map_err(|e| e.1)?[0]
+        };
+
+        unsafe {
+            device.as_raw().destroy_shader_module(vert, None);
+            device.as_raw().destroy_shader_module(frag, None);
+        }
+        Ok((pipeline_layout, pipeline))
+    }
+
+    pub fn bins(&self) -> u32 {
+        self.bins
+    }
+
+    /// Color by `palette` from the next frame on.  The old table is queued on `deletions` behind
+    /// the frames already recorded with it.
+    pub fn set_palette(
+        &mut self,
+        device: &Device,
+        palette: &Palette,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        self.palette.replace(device, palette, deletions, frames)
+    }
+
+    /// Advance the particles by `dt`.  `bands` is the filter bank's output, such as
+    /// [`GpuSpectrogram::output_buffer`](utate::dsp::compute::GpuSpectrogram::output_buffer) and
+    /// its index, last written by a compute shader on this queue.  Record before drawing.
+    pub fn simulate(
+        &mut self,
+        device: &Device,
+        cb: vk::CommandBuffer,
+        bands: (vk::Buffer, SsboIdx),
+        dt: Duration,
+    ) {
+        let raw = device.as_raw();
+        let (bands_buffer, bands_idx) = bands;
+        let barrier = |buffer, src_access, dst_access| vk::BufferMemoryBarrier {
+            src_access_mask: src_access,
+            dst_access_mask: dst_access,
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            buffer,
+            offset: 0,
+            size: vk::WHOLE_SIZE,
+            ..Default::default()
+        };
+        let rw = vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE;
+
+        // The last pass may still be claiming spawns.
+        unsafe {
+            raw.cmd_pipeline_barrier(
+                cb,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[barrier(
+                    self.spawned.buffer,
+                    rw,
+                    vk::AccessFlags::TRANSFER_WRITE,
+                )],
+                &[],
+            );
+            raw.cmd_fill_buffer(cb, self.spawned.buffer, 0, vk::WHOLE_SIZE, 0);
+        }
+        let before = [
+            barrier(self.spawned.buffer, vk::AccessFlags::TRANSFER_WRITE, rw),
+            barrier(
+                bands_buffer,
+                vk::AccessFlags::SHADER_WRITE,
+                vk::AccessFlags::SHADER_READ,
+            ),
+            // The last frame's draw may still be reading the particles.
+            barrier(self.particles.buffer, rw, rw),
+        ];
+        unsafe {
+            raw.cmd_pipeline_barrier(
+                cb,
+                vk::PipelineStageFlags::TRANSFER
+                    | vk::PipelineStageFlags::COMPUTE_SHADER
+                    | vk::PipelineStageFlags::VERTEX_SHADER,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &before,
+                &[],
+            );
+        }
+
+        let m = self.modulation;
+        let push = ParticleSimulateConstants {
+            particles_idx: self.particles_idx.index(),
+            spawned_idx: self.spawned_idx.index(),
+            bands_idx,
+            count: self.count.into(),
+            frame: self.frame.into(),
+            dt: dt.as_secs_f32().into(),
+            floor_db: self.floor_db.into(),
+            ceiling_db: self.ceiling_db.into(),
+            emission: m.emission.packed().into(),
+            velocity: m.velocity.packed().into(),
+            color: m.color.packed().into(),
+            rate: self.rate.into(),
+            speed: self.speed.into(),
+            lifetime: self.lifetime.as_secs_f32().into(),
+        };
+        self.simulate.push(device, cb, &push);
+        self.simulate
+            .dispatch(device, cb, self.count.div_ceil(WORKGROUP), 1, 1);
+        self.frame = self.frame.wrapping_add(1);
+
+        let after = barrier(
+            self.particles.buffer,
+            vk::AccessFlags::SHADER_WRITE,
+            vk::AccessFlags::SHADER_READ,
+        );
+        unsafe {
+            raw.cmd_pipeline_barrier(
+                cb,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::VERTEX_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[after],
+                &[],
+            );
+        }
+    }
+
+    /// Record inside `graphics_present`, after [`simulate`](Self::simulate), drawing into `area`.
+    pub fn draw(
+        &self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        target: &MsaaTarget,
+        area: vk::Rect2D,
+    ) {
+        let raw = device.as_raw();
+        let extent = area.extent;
+        let color_attachment = target.color_attachment(acquired_image);
+        target.prepare(device, **cb);
+        let rendering_info = vk::RenderingInfo::default()
+            .render_area(area)
+            .layer_count(1)
+            .color_attachments(std::slice::from_ref(&color_attachment));
+        let viewport = super::viewport(area);
+
+        let aspect = extent.width as f32 / extent.height.max(1) as f32;
+        let push: [u32; 4] = [
+            self.particles_idx.index().raw(),
+            aspect.to_bits(),
+            self.size.to_bits(),
+            self.palette.index(),
+        ];
+        unsafe {
+            raw.cmd_begin_rendering(**cb, &rendering_info);
+            raw.cmd_bind_pipeline(**cb, vk::PipelineBindPoint::GRAPHICS, self.pipeline);
+            raw.cmd_bind_descriptor_sets(
+                **cb,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                0,
+                &[device.descriptors.set()],
+                &[],
+            );
+            raw.cmd_push_constants(
+                **cb,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::VERTEX,
+                0,
+                bytemuck::cast_slice(&push),
+            );
+            raw.cmd_set_viewport(**cb, 0, &[viewport]);
+            raw.cmd_set_scissor(**cb, 0, &[area]);
+            raw.cmd_draw(**cb, 6, self.count, 0, 0);
+            raw.cmd_end_rendering(**cb);
+        }
+    }
+
+    /// Caller must drain work that simulated or drew the particles first.
+    pub fn destroy(self, device: &Device) -> Result<(), utate::MutateError> {
+        device.descriptors.release(self.particles_idx);
+        device.descriptors.release(self.spawned_idx);
+        unsafe {
+            self.simulate.destroy(device);
+            device.as_raw().destroy_pipeline(self.pipeline, None);
+            device
+                .as_raw()
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+        }
+        self.particles.destroy(device)?;
+        self.spawned.destroy(device)?;
+        self.palette.destroy(device)?;
+        Ok(())
+    }
+}
@@ -4,10 +4,15 @@
 //! # Ring
 //!
 //! Dump the raw audio ring buffer onto the screen
+//!
+//! Under load the ring can render at a [scale](RawRingDraw::set_scale) of the frame's size.  The
+//! smaller output is copied into an image of its own and stretched over the frame with a blit.
 
 use ash::vk;
 use mutate_lib::{self as utate, prelude::*};
-use utate::vulkan::resource::{buffer, image};
+use utate::gpu::device::descriptors::Handle;
+use utate::gpu::resource::{buffer, image};
+use utate::graph::DeletionQueue;
 
 #[compute_pipeline(
     compute = stage!("ring/compute", Compute, c"main"),
@@ -23,57 +28,270 @@ use utate::vulkan::resource::{buffer, image};
 )]
 pub struct RawRingPipeline;
 
+/// Where the newest audio is in the device ring.  Read from the consumer before recording.
+#[derive(Clone, Copy, Debug)]
+pub struct RingPosition {
+    /// Base address of each channel.  See
+    /// [`Consumer::channels`](utate::audio::import::Consumer::channels).
+    pub channels: [vk::DeviceAddress; 2],
+    pub capacity: u32,
+    /// One past the newest sample.
+    pub write_index: u32,
+    pub rate: Option<u32>,
+}
+
+impl RingPosition {
+    /// Where `consumer` last wrote.
+    pub fn of(consumer: &utate::audio::import::Consumer<2>) -> Result<Self, utate::MutateError> {
+        Ok(Self {
+            channels: unsafe { consumer.channels()? },
+            capacity: consumer.capacity(),
+            write_index: consumer.write_index()?,
+            rate: consumer.sample_rate(),
+        })
+    }
+}
+
 pub struct RawRingDraw {
     pipeline: ComputePipeline<RawRingPipeline>,
     counter: u32,
 
     output_buffer: Option<buffer::MappedAllocation<rgb::Rgba<u8>>>,
-    output_idx: SsboIdx,
+    output_idx: Option<Handle<SsboIdx>>,
+    /// Format of the frames drawn into, which the scaled image copies byte for byte.
+    format: vk::Format,
+    /// Provisioned size.
+    size: vk::Extent2D,
+    scale: f32,
+    /// Holds the output while it is stretched.  `None` at full scale.
+    scaled: Option<image::Image>,
 }
 
 impl RawRingDraw {
-    pub fn new(device: &Device) -> Self {
-        Self {
-            pipeline: ComputePipeline::<RawRingPipeline>::new(device).unwrap(),
+    /// Draws into images of `format`.
+    pub fn new(device: &Device, format: vk::Format) -> Result<Self, VulkanError> {
+        Ok(Self {
+            pipeline: ComputePipeline::<RawRingPipeline>::new(device)?,
             counter: 0,
             output_buffer: None,
-            output_idx: SsboIdx::INVALID,
+            output_idx: None,
+            format,
+            size: vk::Extent2D::default(),
+            scale: 1.0,
+            scaled: None,
+        })
+    }
+
+    /// Render at `scale` of the frame's size from the next frame on, clamped to a quarter through
+    /// one.  A replaced image is queued on `deletions` behind the frames already recorded with it.
+    pub fn set_scale(
+        &mut self,
+        device: &Device,
+        scale: f32,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        let scale = scale.clamp(0.25, 1.0);
+        if scale == self.scale {
+            return Ok(());
+        }
+        self.scale = scale;
+        self.provision_scaled(device, deletions, frames)
+    }
+
+    /// The size the ring renders at for frames of `extent`.
+    fn scaled_extent(&self, extent: vk::Extent2D) -> vk::Extent2D {
+        let scale = |v: u32| ((v as f32 * self.scale) as u32).clamp(1, v.max(1));
+        vk::Extent2D {
+            width: scale(extent.width),
+            height: scale(extent.height),
+        }
+    }
+
+    fn provision_scaled(
+        &mut self,
+        device: &Device,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        if let Some(existing) = self.scaled.take() {
+            deletions.defer(frames, move |device| {
+                if let Err(e) = existing.destroy(device) {
+                    eprintln!("ring: scaled image destruction failed {:?}", e);
+                }
+            });
         }
+        let extent = self.scaled_extent(self.size);
+        if extent == self.size {
+            return Ok(());
+        }
+        let usage = vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::TRANSFER_SRC;
+        let scaled = image::Image::new(device, extent, self.format, usage)?;
+        scaled.set_name(device, "ring scaled");
+        self.scaled = Some(scaled);
+        Ok(())
     }
 
+    /// Provision the output for `size`.  A replaced output is queued on `deletions` behind the
+    /// frames already recorded with it.
     pub fn provision(
         &mut self,
         device: &Device,
         size: vk::Extent2D,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
     ) -> Result<(), utate::MutateError> {
         if let Some(existing) = self.output_buffer.take() {
-            unsafe {
-                existing.destroy(device)?;
-                device.descriptors.unbind_ssbo(self.output_idx);
-            }
-            self.output_idx = SsboIdx::INVALID;
+            let output_idx = self.output_idx.take();
+            deletions.defer(frames, move |device| {
+                if let Some(output_idx) = output_idx {
+                    device.descriptors.release(output_idx);
+                }
+                if let Err(e) = existing.destroy(device) {
+                    eprintln!("ring: output destruction failed {:?}", e);
+                }
+            });
         }
 
         let output_buffer =
             buffer::MappedAllocation::new((size.width * size.height) as usize, device)?;
+        output_buffer.set_name(device, "ring output");
 
-        self.output_idx = output_buffer.bound(device);
+        self.output_idx = Some(output_buffer.register(device)?);
         self.output_buffer = Some(output_buffer);
+        self.size = size;
+
+        self.provision_scaled(device, deletions, frames)
+    }
+
+    /// Shaders to watch for hot reload.
+    pub fn shaders(&self) -> [&'static str; 1] {
+        [self.pipeline.shader_name()]
+    }
 
+    /// Rebuild the pipeline if `shader` is one of ours.  The replaced pipeline is queued on
+    /// `deletions` behind the frames already recorded with it.
+    pub fn reload(
+        &mut self,
+        device: &Device,
+        shader: &str,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        if shader != self.pipeline.shader_name() {
+            return Ok(());
+        }
+        let replaced = self.pipeline.rebuild(device)?;
+        deletions.defer(frames, move |device| unsafe {
+            ComputePipeline::<RawRingPipeline>::destroy_replaced(device, replaced)
+        });
         Ok(())
     }
 
+    /// Draw into `area` of `acquired_image`, which must be in `TRANSFER_DST_OPTIMAL`.  Only the
+    /// whole image is drawn at a reduced scale.
     pub fn draw(
         &mut self,
         device: &Device,
         cb: &RecordingBuffer<Graphics, OneTime>,
         acquired_image: &AcquiredImage,
+        area: vk::Rect2D,
+        ring: &RingPosition,
+    ) {
+        let [left_channel, right_channel] = ring.channels;
+        let full = area.extent;
+        let whole = area.offset == vk::Offset2D::default() && full == acquired_image.extent;
+        let extent = match &self.scaled {
+            Some(scaled) if whole && full == self.size => scaled.extent,
+            _ => full,
+        };
+        self.dispatch(
+            device,
+            cb,
+            extent,
+            left_channel,
+            right_channel,
+            ring.capacity,
+        );
+
+        self.output_buffer
+            .as_ref()
+            .unwrap()
+            .barrier_compute_post(&cb, device);
+
+        let mut region = buffer::buffer_image_copy_full(extent);
+        let output = self.output_buffer.as_ref().unwrap().buffer;
+        let raw = device.as_raw();
+        let Some(scaled) = self.scaled.as_ref().filter(|_| extent != full) else {
+            region.image_offset = vk::Offset3D {
+                x: area.offset.x,
+                y: area.offset.y,
+                z: 0,
+            };
+            unsafe {
+                raw.cmd_copy_buffer_to_image(
+                    **cb,
+                    output,
+                    acquired_image.image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[region],
+                );
+            }
+            return;
+        };
+        // NOTE blitting between 8-bit BGRA and RGBA formats is required of every device.
+        scaled.transition_to_transfer_dst(**cb, device);
+        let corner = |e: vk::Extent2D| vk::Offset3D {
+            x: e.width as i32,
+            y: e.height as i32,
+            z: 1,
+        };
+        let blit = vk::ImageBlit {
+            src_subresource: region.image_subresource,
+            src_offsets: [vk::Offset3D::default(), corner(extent)],
+            dst_subresource: region.image_subresource,
+            dst_offsets: [vk::Offset3D::default(), corner(full)],
+        };
+        unsafe {
+            raw.cmd_copy_buffer_to_image(
+                **cb,
+                output,
+                scaled.image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[region],
+            );
+            scaled.transition_layout(
+                **cb,
+                image::range(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                device,
+            );
+            raw.cmd_blit_image(
+                **cb,
+                scaled.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                acquired_image.image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[blit],
+                vk::Filter::LINEAR,
+            );
+        }
+    }
+
+    /// Fill the output without copying it anywhere, for nodes that read it from [`output`].  Those
+    /// nodes record their own barrier after the compute write.
+    ///
+    /// [`output`]: Self::output
+    pub fn dispatch(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        extent: vk::Extent2D,
         left_channel: vk::DeviceAddress,
         right_channel: vk::DeviceAddress,
         capacity: u32,
     ) {
-        let extent = acquired_image.extent;
-
         // XXX argument order (reverse cb & device)
         self.output_buffer
             .as_ref()
@@ -87,7 +305,7 @@ impl RawRingDraw {
             counter: self.counter.into(),
             window_width: (extent.width as f32).into(),
             window_height: (extent.height as f32).into(),
-            output_idx: self.output_idx,
+            output_idx: self.output_idx.unwrap().index(),
         };
         // XXX allow pushing to wrapped buffers
         self.pipeline.push(device, **cb, &push);
@@ -104,22 +322,12 @@ impl RawRingDraw {
         let dispatch_y = (extent.height + wg_y - 1) / wg_y;
         self.pipeline
             .dispatch(device, **cb, dispatch_x, dispatch_y, 1);
+    }
 
-        self.output_buffer
-            .as_ref()
-            .unwrap()
-            .barrier_compute_post(&cb, device);
-
-        let region = buffer::buffer_image_copy_full(extent);
-        unsafe {
-            device.as_raw().cmd_copy_buffer_to_image(
-                **cb,
-                self.output_buffer.as_ref().unwrap().buffer,
-                acquired_image.image,
-                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                &[region],
-            );
-        }
+    /// The output buffer and its storage buffer handle, once provisioned.  Pixels are packed BGRA,
+    /// row by row at the provisioned size.
+    pub fn output(&self) -> Option<(vk::Buffer, Handle<SsboIdx>)> {
+        Some((self.output_buffer.as_ref()?.buffer, self.output_idx?))
     }
 
     pub fn destroy(self, device: &Device) -> Result<(), utate::MutateError> {
@@ -127,8 +335,13 @@ impl RawRingDraw {
             self.pipeline.destroy(device);
             if let Some(allocated) = self.output_buffer {
                 allocated.destroy(&device)?;
-                device.descriptors.unbind_ssbo(self.output_idx);
             }
+            if let Some(output_idx) = self.output_idx {
+                device.descriptors.release(output_idx);
+            }
+        }
+        if let Some(scaled) = self.scaled {
+            scaled.destroy(device)?;
         }
         Ok(())
     }
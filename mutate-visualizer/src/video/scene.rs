@@ -0,0 +1,744 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Scenes
+//!
+//! A scene is one visualization, the nodes that draw a whole frame from the audio.  Every scene's
+//! nodes are built and provisioned together in [`SceneNodes`], pipelines included, so switching
+//! scenes never waits on the driver.  [`Scenes`] tracks which one is showing, rotates through them on a timer
+//! when asked to, and times the crossfade from one to the next.  While a fade lasts, both scenes
+//! draw every frame and the [crossfade](super::crossfade) blends the outgoing one over the other.
+//!
+//! Scenes are chosen with `--scene` or the `[scenes]` table of the [config file](crate::config):
+//!
+//! ```toml
+//! [scenes]
+//! start = "vectorscope"
+//! # Seconds each scene shows.  Leave out to stay on one scene.
+//! rotate = 30
+//! # Seconds each crossfade takes.  Zero cuts.
+//! fade = 1.5
+//! # Shown by the image scene, a binary PPM such as a screenshot.
+//! image = "cover.ppm"
+//! # Colors the waterfall and particles.  A colormap name or a table of stops.
+//! palette = "magma"
+//! ```
+//!
+//! Palettes are written as described with [`mutate_lib::color`].  A new palette in an edited config
+//! file recolors open windows from the next frame.
+
+// NEXT a scene becomes a subgraph of a preset once render nodes move into the graph.  Switching
+// then enables one subgraph's nodes and disables the other's.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use ash::vk;
+use mutate_lib::{self as utate, prelude::*};
+use utate::color::Palette;
+use utate::graph::throttle::ThrottleGovernor;
+use utate::graph::DeletionQueue;
+use utate::settings::DspSettings;
+
+use super::crossfade::CrossfadeNode;
+use super::feedback::FeedbackNode;
+use super::particles::ParticleNode;
+use super::ring::{RawRingDraw, RingPosition};
+use super::scope::ScopeNode;
+use super::spectrum::Spectrum;
+use super::texture::{Picture, TextureNode};
+use super::vectorscope::VectorscopeNode;
+use super::waterfall::{self, WaterfallNode};
+
+/// Fade used unless configured.
+const FADE: Duration = Duration::from_millis(1000);
+/// Frames of history the waterfall shows.
+const WATERFALL_ROWS: u32 = 600;
+/// Particles in the fountain, alive or waiting to be born.
+const PARTICLES: u32 = 16384;
+/// Longest step the particles take, so that a stalled frame does not launch a burst.
+const MAX_STEP: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scene {
+    /// The spectrum ring.
+    #[default]
+    Ring,
+    /// The ring over echo trails of previous frames.
+    Feedback,
+    /// An oscilloscope of the raw audio.
+    Scope,
+    /// Left against right.
+    Vectorscope,
+    /// The filter bank's bins scrolling down.
+    Waterfall,
+    /// A fountain whose births, speed, and color follow the filter bank's bands.
+    Particles,
+    /// The picture from `scenes.image`, or a checkerboard without one.
+    Image,
+}
+
+impl Scene {
+    /// Every scene, in rotation order.
+    pub const ALL: [Scene; 7] = [
+        Scene::Ring,
+        Scene::Feedback,
+        Scene::Scope,
+        Scene::Vectorscope,
+        Scene::Waterfall,
+        Scene::Particles,
+        Scene::Image,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scene::Ring => "ring",
+            Scene::Feedback => "feedback",
+            Scene::Scope => "scope",
+            Scene::Vectorscope => "vectorscope",
+            Scene::Waterfall => "waterfall",
+            Scene::Particles => "particles",
+            Scene::Image => "image",
+        }
+    }
+
+    /// Whether the scene draws from the filter bank on the device.
+    pub fn is_spectral(self) -> bool {
+        matches!(self, Scene::Waterfall | Scene::Particles)
+    }
+
+    /// The scene after this one in rotation.
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|s| *s == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for Scene {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Scene {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scene| scene.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|s| s.name()).collect();
+                format!("unknown scene `{s}`, expected one of {}", names.join(", "))
+            })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SceneSettings {
+    /// Shown first.
+    pub start: Scene,
+    /// How long each scene shows before the next.  `None` stays on one scene.
+    pub rotate: Option<Duration>,
+    /// How long switching takes.  Zero cuts.
+    pub fade: Duration,
+    /// What the image scene shows.
+    pub image: Option<PathBuf>,
+    /// How scenes that color by level color.
+    pub palette: Palette,
+}
+
+impl Default for SceneSettings {
+    fn default() -> Self {
+        Self {
+            start: Scene::default(),
+            rotate: None,
+            fade: FADE,
+            image: None,
+            palette: Palette::default(),
+        }
+    }
+}
+
+/// The scene showing, the one fading out, and when to rotate.
+pub struct Scenes {
+    /// Shown first, and for the first section.
+    start: Scene,
+    current: Scene,
+    /// When `current` was switched to.
+    since: Instant,
+    /// Fading out behind `current`.
+    leaving: Option<Scene>,
+    rotate: Option<Duration>,
+    fade: Duration,
+    /// Label of the last section followed.
+    section: Option<u32>,
+}
+
+impl Scenes {
+    /// Show the first scene from `now`.
+    pub fn new(settings: &SceneSettings, now: Instant) -> Self {
+        Self {
+            start: settings.start,
+            current: settings.start,
+            since: now,
+            leaving: None,
+            rotate: settings.rotate,
+            fade: settings.fade,
+            section: None,
+        }
+    }
+
+    pub fn current(&self) -> Scene {
+        self.current
+    }
+
+    /// The scene fading out and how much of it still shows, from one down to zero.
+    pub fn leaving(&self, now: Instant) -> Option<(Scene, f32)> {
+        let scene = self.leaving?;
+        let elapsed = now.saturating_duration_since(self.since);
+        let opacity = 1.0 - elapsed.as_secs_f32() / self.fade.as_secs_f32();
+        (opacity > 0.0).then_some((scene, opacity))
+    }
+
+    /// Start fading to `scene`.  Returns whether it was not already showing.  A fade already under
+    /// way is cut short.
+    pub fn switch(&mut self, scene: Scene, now: Instant) -> bool {
+        if scene == self.current {
+            return false;
+        }
+        self.leaving = (!self.fade.is_zero()).then_some(self.current);
+        self.current = scene;
+        self.since = now;
+        true
+    }
+
+    /// Switch to `scene`, or back to the ring when `scene` is already showing.  Returns the scene
+    /// switched to.
+    pub fn toggle(&mut self, scene: Scene, now: Instant) -> Scene {
+        let scene = match scene == self.current {
+            true => Scene::Ring,
+            false => scene,
+        };
+        self.switch(scene, now);
+        self.current
+    }
+
+    /// Switch to the next scene in rotation.  Returns the scene switched to.
+    pub fn next(&mut self, now: Instant) -> Scene {
+        self.switch(self.current.next(), now);
+        self.current
+    }
+
+    /// Follow the song into the section labelled `label`.  Each label has its own scene, counted in
+    /// rotation from the first, so that a section that repeats shows what it showed before.
+    /// Returns the scene switched to when the section changed.
+    pub fn section(&mut self, label: u32, now: Instant) -> Option<Scene> {
+        if self.section.replace(label) == Some(label) {
+            return None;
+        }
+        let start = Scene::ALL
+            .iter()
+            .position(|s| *s == self.start)
+            .unwrap_or(0);
+        let scene = Scene::ALL[(start + label as usize) % Scene::ALL.len()];
+        self.switch(scene, now).then_some(scene)
+    }
+
+    /// End a finished fade and rotate when the scene has shown long enough.  Returns the scene
+    /// rotated to, if any.  Call before drawing each frame.
+    pub fn update(&mut self, now: Instant) -> Option<Scene> {
+        if self.leaving(now).is_none() {
+            self.leaving = None;
+        }
+        let rotate = self.rotate?;
+        (now.saturating_duration_since(self.since) >= rotate).then(|| self.next(now))
+    }
+
+    /// [Update](Self::update) for a frame drawn at `now`, entering any scene rotated to in `nodes`.
+    /// Returns the scene to draw and the one fading out, as [`SceneNodes::draw`] takes them.
+    pub fn frame(&mut self, now: Instant, nodes: &mut SceneNodes) -> (Scene, Option<(Scene, f32)>) {
+        if let Some(scene) = self.update(now) {
+            nodes.enter(scene);
+        }
+        (self.current, self.leaving(now))
+    }
+}
+
+/// What the scenes are built with beyond the image they draw into.
+#[derive(Clone, Debug)]
+pub struct SceneOptions {
+    /// Samples per pixel for nodes that draw through an [`MsaaTarget`].
+    pub msaa: u32,
+    /// Bins of the filter bank.
+    pub dsp: DspSettings,
+    /// Binary PPM the image scene shows.  See [`Picture::read`].
+    pub image: Option<PathBuf>,
+    /// Colors the waterfall and particles.
+    pub palette: Palette,
+}
+
+impl Default for SceneOptions {
+    fn default() -> Self {
+        Self {
+            msaa: 4,
+            dsp: DspSettings::default(),
+            image: None,
+            palette: Palette::default(),
+        }
+    }
+}
+
+/// The nodes that draw every scene, and the crossfade between them.
+pub struct SceneNodes {
+    pub ring: RawRingDraw,
+    pub crossfade: CrossfadeNode,
+    pub feedback: FeedbackNode,
+    pub scope: ScopeNode,
+    pub vectorscope: VectorscopeNode,
+    /// Feeds the waterfall and particles.  The host pushes what plays into its
+    /// [inlet](Spectrum::inlet).
+    pub spectrum: Spectrum,
+    /// Built once the bank knows its width, and again when a new rate changes it.
+    waterfall: Option<WaterfallNode>,
+    /// Built like the waterfall.
+    particles: Option<ParticleNode>,
+    /// What the waterfall and particles are built with.
+    palette: Palette,
+    /// Read up front so that a missing file fails before any window opens.
+    picture: Picture,
+    /// Built when the image scene first shows.
+    texture: Option<TextureNode>,
+    /// Whether the filter bank runs this frame.  See [`throttle`](Self::throttle).
+    analyze: bool,
+    /// When the last frame was updated, and how long since the one before.
+    updated: Option<Instant>,
+    dt: Duration,
+    /// `None` until provisioned.
+    msaa: Option<MsaaTarget>,
+    format: vk::Format,
+    samples: u32,
+}
+
+impl SceneNodes {
+    /// Nodes that draw into images of `format` created with `usage`.  Provision before drawing.
+    pub fn new(
+        device: &Device,
+        format: vk::Format,
+        usage: vk::ImageUsageFlags,
+        options: &SceneOptions,
+    ) -> Result<Self, utate::MutateError> {
+        let picture = match &options.image {
+            Some(path) => Picture::read(path)?,
+            None => Picture::checker(),
+        };
+        Ok(Self {
+            ring: RawRingDraw::new(device, format)?,
+            crossfade: CrossfadeNode::new(device, format, usage)?,
+            feedback: FeedbackNode::new(device)?,
+            scope: ScopeNode::new(device)?,
+            vectorscope: VectorscopeNode::new(device)?,
+            spectrum: Spectrum::new(&options.dsp),
+            waterfall: None,
+            particles: None,
+            palette: options.palette.clone(),
+            picture,
+            texture: None,
+            analyze: true,
+            updated: None,
+            dt: Duration::ZERO,
+            msaa: None,
+            format,
+            samples: options.msaa,
+        })
+    }
+
+    /// Provision every node for `size`.  Replaced outputs are queued on `deletions` behind the
+    /// frames already recorded with them.
+    pub fn provision(
+        &mut self,
+        device: &Device,
+        size: vk::Extent2D,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        self.ring.provision(device, size, deletions, frames)?;
+        self.crossfade.provision(device, size, deletions, frames)?;
+        self.feedback.provision(device, size, deletions, frames)?;
+        self.scope.provision(device, size, deletions, frames)?;
+        self.vectorscope
+            .provision(device, size, deletions, frames)?;
+        if let Some(existing) = self.msaa.take() {
+            deletions.defer(frames, move |device| {
+                if let Err(e) = existing.destroy(device) {
+                    eprintln!("application: msaa target destruction failed {:?}", e);
+                }
+            });
+        }
+        self.msaa = Some(MsaaTarget::new(device, size, self.format, self.samples)?);
+        Ok(())
+    }
+
+    /// Read what arrived since the last frame for the scenes drawn at `now`, as [`Scenes::frame`]
+    /// returns them.  Call before [`draw`](Self::draw).  Replaced nodes are queued on `deletions`
+    /// behind the frames already recorded with them.
+    pub fn update(
+        &mut self,
+        device: &Device,
+        now: Instant,
+        current: Scene,
+        leaving: Option<(Scene, f32)>,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        let showing: Vec<Scene> = std::iter::once(current)
+            .chain(leaving.map(|(scene, _)| scene))
+            .collect();
+        self.update_showing(device, now, &showing, deletions, frames)
+    }
+
+    /// [Update](Self::update) for the scenes of `tiles`, before [`draw_tiles`](Self::draw_tiles).
+    pub fn update_tiles(
+        &mut self,
+        device: &Device,
+        now: Instant,
+        tiles: &[(Scene, vk::Rect2D)],
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        let showing: Vec<Scene> = tiles.iter().map(|(scene, _)| *scene).collect();
+        self.update_showing(device, now, &showing, deletions, frames)
+    }
+
+    fn update_showing(
+        &mut self,
+        device: &Device,
+        now: Instant,
+        showing: &[Scene],
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        let since = self.updated.map(|at| now.saturating_duration_since(at));
+        self.dt = since.unwrap_or_default().min(MAX_STEP);
+        self.updated = Some(now);
+        let showing = |scene| showing.contains(&scene);
+        if let (true, None, Some(target)) = (showing(Scene::Image), &self.texture, &self.msaa) {
+            let samples = target.samples();
+            self.texture = Some(TextureNode::new(
+                device,
+                self.format,
+                samples,
+                &self.picture,
+            )?);
+        }
+        if !Scene::ALL
+            .into_iter()
+            .any(|s| s.is_spectral() && showing(s))
+        {
+            self.spectrum.skip();
+            return Ok(());
+        }
+        // Audio skipped by the bank stays in its inlet for the next frame that runs it.
+        if self.analyze || self.spectrum.bank().is_none() {
+            self.spectrum.update(device, deletions, frames)?;
+        }
+        let (Some(bank), Some(target)) = (self.spectrum.bank(), &self.msaa) else {
+            return Ok(());
+        };
+        let width = bank.width() as u32;
+        let samples = target.samples();
+        let palette = &self.palette;
+        let bins = width.min(waterfall::MAX_BINS);
+        if showing(Scene::Waterfall) && self.waterfall.as_ref().is_none_or(|w| w.bins() != bins) {
+            let next =
+                WaterfallNode::new(device, self.format, samples, bins, WATERFALL_ROWS, palette)?;
+            if let Some(existing) = self.waterfall.replace(next) {
+                deletions.defer(frames, move |device| {
+                    if let Err(e) = existing.destroy(device) {
+                        eprintln!("application: waterfall destruction failed {:?}", e);
+                    }
+                });
+            }
+        }
+        if showing(Scene::Particles) && self.particles.as_ref().is_none_or(|p| p.bins() != width) {
+            let next = ParticleNode::new(device, self.format, samples, PARTICLES, width, palette)?;
+            if let Some(existing) = self.particles.replace(next) {
+                deletions.defer(frames, move |device| {
+                    if let Err(e) = existing.destroy(device) {
+                        eprintln!("application: particles destruction failed {:?}", e);
+                    }
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Shed work as `governor` decides for the next frame: the filter bank runs at the rate it allows
+    /// and the ring renders at its resolution scale.  Call before [`update`](Self::update).
+    pub fn throttle(
+        &mut self,
+        device: &Device,
+        governor: &ThrottleGovernor,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        self.analyze = governor.run_analysis();
+        let scale = governor.resolution_scale();
+        self.ring.set_scale(device, scale, deletions, frames)
+    }
+
+    /// Color by `palette` from the next frame on.  Old tables are queued on `deletions` behind the
+    /// frames already recorded with them.
+    pub fn set_palette(
+        &mut self,
+        device: &Device,
+        palette: &Palette,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        if *palette == self.palette {
+            return Ok(());
+        }
+        self.palette = palette.clone();
+        if let Some(waterfall) = &mut self.waterfall {
+            waterfall.set_palette(device, palette, deletions, frames)?;
+        }
+        if let Some(particles) = &mut self.particles {
+            particles.set_palette(device, palette, deletions, frames)?;
+        }
+        Ok(())
+    }
+
+    /// Draw `current`, under `leaving` fading out if a fade is under way and frames can be
+    /// captured.  The image must be in `TRANSFER_DST_OPTIMAL` and is left that way.
+    pub fn draw(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        ring: &RingPosition,
+        current: Scene,
+        leaving: Option<(Scene, f32)>,
+    ) {
+        let leaving = leaving.filter(|_| self.crossfade.is_ready());
+        // One dispatch serves every scene drawing the bank this frame.  Frames that skip it fill the
+        // output from the last two.
+        let spectral = current.is_spectral() || leaving.is_some_and(|(s, _)| s.is_spectral());
+        if spectral {
+            self.record_spectrum(device, cb);
+        }
+        let area = super::full(acquired_image);
+        // The outgoing scene draws first and is laid back over the incoming one.
+        if let Some((scene, _)) = leaving {
+            self.draw_scene(device, cb, acquired_image, ring, (scene, area), true);
+            self.crossfade.capture(device, cb, acquired_image);
+        }
+        self.draw_scene(device, cb, acquired_image, ring, (current, area), true);
+        if let Some((_, opacity)) = leaving {
+            self.crossfade.draw(device, cb, acquired_image, opacity);
+        }
+    }
+
+    /// Draw each scene of `tiles` into its area, over black.  Tiles cut from one scene to the next
+    /// rather than fading.  Scenes that animate advance once however many tiles show them.
+    pub fn draw_tiles(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        ring: &RingPosition,
+        tiles: &[(Scene, vk::Rect2D)],
+    ) {
+        // Gaps between tiles stay black.
+        clear(device, cb, acquired_image, super::full(acquired_image));
+        if tiles.iter().any(|(s, _)| s.is_spectral()) {
+            self.record_spectrum(device, cb);
+        }
+        for (i, &tile) in tiles.iter().enumerate() {
+            let advance = !tiles[..i].iter().any(|(s, _)| *s == tile.0);
+            self.draw_scene(device, cb, acquired_image, ring, tile, advance);
+        }
+    }
+
+    fn record_spectrum(&mut self, device: &Device, cb: &RecordingBuffer<Graphics, OneTime>) {
+        if self.analyze {
+            self.spectrum.record(device, **cb);
+        } else {
+            self.spectrum.fill(device, **cb);
+        }
+    }
+
+    /// Draw `scene` into `area`.  With `advance`, scenes with history or motion step to this frame
+    /// first.
+    fn draw_scene(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        ring: &RingPosition,
+        (scene, area): (Scene, vk::Rect2D),
+        advance: bool,
+    ) {
+        let [left, right] = ring.channels;
+        // NOTE the feedback trail is one image the size of the window, so a tile draws the plain
+        // ring instead.
+        let whole = area == super::full(acquired_image);
+        match (scene, self.ring.output()) {
+            (Scene::Scope, _) => self.scope.draw(device, cb, acquired_image, ring, area),
+            (Scene::Vectorscope, _) => {
+                self.vectorscope
+                    .draw(device, cb, acquired_image, ring, area)
+            }
+            (Scene::Waterfall, _) => self.draw_waterfall(device, cb, acquired_image, area, advance),
+            (Scene::Particles, _) => self.draw_particles(device, cb, acquired_image, area, advance),
+            (Scene::Image, _) => self.draw_image(device, cb, acquired_image, area),
+            (Scene::Feedback, Some(spectrum)) if whole => {
+                let extent = acquired_image.extent;
+                self.ring
+                    .dispatch(device, cb, extent, left, right, ring.capacity);
+                self.feedback.draw(device, cb, acquired_image, spectrum);
+            }
+            _ => self.ring.draw(device, cb, acquired_image, area, ring),
+        }
+    }
+
+    /// Add a row of the bank's newest bins and draw the history.  Black until audio arrives.
+    fn draw_waterfall(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        area: vk::Rect2D,
+        advance: bool,
+    ) {
+        let (Some(bank), Some(waterfall), Some(target)) =
+            (self.spectrum.bank(), &mut self.waterfall, &self.msaa)
+        else {
+            return clear(device, cb, acquired_image, area);
+        };
+        if advance {
+            waterfall.record_row(device, **cb, bank.output_buffer(), 0);
+        }
+        super::as_attachment(device, **cb, acquired_image, || {
+            waterfall.draw(device, cb, acquired_image, target, area)
+        });
+    }
+
+    /// Advance the particles by the time since the last frame and draw them.  Black until audio
+    /// arrives.
+    fn draw_particles(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        area: vk::Rect2D,
+        advance: bool,
+    ) {
+        let (Some(bank), Some(particles), Some(target)) =
+            (self.spectrum.bank(), &mut self.particles, &self.msaa)
+        else {
+            return clear(device, cb, acquired_image, area);
+        };
+        if advance {
+            let bands = (bank.output_buffer(), bank.output_idx());
+            particles.simulate(device, **cb, bands, self.dt);
+        }
+        super::as_attachment(device, **cb, acquired_image, || {
+            particles.draw(device, cb, acquired_image, target, area)
+        });
+    }
+
+    /// Stretch the picture over `area`.
+    fn draw_image(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        area: vk::Rect2D,
+    ) {
+        let (Some(texture), Some(target)) = (&mut self.texture, &self.msaa) else {
+            return clear(device, cb, acquired_image, area);
+        };
+        super::as_attachment(device, **cb, acquired_image, || {
+            texture.draw(device, cb, acquired_image, target, area, 1.0)
+        });
+    }
+
+    /// Scenes that keep frames start over when shown again.
+    pub fn enter(&mut self, scene: Scene) {
+        match scene {
+            Scene::Feedback => self.feedback.reset(),
+            Scene::Vectorscope => self.vectorscope.reset(),
+            Scene::Ring | Scene::Scope | Scene::Waterfall | Scene::Particles | Scene::Image => {}
+        }
+    }
+
+    /// Forget what offscreen targets hold after abandoning a frame, which may have moved them.
+    pub fn reset(&mut self) {
+        self.crossfade.reset();
+        self.feedback.reset();
+        self.scope.reset();
+        self.vectorscope.reset();
+        if let Some(waterfall) = &mut self.waterfall {
+            waterfall.reset();
+        }
+    }
+
+    /// Caller must drain the frames that drew with the nodes first.
+    pub fn destroy(self, device: &Device) {
+        if let Err(e) = self.crossfade.destroy(device) {
+            eprintln!("application: crossfade destruction failed {:?}", e);
+        }
+        if let Err(e) = self.feedback.destroy(device) {
+            eprintln!("application: feedback destruction failed {:?}", e);
+        }
+        if let Err(e) = self.scope.destroy(device) {
+            eprintln!("application: scope destruction failed {:?}", e);
+        }
+        if let Err(e) = self.vectorscope.destroy(device) {
+            eprintln!("application: vectorscope destruction failed {:?}", e);
+        }
+        if let Err(e) = self.ring.destroy(device) {
+            eprintln!("application: ring destruction failed {:?}", e);
+        }
+        if let Err(e) = self.spectrum.destroy(device) {
+            eprintln!("application: spectrum destruction failed {:?}", e);
+        }
+        if let Some(Err(e)) = self.waterfall.map(|w| w.destroy(device)) {
+            eprintln!("application: waterfall destruction failed {:?}", e);
+        }
+        if let Some(Err(e)) = self.particles.map(|p| p.destroy(device)) {
+            eprintln!("application: particles destruction failed {:?}", e);
+        }
+        if let Some(Err(e)) = self.texture.map(|t| t.destroy(device)) {
+            eprintln!("application: texture destruction failed {:?}", e);
+        }
+        if let Some(Err(e)) = self.msaa.map(|t| t.destroy(device)) {
+            eprintln!("application: msaa target destruction failed {:?}", e);
+        }
+    }
+}
+
+/// Black out `acquired_image`, which is in `TRANSFER_DST_OPTIMAL`, for scenes with nothing to draw.
+/// A tile's `area` is already black, since [`SceneNodes::draw_tiles`] clears the image first.
+fn clear(
+    device: &Device,
+    cb: &RecordingBuffer<Graphics, OneTime>,
+    acquired_image: &AcquiredImage,
+    area: vk::Rect2D,
+) {
+    if area != super::full(acquired_image) {
+        return;
+    }
+    unsafe {
+        device.as_raw().cmd_clear_color_image(
+            **cb,
+            acquired_image.image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &vk::ClearColorValue::default(),
+            &[utate::gpu::resource::image::range()],
+        );
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Scope
+//!
+//! An oscilloscope of the most recent audio, read straight from the device ring.  A compute pass
+//! walks back from the newest sample to a rising zero crossing, the trigger, and gathers the window
+//! after it into a trace.  The trace is drawn as a line across the window.  Starting every frame on
+//! a crossing keeps a steady tone standing still instead of swimming across the screen.
+//!
+//! The channels are mixed to mono.  When no crossing is found, such as in silence or below the
+//! lowest frequency that fits the window, the newest window is drawn as it is.
+
+// NEXT a graph node once render nodes move into the graph.  The ring then arrives on an edge
+// instead of through the consumer.
+// MAYBE a filled shape.  Lines and triangles need separate pipelines.
+// MAYBE wide lines.  One pixel is thin on dense displays, and `wideLines` is not universal.
+
+use std::time::Duration;
+
+use ash::vk;
+use mutate_lib::{self as utate, prelude::*};
+use utate::gpu::device::descriptors::Handle;
+use utate::gpu::resource::buffer;
+use utate::graph::DeletionQueue;
+
+use super::ring::RingPosition;
+
+/// The target is copied to the swapchain, so it matches the swapchain's texel size.
+const FORMAT: vk::Format = vk::Format::B8G8R8A8_UNORM;
+/// Vertices in the trace.  Longer windows are sampled down to this.
+const POINTS: u32 = 1024;
+/// Rate assumed until the stream negotiates one.  The ring holds silence until then.
+const FALLBACK_RATE: u32 = 48_000;
+
+#[compute_pipeline(
+    compute = stage!("scope/trigger", Compute, c"main"),
+    push = push!(ScopeTriggerConstants {
+        pub left_channel: DeviceAddress,
+        pub right_channel: DeviceAddress,
+        pub capacity: UInt,
+        pub write_index: UInt,
+        pub window: UInt,
+        pub search: UInt,
+        pub points: UInt,
+        pub hysteresis: Float,
+        pub trace_idx: SsboIdx,
+    }),
+)]
+pub struct ScopeTriggerPipeline;
+
+pub struct ScopeNode {
+    trigger: ComputePipeline<ScopeTriggerPipeline>,
+    pipeline_layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+    /// How much audio is shown.
+    pub window: Duration,
+    /// Vertical scale.  One puts full scale at the window edges.
+    pub gain: f32,
+    /// Multiplies `gain`.  The graph [drives](super::drive) it.
+    pub level: f32,
+    /// How far the signal must rise past a crossing for it to trigger.
+    pub hysteresis: f32,
+
+    trace: buffer::MappedAllocation<f32>,
+    trace_idx: Handle<SsboIdx>,
+    target: Option<OffscreenTarget>,
+}
+
+impl ScopeNode {
+    pub fn new(device: &Device) -> Result<Self, utate::MutateError> {
+        let trigger = ComputePipeline::<ScopeTriggerPipeline>::new(device)?;
+        let mut trace = buffer::MappedAllocation::<f32>::new(POINTS as usize, device)?;
+        trace.set_name(device, "scope trace");
+        trace.as_mut_slice().fill(0.0);
+        trace.flush(device)?;
+        let trace_idx = trace.register(device)?;
+        let (pipeline_layout, pipeline) = Self::pipeline(device)?;
+        Ok(Self {
+            trigger,
+            pipeline_layout,
+            pipeline,
+            window: Duration::from_millis(20),
+            gain: 0.9,
+            level: 1.0,
+            hysteresis: 0.01,
+            trace,
+            trace_idx,
+            target: None,
+        })
+    }
+
+    fn pipeline(device: &Device) -> Result<(vk::PipelineLayout, vk::Pipeline), VulkanError> {
+        let vert = super::shader_module(device, "scope/vertex")?;
+        let frag = super::shader_module(device, "scope/fragment")?;
+
+        let stages = [
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::VERTEX)
+                .module(vert)
+                .name(c"main"),
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::FRAGMENT)
+                .module(frag)
+                .name(c"main"),
+        ];
+
+        let push_range = vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::VERTEX,
+            offset: 0,
+            size: std::mem::size_of::<[u32; 3]>() as u32,
+        };
+        let layout_ci = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(device.descriptors.layout())
+            .push_constant_ranges(std::slice::from_ref(&push_range));
+        let pipeline_layout = unsafe { device.as_raw().create_pipeline_layout(&layout_ci, None)? };
+
+        let vertex_input = vk::PipelineVertexInputStateCreateInfo::default();
+        let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::default()
+            .topology(vk::PrimitiveTopology::LINE_STRIP);
+        let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
+        let rasterizer = vk::PipelineRasterizationStateCreateInfo::default()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .line_width(1.0);
+        let multisampling = vk::PipelineMultisampleStateCreateInfo::default()
+            .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+        let blend_attachment = vk::PipelineColorBlendAttachmentState::default()
+            .color_write_mask(vk::ColorComponentFlags::RGBA);
+        let color_blend = vk::PipelineColorBlendStateCreateInfo::default()
+            .attachments(std::slice::from_ref(&blend_attachment));
+        let color_formats = [FORMAT];
+        let mut rendering =
+            vk::PipelineRenderingCreateInfo::default().color_attachment_formats(&color_formats);
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state =
+            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+
+        let pipeline_ci = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input)
+            .input_assembly_state(&input_assembly)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterizer)
+            .multisample_state(&multisampling)
+            .color_blend_state(&color_blend)
+            .dynamic_state(&dynamic_state)
+            .layout(pipeline_layout)
+            .push_next(&mut rendering);
+        let pipeline = unsafe {
+            device
+                .as_raw()
+                .create_graphics_pipelines(device.pipeline_cache(), &[pipeline_ci], None)
+                .map_err(|e| e.1)?[0]
+        };
+
+        unsafe {
+            device.as_raw().destroy_shader_module(vert, None);
+            device.as_raw().destroy_shader_module(frag, None);
+        }
+        Ok((pipeline_layout, pipeline))
+    }
+
+    /// Provision the target for `size`.  A replaced target is queued on `deletions` behind the
+    /// frames already recorded with it.
+    pub fn provision(
+        &mut self,
+        device: &Device,
+        size: vk::Extent2D,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        if let Some(existing) = self.target.take() {
+            deletions.defer(frames, move |device| {
+                if let Err(e) = existing.destroy(device) {
+                    eprintln!("scope: target destruction failed {:?}", e);
+                }
+            });
+        }
+        let target = OffscreenTarget::new(device, size, FORMAT)?;
+        target.set_name(device, "scope");
+        self.target = Some(target);
+        Ok(())
+    }
+
+    /// Samples shown and searched for a trigger.  Both fit in the ring with room to spare for the
+    /// writer, which is filling the slots behind the oldest of them.
+    fn window_samples(&self, ring: &RingPosition) -> u32 {
+        let rate = ring.rate.unwrap_or(FALLBACK_RATE);
+        let window = (self.window.as_secs_f64() * rate as f64) as u32;
+        window.clamp(2, ring.capacity / 3)
+    }
+
+    /// Draw the scope into `area` and copy it into `acquired_image`, which must be in
+    /// `TRANSFER_DST_OPTIMAL`.
+    pub fn draw(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        ring: &RingPosition,
+        area: vk::Rect2D,
+    ) {
+        let window = self.window_samples(ring);
+        let points = POINTS.min(window);
+        let Some(target) = &mut self.target else {
+            return;
+        };
+        let raw = device.as_raw();
+
+        // The last frame's draw may still be reading the trace.
+        let trace_barrier = |src_stage, src_access, dst_stage, dst_access| {
+            let barrier = vk::BufferMemoryBarrier {
+                src_access_mask: src_access,
+                dst_access_mask: dst_access,
+                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                buffer: self.trace.buffer,
+                offset: 0,
+                size: vk::WHOLE_SIZE,
+                ..Default::default()
+            };
+            unsafe {
+                raw.cmd_pipeline_barrier(
+                    **cb,
+                    src_stage,
+                    dst_stage,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[barrier],
+                    &[],
+                );
+            }
+        };
+        trace_barrier(
+            vk::PipelineStageFlags::VERTEX_SHADER,
+            vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::AccessFlags::SHADER_WRITE,
+        );
+        let push = ScopeTriggerConstants {
+            left_channel: DeviceAddress::from(ring.channels[0]),
+            right_channel: DeviceAddress::from(ring.channels[1]),
+            capacity: ring.capacity.into(),
+            write_index: ring.write_index.into(),
+            window: window.into(),
+            search: window.into(),
+            points: points.into(),
+            hysteresis: self.hysteresis.into(),
+            trace_idx: self.trace_idx.index(),
+        };
+        self.trigger.push(device, **cb, &push);
+        self.trigger.dispatch(device, **cb, 1, 1, 1);
+        trace_barrier(
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::AccessFlags::SHADER_WRITE,
+            vk::PipelineStageFlags::VERTEX_SHADER,
+            vk::AccessFlags::SHADER_READ,
+        );
+
+        let color_attachment = target.color_attachment(device, **cb);
+        let rendering_info = vk::RenderingInfo::default()
+            .render_area(area)
+            .layer_count(1)
+            .color_attachments(std::slice::from_ref(&color_attachment));
+        let viewport = super::viewport(area);
+
+        let push: [u32; 3] = [
+            self.trace_idx.index().raw(),
+            points,
+            (self.gain * self.level).to_bits(),
+        ];
+        unsafe {
+            raw.cmd_begin_rendering(**cb, &rendering_info);
+            raw.cmd_bind_pipeline(**cb, vk::PipelineBindPoint::GRAPHICS, self.pipeline);
+            raw.cmd_bind_descriptor_sets(
+                **cb,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                0,
+                &[device.descriptors.set()],
+                &[],
+            );
+            raw.cmd_push_constants(
+                **cb,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::VERTEX,
+                0,
+                bytemuck::cast_slice(&push),
+            );
+            raw.cmd_set_viewport(**cb, 0, &[viewport]);
+            raw.cmd_set_scissor(**cb, 0, &[area]);
+            raw.cmd_draw(**cb, points, 1, 0, 0);
+            raw.cmd_end_rendering(**cb);
+        }
+        target.copy_area_to(device, **cb, acquired_image.image, area);
+    }
+
+    /// Forget the target's layout after a frame that drew it was abandoned.
+    pub fn reset(&mut self) {
+        if let Some(target) = &mut self.target {
+            target.discard();
+        }
+    }
+
+    /// Caller must drain work that drew the scope first.
+    pub fn destroy(self, device: &Device) -> Result<(), utate::MutateError> {
+        unsafe {
+            self.trigger.destroy(device);
+            device.as_raw().destroy_pipeline(self.pipeline, None);
+            device
+                .as_raw()
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+        }
+        device.descriptors.release(self.trace_idx);
+        self.trace.destroy(device)?;
+        if let Some(target) = self.target {
+            target.destroy(device)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Screenshot
+//!
+//! Copies a frame back from the swapchain and writes it as a binary PPM in the working directory.
+//! The copy is recorded after everything else, so overlays and text are in the picture.  The file
+//! is written from the deletion queue once the frame retires, so the render loop never waits on the
+//! copy.
+//!
+//! Surfaces only allow the copy where they support `TRANSFER_SRC`, and only 8-bit RGBA and BGRA
+//! surfaces are understood.
+
+// MAYBE PNG once something else needs an encoder.
+
+use std::io::Write;
+
+use ash::vk;
+use mutate_lib::{self as utate, prelude::*};
+use utate::gpu::resource::{buffer, image};
+use utate::graph::DeletionQueue;
+
+pub struct Screenshot {
+    /// Whether swapchain images can be copied from.
+    readable: bool,
+    requested: bool,
+    /// Recorded into the current frame.
+    copy: Option<Copy>,
+}
+
+struct Copy {
+    buffer: buffer::MappedAllocation<[u8; 4]>,
+    extent: vk::Extent2D,
+    /// Channels are stored blue first.
+    bgra: bool,
+}
+
+impl Screenshot {
+    /// `usage` is the swapchain's image usage.
+    pub fn new(usage: vk::ImageUsageFlags) -> Self {
+        Self {
+            readable: usage.contains(vk::ImageUsageFlags::TRANSFER_SRC),
+            requested: false,
+            copy: None,
+        }
+    }
+
+    /// Take the next frame drawn.
+    pub fn request(&mut self) {
+        if self.readable {
+            self.requested = true;
+        } else {
+            eprintln!("screenshot: the surface does not allow reading frames back");
+        }
+    }
+
+    /// Copy `acquired_image` if a screenshot was requested.  The image must be in
+    /// `TRANSFER_DST_OPTIMAL` after transfer writes and is left that way.
+    pub fn record(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        format: vk::Format,
+    ) -> Result<(), utate::MutateError> {
+        if !self.requested || self.copy.is_some() {
+            return Ok(());
+        }
+        self.requested = false;
+        let bgra = match format {
+            vk::Format::B8G8R8A8_SRGB | vk::Format::B8G8R8A8_UNORM => true,
+            vk::Format::R8G8B8A8_SRGB | vk::Format::R8G8B8A8_UNORM => false,
+            _ => {
+                eprintln!("screenshot: {format:?} surfaces are not supported");
+                return Ok(());
+            }
+        };
+        let extent = acquired_image.extent;
+        let buffer =
+            buffer::MappedAllocation::new((extent.width * extent.height) as usize, device)?;
+        buffer.set_name(device, "screenshot");
+
+        let barrier = |old_layout, new_layout, src_access, dst_access| {
+            vk::ImageMemoryBarrier2::default()
+                .src_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+                .src_access_mask(src_access)
+                .dst_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+                .dst_access_mask(dst_access)
+                .old_layout(old_layout)
+                .new_layout(new_layout)
+                .image(acquired_image.image)
+                .subresource_range(image::range())
+        };
+        let to_source = barrier(
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::AccessFlags2::TRANSFER_WRITE,
+            vk::AccessFlags2::TRANSFER_READ,
+        );
+        let to_destination = barrier(
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::AccessFlags2::TRANSFER_READ,
+            vk::AccessFlags2::TRANSFER_WRITE,
+        );
+        // The host reads the buffer after the frame's fence.
+        let to_host = vk::BufferMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::HOST)
+            .dst_access_mask(vk::AccessFlags2::HOST_READ)
+            .buffer(buffer.buffer)
+            .size(vk::WHOLE_SIZE);
+        unsafe {
+            let raw = device.as_raw();
+            raw.cmd_pipeline_barrier2(
+                **cb,
+                &vk::DependencyInfo::default().image_memory_barriers(&[to_source]),
+            );
+            raw.cmd_copy_image_to_buffer(
+                **cb,
+                acquired_image.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer.buffer,
+                &[buffer::buffer_image_copy_full(extent)],
+            );
+            raw.cmd_pipeline_barrier2(
+                **cb,
+                &vk::DependencyInfo::default()
+                    .image_memory_barriers(&[to_destination])
+                    .buffer_memory_barriers(&[to_host]),
+            );
+        }
+        self.copy = Some(Copy {
+            buffer,
+            extent,
+            bgra,
+        });
+        Ok(())
+    }
+
+    /// Queue writing the copy recorded this frame behind the `frames` recorded so far.
+    pub fn finish(&mut self, deletions: &mut DeletionQueue<Device>, frames: u64) {
+        let Some(mut copy) = self.copy.take() else {
+            return;
+        };
+        deletions.defer(frames, move |device| {
+            let path = file_name();
+            match copy.write(device, &path) {
+                Ok(()) => println!("screenshot: {}", path.display()),
+                Err(e) => eprintln!("screenshot: writing {} failed: {e}", path.display()),
+            }
+            if let Err(e) = copy.buffer.destroy(device) {
+                eprintln!("screenshot: buffer destruction failed {:?}", e);
+            }
+        });
+    }
+
+    /// Drop the copy recorded into a frame that was abandoned.  The screenshot is taken again from
+    /// the next frame.
+    pub fn abandon(&mut self, deletions: &mut DeletionQueue<Device>, frames: u64) {
+        let Some(copy) = self.copy.take() else {
+            return;
+        };
+        self.requested = true;
+        deletions.defer(frames, move |device| {
+            if let Err(e) = copy.buffer.destroy(device) {
+                eprintln!("screenshot: buffer destruction failed {:?}", e);
+            }
+        });
+    }
+}
+
+impl Copy {
+    fn write(&mut self, device: &Device, path: &std::path::Path) -> Result<(), String> {
+        self.buffer.invalidate(device).map_err(|e| format!("{e}"))?;
+        let pixels = self.buffer.as_mut_slice();
+        let mut rgb = Vec::with_capacity(pixels.len() * 3);
+        for &[a, b, c, _] in pixels.iter() {
+            if self.bgra {
+                rgb.extend_from_slice(&[c, b, a]);
+            } else {
+                rgb.extend_from_slice(&[a, b, c]);
+            }
+        }
+        let write = || -> std::io::Result<()> {
+            let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+            write!(
+                file,
+                "P6\n{} {}\n255\n",
+                self.extent.width, self.extent.height
+            )?;
+            file.write_all(&rgb)?;
+            file.flush()
+        };
+        write().map_err(|e| format!("{e}"))
+    }
+}
+
+/// Named by the time taken, so that screenshots sort in order.
+fn file_name() -> std::path::PathBuf {
+    let since = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("mutate-{}.ppm", since.as_millis()).into()
+}
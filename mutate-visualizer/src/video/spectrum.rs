@@ -0,0 +1,152 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Spectrum
+//!
+//! The filter bank on the device, for scenes that draw bins.  A [`Spectrum`] reads what plays
+//! through its own [`AudioInlet`], designs a bank for the stream's rate from the `[dsp]` settings
+//! the way the daemon does, and records one [`GpuSpectrogram`] dispatch per frame.  Nothing is
+//! designed until audio arrives, so a window that never shows such a scene only pays for the inlet.
+//!
+//! Frames that the throttle keeps from analyzing [`fill`](Spectrum::fill) the output instead,
+//! extrapolating from the last two dispatches through an [`OutputHistory`].
+
+// DEBT the host writes the bank's input while frames in flight may still read it.  A torn window
+// shows as one noisy row.  An input buffer per frame in flight would fix it.
+
+use ash::vk;
+use mutate_lib::{self as utate, prelude::*};
+use utate::audio::node::AudioInlet;
+use utate::dsp::compute::{GpuSpectrogram, OutputHistory};
+use utate::dsp::units::SampleRate;
+use utate::graph::DeletionQueue;
+use utate::settings::DspSettings;
+
+pub struct Spectrum {
+    inlet: AudioInlet,
+    dsp: DspSettings,
+    /// Rate the bank was designed for.
+    rate: u32,
+    bank: Option<GpuSpectrogram>,
+    /// Outputs of `bank`, replaced with it.
+    history: Option<OutputHistory>,
+}
+
+impl Spectrum {
+    /// Bins spaced as `dsp` asks.
+    pub fn new(dsp: &DspSettings) -> Self {
+        Self {
+            inlet: AudioInlet::new(2),
+            dsp: dsp.clone(),
+            rate: 0,
+            bank: None,
+            history: None,
+        }
+    }
+
+    /// Where the host pushes what plays.  See [`Audio::feed`](crate::audio::Audio::feed).
+    pub fn inlet(&self) -> &AudioInlet {
+        &self.inlet
+    }
+
+    /// `None` until audio arrives.
+    pub fn bank(&self) -> Option<&GpuSpectrogram> {
+        self.bank.as_ref()
+    }
+
+    /// Push what arrived since the last frame through the bank and upload it, first designing a
+    /// bank for a new rate.  The bank replaced is queued on `deletions` behind the frames already
+    /// recorded with it.
+    pub fn update(
+        &mut self,
+        device: &Device,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        let Some((samples, rate)) = self.inlet.take() else {
+            return Ok(());
+        };
+        if self.bank.is_none() || rate != self.rate {
+            let table = self.dsp.design_table(SampleRate(rate as f64));
+            let bank = GpuSpectrogram::new(device, table)?.with_channels(self.inlet.channels());
+            let history = OutputHistory::new(device, &bank)?;
+            if let (Some(old), Some(old_history)) =
+                (self.bank.replace(bank), self.history.replace(history))
+            {
+                deletions.defer(frames, move |device| {
+                    if let Err(e) = old.destroy(device) {
+                        eprintln!("spectrum: bank destruction failed {:?}", e);
+                    }
+                    if let Err(e) = old_history.destroy(device) {
+                        eprintln!("spectrum: history destruction failed {:?}", e);
+                    }
+                });
+            }
+            self.rate = rate;
+        }
+        if let Some(bank) = &mut self.bank {
+            bank.push(&samples);
+            bank.upload(device)?;
+        }
+        Ok(())
+    }
+
+    /// Discard what arrived, for frames that draw no bins.
+    pub fn skip(&self) {
+        let _ = self.inlet.take();
+    }
+
+    /// Record the dispatch and keep its output.  Work still reading the last output, such as the
+    /// previous frame's row copy or particle pass, finishes first.
+    pub fn record(&mut self, device: &Device, cb: vk::CommandBuffer) {
+        let (Some(bank), Some(history)) = (&self.bank, &mut self.history) else {
+            return;
+        };
+        Self::writable(device, cb, bank);
+        bank.record(device, cb);
+        history.record_keep(device, cb, bank);
+    }
+
+    /// Record the output of a frame that skips the dispatch, extrapolated from the last two kept.
+    pub fn fill(&self, device: &Device, cb: vk::CommandBuffer) {
+        let (Some(bank), Some(history)) = (&self.bank, &self.history) else {
+            return;
+        };
+        Self::writable(device, cb, bank);
+        history.record_extrapolate(device, cb, bank);
+    }
+
+    fn writable(device: &Device, cb: vk::CommandBuffer, bank: &GpuSpectrogram) {
+        let barrier = vk::BufferMemoryBarrier {
+            src_access_mask: vk::AccessFlags::TRANSFER_READ | vk::AccessFlags::SHADER_READ,
+            dst_access_mask: vk::AccessFlags::SHADER_WRITE,
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            buffer: bank.output_buffer(),
+            size: vk::WHOLE_SIZE,
+            ..Default::default()
+        };
+        unsafe {
+            device.as_raw().cmd_pipeline_barrier(
+                cb,
+                vk::PipelineStageFlags::TRANSFER | vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[barrier],
+                &[],
+            );
+        }
+    }
+
+    /// Caller must drain the frames that dispatched first.
+    pub fn destroy(self, device: &Device) -> Result<(), utate::MutateError> {
+        if let Some(history) = self.history {
+            history.destroy(device)?;
+        }
+        match self.bank {
+            Some(bank) => bank.destroy(device),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,403 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Text
+//!
+//! Bitmap text for debug overlays and HUDs: device names, frame rates, bin frequencies.  The bundled
+//! `hud` font covers printable ASCII and is packed into a coverage atlas once.  Callers queue strings
+//! with [`TextNode::draw_text`] while building a frame, and [`TextNode::draw`] draws everything
+//! queued over the acquired image in one instanced draw, one quad per glyph.
+//!
+//! Glyphs scale by whole pixels so they stay crisp.  Characters the font lacks draw as `?`.
+
+// NEXT a graph node once render nodes move into the graph.  HUD text then arrives as events.
+// DEBT the atlas staging buffer is kept for the node's life.  There is no upload queue yet.
+// MAYBE an outline or shadow style for text over bright visuals.
+
+use ash::vk;
+use mutate_lib::{self as utate, assets, prelude::*};
+use utate::gpu::device::descriptors::Handle;
+use utate::gpu::resource::{buffer, image};
+use utate::graph::context::FRAMES_IN_FLIGHT;
+
+const FONT: &str = "hud";
+/// Glyphs drawn per frame.  Text queued past this is dropped.
+const MAX_GLYPHS: usize = 4096;
+/// Drawn for characters the font lacks.
+const FALLBACK: char = '?';
+
+/// Cell top left in pixels, scale, packed atlas origin, and color.  Matches the vertex shader.
+type Glyph = [f32; 8];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+    /// Screen pixels per font pixel.
+    pub scale: u32,
+    /// Straight alpha RGBA, written to the swapchain as is.
+    pub color: [f32; 4],
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            scale: 2,
+            color: [1.0; 4],
+        }
+    }
+}
+
+pub struct TextNode {
+    pipeline_layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+    /// Metrics and cells.  The pixels are only needed for the upload.
+    atlas: assets::font::Atlas,
+
+    image: image::Image,
+    view: image::ImageView,
+    staging: buffer::MappedAllocation<u8>,
+    atlas_idx: Handle<SampledImageIdx>,
+    uploaded: bool,
+
+    /// One buffer per frame in flight, so queuing never races a draw.
+    glyphs: Vec<(buffer::MappedAllocation<Glyph>, Handle<SsboIdx>)>,
+    cursor: usize,
+    queued: Vec<Glyph>,
+}
+
+impl TextNode {
+    /// Draws into swapchain images of `format`.
+    pub fn new(device: &Device, format: vk::Format) -> Result<Self, utate::MutateError> {
+        let font = assets::AssetDirs::new().find_font(FONT)?;
+        let mut atlas = font.atlas();
+        let extent = vk::Extent2D {
+            width: atlas.width,
+            height: atlas.height,
+        };
+        let image = image::Image::new(
+            device,
+            extent,
+            vk::Format::R8_UNORM,
+            vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
+        )?;
+        image.set_name(device, "font atlas");
+        let view = image.default_view(device)?;
+        let mut staging = buffer::MappedAllocation::<u8>::new(atlas.pixels.len(), device)?;
+        staging.set_name(device, "font atlas staging");
+        staging.as_mut_slice().copy_from_slice(&atlas.pixels);
+        staging.flush(device)?;
+        atlas.pixels = Vec::new();
+        // Usable once the frame that uploads the atlas is submitted.
+        let atlas_idx = device
+            .descriptors
+            .register_sampled_image(view.view, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)?;
+
+        let mut glyphs = Vec::with_capacity(FRAMES_IN_FLIGHT);
+        for _ in 0..FRAMES_IN_FLIGHT {
+            let buffer = buffer::MappedAllocation::<Glyph>::new(MAX_GLYPHS, device)?;
+            buffer.set_name(device, "text glyphs");
+            let idx = buffer.register(device)?;
+            glyphs.push((buffer, idx));
+        }
+
+        let (pipeline_layout, pipeline) = Self::pipeline(device, format)?;
+        Ok(Self {
+            pipeline_layout,
+            pipeline,
+            atlas,
+            image,
+            view,
+            staging,
+            atlas_idx,
+            uploaded: false,
+            glyphs,
+            cursor: 0,
+            queued: Vec::new(),
+        })
+    }
+
+    fn pipeline(
+        device: &Device,
+        format: vk::Format,
+    ) -> Result<(vk::PipelineLayout, vk::Pipeline), VulkanError> {
+        let vert = super::shader_module(device, "text/vertex")?;
+        let frag = super::shader_module(device, "text/fragment")?;
+
+        let stages = [
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::VERTEX)
+                .module(vert)
+                .name(c"main"),
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::FRAGMENT)
+                .module(frag)
+                .name(c"main"),
+        ];
+
+        let push_range = vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            offset: 0,
+            size: std::mem::size_of::<[u32; 6]>() as u32,
+        };
+        let layout_ci = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(device.descriptors.layout())
+            .push_constant_ranges(std::slice::from_ref(&push_range));
+        let pipeline_layout = unsafe { device.as_raw().create_pipeline_layout(&layout_ci, None)? };
+
+        let vertex_input = vk::PipelineVertexInputStateCreateInfo::default();
+        let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::default()
+            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+        let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
+        let rasterizer = vk::PipelineRasterizationStateCreateInfo::default()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .line_width(1.0);
+        let multisampling = vk::PipelineMultisampleStateCreateInfo::default()
+            .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+        let blend_attachment = vk::PipelineColorBlendAttachmentState::default()
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+            .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ONE)
+            .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .color_write_mask(vk::ColorComponentFlags::RGBA);
+        let color_blend = vk::PipelineColorBlendStateCreateInfo::default()
+            .attachments(std::slice::from_ref(&blend_attachment));
+        let color_formats = [format];
+        let mut rendering =
+            vk::PipelineRenderingCreateInfo::default().color_attachment_formats(&color_formats);
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state =
+            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+
+        let pipeline_ci = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input)
+            .input_assembly_state(&input_assembly)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterizer)
+            .multisample_state(&multisampling)
+            .color_blend_state(&color_blend)
+            .dynamic_state(&dynamic_state)
+            .layout(pipeline_layout)
+            .push_next(&mut rendering);
+        let pipeline = unsafe {
+            device
+                .as_raw()
+                .create_graphics_pipelines(device.pipeline_cache(), &[pipeline_ci], None)
+                .map_err(|e| e.1)?[0]
+        };
+
+        unsafe {
+            device.as_raw().destroy_shader_module(vert, None);
+            device.as_raw().destroy_shader_module(frag, None);
+        }
+        Ok((pipeline_layout, pipeline))
+    }
+
+    /// Height of a line in pixels.
+    pub fn line_height(&self, style: &TextStyle) -> f32 {
+        (self.atlas.line_height * style.scale.max(1)) as f32
+    }
+
+    /// Size of `text` in pixels, for aligning it before drawing.
+    pub fn measure(&self, text: &str, style: &TextStyle) -> [f32; 2] {
+        let mut width = 0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let advance: u32 = line
+                .chars()
+                .filter_map(|c| self.glyph(c))
+                .map(|g| g.advance)
+                .sum();
+            width = width.max(advance);
+            lines += 1;
+        }
+        let scale = style.scale.max(1);
+        [
+            (width * scale) as f32,
+            (lines * self.atlas.line_height * scale) as f32,
+        ]
+    }
+
+    /// Queue `text` with the top left of its first line at `pos`, in pixels from the top left of
+    /// the window.  Newlines return to `pos`'s column.  Drawn and cleared by the next
+    /// [`draw`](Self::draw).
+    pub fn draw_text(&mut self, pos: [f32; 2], text: &str, style: &TextStyle) {
+        let scale = style.scale.max(1) as f32;
+        let [r, g, b, a] = style.color;
+        let mut pen = pos;
+        for c in text.chars() {
+            if c == '\n' {
+                pen = [pos[0], pen[1] + self.atlas.line_height as f32 * scale];
+                continue;
+            }
+            let Some(glyph) = self.glyph(c) else {
+                continue;
+            };
+            // Blank cells cost a quad for nothing.
+            if !c.is_whitespace() && self.queued.len() < MAX_GLYPHS {
+                let top = self.atlas.ascent as f32 - self.atlas.baseline as f32;
+                let origin = glyph.origin[0] | glyph.origin[1] << 16;
+                self.queued.push([
+                    pen[0] + self.atlas.left as f32 * scale,
+                    pen[1] + top * scale,
+                    scale,
+                    f32::from_bits(origin),
+                    r,
+                    g,
+                    b,
+                    a,
+                ]);
+            }
+            pen[0] += glyph.advance as f32 * scale;
+        }
+    }
+
+    fn glyph(&self, c: char) -> Option<assets::font::AtlasGlyph> {
+        let stand_in = if c.is_whitespace() { ' ' } else { FALLBACK };
+        self.atlas.glyph(c).or_else(|| self.atlas.glyph(stand_in))
+    }
+
+    /// Draw the queued text over `acquired_image`, which must be in `TRANSFER_DST_OPTIMAL` after
+    /// transfer writes and is left that way.  The first draw also records the atlas upload.
+    pub fn draw(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+    ) -> Result<(), utate::MutateError> {
+        if self.queued.is_empty() {
+            return Ok(());
+        }
+        let raw = device.as_raw();
+        if !self.uploaded {
+            self.image.transition_to_transfer_dst(**cb, device);
+            let region = buffer::buffer_image_copy_full(self.image.extent);
+            unsafe {
+                raw.cmd_copy_buffer_to_image(
+                    **cb,
+                    self.staging.buffer,
+                    self.image.image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[region],
+                );
+            }
+            self.image.transition_to_shader_read(**cb, device);
+            self.uploaded = true;
+        }
+
+        let slot = self.cursor;
+        self.cursor = (slot + 1) % self.glyphs.len();
+        let (buffer, glyphs_idx) = &mut self.glyphs[slot];
+        let count = self.queued.len();
+        buffer.as_mut_slice()[..count].copy_from_slice(&self.queued);
+        buffer.flush(device)?;
+        self.queued.clear();
+
+        // The frame was copied into the image, and whatever follows copies into it too.
+        let to_attachment = vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+            .dst_access_mask(
+                vk::AccessFlags2::COLOR_ATTACHMENT_READ | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+            )
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .image(acquired_image.image)
+            .subresource_range(image::range());
+        let to_transfer = vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+            .src_access_mask(vk::AccessFlags2::COLOR_ATTACHMENT_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::ALL_TRANSFER)
+            .dst_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .old_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .image(acquired_image.image)
+            .subresource_range(image::range());
+
+        let extent = acquired_image.extent;
+        let color_attachment = vk::RenderingAttachmentInfo::default()
+            .image_view(acquired_image.image_view)
+            .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::LOAD)
+            .store_op(vk::AttachmentStoreOp::STORE);
+        let render_area = vk::Rect2D {
+            offset: vk::Offset2D::default(),
+            extent,
+        };
+        let rendering_info = vk::RenderingInfo::default()
+            .render_area(render_area)
+            .layer_count(1)
+            .color_attachments(std::slice::from_ref(&color_attachment));
+        let viewport = vk::Viewport {
+            width: extent.width as f32,
+            height: extent.height as f32,
+            max_depth: 1.0,
+            ..Default::default()
+        };
+
+        let push: [u32; 6] = [
+            glyphs_idx.index().raw(),
+            self.atlas_idx.index().raw(),
+            (extent.width as f32).to_bits(),
+            (extent.height as f32).to_bits(),
+            self.atlas.cell_width,
+            self.atlas.cell_height,
+        ];
+        unsafe {
+            raw.cmd_pipeline_barrier2(
+                **cb,
+                &vk::DependencyInfo::default().image_memory_barriers(&[to_attachment]),
+            );
+            raw.cmd_begin_rendering(**cb, &rendering_info);
+            raw.cmd_bind_pipeline(**cb, vk::PipelineBindPoint::GRAPHICS, self.pipeline);
+            raw.cmd_bind_descriptor_sets(
+                **cb,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                0,
+                &[device.descriptors.set()],
+                &[],
+            );
+            raw.cmd_push_constants(
+                **cb,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                0,
+                bytemuck::cast_slice(&push),
+            );
+            raw.cmd_set_viewport(**cb, 0, &[viewport]);
+            raw.cmd_set_scissor(**cb, 0, &[render_area]);
+            raw.cmd_draw(**cb, 6, count as u32, 0, 0);
+            raw.cmd_end_rendering(**cb);
+            raw.cmd_pipeline_barrier2(
+                **cb,
+                &vk::DependencyInfo::default().image_memory_barriers(&[to_transfer]),
+            );
+        }
+        Ok(())
+    }
+
+    /// Caller must drain frames that drew text first.
+    pub fn destroy(self, device: &Device) -> Result<(), utate::MutateError> {
+        device.descriptors.release(self.atlas_idx);
+        unsafe {
+            device.as_raw().destroy_pipeline(self.pipeline, None);
+            device
+                .as_raw()
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+        }
+        for (buffer, idx) in self.glyphs {
+            device.descriptors.release(idx);
+            buffer.destroy(device)?;
+        }
+        self.view.destroy(device)?;
+        self.image.destroy(device)?;
+        self.staging.destroy(device)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,342 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Texture
+//!
+//! Draws a [`Picture`] through a bindless texture.  The texture is registered into the
+//! sampled-image array once, and each draw pushes its index along with a default sampler's index.
+//! Nothing about the pipeline layout depends on which texture is drawn.
+//!
+//! Pictures are binary PPM files, the format [screenshots](super::screenshot) are saved in, so any
+//! image converter can make one.  Without a picture, a generated checkerboard is drawn.
+//!
+//! The pipeline is created for the sample count of the [`MsaaTarget`] it draws into, so it must be
+//! rebuilt along with a target of a different count.
+
+// DEBT the staging buffer lives as long as the node because there is no upload queue to retire it.
+// NEXT declare with `graphics_pipeline!` once it can hydrate pipelines.
+
+use std::path::Path;
+
+use ash::vk;
+use mutate_lib::{self as utate, prelude::*};
+use utate::gpu::device::descriptors::{samplers, Handle};
+use utate::gpu::resource::{buffer, image};
+
+/// Edge length of the generated texture.
+const TEXTURE_SIZE: u32 = 64;
+/// Checker squares per edge.
+const CHECKS: u32 = 8;
+/// Longest edge of a picture, the smallest `maxImageDimension2D` a device may have.
+const MAX_EDGE: u32 = 4096;
+
+/// RGBA texels, top row first.
+#[derive(Clone, Debug)]
+pub struct Picture {
+    pub width: u32,
+    pub height: u32,
+    pub texels: Vec<[u8; 4]>,
+}
+
+impl Picture {
+    /// A shaded checkerboard, drawn when no picture is given.
+    pub fn checker() -> Self {
+        let check = TEXTURE_SIZE / CHECKS;
+        let texels = (0..TEXTURE_SIZE * TEXTURE_SIZE)
+            .map(|i| {
+                let (x, y) = (i % TEXTURE_SIZE, i / TEXTURE_SIZE);
+                let shade = if (x / check + y / check).is_multiple_of(2) {
+                    255
+                } else {
+                    160
+                };
+                let ramp = |v: u32| (v * 255 / (TEXTURE_SIZE - 1) * shade / 255) as u8;
+                [ramp(x), ramp(y), ramp(TEXTURE_SIZE - 1 - x), 255]
+            })
+            .collect();
+        Self {
+            width: TEXTURE_SIZE,
+            height: TEXTURE_SIZE,
+            texels,
+        }
+    }
+
+    /// Read a binary PPM of at most 8 bits per channel.
+    pub fn read(path: &Path) -> Result<Self, utate::MutateError> {
+        std::fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| Self::parse_ppm(&bytes))
+            .map_err(|e| utate::MutateError::Config(format!("{}: {e}", path.display())))
+    }
+
+    fn parse_ppm(bytes: &[u8]) -> Result<Self, String> {
+        let mut rest = bytes
+            .strip_prefix(b"P6")
+            .ok_or("not a binary PPM, which starts with `P6`")?;
+        let mut header = [0u32; 3];
+        for value in header.iter_mut() {
+            // Whitespace and comments may come between values.
+            loop {
+                match rest.first() {
+                    Some(c) if c.is_ascii_whitespace() => rest = &rest[1..],
+                    Some(b'#') => {
+                        let end = rest.iter().position(|&c| c == b'\n').unwrap_or(rest.len());
+                        rest = &rest[end..];
+                    }
+                    _ => break,
+                }
+            }
+            let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+            *value = std::str::from_utf8(&rest[..digits])
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or("the PPM header is cut short")?;
+            rest = &rest[digits..];
+        }
+        let [width, height, max] = header;
+        if width == 0 || height == 0 || width > MAX_EDGE || height > MAX_EDGE {
+            return Err(format!(
+                "{width}x{height} is not between 1x1 and {MAX_EDGE}x{MAX_EDGE}"
+            ));
+        }
+        if max == 0 || max > 255 {
+            return Err(format!(
+                "only 8 bits per channel are read, not a maximum of {max}"
+            ));
+        }
+        // One whitespace character separates the header from the texels.
+        let data = rest.get(1..).unwrap_or_default();
+        let len = (width * height * 3) as usize;
+        let data = data
+            .get(..len)
+            .ok_or("the PPM ends before its last texel")?;
+        let scale = |c: u8| (c as u32 * 255 / max).min(255) as u8;
+        let texels = data
+            .chunks_exact(3)
+            .map(|rgb| [scale(rgb[0]), scale(rgb[1]), scale(rgb[2]), 255])
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            texels,
+        })
+    }
+}
+
+pub struct TextureNode {
+    pipeline_layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+
+    image: image::Image,
+    view: image::ImageView,
+    staging: buffer::MappedAllocation<[u8; 4]>,
+    texture_idx: Handle<SampledImageIdx>,
+    uploaded: bool,
+}
+
+impl TextureNode {
+    /// Draws `picture` into swapchain images of `format` through a target with `samples` per
+    /// pixel.
+    pub fn new(
+        device: &Device,
+        format: vk::Format,
+        samples: vk::SampleCountFlags,
+        picture: &Picture,
+    ) -> Result<Self, utate::MutateError> {
+        let (pipeline_layout, pipeline) = Self::pipeline(device, format, samples)?;
+
+        let extent = vk::Extent2D {
+            width: picture.width,
+            height: picture.height,
+        };
+        let image = image::Image::new(
+            device,
+            extent,
+            vk::Format::R8G8B8A8_UNORM,
+            vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
+        )?;
+        image.set_name(device, "picture texture");
+        let view = image.default_view(device)?;
+
+        let mut staging = buffer::MappedAllocation::<[u8; 4]>::new(picture.texels.len(), device)?;
+        staging.set_name(device, "picture staging");
+        staging.as_mut_slice().copy_from_slice(&picture.texels);
+        staging.flush(device)?;
+
+        // Usable once the frame that uploads the texture is submitted.
+        let texture_idx = device
+            .descriptors
+            .register_sampled_image(view.view, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)?;
+
+        Ok(Self {
+            pipeline_layout,
+            pipeline,
+            image,
+            view,
+            staging,
+            texture_idx,
+            uploaded: false,
+        })
+    }
+
+    fn pipeline(
+        device: &Device,
+        format: vk::Format,
+        samples: vk::SampleCountFlags,
+    ) -> Result<(vk::PipelineLayout, vk::Pipeline), VulkanError> {
+        let vert = super::shader_module(device, "texture/vertex")?;
+        let frag = super::shader_module(device, "texture/fragment")?;
+
+        let stages = [
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::VERTEX)
+                .module(vert)
+                .name(c"main"),
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::FRAGMENT)
+                .module(frag)
+                .name(c"main"),
+        ];
+
+        let push_range = vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            offset: 0,
+            size: std::mem::size_of::<[u32; 3]>() as u32,
+        };
+        // The one bindless set.  Which texture to sample is only ever a push constant.
+        let layout_ci = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(device.descriptors.layout())
+            .push_constant_ranges(std::slice::from_ref(&push_range));
+        let pipeline_layout = unsafe { device.as_raw().create_pipeline_layout(&layout_ci, None)? };
+
+        let vertex_input = vk::PipelineVertexInputStateCreateInfo::default();
+        let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::default()
+            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+        let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
+        let rasterizer = vk::PipelineRasterizationStateCreateInfo::default()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .line_width(1.0);
+        let multisampling =
+            vk::PipelineMultisampleStateCreateInfo::default().rasterization_samples(samples);
+        let blend_attachment = vk::PipelineColorBlendAttachmentState::default()
+            .color_write_mask(vk::ColorComponentFlags::RGBA);
+        let color_blend = vk::PipelineColorBlendStateCreateInfo::default()
+            .attachments(std::slice::from_ref(&blend_attachment));
+        let color_formats = [format];
+        let mut rendering =
+            vk::PipelineRenderingCreateInfo::default().color_attachment_formats(&color_formats);
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state =
+            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+
+        let pipeline_ci = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input)
+            .input_assembly_state(&input_assembly)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterizer)
+            .multisample_state(&multisampling)
+            .color_blend_state(&color_blend)
+            .dynamic_state(&dynamic_state)
+            .layout(pipeline_layout)
+            .push_next(&mut rendering);
+        let pipeline = unsafe {
+            device
+                .as_raw()
+                .create_graphics_pipelines(device.pipeline_cache(), &[pipeline_ci], None)
+                .map_err(|e| e.1)?[0]
+        };
+
+        unsafe {
+            device.as_raw().destroy_shader_module(vert, None);
+            device.as_raw().destroy_shader_module(frag, None);
+        }
+        Ok((pipeline_layout, pipeline))
+    }
+
+    /// Record inside `graphics_present`, drawing into `area`.  The first draw also records the
+    /// texture upload.
+    pub fn draw(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        target: &MsaaTarget,
+        area: vk::Rect2D,
+        tiles: f32,
+    ) {
+        let raw = device.as_raw();
+        if !self.uploaded {
+            self.image.transition_to_transfer_dst(**cb, device);
+            let region = buffer::buffer_image_copy_full(self.image.extent);
+            unsafe {
+                raw.cmd_copy_buffer_to_image(
+                    **cb,
+                    self.staging.buffer,
+                    self.image.image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[region],
+                );
+            }
+            self.image.transition_to_shader_read(**cb, device);
+            self.uploaded = true;
+        }
+
+        // The triangle covers the target, so nothing needs clearing.
+        let color_attachment = target
+            .color_attachment(acquired_image)
+            .load_op(vk::AttachmentLoadOp::DONT_CARE);
+        target.prepare(device, **cb);
+        let rendering_info = vk::RenderingInfo::default()
+            .render_area(area)
+            .layer_count(1)
+            .color_attachments(std::slice::from_ref(&color_attachment));
+        let viewport = super::viewport(area);
+
+        let push: [u32; 3] = [
+            self.texture_idx.index().raw(),
+            samplers::LINEAR_REPEAT.raw(),
+            tiles.to_bits(),
+        ];
+        unsafe {
+            raw.cmd_begin_rendering(**cb, &rendering_info);
+            raw.cmd_bind_pipeline(**cb, vk::PipelineBindPoint::GRAPHICS, self.pipeline);
+            raw.cmd_bind_descriptor_sets(
+                **cb,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                0,
+                &[device.descriptors.set()],
+                &[],
+            );
+            raw.cmd_push_constants(
+                **cb,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                0,
+                bytemuck::cast_slice(&push),
+            );
+            raw.cmd_set_viewport(**cb, 0, &[viewport]);
+            raw.cmd_set_scissor(**cb, 0, &[area]);
+            raw.cmd_draw(**cb, 3, 1, 0, 0);
+            raw.cmd_end_rendering(**cb);
+        }
+    }
+
+    /// Caller must drain work that sampled the texture first.
+    pub fn destroy(self, device: &Device) -> Result<(), utate::MutateError> {
+        device.descriptors.release(self.texture_idx);
+        unsafe {
+            device.as_raw().destroy_pipeline(self.pipeline, None);
+            device
+                .as_raw()
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+        }
+        self.view.destroy(device)?;
+        self.image.destroy(device)?;
+        self.staging.destroy(device)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Tiles
+//!
+//! Several scenes side by side in one window, placed by the `[layout]` of the `--graph` preset.
+//! Tiles name nodes of the `scene` kind, which the visualizer registers.  Such a node runs no work
+//! in the graph.  Its `scene` key is what its tiles show first, the ring by default.
+//!
+//! ```toml
+//! [[node]]
+//! name = "top"
+//! kind = "scene"
+//! scene = "waterfall"
+//!
+//! [[node]]
+//! name = "left"
+//! kind = "scene"
+//! scene = "scope"
+//!
+//! [[node]]
+//! name = "right"
+//! kind = "scene"
+//! scene = "vectorscope"
+//!
+//! [layout]
+//! rows = 2
+//! cols = 2
+//! gap = 4
+//!
+//! [[layout.tile]]
+//! node = "top"
+//! col_span = 2
+//!
+//! [[layout.tile]]
+//! node = "left"
+//! row = 1
+//!
+//! [[layout.tile]]
+//! node = "right"
+//! row = 1
+//! col = 1
+//! ```
+//!
+//! Clicking a tile focuses it, and the scene keys then change only what it shows.  Tiles naming the
+//! same node change together.  Tiles cut from one scene to the next rather than fading, and do not
+//! rotate.  The feedback trail is the size of the window, so a tile showing feedback draws the
+//! plain ring.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use ash::vk;
+use mutate_lib::{self as utate, graph::prelude::*};
+use utate::graph::layout::{Compositor, Layout};
+use utate::graph::preset::NodeRegistry;
+use utate::MutateError;
+
+use super::scene::Scene;
+
+/// The scene each `scene` node of a preset was built with, by node name.  Clones share the names.
+#[derive(Clone, Default)]
+pub struct TileScenes {
+    built: Arc<Mutex<BTreeMap<String, Scene>>>,
+}
+
+impl TileScenes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the `scene` kind to `registry`.  Build the preset before asking for [`tiles`](Self::tiles).
+    pub fn register(&self, registry: &mut NodeRegistry) {
+        let built = self.built.clone();
+        registry.register("scene", move |args| {
+            let scene = args
+                .str("scene", Scene::default().name())?
+                .parse::<Scene>()
+                .map_err(|e| MutateError::Preset(format!("node `{}`: {e}", args.name())))?;
+            let mut built = built.lock().unwrap_or_else(PoisonError::into_inner);
+            built.insert(args.name().to_owned(), scene);
+            Ok(SceneTile)
+        });
+    }
+
+    /// A window's tiles for `layout`, every one of which must name a `scene` node.
+    pub fn tiles(&self, layout: &Layout) -> Result<Tiles, MutateError> {
+        let built = self.built.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut showing = BTreeMap::new();
+        for tile in &layout.tiles {
+            let scene = built.get(&tile.node).ok_or_else(|| {
+                MutateError::InvalidLayout(format!("tile `{}` is not a `scene` node", tile.node))
+            })?;
+            showing.insert(tile.node.clone(), *scene);
+        }
+        Ok(Tiles {
+            compositor: Compositor::new(layout.clone())?,
+            showing,
+        })
+    }
+}
+
+/// The graph's end of a tile.  See the [module](self) docs.
+pub struct SceneTile;
+
+impl Params for SceneTile {
+    fn param_specs(&self) -> &'static [ParamSpec] {
+        &[]
+    }
+}
+
+impl Node for SceneTile {
+    fn attach(&mut self, _params: ParamHandle) {}
+
+    fn run(&mut self, _frame: &mut Frame<'_>) -> Result<(), MutateError> {
+        Ok(())
+    }
+}
+
+/// Where one window draws each tile, and what the tiles show.
+pub struct Tiles {
+    compositor: Compositor,
+    /// By node name.
+    showing: BTreeMap<String, Scene>,
+}
+
+impl Tiles {
+    /// Place the tiles in a window of `extent`.  Call on every swapchain resize.
+    pub fn resize(&mut self, extent: vk::Extent2D) {
+        self.compositor.resize(extent.width, extent.height);
+    }
+
+    /// Each tile's scene and area, in tile order, as [`SceneNodes::draw_tiles`] takes them.
+    ///
+    /// [`SceneNodes::draw_tiles`]: super::scene::SceneNodes::draw_tiles
+    pub fn placed(&self) -> Vec<(Scene, vk::Rect2D)> {
+        self.compositor
+            .tiles()
+            .map(|(node, rect)| (self.showing[node], rect.scissor()))
+            .collect()
+    }
+
+    /// Focus the tile under a window position.  Positions in gaps keep the current focus.
+    pub fn focus_at(&mut self, x: f64, y: f64) {
+        self.compositor.focus_at(x, y);
+    }
+
+    /// Show `scene` in the focused tile, or the ring when it already shows `scene`.  Returns the
+    /// scene switched to.
+    pub fn toggle(&mut self, scene: Scene) -> Option<Scene> {
+        let showing = self.focused()?;
+        *showing = match *showing == scene {
+            true => Scene::Ring,
+            false => scene,
+        };
+        Some(*showing)
+    }
+
+    /// Show the focused tile's next scene in rotation.  Returns the scene switched to.
+    pub fn next(&mut self) -> Option<Scene> {
+        let showing = self.focused()?;
+        *showing = showing.next();
+        Some(*showing)
+    }
+
+    fn focused(&mut self) -> Option<&mut Scene> {
+        let node = self.compositor.focused_node()?;
+        self.showing.get_mut(node)
+    }
+}
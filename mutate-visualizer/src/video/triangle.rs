@@ -7,8 +7,13 @@
 
 use ash::vk;
 
-use mutate_assets as assets;
-use mutate_lib::vulkan::prelude::*;
+use mutate_lib::{assets, prelude::*};
+
+// Both stages declare the same push block.  The fragment stage only reads the color.
+// NEXT create the pipeline from declared stages instead of loading modules by hand below.
+#[allow(dead_code)]
+#[stage("triangle/vertex", Vertex, c"main", push = TrianglePush)]
+struct TriangleVertex;
 
 // This will be an interface after more nodes exist
 pub struct TriangleNode {
@@ -110,12 +115,10 @@ impl TriangleNode {
             ..Default::default()
         };
 
-        // I probably had no idea about the offsets and layouts of push constants when deciding to
-        // pack them all as one.  I had not switched to scalar block layout.
         let push_constant_range = vk::PushConstantRange {
             stage_flags: vk::ShaderStageFlags::FRAGMENT | vk::ShaderStageFlags::VERTEX,
             offset: 0,
-            size: std::mem::size_of::<[f32; 5]>() as u32,
+            size: std::mem::size_of::<TrianglePush>() as u32,
         };
 
         let pipeline_layout_info = vk::PipelineLayoutCreateInfo {
@@ -176,17 +179,17 @@ impl TriangleNode {
             device.as_raw().cmd_bind_pipeline(*cb, vk::PipelineBindPoint::GRAPHICS, pipeline);
         }
 
-        let combined_push: [f32; 5] = [rgb.red, rgb.green, rgb.blue, 1.0, scale];
+        let push = TrianglePush {
+            u_color: [rgb.red, rgb.green, rgb.blue, 1.0],
+            scale,
+        };
         unsafe {
             device.as_raw().cmd_push_constants(
                 *cb,
                 self.pipeline_layout,
                 vk::ShaderStageFlags::FRAGMENT | vk::ShaderStageFlags::VERTEX,
                 0,
-                std::slice::from_raw_parts(
-                    combined_push.as_ptr() as *const u8,
-                    std::mem::size_of::<[f32; 5]>(),
-                ),
+                push.as_bytes(),
             );
         }
 
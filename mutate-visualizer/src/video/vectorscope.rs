@@ -0,0 +1,409 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Vectorscope
+//!
+//! Left against right, one point per sample pair, read straight from the device ring.  Mono
+//! collapses to a line and uncorrelated channels fill a disc, so the shape shows phase correlation
+//! and stereo width at a glance.  Channels out of phase spread the shape sideways.
+//!
+//! A compute pass gathers the newest samples into points.  They are drawn additively into a target
+//! that keeps its contents between frames.  Each frame first darkens the target, so older points
+//! decay instead of piling up.
+
+// NEXT a graph node sharing the raw ring input with the scope, once render nodes move into the
+// graph.
+// MAYBE accumulate intensity in a storage image with a compute pass, which needs storage usage on
+// the target.  Points are enough while one frame of audio is a few thousand of them.
+
+use std::time::Duration;
+
+use ash::vk;
+use mutate_lib::{self as utate, prelude::*};
+use utate::gpu::device::descriptors::Handle;
+use utate::gpu::resource::buffer;
+use utate::graph::DeletionQueue;
+
+use super::ring::RingPosition;
+
+/// The target is copied to the swapchain, so it matches the swapchain's texel size.
+const FORMAT: vk::Format = vk::Format::B8G8R8A8_UNORM;
+/// Most sample pairs drawn in a frame.
+const MAX_POINTS: u32 = 4096;
+/// Rate assumed until the stream negotiates one.  The ring holds silence until then.
+const FALLBACK_RATE: u32 = 48_000;
+
+#[compute_pipeline(
+    compute = stage!("vectorscope/gather", Compute, c"main"),
+    push = push!(VectorscopeGatherConstants {
+        pub left_channel: DeviceAddress,
+        pub right_channel: DeviceAddress,
+        pub capacity: UInt,
+        pub write_index: UInt,
+        pub count: UInt,
+        pub mid_side: UInt,
+        pub points_idx: SsboIdx,
+    }),
+)]
+pub struct VectorscopeGatherPipeline;
+
+/// How the channels map to the axes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Orientation {
+    /// Goniometer.  Mono is vertical and the sides are the diagonals.
+    #[default]
+    MidSide,
+    /// Lissajous.  Left is horizontal and right is vertical, so mono is a diagonal.
+    LeftRight,
+}
+
+pub struct VectorscopeNode {
+    gather: ComputePipeline<VectorscopeGatherPipeline>,
+    pipeline_layout: vk::PipelineLayout,
+    fade_pipeline: vk::Pipeline,
+    points_pipeline: vk::Pipeline,
+    pub orientation: Orientation,
+    /// How much audio each frame draws.
+    pub window: Duration,
+    /// Brightness kept per frame.
+    pub decay: f32,
+    /// Scale.  One puts full scale at the window edges.
+    pub gain: f32,
+    /// Multiplies `gain`.  The graph [drives](super::drive) it.
+    pub level: f32,
+    /// Point diameter in pixels.
+    pub point_size: f32,
+    /// Brightness each point adds.
+    pub intensity: f32,
+
+    points: buffer::MappedAllocation<[f32; 2]>,
+    points_idx: Handle<SsboIdx>,
+    target: Option<OffscreenTarget>,
+    /// Whether the target holds a frame yet.
+    primed: bool,
+}
+
+impl VectorscopeNode {
+    pub fn new(device: &Device) -> Result<Self, utate::MutateError> {
+        let gather = ComputePipeline::<VectorscopeGatherPipeline>::new(device)?;
+        let points = buffer::MappedAllocation::<[f32; 2]>::new(MAX_POINTS as usize, device)?;
+        points.set_name(device, "vectorscope points");
+        let points_idx = points.register(device)?;
+        let (pipeline_layout, fade_pipeline, points_pipeline) = Self::pipelines(device)?;
+        Ok(Self {
+            gather,
+            pipeline_layout,
+            fade_pipeline,
+            points_pipeline,
+            orientation: Orientation::default(),
+            window: Duration::from_millis(20),
+            decay: 0.85,
+            gain: 0.7,
+            level: 1.0,
+            point_size: 2.0,
+            intensity: 0.15,
+            points,
+            points_idx,
+            target: None,
+            primed: false,
+        })
+    }
+
+    /// Switch between goniometer and Lissajous.  The trace starts over.
+    pub fn toggle_orientation(&mut self) -> Orientation {
+        self.orientation = match self.orientation {
+            Orientation::MidSide => Orientation::LeftRight,
+            Orientation::LeftRight => Orientation::MidSide,
+        };
+        self.primed = false;
+        self.orientation
+    }
+
+    /// One layout, and a pipeline each for darkening the target and drawing points.
+    fn pipelines(
+        device: &Device,
+    ) -> Result<(vk::PipelineLayout, vk::Pipeline, vk::Pipeline), VulkanError> {
+        let push_range = vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            offset: 0,
+            size: std::mem::size_of::<[u32; 4]>() as u32,
+        };
+        let layout_ci = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(device.descriptors.layout())
+            .push_constant_ranges(std::slice::from_ref(&push_range));
+        let pipeline_layout = unsafe { device.as_raw().create_pipeline_layout(&layout_ci, None)? };
+
+        // Scale what is drawn by the blend constants.
+        let fade_blend = vk::PipelineColorBlendAttachmentState::default()
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::ZERO)
+            .dst_color_blend_factor(vk::BlendFactor::CONSTANT_COLOR)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ZERO)
+            .dst_alpha_blend_factor(vk::BlendFactor::ONE)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .color_write_mask(vk::ColorComponentFlags::RGBA);
+        // Add points to what is drawn.
+        let points_blend = vk::PipelineColorBlendAttachmentState::default()
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::ONE)
+            .dst_color_blend_factor(vk::BlendFactor::ONE)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ONE)
+            .dst_alpha_blend_factor(vk::BlendFactor::ONE)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .color_write_mask(vk::ColorComponentFlags::RGBA);
+
+        let build = |vert: &str,
+                     frag: &str,
+                     topology: vk::PrimitiveTopology,
+                     blend_attachment: vk::PipelineColorBlendAttachmentState,
+                     dynamic_states: &[vk::DynamicState]|
+         -> Result<vk::Pipeline, VulkanError> {
+            let vert = super::shader_module(device, vert)?;
+            let frag = super::shader_module(device, frag)?;
+            let stages = [
+                vk::PipelineShaderStageCreateInfo::default()
+                    .stage(vk::ShaderStageFlags::VERTEX)
+                    .module(vert)
+                    .name(c"main"),
+                vk::PipelineShaderStageCreateInfo::default()
+                    .stage(vk::ShaderStageFlags::FRAGMENT)
+                    .module(frag)
+                    .name(c"main"),
+            ];
+            let vertex_input = vk::PipelineVertexInputStateCreateInfo::default();
+            let input_assembly =
+                vk::PipelineInputAssemblyStateCreateInfo::default().topology(topology);
+            let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+                .viewport_count(1)
+                .scissor_count(1);
+            let rasterizer = vk::PipelineRasterizationStateCreateInfo::default()
+                .polygon_mode(vk::PolygonMode::FILL)
+                .line_width(1.0);
+            let multisampling = vk::PipelineMultisampleStateCreateInfo::default()
+                .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+            let color_blend = vk::PipelineColorBlendStateCreateInfo::default()
+                .attachments(std::slice::from_ref(&blend_attachment));
+            let color_formats = [FORMAT];
+            let mut rendering =
+                vk::PipelineRenderingCreateInfo::default().color_attachment_formats(&color_formats);
+            let dynamic_state =
+                vk::PipelineDynamicStateCreateInfo::default().dynamic_states(dynamic_states);
+
+            let pipeline_ci = vk::GraphicsPipelineCreateInfo::default()
+                .stages(&stages)
+                .vertex_input_state(&vertex_input)
+                .input_assembly_state(&input_assembly)
+                .viewport_state(&viewport_state)
+                .rasterization_state(&rasterizer)
+                .multisample_state(&multisampling)
+                .color_blend_state(&color_blend)
+                .dynamic_state(&dynamic_state)
+                .layout(pipeline_layout)
+                .push_next(&mut rendering);
+            let pipeline = unsafe {
+                device
+                    .as_raw()
+                    .create_graphics_pipelines(device.pipeline_cache(), &[pipeline_ci], None)
+                    .map_err(|e| e.1)?[0]
+            };
+            unsafe {
+                device.as_raw().destroy_shader_module(vert, None);
+                device.as_raw().destroy_shader_module(frag, None);
+            }
+            Ok(pipeline)
+        };
+
+        let fade_pipeline = build(
+            "vectorscope/fade_vertex",
+            "vectorscope/fade_fragment",
+            vk::PrimitiveTopology::TRIANGLE_LIST,
+            fade_blend,
+            &[
+                vk::DynamicState::VIEWPORT,
+                vk::DynamicState::SCISSOR,
+                vk::DynamicState::BLEND_CONSTANTS,
+            ],
+        )?;
+        let points_pipeline = build(
+            "vectorscope/vertex",
+            "vectorscope/fragment",
+            vk::PrimitiveTopology::POINT_LIST,
+            points_blend,
+            &[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR],
+        )?;
+        Ok((pipeline_layout, fade_pipeline, points_pipeline))
+    }
+
+    /// Provision the target for `size`.  A replaced target is queued on `deletions` behind the
+    /// frames already recorded with it, and the trace starts over.
+    pub fn provision(
+        &mut self,
+        device: &Device,
+        size: vk::Extent2D,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        if let Some(existing) = self.target.take() {
+            deletions.defer(frames, move |device| {
+                if let Err(e) = existing.destroy(device) {
+                    eprintln!("vectorscope: target destruction failed {:?}", e);
+                }
+            });
+        }
+        let target = OffscreenTarget::new(device, size, FORMAT)?;
+        target.set_name(device, "vectorscope");
+        self.target = Some(target);
+        self.primed = false;
+        Ok(())
+    }
+
+    /// Sample pairs drawn this frame.
+    fn count(&self, ring: &RingPosition) -> u32 {
+        let rate = ring.rate.unwrap_or(FALLBACK_RATE);
+        let count = (self.window.as_secs_f64() * rate as f64) as u32;
+        count.clamp(1, MAX_POINTS.min(ring.capacity / 2))
+    }
+
+    /// Draw the vectorscope into `area` and copy it into `acquired_image`, which must be in
+    /// `TRANSFER_DST_OPTIMAL`.
+    pub fn draw(
+        &mut self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        ring: &RingPosition,
+        area: vk::Rect2D,
+    ) {
+        let count = self.count(ring);
+        let Some(target) = &mut self.target else {
+            return;
+        };
+        let raw = device.as_raw();
+
+        // The last frame's draw may still be reading the points.
+        let points_barrier = |src_stage, src_access, dst_stage, dst_access| {
+            let barrier = vk::BufferMemoryBarrier {
+                src_access_mask: src_access,
+                dst_access_mask: dst_access,
+                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                buffer: self.points.buffer,
+                offset: 0,
+                size: vk::WHOLE_SIZE,
+                ..Default::default()
+            };
+            unsafe {
+                raw.cmd_pipeline_barrier(
+                    **cb,
+                    src_stage,
+                    dst_stage,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[barrier],
+                    &[],
+                );
+            }
+        };
+        points_barrier(
+            vk::PipelineStageFlags::VERTEX_SHADER,
+            vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::AccessFlags::SHADER_WRITE,
+        );
+        let push = VectorscopeGatherConstants {
+            left_channel: DeviceAddress::from(ring.channels[0]),
+            right_channel: DeviceAddress::from(ring.channels[1]),
+            capacity: ring.capacity.into(),
+            write_index: ring.write_index.into(),
+            count: count.into(),
+            mid_side: ((self.orientation == Orientation::MidSide) as u32).into(),
+            points_idx: self.points_idx.index(),
+        };
+        self.gather.push(device, **cb, &push);
+        self.gather.dispatch(device, **cb, count.div_ceil(64), 1, 1);
+        points_barrier(
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::AccessFlags::SHADER_WRITE,
+            vk::PipelineStageFlags::VERTEX_SHADER,
+            vk::AccessFlags::SHADER_READ,
+        );
+
+        // An unprimed target was never drawn, so it is cleared instead of faded.
+        let load_op = match self.primed {
+            true => vk::AttachmentLoadOp::LOAD,
+            false => vk::AttachmentLoadOp::CLEAR,
+        };
+        let color_attachment = target.color_attachment(device, **cb).load_op(load_op);
+        let rendering_info = vk::RenderingInfo::default()
+            .render_area(area)
+            .layer_count(1)
+            .color_attachments(std::slice::from_ref(&color_attachment));
+        let viewport = super::viewport(area);
+
+        let push: [u32; 4] = [
+            self.points_idx.index().raw(),
+            (self.gain * self.level).to_bits(),
+            self.point_size.to_bits(),
+            self.intensity.to_bits(),
+        ];
+        unsafe {
+            raw.cmd_begin_rendering(**cb, &rendering_info);
+            raw.cmd_bind_descriptor_sets(
+                **cb,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                0,
+                &[device.descriptors.set()],
+                &[],
+            );
+            raw.cmd_set_viewport(**cb, 0, &[viewport]);
+            raw.cmd_set_scissor(**cb, 0, &[area]);
+            if self.primed {
+                raw.cmd_bind_pipeline(**cb, vk::PipelineBindPoint::GRAPHICS, self.fade_pipeline);
+                raw.cmd_set_blend_constants(**cb, &[self.decay; 4]);
+                raw.cmd_draw(**cb, 3, 1, 0, 0);
+            }
+            raw.cmd_bind_pipeline(**cb, vk::PipelineBindPoint::GRAPHICS, self.points_pipeline);
+            raw.cmd_push_constants(
+                **cb,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                0,
+                bytemuck::cast_slice(&push),
+            );
+            raw.cmd_draw(**cb, count, 1, 0, 0);
+            raw.cmd_end_rendering(**cb);
+        }
+        target.copy_area_to(device, **cb, acquired_image.image, area);
+        self.primed = true;
+    }
+
+    /// Start the trace over, such as after a frame that drew it was abandoned or when its scene is
+    /// shown again.
+    pub fn reset(&mut self) {
+        if let Some(target) = &mut self.target {
+            target.discard();
+        }
+        self.primed = false;
+    }
+
+    /// Caller must drain work that drew the vectorscope first.
+    pub fn destroy(self, device: &Device) -> Result<(), utate::MutateError> {
+        unsafe {
+            self.gather.destroy(device);
+            device.as_raw().destroy_pipeline(self.fade_pipeline, None);
+            device.as_raw().destroy_pipeline(self.points_pipeline, None);
+            device
+                .as_raw()
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+        }
+        device.descriptors.release(self.points_idx);
+        self.points.destroy(device)?;
+        if let Some(target) = self.target {
+            target.destroy(device)?;
+        }
+        Ok(())
+    }
+}
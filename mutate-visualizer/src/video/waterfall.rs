@@ -0,0 +1,374 @@
+// Copyright 2026 The MuTate Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Waterfall
+//!
+//! The scrolling spectrogram.  A history image holds one row of bin amplitudes per frame, pitch
+//! running across.  Each frame copies the newest row from a device buffer, such as the output of a
+//! [`GpuSpectrogram`](mutate_lib::dsp::compute::GpuSpectrogram), into the next row of the image,
+//! which is a ring.  Drawing unrolls the ring so the newest row is at the top and maps amplitudes
+//! through a [`PaletteLut`].
+//!
+//! Rows arrive by buffer copy, so the bins never visit the host.  The image is `R32_SFLOAT` and at
+//! most 4096 bins wide, the smallest `maxImageDimension2D` a device may have.
+//!
+//! The pipeline is created for the sample count of the [`MsaaTarget`] it draws into, so it must be
+//! rebuilt along with a target of a different count.
+
+// MAYBE smooth between bins when the window is wider than the bank.
+
+use ash::vk;
+use mutate_lib::{self as utate, prelude::*};
+use utate::color::Palette;
+use utate::gpu::device::descriptors::Handle;
+use utate::gpu::resource::image;
+use utate::graph::DeletionQueue;
+
+use super::lut::PaletteLut;
+
+/// Widest history.  See the [module](self) docs.
+pub const MAX_BINS: u32 = 4096;
+
+pub struct WaterfallNode {
+    pipeline_layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+
+    history: image::Image,
+    view: image::ImageView,
+    history_idx: Handle<SampledImageIdx>,
+    bins: u32,
+    rows: u32,
+    /// Row written next.
+    cursor: u32,
+    /// Whether the history was cleared and holds rows.
+    started: bool,
+    palette: PaletteLut,
+
+    /// dB drawn at the bottom of the palette.  Anything quieter is clamped.
+    pub floor_db: f32,
+    /// dB drawn at the top of the palette.
+    pub ceiling_db: f32,
+}
+
+impl WaterfallNode {
+    /// Keeps `rows` frames of `bins` amplitudes colored by `palette`.  Draws into swapchain images
+    /// of `format` through a target with `samples` per pixel.
+    pub fn new(
+        device: &Device,
+        format: vk::Format,
+        samples: vk::SampleCountFlags,
+        bins: u32,
+        rows: u32,
+        palette: &Palette,
+    ) -> Result<Self, utate::MutateError> {
+        let extent = vk::Extent2D {
+            width: bins,
+            height: rows,
+        };
+        let history = image::Image::new(
+            device,
+            extent,
+            vk::Format::R32_SFLOAT,
+            vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
+        )?;
+        history.set_name(device, "waterfall history");
+        let view = match history.default_view(device) {
+            Ok(view) => view,
+            Err(e) => {
+                let _ = history.destroy(device);
+                return Err(e.into());
+            }
+        };
+        let palette = match PaletteLut::new(device, palette) {
+            Ok(palette) => palette,
+            Err(e) => {
+                let _ = view.destroy(device);
+                let _ = history.destroy(device);
+                return Err(e);
+            }
+        };
+        let (pipeline_layout, pipeline) = match Self::pipeline(device, format, samples) {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                let _ = palette.destroy(device);
+                let _ = view.destroy(device);
+                let _ = history.destroy(device);
+                return Err(e.into());
+            }
+        };
+        let history_idx = view.sampled(device, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)?;
+
+        Ok(Self {
+            pipeline_layout,
+            pipeline,
+            history,
+            view,
+            history_idx,
+            bins,
+            rows,
+            cursor: 0,
+            started: false,
+            palette,
+            floor_db: -90.0,
+            ceiling_db: 0.0,
+        })
+    }
+
+    fn pipeline(
+        device: &Device,
+        format: vk::Format,
+        samples: vk::SampleCountFlags,
+    ) -> Result<(vk::PipelineLayout, vk::Pipeline), VulkanError> {
+        let vert = super::shader_module(device, "waterfall/vertex")?;
+        let frag = super::shader_module(device, "waterfall/fragment")?;
+
+        let stages = [
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::VERTEX)
+                .module(vert)
+                .name(c"main"),
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::FRAGMENT)
+                .module(frag)
+                .name(c"main"),
+        ];
+
+        let push_range = vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            offset: 0,
+            size: std::mem::size_of::<[u32; 7]>() as u32,
+        };
+        let layout_ci = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(device.descriptors.layout())
+            .push_constant_ranges(std::slice::from_ref(&push_range));
+        let pipeline_layout = unsafe { device.as_raw().create_pipeline_layout(&layout_ci, None)? };
+
+        let vertex_input = vk::PipelineVertexInputStateCreateInfo::default();
+        let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::default()
+            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+        let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
+        let rasterizer = vk::PipelineRasterizationStateCreateInfo::default()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .line_width(1.0);
+        let multisampling =
+            vk::PipelineMultisampleStateCreateInfo::default().rasterization_samples(samples);
+        let blend_attachment = vk::PipelineColorBlendAttachmentState::default()
+            .color_write_mask(vk::ColorComponentFlags::RGBA);
+        let color_blend = vk::PipelineColorBlendStateCreateInfo::default()
+            .attachments(std::slice::from_ref(&blend_attachment));
+        let color_formats = [format];
+        let mut rendering =
+            vk::PipelineRenderingCreateInfo::default().color_attachment_formats(&color_formats);
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state =
+            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+
+        let pipeline_ci = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input)
+            .input_assembly_state(&input_assembly)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterizer)
+            .multisample_state(&multisampling)
+            .color_blend_state(&color_blend)
+            .dynamic_state(&dynamic_state)
+            .layout(pipeline_layout)
+            .push_next(&mut rendering);
+        let pipeline = unsafe {
+            device
+                .as_raw()
+                .create_graphics_pipelines(device.pipeline_cache(), &[pipeline_ci], None)
+                .map_err(|e| e.1)?[0]
+        };
+
+        unsafe {
+            device.as_raw().destroy_shader_module(vert, None);
+            device.as_raw().destroy_shader_module(frag, None);
+        }
+        Ok((pipeline_layout, pipeline))
+    }
+
+    pub fn bins(&self) -> u32 {
+        self.bins
+    }
+
+    /// Color by `palette` from the next frame on.  The old table is queued on `deletions` behind
+    /// the frames already recorded with it.
+    pub fn set_palette(
+        &mut self,
+        device: &Device,
+        palette: &Palette,
+        deletions: &mut DeletionQueue<Device>,
+        frames: u64,
+    ) -> Result<(), utate::MutateError> {
+        self.palette.replace(device, palette, deletions, frames)
+    }
+
+    /// Copy `bins` amplitudes at `offset` of `src` into the next row.  `src` was last written by a
+    /// compute shader on this queue, such as the filter bank's dispatch.  Record before drawing.
+    pub fn record_row(
+        &mut self,
+        device: &Device,
+        cb: vk::CommandBuffer,
+        src: vk::Buffer,
+        offset: vk::DeviceSize,
+    ) {
+        let raw = device.as_raw();
+        let src_barrier = vk::BufferMemoryBarrier {
+            src_access_mask: vk::AccessFlags::SHADER_WRITE,
+            dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            buffer: src,
+            offset,
+            size: self.bins as vk::DeviceSize * 4,
+            ..Default::default()
+        };
+        unsafe {
+            raw.cmd_pipeline_barrier(
+                cb,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[src_barrier],
+                &[],
+            );
+        }
+
+        if self.started {
+            self.history.transition_layout(
+                cb,
+                image::range(),
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                device,
+            );
+        } else {
+            // Rows not written yet draw as silence.
+            self.history.transition_to_transfer_dst(cb, device);
+            unsafe {
+                raw.cmd_clear_color_image(
+                    cb,
+                    self.history.image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &vk::ClearColorValue::default(),
+                    &[image::range()],
+                );
+            }
+        }
+
+        let region = vk::BufferImageCopy {
+            buffer_offset: offset,
+            image_subresource: vk::ImageSubresourceLayers::default()
+                .aspect_mask(vk::ImageAspectFlags::COLOR)
+                .layer_count(1),
+            image_offset: vk::Offset3D {
+                x: 0,
+                y: self.cursor as i32,
+                z: 0,
+            },
+            image_extent: vk::Extent3D {
+                width: self.bins,
+                height: 1,
+                depth: 1,
+            },
+            ..Default::default()
+        };
+        unsafe {
+            raw.cmd_copy_buffer_to_image(
+                cb,
+                src,
+                self.history.image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[region],
+            );
+        }
+        self.history.transition_to_shader_read(cb, device);
+        self.cursor = (self.cursor + 1) % self.rows;
+        self.started = true;
+    }
+
+    /// Record inside `graphics_present`, drawing into `area`.  Draws nothing until the first row is
+    /// recorded.
+    pub fn draw(
+        &self,
+        device: &Device,
+        cb: &RecordingBuffer<Graphics, OneTime>,
+        acquired_image: &AcquiredImage,
+        target: &MsaaTarget,
+        area: vk::Rect2D,
+    ) {
+        if !self.started {
+            return;
+        }
+        let raw = device.as_raw();
+        // The triangle covers the target, so nothing needs clearing.
+        let color_attachment = target
+            .color_attachment(acquired_image)
+            .load_op(vk::AttachmentLoadOp::DONT_CARE);
+        target.prepare(device, **cb);
+        let rendering_info = vk::RenderingInfo::default()
+            .render_area(area)
+            .layer_count(1)
+            .color_attachments(std::slice::from_ref(&color_attachment));
+        let viewport = super::viewport(area);
+
+        let newest = (self.cursor + self.rows - 1) % self.rows;
+        let push: [u32; 7] = [
+            self.history_idx.index().raw(),
+            self.bins,
+            self.rows,
+            newest,
+            self.floor_db.to_bits(),
+            self.ceiling_db.to_bits(),
+            self.palette.index(),
+        ];
+        unsafe {
+            raw.cmd_begin_rendering(**cb, &rendering_info);
+            raw.cmd_bind_pipeline(**cb, vk::PipelineBindPoint::GRAPHICS, self.pipeline);
+            raw.cmd_bind_descriptor_sets(
+                **cb,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                0,
+                &[device.descriptors.set()],
+                &[],
+            );
+            raw.cmd_push_constants(
+                **cb,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                0,
+                bytemuck::cast_slice(&push),
+            );
+            raw.cmd_set_viewport(**cb, 0, &[viewport]);
+            raw.cmd_set_scissor(**cb, 0, &[area]);
+            raw.cmd_draw(**cb, 3, 1, 0, 0);
+            raw.cmd_end_rendering(**cb);
+        }
+    }
+
+    /// Start the history over after a frame that recorded a row was abandoned, since its layout is
+    /// no longer known.
+    pub fn reset(&mut self) {
+        self.started = false;
+    }
+
+    /// Caller must drain work that drew the waterfall first.
+    pub fn destroy(self, device: &Device) -> Result<(), utate::MutateError> {
+        device.descriptors.release(self.history_idx);
+        unsafe {
+            device.as_raw().destroy_pipeline(self.pipeline, None);
+            device
+                .as_raw()
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+        }
+        self.view.destroy(device)?;
+        self.history.destroy(device)?;
+        self.palette.destroy(device)?;
+        Ok(())
+    }
+}
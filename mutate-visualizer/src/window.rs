@@ -8,12 +8,12 @@
 //! warrant belonging in lib.  Using windows is a frontend behavior.  If multiple frontends use
 //! windows, consider lifting this code into a shared module.
 
+use std::time::Duration;
+
 use ash::{khr::xlib_surface, vk};
 use winit::{event_loop::ActiveEventLoop, window::Window};
 
-use mutate_lib::prelude::*;
-
-use crate::Args;
+use mutate_lib::{prelude::*, settings::VideoSettings};
 
 // NEXT now that the vulkan module has a cfg gate for winit support, it is appropriate to move this
 // support into Vulkan.
@@ -21,15 +21,21 @@ use crate::Args;
 // may be relevant again.
 
 pub trait WindowExt {
-    fn from_args(args: &Args, event_loop: &ActiveEventLoop) -> Window;
+    fn from_settings(video: &VideoSettings, event_loop: &ActiveEventLoop) -> Window;
     fn toggle_fullscreen(&self);
+    fn refresh_period(&self) -> Duration;
 }
 
+pub const TITLE: &str = "µTate";
+
+/// Assumed when the platform does not report the monitor's refresh rate.
+const FALLBACK_REFRESH_MILLIHERTZ: u32 = 60_000;
+
 impl WindowExt for Window {
     /// Create the window from the visualizer's configuration options.
-    fn from_args(args: &Args, event_loop: &ActiveEventLoop) -> Window {
-        let mut attrs = Window::default_attributes().with_title("µTate");
-        if args.fullscreen {
+    fn from_settings(video: &VideoSettings, event_loop: &ActiveEventLoop) -> Window {
+        let mut attrs = Window::default_attributes().with_title(TITLE);
+        if video.fullscreen {
             // LIES None is not correct here.  We should pick a window.  Maybe all windows.
             attrs = attrs.with_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
         }
@@ -37,7 +43,7 @@ impl WindowExt for Window {
             .create_window(attrs)
             .expect("Failed to create window");
 
-        if args.fullscreen {
+        if video.fullscreen {
             window.set_cursor_visible(false);
         }
         window
@@ -57,4 +63,16 @@ impl WindowExt for Window {
             }
         }
     }
+
+    /// Refresh period of the monitor the window is on.  On a variable refresh monitor, this is the
+    /// period at the maximum rate.
+    // LIES a window spanning monitors refreshes with whichever one winit calls current.
+    fn refresh_period(&self) -> Duration {
+        let millihertz = self
+            .current_monitor()
+            .and_then(|m| m.refresh_rate_millihertz())
+            .filter(|&mhz| mhz > 0)
+            .unwrap_or(FALLBACK_REFRESH_MILLIHERTZ);
+        Duration::from_secs_f64(1000.0 / f64::from(millihertz))
+    }
 }